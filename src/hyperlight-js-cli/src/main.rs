@@ -0,0 +1,118 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! `hyperlight-js`, a command line tool to run JavaScript handlers in a
+//! hyperlight-js sandbox.
+#![allow(clippy::disallowed_macros)]
+
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::{fs, process};
+
+use anyhow::{Context as _, Result};
+use clap::{Args, Parser, Subcommand};
+use hyperlight_js::HostPrintFn;
+
+mod repl;
+mod sandbox;
+
+use repl::ReplArgs;
+use sandbox::SandboxArgs;
+
+const EXAMPLES: &str = "\u{001b}[1;4mExamples:\u{001b}[0m
+  Run the handler in ./handler.js with the event in ./event.json:
+    $ hyperlight-js run ./handler.js --event ./event.json
+
+  Run the handler of a directory, with a 100ms timeout, reading the event from stdin:
+    $ echo '{\"name\":\"World\"}' | hyperlight-js run ./greeter --event - --timeout 100
+
+  Send events to the handler in ./handler.js interactively, reloading it when it changes:
+    $ hyperlight-js repl ./handler.js --watch";
+
+#[derive(Parser)]
+#[command(version, about = "Run JavaScript handlers in a hyperlight-js sandbox", after_help = EXAMPLES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a handler with an event and print its result.
+    Run(RunArgs),
+    /// Send events to a handler interactively.
+    Repl(ReplArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    #[command(flatten)]
+    sandbox: SandboxArgs,
+
+    /// The file with the JSON event to pass to the handler, or `-` to read it
+    /// from stdin. The event is `{}` if not given.
+    #[arg(short, long)]
+    event: Option<PathBuf>,
+
+    /// Pretty print the result.
+    #[arg(long)]
+    pretty: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Run(args) => run(args),
+        Command::Repl(args) => repl::repl(args),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {e:#}");
+        process::exit(1);
+    }
+}
+
+fn run(args: RunArgs) -> Result<()> {
+    let event = match args.event.as_deref() {
+        None => "{}".to_string(),
+        Some(path) if path == Path::new("-") => {
+            let mut event = String::new();
+            io::stdin()
+                .read_to_string(&mut event)
+                .context("Reading the event from stdin")?;
+            event
+        }
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Reading the event from {}", path.display()))?,
+    };
+
+    // Keep stdout for the result of the handler
+    let print: HostPrintFn = (|message: String| -> hyperlight_js::Result<i32> {
+        eprint!("{message}");
+        Ok(message.len() as i32)
+    })
+    .into();
+    let result = args.sandbox.load(print)?.call(event)?;
+    println!("{}", format_result(&result, args.pretty)?);
+    Ok(())
+}
+
+/// Format the JSON `result` of a handler, pretty printing it if `pretty`.
+fn format_result(result: &str, pretty: bool) -> Result<String> {
+    if !pretty {
+        return Ok(result.to_string());
+    }
+    let result: serde_json::Value = serde_json::from_str(result)?;
+    Ok(serde_json::to_string_pretty(&result)?)
+}
@@ -0,0 +1,171 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::io::{self, BufRead, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use clap::Args;
+use hyperlight_js::HostPrintFn;
+
+use crate::format_result;
+use crate::sandbox::{HandlerSandbox, SandboxArgs};
+
+const HELP: &str = "Enter a JSON event to call the handler with it, or a command:
+  .reload  load the handler and its modules again
+  .help    print this help
+  .exit    quit";
+
+#[derive(Args)]
+pub(crate) struct ReplArgs {
+    #[command(flatten)]
+    sandbox: SandboxArgs,
+
+    /// Load the handler and its modules again when they change, before the
+    /// next event.
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Pretty print the results.
+    #[arg(long)]
+    pretty: bool,
+}
+
+/// The state of the REPL, with the sandbox kept alive across events.
+struct Repl {
+    args: ReplArgs,
+    logs: Arc<Mutex<String>>,
+    // `None` if the handler failed to load, it is loaded again on the next event
+    sandbox: Option<HandlerSandbox>,
+    modification_times: Vec<(PathBuf, SystemTime)>,
+    calls: u64,
+}
+
+/// Read events from stdin and call the handler with them, printing the
+/// result, the output and the stats of every call.
+pub(crate) fn repl(args: ReplArgs) -> Result<()> {
+    let mut repl = Repl::new(args)?;
+    println!("{HELP}");
+
+    let mut stdin = io::stdin().lock();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        match line.trim() {
+            "" => {}
+            ".exit" | ".quit" => return Ok(()),
+            ".help" => println!("{HELP}"),
+            ".reload" => repl.reload(),
+            event => repl.call(event.to_string()),
+        }
+    }
+}
+
+impl Repl {
+    fn new(args: ReplArgs) -> Result<Self> {
+        let logs = Arc::new(Mutex::new(String::new()));
+        let modification_times = args.sandbox.modification_times();
+        let sandbox = args.sandbox.load(capture_print(&logs))?;
+        Ok(Self {
+            args,
+            logs,
+            sandbox: Some(sandbox),
+            modification_times,
+            calls: 0,
+        })
+    }
+
+    fn reload(&mut self) {
+        self.modification_times = self.args.sandbox.modification_times();
+        let reloaded = match self.sandbox.take() {
+            Some(sandbox) => sandbox.reload(&self.args.sandbox),
+            None => self.args.sandbox.load(capture_print(&self.logs)),
+        };
+        match reloaded {
+            Ok(sandbox) => {
+                self.sandbox = Some(sandbox);
+                println!("Reloaded the handler");
+            }
+            Err(e) => println!("Error loading the handler: {e:#}"),
+        }
+    }
+
+    fn call(&mut self, event: String) {
+        if self.sandbox.is_none()
+            || (self.args.watch
+                && self.args.sandbox.modification_times() != self.modification_times)
+        {
+            self.reload();
+        }
+        let Some(sandbox) = &mut self.sandbox else {
+            return;
+        };
+
+        let start = Instant::now();
+        let result = sandbox.call(event);
+        let elapsed = start.elapsed();
+        self.calls += 1;
+
+        match result.and_then(|result| format_result(&result, self.args.pretty)) {
+            Ok(result) => println!("{result}"),
+            Err(e) => println!("Error: {e:#}"),
+        }
+        let logs = mem::take(&mut *self.logs.lock().unwrap_or_else(|e| e.into_inner()));
+        for line in logs.lines() {
+            println!("log: {line}");
+        }
+        println!("{}", self.stats(elapsed));
+
+        if self.sandbox.as_mut().is_some_and(|s| s.loaded().poisoned()) {
+            println!("The sandbox is poisoned, reloading the handler");
+            self.reload();
+        }
+    }
+
+    fn stats(&mut self, elapsed: Duration) -> String {
+        let mut stats = format!("time: {elapsed:?}, calls: {}", self.calls);
+        if let Some(sandbox) = &mut self.sandbox
+            && !sandbox.loaded().poisoned()
+            && let Ok(memory) = sandbox.loaded().memory_usage()
+        {
+            stats += &format!(
+                ", heap: {} KiB used, {} KiB allocated",
+                memory.memory_used_size / 1024,
+                memory.malloc_size / 1024
+            );
+        }
+        stats
+    }
+}
+
+/// A print function appending the output of the handler to `logs`.
+fn capture_print(logs: &Arc<Mutex<String>>) -> HostPrintFn {
+    let logs = logs.clone();
+    (move |message: String| -> hyperlight_js::Result<i32> {
+        logs.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_str(&message);
+        Ok(message.len() as i32)
+    })
+    .into()
+}
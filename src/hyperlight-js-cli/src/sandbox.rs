@@ -0,0 +1,209 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result};
+use clap::Args;
+use hyperlight_js::{
+    BoxedMonitorSet, CpuTimeMonitor, FileSystemMemory, HostPrintFn, LoadedJSSandbox,
+    SandboxBuilder, Script, WallClockMonitor,
+};
+
+/// The name the handler is registered under.
+const HANDLER: &str = "handler";
+
+/// The handler to run, and the sandbox to run it in.
+#[derive(Args)]
+pub(crate) struct SandboxArgs {
+    /// The handler script, or a directory with a `handler.js` or `index.js`
+    /// entry point whose files the handler can import.
+    handler: PathBuf,
+
+    /// A directory of modules the handler can import, with paths relative to
+    /// the directory.
+    #[arg(short, long)]
+    modules: Option<PathBuf>,
+
+    /// The size of the guest heap, in bytes.
+    #[arg(long)]
+    heap_size: Option<u64>,
+
+    /// The maximum size of the JavaScript stack, in bytes.
+    #[arg(long)]
+    stack_size: Option<usize>,
+
+    /// Terminate the handler after this many milliseconds of wall-clock time.
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Terminate the handler after this many milliseconds of CPU time.
+    #[arg(long)]
+    cpu_timeout: Option<u64>,
+}
+
+/// A sandbox with the handler loaded.
+pub(crate) struct HandlerSandbox {
+    loaded: LoadedJSSandbox,
+    modules: FileSystemMemory,
+    monitors: BoxedMonitorSet,
+}
+
+impl SandboxArgs {
+    /// Build a sandbox printing the output of the handler with `print`, and
+    /// load the handler.
+    pub(crate) fn load(&self, print: HostPrintFn) -> Result<HandlerSandbox> {
+        let mut builder = SandboxBuilder::new().with_host_print_fn(print);
+        if let Some(heap_size) = self.heap_size {
+            builder = builder.with_guest_heap_size(heap_size);
+        }
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.with_js_max_stack_size(stack_size);
+        }
+
+        let mut monitors = BoxedMonitorSet::new();
+        if let Some(timeout) = self.timeout {
+            monitors.push(WallClockMonitor::new(Duration::from_millis(timeout))?);
+        }
+        if let Some(cpu_timeout) = self.cpu_timeout {
+            monitors.push(CpuTimeMonitor::new(Duration::from_millis(cpu_timeout))?);
+        }
+
+        let modules = FileSystemMemory::new();
+        let handler = self.script(&modules)?;
+        let mut sandbox = builder
+            .build()?
+            .set_module_loader(modules.clone())?
+            .load_runtime()?;
+        sandbox.add_handler(HANDLER, handler)?;
+        Ok(HandlerSandbox {
+            loaded: sandbox.get_loaded_sandbox()?,
+            modules,
+            monitors,
+        })
+    }
+
+    /// The modification times of the handler and module files, to tell when
+    /// they change.
+    pub(crate) fn modification_times(&self) -> Vec<(PathBuf, SystemTime)> {
+        let mut times = Vec::new();
+        for path in [Some(&self.handler), self.modules.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            collect_modification_times(path, &mut times);
+        }
+        times
+    }
+
+    /// Read the handler script, inserting the modules it can import into
+    /// `modules`.
+    fn script(&self, modules: &FileSystemMemory) -> Result<Script> {
+        let script = if self.handler.is_dir() {
+            Script::from_directory_with_modules(&self.handler, modules)?
+        } else if self.modules.is_some() {
+            Script::from_file(&self.handler)?.with_virtual_base("/")
+        } else {
+            Script::from_file(&self.handler)?
+        };
+        if let Some(dir) = &self.modules {
+            insert_modules(dir, dir, modules)?;
+        }
+        Ok(script)
+    }
+}
+
+impl HandlerSandbox {
+    /// Call the handler with the JSON `event`, under the timeouts.
+    pub(crate) fn call(&mut self, event: String) -> Result<String> {
+        let result = if self.monitors.is_empty() {
+            self.loaded.handle_event(HANDLER, event, None)?
+        } else {
+            self.loaded
+                .handle_event_with_monitor(HANDLER, event, &self.monitors, None)?
+        };
+        Ok(result)
+    }
+
+    /// Load the handler and its modules again from `args`, recovering the
+    /// sandbox if it is poisoned.
+    pub(crate) fn reload(self, args: &SandboxArgs) -> Result<Self> {
+        let mut sandbox = self.loaded.unload()?;
+        self.modules.clear();
+        sandbox.add_handler(HANDLER, args.script(&self.modules)?)?;
+        Ok(Self {
+            loaded: sandbox.get_loaded_sandbox()?,
+            modules: self.modules,
+            monitors: self.monitors,
+        })
+    }
+
+    /// The sandbox running the handler.
+    pub(crate) fn loaded(&mut self) -> &mut LoadedJSSandbox {
+        &mut self.loaded
+    }
+}
+
+/// Insert the files in `dir`, and its subdirectories, into `modules` under
+/// their path relative to `root`, skipping hidden files and directories.
+fn insert_modules(root: &Path, dir: &Path, modules: &FileSystemMemory) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Reading the modules in {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            insert_modules(root, &path, modules)?;
+            continue;
+        }
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("Reading the module {}", path.display()))?;
+        let key = path
+            .strip_prefix(root)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        modules.insert(key, source);
+    }
+    Ok(())
+}
+
+fn collect_modification_times(path: &Path, times: &mut Vec<(PathBuf, SystemTime)>) {
+    if path.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| !is_hidden(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            collect_modification_times(&path, times);
+        }
+    } else if let Ok(modified) = fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        times.push((path.to_path_buf(), modified));
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
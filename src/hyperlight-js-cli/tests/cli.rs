@@ -0,0 +1,188 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fs::{create_dir, write};
+use std::io::{BufRead as _, BufReader, Write as _};
+use std::process::{Command, Stdio};
+
+use tempfile::tempdir;
+
+fn cli() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_hyperlight-js"))
+}
+
+#[test]
+fn run_prints_the_result_of_the_handler() {
+    let dir = tempdir().unwrap();
+    create_dir(dir.path().join("lib")).unwrap();
+    write(
+        dir.path().join("handler.js"),
+        r#"
+            import { add } from './lib/math.js';
+            function handler(event) {
+                console.log("adding");
+                return { sum: add(event.a, event.b) };
+            }
+        "#,
+    )
+    .unwrap();
+    write(
+        dir.path().join("lib/math.js"),
+        "export const add = (a, b) => a + b;",
+    )
+    .unwrap();
+    write(dir.path().join("event.json"), r#"{"a":1,"b":41}"#).unwrap();
+
+    let output = cli()
+        .arg("run")
+        .arg(dir.path())
+        .arg("--event")
+        .arg(dir.path().join("event.json"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "{\"sum\":42}\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "adding\n");
+}
+
+#[test]
+fn run_reads_the_event_from_stdin_and_imports_modules() {
+    let dir = tempdir().unwrap();
+    create_dir(dir.path().join("modules")).unwrap();
+    write(
+        dir.path().join("handler.js"),
+        r#"
+            import { greet } from './greet.js';
+            function handler(event) { return greet(event.name); }
+        "#,
+    )
+    .unwrap();
+    write(
+        dir.path().join("modules/greet.js"),
+        "export const greet = (name) => `Hello, ${name}!`;",
+    )
+    .unwrap();
+
+    let mut child = cli()
+        .arg("run")
+        .arg(dir.path().join("handler.js"))
+        .arg("--modules")
+        .arg(dir.path().join("modules"))
+        .args(["--event", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(br#"{"name":"World"}"#)
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\"Hello, World!\"\n"
+    );
+}
+
+#[test]
+fn run_fails_when_the_handler_times_out() {
+    let dir = tempdir().unwrap();
+    write(
+        dir.path().join("handler.js"),
+        "function handler(event) { while (true) {} }",
+    )
+    .unwrap();
+
+    let output = cli()
+        .arg("run")
+        .arg(dir.path().join("handler.js"))
+        .args(["--timeout", "100"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: "));
+}
+
+#[test]
+fn repl_calls_the_handler_with_every_event_and_reloads_it() {
+    let dir = tempdir().unwrap();
+    let handler = dir.path().join("handler.js");
+    write(
+        &handler,
+        r#"
+            let calls = 0;
+            function handler(event) {
+                console.log(`call ${++calls}`);
+                return event.n * 2;
+            }
+        "#,
+    )
+    .unwrap();
+
+    let mut child = cli()
+        .arg("repl")
+        .arg(&handler)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    stdin.write_all(b"{\"n\":1}\n{\"n\":2}\n").unwrap();
+    let mut lines = Vec::new();
+    // Only change the handler once the REPL is done with the first version
+    while !lines
+        .last()
+        .is_some_and(|line: &String| line.contains("calls: 2"))
+    {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        lines.push(line.trim_end().trim_start_matches("> ").to_string());
+    }
+    write(&handler, "function handler(event) { return event.n * 3; }").unwrap();
+    stdin.write_all(b".reload\n{\"n\":2}\n.exit\n").unwrap();
+    drop(stdin);
+    lines.extend(
+        stdout
+            .lines()
+            .map(|line| line.unwrap().trim_start_matches("> ").to_string()),
+    );
+    assert!(child.wait().unwrap().success());
+
+    let lines = lines
+        .iter()
+        .filter(|line| !line.starts_with("time: "))
+        .skip_while(|line| *line != "2")
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "2",
+            "log: call 1",
+            "4",
+            "log: call 2",
+            "Reloaded the handler",
+            "6",
+            ""
+        ]
+    );
+}
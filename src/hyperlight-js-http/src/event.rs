@@ -0,0 +1,171 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use http::header::CONTENT_TYPE;
+use http::request::Parts;
+use http::{HeaderName, HeaderValue, Response, StatusCode};
+use http_body_util::Full;
+use hyperlight_js::{new_error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The event passed to handlers by default, see [`HttpEvent::from_request`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpEvent {
+    /// The method of the request, e.g. `GET`.
+    pub method: String,
+    /// The path of the request, e.g. `/users/42`.
+    pub path: String,
+    /// The query string of the request, without the `?`.
+    pub query: Option<String>,
+    /// The headers of the request, with lowercase names. The values of
+    /// repeated headers are joined with `, `.
+    pub headers: BTreeMap<String, String>,
+    /// The body of the request: `null` if empty, the parsed body for JSON
+    /// content types, and the body as a string otherwise.
+    pub body: Value,
+}
+
+impl HttpEvent {
+    /// The event for the request with `parts` and `body`, failing if the body
+    /// is not valid UTF-8, or not valid JSON for JSON content types.
+    pub fn from_request(parts: &Parts, body: &Bytes) -> Result<Self> {
+        let mut headers = BTreeMap::<String, String>::new();
+        for (name, value) in &parts.headers {
+            let value = value
+                .to_str()
+                .map_err(|_| new_error!("The value of the header '{}' is not valid", name))?;
+            headers
+                .entry(name.as_str().to_string())
+                .and_modify(|values| {
+                    values.push_str(", ");
+                    values.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            let text = std::str::from_utf8(body)
+                .map_err(|_| new_error!("The body of the request is not valid UTF-8"))?;
+            if is_json(parts) {
+                serde_json::from_str(text)
+                    .map_err(|e| new_error!("The body of the request is not valid JSON: {}", e))?
+            } else {
+                Value::String(text.to_string())
+            }
+        };
+
+        Ok(Self {
+            method: parts.method.to_string(),
+            path: parts.uri.path().to_string(),
+            query: parts.uri.query().map(str::to_string),
+            headers,
+            body,
+        })
+    }
+}
+
+fn is_json(parts: &Parts) -> bool {
+    parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim();
+            mime == "application/json" || mime.ends_with("+json")
+        })
+}
+
+/// A response returned by a handler, as an object with a `statusCode`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HandlerResponse {
+    status_code: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+/// The HTTP response for the JSON `output` of a handler.
+///
+/// An object with a numeric `statusCode` describes the response, with
+/// optional `headers` and `body`. A string body is sent as is, as
+/// `text/plain` unless the headers have a content type, and any other body
+/// is sent as JSON. Any other output is sent as a JSON body with status 200.
+pub(crate) fn to_response(output: String) -> Result<Response<Full<Bytes>>> {
+    let value: Value = serde_json::from_str(&output)?;
+    let is_response = value.get("statusCode").is_some_and(Value::is_u64);
+    if !is_response {
+        return Ok(json_response(StatusCode::OK, output));
+    }
+
+    let response: HandlerResponse = serde_json::from_value(value)?;
+    let status = StatusCode::from_u16(response.status_code)
+        .map_err(|e| new_error!("The handler returned an invalid status code: {}", e))?;
+    let (content_type, body) = match response.body {
+        Value::Null => (None, String::new()),
+        Value::String(body) => (Some("text/plain; charset=utf-8"), body),
+        body => (Some("application/json"), body.to_string()),
+    };
+
+    let mut builder = Response::builder().status(status);
+    if let Some(content_type) = content_type
+        && !response
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+    {
+        builder = builder.header(CONTENT_TYPE, content_type);
+    }
+    for (name, value) in response.headers {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| new_error!("The handler returned an invalid header name: {}", e))?;
+        let value = HeaderValue::try_from(value)
+            .map_err(|e| new_error!("The handler returned an invalid header value: {}", e))?;
+        builder = builder.header(name, value);
+    }
+    builder
+        .body(Full::new(Bytes::from(body)))
+        .map_err(|e| new_error!("Failed to build the response: {}", e))
+}
+
+/// A response with a JSON `body`.
+fn json_response(status: StatusCode, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// A response with status `status` and a plain text `message`.
+pub(crate) fn error_response(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(message.to_string())));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    response
+}
@@ -0,0 +1,265 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use http::{Request, Response};
+use http_body::Body;
+use hyperlight_js::{HyperlightError, WallClockMonitor};
+use tonic::metadata::MetadataMap;
+use tonic::server::{Grpc, NamedService, UnaryService};
+use tonic::Status;
+use tonic_prost::ProstCodec;
+use tower_service::Service;
+
+use crate::pool::SandboxPool;
+
+/// The full name of the gRPC service, see `proto/invoker.proto`.
+pub const SERVICE_NAME: &str = "hyperlight_js.v1.Invoker";
+
+const INVOKE_PATH: &str = "/hyperlight_js.v1.Invoker/Invoke";
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A call to a handler, the request of `Invoker.Invoke`.
+#[derive(Clone, PartialEq, Eq, Hash, prost::Message)]
+pub struct InvokeRequest {
+    /// The name of the handler.
+    #[prost(string, tag = "1")]
+    pub handler: String,
+    /// The event passed to the handler, as JSON. Empty for `{}`.
+    #[prost(string, tag = "2")]
+    pub payload: String,
+}
+
+/// The result of a handler, the response of `Invoker.Invoke`.
+#[derive(Clone, PartialEq, Eq, Hash, prost::Message)]
+pub struct InvokeResponse {
+    /// The result of the handler, as JSON.
+    #[prost(string, tag = "1")]
+    pub result: String,
+}
+
+/// The `hyperlight_js.v1.Invoker` gRPC service, defined in
+/// `proto/invoker.proto`, calling handlers of the sandboxes of a
+/// [`SandboxPool`].
+///
+/// The deadline of a call, from its `grpc-timeout` header, guards the handler
+/// with a [`WallClockMonitor`], so a handler still running when the caller
+/// gives up is terminated. The deadline runs from the arrival of the call,
+/// including the time it waits for a free sandbox. The service maps failures to these status codes:
+///
+/// | Cause | Code |
+/// | --- | --- |
+/// | The payload is not valid JSON, or the deadline is invalid | `INVALID_ARGUMENT` |
+/// | The handler is not one of those set with [`with_handlers`](Self::with_handlers) | `NOT_FOUND` |
+/// | The handler ran until the deadline, or the timeout | `DEADLINE_EXCEEDED` |
+/// | The handler failed | `INTERNAL`, without the error |
+///
+/// Only available with the `grpc` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use hyperlight_js::{SandboxBuilder, Script};
+/// use hyperlight_js_http::{InvokeService, SandboxPool};
+///
+/// # async fn serve() -> Result<(), Box<dyn std::error::Error>> {
+/// let pool = SandboxPool::new(4, || {
+///     let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+///     sandbox.add_handler("resize", Script::from_file("resize.js")?)?;
+///     sandbox.get_loaded_sandbox()
+/// });
+/// let service = InvokeService::new(pool)
+///     .with_handlers(["resize"])
+///     .with_timeout(Duration::from_secs(5));
+///
+/// tonic::transport::Server::builder()
+///     .add_service(service)
+///     .serve("0.0.0.0:50051".parse()?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InvokeService {
+    pool: SandboxPool,
+    handlers: Option<Arc<HashSet<String>>>,
+    timeout: Option<Duration>,
+}
+
+impl InvokeService {
+    /// A service calling handlers of the sandboxes of `pool`.
+    pub fn new(pool: SandboxPool) -> Self {
+        Self {
+            pool,
+            handlers: None,
+            timeout: None,
+        }
+    }
+
+    /// Only let callers invoke `handlers`, rather than any handler of the
+    /// sandboxes.
+    pub fn with_handlers<I, S>(mut self, handlers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.handlers = Some(Arc::new(handlers.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Terminate handlers after `timeout`, or at the deadline of the call if
+    /// it comes first. Without a timeout, calls without a deadline are only
+    /// guarded by the default monitors of the sandboxes.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    async fn invoke(
+        &self,
+        request: tonic::Request<InvokeRequest>,
+    ) -> Result<tonic::Response<InvokeResponse>, Status> {
+        let received = Instant::now();
+        let deadline = grpc_timeout(request.metadata())?;
+        let InvokeRequest { handler, payload } = request.into_inner();
+        if let Some(handlers) = &self.handlers
+            && !handlers.contains(&handler)
+        {
+            return Err(Status::not_found(format!("Unknown handler '{handler}'")));
+        }
+        let payload = if payload.is_empty() {
+            "{}".to_string()
+        } else {
+            serde_json::from_str::<serde::de::IgnoredAny>(&payload).map_err(|e| {
+                Status::invalid_argument(format!("The payload is not valid JSON: {e}"))
+            })?;
+            payload
+        };
+
+        let timeout = match (deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) => Some(deadline.min(timeout)),
+            (deadline, timeout) => deadline.or(timeout),
+        };
+        // The deadline runs from the arrival of the call, so the time spent
+        // waiting for a sandbox counts towards it.
+        let deadline = timeout.and_then(|timeout| received.checked_add(timeout));
+        let name = handler.clone();
+        let result = self
+            .pool
+            .run(move |sandbox| {
+                match deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())) {
+                    Some(remaining) if remaining.is_zero() => {
+                        Err(HyperlightError::ExecutionCanceledByHost())
+                    }
+                    Some(remaining) => {
+                        let monitor = WallClockMonitor::new(remaining)?;
+                        sandbox.handle_event_with_monitor(handler, payload, &monitor, None)
+                    }
+                    None => sandbox.handle_event(handler, payload, None),
+                }
+            })
+            .await;
+        match result {
+            Ok(result) => Ok(tonic::Response::new(InvokeResponse { result })),
+            Err(HyperlightError::ExecutionCanceledByHost()) => Err(Status::deadline_exceeded(
+                "The handler did not finish before the deadline",
+            )),
+            Err(e) => {
+                tracing::warn!(handler = %name, error = %e, "The handler failed");
+                Err(Status::internal("The handler failed"))
+            }
+        }
+    }
+}
+
+/// The timeout of a call from its `grpc-timeout` header, see
+/// <https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md>.
+fn grpc_timeout(metadata: &MetadataMap) -> Result<Option<Duration>, Status> {
+    let Some(value) = metadata.get("grpc-timeout") else {
+        return Ok(None);
+    };
+    let invalid = || Status::invalid_argument("Invalid grpc-timeout header");
+    let value = value.to_str().map_err(|_| invalid())?;
+    if value.len() < 2 || value.len() > 9 {
+        return Err(invalid());
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let timeout = match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(Some(timeout))
+}
+
+/// The `Invoke` method, as a [`UnaryService`] for [`Grpc::unary`].
+struct Invoke(InvokeService);
+
+impl UnaryService<InvokeRequest> for Invoke {
+    type Response = InvokeResponse;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<tonic::Response<InvokeResponse>, Status>> + Send>>;
+
+    fn call(&mut self, request: tonic::Request<InvokeRequest>) -> Self::Future {
+        let service = self.0.clone();
+        Box::pin(async move { service.invoke(request).await })
+    }
+}
+
+impl<B> Service<Request<B>> for InvokeService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<BoxError> + Send,
+{
+    type Response = Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future =
+        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        if request.uri().path() != INVOKE_PATH {
+            let status = Status::unimplemented(format!("Unknown method {}", request.uri().path()));
+            return Box::pin(async move { Ok(status.into_http()) });
+        }
+        let method = Invoke(self.clone());
+        Box::pin(async move {
+            let mut grpc = Grpc::new(ProstCodec::default());
+            Ok(grpc.unary(method, request).await)
+        })
+    }
+}
+
+impl NamedService for InvokeService {
+    const NAME: &'static str = SERVICE_NAME;
+}
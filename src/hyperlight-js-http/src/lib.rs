@@ -0,0 +1,35 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! This crate serves HTTP requests with JavaScript handlers running in
+//! hyperlight-js sandboxes, as a [`tower_service::Service`] that works with
+//! axum, hyper and other tower based servers.
+#![deny(dead_code, missing_docs, unused_mut)]
+
+mod event;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod pool;
+mod service;
+
+/// The event passed to handlers by default.
+pub use event::HttpEvent;
+/// The gRPC service invoking handlers, and its messages.
+#[cfg(feature = "grpc")]
+pub use grpc::{InvokeRequest, InvokeResponse, InvokeService, SERVICE_NAME};
+/// A pool of loaded sandboxes.
+pub use pool::SandboxPool;
+/// The tower service serving requests with a handler.
+pub use service::{HandlerService, DEFAULT_MAX_BODY_BYTES};
@@ -0,0 +1,137 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use hyperlight_js::{new_error, LoadedJSSandbox, Result};
+use tokio::sync::Semaphore;
+
+type SandboxFactory = dyn Fn() -> Result<LoadedJSSandbox> + Send + Sync;
+
+/// A pool of loaded sandboxes, each running one call at a time.
+///
+/// Sandboxes are created by the factory of the pool when a call finds no idle
+/// one, up to the size of the pool; further calls wait for a sandbox to be
+/// returned. A sandbox poisoned by a call is dropped rather than returned, and
+/// replaced by a new one when needed.
+///
+/// Clones of the pool share its sandboxes.
+#[derive(Clone)]
+pub struct SandboxPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    factory: Box<SandboxFactory>,
+    idle: Mutex<Vec<LoadedJSSandbox>>,
+    permits: Arc<Semaphore>,
+    size: usize,
+}
+
+impl SandboxPool {
+    /// Create a pool of up to `size` sandboxes made by `factory`, which
+    /// should load the handlers the pool is used for.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyperlight_js::{SandboxBuilder, Script};
+    /// use hyperlight_js_http::SandboxPool;
+    ///
+    /// let pool = SandboxPool::new(4, || {
+    ///     let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+    ///     sandbox.add_handler("handler", Script::from_file("handler.js")?)?;
+    ///     sandbox.get_loaded_sandbox()
+    /// });
+    /// ```
+    pub fn new(
+        size: usize,
+        factory: impl Fn() -> Result<LoadedJSSandbox> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                factory: Box::new(factory),
+                idle: Mutex::new(Vec::new()),
+                permits: Arc::new(Semaphore::new(size)),
+                size,
+            }),
+        }
+    }
+
+    /// Create the sandboxes of the pool now, rather than on the first calls.
+    pub fn fill(&self) -> Result<()> {
+        let mut idle = self.inner.idle();
+        while idle.len() < self.inner.size {
+            idle.push((self.inner.factory)()?);
+        }
+        Ok(())
+    }
+
+    /// The maximum number of sandboxes of the pool.
+    pub fn size(&self) -> usize {
+        self.inner.size
+    }
+
+    /// The number of sandboxes waiting for a call.
+    pub fn idle(&self) -> usize {
+        self.inner.idle().len()
+    }
+
+    /// Run `f` with a sandbox of the pool, on the blocking threads of the
+    /// tokio runtime, waiting for a sandbox if they are all busy.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut LoadedJSSandbox) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let permit = self
+            .inner
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| new_error!("The sandbox pool is closed: {}", e))?;
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let idle = inner.idle().pop();
+            let mut sandbox = match idle {
+                Some(sandbox) => sandbox,
+                None => (inner.factory)()?,
+            };
+            let result = f(&mut sandbox);
+            if !sandbox.poisoned() {
+                inner.idle().push(sandbox);
+            }
+            result
+        })
+        .await
+        .map_err(|e| new_error!("The sandbox call failed: {}", e))?
+    }
+}
+
+impl PoolInner {
+    fn idle(&self) -> MutexGuard<'_, Vec<LoadedJSSandbox>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl std::fmt::Debug for SandboxPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SandboxPool")
+            .field("size", &self.inner.size)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
@@ -0,0 +1,197 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::request::Parts;
+use http::{Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, Full, Limited};
+use hyperlight_js::{HyperlightError, LoadedJSSandbox, MonitorSet, Result};
+use serde_json::Value;
+use tower_service::Service;
+
+use crate::event::{error_response, to_response, HttpEvent};
+use crate::pool::SandboxPool;
+
+/// The default limit on the size of request bodies.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type EventMapper = dyn Fn(&Parts, &Bytes) -> Result<Value> + Send + Sync;
+type HandlerCall = dyn Fn(&mut LoadedJSSandbox, &str, String) -> Result<String> + Send + Sync;
+
+/// A [`tower_service::Service`] serving HTTP requests with a handler of the
+/// sandboxes of a [`SandboxPool`].
+///
+/// Each request is mapped to an event, by default an [`HttpEvent`], and the
+/// output of the handler is converted into the response: an object with a
+/// numeric `statusCode` and optional `headers` and `body` describes the
+/// response, and any other output is sent as a JSON body with status 200.
+///
+/// Requests that cannot be mapped to an event get a 400 response, requests
+/// with a body over the limit a 413, handlers terminated by the monitor a 504,
+/// and handlers that fail a 500 response, whose body does not include the
+/// error.
+///
+/// The service never fails, so it can be used with axum's `route_service`
+/// and `fallback_service`.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{SandboxBuilder, Script};
+/// use hyperlight_js_http::{HandlerService, SandboxPool};
+///
+/// let pool = SandboxPool::new(4, || {
+///     let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+///     sandbox.add_handler(
+///         "hello",
+///         Script::from_content(
+///             r#"function handler(request) {
+///                 return { statusCode: 200, body: `Hello from ${request.path}` };
+///             }"#,
+///         ),
+///     )?;
+///     sandbox.get_loaded_sandbox()
+/// });
+/// let service = HandlerService::new(pool, "hello");
+/// ```
+#[derive(Clone)]
+pub struct HandlerService {
+    pool: SandboxPool,
+    handler: Arc<str>,
+    event_mapper: Arc<EventMapper>,
+    call: Arc<HandlerCall>,
+    max_body_bytes: usize,
+}
+
+impl HandlerService {
+    /// A service calling the handler `handler` of the sandboxes of `pool`.
+    pub fn new(pool: SandboxPool, handler: impl Into<Arc<str>>) -> Self {
+        Self {
+            pool,
+            handler: handler.into(),
+            event_mapper: Arc::new(|parts, body| {
+                Ok(serde_json::to_value(HttpEvent::from_request(parts, body)?)?)
+            }),
+            call: Arc::new(|sandbox, handler, event| sandbox.handle_event(handler, event, None)),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+
+    /// Map requests to the events of the handler with `mapper`, rather than
+    /// to an [`HttpEvent`]. A request for which `mapper` fails gets a 400
+    /// response with the message of the error.
+    pub fn with_event_mapper(
+        mut self,
+        mapper: impl Fn(&Parts, &Bytes) -> Result<Value> + Send + Sync + 'static,
+    ) -> Self {
+        self.event_mapper = Arc::new(mapper);
+        self
+    }
+
+    /// Guard every request with `monitor`, e.g. a
+    /// [`WallClockMonitor`](hyperlight_js::WallClockMonitor), instead of the
+    /// default monitors of the sandboxes. Requests terminated by the monitor
+    /// get a 504 response.
+    pub fn with_monitor(mut self, monitor: impl MonitorSet + 'static) -> Self {
+        self.call = Arc::new(move |sandbox, handler, event| {
+            sandbox.handle_event_with_monitor(handler, event, &monitor, None)
+        });
+        self
+    }
+
+    /// Reject requests whose body is over `max_body_bytes` with a 413
+    /// response. Defaults to [`DEFAULT_MAX_BODY_BYTES`].
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    async fn serve<B>(self, request: Request<B>) -> Response<Full<Bytes>>
+    where
+        B: Body,
+        B::Error: Into<BoxError>,
+    {
+        let (parts, body) = request.into_parts();
+        let body = match Limited::new(body, self.max_body_bytes).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(_) => {
+                return error_response(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "The request body is too large",
+                );
+            }
+        };
+        let event = match (self.event_mapper)(&parts, &body) {
+            Ok(event) => event.to_string(),
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+
+        let handler = self.handler.clone();
+        let call = self.call.clone();
+        let output = self
+            .pool
+            .run(move |sandbox| call(sandbox, &handler, event))
+            .await;
+        match output.and_then(to_response) {
+            Ok(response) => response,
+            Err(HyperlightError::ExecutionCanceledByHost()) => {
+                error_response(StatusCode::GATEWAY_TIMEOUT, "The handler timed out")
+            }
+            Err(e) => {
+                tracing::warn!(handler = %self.handler, error = %e, "The handler failed");
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "The handler failed")
+            }
+        }
+    }
+}
+
+impl<B> Service<Request<B>> for HandlerService
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future =
+        Pin<Box<dyn Future<Output = std::result::Result<Self::Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let service = self.clone();
+        Box::pin(async move { Ok(service.serve(request).await) })
+    }
+}
+
+impl std::fmt::Debug for HandlerService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandlerService")
+            .field("pool", &self.pool)
+            .field("handler", &self.handler)
+            .field("max_body_bytes", &self.max_body_bytes)
+            .finish()
+    }
+}
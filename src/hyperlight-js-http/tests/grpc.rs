@@ -0,0 +1,177 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+#![cfg(feature = "grpc")]
+
+use std::time::{Duration, Instant};
+
+use http::uri::PathAndQuery;
+use hyperlight_js::{SandboxBuilder, Script};
+use hyperlight_js_http::{InvokeRequest, InvokeResponse, InvokeService, SandboxPool};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+use tonic_prost::ProstCodec;
+
+const HANDLERS: &str = r#"
+    function handler(event) {
+        if (event.spin) {
+            while (true) {}
+        }
+        if (event.fail) {
+            throw new Error("secret failure");
+        }
+        return { doubled: event.value * 2 };
+    }
+"#;
+
+fn pool() -> SandboxPool {
+    SandboxPool::new(1, || {
+        let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+        sandbox.add_handler("double", Script::from_content(HANDLERS))?;
+        sandbox.add_handler("hidden", Script::from_content(HANDLERS))?;
+        sandbox.get_loaded_sandbox()
+    })
+}
+
+/// Serve `service` on a local port, and connect a client to it.
+async fn serve(service: InvokeService) -> tonic::client::Grpc<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpIncoming::from(listener)),
+    );
+    let channel = Channel::from_shared(format!("http://{address}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    tonic::client::Grpc::new(channel)
+}
+
+async fn invoke(
+    client: &mut tonic::client::Grpc<Channel>,
+    request: tonic::Request<InvokeRequest>,
+) -> Result<InvokeResponse, tonic::Status> {
+    client.ready().await.unwrap();
+    client
+        .unary(
+            request,
+            PathAndQuery::from_static("/hyperlight_js.v1.Invoker/Invoke"),
+            ProstCodec::default(),
+        )
+        .await
+        .map(tonic::Response::into_inner)
+}
+
+fn request(handler: &str, payload: Value) -> tonic::Request<InvokeRequest> {
+    tonic::Request::new(InvokeRequest {
+        handler: handler.to_string(),
+        payload: payload.to_string(),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handlers_are_invoked() {
+    let mut client = serve(InvokeService::new(pool())).await;
+
+    let response = invoke(&mut client, request("double", json!({ "value": 21 })))
+        .await
+        .unwrap();
+
+    let result: Value = serde_json::from_str(&response.result).unwrap();
+    assert_eq!(result, json!({ "doubled": 42 }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handlers_are_terminated_at_the_deadline() {
+    let mut client = serve(InvokeService::new(pool())).await;
+
+    let mut spin = request("double", json!({ "spin": true }));
+    spin.set_timeout(Duration::from_millis(200));
+    let status = invoke(&mut client, spin).await.unwrap_err();
+    // tonic servers cancel calls at their deadline too, and may get there first
+    assert!(
+        matches!(status.code(), Code::DeadlineExceeded | Code::Cancelled),
+        "{status:?}"
+    );
+    // The handler was terminated, so the only sandbox of the pool is free
+    let mut double = request("double", json!({ "value": 1 }));
+    double.set_timeout(Duration::from_secs(5));
+    invoke(&mut client, double).await.unwrap();
+
+    let mut spin = request("double", json!({ "spin": true }));
+    spin.set_timeout(Duration::from_secs(30));
+    let mut client =
+        serve(InvokeService::new(pool()).with_timeout(Duration::from_millis(200))).await;
+    let status = invoke(&mut client, spin).await.unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded, "{status:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_deadline_includes_the_wait_for_a_sandbox() {
+    let timeout = Duration::from_millis(500);
+    let client = serve(InvokeService::new(pool()).with_timeout(timeout)).await;
+
+    // The only sandbox of the pool is busy with the first call until its
+    // deadline, which is the deadline of the second call too
+    let start = Instant::now();
+    let calls: Vec<_> = (0..2)
+        .map(|_| {
+            let mut client = client.clone();
+            tokio::spawn(async move {
+                invoke(&mut client, request("double", json!({ "spin": true }))).await
+            })
+        })
+        .collect();
+    for call in calls {
+        let status = call.await.unwrap().unwrap_err();
+        assert_eq!(status.code(), Code::DeadlineExceeded, "{status:?}");
+    }
+    assert!(start.elapsed() < timeout * 2, "{:?}", start.elapsed());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invalid_calls_are_rejected() {
+    let mut client = serve(InvokeService::new(pool()).with_handlers(["double"])).await;
+
+    let status = invoke(&mut client, request("hidden", json!({ "value": 1 })))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let invalid = tonic::Request::new(InvokeRequest {
+        handler: "double".to_string(),
+        payload: "{".to_string(),
+    });
+    let status = invoke(&mut client, invalid).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handler_errors_are_not_leaked() {
+    let mut client = serve(InvokeService::new(pool())).await;
+
+    let status = invoke(&mut client, request("double", json!({ "fail": true })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::Internal);
+    assert!(!status.message().contains("secret"));
+}
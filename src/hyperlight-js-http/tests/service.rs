@@ -0,0 +1,154 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use hyperlight_js::{SandboxBuilder, Script, WallClockMonitor};
+use hyperlight_js_http::{HandlerService, SandboxPool};
+use serde_json::{json, Value};
+use tower_service::Service;
+
+const HANDLERS: &str = r#"
+    function handler(request) {
+        switch (request.path) {
+            case "/echo":
+                return request;
+            case "/created":
+                return { statusCode: 201, headers: { "x-id": "42" }, body: "created" };
+            case "/loop":
+                while (true) {}
+            default:
+                throw new Error("not found");
+        }
+    }
+"#;
+
+fn pool(size: usize) -> SandboxPool {
+    SandboxPool::new(size, || {
+        let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+        sandbox.add_handler("handler", Script::from_content(HANDLERS))?;
+        sandbox.get_loaded_sandbox()
+    })
+}
+
+async fn send(
+    service: &mut HandlerService,
+    request: Request<Full<Bytes>>,
+) -> (Response<()>, Bytes) {
+    let response = service.call(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    (
+        Response::from_parts(parts, ()),
+        body.collect().await.unwrap().to_bytes(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_are_mapped_to_events() {
+    let mut service = HandlerService::new(pool(1), "handler");
+    let request = Request::post("/echo?a=1")
+        .header("content-type", "application/json")
+        .header("x-tag", "one")
+        .header("x-tag", "two")
+        .body(Full::new(Bytes::from(r#"{"name":"World"}"#)))
+        .unwrap();
+
+    let (response, body) = send(&mut service, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let event: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        event,
+        json!({
+            "method": "POST",
+            "path": "/echo",
+            "query": "a=1",
+            "headers": { "content-type": "application/json", "x-tag": "one, two" },
+            "body": { "name": "World" },
+        })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handlers_describe_responses_with_a_status_code() {
+    let mut service = HandlerService::new(pool(1), "handler");
+
+    let (response, body) = send(
+        &mut service,
+        Request::get("/created").body(Full::default()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["x-id"], "42");
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(body, "created");
+
+    let (response, body) = send(
+        &mut service,
+        Request::get("/missing").body(Full::default()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(!String::from_utf8_lossy(&body).contains("not found"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn invalid_and_oversized_requests_are_rejected() {
+    let mut service = HandlerService::new(pool(1), "handler").with_max_body_bytes(16);
+
+    let request = Request::post("/echo")
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from("{")))
+        .unwrap();
+    let (response, _) = send(&mut service, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let request = Request::post("/echo")
+        .body(Full::new(Bytes::from("x".repeat(17))))
+        .unwrap();
+    let (response, _) = send(&mut service, request).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_are_guarded_by_the_monitor() {
+    let pool = pool(1);
+    let mut service = HandlerService::new(pool.clone(), "handler")
+        .with_monitor(WallClockMonitor::new(Duration::from_millis(100)).unwrap());
+
+    let (response, _) = send(
+        &mut service,
+        Request::get("/loop").body(Full::default()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    // The poisoned sandbox is dropped, and replaced for the next request
+    assert_eq!(pool.idle(), 0);
+
+    let (response, _) = send(
+        &mut service,
+        Request::get("/created").body(Full::default()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(pool.idle(), 1);
+}
@@ -0,0 +1,101 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyperlight_js::{new_error, Result};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The context of a Lambda invocation, with the fields of the context object
+/// of the Node.js runtime.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LambdaContext {
+    /// The id of the invocation.
+    pub aws_request_id: String,
+    /// The ARN used to invoke the function.
+    pub invoked_function_arn: String,
+    /// The name of the function.
+    pub function_name: String,
+    /// The version of the function.
+    pub function_version: String,
+    /// The memory of the function, in MB.
+    #[serde(rename = "memoryLimitInMB")]
+    pub memory_limit_in_mb: String,
+    /// The CloudWatch log group of the function.
+    pub log_group_name: String,
+    /// The CloudWatch log stream of the function instance.
+    pub log_stream_name: String,
+    /// When the invocation times out, in milliseconds since the Unix epoch.
+    pub deadline_ms: u64,
+    /// The X-Ray tracing header of the invocation.
+    pub trace_id: Option<String>,
+    /// The client context of invocations from the AWS Mobile SDK.
+    pub client_context: Option<Value>,
+    /// The Amazon Cognito identity of invocations from the AWS Mobile SDK.
+    pub identity: Option<Value>,
+}
+
+impl LambdaContext {
+    /// The context of the invocation with the `headers` of the response of
+    /// the Runtime API, and the function settings from the environment.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Result<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let json_header = |name: &str| -> Result<Option<Value>> {
+            header(name)
+                .map(|value| {
+                    serde_json::from_str(&value)
+                        .map_err(|e| new_error!("Invalid {} header: {}", name, e))
+                })
+                .transpose()
+        };
+
+        let aws_request_id = header("lambda-runtime-aws-request-id")
+            .ok_or_else(|| new_error!("The invocation has no request id"))?;
+        let deadline_ms = header("lambda-runtime-deadline-ms")
+            .and_then(|deadline| deadline.parse().ok())
+            .ok_or_else(|| new_error!("The invocation has no valid deadline"))?;
+        Ok(Self {
+            aws_request_id,
+            invoked_function_arn: header("lambda-runtime-invoked-function-arn").unwrap_or_default(),
+            function_name: env::var("AWS_LAMBDA_FUNCTION_NAME").unwrap_or_default(),
+            function_version: env::var("AWS_LAMBDA_FUNCTION_VERSION").unwrap_or_default(),
+            memory_limit_in_mb: env::var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").unwrap_or_default(),
+            log_group_name: env::var("AWS_LAMBDA_LOG_GROUP_NAME").unwrap_or_default(),
+            log_stream_name: env::var("AWS_LAMBDA_LOG_STREAM_NAME").unwrap_or_default(),
+            deadline_ms,
+            trace_id: header("lambda-runtime-trace-id"),
+            client_context: json_header("lambda-runtime-client-context")?,
+            identity: json_header("lambda-runtime-cognito-identity")?,
+        })
+    }
+
+    /// The time left until the deadline of the invocation, like
+    /// `getRemainingTimeInMillis` in Node.js.
+    pub fn remaining_time(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Duration::from_millis(self.deadline_ms).saturating_sub(now)
+    }
+}
@@ -0,0 +1,26 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! This crate runs JavaScript handlers migrated from AWS Lambda in
+//! hyperlight-js sandboxes, implementing the loop of a Lambda custom runtime.
+#![deny(dead_code, missing_docs, unused_mut)]
+
+mod context;
+mod runtime;
+
+/// The context of a Lambda invocation.
+pub use context::LambdaContext;
+/// The Lambda custom runtime, and the events it passes to handlers.
+pub use runtime::{EventShape, LambdaRuntime, DEFAULT_DEADLINE_MARGIN};
@@ -0,0 +1,245 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::env;
+use std::time::Duration;
+
+use hyperlight_js::{new_error, HyperlightError, LoadedJSSandbox, Result, WallClockMonitor};
+use reqwest::blocking::Client;
+use serde::Serialize;
+
+use crate::context::LambdaContext;
+
+/// The version of the Lambda Runtime API.
+const API_VERSION: &str = "2018-06-01";
+
+/// The default time kept between the end of the handler and the deadline of
+/// the invocation, to report the outcome.
+pub const DEFAULT_DEADLINE_MARGIN: Duration = Duration::from_millis(50);
+
+/// The event a [`LambdaRuntime`] passes to the handler.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventShape {
+    /// The Lambda event, as is.
+    #[default]
+    Event,
+    /// An object with the Lambda event as `event` and the
+    /// [`LambdaContext`] as `context`.
+    EventAndContext,
+}
+
+/// A Lambda [custom runtime](https://docs.aws.amazon.com/lambda/latest/dg/runtimes-custom.html)
+/// running a handler in a sandbox.
+///
+/// The runtime gets the invocations of the function from the Lambda Runtime
+/// API, calls the handler with their events, and reports the results, or the
+/// errors, back. Every call is guarded by a [`WallClockMonitor`] firing at the
+/// deadline of the invocation, less a margin to report the outcome.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{SandboxBuilder, Script};
+/// use hyperlight_js_lambda::LambdaRuntime;
+///
+/// // `main` of the `bootstrap` executable of the function
+/// LambdaRuntime::from_env()?.run(|| {
+///     let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+///     sandbox.add_handler("handler", Script::from_file("index.js")?)?;
+///     sandbox.get_loaded_sandbox()
+/// })?;
+/// # Ok::<(), hyperlight_js::HyperlightError>(())
+/// ```
+#[derive(Debug)]
+pub struct LambdaRuntime {
+    client: Client,
+    endpoint: String,
+    handler: String,
+    event_shape: EventShape,
+    deadline_margin: Duration,
+}
+
+/// An error reported to the Runtime API.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorReport {
+    error_message: String,
+    error_type: &'static str,
+}
+
+impl LambdaRuntime {
+    /// A runtime using the Runtime API at `runtime_api`, a `host:port`
+    /// address, calling the handler `handler`.
+    pub fn new(runtime_api: &str) -> Result<Self> {
+        let client = Client::builder()
+            // Waiting for the next invocation can take arbitrarily long
+            .timeout(None)
+            .build()
+            .map_err(|e| new_error!("Failed to create the Runtime API client: {}", e))?;
+        Ok(Self {
+            client,
+            endpoint: format!("http://{runtime_api}/{API_VERSION}/runtime"),
+            handler: "handler".to_string(),
+            event_shape: EventShape::default(),
+            deadline_margin: DEFAULT_DEADLINE_MARGIN,
+        })
+    }
+
+    /// A runtime using the Runtime API of the Lambda environment, from the
+    /// `AWS_LAMBDA_RUNTIME_API` variable.
+    pub fn from_env() -> Result<Self> {
+        let runtime_api = env::var("AWS_LAMBDA_RUNTIME_API")
+            .map_err(|_| new_error!("AWS_LAMBDA_RUNTIME_API is not set"))?;
+        Self::new(&runtime_api)
+    }
+
+    /// Call the handler `handler`, rather than `handler`.
+    pub fn with_handler(mut self, handler: impl Into<String>) -> Self {
+        self.handler = handler.into();
+        self
+    }
+
+    /// Pass the handler events of the shape `event_shape`. Defaults to
+    /// [`EventShape::Event`].
+    pub fn with_event_shape(mut self, event_shape: EventShape) -> Self {
+        self.event_shape = event_shape;
+        self
+    }
+
+    /// Terminate the handler `margin` before the deadline of the invocation.
+    /// Defaults to [`DEFAULT_DEADLINE_MARGIN`].
+    pub fn with_deadline_margin(mut self, margin: Duration) -> Self {
+        self.deadline_margin = margin;
+        self
+    }
+
+    /// Process invocations forever, with a sandbox loaded by `load`.
+    ///
+    /// A failure of `load` is reported to the Runtime API as an
+    /// initialization error, and returned. A sandbox poisoned by an
+    /// invocation, e.g. because the handler timed out, is replaced by a new
+    /// one from `load`. Only errors talking to the Runtime API, or loading a
+    /// replacement sandbox, end the loop.
+    pub fn run(&self, mut load: impl FnMut() -> Result<LoadedJSSandbox>) -> Result<()> {
+        let mut sandbox = match load() {
+            Ok(sandbox) => sandbox,
+            Err(e) => {
+                self.report_init_error(&e)?;
+                return Err(e);
+            }
+        };
+        loop {
+            self.next(&mut sandbox)?;
+            if sandbox.poisoned() {
+                tracing::warn!("The sandbox is poisoned, loading a new one");
+                sandbox = load()?;
+            }
+        }
+    }
+
+    /// Wait for the next invocation, call the handler with its event in
+    /// `sandbox` and report the outcome to the Runtime API.
+    ///
+    /// Failures of the handler are reported to the Runtime API rather than
+    /// returned; only errors talking to the Runtime API are.
+    pub fn next(&self, sandbox: &mut LoadedJSSandbox) -> Result<()> {
+        let response = self
+            .client
+            .get(format!("{}/invocation/next", self.endpoint))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| new_error!("Failed to get the next invocation: {}", e))?;
+        let context = LambdaContext::from_headers(response.headers())?;
+        let event = response
+            .text()
+            .map_err(|e| new_error!("Failed to read the invocation event: {}", e))?;
+
+        match self.invoke(sandbox, event, &context) {
+            Ok(result) => self.post(
+                &format!("invocation/{}/response", context.aws_request_id),
+                result,
+                None,
+            ),
+            Err(e) => {
+                let error_type = match e {
+                    HyperlightError::ExecutionCanceledByHost() => "Sandbox.Timeout",
+                    _ => "Handler.Error",
+                };
+                tracing::warn!(request_id = %context.aws_request_id, error = %e, "The handler failed");
+                self.post_error(
+                    &format!("invocation/{}/error", context.aws_request_id),
+                    &e,
+                    error_type,
+                )
+            }
+        }
+    }
+
+    /// Report a failure to initialize the function to the Runtime API.
+    pub fn report_init_error(&self, error: &HyperlightError) -> Result<()> {
+        self.post_error("init/error", error, "Runtime.InitError")
+    }
+
+    fn invoke(
+        &self,
+        sandbox: &mut LoadedJSSandbox,
+        event: String,
+        context: &LambdaContext,
+    ) -> Result<String> {
+        let event = match self.event_shape {
+            EventShape::Event => event,
+            EventShape::EventAndContext => {
+                let event: serde_json::Value = serde_json::from_str(&event)?;
+                serde_json::json!({ "event": event, "context": context }).to_string()
+            }
+        };
+        let budget = context
+            .remaining_time()
+            .saturating_sub(self.deadline_margin);
+        if budget.is_zero() {
+            return Err(HyperlightError::ExecutionCanceledByHost());
+        }
+        let monitor = WallClockMonitor::new(budget)?;
+        sandbox.handle_event_with_monitor(self.handler.as_str(), event, &monitor, None)
+    }
+
+    fn post_error(
+        &self,
+        path: &str,
+        error: &HyperlightError,
+        error_type: &'static str,
+    ) -> Result<()> {
+        let report = ErrorReport {
+            error_message: error.to_string(),
+            error_type,
+        };
+        self.post(path, serde_json::to_string(&report)?, Some(error_type))
+    }
+
+    fn post(&self, path: &str, body: String, error_type: Option<&str>) -> Result<()> {
+        let mut request = self
+            .client
+            .post(format!("{}/{path}", self.endpoint))
+            .body(body);
+        if let Some(error_type) = error_type {
+            request = request.header("Lambda-Runtime-Function-Error-Type", error_type);
+        }
+        request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| new_error!("Failed to post to {}: {}", path, e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyperlight_js::{new_error, LoadedJSSandbox, SandboxBuilder, Script};
+use hyperlight_js_lambda::{EventShape, LambdaRuntime};
+use serde_json::{json, Value};
+
+const HANDLERS: &str = r#"
+    function handler(event) {
+        if (event.context) {
+            return { id: event.context.awsRequestId, arn: event.context.invokedFunctionArn, event: event.event };
+        }
+        if (event.fail) {
+            throw new Error("handler failed");
+        }
+        if (event.spin) {
+            while (true) {}
+        }
+        return { doubled: event.value * 2 };
+    }
+"#;
+
+/// A request posted to the mock Runtime API.
+#[derive(Debug, Clone)]
+struct Post {
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Post {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+}
+
+/// An invocation queued in the mock Runtime API.
+struct Invocation {
+    id: &'static str,
+    timeout: Duration,
+    event: Value,
+}
+
+/// A minimal Lambda Runtime API, serving queued invocations and recording
+/// the requests posted back.
+struct MockApi {
+    address: String,
+    posts: Arc<Mutex<Vec<Post>>>,
+}
+
+impl MockApi {
+    fn start(invocations: Vec<Invocation>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let invocations = Arc::new(Mutex::new(VecDeque::from(invocations)));
+        let posts = Arc::new(Mutex::new(Vec::new()));
+        let api_posts = posts.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let invocations = invocations.clone();
+                let posts = api_posts.clone();
+                thread::spawn(move || serve(stream.unwrap(), &invocations, &posts));
+            }
+        });
+        Self { address, posts }
+    }
+
+    fn posts(&self) -> Vec<Post> {
+        self.posts.lock().unwrap().clone()
+    }
+}
+
+fn serve(stream: TcpStream, invocations: &Mutex<VecDeque<Invocation>>, posts: &Mutex<Vec<Post>>) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap().to_string();
+        let path = parts.next().unwrap().to_string();
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':').unwrap();
+            headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        }
+        let length = headers
+            .iter()
+            .find(|(name, _)| name == "content-length")
+            .map_or(0, |(_, value)| value.parse().unwrap());
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+
+        let response = if method == "GET" && path.ends_with("/invocation/next") {
+            match invocations.lock().unwrap().pop_front() {
+                Some(invocation) => {
+                    let deadline =
+                        SystemTime::now().duration_since(UNIX_EPOCH).unwrap() + invocation.timeout;
+                    let event = invocation.event.to_string();
+                    format!(
+                        "HTTP/1.1 200 OK\r\n\
+                         Lambda-Runtime-Aws-Request-Id: {}\r\n\
+                         Lambda-Runtime-Deadline-Ms: {}\r\n\
+                         Lambda-Runtime-Invoked-Function-Arn: arn:aws:lambda:test\r\n\
+                         Content-Length: {}\r\n\r\n{}",
+                        invocation.id,
+                        deadline.as_millis(),
+                        event.len(),
+                        event
+                    )
+                }
+                None => "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".into(),
+            }
+        } else {
+            posts.lock().unwrap().push(Post {
+                path,
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            });
+            "HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n".into()
+        };
+        writer.write_all(response.as_bytes()).unwrap();
+    }
+}
+
+fn load() -> hyperlight_js::Result<LoadedJSSandbox> {
+    let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+    sandbox.add_handler("handler", Script::from_content(HANDLERS))?;
+    sandbox.get_loaded_sandbox()
+}
+
+fn invocation(id: &'static str, event: Value) -> Invocation {
+    Invocation {
+        id,
+        timeout: Duration::from_secs(30),
+        event,
+    }
+}
+
+#[test]
+fn results_are_posted_to_the_runtime_api() {
+    let api = MockApi::start(vec![
+        invocation("one", json!({ "value": 1 })),
+        invocation("two", json!({ "value": 21 })),
+    ]);
+    let runtime = LambdaRuntime::new(&api.address).unwrap();
+    let mut sandbox = load().unwrap();
+
+    runtime.next(&mut sandbox).unwrap();
+    runtime.next(&mut sandbox).unwrap();
+
+    let posts = api.posts();
+    assert_eq!(posts.len(), 2);
+    assert_eq!(posts[0].path, "/2018-06-01/runtime/invocation/one/response");
+    assert_eq!(posts[0].json(), json!({ "doubled": 2 }));
+    assert_eq!(posts[1].path, "/2018-06-01/runtime/invocation/two/response");
+    assert_eq!(posts[1].json(), json!({ "doubled": 42 }));
+}
+
+#[test]
+fn handlers_can_get_the_context() {
+    let api = MockApi::start(vec![invocation("ctx", json!({ "value": 1 }))]);
+    let runtime = LambdaRuntime::new(&api.address)
+        .unwrap()
+        .with_event_shape(EventShape::EventAndContext);
+    let mut sandbox = load().unwrap();
+
+    runtime.next(&mut sandbox).unwrap();
+
+    assert_eq!(
+        api.posts()[0].json(),
+        json!({ "id": "ctx", "arn": "arn:aws:lambda:test", "event": { "value": 1 } })
+    );
+}
+
+#[test]
+fn handler_errors_are_posted_to_the_runtime_api() {
+    let api = MockApi::start(vec![invocation("fail", json!({ "fail": true }))]);
+    let runtime = LambdaRuntime::new(&api.address).unwrap();
+    let mut sandbox = load().unwrap();
+
+    runtime.next(&mut sandbox).unwrap();
+
+    let post = &api.posts()[0];
+    assert_eq!(post.path, "/2018-06-01/runtime/invocation/fail/error");
+    assert_eq!(
+        post.header("lambda-runtime-function-error-type"),
+        Some("Handler.Error")
+    );
+    assert_eq!(post.json()["errorType"], "Handler.Error");
+    assert!(post.json()["errorMessage"]
+        .as_str()
+        .unwrap()
+        .contains("handler failed"));
+}
+
+#[test]
+fn handlers_are_terminated_at_the_deadline() {
+    let api = MockApi::start(vec![Invocation {
+        id: "spin",
+        timeout: Duration::from_millis(300),
+        event: json!({ "spin": true }),
+    }]);
+    let runtime = LambdaRuntime::new(&api.address).unwrap();
+    let mut sandbox = load().unwrap();
+
+    runtime.next(&mut sandbox).unwrap();
+
+    let post = &api.posts()[0];
+    assert_eq!(post.path, "/2018-06-01/runtime/invocation/spin/error");
+    assert_eq!(post.json()["errorType"], "Sandbox.Timeout");
+    assert!(sandbox.poisoned());
+}
+
+#[test]
+fn load_errors_are_posted_as_init_errors() {
+    let api = MockApi::start(vec![]);
+    let runtime = LambdaRuntime::new(&api.address).unwrap();
+
+    let result = runtime.run(|| Err(new_error!("no handler")));
+
+    assert!(result.is_err());
+    let post = &api.posts()[0];
+    assert_eq!(post.path, "/2018-06-01/runtime/init/error");
+    assert_eq!(post.json()["errorType"], "Runtime.InitError");
+}
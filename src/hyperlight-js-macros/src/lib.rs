@@ -0,0 +1,179 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Procedural macros for hyperlight-js.
+//!
+//! These are re-exported by the `hyperlight-js` crate, use them from there.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, FnArg, ImplItem, ItemImpl, LitStr};
+
+/// Embed all the files in a directory in a `FileSystemEmbedded`.
+///
+/// See `hyperlight_js::embed_dir!` for details.
+#[proc_macro]
+pub fn embed_dir(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as LitStr);
+
+    match embed_dir_impl(&dir.value()) {
+        Ok(tokens) => tokens,
+        Err(message) => syn::Error::new(dir.span(), message)
+            .to_compile_error()
+            .into(),
+    }
+}
+
+/// Make the methods of a type the host functions of a host module.
+///
+/// See `hyperlight_js::host_module` for details.
+#[proc_macro_attribute]
+pub fn host_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let name = parse_macro_input!(attr as LitStr);
+    let item = parse_macro_input!(item as ItemImpl);
+
+    match host_module_impl(&name, &item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn host_module_impl(name: &LitStr, item: &ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[host_module] does not support generic impl blocks",
+        ));
+    }
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "#[host_module] must be used on an inherent impl block",
+        ));
+    }
+
+    // Every method taking `&self` is a host function. Other associated
+    // functions, like constructors, are left alone.
+    let mut registrations = Vec::new();
+    for impl_item in &item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        let Some(FnArg::Receiver(receiver)) = method.sig.inputs.first() else {
+            continue;
+        };
+        if receiver.reference.is_none() || receiver.mutability.is_some() {
+            continue;
+        }
+        if method.sig.asyncness.is_some() || !method.sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &method.sig,
+                "host functions must be synchronous and not generic",
+            ));
+        }
+
+        let method_name = &method.sig.ident;
+        let function_name = method_name.to_string();
+        let (args, types): (Vec<_>, Vec<_>) = method
+            .sig
+            .inputs
+            .iter()
+            .skip(1)
+            .enumerate()
+            .filter_map(|(i, arg)| match arg {
+                FnArg::Typed(arg) => Some((format_ident!("arg{i}"), &arg.ty)),
+                FnArg::Receiver(_) => None,
+            })
+            .unzip();
+        registrations.push(quote! {
+            {
+                let this = ::std::sync::Arc::clone(&self);
+                module.register(#function_name, move |#(#args: #types),*| this.#method_name(#(#args),*));
+            }
+        });
+    }
+
+    let self_ty = &item.self_ty;
+    Ok(quote! {
+        #item
+
+        impl ::hyperlight_js::HostModuleDefinition for #self_ty {
+            const NAME: &'static str = #name;
+
+            fn register_functions(
+                self: ::std::sync::Arc<Self>,
+                module: &mut ::hyperlight_js::HostModule,
+            ) {
+                #(#registrations)*
+            }
+        }
+    })
+}
+
+fn embed_dir_impl(dir: &str) -> Result<TokenStream, String> {
+    // Relative paths are resolved from the root of the crate invoking the macro.
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| "CARGO_MANIFEST_DIR is not set".to_string())?;
+    let root = Path::new(&manifest_dir).join(dir);
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to read directory {}: {e}", root.display()))?;
+
+    let mut files = Vec::new();
+    collect_files(&root, &mut files)?;
+    files.sort();
+
+    let mut entries = String::new();
+    for file in files {
+        let key = file
+            .strip_prefix(&root)
+            .map_err(|e| format!("Failed to get relative path of {}: {e}", file.display()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let path = file
+            .to_str()
+            .ok_or_else(|| format!("Path {} is not valid UTF-8", file.display()))?;
+        entries.push_str(&format!("{key:?} => {path:?},\n"));
+    }
+
+    format!("::hyperlight_js::embed_modules! {{\n{entries}}}")
+        .parse()
+        .map_err(|e| format!("Failed to generate embedded modules: {e}"))
+}
+
+/// Recursively collect the files in `dir`, skipping hidden files and directories.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {e}", dir.display()))?;
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory {}: {e}", dir.display()))?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! The allocator of the QuickJS heap.
+
+use alloc::alloc::{alloc, alloc_zeroed, dealloc, realloc, Layout};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::{mem, ptr};
+
+use rquickjs::allocator::Allocator;
+
+/// The owner of the allocations that are not made for a handler: the built-ins, the setup
+/// script, the host modules, ...
+pub(crate) const RUNTIME_OWNER: u32 = 0;
+
+// QuickJS allocates values up to a u64, so every allocation is aligned like one.
+const ALIGN: usize = mem::align_of::<u64>();
+
+// The header of an allocation, right before the memory handed to QuickJS. Like the header of
+// `rquickjs::allocator::RustAllocator`, it is the size of a u64, so recording the owner doesn't
+// take any more memory.
+#[derive(Clone, Copy)]
+#[repr(C, align(8))]
+struct Header {
+    // The size of the allocation, without the header.
+    size: u32,
+    // Who the allocation is attributed to.
+    owner: u32,
+}
+
+const HEADER_SIZE: usize = mem::size_of::<Header>();
+
+impl Header {
+    fn layout(self) -> Layout {
+        // SAFETY: the size was checked to fit in a layout when the header was made.
+        unsafe { Layout::from_size_align_unchecked(HEADER_SIZE + self.size as usize, ALIGN) }
+    }
+}
+
+/// The state of the QuickJS heap, shared by the allocator and the runtime.
+#[derive(Default)]
+pub(crate) struct HeapState {
+    // The number of bytes QuickJS may allocate, unlimited if `None`.
+    limit: Cell<Option<usize>>,
+    // The number of bytes QuickJS has allocated.
+    used: Cell<usize>,
+    // Whether an allocation failed since `reset_failed` was last called.
+    failed: Cell<bool>,
+    // Who new allocations are attributed to.
+    owner: Cell<u32>,
+    // The number of bytes allocated and not freed yet, by owner.
+    owned: RefCell<Vec<usize>>,
+}
+
+impl HeapState {
+    pub(crate) fn set_limit(&self, limit: usize) {
+        self.limit.set(Some(limit));
+    }
+
+    /// Whether an allocation failed, because of the limit or because the heap of the process is
+    /// exhausted, since the last call to [`HeapState::reset_failed`].
+    pub(crate) fn failed(&self) -> bool {
+        self.failed.get()
+    }
+
+    pub(crate) fn reset_failed(&self) {
+        self.failed.set(false);
+    }
+
+    /// Attribute the allocations to `owner` until the returned guard is dropped.
+    pub(crate) fn attribute_to(self: &Rc<Self>, owner: u32) -> Attribution {
+        Attribution {
+            previous: self.owner.replace(owner),
+            heap: self.clone(),
+        }
+    }
+
+    /// The number of bytes allocated while the allocations were attributed to `owner`, and not
+    /// freed yet.
+    pub(crate) fn owned(&self, owner: u32) -> usize {
+        self.owned
+            .borrow()
+            .get(owner as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // The header of an allocation of `size` bytes for `owner`, replacing one of `old_size` bytes,
+    // or `None`, recording a failure, if the heap can't grow that much.
+    fn reserve(&self, owner: u32, old_size: usize, size: usize) -> Option<Header> {
+        let fits = |size: usize| {
+            let within_limit = match self.limit.get() {
+                Some(limit) => self.used.get() - old_size + size <= limit,
+                None => true,
+            };
+            within_limit && Layout::from_size_align(HEADER_SIZE + size, ALIGN).is_ok()
+        };
+        let header = size
+            .checked_next_multiple_of(ALIGN)
+            .filter(|size| fits(*size))
+            .and_then(|size| u32::try_from(size).ok())
+            .map(|size| Header { size, owner });
+        if header.is_none() {
+            self.failed.set(true);
+        }
+        header
+    }
+
+    fn add(&self, header: Header) {
+        self.used.set(self.used.get() + header.size as usize);
+        let mut owned = self.owned.borrow_mut();
+        let owner = header.owner as usize;
+        if owned.len() <= owner {
+            owned.resize(owner + 1, 0);
+        }
+        owned[owner] += header.size as usize;
+    }
+
+    fn remove(&self, header: Header) {
+        self.used.set(self.used.get() - header.size as usize);
+        self.owned.borrow_mut()[header.owner as usize] -= header.size as usize;
+    }
+}
+
+/// Attributes the allocations to an owner, see [`HeapState::attribute_to`].
+pub(crate) struct Attribution {
+    heap: Rc<HeapState>,
+    previous: u32,
+}
+
+impl Drop for Attribution {
+    fn drop(&mut self) {
+        self.heap.owner.set(self.previous);
+    }
+}
+
+/// Allocates the QuickJS heap from the Rust allocator, enforcing the memory limit of the runtime,
+/// recording the allocations that fail, and attributing every allocation to an owner.
+///
+/// QuickJS throws the same `InternalError` when an allocation fails as handlers can throw
+/// themselves, so the runtime checks the state of the heap to tell them apart. The limit is
+/// enforced here rather than by QuickJS, whose own limit fails allocations before they get here.
+///
+/// All the realms of a runtime share its heap, so QuickJS can't tell which realm holds what.
+/// Instead, every allocation is attributed to the handler running when it is made, and stays
+/// attributed to it until it is freed.
+pub(crate) struct HeapAllocator {
+    state: Rc<HeapState>,
+}
+
+impl HeapAllocator {
+    pub(crate) fn new(state: Rc<HeapState>) -> Self {
+        Self { state }
+    }
+
+    fn allocate(&mut self, size: usize, zeroed: bool) -> *mut u8 {
+        // Like with `RustAllocator`, empty allocations fail
+        if size == 0 {
+            return ptr::null_mut();
+        }
+        let Some(header) = self.state.reserve(self.state.owner.get(), 0, size) else {
+            return ptr::null_mut();
+        };
+        // SAFETY: the layout is not empty, as it has room for the header.
+        let base = unsafe {
+            match zeroed {
+                true => alloc_zeroed(header.layout()),
+                false => alloc(header.layout()),
+            }
+        };
+        if base.is_null() {
+            self.state.failed.set(true);
+            return ptr::null_mut();
+        }
+        self.state.add(header);
+        // SAFETY: `base` is aligned for the header, and followed by `header.size` bytes.
+        unsafe {
+            base.cast::<Header>().write(header);
+            base.add(HEADER_SIZE)
+        }
+    }
+}
+
+// SAFETY: every allocation is aligned like a u64, and follows its header, which records its size
+// so it is freed and resized with the layout it was allocated with.
+unsafe impl Allocator for HeapAllocator {
+    fn alloc(&mut self, size: usize) -> *mut u8 {
+        self.allocate(size, false)
+    }
+
+    fn calloc(&mut self, count: usize, size: usize) -> *mut u8 {
+        match count.checked_mul(size) {
+            Some(size) => self.allocate(size, true),
+            None => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8) {
+        // SAFETY: the caller guarantees `ptr` was allocated by this allocator, so it follows its
+        // header.
+        unsafe {
+            let base = ptr.sub(HEADER_SIZE);
+            let header = base.cast::<Header>().read();
+            self.state.remove(header);
+            dealloc(base, header.layout());
+        }
+    }
+
+    unsafe fn realloc(&mut self, ptr: *mut u8, new_size: usize) -> *mut u8 {
+        // SAFETY: the caller guarantees `ptr` was allocated by this allocator, so it follows its
+        // header.
+        let (base, old) = unsafe {
+            let base = ptr.sub(HEADER_SIZE);
+            (base, base.cast::<Header>().read())
+        };
+        // The allocation stays attributed to the owner it was allocated for
+        let Some(new) = self.state.reserve(old.owner, old.size as usize, new_size) else {
+            return ptr::null_mut();
+        };
+        // SAFETY: `base` was allocated with the layout of `old`, and the new size was checked to
+        // fit in a layout.
+        let base = unsafe { realloc(base, old.layout(), new.layout().size()) };
+        if base.is_null() {
+            // The old allocation is left as it was
+            self.state.failed.set(true);
+            return ptr::null_mut();
+        }
+        self.state.remove(old);
+        self.state.add(new);
+        // SAFETY: as in `allocate`.
+        unsafe {
+            base.cast::<Header>().write(new);
+            base.add(HEADER_SIZE)
+        }
+    }
+
+    unsafe fn usable_size(ptr: *mut u8) -> usize {
+        // SAFETY: the caller guarantees `ptr` was allocated by this allocator, so it follows its
+        // header.
+        unsafe { ptr.sub(HEADER_SIZE).cast::<Header>().read().size as usize }
+    }
+}
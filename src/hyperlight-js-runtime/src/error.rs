@@ -0,0 +1,133 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use rquickjs::{Array, Coerced, Ctx, Exception, Object, Value};
+
+/// The maximum number of `cause`s followed when describing an error.
+const MAX_CAUSE_DEPTH: usize = 8;
+
+/// The maximum number of the `errors` of an `AggregateError` described.
+const MAX_AGGREGATE_ERRORS: usize = 10;
+
+/// Describe the `cause` chain and the `errors` of an `AggregateError` thrown by a script,
+/// to append to the description of the error itself.
+///
+/// Each error is described by its name, message and stack, e.g.
+///
+/// ```text
+/// Caused by: TypeError: not a function
+///     at load (handler.js:3:9)
+/// ```
+///
+/// so the host gets the full story behind a failure in the guest error message.
+pub(crate) fn describe_causes<'js>(ctx: &Ctx<'js>, exception: &Exception<'js>) -> String {
+    let mut description = String::new();
+    let mut seen: Vec<Object<'js>> = Vec::new();
+    let mut current = exception.as_object().clone();
+    for _ in 0..MAX_CAUSE_DEPTH {
+        seen.push(current.clone());
+        describe_aggregate_errors(ctx, &current, &mut description);
+
+        // `cause` is only set when the error was created with one
+        if !current.contains_key("cause").unwrap_or(false) {
+            break;
+        }
+        let Ok(cause) = current.get::<_, Value>("cause") else {
+            break;
+        };
+        let _ = write!(description, "\nCaused by: {}", describe_value(ctx, &cause));
+        match cause.into_object() {
+            Some(cause) if !seen.contains(&cause) => current = cause,
+            _ => break,
+        }
+    }
+    description
+}
+
+/// Describe the `errors` of `error`, if it is an `AggregateError`.
+fn describe_aggregate_errors<'js>(ctx: &Ctx<'js>, error: &Object<'js>, description: &mut String) {
+    let Ok(Some(errors)) = error.get::<_, Option<Array>>("errors") else {
+        return;
+    };
+    if errors.is_empty() {
+        return;
+    }
+    description.push_str("\nErrors:");
+    for (index, error) in errors.iter::<Value>().enumerate() {
+        if index == MAX_AGGREGATE_ERRORS {
+            let _ = write!(
+                description,
+                "\n  ... and {} more",
+                errors.len() - MAX_AGGREGATE_ERRORS
+            );
+            break;
+        }
+        let description_of_error = match error {
+            Ok(error) => describe_value(ctx, &error),
+            Err(_) => String::from("<unreadable>"),
+        };
+        let _ = write!(
+            description,
+            "\n  [{index}] {}",
+            description_of_error.replace('\n', "\n  ")
+        );
+    }
+}
+
+/// Describe a thrown value: errors by their name, message and stack, other
+/// values as JSON when possible.
+fn describe_value<'js>(ctx: &Ctx<'js>, value: &Value<'js>) -> String {
+    if let Some(exception) = value
+        .as_object()
+        .and_then(|object| Exception::from_object(object.clone()))
+    {
+        let name = exception
+            .get::<_, Option<Coerced<String>>>("name")
+            .ok()
+            .flatten()
+            .map_or_else(|| String::from("Error"), |name| name.0);
+        let mut description = match exception.message() {
+            Some(message) if !message.is_empty() => format!("{name}: {message}"),
+            _ => name,
+        };
+        if let Some(stack) = exception.stack() {
+            let stack = stack.trim_end();
+            if !stack.is_empty() {
+                description.push('\n');
+                description.push_str(stack);
+            }
+        }
+        return description;
+    }
+
+    match ctx.json_stringify(value.clone()) {
+        Ok(Some(json)) => {
+            if let Ok(json) = json.to_string() {
+                return json;
+            }
+        }
+        Ok(None) => {}
+        // Clear the exception so it is not reported as the error of the handler
+        Err(_) => drop(ctx.catch()),
+    }
+    value
+        .get::<Coerced<String>>()
+        .map_or_else(|_| format!("{value:?}"), |string| string.0)
+}
@@ -0,0 +1,82 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use alloc::format;
+use alloc::string::String;
+
+use rquickjs::{Ctx, Exception, Function, Object};
+
+/// The methods of the standard `Atomics` namespace object.
+const ATOMICS_METHODS: &[&str] = &[
+    "add",
+    "and",
+    "compareExchange",
+    "exchange",
+    "isLockFree",
+    "load",
+    "notify",
+    "or",
+    "store",
+    "sub",
+    "wait",
+    "waitAsync",
+    "xor",
+];
+
+/// Create a `NotSupportedError` explaining that `feature` is not available in the sandbox.
+fn not_supported(ctx: &Ctx<'_>, feature: &str) -> rquickjs::Error {
+    let message = format!(
+        "{feature} is not supported: handlers run on a single thread inside a Hyperlight \
+         sandbox, so shared memory and concurrency primitives are not available"
+    );
+    let exception = match Exception::from_message(ctx.clone(), &message) {
+        Ok(exception) => exception,
+        Err(err) => return err,
+    };
+    if let Err(err) = exception.set("name", "NotSupportedError") {
+        return err;
+    }
+    exception.throw()
+}
+
+fn throwing_function<'js>(ctx: &Ctx<'js>, feature: String) -> rquickjs::Result<Function<'js>> {
+    Function::new(ctx.clone(), move |ctx: Ctx<'_>| -> rquickjs::Result<()> {
+        Err(not_supported(&ctx, &feature))
+    })
+}
+
+/// Replace the `SharedArrayBuffer` and `Atomics` globals QuickJS provides with stubs that
+/// throw a descriptive `NotSupportedError`.
+///
+/// The guest is single threaded, so these can never be used meaningfully, and
+/// leaving the QuickJS implementations in place makes code ported from other
+/// runtimes fail in confusing ways.
+pub fn setup(ctx: &Ctx<'_>) -> rquickjs::Result<()> {
+    let globals = ctx.globals();
+
+    let shared_array_buffer =
+        throwing_function(ctx, "SharedArrayBuffer".into())?.with_name("SharedArrayBuffer")?;
+    shared_array_buffer.set_constructor(true);
+    globals.set("SharedArrayBuffer", shared_array_buffer)?;
+
+    let atomics = Object::new(ctx.clone())?;
+    for method in ATOMICS_METHODS {
+        let function = throwing_function(ctx, format!("Atomics.{method}"))?.with_name(method)?;
+        atomics.set(*method, function)?;
+    }
+    globals.set("Atomics", atomics)?;
+
+    Ok(())
+}
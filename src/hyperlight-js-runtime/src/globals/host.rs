@@ -0,0 +1,70 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use rquickjs::{Array, Ctx, Exception, FromIteratorJs as _, Function, Object, Value};
+
+use crate::host_fn::HostModuleLoader;
+
+/// Call several host functions in a single transition to the host.
+///
+/// Each call is an object `{ module, name, args }`, where `args` is an optional array of
+/// arguments. The results are returned as an array, in the same order as the calls.
+/// If any call fails, the whole batch throws.
+fn batch<'js>(ctx: Ctx<'js>, calls: Array<'js>) -> rquickjs::Result<Array<'js>> {
+    let calls = calls
+        .iter::<Object>()
+        .map(|call| {
+            let call = call?;
+            let module: String = call.get("module")?;
+            let name: String = call.get("name")?;
+            let args: Option<Array> = call.get("args")?;
+            let args = match args {
+                Some(args) => args.iter::<Value>().collect::<rquickjs::Result<Vec<_>>>()?,
+                None => Vec::new(),
+            };
+            Ok((module, name, args))
+        })
+        .collect::<rquickjs::Result<Vec<_>>>()?;
+
+    let Some(loader) = ctx
+        .userdata::<HostModuleLoader>()
+        .map(|loader| loader.clone())
+    else {
+        return Err(Exception::throw_internal(
+            &ctx,
+            "HostModuleLoader not found",
+        ));
+    };
+    let results = loader
+        .call_batch(&ctx, calls)
+        .map_err(|e| Exception::throw_internal(&ctx, &format!("{e:#}")))?;
+
+    Array::from_iter_js(&ctx, results)
+}
+
+/// Setup the `host` global object, which exposes helpers to interact with the host.
+pub fn setup(ctx: &Ctx<'_>) -> rquickjs::Result<()> {
+    let host = Object::new(ctx.clone())?;
+    host.set(
+        "batch",
+        Function::new(ctx.clone(), batch)?.with_name("batch")?,
+    )?;
+    ctx.globals().set("host", host)?;
+    Ok(())
+}
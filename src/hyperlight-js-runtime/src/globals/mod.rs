@@ -15,15 +15,19 @@ limitations under the License.
 */
 use rquickjs::Ctx;
 
+mod concurrency;
 mod console;
+mod host;
 mod print;
 mod require;
 mod string;
 
 pub fn setup(ctx: &Ctx<'_>) -> rquickjs::Result<()> {
     string::setup(ctx)?;
+    concurrency::setup(ctx)?;
     print::setup(ctx)?;
     console::setup(ctx)?;
+    host::setup(ctx)?;
     require::setup(ctx)?;
     Ok(())
 }
@@ -0,0 +1,191 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Optional hardening of the JavaScript environment shared by all handlers.
+
+use rquickjs::{Ctx, Result};
+
+// The freezing walks the object graph reachable from the built-in constructors
+// (including the "hidden" intrinsics such as %TypedArray% or the generator
+// prototypes, which are not reachable from a global binding) and freezes every
+// object it finds.
+//
+// Freezing a prototype means that assigning a property it defines on an object
+// that inherits from it throws (the so-called "override mistake"), which breaks
+// common patterns such as `this.name = "MyError"` in an `Error` subclass.
+// To avoid that, a few commonly overridden properties are turned into accessors
+// that define an own property on the receiver when assigned.
+const FREEZE_BUILTINS: &str = r#"
+(() => {
+    "use strict";
+
+    const {
+        defineProperty,
+        freeze,
+        getOwnPropertyDescriptor,
+        getOwnPropertyDescriptors,
+        getPrototypeOf,
+        prototype: { hasOwnProperty },
+    } = Object;
+    const { apply, ownKeys } = Reflect;
+
+    const enableOverride = (obj, key) => {
+        const desc = getOwnPropertyDescriptor(obj, key);
+        if (!desc || !("value" in desc) || !desc.configurable) {
+            return;
+        }
+        const value = desc.value;
+        defineProperty(obj, key, {
+            get() {
+                return value;
+            },
+            set(newValue) {
+                if (this === obj) {
+                    throw new TypeError(`Cannot assign to read only property '${String(key)}' of frozen built-in`);
+                }
+                if (apply(hasOwnProperty, this, [key])) {
+                    this[key] = newValue;
+                } else {
+                    defineProperty(this, key, {
+                        value: newValue,
+                        writable: true,
+                        enumerable: true,
+                        configurable: true,
+                    });
+                }
+            },
+            enumerable: desc.enumerable,
+            configurable: false,
+        });
+    };
+
+    const overrides = [
+        [Object.prototype, ["constructor", "hasOwnProperty", "isPrototypeOf", "propertyIsEnumerable", "toLocaleString", "toString", "valueOf"]],
+        [Array.prototype, ["constructor", "toString", "push"]],
+        [Function.prototype, ["constructor", "name", "toString", "bind", "apply", "call"]],
+        [Promise.prototype, ["constructor"]],
+    ];
+    for (const name of ["Error", "EvalError", "RangeError", "ReferenceError", "SyntaxError", "TypeError", "URIError", "AggregateError", "InternalError"]) {
+        if (typeof globalThis[name] === "function") {
+            overrides.push([globalThis[name].prototype, ["constructor", "name", "message", "toString"]]);
+        }
+    }
+    for (const [obj, keys] of overrides) {
+        for (const key of keys) {
+            enableOverride(obj, key);
+        }
+    }
+
+    const roots = [
+        "Object", "Function", "Array", "Number", "Boolean", "String", "Symbol", "BigInt",
+        "Math", "JSON", "Reflect", "Proxy", "Date", "RegExp", "Promise",
+        "Error", "EvalError", "RangeError", "ReferenceError", "SyntaxError", "TypeError", "URIError",
+        "AggregateError", "InternalError",
+        "Map", "Set", "WeakMap", "WeakSet", "WeakRef", "FinalizationRegistry", "Iterator",
+        "ArrayBuffer", "SharedArrayBuffer", "DataView", "Atomics",
+        "Int8Array", "Uint8Array", "Uint8ClampedArray", "Int16Array", "Uint16Array",
+        "Int32Array", "Uint32Array", "Float16Array", "Float32Array", "Float64Array",
+        "BigInt64Array", "BigUint64Array",
+        "escape", "unescape", "isFinite", "isNaN", "parseFloat", "parseInt",
+        "decodeURI", "decodeURIComponent", "encodeURI", "encodeURIComponent",
+    ].map((name) => globalThis[name]);
+
+    // Intrinsics that are not reachable from a global binding.
+    roots.push(
+        getPrototypeOf(Int8Array),
+        getPrototypeOf(function* () {}),
+        getPrototypeOf(async function () {}),
+        getPrototypeOf(async function* () {}),
+        getPrototypeOf([][Symbol.iterator]()),
+        getPrototypeOf(""[Symbol.iterator]()),
+        getPrototypeOf(new Map()[Symbol.iterator]()),
+        getPrototypeOf(new Set()[Symbol.iterator]()),
+        getPrototypeOf(/./[Symbol.matchAll]("")),
+    );
+
+    const seen = new Set();
+    const pending = [];
+    const enqueue = (value) => {
+        if (((typeof value === "object" && value !== null) || typeof value === "function") && !seen.has(value)) {
+            seen.add(value);
+            pending.push(value);
+        }
+    };
+
+    roots.forEach(enqueue);
+    while (pending.length > 0) {
+        const obj = pending.pop();
+        freeze(obj);
+        const descs = getOwnPropertyDescriptors(obj);
+        for (const key of ownKeys(descs)) {
+            const desc = descs[key];
+            enqueue(desc.value);
+            enqueue(desc.get);
+            enqueue(desc.set);
+        }
+        enqueue(getPrototypeOf(obj));
+    }
+})();
+"#;
+
+/// Freeze the built-in constructors and prototypes (`Object`, `Array`,
+/// `Function`, ...) and everything reachable from them.
+pub(crate) fn freeze_builtins(ctx: &Ctx<'_>) -> Result<()> {
+    ctx.eval::<(), _>(FREEZE_BUILTINS)
+}
+
+// Replacing the global `eval` also disables direct `eval(...)` calls, as the
+// engine only treats a call as a direct eval when it calls the original one.
+//
+// The function constructors are also reachable as the `constructor` of the
+// prototype of every kind of function, so each of these is replaced by a
+// function that throws, keeping the original prototype so that `instanceof`
+// checks keep working.
+const DISABLE_DYNAMIC_CODE: &str = r#"
+(() => {
+    "use strict";
+
+    const { defineProperty, getPrototypeOf } = Object;
+
+    const refuse = (what) => function () {
+        throw new EvalError(`${what} is disabled in this sandbox`);
+    };
+
+    defineProperty(globalThis, "eval", { value: refuse("eval") });
+
+    const constructors = [
+        ["Function", function () {}],
+        ["GeneratorFunction", function* () {}],
+        ["AsyncFunction", async function () {}],
+        ["AsyncGeneratorFunction", async function* () {}],
+    ];
+    for (const [name, sample] of constructors) {
+        const prototype = getPrototypeOf(sample);
+        const refused = refuse(`The ${name} constructor`);
+        defineProperty(refused, "name", { value: name });
+        defineProperty(refused, "prototype", { value: prototype, writable: false });
+        defineProperty(prototype, "constructor", { value: refused });
+    }
+    defineProperty(globalThis, "Function", { value: Function.prototype.constructor });
+})();
+"#;
+
+/// Make `eval` and the function constructors (`Function`, `AsyncFunction`,
+/// ...) throw an `EvalError`, so that handlers cannot evaluate code from
+/// strings.
+/// This has to be done before the built-ins are frozen.
+pub(crate) fn disable_dynamic_code(ctx: &Ctx<'_>) -> Result<()> {
+    ctx.eval::<(), _>(DISABLE_DYNAMIC_CODE)
+}
@@ -17,18 +17,22 @@ use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString as _};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::cell::{Ref, RefCell, RefMut};
 use core::ptr::NonNull;
+use core::time::Duration;
 
 use anyhow::{bail, ensure, Context as _};
 use hashbrown::HashMap;
 use rquickjs::loader::{Loader, Resolver};
 use rquickjs::module::{Declarations, Exports, ModuleDef};
 use rquickjs::prelude::Rest;
-use rquickjs::{Ctx, Exception, Function, JsLifetime, Module, Value};
+use rquickjs::{Array, Ctx, Exception, FromIteratorJs as _, Function, JsLifetime, Module, Value};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::utils::now_micros;
+
 /// A clone of rquickjs::Module so that we can access the ctx from it by transmuting.
 struct NakedModule<'js> {
     _ptr: NonNull<rquickjs::qjs::JSModuleDef>,
@@ -151,11 +155,71 @@ impl ModuleDef for HostModuleDef {
     }
 }
 
+// This prefix and the deserialization of `HostError` have to match the
+// serialization of `HostError` in src/hyperlight-js/src/sandbox/host_error.rs
+const HOST_ERROR_PREFIX: &str = "HostError: ";
+
+/// A structured error returned by a host function, thrown as a JS `Error` with the same name,
+/// message and code.
+#[derive(Debug, serde::Deserialize)]
+pub struct HostError {
+    name: String,
+    message: String,
+    code: Option<String>,
+}
+
+impl HostError {
+    /// An error with the given `name` and `message`.
+    pub fn new(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    /// Set the `code` property of the error.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Parse the message of an error returned by the host, if it is a structured error.
+    pub fn from_message(message: &str) -> Option<Self> {
+        serde_json::from_str(message.strip_prefix(HOST_ERROR_PREFIX)?).ok()
+    }
+
+    /// Throw the error as a JS `Error`, with its `name` and `code` properties set.
+    fn throw(&self, ctx: &Ctx<'_>) -> rquickjs::Error {
+        let exception = match Exception::from_message(ctx.clone(), &self.message) {
+            Ok(exception) => exception,
+            Err(e) => return e,
+        };
+        let object = exception.as_object();
+        if let Err(e) = object.set("name", self.name.as_str()) {
+            return e;
+        }
+        if let Some(code) = &self.code
+            && let Err(e) = object.set("code", code.as_str())
+        {
+            return e;
+        }
+        ctx.throw(exception.into_value())
+    }
+}
+
+impl core::fmt::Display for HostError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
 /// A host function that can be called from JavaScript. This is a wrapper around a Rust closure that
 /// can be called from JavaScript.
 ///
 /// The main purpose of this wrapper is that we can construct them from different types of closures,
-/// and also handles the error conversion from `anyhow::Error` to `rquickjs::Error` in a consistent way.
+/// and also handles the error conversion from `anyhow::Error` to `rquickjs::Error` in a consistent way:
+/// a [`HostError`] is thrown as a JS `Error`, and other errors as internal errors.
 #[derive(Clone)]
 pub struct HostFunction {
     #[allow(clippy::type_complexity)]
@@ -175,9 +239,13 @@ impl HostFunction {
                 move |ctx: &Ctx, args: Rest<Value>| -> rquickjs::Result<Value> {
                     func(ctx, args).map_err(|e| match e.downcast::<rquickjs::Error>() {
                         Ok(e) => e,
-                        Err(e) => {
-                            Exception::throw_internal(ctx, &format!("Host function error: {e:#?}"))
-                        }
+                        Err(e) => match e.downcast_ref::<HostError>() {
+                            Some(error) => error.throw(ctx),
+                            None => Exception::throw_internal(
+                                ctx,
+                                &format!("Host function error: {e:#?}"),
+                            ),
+                        },
                     })
                 },
             ),
@@ -234,6 +302,37 @@ impl HostFunction {
     }
 }
 
+/// How the runtime caches the results of a host function.
+///
+/// Results are cached per JSON serialized arguments, and errors are never cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostFunctionCache {
+    /// Reuse results until the end of the current handler invocation.
+    Invocation,
+    /// Reuse results, across handler invocations, until the given time has elapsed.
+    Ttl(Duration),
+}
+
+/// The cached results of host functions, keyed by module name, function name and JSON serialized
+/// arguments.
+#[derive(Default)]
+struct ResultCache {
+    entries: HashMap<(String, String, String), CachedResult>,
+}
+
+struct CachedResult {
+    result: String,
+    // The time the result expires at, in microseconds since the epoch, or `None` if the result
+    // is only valid for the current invocation.
+    expires_at: Option<u64>,
+}
+
+impl CachedResult {
+    fn is_valid(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
 /// A host module that can be imported from JavaScript. This is a collection of `HostFunction`s that
 /// can be imported as a module from JavaScript.
 #[derive(Default, JsLifetime)]
@@ -259,8 +358,16 @@ impl HostModule {
 #[derive(Clone, Default, JsLifetime)]
 pub struct HostModuleLoader {
     modules: Rc<RefCell<HashMap<String, HostModule>>>,
+    batch: Rc<RefCell<Option<BatchFunction>>>,
+    results: Rc<RefCell<ResultCache>>,
 }
 
+/// A function that calls several host functions in a single transition to the host.
+/// It takes the JSON serialized list of calls, as `[module, function, args]` triples where `args`
+/// is the JSON serialized array of arguments, and returns the JSON serialized array of the JSON
+/// serialized results.
+type BatchFunction = Rc<dyn Fn(String) -> anyhow::Result<String>>;
+
 impl Resolver for HostModuleLoader {
     fn resolve(&mut self, _ctx: &Ctx<'_>, base: &str, name: &str) -> rquickjs::Result<String> {
         if !self.borrow().contains_key(name) {
@@ -291,6 +398,112 @@ impl HostModuleLoader {
         Ok(())
     }
 
+    /// Wrap a JSON host function so that its results are cached according to `cache`.
+    pub(crate) fn cached_function(
+        &self,
+        module_name: String,
+        function_name: String,
+        cache: HostFunctionCache,
+        func: impl Fn(String) -> anyhow::Result<String> + 'static,
+    ) -> HostFunction {
+        let results = self.results.clone();
+        HostFunction::new_json(move |args: String| -> anyhow::Result<String> {
+            let key = (module_name.clone(), function_name.clone(), args);
+            let now = now_micros();
+            if let Some(cached) = results.borrow().entries.get(&key)
+                && cached.is_valid(now)
+            {
+                return Ok(cached.result.clone());
+            }
+
+            let result = func(key.2.clone())?;
+            let expires_at = match cache {
+                HostFunctionCache::Invocation => None,
+                HostFunctionCache::Ttl(ttl) => {
+                    Some(now.saturating_add(u64::try_from(ttl.as_micros()).unwrap_or(u64::MAX)))
+                }
+            };
+            results.borrow_mut().entries.insert(
+                key,
+                CachedResult {
+                    result: result.clone(),
+                    expires_at,
+                },
+            );
+            Ok(result)
+        })
+    }
+
+    /// Start a new handler invocation, dropping the cached results that were only valid for the
+    /// previous invocation, as well as the expired ones.
+    pub(crate) fn start_invocation(&self) {
+        let now = now_micros();
+        self.results
+            .borrow_mut()
+            .entries
+            .retain(|_, cached| cached.expires_at.is_some() && cached.is_valid(now));
+    }
+
+    pub(crate) fn set_batch_function(
+        &self,
+        func: impl Fn(String) -> anyhow::Result<String> + 'static,
+    ) {
+        *self.batch.borrow_mut() = Some(Rc::new(func));
+    }
+
+    /// Call several host functions, returning their results in order.
+    ///
+    /// If a batch function has been set, all the calls are sent to the host at once. Otherwise
+    /// each call is dispatched to the registered `HostFunction`.
+    pub(crate) fn call_batch<'js>(
+        &self,
+        ctx: &Ctx<'js>,
+        calls: Vec<(String, String, Vec<Value<'js>>)>,
+    ) -> anyhow::Result<Vec<Value<'js>>> {
+        let batch = self.batch.borrow().clone();
+        let Some(batch) = batch else {
+            return calls
+                .into_iter()
+                .map(|(module_name, function_name, args)| {
+                    let func = self
+                        .borrow()
+                        .get(&module_name)
+                        .and_then(|module| module.functions.get(&function_name))
+                        .cloned()
+                        .with_context(|| {
+                            format!("Host function {module_name:?} {function_name:?} not found")
+                        })?;
+                    Ok(func.call(ctx, Rest(args))?)
+                })
+                .collect();
+        };
+
+        let calls = calls
+            .into_iter()
+            .map(|(module_name, function_name, args)| {
+                let args = Array::from_iter_js(ctx, args)?;
+                let args = ctx
+                    .json_stringify(args)?
+                    .map(|s| s.to_string())
+                    .transpose()?
+                    .context("Serializing host function arguments")?;
+                Ok((module_name, function_name, args))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let calls = serde_json::to_string(&calls).context("Serializing host function calls")?;
+
+        let results = batch(calls).context("Calling host functions")?;
+        let results: Vec<String> =
+            serde_json::from_str(&results).context("Deserializing host function results")?;
+        results
+            .into_iter()
+            .map(|result| {
+                ctx.json_parse(result)
+                    .context("Parsing host function result")
+            })
+            .collect()
+    }
+
     pub(crate) fn borrow(&self) -> Ref<'_, HashMap<String, HostModule>> {
         self.modules.borrow()
     }
@@ -0,0 +1,263 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Serialization of JavaScript values to JSON, equivalent to `JSON.stringify(value)`.
+//!
+//! Unlike `JSON.stringify`, the JSON is written directly into a Rust `String` as the value is
+//! walked, instead of building a JavaScript string that then has to be copied out of the engine.
+//! This avoids holding two full copies of a large result at once, which otherwise runs the guest
+//! out of memory when the result gets close to the heap limit.
+
+use alloc::string::{String, ToString as _};
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use rquickjs::function::This;
+use rquickjs::{Coerced, Ctx, Exception, Object, Result, Type, Value};
+
+/// Nesting depth from which values are serialized by the engine's `JSON.stringify` instead,
+/// which guards against overflowing the stack on its own.
+const MAX_DEPTH: usize = 128;
+
+/// Serialize `value` to JSON, like `JSON.stringify(value)`.
+///
+/// Returns `None` if `value` cannot be serialized, e.g., if it is `undefined` or a function.
+pub fn stringify<'js>(ctx: &Ctx<'js>, value: Value<'js>) -> Result<Option<String>> {
+    let mut serializer = Serializer::new(ctx)?;
+    let written = serializer.write_value(value, Key::Root)?;
+    Ok(written.then_some(serializer.out))
+}
+
+/// The key a value is serialized under, passed to its `toJSON` method.
+#[derive(Clone, Copy)]
+enum Key<'a> {
+    Root,
+    Property(&'a str),
+    Index(usize),
+}
+
+struct Serializer<'js> {
+    ctx: Ctx<'js>,
+    out: String,
+    // The objects being serialized, to detect circular references.
+    stack: Vec<Object<'js>>,
+    // Prototypes of the wrapper objects of primitives (e.g., `new Number(1)`),
+    // which are serialized as the primitive they wrap.
+    primitive_prototypes: Vec<Object<'js>>,
+}
+
+impl<'js> Serializer<'js> {
+    fn new(ctx: &Ctx<'js>) -> Result<Self> {
+        let globals = ctx.globals();
+        let mut primitive_prototypes = Vec::new();
+        for name in ["Number", "String", "Boolean", "BigInt"] {
+            let constructor: Value = globals.get(name)?;
+            let Some(constructor) = constructor.as_object() else {
+                continue;
+            };
+            if let Some(prototype) = constructor.get::<_, Value>("prototype")?.into_object() {
+                primitive_prototypes.push(prototype);
+            }
+        }
+        Ok(Self {
+            ctx: ctx.clone(),
+            out: String::new(),
+            stack: Vec::new(),
+            primitive_prototypes,
+        })
+    }
+
+    /// Write `value` to the output.
+    /// Returns `false`, without writing anything, if the value cannot be serialized.
+    fn write_value(&mut self, value: Value<'js>, key: Key<'_>) -> Result<bool> {
+        let value = self.to_json(value, key)?;
+        match value.type_of() {
+            Type::Null => self.out.push_str("null"),
+            Type::Bool => {
+                let value = value.as_bool().unwrap_or_default();
+                self.out.push_str(if value { "true" } else { "false" });
+            }
+            Type::Int => {
+                let _ = write!(self.out, "{}", value.as_int().unwrap_or_default());
+            }
+            Type::Float => self.write_float(value)?,
+            Type::String => self.write_string(value)?,
+            Type::Array => self.write_array(value)?,
+            Type::Object | Type::Exception | Type::Promise => self.write_object(value)?,
+            // BigInts (which cannot be serialized unless they have a `toJSON`
+            // method) and proxies are left to the engine.
+            Type::BigInt | Type::Proxy => return self.write_with_engine(value),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Call the `toJSON` method of `value`, if it has one.
+    fn to_json(&self, value: Value<'js>, key: Key<'_>) -> Result<Value<'js>> {
+        let Some(object) = value.as_object() else {
+            return Ok(value);
+        };
+        let to_json: Value = object.get("toJSON")?;
+        let Some(to_json) = to_json.as_function() else {
+            return Ok(value);
+        };
+        let key = match key {
+            Key::Root => String::new(),
+            Key::Property(key) => key.to_string(),
+            Key::Index(index) => index.to_string(),
+        };
+        to_json.call((This(value.clone()), key))
+    }
+
+    fn write_float(&mut self, value: Value<'js>) -> Result<()> {
+        let float = value.as_float().unwrap_or_default();
+        if !float.is_finite() {
+            self.out.push_str("null");
+        } else if float.abs() < (1u64 << 53) as f64 && float as i64 as f64 == float {
+            let _ = write!(self.out, "{}", float as i64);
+        } else {
+            // Let the engine format the number, as the formatting of Rust's f64 differs from JS.
+            let formatted: Coerced<String> = value.get()?;
+            self.out.push_str(&formatted);
+        }
+        Ok(())
+    }
+
+    fn write_string(&mut self, value: Value<'js>) -> Result<()> {
+        let Some(string) = value.as_string() else {
+            return Ok(());
+        };
+        match string.to_string() {
+            Ok(string) => write_escaped(&mut self.out, &string),
+            // Strings that are not valid UTF-8 (e.g., with lone surrogates) are
+            // escaped by the engine.
+            Err(_) => {
+                self.write_with_engine(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_array(&mut self, value: Value<'js>) -> Result<()> {
+        let Some(array) = value.as_array().cloned() else {
+            return Ok(());
+        };
+        if self.enter(array.as_object())? {
+            return self.write_with_engine(value).map(|_| ());
+        }
+        self.out.push('[');
+        for index in 0..array.len() {
+            if index > 0 {
+                self.out.push(',');
+            }
+            let element: Value = array.get(index)?;
+            if !self.write_value(element, Key::Index(index))? {
+                self.out.push_str("null");
+            }
+        }
+        self.out.push(']');
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn write_object(&mut self, value: Value<'js>) -> Result<()> {
+        let Some(object) = value.as_object().cloned() else {
+            return Ok(());
+        };
+        if object
+            .get_prototype()
+            .is_some_and(|prototype| self.primitive_prototypes.contains(&prototype))
+        {
+            return self.write_with_engine(value).map(|_| ());
+        }
+        if self.enter(&object)? {
+            return self.write_with_engine(value).map(|_| ());
+        }
+        self.out.push('{');
+        let mut first = true;
+        for key in object.keys::<String>() {
+            let key = key?;
+            let property: Value = object.get(key.as_str())?;
+            let start = self.out.len();
+            if !first {
+                self.out.push(',');
+            }
+            write_escaped(&mut self.out, &key);
+            self.out.push(':');
+            if self.write_value(property, Key::Property(&key))? {
+                first = false;
+            } else {
+                // Properties that cannot be serialized are skipped.
+                self.out.truncate(start);
+            }
+        }
+        self.out.push('}');
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Start serializing `object`, failing on circular references.
+    /// Returns `true` if the object is nested too deep and must be serialized by the engine.
+    fn enter(&mut self, object: &Object<'js>) -> Result<bool> {
+        if self.stack.contains(object) {
+            return Err(Exception::throw_type(&self.ctx, "circular reference"));
+        }
+        if self.stack.len() >= MAX_DEPTH {
+            return Ok(true);
+        }
+        self.stack.push(object.clone());
+        Ok(false)
+    }
+
+    /// Serialize `value` with the engine's `JSON.stringify`.
+    fn write_with_engine(&mut self, value: Value<'js>) -> Result<bool> {
+        match self.ctx.json_stringify(value)? {
+            Some(json) => {
+                self.out.push_str(&json.to_string()?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Write `string` as a JSON string literal.
+fn write_escaped(out: &mut String, string: &str) {
+    out.reserve(string.len() + 2);
+    out.push('"');
+    let mut start = 0;
+    for (index, byte) in string.bytes().enumerate() {
+        let escape = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x08 => "\\b",
+            0x0c => "\\f",
+            0..0x20 => "",
+            _ => continue,
+        };
+        out.push_str(&string[start..index]);
+        if escape.is_empty() {
+            let _ = write!(out, "\\u{byte:04x}");
+        } else {
+            out.push_str(escape);
+        }
+        start = index + 1;
+    }
+    out.push_str(&string[start..]);
+    out.push('"');
+}
@@ -17,42 +17,159 @@ limitations under the License.
 #![no_main]
 extern crate alloc;
 
+mod allocator;
+mod error;
 mod globals;
+mod hardening;
 pub mod host;
 mod host_fn;
+mod json;
+mod jsonlogic;
 mod libc;
 mod modules;
+mod profiler;
 pub(crate) mod utils;
+pub mod wire;
 
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
 
-use anyhow::{anyhow, Context as _};
+use anyhow::{anyhow, bail, ensure, Context as _};
 use hashbrown::HashMap;
 use rquickjs::loader::{Loader, Resolver};
 use rquickjs::promise::MaybePromise;
-use rquickjs::{Context, Ctx, Function, Module, Persistent, Result, Runtime, Value};
+use rquickjs::{
+    CaughtError, Context, Ctx, Exception, Function, JsLifetime, Module, Object, Persistent, Result,
+    Runtime, Value,
+};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
+use crate::allocator::{HeapAllocator, HeapState, RUNTIME_OWNER};
 use crate::host::Host;
+pub use crate::host_fn::{HostError, HostFunctionCache};
 use crate::host_fn::{HostFunction, HostModuleLoader};
 use crate::modules::NativeModuleLoader;
+use crate::profiler::Profiler;
 
-/// A handler is a javascript function that takes a single `event` object parameter,
-/// and is registered to the static `Context` instance
+/// A handler is either a javascript function that takes an `event` object parameter and a
+/// `context` object parameter, and is registered to the `Context` (realm) it was evaluated in,
+/// a JSON-logic rule that is evaluated natively against the `event`,
+/// or a pipeline of other handlers, each getting the result of the previous one as its `event`.
 #[derive(Clone)]
-struct Handler<'a> {
-    func: Persistent<Function<'a>>,
+enum Handler<'a> {
+    Script(Persistent<Function<'a>>, Context),
+    JsonLogic(Rc<serde_json::Value>),
+    Pipeline(Rc<[String]>),
 }
 
+/// Information about a handler invocation, passed to the handler as its second argument.
+#[derive(Debug, Clone, Default)]
+pub struct HandlerContext {
+    /// The monotonically increasing sequence number of the invocation.
+    pub sequence: u64,
+    /// The seed of the invocation, which seeds the generator of the `random` module.
+    ///
+    /// Running an invocation again with the same seed draws the same random numbers.
+    pub seed: u64,
+    /// The W3C `traceparent` of the span the host invoked the handler in, if it is traced, so
+    /// the handler can propagate it to downstream services.
+    pub traceparent: Option<String>,
+}
+
+impl HandlerContext {
+    fn to_object<'js>(&self, ctx: &Ctx<'js>) -> Result<Object<'js>> {
+        let obj = Object::new(ctx.clone())?;
+        // JS numbers represent integers exactly up to 2^53, well beyond any real sequence.
+        obj.set("sequence", self.sequence as f64)?;
+        // Seeds use all 64 bits, so they are passed as hex strings to be exact.
+        obj.set("seed", format!("{:016x}", self.seed))?;
+        if let Some(traceparent) = &self.traceparent {
+            obj.set("traceparent", traceparent.as_str())?;
+        }
+        Ok(obj)
+    }
+}
+
+/// The name of the error a handler fails with when it is cancelled by the host.
+///
+/// This has to match `CANCELLED_ERROR` in src/hyperlight-js/src/sandbox/cancellation.rs
+pub const CANCELLED_ERROR: &str = "ExecutionCancelled";
+
+/// The name of the error a handler fails with when its result is larger than the limit set with
+/// [`JsRuntime::set_max_result_size`].
+///
+/// This has to match `RESULT_TOO_LARGE_ERROR` in src/hyperlight-js/src/sandbox/result_size.rs
+pub const RESULT_TOO_LARGE_ERROR: &str = "ResultTooLarge";
+
+/// The name of the error a handler fails with when QuickJS runs out of memory, and the handler
+/// doesn't catch the `InternalError` it throws.
+///
+/// This has to match `OUT_OF_MEMORY_ERROR` in src/hyperlight-js/src/sandbox/out_of_memory.rs
+pub const OUT_OF_MEMORY_ERROR: &str = "GuestOutOfMemory";
+
+/// The name of the error a handler call fails with when no handler is registered with its name.
+/// The message of the error is `No handler registered for function ` followed by the name of the
+/// handler.
+///
+/// This has to match `UNKNOWN_HANDLER_ERROR` and `NO_HANDLER_REGISTERED` in src/hyperlight-js/src/sandbox/js_sandbox_error.rs
+pub const UNKNOWN_HANDLER_ERROR: &str = "UnknownHandler";
+
+/// The name of the error a handler fails with when a module it imports can't be resolved or
+/// loaded. The exception thrown in the guest carries it as its `code`.
+///
+/// This has to match `MODULE_ERROR` in src/hyperlight-js/src/sandbox/js_sandbox_error.rs
+pub const MODULE_ERROR: &str = "ModuleResolution";
+
+/// The name of the error a handler fails with when it throws an exception, or the runtime fails
+/// to evaluate it.
+///
+/// This has to match `RUNTIME_ERROR` in src/hyperlight-js/src/sandbox/js_sandbox_error.rs
+pub const RUNTIME_ERROR: &str = "Runtime error";
+
+/// The specifier handlers import the exports of the setup script from, see
+/// [`JsRuntime::register_setup_script`].
+pub const SETUP_MODULE: &str = "setup";
+
+// The name the path of the setup module is made from, like the function name of a handler.
+// This has to match SETUP_MODULE_NAME in src/hyperlight-js/src/sandbox/js_sandbox.rs
+const SETUP_MODULE_NAME: &str = "__setup__";
+
 /// This is the main entry point for the library.
 /// It manages the QuickJS runtime, as well as the registered handlers and host modules.
 pub struct JsRuntime {
+    runtime: Runtime,
     context: Context,
+    // Whether every handler gets its own realm, which is dropped with the handler.
+    per_handler_realms: bool,
+    // Whether new realms get a seeded `Math.random`, frozen built-ins, and dynamic code disabled.
+    seeded_math_random: bool,
+    frozen_builtins: bool,
+    dynamic_code_disabled: bool,
+    // Whether the module loader refuses to load module sources, while handlers run with dynamic
+    // code disabled.
+    module_sources_locked: Rc<Cell<bool>>,
     handlers: HashMap<String, Handler<'static>>,
+    // The owners the QuickJS heap is attributed to for every handler, by name.
+    owners: HashMap<String, u32>,
+    preloaded: Rc<RefCell<PreloadedModules>>,
+    // The path and source of the setup module, once it has been evaluated.
+    setup: Rc<RefCell<Option<(String, String)>>>,
+    // Whether the running script was interrupted because the host cancelled it.
+    cancelled: Rc<Cell<bool>>,
+    // The function polled to check whether the host cancelled the execution.
+    cancellation_check: Option<Rc<dyn Fn() -> bool>>,
+    // The state of the QuickJS heap, to limit it and to tell whether it ran out of memory.
+    heap: Rc<HeapState>,
+    profiler: Option<Rc<Profiler>>,
+    // The maximum size of the results of the handlers, in bytes.
+    max_result_size: Option<usize>,
 }
 
 // SAFETY:
@@ -71,7 +188,9 @@ impl JsRuntime {
     /// The resulting runtime will have global objects registered.
     #[instrument(skip_all, level = "info")]
     pub fn new<H: Host + 'static>(host: H) -> anyhow::Result<Self> {
-        let runtime = Runtime::new().context("Unable to initialize JS_RUNTIME")?;
+        let heap = Rc::new(HeapState::default());
+        let runtime = Runtime::new_with_alloc(HeapAllocator::new(heap.clone()))
+            .context("Unable to initialize JS_RUNTIME")?;
         let context = Context::full(&runtime).context("Unable to create JS context")?;
 
         // Setup the module loader.
@@ -80,6 +199,9 @@ impl JsRuntime {
         let host_loader = HostModuleLoader::default();
         let native_loader = NativeModuleLoader;
         let module_loader = ModuleLoader::new(host);
+        let preloaded = module_loader.preloaded.clone();
+        let setup = module_loader.setup.clone();
+        let module_sources_locked = module_loader.sources_locked.clone();
 
         let loader = (host_loader.clone(), native_loader, module_loader);
         runtime.set_loader(loader.clone(), loader);
@@ -87,18 +209,165 @@ impl JsRuntime {
         context.with(|ctx| -> anyhow::Result<()> {
             // we need to install the host loader in the context as the loader uses the context to
             // store some global state needed for module instantiation.
+            // The userdata of the context is shared by all the contexts of the runtime, so this
+            // also installs it in the realms of the handlers.
             host_loader.install(&ctx)?;
+            Failures::install(&ctx, heap.clone())?;
 
             // Setup the global objects in the context, so they are available to the handler scripts.
             globals::setup(&ctx).catch(&ctx)
         })?;
 
         Ok(Self {
+            runtime,
             context,
+            per_handler_realms: false,
+            seeded_math_random: false,
+            frozen_builtins: false,
+            dynamic_code_disabled: false,
+            module_sources_locked,
             handlers: HashMap::new(),
+            owners: HashMap::new(),
+            preloaded,
+            setup,
+            cancelled: Rc::default(),
+            cancellation_check: None,
+            heap,
+            profiler: None,
+            max_result_size: None,
         })
     }
 
+    /// Freeze the built-in constructors and prototypes (`Object`, `Array`, `Function`, ...),
+    /// so that handlers cannot modify the globals they share with other handlers.
+    /// This should be called once the runtime has been set up and before any handler is registered.
+    pub fn freeze_builtins(&mut self) -> anyhow::Result<()> {
+        self.frozen_builtins = true;
+        self.context
+            .with(|ctx| hardening::freeze_builtins(&ctx).catch(&ctx))
+    }
+
+    /// Make `eval` and the function constructors (`Function`, `AsyncFunction`, ...) throw an
+    /// `EvalError`, and make handlers fail to import module sources they did not import when they
+    /// were registered, so that handlers cannot run code that was not loaded with them.
+    /// This should be called before the built-ins are frozen.
+    pub fn disable_dynamic_code(&mut self) -> anyhow::Result<()> {
+        self.dynamic_code_disabled = true;
+        self.context
+            .with(|ctx| hardening::disable_dynamic_code(&ctx).catch(&ctx))
+    }
+
+    /// Evaluate every handler registered afterwards in its own realm, with its own global object
+    /// and built-ins, so that handlers cannot see each other's global mutations.
+    /// Host modules are available in every realm, and a handler importing the setup script
+    /// evaluates it again in its realm.
+    /// This should be called once the runtime has been set up and before any handler is registered.
+    pub fn set_per_handler_realms(&mut self) {
+        self.per_handler_realms = true;
+    }
+
+    /// Limit the memory QuickJS allocates to `limit` bytes. Allocations over the limit fail with
+    /// an out of memory error that handlers can catch, rather than exhausting the heap of the
+    /// process.
+    pub fn set_memory_limit(&mut self, limit: usize) {
+        self.heap.set_limit(limit);
+    }
+
+    /// Limit the results of the handlers to `limit` bytes of JSON. A handler returning a larger
+    /// result fails with a [`RESULT_TOO_LARGE_ERROR`] error instead of the result being written
+    /// out.
+    pub fn set_max_result_size(&mut self, limit: usize) {
+        self.max_result_size = Some(limit);
+    }
+
+    /// Limit the native stack QuickJS uses to `limit` bytes. Deeper recursion fails with a
+    /// stack overflow error that handlers can catch, rather than overflowing the stack.
+    pub fn set_max_stack_size(&mut self, limit: usize) {
+        self.runtime.set_max_stack_size(limit);
+    }
+
+    /// Measure the stack limit from the current stack pointer. The guest always runs on the same
+    /// stack, but a runtime embedded in a host process may be called from other threads than the
+    /// one it was created on, whose stacks are elsewhere.
+    pub fn update_stack_top(&mut self) {
+        self.context.with(|ctx| {
+            // SAFETY: the runtime pointer comes from a live context, and updating the stack top
+            // only reads the current stack pointer.
+            unsafe {
+                rquickjs::qjs::JS_UpdateStackTop(rquickjs::qjs::JS_GetRuntime(
+                    ctx.as_raw().as_ptr(),
+                ))
+            }
+        });
+    }
+
+    /// Run a garbage collection cycle whenever QuickJS has allocated `threshold` bytes since the
+    /// last one.
+    pub fn set_gc_threshold(&mut self, threshold: usize) {
+        self.runtime.set_gc_threshold(threshold);
+    }
+
+    /// Make `Math.random` draw from the generator of the `random` module, which is reseeded with
+    /// the seed of every invocation, so invocations are reproducible from their seed.
+    /// This should be called before the built-ins are frozen.
+    pub fn seed_math_random(&mut self) -> anyhow::Result<()> {
+        self.seeded_math_random = true;
+        self.context.with(|ctx| seed_math_random(&ctx))
+    }
+
+    /// Set the function polled by the engine while running JavaScript to check whether the host
+    /// cancelled the execution.
+    /// Once it returns `true`, the running script is interrupted (which cannot be caught by the
+    /// script), and the handler fails with a [`CANCELLED_ERROR`] error.
+    pub fn set_cancellation_check(&mut self, check: impl Fn() -> bool + 'static) {
+        self.cancellation_check = Some(Rc::new(check));
+        self.set_interrupt_handler();
+    }
+
+    /// Sample the JavaScript call stack every `interval_micros` microseconds while JavaScript
+    /// runs, see [`JsRuntime::take_profile`].
+    /// Stack traces are raised to the deepest QuickJS records, by setting `Error.stackTraceLimit`,
+    /// so this should be called before the built-ins are frozen.
+    pub fn enable_profiling(&mut self, interval_micros: u64) -> anyhow::Result<()> {
+        let ctx = self.context.with(|ctx| -> anyhow::Result<_> {
+            let error: Object = ctx.globals().get("Error").catch(&ctx)?;
+            error
+                .set("stackTraceLimit", profiler::MAX_DEPTH as u32)
+                .catch(&ctx)?;
+            Ok(ctx.as_raw())
+        })?;
+        self.profiler = Some(Rc::new(Profiler::new(ctx, interval_micros)));
+        self.set_interrupt_handler();
+        Ok(())
+    }
+
+    /// Returns the samples of the call stack taken since the last call, in the folded format of
+    /// inferno and flamegraph.pl, or an empty string if profiling is not enabled.
+    pub fn take_profile(&mut self) -> String {
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.take())
+            .unwrap_or_default()
+    }
+
+    // The engine has a single interrupt handler, which checks for cancellation and samples the
+    // call stack.
+    fn set_interrupt_handler(&mut self) {
+        let cancelled = self.cancelled.clone();
+        let check = self.cancellation_check.clone();
+        let profiler = self.profiler.clone();
+        self.runtime.set_interrupt_handler(Some(Box::new(move || {
+            if let Some(profiler) = &profiler {
+                profiler.poll();
+            }
+            let cancel = check.as_ref().is_some_and(|check| check());
+            if cancel {
+                cancelled.set(true);
+            }
+            cancel
+        })));
+    }
+
     /// Register a host function in the specified module.
     /// The function takes and returns a JSON string, which is deserialized and serialized by the runtime.
     /// The arguments are serialized as a JSON array containing all the arguments passed to the function.
@@ -142,6 +411,111 @@ impl JsRuntime {
         })
     }
 
+    /// Register a host function in the specified module, like [`JsRuntime::register_json_host_function`],
+    /// caching its results according to `cache` instead of calling the function every time.
+    pub fn register_cached_json_host_function(
+        &mut self,
+        module_name: impl Into<String>,
+        function_name: impl Into<String>,
+        cache: HostFunctionCache,
+        function: impl Fn(String) -> anyhow::Result<String> + 'static,
+    ) -> anyhow::Result<()> {
+        let module_name = module_name.into();
+        let function_name = function_name.into();
+        self.context.with(|ctx| {
+            let loader = ctx
+                .userdata::<HostModuleLoader>()
+                .context("HostModuleLoader not found in context")?;
+            let function =
+                loader.cached_function(module_name.clone(), function_name.clone(), cache, function);
+            loader
+                .borrow_mut()
+                .entry(module_name)
+                .or_default()
+                .add_function(function_name, function);
+            Ok(())
+        })
+    }
+
+    /// Set the function used by `host.batch` to call several host functions at once.
+    /// The function takes the JSON serialized list of calls, as `[module, function, args]` triples
+    /// where `args` is the JSON serialized array of arguments, and returns the JSON serialized array
+    /// of the JSON serialized results.
+    /// If no batch function is set, `host.batch` calls the registered host functions one by one.
+    pub fn set_host_batch_function(
+        &mut self,
+        function: impl Fn(String) -> anyhow::Result<String> + 'static,
+    ) -> anyhow::Result<()> {
+        self.context.with(|ctx| {
+            ctx.userdata::<HostModuleLoader>()
+                .context("HostModuleLoader not found in context")?
+                .set_batch_function(function);
+            Ok(())
+        })
+    }
+
+    /// Evaluate the setup script, like [`JsRuntime::register_setup_script`], using the given
+    /// pre-bundled modules to resolve and load its imports instead of calling out to the host.
+    pub fn register_setup_script_with_modules(
+        &mut self,
+        setup_script: impl Into<String>,
+        setup_pwd: impl Into<String>,
+        modules: PreloadedModules,
+    ) -> anyhow::Result<()> {
+        *self.preloaded.borrow_mut() = modules;
+        let result = self.register_setup_script(setup_script, setup_pwd);
+        *self.preloaded.borrow_mut() = PreloadedModules::default();
+        result
+    }
+
+    /// Evaluate the setup script, a JavaScript module doing the initialization shared by the
+    /// handlers, like loading configuration or building lookup tables.
+    /// The module is evaluated once, and handlers registered afterwards import its exports from
+    /// the [`SETUP_MODULE`] specifier.
+    pub fn register_setup_script(
+        &mut self,
+        setup_script: impl Into<String>,
+        setup_pwd: impl Into<String>,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            self.setup.borrow().is_none(),
+            "A setup script has already been registered"
+        );
+        let setup_path = make_handler_path(SETUP_MODULE_NAME, &setup_pwd.into());
+        let setup_script = setup_script.into();
+
+        self.start_script();
+        let result = self.context.with(|ctx| -> anyhow::Result<()> {
+            let module = Module::declare(ctx.clone(), setup_path.as_str(), setup_script.clone())
+                .catch(&ctx)?;
+            let (_, promise) = module.eval().catch(&ctx)?;
+            promise.finish::<()>().catch(&ctx)
+        });
+        self.check_cancelled(result)?;
+
+        *self.setup.borrow_mut() = Some((setup_path, setup_script));
+        Ok(())
+    }
+
+    /// Register a handler function with the runtime, like [`JsRuntime::register_handler`], using the
+    /// given pre-bundled modules to resolve and load the handler's imports instead of calling out to
+    /// the host.
+    /// Imports that are not in `modules` are still resolved and loaded through the host.
+    pub fn register_handler_with_modules(
+        &mut self,
+        function_name: impl Into<String>,
+        handler_script: impl Into<String>,
+        handler_pwd: impl Into<String>,
+        modules: PreloadedModules,
+    ) -> anyhow::Result<()> {
+        *self.preloaded.borrow_mut() = modules;
+        let result = self.register_handler(function_name, handler_script, handler_pwd);
+        // Modules that were not imported while registering the handler are not needed anymore,
+        // and any later import (e.g., a dynamic `import()`) goes through the host.
+        *self.preloaded.borrow_mut() = PreloadedModules::default();
+        result
+    }
+
     /// Register a handler function with the runtime.
     /// The handler script is a JavaScript module that exports a function named `handler`.
     /// The handler function takes a single argument, which is the event data deserialized from a JSON string.
@@ -167,7 +541,21 @@ impl JsRuntime {
         // We create a "virtual" path for the handler module based on the function name and the provided handler directory.
         let handler_path = make_handler_path(&function_name, &handler_pwd);
 
-        let func = self.context.with(|ctx| -> anyhow::Result<_> {
+        // The realm of the handler, and what its script allocates, are attributed to it.
+        let next_owner = self.owners.len() as u32 + 1;
+        let owner = *self
+            .owners
+            .entry(function_name.clone())
+            .or_insert(next_owner);
+        let _attribution = self.heap.attribute_to(owner);
+
+        let context = match self.per_handler_realms {
+            true => self.new_realm()?,
+            false => self.context.clone(),
+        };
+
+        self.start_script();
+        let func = context.with(|ctx| -> anyhow::Result<_> {
             // Declare the module for the handler script, and evaluate it to get the exported handler function.
             let module =
                 Module::declare(ctx.clone(), handler_path.as_str(), handler_script.clone())
@@ -182,57 +570,281 @@ impl JsRuntime {
 
             // Save the handler function as a Persistent so it can be returned outside of the `enter` closure.
             Ok(Persistent::save(&ctx, handler_func))
-        })?;
+        });
+        let func = self.check_cancelled(func)?;
 
         // Store the handler function in the `handlers` map, so it can be called later when the handler is triggered.
-        self.handlers.insert(function_name, Handler { func });
+        // This drops the handler it replaces, and with it the realm that handler was evaluated in.
+        self.handlers
+            .insert(function_name, Handler::Script(func, context));
+
+        Ok(())
+    }
 
+    /// Register a handler that evaluates a [JSON-logic](https://jsonlogic.com) rule against the event
+    /// instead of running JavaScript.
+    /// The rule is evaluated natively, and the handler returns the result of the rule.
+    pub fn register_jsonlogic_handler(
+        &mut self,
+        function_name: impl Into<String>,
+        rule: &str,
+    ) -> anyhow::Result<()> {
+        let rule: serde_json::Value =
+            serde_json::from_str(rule).context("The JSON-logic rule is not valid JSON")?;
+        self.handlers
+            .insert(function_name.into(), Handler::JsonLogic(Rc::new(rule)));
+        Ok(())
+    }
+
+    /// Register a handler that runs the registered handlers `stages` in order, passing the result
+    /// of every stage as the event of the next one, and returns the result of the last stage.
+    ///
+    /// The stages have to be registered before the pipeline, and can't be pipelines themselves.
+    pub fn register_pipeline(
+        &mut self,
+        function_name: impl Into<String>,
+        stages: Vec<String>,
+    ) -> anyhow::Result<()> {
+        if stages.is_empty() {
+            bail!("A pipeline must have at least one stage");
+        }
+        for stage in &stages {
+            match self.handlers.get(stage) {
+                None => bail!("No handler registered for the pipeline stage {stage}"),
+                Some(Handler::Pipeline(_)) => {
+                    bail!("The pipeline stage {stage} is a pipeline itself")
+                }
+                Some(_) => {}
+            }
+        }
+        self.handlers
+            .insert(function_name.into(), Handler::Pipeline(stages.into()));
         Ok(())
     }
 
+    /// Compute the memory usage of the QuickJS heap, e.g. to detect leaks across invocations.
+    pub fn memory_usage(&self) -> rquickjs::runtime::MemoryUsage {
+        self.runtime.memory_usage()
+    }
+
+    /// Compute the number of bytes of the QuickJS heap attributed to every script handler: the
+    /// bytes allocated while it was registered or running, including its realm, and not freed
+    /// yet. The rest of the heap is used by the built-ins, the setup script and the host modules.
+    ///
+    /// A handler holding memory allocated by another one, e.g. through a module they share, is
+    /// not attributed that memory.
+    pub fn memory_usage_by_handler(&self) -> BTreeMap<String, usize> {
+        self.handlers
+            .iter()
+            .filter(|(_, handler)| matches!(handler, Handler::Script(..)))
+            .map(|(name, _)| {
+                let owner = self.owners.get(name).copied().unwrap_or(RUNTIME_OWNER);
+                (name.clone(), self.heap.owned(owner))
+            })
+            .collect()
+    }
+
+    /// Count the enumerable properties of the global object. The built-in
+    /// globals are not enumerable, so these are mostly created by handlers.
+    pub fn global_count(&self) -> usize {
+        let realms = self.handlers.values().filter_map(|handler| match handler {
+            Handler::Script(_, realm) if self.per_handler_realms => Some(realm),
+            _ => None,
+        });
+        core::iter::once(&self.context)
+            .chain(realms)
+            .map(|realm| realm.with(|ctx| ctx.globals().keys::<rquickjs::Atom>().count()))
+            .sum()
+    }
+
+    /// Run a garbage collection cycle, freeing the objects only reachable from reference cycles.
+    pub fn run_gc(&self) {
+        self.runtime.run_gc();
+    }
+
     /// Run a registered handler function with the given event data.
     /// The event data is passed as a JSON string, and the handler function is expected to return a value that can be serialized to JSON.
-    /// The result is returned as a JSON string.
+    /// The result is returned as a JSON string, unless it is larger than the limit set with
+    /// [`JsRuntime::set_max_result_size`].
     /// If `run_gc` is true, the runtime will run a garbage collection cycle after running the handler.
     pub fn run_handler(
         &mut self,
         function_name: String,
         event: String,
+        context: HandlerContext,
+        run_gc: bool,
+    ) -> anyhow::Result<String> {
+        let result = self.invoke_handler(function_name, event, context, run_gc)?;
+        match self.max_result_size {
+            Some(limit) if result.len() > limit => Err(anyhow!(
+                "{RESULT_TOO_LARGE_ERROR}: the result is {} bytes, more than the limit of {limit} bytes",
+                result.len()
+            )),
+            _ => Ok(result),
+        }
+    }
+
+    // Run a registered handler, like `run_handler`, without limiting the size of its result.
+    fn invoke_handler(
+        &mut self,
+        function_name: String,
+        event: String,
+        context: HandlerContext,
         run_gc: bool,
     ) -> anyhow::Result<String> {
         // Get the handler function from the `handlers` map. If there is no handler registered for the given function name, return an error.
         let handler = self
             .handlers
             .get(&function_name)
-            .with_context(|| format!("No handler registered for function {function_name}"))?
+            .with_context(|| {
+                format!(
+                    "{UNKNOWN_HANDLER_ERROR}: No handler registered for function {function_name}"
+                )
+            })?
             .clone();
 
+        let (func, realm) = match handler {
+            Handler::Script(func, realm) => (func, realm),
+            Handler::JsonLogic(rule) => {
+                let data: serde_json::Value =
+                    serde_json::from_str(&event).context("The event is not valid JSON")?;
+                let result = jsonlogic::apply(&rule, &data)?;
+                return Ok(serde_json::to_string(&result)?);
+            }
+            Handler::Pipeline(stages) => {
+                let mut event = event;
+                for stage in stages.iter() {
+                    event = self
+                        .invoke_handler(stage.clone(), event, context.clone(), false)
+                        .with_context(|| {
+                            format!("In stage {stage} of the pipeline {function_name}")
+                        })?;
+                }
+                if run_gc {
+                    self.run_gc();
+                }
+                return Ok(event);
+            }
+        };
+
         // Create a guard that will flush any output when dropped (i.e., after running the handler).
         // This makes sure that any output generated through libc is flushed out of the libc's stdout buffer.
         let _guard = FlushGuard;
 
         // Evaluate `handler(event)`, and get resulting object as String
-        self.context.with(|ctx| {
+        let owner = self.owners.get(&function_name).copied();
+        let _attribution = self.heap.attribute_to(owner.unwrap_or(RUNTIME_OWNER));
+        self.start_script();
+        modules::random::reseed(context.seed);
+        self.module_sources_locked.set(self.dynamic_code_disabled);
+        let result = realm.with(|ctx| {
             // Create a guard that will run a GC cycle when dropped if `run_gc` is true.
             let _gc_guard = MaybeRunGcGuard::new(run_gc, &ctx);
 
+            // Results cached for the previous invocation must not be reused in this one.
+            ctx.userdata::<HostModuleLoader>()
+                .context("HostModuleLoader not found in context")?
+                .start_invocation();
+
+            // The trace context of the previous invocation must not leak into this one.
+            match &context.traceparent {
+                Some(traceparent) => ctx.globals().set("__traceparent", traceparent.as_str()),
+                None => ctx.globals().remove("__traceparent"),
+            }
+            .catch(&ctx)?;
+
             // Restore the handler function from the Persistent reference.
-            let func = handler.func.clone().restore(&ctx).catch(&ctx)?;
+            let func = func.restore(&ctx).catch(&ctx)?;
 
-            // Call it with the event data parsed as a JSON value.
+            // Call it with the event data parsed as a JSON value, and the invocation context.
             let arg = ctx.json_parse(event).catch(&ctx)?;
+            let context = context.to_object(&ctx).catch(&ctx)?;
 
             // If the handler returned a promise that resolves immediately, we resolve it.
-            let promise: MaybePromise = func.call((arg,)).catch(&ctx)?;
+            let promise: MaybePromise = func.call((arg, context)).catch(&ctx)?;
             let obj: Value = promise.finish().catch(&ctx)?;
 
             // Serialize the result to a JSON string and return it.
-            ctx.json_stringify(obj)
+            json::stringify(&ctx, obj)
                 .catch(&ctx)?
-                .context("The handler function did not return a value")?
-                .to_string()
-                .catch(&ctx)
-        })
+                .context("The handler function did not return a value")
+        });
+        self.module_sources_locked.set(false);
+        self.check_cancelled(result)
+    }
+
+    /// Run a batch of invocations of registered handlers, like [`JsRuntime::run_handler`],
+    /// returning the result of every invocation in order.
+    ///
+    /// Every invocation is the name of the handler to run, the index of its event in `events`
+    /// and its context, so handlers can share an event without copying it.
+    /// A failing invocation does not stop the batch, but once an invocation is cancelled by the
+    /// host the remaining ones fail without running.
+    /// If `run_gc` is true, the runtime will run a garbage collection cycle once, after running
+    /// the whole batch.
+    pub fn run_handler_batch(
+        &mut self,
+        events: &[String],
+        invocations: Vec<(String, usize, HandlerContext)>,
+        run_gc: bool,
+    ) -> Vec<anyhow::Result<String>> {
+        let mut cancelled = false;
+        let results = invocations
+            .into_iter()
+            .map(|(function_name, event, context)| {
+                if cancelled {
+                    return Err(anyhow!(
+                        "{CANCELLED_ERROR}: the batch was cancelled by the host"
+                    ));
+                }
+                let event = events
+                    .get(event)
+                    .with_context(|| format!("No event at index {event} in the batch"))?;
+                let result = self.run_handler(function_name, event.clone(), context, false);
+                cancelled = self.cancelled.get();
+                result
+            })
+            .collect();
+        if run_gc {
+            self.run_gc();
+        }
+        results
+    }
+
+    // Create a realm for a handler, set up like the main one.
+    fn new_realm(&self) -> anyhow::Result<Context> {
+        let realm = Context::full(&self.runtime).context("Unable to create JS context")?;
+        realm.with(|ctx| -> anyhow::Result<()> {
+            globals::setup(&ctx).catch(&ctx)?;
+            // Math is frozen with the other built-ins, so it has to be patched first.
+            if self.seeded_math_random {
+                seed_math_random(&ctx)?;
+            }
+            if self.dynamic_code_disabled {
+                hardening::disable_dynamic_code(&ctx).catch(&ctx)?;
+            }
+            if self.frozen_builtins {
+                hardening::freeze_builtins(&ctx).catch(&ctx)?;
+            }
+            Ok(())
+        })?;
+        Ok(realm)
+    }
+
+    // Forget what the previous script left behind, before running a script.
+    fn start_script(&self) {
+        self.cancelled.set(false);
+        self.heap.reset_failed();
+    }
+
+    // Replace the error of a script that was interrupted because the host cancelled it.
+    fn check_cancelled<T>(&self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        if result.is_err() && self.cancelled.get() {
+            return Err(anyhow!(
+                "{CANCELLED_ERROR}: the handler was cancelled by the host"
+            ));
+        }
+        result
     }
 }
 
@@ -246,30 +858,77 @@ impl Drop for JsRuntime {
     }
 }
 
+/// Modules resolved and loaded by the host ahead of time, so that registering a handler does not
+/// need to call out to the host for each import.
+// The deserialization in here has to match the serialization of
+// ModuleBundle in src/hyperlight-js/src/module_loader.rs
+#[derive(Default, Deserialize)]
+pub struct PreloadedModules {
+    // (importer, specifier, resolved path)
+    resolutions: Vec<(String, String, String)>,
+    // (resolved path, source)
+    sources: Vec<(String, String)>,
+}
+
+impl PreloadedModules {
+    fn resolve(&self, base: &str, name: &str) -> Option<String> {
+        self.resolutions
+            .iter()
+            .find(|(importer, specifier, _)| importer == base && specifier == name)
+            .map(|(_, _, path)| path.clone())
+    }
+
+    fn take_source(&mut self, path: &str) -> Option<String> {
+        let index = self.sources.iter().position(|(p, _)| p == path)?;
+        Some(self.sources.swap_remove(index).1)
+    }
+}
+
 // A module loader that calls out to the host to resolve and load modules
 #[derive(Clone)]
 struct ModuleLoader {
     host: Rc<dyn Host>,
+    preloaded: Rc<RefCell<PreloadedModules>>,
+    setup: Rc<RefCell<Option<(String, String)>>>,
+    sources_locked: Rc<Cell<bool>>,
 }
 
 impl ModuleLoader {
     fn new(host: impl Host + 'static) -> Self {
         Self {
             host: Rc::new(host),
+            preloaded: Rc::default(),
+            setup: Rc::default(),
+            sources_locked: Rc::default(),
         }
     }
 }
 
 impl Resolver for ModuleLoader {
-    fn resolve(&mut self, _ctx: &Ctx<'_>, base: &str, name: &str) -> Result<String> {
-        // quickjs uses the module path as the base for relative imports
-        // but oxc_resolver expects the directory as the base
-        let (dir, _) = base.rsplit_once('/').unwrap_or((".", ""));
+    fn resolve(&mut self, ctx: &Ctx<'_>, base: &str, name: &str) -> Result<String> {
+        // The setup module is declared at its own path, see `Loader::load` below.
+        if name == SETUP_MODULE
+            && let Some((path, _)) = self.setup.borrow().as_ref()
+        {
+            return Ok(path.clone());
+        }
 
+        let preloaded = self.preloaded.borrow().resolve(base, name);
+        if let Some(path) = preloaded {
+            return Ok(path);
+        }
+
+        // quickjs uses the path of the importing module as the base for relative imports,
+        // the host is responsible for resolving the name relative to it.
         let path = self
             .host
-            .resolve_module(dir.to_string(), name.to_string())
-            .map_err(|_err| rquickjs::Error::new_resolving(base, name))?;
+            .resolve_module(base.to_string(), name.to_string())
+            .map_err(|err| {
+                module_error(
+                    ctx,
+                    rquickjs::Error::new_resolving_message(base, name, format!("{err:#}")),
+                )
+            })?;
 
         // convert backslashes to forward slashes for windows compatibility
         let path = path.replace('\\', "/");
@@ -279,15 +938,100 @@ impl Resolver for ModuleLoader {
 
 impl Loader for ModuleLoader {
     fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js>> {
-        let source = self
-            .host
-            .load_module(name.to_string())
-            .map_err(|_err| rquickjs::Error::new_loading(name))?;
+        if self.sources_locked.get() {
+            return Err(module_error(
+                ctx,
+                rquickjs::Error::new_loading_message(
+                    name,
+                    "Loading module sources while a handler runs is disabled in this sandbox",
+                ),
+            ));
+        }
+
+        // quickjs only loads the setup module in realms it hasn't been evaluated in yet.
+        let setup = match self.setup.borrow().as_ref() {
+            Some((path, source)) if path == name => Some(source.clone()),
+            _ => None,
+        };
+        if let Some(source) = setup {
+            return Module::declare(ctx.clone(), name, source);
+        }
+
+        // Declaring the module resolves its imports, so the borrow must end before that.
+        let preloaded = self.preloaded.borrow_mut().take_source(name);
+        if let Some(source) = preloaded {
+            return Module::declare(ctx.clone(), name, source);
+        }
+
+        let source = self.host.load_module(name.to_string()).map_err(|err| {
+            module_error(
+                ctx,
+                rquickjs::Error::new_loading_message(name, format!("{err:#}")),
+            )
+        })?;
 
         Module::declare(ctx.clone(), name, source)
     }
 }
 
+// Throw `error` as a `ReferenceError`, like rquickjs does for the errors of resolvers and
+// loaders, with the `MODULE_ERROR` code, and record it so `catch` tells it apart from the
+// exceptions of the handlers.
+fn module_error(ctx: &Ctx<'_>, error: rquickjs::Error) -> rquickjs::Error {
+    let _ = Exception::throw_reference(ctx, &error.to_string());
+    let exception = ctx.catch();
+    if let Some(exception) = exception.as_object() {
+        let _ = exception.set("code", MODULE_ERROR);
+    }
+    if let Some(failures) = ctx.userdata::<Failures>() {
+        failures.module_error.replace(Some(exception.clone()));
+    }
+    ctx.throw(exception)
+}
+
+/// The failures `catch` can't tell from the exceptions of the handlers by looking at them, as
+/// handlers can throw any object, so the runtime records them as they happen. Stored in the
+/// userdata of the runtime, shared by all its realms.
+#[derive(JsLifetime)]
+struct Failures<'js> {
+    heap: Rc<HeapState>,
+    // The exception thrown for the last module that failed to resolve or load.
+    module_error: RefCell<Option<Value<'js>>>,
+}
+
+impl Failures<'_> {
+    fn install(ctx: &Ctx<'_>, heap: Rc<HeapState>) -> anyhow::Result<()> {
+        let failures = Failures {
+            heap,
+            module_error: RefCell::default(),
+        };
+        let Ok(None) = ctx.store_userdata(failures) else {
+            bail!("Failed to install the failures of the runtime");
+        };
+        Ok(())
+    }
+
+    // Whether `exception` is the one thrown for the last module that failed to resolve or load.
+    fn is_module_error<'js>(ctx: &Ctx<'js>, exception: &Exception<'js>) -> bool {
+        ctx.userdata::<Failures>().is_some_and(|failures| {
+            failures.module_error.borrow().as_ref() == Some(exception.as_value())
+        })
+    }
+
+    // Whether QuickJS ran out of memory since the script started.
+    fn out_of_memory(ctx: &Ctx<'_>) -> bool {
+        ctx.userdata::<Failures>()
+            .is_some_and(|failures| failures.heap.failed())
+    }
+}
+
+fn seed_math_random(ctx: &Ctx<'_>) -> anyhow::Result<()> {
+    let math: Object = ctx.globals().get("Math").catch(ctx)?;
+    let random = Function::new(ctx.clone(), modules::random::next_f64).catch(ctx)?;
+    math.set("random", random).catch(ctx)?;
+    Ok(())
+}
+
 fn make_handler_path(function_name: &str, handler_dir: &str) -> String {
     let handler_dir = if handler_dir.is_empty() {
         "."
@@ -335,8 +1079,37 @@ impl<T> CatchJsErrorExt for rquickjs::Result<T> {
     fn catch(self, ctx: &Ctx<'_>) -> anyhow::Result<T> {
         match rquickjs::CatchResultExt::catch(self, ctx) {
             Ok(s) => Ok(s),
-            Err(e) => Err(anyhow!("Runtime error: {e:#?}")),
+            Err(e) if is_out_of_memory(ctx, &e) => Err(anyhow!(
+                "{OUT_OF_MEMORY_ERROR}: the JavaScript heap is out of memory"
+            )),
+            Err(CaughtError::Exception(e)) if Failures::is_module_error(ctx, &e) => Err(anyhow!(
+                "{MODULE_ERROR}: {}",
+                e.message().unwrap_or_default()
+            )),
+            Err(CaughtError::Exception(e)) => {
+                let causes = error::describe_causes(ctx, &e);
+                Err(anyhow!(
+                    "{RUNTIME_ERROR}: {:#?}{causes}",
+                    CaughtError::Exception(e)
+                ))
+            }
+            Err(e) => Err(anyhow!("{RUNTIME_ERROR}: {e:#?}")),
+        }
+    }
+}
+
+// Whether `error` is an allocation failure, or the `InternalError: out of memory` QuickJS throws
+// when an allocation fails. Handlers can throw the same error, so it only counts if an
+// allocation did fail.
+fn is_out_of_memory(ctx: &Ctx<'_>, error: &CaughtError<'_>) -> bool {
+    match error {
+        CaughtError::Error(rquickjs::Error::Allocation) => true,
+        CaughtError::Exception(e) => {
+            e.message().as_deref() == Some("out of memory")
+                && e.get::<_, String>("name").ok().as_deref() == Some("InternalError")
+                && Failures::out_of_memory(ctx)
         }
+        _ => false,
     }
 }
 
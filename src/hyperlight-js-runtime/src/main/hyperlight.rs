@@ -18,14 +18,19 @@ extern crate alloc;
 use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
 use anyhow::{anyhow, Context as _};
 use hashbrown::HashMap;
 use hyperlight_common::flatbuffer_wrappers::function_call::FunctionCall;
+use hyperlight_common::flatbuffer_wrappers::function_types::ParameterValue;
 use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
 use hyperlight_common::flatbuffer_wrappers::util::get_flatbuffer_result;
 use hyperlight_common::func::ParameterTuple;
 use hyperlight_guest::error::{HyperlightGuestError, Result};
 use hyperlight_guest_bin::{guest_function, host_function};
+use hyperlight_js_runtime::{wire, HostError, HostFunctionCache};
 use spin::Mutex;
 use tracing::instrument;
 
@@ -66,11 +71,23 @@ impl hyperlight_js_runtime::host::Host for Host {
 }
 
 static RUNTIME: spin::Lazy<Mutex<hyperlight_js_runtime::JsRuntime>> = spin::Lazy::new(|| {
-    Mutex::new(hyperlight_js_runtime::JsRuntime::new(Host).unwrap_or_else(|e| {
+    Mutex::new(new_runtime().unwrap_or_else(|e| {
         panic!("Failed to initialize JS runtime: {e:#?}");
     }))
 });
 
+fn new_runtime() -> anyhow::Result<hyperlight_js_runtime::JsRuntime> {
+    let mut runtime = hyperlight_js_runtime::JsRuntime::new(Host)?;
+    // `host.batch` is available whether or not the sandbox has host modules.
+    runtime.set_host_batch_function(|calls: String| -> anyhow::Result<String> {
+        call_host_js_function_batch(calls).map_err(|e| match HostError::from_message(&e.message) {
+            Some(error) => anyhow::Error::msg(error),
+            None => anyhow!("Calling host functions in a batch failed: {e:#?}"),
+        })
+    })?;
+    Ok(runtime)
+}
+
 #[unsafe(no_mangle)]
 #[instrument(skip_all, level = "info")]
 pub extern "C" fn hyperlight_main() {
@@ -79,28 +96,263 @@ pub extern "C" fn hyperlight_main() {
     let _ = &*RUNTIME;
 }
 
-#[guest_function("register_handler")]
+// The deserialization in here has to match the serialization of
+// HandlerRegistration in src/hyperlight-js/src/sandbox/js_sandbox.rs
+#[derive(serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum HandlerRegistration {
+    Script {
+        name: String,
+        script: String,
+        pwd: String,
+        // The pre-bundled modules as JSON, or an empty string if there are none
+        modules: String,
+    },
+    JsonLogic {
+        name: String,
+        rule: String,
+    },
+}
+
+/// Register all the handlers of a sandbox, where `handlers` is the JSON serialized list of their
+/// registrations, stopping at the first handler that fails to register.
+///
+/// Returns the JSON serialized list of the names of the handlers that were compiled.
+#[guest_function("register_handlers")]
 #[instrument(skip_all, level = "info")]
-fn register_handler(
-    function_name: String,
-    handler_script: String,
-    handler_pwd: String,
+fn register_handlers(handlers: String) -> Result<String> {
+    let handlers: Vec<HandlerRegistration> = serde_json::from_str(&handlers)?;
+    let mut runtime = RUNTIME.lock();
+    let mut compiled = Vec::with_capacity(handlers.len());
+    for handler in handlers {
+        let name = match handler {
+            HandlerRegistration::Script {
+                name,
+                script,
+                pwd,
+                modules,
+            } => {
+                runtime.register_handler_with_modules(
+                    name.clone(),
+                    script,
+                    pwd,
+                    parse_modules(&modules)?,
+                )?;
+                name
+            }
+            HandlerRegistration::JsonLogic { name, rule } => {
+                runtime.register_jsonlogic_handler(name.clone(), &rule)?;
+                name
+            }
+        };
+        compiled.push(name);
+    }
+    Ok(serde_json::to_string(&compiled)?)
+}
+
+#[guest_function("register_setup_script")]
+#[instrument(skip_all, level = "info")]
+fn register_setup_script(
+    setup_script: String,
+    setup_pwd: String,
+    modules_json: String,
 ) -> Result<()> {
-    RUNTIME
+    RUNTIME.lock().register_setup_script_with_modules(
+        setup_script,
+        setup_pwd,
+        parse_modules(&modules_json)?,
+    )?;
+    Ok(())
+}
+
+fn parse_modules(modules_json: &str) -> Result<hyperlight_js_runtime::PreloadedModules> {
+    // An empty string means the host did not pre-bundle the modules.
+    if modules_json.is_empty() {
+        return Ok(Default::default());
+    }
+    // The deserialization in here has to match the serialization of
+    // ModuleBundle in src/hyperlight-js/src/module_loader.rs
+    serde_json::from_str(modules_json).map_err(|e| {
+        HyperlightGuestError::new(
+            ErrorCode::GuestError,
+            format!("Failed to parse pre-bundled modules JSON: {e:#?}"),
+        )
+    })
+}
+
+#[guest_function("register_pipeline")]
+#[instrument(skip_all, level = "info")]
+fn register_pipeline(function_name: String, stages: String) -> Result<()> {
+    // The deserialization in here has to match the serialization of
+    // the pipeline stages in src/hyperlight-js/src/sandbox/js_sandbox.rs
+    let stages: Vec<String> = serde_json::from_str(&stages)?;
+    RUNTIME.lock().register_pipeline(function_name, stages)?;
+    Ok(())
+}
+
+// The serialization in here has to match the deserialization of
+// MemoryStats in src/hyperlight-js/src/sandbox/memory_stats.rs
+#[derive(serde::Serialize)]
+struct MemoryStats {
+    malloc_size: u64,
+    malloc_count: u64,
+    memory_used_size: u64,
+    atom_count: u64,
+    atom_size: u64,
+    string_count: u64,
+    string_size: u64,
+    object_count: u64,
+    object_size: u64,
+    property_count: u64,
+    property_size: u64,
+    function_count: u64,
+    function_size: u64,
+    array_count: u64,
+    global_count: u64,
+}
+
+#[guest_function("memory_usage")]
+#[instrument(skip_all, level = "info")]
+fn memory_usage() -> Result<String> {
+    let runtime = RUNTIME.lock();
+    let usage = runtime.memory_usage();
+    // QuickJS reports the sizes as signed integers, but they are never negative.
+    let unsigned = |value: i64| value.max(0) as u64;
+    let stats = MemoryStats {
+        malloc_size: unsigned(usage.malloc_size),
+        malloc_count: unsigned(usage.malloc_count),
+        memory_used_size: unsigned(usage.memory_used_size),
+        atom_count: unsigned(usage.atom_count),
+        atom_size: unsigned(usage.atom_size),
+        string_count: unsigned(usage.str_count),
+        string_size: unsigned(usage.str_size),
+        object_count: unsigned(usage.obj_count),
+        object_size: unsigned(usage.obj_size),
+        property_count: unsigned(usage.prop_count),
+        property_size: unsigned(usage.prop_size),
+        function_count: unsigned(usage.js_func_count),
+        function_size: unsigned(usage.js_func_size),
+        array_count: unsigned(usage.array_count),
+        global_count: runtime.global_count() as u64,
+    };
+    serde_json::to_string(&stats).map_err(|e| {
+        HyperlightGuestError::new(
+            ErrorCode::GuestError,
+            format!("Failed to serialize memory usage: {e:#?}"),
+        )
+    })
+}
+
+// The serialization in here has to match the deserialization of the batch
+// results in src/hyperlight-js/src/sandbox/loaded_js_sandbox.rs
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchResult {
+    Ok(String),
+    Err(String),
+}
+
+// The deserialization in here has to match the serialization of
+// Batch in src/hyperlight-js/src/sandbox/loaded_js_sandbox.rs
+#[derive(serde::Deserialize)]
+struct Batch {
+    events: Vec<String>,
+    // `[handler, event index, sequence, seed]` for every invocation
+    invocations: Vec<(String, usize, u64, u64)>,
+    // The traceparent every invocation of the batch is made in
+    traceparent: Option<String>,
+}
+
+/// Run a batch of handler invocations, where the batch is the JSON serialized list of events
+/// and of invocations, returning the JSON serialized list of their results.
+#[guest_function("memory_usage_by_handler")]
+#[instrument(skip_all, level = "info")]
+fn memory_usage_by_handler() -> Result<String> {
+    let usage = RUNTIME.lock().memory_usage_by_handler();
+    Ok(serde_json::to_string(&usage)?)
+}
+
+#[guest_function("run_handler_batch")]
+#[instrument(skip_all, level = "info")]
+fn run_handler_batch(batch: String, run_gc: bool) -> Result<String> {
+    let Batch {
+        events,
+        invocations,
+        traceparent,
+    } = serde_json::from_str(&batch)?;
+    let invocations = invocations
+        .into_iter()
+        .map(|(function_name, event, sequence, seed)| {
+            let context = hyperlight_js_runtime::HandlerContext {
+                sequence,
+                seed,
+                traceparent: traceparent.clone(),
+            };
+            (function_name, event, context)
+        })
+        .collect();
+    let results: Vec<BatchResult> = RUNTIME
         .lock()
-        .register_handler(function_name, handler_script, handler_pwd)?;
+        .run_handler_batch(&events, invocations, run_gc)
+        .into_iter()
+        .map(|result| match result {
+            Ok(result) => BatchResult::Ok(result),
+            // Formatted like the errors of single invocations
+            Err(e) => BatchResult::Err(format!("Error: {e:?}")),
+        })
+        .collect();
+    Ok(serde_json::to_string(&results)?)
+}
+
+#[guest_function("take_profile")]
+#[instrument(skip_all, level = "info")]
+fn take_profile() -> Result<String> {
+    Ok(RUNTIME.lock().take_profile())
+}
+
+#[guest_function("run_gc")]
+#[instrument(skip_all, level = "info")]
+fn run_gc() -> Result<()> {
+    RUNTIME.lock().run_gc();
     Ok(())
 }
 
 #[host_function("CallHostJsFunction")]
 fn call_host_js_function(module_name: String, func_name: String, args: String) -> Result<String>;
 
+#[host_function("CallHostJsFunctionBatch")]
+fn call_host_js_function_batch(calls: String) -> Result<String>;
+
+// The deserialization in here has to match the serialization of
+// HostModule in src/hyperlight_js/src/sandbox/host_fn.rs
+#[derive(serde::Deserialize)]
+struct HostFunctionDescriptor {
+    name: String,
+    cache: Option<CacheDescriptor>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CacheDescriptor {
+    Invocation,
+    Ttl { micros: u64 },
+}
+
+impl From<CacheDescriptor> for HostFunctionCache {
+    fn from(cache: CacheDescriptor) -> Self {
+        match cache {
+            CacheDescriptor::Invocation => HostFunctionCache::Invocation,
+            CacheDescriptor::Ttl { micros } => {
+                HostFunctionCache::Ttl(Duration::from_micros(micros))
+            }
+        }
+    }
+}
+
 #[guest_function("RegisterHostModules")]
 fn register_host_modules(host_modules_json: String) -> Result<()> {
-    // The serialization in here has to match the serialization of
-    // HostModule in src/hyperlight_js/src/sandbox/host_fn.rs
-    let host_modules: HashMap<String, Vec<String>> = serde_json::from_str(&host_modules_json)
-        .map_err(|e| {
+    let host_modules: HashMap<String, Vec<HostFunctionDescriptor>> =
+        serde_json::from_str(&host_modules_json).map_err(|e| {
             HyperlightGuestError::new(
                 ErrorCode::GuestError,
                 format!("Failed to parse host modules JSON: {e:#?}"),
@@ -110,26 +362,156 @@ fn register_host_modules(host_modules_json: String) -> Result<()> {
     let mut runtime = RUNTIME.lock();
 
     for (module_name, functions) in host_modules {
-        for function_name in functions {
-            let module_name = module_name.clone();
-            runtime.register_json_host_function(
-                module_name.clone(),
-                function_name.clone(),
+        for HostFunctionDescriptor {
+            name: function_name,
+            cache,
+        } in functions
+        {
+            let function = {
+                let module_name = module_name.clone();
+                let function_name = function_name.clone();
                 move |args: String| -> anyhow::Result<String> {
                     call_host_js_function(module_name.clone(), function_name.clone(), args)
-                        .map_err(|e| anyhow!("Calling host function {module_name:?} {function_name:?} failed: {e:#?}"))
-                },
-            )?;
+                        .map_err(|e| match HostError::from_message(&e.message) {
+                            Some(error) => anyhow::Error::msg(error),
+                            None => anyhow!("Calling host function {module_name:?} {function_name:?} failed: {e:#?}"),
+                        })
+                }
+            };
+            match cache {
+                Some(cache) => runtime.register_cached_json_host_function(
+                    module_name.clone(),
+                    function_name,
+                    cache.into(),
+                    function,
+                )?,
+                None => runtime.register_json_host_function(
+                    module_name.clone(),
+                    function_name,
+                    function,
+                )?,
+            }
         }
     }
     Ok(())
 }
 
+// The deserialization in here has to match the serialization of
+// RuntimeOptions in src/hyperlight_js/src/sandbox/runtime_options.rs
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct RuntimeOptions {
+    freeze_builtins: bool,
+    seeded_math_random: bool,
+    cooperative_cancellation: bool,
+    wire_compression_min_size: Option<usize>,
+    memory_limit: Option<usize>,
+    max_stack_size: Option<usize>,
+    gc_threshold: Option<usize>,
+    per_handler_realms: bool,
+    dynamic_code_disabled: bool,
+    profile_interval_micros: Option<u64>,
+    max_result_size: Option<usize>,
+}
+
+/// The minimum size of the results compressed when the host sends framed events.
+static WIRE_COMPRESSION_MIN_SIZE: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+#[guest_function("ConfigureRuntime")]
+#[instrument(skip_all, level = "info")]
+fn configure_runtime(options_json: String) -> Result<()> {
+    let options: RuntimeOptions = serde_json::from_str(&options_json).map_err(|e| {
+        HyperlightGuestError::new(
+            ErrorCode::GuestError,
+            format!("Failed to parse runtime options JSON: {e:#?}"),
+        )
+    })?;
+
+    let mut runtime = RUNTIME.lock();
+
+    // Math is frozen with the other built-ins, so it has to be patched first.
+    if options.seeded_math_random {
+        runtime.seed_math_random()?;
+    }
+
+    // The function constructors are replaced before they are frozen.
+    if options.dynamic_code_disabled {
+        runtime.disable_dynamic_code()?;
+    }
+
+    // `Error.stackTraceLimit` is raised before the built-ins are frozen.
+    if let Some(interval) = options.profile_interval_micros {
+        runtime.enable_profiling(interval)?;
+    }
+
+    if options.freeze_builtins {
+        runtime.freeze_builtins()?;
+    }
+
+    if options.per_handler_realms {
+        runtime.set_per_handler_realms();
+    }
+
+    if options.cooperative_cancellation {
+        runtime.set_cancellation_check(|| {
+            #[host_function("IsExecutionCancelled")]
+            fn is_execution_cancelled() -> Result<bool>;
+
+            // If the host cannot be reached, keep running and let the monitors kill the guest.
+            is_execution_cancelled().unwrap_or(false)
+        });
+    }
+
+    if let Some(min_size) = options.wire_compression_min_size {
+        WIRE_COMPRESSION_MIN_SIZE.store(min_size, Ordering::Relaxed);
+    }
+
+    if let Some(limit) = options.memory_limit {
+        runtime.set_memory_limit(limit);
+    }
+    if let Some(limit) = options.max_stack_size {
+        runtime.set_max_stack_size(limit);
+    }
+    if let Some(threshold) = options.gc_threshold {
+        runtime.set_gc_threshold(threshold);
+    }
+    if let Some(limit) = options.max_result_size {
+        runtime.set_max_result_size(limit);
+    }
+    Ok(())
+}
+
 #[unsafe(no_mangle)]
 pub fn guest_dispatch_function(function_call: FunctionCall) -> Result<Vec<u8>> {
-    let params = function_call.parameters.unwrap_or_default();
+    let mut params = function_call.parameters.unwrap_or_default();
     let function_name = function_call.function_name;
-    let (event, run_gc) = ParameterTuple::from_value(params)?;
-    let result = RUNTIME.lock().run_handler(function_name, event, run_gc)?;
+
+    // With wire compression the host sends the event framed, see
+    // src/hyperlight-js-runtime/src/wire.rs, and expects a framed result.
+    let framed = if let Some(ParameterValue::VecBytes(frame)) = params.first() {
+        let event =
+            String::from_utf8(wire::decode(frame)?).context("The event is not valid UTF-8")?;
+        params[0] = ParameterValue::String(event);
+        true
+    } else {
+        false
+    };
+
+    let (event, run_gc, sequence, seed, traceparent): (String, bool, u64, u64, String) =
+        ParameterTuple::from_value(params)?;
+    // An empty traceparent is sent when the invocation is not traced.
+    let context = hyperlight_js_runtime::HandlerContext {
+        sequence,
+        seed,
+        traceparent: (!traceparent.is_empty()).then_some(traceparent),
+    };
+    let result = RUNTIME
+        .lock()
+        .run_handler(function_name, event, context, run_gc)?;
+    if framed {
+        let min_size = WIRE_COMPRESSION_MIN_SIZE.load(Ordering::Relaxed);
+        let frame = wire::encode(result.as_bytes(), min_size);
+        return Ok(get_flatbuffer_result(frame.as_slice()));
+    }
     Ok(get_flatbuffer_result(result.as_str()))
 }
@@ -14,17 +14,19 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 use std::path::{Path, PathBuf};
-use std::{env, fs};
+use std::{env, fs, io};
 
 use anyhow::{Context as _, Result};
 use clap::Parser;
+use hyperlight_js_runtime::HostError;
 use tracing::instrument;
 
 struct Host;
 
 impl hyperlight_js_runtime::host::Host for Host {
     fn resolve_module(&self, base: String, name: String) -> Result<String> {
-        let base = PathBuf::from(base);
+        // the base is the path of the importing module, resolve relative to its directory
+        let base = Path::new(&base).parent().unwrap_or_else(|| Path::new("."));
         let path = base.join(&name);
 
         let path = path
@@ -78,15 +80,65 @@ struct Cli {
 
     /// The event to pass to the handler function as a JSON string.
     event: String,
+
+    /// The seed of the invocation, to replay an invocation that used the `random` module.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Draw `Math.random` from the seeded generator, like sandboxes in deterministic mode.
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Limit the memory the JavaScript engine allocates, in bytes.
+    #[arg(long)]
+    memory_limit: Option<usize>,
+
+    /// Limit the stack the JavaScript engine uses, in bytes.
+    #[arg(long)]
+    max_stack_size: Option<usize>,
+
+    /// Make `eval` and the function constructors throw, like sandboxes with dynamic code disabled.
+    #[arg(long)]
+    no_dynamic_code: bool,
+
+    /// Treat the event as a JSON array of events, and run the handler once per event.
+    #[arg(long)]
+    batch: bool,
+
+    /// The path to a setup script, evaluated before the handler, whose exports the handler can
+    /// import from "setup".
+    #[arg(long)]
+    setup: Option<PathBuf>,
 }
 
 #[instrument(skip_all, level = "info")]
 fn main() -> Result<()> {
-    let Cli { file, event } = Cli::parse();
+    let Cli {
+        file,
+        event,
+        seed,
+        deterministic,
+        memory_limit,
+        max_stack_size,
+        no_dynamic_code,
+        batch,
+        setup,
+    } = Cli::parse();
 
     let handler_script = fs::read_to_string(&file)
         .with_context(|| format!("Reading handler script from {:?}", file))?;
 
+    // Read before changing the current directory, as the path can be relative to it.
+    let setup = setup
+        .map(|setup| -> Result<_> {
+            let script = fs::read_to_string(&setup)
+                .with_context(|| format!("Reading setup script from {:?}", setup))?;
+            let setup = fs::canonicalize(&setup)?;
+            let pwd = setup.parent().unwrap_or_else(|| Path::new("."));
+            Ok((script, pwd.to_string_lossy().to_string()))
+        })
+        .transpose()?;
+
     let handler_pwd = file.parent().unwrap_or_else(|| Path::new("."));
 
     env::set_current_dir(handler_pwd).with_context(|| {
@@ -94,14 +146,67 @@ fn main() -> Result<()> {
     })?;
 
     let mut runtime = hyperlight_js_runtime::JsRuntime::new(Host)?;
+    if deterministic {
+        runtime.seed_math_random()?;
+    }
+    if no_dynamic_code {
+        runtime.disable_dynamic_code()?;
+    }
+    if let Some(limit) = memory_limit {
+        runtime.set_memory_limit(limit);
+    }
+    if let Some(limit) = max_stack_size {
+        runtime.set_max_stack_size(limit);
+    }
 
     runtime.register_host_function("fs", "readFile", move |path: String| -> Result<String> {
-        Ok(fs::read_to_string(&path)?)
+        fs::read_to_string(&path).map_err(|e| {
+            let error = HostError::new("Error", format!("{path}: {e}"));
+            let error = match e.kind() {
+                io::ErrorKind::NotFound => error.with_code("ENOENT"),
+                io::ErrorKind::PermissionDenied => error.with_code("EACCES"),
+                _ => error,
+            };
+            anyhow::Error::msg(error)
+        })
     })?;
 
+    if let Some((setup_script, setup_pwd)) = setup {
+        runtime.register_setup_script(setup_script, setup_pwd)?;
+    }
     runtime.register_handler("handler".to_string(), handler_script, String::from("."))?;
 
-    let result = runtime.run_handler("handler".to_string(), event, false)?;
+    if batch {
+        let events: Vec<serde_json::Value> =
+            serde_json::from_str(&event).context("The batch is not a JSON array")?;
+        let events: Vec<String> = events.iter().map(ToString::to_string).collect();
+        let invocations = (0..events.len())
+            .zip(1..)
+            .map(|(event, sequence)| {
+                let context = hyperlight_js_runtime::HandlerContext {
+                    sequence,
+                    seed,
+                    traceparent: None,
+                };
+                ("handler".to_string(), event, context)
+            })
+            .collect();
+        let results = runtime.run_handler_batch(&events, invocations, false);
+        for result in results {
+            match result {
+                Ok(result) => println!("Handler result: {result}"),
+                Err(e) => println!("Handler error: {e:#}"),
+            }
+        }
+        return Ok(());
+    }
+
+    let context = hyperlight_js_runtime::HandlerContext {
+        sequence: 1,
+        seed,
+        traceparent: None,
+    };
+    let result = runtime.run_handler("handler".to_string(), event, context, false)?;
     println!("Handler result: {result}");
 
     Ok(())
@@ -25,6 +25,13 @@ fn micros_since_epoch() -> u64 {
     current_time_micros().unwrap_or(1609459200u64 * 1_000_000u64)
 }
 
+fn monotonic_micros() -> u64 {
+    #[host_function("MonotonicTimeMicros")]
+    fn monotonic_time_micros() -> Result<u64>;
+
+    monotonic_time_micros().unwrap_or(0)
+}
+
 #[unsafe(no_mangle)]
 extern "C" fn clock_gettime(clk_id: libc::clockid_t, ts: *mut libc::timespec) -> libc::c_int {
     const CLOCK_REALTIME: libc::clockid_t = libc::CLOCK_REALTIME as libc::clockid_t;
@@ -34,7 +41,11 @@ extern "C" fn clock_gettime(clk_id: libc::clockid_t, ts: *mut libc::timespec) ->
         unsafe { libc::__errno_location().write(libc::EINVAL as _) };
         return -1;
     }
-    let micros = micros_since_epoch();
+    let micros = if clk_id == CLOCK_MONOTONIC {
+        monotonic_micros()
+    } else {
+        micros_since_epoch()
+    };
     unsafe {
         ts.write(libc::timespec {
             tv_sec: (micros / 1_000_000) as _,
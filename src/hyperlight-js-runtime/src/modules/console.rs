@@ -13,12 +13,92 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::string::String;
+use core::cell::RefCell;
 
-use rquickjs::prelude::Rest;
-use rquickjs::Coerced;
+use rquickjs::prelude::{Opt, Rest};
+use rquickjs::{Coerced, Ctx, Exception, JsLifetime};
 
 use super::io::io::print;
+use crate::utils::monotonic_micros;
+
+/// The start times of the timers of `console.time`, by label, shared by all the realms of the
+/// runtime.
+#[derive(Default, JsLifetime)]
+struct Timers(RefCell<BTreeMap<String, u64>>);
+
+/// Run `f` with the timers of the runtime of `ctx`.
+fn with_timers<R>(
+    ctx: &Ctx<'_>,
+    f: impl FnOnce(&mut BTreeMap<String, u64>) -> R,
+) -> rquickjs::Result<R> {
+    if ctx.userdata::<Timers>().is_none() && ctx.store_userdata(Timers::default()).is_err() {
+        return Err(Exception::throw_internal(
+            ctx,
+            "Unable to store the console timers",
+        ));
+    }
+    let timers = ctx
+        .userdata::<Timers>()
+        .ok_or_else(|| Exception::throw_internal(ctx, "The console timers are not stored"))?;
+    let mut timers = timers.0.borrow_mut();
+    Ok(f(&mut timers))
+}
+
+/// The label of a timer, `default` when it is not given.
+fn label(label: Opt<Option<Coerced<String>>>) -> String {
+    label
+        .0
+        .flatten()
+        .map(|label| label.0)
+        .unwrap_or_else(|| String::from("default"))
+}
+
+/// Join `parts` with spaces into a line.
+fn line(parts: impl IntoIterator<Item = String>) -> String {
+    let mut txt = parts
+        .into_iter()
+        .map(|mut part| {
+            part.push(' ');
+            part
+        })
+        .collect::<String>();
+    txt.pop(); // remove the last space
+    txt.push('\n'); // add a newline at the end
+    txt
+}
+
+/// Print the time elapsed since the timer `label` started, followed by `data`, or a warning if
+/// there is no such timer. The timer is stopped if `stop` is set.
+fn log_timer(
+    ctx: &Ctx<'_>,
+    label: String,
+    data: Rest<Coerced<String>>,
+    stop: bool,
+    method: &str,
+) -> rquickjs::Result<()> {
+    let start = with_timers(ctx, |timers| {
+        if stop {
+            timers.remove(&label)
+        } else {
+            timers.get(&label).copied()
+        }
+    })?;
+    let Some(start) = start else {
+        print(format!(
+            "Warning: No such label '{label}' for console.{method}()\n"
+        ));
+        return Ok(());
+    };
+    let elapsed = monotonic_micros().saturating_sub(start) as f64 / 1000.0;
+    let timer = format!("{label}: {elapsed:.3}ms");
+    print(line(
+        core::iter::once(timer).chain(data.into_inner().into_iter().map(|c| c.0)),
+    ));
+    Ok(())
+}
 
 #[rquickjs::module(rename_vars = "camelCase", rename_types = "camelCase")]
 #[allow(clippy::module_inception)]
@@ -27,17 +107,48 @@ pub mod console {
 
     #[rquickjs::function]
     pub fn log(txt: Rest<Coerced<String>>) -> rquickjs::Result<()> {
-        let mut txt = txt
-            .into_inner()
-            .into_iter()
-            .map(|mut c| {
-                c.0.push(' ');
-                c.0
-            })
-            .collect::<String>();
-        txt.pop(); // remove the last space
-        txt.push('\n'); // add a newline at the end
-        print(txt);
+        print(line(txt.into_inner().into_iter().map(|c| c.0)));
         Ok(())
     }
+
+    /// Start the timer `label`, measured with the monotonic clock.
+    #[rquickjs::function]
+    pub fn time(ctx: Ctx<'_>, label: Opt<Option<Coerced<String>>>) -> rquickjs::Result<()> {
+        let label = super::label(label);
+        let started = with_timers(&ctx, |timers| {
+            if timers.contains_key(&label) {
+                return false;
+            }
+            timers.insert(label.clone(), monotonic_micros());
+            true
+        })?;
+        if !started {
+            print(format!(
+                "Warning: Label '{label}' already exists for console.time()\n"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Print the time elapsed since the timer `label` started, and stop it.
+    #[rquickjs::function]
+    pub fn time_end(ctx: Ctx<'_>, label: Opt<Option<Coerced<String>>>) -> rquickjs::Result<()> {
+        log_timer(
+            &ctx,
+            super::label(label),
+            Rest(Default::default()),
+            true,
+            "timeEnd",
+        )
+    }
+
+    /// Print the time elapsed since the timer `label` started, followed by `data`.
+    #[rquickjs::function]
+    pub fn time_log(
+        ctx: Ctx<'_>,
+        label: Opt<Option<Coerced<String>>>,
+        data: Rest<Coerced<String>>,
+    ) -> rquickjs::Result<()> {
+        log_timer(&ctx, super::label(label), data, false, "timeLog")
+    }
 }
@@ -24,7 +24,9 @@ use spin::Lazy;
 pub mod console;
 pub mod crypto;
 pub mod io;
+pub mod random;
 pub mod require;
+pub mod temporal;
 
 // A loader for native Rust modules
 #[derive(Clone)]
@@ -52,7 +54,9 @@ static NATIVE_MODULES: Lazy<HashMap<&str, ModuleDeclarationFn>> = Lazy::new(|| {
         ("io", declaration::<io::js_io>()),
         ("crypto", declaration::<crypto::js_crypto>()),
         ("console", declaration::<console::js_console>()),
+        ("random", declaration::<random::js_random>()),
         ("require", declaration::<require::js_require>()),
+        ("temporal", declaration::<temporal::js_temporal>()),
     ])
 });
 
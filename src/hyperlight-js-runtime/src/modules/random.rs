@@ -0,0 +1,70 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use rquickjs::{Ctx, Exception, Result};
+
+// The state of the SplitMix64 generator, reseeded with the seed of every invocation.
+static STATE: AtomicU64 = AtomicU64::new(0);
+
+/// Reseed the generator, so the numbers drawn by an invocation only depend on its seed.
+pub(crate) fn reseed(seed: u64) {
+    STATE.store(seed, Ordering::Relaxed);
+}
+
+/// Draw the next number of the sequence, see <https://prng.di.unimi.it/splitmix64.c>.
+fn next_u64() -> u64 {
+    let state = STATE
+        .fetch_add(0x9e3779b97f4a7c15, Ordering::Relaxed)
+        .wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Draw a number in `[0, 1)`.
+pub(crate) fn next_f64() -> f64 {
+    // The top 53 bits fill the mantissa of a double exactly.
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[rquickjs::module(rename_vars = "camelCase", rename_types = "camelCase")]
+#[allow(clippy::module_inception)]
+pub mod random {
+    use super::*;
+
+    /// A number in `[0, 1)`, drawn from the generator seeded with `context.seed`.
+    #[rquickjs::function]
+    pub fn random() -> f64 {
+        next_f64()
+    }
+
+    /// An integer in `[min, max)`, drawn from the generator seeded with `context.seed`.
+    #[rquickjs::function]
+    pub fn random_int(ctx: Ctx<'_>, min: i64, max: i64) -> Result<i64> {
+        if min >= max {
+            return Err(Exception::throw_range(
+                &ctx,
+                "randomInt requires min to be less than max",
+            ));
+        }
+        // Scale with a 128-bit multiplication rather than a modulo, which favours small offsets.
+        let range = max.abs_diff(min) as u128;
+        let offset = ((next_u64() as u128 * range) >> 64) as u64;
+        Ok(min.wrapping_add_unsigned(offset))
+    }
+}
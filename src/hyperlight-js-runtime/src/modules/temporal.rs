@@ -0,0 +1,409 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! A small subset of Temporal: calendar dates as `YYYY-MM-DD` strings, instants
+//! as milliseconds since the Unix epoch, and ISO 8601 durations.
+//!
+//! There is no time zone database in the guest, so all the arithmetic is done
+//! in UTC and never depends on the local time zone or daylight saving time.
+
+use alloc::format;
+use alloc::string::String;
+
+use rquickjs::{Ctx, Exception, Object, Result};
+
+const MS_PER_DAY: i64 = 86_400_000;
+// The range of a JavaScript `Date`.
+const MAX_EPOCH_MS: i64 = 8_640_000_000_000_000;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Duration {
+    years: i64,
+    months: i64,
+    weeks: i64,
+    days: i64,
+    hours: i64,
+    minutes: i64,
+    seconds: i64,
+    milliseconds: i64,
+}
+
+impl Duration {
+    fn has_time(&self) -> bool {
+        self.hours != 0 || self.minutes != 0 || self.seconds != 0 || self.milliseconds != 0
+    }
+
+    fn has_calendar_units(&self) -> bool {
+        self.years != 0 || self.months != 0
+    }
+
+    /// The length of the duration in milliseconds, counting days as 24 hours.
+    fn exact_milliseconds(&self) -> Option<i64> {
+        let days = self.weeks.checked_mul(7)?.checked_add(self.days)?;
+        let hours = days.checked_mul(24)?.checked_add(self.hours)?;
+        let minutes = hours.checked_mul(60)?.checked_add(self.minutes)?;
+        let seconds = minutes.checked_mul(60)?.checked_add(self.seconds)?;
+        seconds.checked_mul(1000)?.checked_add(self.milliseconds)
+    }
+}
+
+/// Parse an ISO 8601 duration such as `P1Y2M`, `-P3W` or `PT1H30M0.5S`.
+/// Only the seconds may have a fraction, which is truncated to milliseconds.
+fn parse_duration(text: &str) -> core::result::Result<Duration, String> {
+    let invalid = || format!("Invalid ISO 8601 duration: {text:?}");
+
+    let mut rest = text.as_bytes();
+    let mut negative = false;
+    if let Some((&sign @ (b'-' | b'+'), tail)) = rest.split_first() {
+        negative = sign == b'-';
+        rest = tail;
+    }
+    match rest.split_first() {
+        Some((b'P' | b'p', tail)) => rest = tail,
+        _ => return Err(invalid()),
+    }
+
+    let mut duration = Duration::default();
+    let mut in_time = false;
+    let mut next_unit = 0;
+    let mut components = 0;
+    let mut time_components = 0;
+    while let Some((&first, tail)) = rest.split_first() {
+        if first.eq_ignore_ascii_case(&b'T') {
+            if in_time {
+                return Err(invalid());
+            }
+            in_time = true;
+            next_unit = 4;
+            rest = tail;
+            continue;
+        }
+
+        let digits = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(invalid());
+        }
+        let value = parse_int(&rest[..digits]).ok_or_else(invalid)?;
+        rest = &rest[digits..];
+
+        let mut milliseconds = None;
+        if let Some((b'.' | b',', tail)) = rest.split_first() {
+            let digits = tail.iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits == 0 || digits > 9 {
+                return Err(invalid());
+            }
+            let mut fraction = [b'0'; 3];
+            let kept = digits.min(3);
+            fraction[..kept].copy_from_slice(&tail[..kept]);
+            milliseconds = parse_int(&fraction);
+            rest = &tail[digits..];
+        }
+
+        let Some((&unit, tail)) = rest.split_first() else {
+            return Err(invalid());
+        };
+        rest = tail;
+        let index = match (in_time, unit.to_ascii_uppercase()) {
+            (false, b'Y') => 0,
+            (false, b'M') => 1,
+            (false, b'W') => 2,
+            (false, b'D') => 3,
+            (true, b'H') => 4,
+            (true, b'M') => 5,
+            (true, b'S') => 6,
+            _ => return Err(invalid()),
+        };
+        // Units must appear at most once, from the largest to the smallest,
+        // and only the seconds can have a fraction.
+        if index < next_unit || (milliseconds.is_some() && index != 6) {
+            return Err(invalid());
+        }
+        next_unit = index + 1;
+        components += 1;
+        if in_time {
+            time_components += 1;
+        }
+
+        let field = match index {
+            0 => &mut duration.years,
+            1 => &mut duration.months,
+            2 => &mut duration.weeks,
+            3 => &mut duration.days,
+            4 => &mut duration.hours,
+            5 => &mut duration.minutes,
+            _ => &mut duration.seconds,
+        };
+        *field = value;
+        if let Some(milliseconds) = milliseconds {
+            duration.milliseconds = milliseconds;
+        }
+    }
+    if components == 0 || (in_time && time_components == 0) {
+        return Err(invalid());
+    }
+
+    if negative {
+        for field in [
+            &mut duration.years,
+            &mut duration.months,
+            &mut duration.weeks,
+            &mut duration.days,
+            &mut duration.hours,
+            &mut duration.minutes,
+            &mut duration.seconds,
+            &mut duration.milliseconds,
+        ] {
+            *field = -*field;
+        }
+    }
+    Ok(duration)
+}
+
+fn parse_int(digits: &[u8]) -> Option<i64> {
+    digits.iter().try_fold(0i64, |value, digit| {
+        value.checked_mul(10)?.checked_add((digit - b'0') as i64)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Date {
+    year: i64,
+    month: i64,
+    day: i64,
+}
+
+impl Date {
+    const MIN_YEAR: i64 = 0;
+    const MAX_YEAR: i64 = 9999;
+
+    /// Parse a `YYYY-MM-DD` date.
+    fn parse(text: &str) -> core::result::Result<Self, String> {
+        let invalid = || format!("Invalid ISO 8601 date: {text:?}");
+        let bytes = text.as_bytes();
+        if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+            return Err(invalid());
+        }
+        let number = |digits: &[u8]| {
+            if digits.iter().all(u8::is_ascii_digit) {
+                parse_int(digits).ok_or_else(invalid)
+            } else {
+                Err(invalid())
+            }
+        };
+        let date = Self {
+            year: number(&bytes[..4])?,
+            month: number(&bytes[5..7])?,
+            day: number(&bytes[8..])?,
+        };
+        if !(1..=12).contains(&date.month)
+            || !(1..=days_in_month(date.year, date.month)).contains(&date.day)
+        {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+
+    /// The number of days since 1970-01-01, see
+    /// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+    fn to_days(self) -> i64 {
+        let year = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = if self.month > 2 {
+            self.month - 3
+        } else {
+            self.month + 9
+        };
+        let day_of_year = (153 * month + 2) / 5 + self.day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// The date `days` days after 1970-01-01, see
+    /// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    fn from_days(days: i64) -> core::result::Result<Self, String> {
+        let min = Self {
+            year: Self::MIN_YEAR,
+            month: 1,
+            day: 1,
+        };
+        let max = Self {
+            year: Self::MAX_YEAR,
+            month: 12,
+            day: 31,
+        };
+        if !(min.to_days()..=max.to_days()).contains(&days) {
+            return Err(format!(
+                "Date out of range, years must be between {} and {}",
+                Self::MIN_YEAR,
+                Self::MAX_YEAR
+            ));
+        }
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Ok(Self { year, month, day })
+    }
+
+    /// Add `duration`, adding the years and months first and clamping the day
+    /// to the end of the month (so `2024-01-31` plus `P1M` is `2024-02-29`),
+    /// then the weeks and days.
+    fn add(self, duration: &Duration) -> core::result::Result<Self, String> {
+        if duration.has_time() {
+            return Err("Cannot add hours, minutes or seconds to a date".into());
+        }
+        let out_of_range = || String::from("Date out of range");
+        let months = duration
+            .years
+            .checked_mul(12)
+            .and_then(|months| months.checked_add(duration.months))
+            .and_then(|months| months.checked_add(self.year * 12 + self.month - 1))
+            .ok_or_else(out_of_range)?;
+        let year = months.div_euclid(12);
+        if !(Self::MIN_YEAR..=Self::MAX_YEAR).contains(&year) {
+            return Err(out_of_range());
+        }
+        let month = months.rem_euclid(12) + 1;
+        let day = self.day.min(days_in_month(year, month));
+        let days = duration
+            .weeks
+            .checked_mul(7)
+            .and_then(|days| days.checked_add(duration.days))
+            .and_then(|days| days.checked_add(Self { year, month, day }.to_days()))
+            .ok_or_else(out_of_range)?;
+        Self::from_days(days)
+    }
+
+    fn format(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn epoch_milliseconds(epoch_ms: f64) -> core::result::Result<i64, String> {
+    if !epoch_ms.is_finite() || epoch_ms.abs() > MAX_EPOCH_MS as f64 {
+        return Err(format!("Invalid epoch milliseconds: {epoch_ms}"));
+    }
+    Ok(epoch_ms as i64)
+}
+
+fn throw_range<T>(ctx: &Ctx<'_>, result: core::result::Result<T, String>) -> Result<T> {
+    result.map_err(|message| Exception::throw_range(ctx, &message))
+}
+
+#[rquickjs::module(rename_vars = "camelCase", rename_types = "camelCase")]
+#[allow(clippy::module_inception)]
+pub mod temporal {
+    use super::*;
+
+    /// Parse an ISO 8601 duration into an object with its `years`, `months`,
+    /// `weeks`, `days`, `hours`, `minutes`, `seconds` and `milliseconds`.
+    #[rquickjs::function]
+    pub fn parse_duration<'js>(ctx: Ctx<'js>, duration: String) -> Result<Object<'js>> {
+        let duration = throw_range(&ctx, super::parse_duration(&duration))?;
+        let object = Object::new(ctx)?;
+        object.set("years", duration.years)?;
+        object.set("months", duration.months)?;
+        object.set("weeks", duration.weeks)?;
+        object.set("days", duration.days)?;
+        object.set("hours", duration.hours)?;
+        object.set("minutes", duration.minutes)?;
+        object.set("seconds", duration.seconds)?;
+        object.set("milliseconds", duration.milliseconds)?;
+        Ok(object)
+    }
+
+    /// Add an ISO 8601 duration of years, months, weeks and days to a
+    /// `YYYY-MM-DD` date.
+    #[rquickjs::function]
+    pub fn add_to_date(ctx: Ctx<'_>, date: String, duration: String) -> Result<String> {
+        let date = throw_range(&ctx, Date::parse(&date))?;
+        let duration = throw_range(&ctx, super::parse_duration(&duration))?;
+        let date = throw_range(&ctx, date.add(&duration))?;
+        Ok(date.format())
+    }
+
+    /// Add an ISO 8601 duration to milliseconds since the Unix epoch, counting
+    /// days as 24 hours. Years and months have no fixed length, so they are
+    /// rejected.
+    #[rquickjs::function]
+    pub fn add_to_instant(ctx: Ctx<'_>, epoch_ms: f64, duration: String) -> Result<f64> {
+        let epoch_ms = throw_range(&ctx, epoch_milliseconds(epoch_ms))?;
+        let duration = throw_range(&ctx, super::parse_duration(&duration))?;
+        if duration.has_calendar_units() {
+            return Err(Exception::throw_range(
+                &ctx,
+                "Cannot add years or months to an instant, use addToDate",
+            ));
+        }
+        let instant = duration
+            .exact_milliseconds()
+            .and_then(|ms| ms.checked_add(epoch_ms))
+            .filter(|ms| ms.abs() <= MAX_EPOCH_MS);
+        match instant {
+            Some(instant) => Ok(instant as f64),
+            None => Err(Exception::throw_range(&ctx, "Instant out of range")),
+        }
+    }
+
+    /// The number of days from the `YYYY-MM-DD` date `start` to `end`,
+    /// negative if `end` is before `start`.
+    #[rquickjs::function]
+    pub fn days_between(ctx: Ctx<'_>, start: String, end: String) -> Result<i64> {
+        let start = throw_range(&ctx, Date::parse(&start))?;
+        let end = throw_range(&ctx, Date::parse(&end))?;
+        Ok(end.to_days() - start.to_days())
+    }
+
+    /// The ISO day of the week of a `YYYY-MM-DD` date, from 1 for Monday to
+    /// 7 for Sunday.
+    #[rquickjs::function]
+    pub fn day_of_week(ctx: Ctx<'_>, date: String) -> Result<i64> {
+        let date = throw_range(&ctx, Date::parse(&date))?;
+        // 1970-01-01 was a Thursday
+        Ok((date.to_days() + 3).rem_euclid(7) + 1)
+    }
+
+    /// The `YYYY-MM-DD` date in UTC of milliseconds since the Unix epoch.
+    #[rquickjs::function]
+    pub fn to_date(ctx: Ctx<'_>, epoch_ms: f64) -> Result<String> {
+        let epoch_ms = throw_range(&ctx, epoch_milliseconds(epoch_ms))?;
+        let date = throw_range(&ctx, Date::from_days(epoch_ms.div_euclid(MS_PER_DAY)))?;
+        Ok(date.format())
+    }
+}
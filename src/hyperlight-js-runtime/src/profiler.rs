@@ -0,0 +1,124 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! A sampling profiler of the JavaScript call stack.
+//!
+//! The stack is sampled from the interrupt handler of the engine, which QuickJS polls while it
+//! runs JavaScript, and the samples are aggregated in the folded format of
+//! [inferno](https://github.com/jonhoo/inferno) and flamegraph.pl: one line per distinct stack,
+//! with its frames from the outermost to the innermost separated by `;`, then the number of
+//! samples of that stack.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::ptr::NonNull;
+
+use rquickjs::{qjs, Ctx, Exception};
+
+use crate::utils::now_micros;
+
+/// The deepest stack sampled, which is the most frames QuickJS records in a stack trace.
+pub(crate) const MAX_DEPTH: usize = 64;
+
+pub(crate) struct Profiler {
+    // The context the samples are taken in. It lives as long as the runtime, and so as long as
+    // the interrupt handler that samples.
+    ctx: NonNull<qjs::JSContext>,
+    interval_micros: u64,
+    next_sample: Cell<u64>,
+    samples: RefCell<BTreeMap<String, u64>>,
+}
+
+impl Profiler {
+    pub(crate) fn new(ctx: NonNull<qjs::JSContext>, interval_micros: u64) -> Self {
+        Self {
+            ctx,
+            interval_micros,
+            next_sample: Cell::new(0),
+            samples: RefCell::default(),
+        }
+    }
+
+    /// Sample the call stack, if the interval elapsed since the last sample.
+    /// This must only be called from the interrupt handler of the runtime.
+    pub(crate) fn poll(&self) {
+        let now = now_micros();
+        if now < self.next_sample.get() {
+            return;
+        }
+        self.next_sample
+            .set(now.saturating_add(self.interval_micros));
+
+        // SAFETY: the interrupt handler is only called while JavaScript runs, so the runtime is
+        // locked, and the context outlives the interrupt handler.
+        let ctx = unsafe { Ctx::from_raw(self.ctx) };
+        // A new error records the stack trace of the running code, innermost frame first.
+        let Some(stack) = Exception::from_message(ctx, "")
+            .ok()
+            .and_then(|error| error.stack())
+        else {
+            return;
+        };
+        let frames: Vec<String> = stack.lines().filter_map(frame).collect();
+        if frames.is_empty() {
+            return;
+        }
+        let mut folded = String::new();
+        for frame in frames.iter().rev() {
+            if !folded.is_empty() {
+                folded.push(';');
+            }
+            folded.push_str(frame);
+        }
+        *self.samples.borrow_mut().entry(folded).or_default() += 1;
+    }
+
+    /// Returns the samples taken since the last call, in the folded format.
+    pub(crate) fn take(&self) -> String {
+        let samples = core::mem::take(&mut *self.samples.borrow_mut());
+        samples
+            .into_iter()
+            .map(|(stack, count)| format!("{stack} {count}\n"))
+            .collect()
+    }
+}
+
+/// The frame of a line of a QuickJS stack trace, `    at name (file:line:column)`, as
+/// `name (file)`.
+/// The line and column are dropped, as the position of the innermost frame is not always up to
+/// date while sampling, and so every function gets a single frame.
+fn frame(line: &str) -> Option<String> {
+    let frame = line.trim().strip_prefix("at ")?;
+    let frame = match frame.rsplit_once(" (") {
+        Some((name, location)) => {
+            let mut file = location.strip_suffix(')').unwrap_or(location);
+            for _ in 0..2 {
+                if let Some((rest, number)) = file.rsplit_once(':')
+                    && !number.is_empty()
+                    && number.bytes().all(|b| b.is_ascii_digit())
+                {
+                    file = rest;
+                }
+            }
+            format!("{name} ({file})")
+        }
+        None => String::from(frame),
+    };
+    // `;` separates the frames of a folded stack.
+    Some(frame.replace(';', ","))
+}
@@ -17,6 +17,8 @@ use alloc::vec::Vec;
 
 use rquickjs::{Exception, Result, Value};
 
+use crate::libc;
+
 /// Converts a JavaScript value to a byte vector.
 /// The value can be a String, or a Uint8Array
 pub fn as_bytes(key: Value) -> Result<Vec<u8>> {
@@ -37,3 +39,30 @@ pub fn as_bytes(key: Value) -> Result<Vec<u8>> {
         "Expected a String or Uint8Array",
     ))
 }
+
+/// Returns the current time in microseconds since the Unix epoch.
+/// In hyperlight, the time is provided by the host.
+pub fn now_micros() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let _ = unsafe { libc::clock_gettime(libc::CLOCK_REALTIME as _, &mut ts) };
+    (ts.tv_sec as u64)
+        .saturating_mul(1_000_000)
+        .saturating_add(ts.tv_nsec as u64 / 1000)
+}
+
+/// Returns the time of a monotonic clock in microseconds, which does not go back when the wall
+/// clock is adjusted, for measuring durations.
+/// In hyperlight, the time is provided by the host.
+pub fn monotonic_micros() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let _ = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC as _, &mut ts) };
+    (ts.tv_sec as u64)
+        .saturating_mul(1_000_000)
+        .saturating_add(ts.tv_nsec as u64 / 1000)
+}
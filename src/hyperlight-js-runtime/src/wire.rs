@@ -0,0 +1,224 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Framing of the events and results exchanged between the host and the guest
+//! when wire compression is enabled.
+//!
+//! A frame is a tag byte followed by the payload: [`RAW`] for a payload sent
+//! as is, or [`LZ4`] for the length of the payload as a little endian `u32`
+//! followed by the payload compressed in the LZ4 block format.
+//!
+//! This module is shared by the host and the guest, so both ends always agree
+//! on the format.
+
+use alloc::vec::Vec;
+
+use anyhow::{bail, ensure, Result};
+
+/// Tag of a frame carrying its payload uncompressed.
+pub const RAW: u8 = 0;
+/// Tag of a frame carrying its payload compressed with LZ4.
+pub const LZ4: u8 = 1;
+
+/// Frame `payload`, compressing it if it is at least `min_size` bytes long
+/// and compression makes it smaller.
+pub fn encode(payload: &[u8], min_size: usize) -> Vec<u8> {
+    if payload.len() >= min_size
+        && let Ok(len) = u32::try_from(payload.len())
+    {
+        let mut frame = Vec::with_capacity(5 + lz4::max_compressed_size(payload.len()));
+        frame.push(LZ4);
+        frame.extend_from_slice(&len.to_le_bytes());
+        lz4::compress(payload, &mut frame);
+        if frame.len() < payload.len() + 1 {
+            return frame;
+        }
+    }
+    let mut frame = Vec::with_capacity(payload.len() + 1);
+    frame.push(RAW);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Get the payload of `frame`.
+pub fn decode(frame: &[u8]) -> Result<Vec<u8>> {
+    match frame.split_first() {
+        Some((&RAW, payload)) => Ok(payload.to_vec()),
+        Some((&LZ4, rest)) => {
+            ensure!(rest.len() >= 4, "Truncated LZ4 frame");
+            let (len, block) = rest.split_at(4);
+            let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+            // LZ4 cannot compress more than 255:1, which bounds the allocation
+            // for frames that lie about their length.
+            ensure!(
+                len <= block.len().saturating_mul(255),
+                "Invalid LZ4 frame: {} bytes cannot decompress to {len} bytes",
+                block.len()
+            );
+            lz4::decompress(block, len)
+        }
+        Some((tag, _)) => bail!("Unknown wire frame tag {tag}"),
+        None => bail!("Empty wire frame"),
+    }
+}
+
+/// The LZ4 block format, see <https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md>.
+mod lz4 {
+    use alloc::vec::Vec;
+
+    use anyhow::{ensure, Result};
+
+    const MIN_MATCH: usize = 4;
+    // The last match must start at least 12 bytes before the end of the block,
+    // and the last 5 bytes are always literals.
+    const MF_LIMIT: usize = 12;
+    const LAST_LITERALS: usize = 5;
+    const MAX_OFFSET: usize = u16::MAX as usize;
+    const HASH_LOG: u32 = 12;
+
+    pub(super) fn max_compressed_size(len: usize) -> usize {
+        len + len / 255 + 16
+    }
+
+    fn read_u32(input: &[u8], pos: usize) -> u32 {
+        u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]])
+    }
+
+    fn hash(sequence: u32) -> usize {
+        (sequence.wrapping_mul(2654435761) >> (32 - HASH_LOG)) as usize
+    }
+
+    fn write_length(output: &mut Vec<u8>, mut len: usize) {
+        while len >= 255 {
+            output.push(255);
+            len -= 255;
+        }
+        output.push(len as u8);
+    }
+
+    fn write_sequence(output: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+        let literal_token = literals.len().min(15) as u8;
+        let match_token = matched.map_or(0, |(_, len)| (len - MIN_MATCH).min(15) as u8);
+        output.push(literal_token << 4 | match_token);
+        if literals.len() >= 15 {
+            write_length(output, literals.len() - 15);
+        }
+        output.extend_from_slice(literals);
+        if let Some((offset, len)) = matched {
+            output.extend_from_slice(&(offset as u16).to_le_bytes());
+            if len - MIN_MATCH >= 15 {
+                write_length(output, len - MIN_MATCH - 15);
+            }
+        }
+    }
+
+    /// Compress `input` as a single LZ4 block appended to `output`.
+    pub(super) fn compress(input: &[u8], output: &mut Vec<u8>) {
+        let mut table = [0usize; 1 << HASH_LOG];
+        let mut anchor = 0;
+        let mut pos = 0;
+        if input.len() > MF_LIMIT {
+            let match_limit = input.len() - MF_LIMIT;
+            while pos < match_limit {
+                let sequence = read_u32(input, pos);
+                let slot = hash(sequence);
+                // Positions are stored off by one, so 0 means an empty slot.
+                let candidate = table[slot].wrapping_sub(1);
+                table[slot] = pos + 1;
+                if candidate < pos
+                    && pos - candidate <= MAX_OFFSET
+                    && read_u32(input, candidate) == sequence
+                {
+                    let mut len = MIN_MATCH;
+                    let end = input.len() - LAST_LITERALS;
+                    while pos + len < end && input[candidate + len] == input[pos + len] {
+                        len += 1;
+                    }
+                    write_sequence(output, &input[anchor..pos], Some((pos - candidate, len)));
+                    pos += len;
+                    anchor = pos;
+                } else {
+                    pos += 1;
+                }
+            }
+        }
+        write_sequence(output, &input[anchor..], None);
+    }
+
+    fn read_length(input: &[u8], pos: &mut usize) -> Result<usize> {
+        let mut len = 0usize;
+        loop {
+            ensure!(*pos < input.len(), "Truncated LZ4 block");
+            let byte = input[*pos];
+            *pos += 1;
+            len = len.saturating_add(byte as usize);
+            if byte != 255 {
+                return Ok(len);
+            }
+        }
+    }
+
+    /// Decompress the LZ4 block `input`, which must decompress to exactly `len` bytes.
+    pub(super) fn decompress(input: &[u8], len: usize) -> Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(len);
+        let mut pos = 0;
+        loop {
+            ensure!(pos < input.len(), "Truncated LZ4 block");
+            let token = input[pos];
+            pos += 1;
+
+            let mut literals = (token >> 4) as usize;
+            if literals == 15 {
+                literals = literals.saturating_add(read_length(input, &mut pos)?);
+            }
+            ensure!(
+                literals <= input.len() - pos && literals <= len - output.len(),
+                "Corrupt LZ4 block"
+            );
+            output.extend_from_slice(&input[pos..pos + literals]);
+            pos += literals;
+
+            // The last sequence has no match
+            if pos == input.len() {
+                break;
+            }
+
+            ensure!(input.len() - pos >= 2, "Truncated LZ4 block");
+            let offset = u16::from_le_bytes([input[pos], input[pos + 1]]) as usize;
+            pos += 2;
+            let mut matched = (token & 0xf) as usize;
+            if matched == 15 {
+                matched = matched.saturating_add(read_length(input, &mut pos)?);
+            }
+            matched = matched.saturating_add(MIN_MATCH);
+            ensure!(
+                offset != 0 && offset <= output.len() && matched <= len - output.len(),
+                "Corrupt LZ4 block"
+            );
+            // Matches may overlap the bytes they produce, so copy byte by byte.
+            let start = output.len() - offset;
+            for i in 0..matched {
+                let byte = output[start + i];
+                output.push(byte);
+            }
+        }
+        ensure!(
+            output.len() == len,
+            "LZ4 block decompressed to {} bytes instead of {len}",
+            output.len()
+        );
+        Ok(output)
+    }
+}
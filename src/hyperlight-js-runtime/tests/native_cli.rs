@@ -61,6 +61,215 @@ fn smoke_test() {
     assert_eq!(lines, [r#"{"a":1,"b":[1,2,3]}"#, "Handler result: 42",]);
 }
 
+#[test]
+fn error_causes() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            async function handler(event) {
+                const results = await Promise.allSettled([
+                    Promise.resolve(1),
+                    Promise.reject(new RangeError("out of range")),
+                ]);
+                if (results[1].status !== "rejected") {
+                    return "unexpected";
+                }
+                try {
+                    await Promise.any([
+                        Promise.reject(new TypeError("first")),
+                        Promise.reject({ code: 42 }),
+                    ]);
+                } catch (err) {
+                    throw new Error("load failed", { cause: err });
+                }
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg(dir.path().join("./index.js"))
+        .arg("{}")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("load failed"), "{stderr}");
+    assert!(stderr.contains("Caused by: AggregateError"), "{stderr}");
+    assert!(stderr.contains("[0] TypeError: first"), "{stderr}");
+    assert!(stderr.contains(r#"[1] {"code":42}"#), "{stderr}");
+}
+
+#[test]
+fn seeded_random() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import { random, randomInt } from "random";
+            function handler(event, context) {
+                return [context.seed, random(), randomInt(1, 7)];
+            }
+        "#,
+    )
+    .unwrap();
+
+    let run = |seed: &str| {
+        let output = js_runtime_cli()
+            .arg(dir.path().join("./index.js"))
+            .arg("{}")
+            .args(["--seed", seed])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let first = run("42");
+    assert!(
+        first.starts_with(r#"Handler result: ["000000000000002a","#),
+        "{first}"
+    );
+    assert_eq!(run("42"), first);
+    assert_ne!(run("43"), first);
+}
+
+#[test]
+fn deterministic_math_random() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import { random } from "random";
+            function handler(event, context) {
+                const math = Math.random();
+                return [math, random(), math !== Math.random()];
+            }
+        "#,
+    )
+    .unwrap();
+
+    let run = |seed: &str| {
+        let output = js_runtime_cli()
+            .arg(dir.path().join("./index.js"))
+            .arg("{}")
+            .args(["--seed", seed, "--deterministic"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let first = run("42");
+    assert!(first.ends_with(",true]"), "{first}");
+    assert_eq!(run("42"), first);
+    assert_ne!(run("43"), first);
+}
+
+#[test]
+fn temporal_date_math() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import * as temporal from "temporal";
+            function attempt(f) {
+                try {
+                    return f();
+                } catch (err) {
+                    return err.name;
+                }
+            }
+            function handler(event) {
+                return [
+                    temporal.parseDuration("-P1Y2M3W4DT5H6M7.0089S"),
+                    temporal.addToDate("2024-01-31", "P1M"),
+                    temporal.addToDate("2024-02-29", "P1Y"),
+                    temporal.addToDate("2024-03-01", "-P1D"),
+                    temporal.addToInstant(0, "P1DT1H0.5S"),
+                    temporal.daysBetween("2024-01-01", "2025-01-01"),
+                    temporal.dayOfWeek("2024-02-29"),
+                    temporal.toDate(-1),
+                    attempt(() => temporal.parseDuration("P1H")),
+                    attempt(() => temporal.addToDate("2024-02-30", "P1D")),
+                    attempt(() => temporal.addToDate("2024-01-01", "PT1H")),
+                    attempt(() => temporal.addToInstant(0, "P1M")),
+                ];
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg(dir.path().join("./index.js"))
+        .arg("{}")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.trim(),
+        concat!(
+            r#"Handler result: [{"years":-1,"months":-2,"weeks":-3,"days":-4,"hours":-5,"minutes":-6,"seconds":-7,"milliseconds":-8},"#,
+            r#""2024-02-29","2025-02-28","2024-02-29",90000500,366,4,"1969-12-31","#,
+            r#""RangeError","RangeError","RangeError","RangeError"]"#
+        )
+    );
+}
+
+#[test]
+fn engine_limits_throw_catchable_errors() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            function attempt(f) {
+                try {
+                    f();
+                    return "no error";
+                } catch (err) {
+                    return `${err.name}: ${err.message}`;
+                }
+            }
+            function handler(event) {
+                const memory = attempt(() => {
+                    const chunks = [];
+                    for (;;) chunks.push(new Array(10000).fill(1));
+                });
+                const stack = attempt(() => {
+                    const recurse = (n) => recurse(n + 1) + 1;
+                    recurse(0);
+                });
+                return [memory, stack];
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg(dir.path().join("./index.js"))
+        .arg("{}")
+        .args(["--memory-limit", "8000000", "--max-stack-size", "262144"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.trim(),
+        r#"Handler result: ["InternalError: out of memory","RangeError: Maximum call stack size exceeded"]"#
+    );
+}
+
 fn js_runtime_cli() -> Command {
     CargoBuild::new()
         .manifest_path(env!("CARGO_MANIFEST_PATH"))
@@ -71,3 +280,206 @@ fn js_runtime_cli() -> Command {
         .unwrap()
         .command()
 }
+
+#[test]
+fn host_errors_are_thrown_as_js_errors() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import { readFile } from "fs";
+            function handler(event) {
+                try {
+                    readFile("missing.txt");
+                } catch (err) {
+                    return [err instanceof Error, err.name, err.code, err.message.includes("missing.txt")];
+                }
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg(dir.path().join("./index.js"))
+        .arg("{}")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(r#"Handler result: [true,"Error","ENOENT",true]"#),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn batches_run_the_handler_once_per_event() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            let calls = 0;
+            function handler(event, context) {
+                calls++;
+                if (event.fail) {
+                    throw new Error(`event ${context.sequence} failed`);
+                }
+                return { value: event.value, sequence: context.sequence, calls };
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg(dir.path().join("./index.js"))
+        .arg(r#"[{"value":1},{"fail":true},{"value":3}]"#)
+        .arg("--batch")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The failing event doesn't stop the batch
+    let results: Vec<&str> = stdout.split("Handler ").skip(1).collect();
+    assert_eq!(results.len(), 3, "{stdout}");
+    assert_eq!(
+        results[0].trim(),
+        r#"result: {"value":1,"sequence":1,"calls":1}"#
+    );
+    assert!(results[1].starts_with("error: "), "{stdout}");
+    assert!(results[1].contains("event 2 failed"), "{stdout}");
+    assert_eq!(
+        results[2].trim(),
+        r#"result: {"value":3,"sequence":3,"calls":3}"#
+    );
+}
+
+#[test]
+fn handlers_import_the_exports_of_the_setup_script() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("setup.js"),
+        r#"
+            import { add } from './math.js';
+            console.log("setting up");
+            export const table = new Map([["a", add(1, 1)], ["b", add(2, 2)]]);
+        "#,
+    )
+    .unwrap();
+
+    write(
+        dir.path().join("math.js"),
+        r#"
+            export const add = (a, b) => a + b;
+        "#,
+    )
+    .unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import { table } from "setup";
+            function handler(event) {
+                return table.get(event.key);
+            }
+        "#,
+    )
+    .unwrap();
+
+    let output = js_runtime_cli()
+        .arg("--setup")
+        .arg(dir.path().join("setup.js"))
+        .arg("--batch")
+        .arg(dir.path().join("index.js"))
+        .arg(r#"[{"key":"a"},{"key":"b"}]"#)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.trim().lines().collect::<Vec<_>>();
+
+    // The setup script is evaluated once, not per invocation
+    assert_eq!(
+        lines,
+        ["setting up", "Handler result: 2", "Handler result: 4"]
+    );
+}
+
+#[test]
+fn dynamic_code_can_be_disabled() {
+    let dir = tempdir().unwrap();
+
+    write(
+        dir.path().join("index.js"),
+        r#"
+            import { double } from './math.js';
+            const attempt = (f) => {
+                try {
+                    f();
+                    return "allowed";
+                } catch (e) {
+                    return e.name;
+                }
+            };
+            async function handler(event) {
+                let dynamicImport;
+                try {
+                    await import('./other.js');
+                    dynamicImport = "allowed";
+                } catch (e) {
+                    dynamicImport = "refused";
+                }
+                return {
+                    eval: attempt(() => eval("1 + 1")),
+                    indirectEval: attempt(() => (0, eval)("1 + 1")),
+                    function: attempt(() => new Function("return 1")),
+                    constructor: attempt(() => (() => {}).constructor("return 1")),
+                    asyncFunction: attempt(() => (async () => {}).constructor("return 1")),
+                    generatorFunction: attempt(() => (function* () {}).constructor("yield 1")),
+                    instanceOf: (() => {}) instanceof Function,
+                    dynamicImport,
+                    staticImport: double(21),
+                };
+            }
+        "#,
+    )
+    .unwrap();
+
+    write(
+        dir.path().join("math.js"),
+        "export const double = (x) => x * 2;",
+    )
+    .unwrap();
+    write(dir.path().join("other.js"), "export const other = 1;").unwrap();
+
+    let output = js_runtime_cli()
+        .arg("--no-dynamic-code")
+        .arg(dir.path().join("index.js"))
+        .arg("{}")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.trim(),
+        r#"Handler result: {"eval":"EvalError","indirectEval":"EvalError","function":"EvalError","constructor":"EvalError","asyncFunction":"EvalError","generatorFunction":"EvalError","instanceOf":true,"dynamicImport":"refused","staticImport":42}"#
+    );
+
+    // Without the flag, dynamic code runs
+    let output = js_runtime_cli()
+        .arg(dir.path().join("index.js"))
+        .arg("{}")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""eval":"allowed""#), "{stdout}");
+    assert!(stdout.contains(r#""dynamicImport":"allowed""#), "{stdout}");
+}
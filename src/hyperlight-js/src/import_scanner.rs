@@ -0,0 +1,291 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! A lightweight scanner for the static imports of a JavaScript module.
+//!
+//! This is not a JavaScript parser. It tokenizes just enough of the source
+//! (comments, strings, template literals and regular expression literals) to
+//! find the specifiers of `import ... from "x"`, `import "x"` and
+//! `export ... from "x"` declarations. It is used to pre-bundle the modules a
+//! handler depends on, so missing an unusual import is harmless: the guest
+//! falls back to asking the host for any module that was not bundled.
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token<'a> {
+    None,
+    Ident(&'a str),
+    Punct(u8),
+    // A string, number, template or regular expression literal.
+    Literal,
+}
+
+impl Token<'_> {
+    // Whether a `/` following this token starts a regular expression rather than a division.
+    fn allows_regex(self) -> bool {
+        match self {
+            Token::None => true,
+            Token::Punct(c) => !matches!(c, b')' | b']' | b'}'),
+            Token::Ident(ident) => matches!(
+                ident,
+                "return"
+                    | "typeof"
+                    | "instanceof"
+                    | "in"
+                    | "of"
+                    | "new"
+                    | "delete"
+                    | "void"
+                    | "throw"
+                    | "case"
+                    | "do"
+                    | "else"
+                    | "yield"
+                    | "await"
+            ),
+            Token::Literal => false,
+        }
+    }
+}
+
+struct Scanner<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.source.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b'/' if self.peek_at(1) == Some(b'/') => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                b'/' if self.peek_at(1) == Some(b'*') => {
+                    self.pos += 2;
+                    while self.peek().is_some()
+                        && !(self.peek() == Some(b'*') && self.peek_at(1) == Some(b'/'))
+                    {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.source.len());
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    // Reads a string literal starting at the opening quote, returning its raw contents.
+    fn read_string(&mut self, quote: u8) -> &'a str {
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            match c {
+                b'\\' => self.pos += 2,
+                b'\n' => break,
+                c if c == quote => break,
+                _ => self.pos += 1,
+            }
+        }
+        let end = self.pos.min(self.source.len());
+        self.pos = (self.pos + 1).min(self.source.len());
+        self.source.get(start..end).unwrap_or_default()
+    }
+
+    // Skips the body of a template literal, returning `true` if it stopped at the
+    // start of a `${` substitution rather than at the closing backtick.
+    fn skip_template(&mut self) -> bool {
+        while let Some(c) = self.peek() {
+            match c {
+                b'\\' => self.pos += 2,
+                b'`' => {
+                    self.pos += 1;
+                    return false;
+                }
+                b'$' if self.peek_at(1) == Some(b'{') => {
+                    self.pos += 2;
+                    return true;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        false
+    }
+
+    fn skip_regex(&mut self) {
+        self.pos += 1;
+        let mut in_class = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'\\' => self.pos += 1,
+                b'[' => in_class = true,
+                b']' => in_class = false,
+                b'/' if !in_class => break,
+                b'\n' => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        self.skip_identifier();
+    }
+
+    fn skip_identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || !c.is_ascii() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.source.get(start..self.pos).unwrap_or_default()
+    }
+}
+
+/// Returns the specifiers of the static imports and re-exports of `source`,
+/// in the order they appear.
+pub(crate) fn scan_imports(source: &str) -> Vec<String> {
+    let mut scanner = Scanner { source, pos: 0 };
+    let mut specifiers = Vec::new();
+    let mut prev = Token::None;
+    let mut before_prev = Token::None;
+    // The brace depth at which each enclosing template substitution was opened.
+    let mut substitutions: Vec<usize> = Vec::new();
+    let mut depth = 0;
+
+    loop {
+        scanner.skip_whitespace_and_comments();
+        let Some(c) = scanner.peek() else {
+            break;
+        };
+
+        let token = match c {
+            b'"' | b'\'' => {
+                let value = scanner.read_string(c);
+                let is_import = matches!(prev, Token::Ident("from" | "import"))
+                    && before_prev != Token::Punct(b'.');
+                if is_import {
+                    specifiers.push(value.to_string());
+                }
+                Token::Literal
+            }
+            b'`' => {
+                scanner.pos += 1;
+                if scanner.skip_template() {
+                    substitutions.push(depth);
+                    depth += 1;
+                    Token::Punct(b'{')
+                } else {
+                    Token::Literal
+                }
+            }
+            b'{' => {
+                scanner.pos += 1;
+                depth += 1;
+                Token::Punct(c)
+            }
+            b'}' => {
+                scanner.pos += 1;
+                depth = depth.saturating_sub(1);
+                if substitutions.last() == Some(&depth) {
+                    substitutions.pop();
+                    if scanner.skip_template() {
+                        substitutions.push(depth);
+                        depth += 1;
+                        Token::Punct(b'{')
+                    } else {
+                        Token::Literal
+                    }
+                } else {
+                    Token::Punct(c)
+                }
+            }
+            b'/' if prev.allows_regex() => {
+                scanner.skip_regex();
+                Token::Literal
+            }
+            c if c.is_ascii_digit() => {
+                scanner.skip_identifier();
+                Token::Literal
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b'$' || !c.is_ascii() => {
+                Token::Ident(scanner.skip_identifier())
+            }
+            c => {
+                scanner.pos += 1;
+                Token::Punct(c)
+            }
+        };
+
+        before_prev = prev;
+        prev = token;
+    }
+
+    specifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_imports() {
+        let source = r#"
+            import { a } from "./a.js";
+            import * as b from './b.js';
+            import "./side-effect.js";
+            export { c } from "./c.js";
+            export * from "./d.js";
+            import def, { e as f } from "pkg/e";
+            const dynamic = import("./dynamic.js");
+        "#;
+        assert_eq!(
+            scan_imports(source),
+            [
+                "./a.js",
+                "./b.js",
+                "./side-effect.js",
+                "./c.js",
+                "./d.js",
+                "pkg/e"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_imports_ignores_comments_strings_and_regexes() {
+        let source = r#"
+            // import { a } from "./comment.js";
+            /* import "./block-comment.js"; */
+            const s = 'import "./string.js"';
+            const t = `import "./template.js" ${`nested ${1}`} from "./template.js"`;
+            const r = /import "\/regex.js"/g;
+            const ratio = 4 / 2 / 1;
+            obj.from("./method.js");
+            import { x } from "./x.js";
+        "#;
+        assert_eq!(scan_imports(source), ["./x.js"]);
+    }
+}
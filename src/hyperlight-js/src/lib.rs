@@ -20,24 +20,85 @@ limitations under the License.
 #![cfg_attr(not(any(test, debug_assertions)), warn(clippy::unwrap_used))]
 #![cfg_attr(any(test, debug_assertions), allow(clippy::disallowed_macros))]
 
+mod import_scanner;
+#[cfg(feature = "lint")]
+mod lint;
+mod module_loader;
 mod resolver;
 mod script;
+mod source_map;
+#[cfg(feature = "typescript")]
+mod typescript;
 
 /// Sandbox module containing all sandbox-related types
 pub mod sandbox;
+/// Helpers to unit test handlers without a hypervisor.
+#[cfg(feature = "testing")]
+pub mod testing;
 
 use hyperlight_host::func::HostFunction;
+/// Warnings reported by [`Script::lint`].
+#[cfg(feature = "lint")]
+pub use lint::{LintDiagnostic, LintRule};
+/// The context of a handler invocation, available to host functions.
+pub use sandbox::call_context::CallContext;
+/// A handle to cooperatively cancel the handler running in a sandbox.
+pub use sandbox::cancellation::{CancellationHandle, TerminationPhase};
+/// A report on a loaded sandbox, to attach to bug reports.
+pub use sandbox::diagnostics::{
+    ConfigurationInfo, Diagnostics, HandlerInfo, InvocationReport, RuntimeInfo,
+};
+/// The error of events too large for the input buffer of the guest.
+pub use sandbox::event_size::EventTooLarge;
+/// When to run garbage collection after handler calls.
+pub use sandbox::gc_policy::GcPolicy;
+/// An error returned by a host function, thrown in the guest as a JS error.
+pub use sandbox::host_error::HostError;
+/// How the guest caches the results of a host function.
+pub use sandbox::host_fn::HostFunctionCache;
+/// A call to a host function, and the rest of its dispatch, passed to host
+/// call interceptors.
+pub use sandbox::host_fn::{HostCall, HostCallNext};
+/// A module of host functions, and types whose methods make one.
+pub use sandbox::host_fn::{HostModule, HostModuleDefinition};
+/// How the handlers of a sandbox are isolated from each other.
+pub use sandbox::isolation_mode::IsolationMode;
 /// A Hyperlight Sandbox with a JavaScript run time loaded but no guest code.
 pub use sandbox::js_sandbox::JSSandbox;
+/// The errors of hyperlight-js, to match on their kind.
+pub use sandbox::js_sandbox_error::JsSandboxError;
 /// A Hyperlight Sandbox with a JavaScript run time loaded and guest code loaded.
 pub use sandbox::loaded_js_sandbox::LoadedJSSandbox;
+/// Statistics on the QuickJS heap of a sandbox.
+pub use sandbox::memory_stats::{HeapReport, MemoryStats};
+/// In-process snapshot of the metrics recorded by hyperlight-js.
+pub use sandbox::metrics::{metrics_snapshot, HandlerLatencies, MetricsSnapshot};
+/// Callbacks on the lifecycle events of a sandbox.
+pub use sandbox::observer::SandboxObserver;
+/// The error of guests that run out of memory.
+pub use sandbox::out_of_memory::GuestOutOfMemory;
+/// Why a sandbox is poisoned.
+pub use sandbox::poison_reason::PoisonReason;
 /// A Hyperlight Sandbox with no JavaScript run time loaded and no guest code.
 /// This is used to register new host functions prior to loading the JavaScript runtime.
 pub use sandbox::proto_js_sandbox::ProtoJSSandbox;
+/// The error of handlers returning results over the size limit.
+pub use sandbox::result_size::ResultTooLarge;
+/// Retrying handlers that fail transiently.
+pub use sandbox::retry::{Retried, RetryPolicy};
 /// A builder for creating a new `JSSandbox`
 pub use sandbox::sandbox_builder::SandboxBuilder;
+/// Running handlers of a sandbox on schedules.
+pub use sandbox::scheduler::{
+    CronSchedule, JobStats, OverlapPolicy, RunOutcome, RunRecord, Schedule, ScheduledJob,
+    Scheduler, SchedulerHandle, DEFAULT_HISTORY_SIZE,
+};
+/// The W3C trace context of the handler invocation calling a host function.
+pub use sandbox::trace_context::current_traceparent;
+/// Compression of the events and results crossing the sandbox boundary.
+pub use sandbox::wire_compression::WireCompression;
 /// Types for working with JS script.
-pub use script::Script;
+pub use script::{Script, ScriptKind};
 /// The function to pass to a new `JSSandbox` to tell it how to handle
 /// guest requests to print some output.
 pub type HostPrintFn = HostFunction<i32, (String,)>;
@@ -58,14 +119,108 @@ pub type ParameterValue = hyperlight_host::func::ParameterValue;
 pub type ReturnValue = hyperlight_host::func::ReturnValue;
 /// The type of the return value from a guest function call.
 pub type ReturnType = hyperlight_host::func::ReturnType;
+/// The argument and return types of the guest function calls of
+/// [`LoadedJSSandbox::call_raw`].
+pub use hyperlight_host::func::{ParameterTuple, SupportedReturnType};
 /// A snapshot of sandbox state that can be used to restore it later.
 pub use hyperlight_host::sandbox::snapshot::Snapshot;
 /// Configuration for sandbox resource limits and behavior.
 pub use hyperlight_host::sandbox::SandboxConfiguration;
+/// Macro to create an embedded file system with all the files in a directory.
+///
+/// This walks the directory at compile time and expands to an
+/// [`embed_modules!`] invocation with one entry per file, keyed by the path of
+/// the file relative to the directory (using `/` as separator), so relative
+/// imports between the embedded modules keep working.
+///
+/// The directory is relative to the root of the crate invoking the macro (the
+/// directory containing its `Cargo.toml`). Hidden files and directories
+/// (starting with `.`) are skipped, and all other files must be valid UTF-8.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{embed_dir, SandboxBuilder};
+///
+/// // Embeds e.g. `math.js`, `strings.js` and `galaxy/index.js`
+/// let fs = embed_dir!("tests/fixtures");
+///
+/// let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// let sandbox = proto_js_sandbox
+///     .set_module_loader(fs)
+///     .unwrap()
+///     .load_runtime()
+///     .unwrap();
+/// ```
+///
+/// # Notes
+///
+/// * Like [`embed_modules!`], this requires the `phf` crate as a dependency.
+/// * Changes to the embedded files are picked up automatically, but adding
+///   files to the directory is not. Add a `cargo:rerun-if-changed` line for the
+///   directory to your build script to rebuild when files are added.
+pub use hyperlight_js_macros::embed_dir;
+/// Attribute to make the methods of a type the host functions of a host
+/// module.
+///
+/// Put it on an `impl` block with the name of the module. Every method of the
+/// block taking `&self` becomes a host function with the same name, whose
+/// arguments and result are mapped with `serde` like the functions passed to
+/// [`ProtoJSSandbox::register`]. Other associated functions, like
+/// constructors, are left alone.
+///
+/// The attribute implements [`HostModuleDefinition`] for the type, so a value
+/// of the type can be passed to [`ProtoJSSandbox::register_module`], which
+/// shares it between its host functions.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{host_module, SandboxBuilder};
+///
+/// struct Math {
+///     factor: i32,
+/// }
+///
+/// #[host_module("math")]
+/// impl Math {
+///     fn scale(&self, x: i32) -> i32 {
+///         x * self.factor
+///     }
+///
+///     fn add(&self, a: i32, b: i32) -> i32 {
+///         a + b
+///     }
+/// }
+///
+/// let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// proto_js_sandbox.register_module(Math { factor: 10 }).unwrap();
+/// // The guest can now `import { scale, add } from "math";`
+/// ```
+///
+/// # Notes
+///
+/// * The impl block must not be generic, and the methods must be synchronous
+///   and not generic.
+/// * Like for [`ProtoJSSandbox::register`], the argument types must be
+///   deserializable into owned values, e.g. `String` rather than `&str`.
+pub use hyperlight_js_macros::host_module;
+/// Options and policies for loading modules imported by guest code.
+pub use module_loader::{
+    ImportPolicy, ImportRequest, ImportRules, ModuleCache, ModuleLoaderOptions,
+};
 /// Module resolution and loading functionality.
-pub use resolver::{FileMetadata, FileSystem, FileSystemEmbedded, ResolveError};
+pub use resolver::{
+    FileMetadata, FileSystem, FileSystemDirectory, FileSystemEmbedded, FileSystemMemory,
+    ResolveError,
+};
 /// The monitor module — re-exports `sleep` so custom monitors don't couple to tokio directly.
 pub use sandbox::monitor;
+/// A set of monitors chosen at runtime.
+pub use sandbox::monitor::BoxedMonitorSet;
+/// Cumulative execution budget across handler invocations.
+#[cfg(any(feature = "monitor-wall-clock", feature = "monitor-cpu-time"))]
+pub use sandbox::monitor::BudgetMonitor;
 /// CPU time based execution monitor.
 #[cfg(feature = "monitor-cpu-time")]
 pub use sandbox::monitor::CpuTimeMonitor;
@@ -75,6 +230,8 @@ pub use sandbox::monitor::ExecutionMonitor;
 /// Sealed trait for monitor composition — automatically derived for all
 /// `ExecutionMonitor` impls and for tuples of up to 5 monitors.
 pub use sandbox::monitor::MonitorSet;
+/// Passed to the callbacks of the soft thresholds of the built-in monitors.
+pub use sandbox::monitor::ThresholdEvent;
 /// Wall-clock based execution monitor.
 #[cfg(feature = "monitor-wall-clock")]
 pub use sandbox::monitor::WallClockMonitor;
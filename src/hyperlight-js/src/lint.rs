@@ -0,0 +1,370 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! A lightweight lint pass over handler scripts.
+//!
+//! The lint runs on the host, before the handler is loaded, to give handler
+//! authors actionable feedback about code that is likely to fail or misbehave
+//! in the guest. It only reports warnings: scripts are loaded regardless.
+
+use std::fmt;
+
+use oxc_allocator::Allocator;
+use oxc_ast::ast::UnaryOperator;
+use oxc_ast::AstKind;
+use oxc_parser::Parser;
+use oxc_semantic::SemanticBuilder;
+use oxc_span::{GetSpan, SourceType, Span};
+
+/// Size, in bytes, above which literals are reported.
+const MAX_LITERAL_BYTES: u32 = 64 * 1024;
+
+/// The globals available to handlers in the guest: the ECMAScript built-ins
+/// provided by QuickJS and the globals set up by the runtime.
+const KNOWN_GLOBALS: &[&str] = &[
+    // ECMAScript
+    "AggregateError",
+    "Array",
+    "ArrayBuffer",
+    "Atomics",
+    "BigInt",
+    "BigInt64Array",
+    "BigUint64Array",
+    "Boolean",
+    "DataView",
+    "Date",
+    "Error",
+    "EvalError",
+    "FinalizationRegistry",
+    "Float32Array",
+    "Float64Array",
+    "Function",
+    "Infinity",
+    "Int16Array",
+    "Int32Array",
+    "Int8Array",
+    "Iterator",
+    "JSON",
+    "Map",
+    "Math",
+    "NaN",
+    "Number",
+    "Object",
+    "Promise",
+    "Proxy",
+    "RangeError",
+    "ReferenceError",
+    "Reflect",
+    "RegExp",
+    "Set",
+    "SharedArrayBuffer",
+    "String",
+    "Symbol",
+    "SyntaxError",
+    "TypeError",
+    "URIError",
+    "Uint16Array",
+    "Uint32Array",
+    "Uint8Array",
+    "Uint8ClampedArray",
+    "WeakMap",
+    "WeakRef",
+    "WeakSet",
+    "decodeURI",
+    "decodeURIComponent",
+    "encodeURI",
+    "encodeURIComponent",
+    "escape",
+    "eval",
+    "globalThis",
+    "isFinite",
+    "isNaN",
+    "parseFloat",
+    "parseInt",
+    "undefined",
+    "unescape",
+    // hyperlight-js runtime
+    "console",
+    "host",
+    "print",
+    "require",
+];
+
+/// APIs of browsers and other JavaScript runtimes that are not available in the guest.
+const UNSUPPORTED_APIS: &[&str] = &[
+    "Buffer",
+    "Bun",
+    "Deno",
+    "WebSocket",
+    "XMLHttpRequest",
+    "clearInterval",
+    "clearTimeout",
+    "document",
+    "fetch",
+    "localStorage",
+    "navigator",
+    "process",
+    "sessionStorage",
+    "setImmediate",
+    "setInterval",
+    "setTimeout",
+    "window",
+];
+
+/// The kind of issue a [`LintDiagnostic`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LintRule {
+    /// The script cannot be parsed.
+    SyntaxError,
+    /// The script evaluates code from strings, with `eval` or `Function`.
+    Eval,
+    /// The script uses a variable that is not declared.
+    UndeclaredVariable,
+    /// The script uses an API of another runtime that is not available in the guest.
+    UnsupportedApi,
+    /// The script contains a very large literal, which bloats the guest heap.
+    LargeLiteral,
+}
+
+impl LintRule {
+    /// The name of the rule, e.g. `undeclared-variable`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintRule::SyntaxError => "syntax-error",
+            LintRule::Eval => "eval",
+            LintRule::UndeclaredVariable => "undeclared-variable",
+            LintRule::UnsupportedApi => "unsupported-api",
+            LintRule::LargeLiteral => "large-literal",
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A warning about a handler script, see [`Script::lint`](crate::Script::lint).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LintDiagnostic {
+    /// The rule that reported the warning.
+    pub rule: LintRule,
+    /// A description of the issue.
+    pub message: String,
+    /// The 1-based line of the issue in the script.
+    pub line: u32,
+    /// The 1-based column of the issue in the script, in characters.
+    pub column: u32,
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} ({})",
+            self.line, self.column, self.message, self.rule
+        )
+    }
+}
+
+/// Lint the JavaScript module `source`.
+pub(crate) fn lint(source: &str) -> Vec<LintDiagnostic> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, source, SourceType::mjs()).parse();
+    let mut diagnostics = Vec::new();
+    let mut report = |rule, span: Span, message: String| {
+        let (line, column) = line_column(source, span.start);
+        diagnostics.push(LintDiagnostic {
+            rule,
+            message,
+            line,
+            column,
+        });
+    };
+
+    if !parsed.errors.is_empty() {
+        for error in parsed.errors {
+            let start = error
+                .labels
+                .as_ref()
+                .and_then(|labels| labels.first())
+                .map_or(0, |label| label.offset() as u32);
+            report(
+                LintRule::SyntaxError,
+                Span::new(start, start),
+                error.to_string(),
+            );
+        }
+        return diagnostics;
+    }
+
+    let semantic = SemanticBuilder::new().build(&parsed.program).semantic;
+    let nodes = semantic.nodes();
+    let scoping = semantic.scoping();
+
+    let mut unresolved: Vec<(&str, Span, bool)> = Vec::new();
+    for (name, references) in scoping.root_unresolved_references() {
+        for reference in references.iter() {
+            let node_id = scoping.get_reference(*reference).node_id();
+            let span = nodes.kind(node_id).span();
+            let callee = match nodes.parent_kind(node_id) {
+                AstKind::CallExpression(call) => call.callee.span() == span,
+                AstKind::NewExpression(new) => new.callee.span() == span,
+                // `typeof x` is the idiomatic way to check whether a global exists
+                AstKind::UnaryExpression(unary) if unary.operator == UnaryOperator::Typeof => {
+                    continue;
+                }
+                _ => false,
+            };
+            unresolved.push((name, span, callee));
+        }
+    }
+    unresolved.sort_by_key(|(_, span, _)| span.start);
+
+    for (name, span, callee) in unresolved {
+        match name {
+            "eval" => report(
+                LintRule::Eval,
+                span,
+                "`eval` evaluates code from a string, which is slow and hard to audit".to_string(),
+            ),
+            "Function" if callee => report(
+                LintRule::Eval,
+                span,
+                "The `Function` constructor evaluates code from a string, which is slow and hard to audit"
+                    .to_string(),
+            ),
+            name if UNSUPPORTED_APIS.contains(&name) => report(
+                LintRule::UnsupportedApi,
+                span,
+                format!("`{name}` is not available in the sandbox"),
+            ),
+            name if !KNOWN_GLOBALS.contains(&name) => report(
+                LintRule::UndeclaredVariable,
+                span,
+                format!("`{name}` is not declared"),
+            ),
+            _ => {}
+        }
+    }
+
+    // Nodes are visited parents first, so literals nested in a reported one are skipped.
+    let mut reported = Span::default();
+    for node in nodes.iter() {
+        let kind = node.kind();
+        let span = kind.span();
+        if !matches!(
+            kind,
+            AstKind::StringLiteral(_)
+                | AstKind::TemplateLiteral(_)
+                | AstKind::ArrayExpression(_)
+                | AstKind::ObjectExpression(_)
+        ) || span.size() <= MAX_LITERAL_BYTES
+            || (reported.start <= span.start && span.end <= reported.end && !reported.is_empty())
+        {
+            continue;
+        }
+        reported = span;
+        report(
+            LintRule::LargeLiteral,
+            span,
+            format!(
+                "This literal is {} KiB, consider passing large data in the event instead",
+                span.size() / 1024
+            ),
+        );
+    }
+
+    diagnostics
+}
+
+/// The 1-based line and column of the byte `offset` in `source`.
+fn line_column(source: &str, offset: u32) -> (u32, u32) {
+    let before = source.get(..offset as usize).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line as u32, column as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Vec<(LintRule, u32)> {
+        lint(source)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_handler() {
+        let source = r#"
+            import { add } from "./math.js";
+            function handler(event) {
+                console.log(JSON.stringify(event));
+                if (typeof fetch === "function") {}
+                return { sum: add(event.a, event.b), now: Date.now() };
+            }
+        "#;
+        assert_eq!(rules(source), vec![]);
+    }
+
+    #[test]
+    fn test_lint_reports_issues() {
+        let source = r#"function handler(event) {
+            const x = eval(event.code);
+            const f = new Function("return 1");
+            total = x + f();
+            return fetch(event.url);
+        }"#;
+        assert_eq!(
+            rules(source),
+            vec![
+                (LintRule::Eval, 2),
+                (LintRule::Eval, 3),
+                (LintRule::UndeclaredVariable, 4),
+                (LintRule::UnsupportedApi, 5),
+            ]
+        );
+
+        let diagnostic = &lint(source)[2];
+        assert_eq!(diagnostic.column, 13);
+        assert_eq!(
+            diagnostic.to_string(),
+            "4:13: `total` is not declared (undeclared-variable)"
+        );
+    }
+
+    #[test]
+    fn test_lint_large_literals() {
+        let data = "x".repeat(MAX_LITERAL_BYTES as usize);
+        let source = format!(
+            "const data = [\"{data}\", \"{data}\"];\nfunction handler() {{ return data.length; }}"
+        );
+        assert_eq!(rules(&source), vec![(LintRule::LargeLiteral, 1)]);
+    }
+
+    #[test]
+    fn test_lint_syntax_error() {
+        let diagnostics = lint("function handler( {");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, LintRule::SyntaxError);
+    }
+}
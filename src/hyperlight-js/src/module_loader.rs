@@ -0,0 +1,901 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Host side of module resolution and loading for guest `import`s.
+//!
+//! This module contains the configuration accepted by
+//! [`ProtoJSSandbox::set_module_loader_with_options`](crate::ProtoJSSandbox::set_module_loader_with_options)
+//! and the implementation of the `ResolveModule` and `LoadModule` host functions.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hyperlight_host::{new_error, Result};
+use oxc_resolver::{ResolveOptions, ResolverGeneric};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::import_scanner::scan_imports;
+use crate::resolver::{normalize_key, FileSystem};
+
+/// A request to import a module, as seen by an [`ImportPolicy`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ImportRequest<'a> {
+    /// The specifier as written in the `import` statement (e.g. `"./math.js"`).
+    pub specifier: &'a str,
+    /// The path of the module containing the `import` statement.
+    pub importer: &'a str,
+    /// The depth of the imported module in the import graph.
+    /// Modules imported directly by a handler have a depth of 1.
+    pub depth: usize,
+    /// The number of distinct modules that have been resolved so far by this sandbox.
+    pub module_count: usize,
+}
+
+/// A policy deciding which modules guest code is allowed to import.
+///
+/// The policy is evaluated by the host every time the guest asks to resolve a
+/// module, before the resolution proceeds. Returning an error fails the import
+/// in the guest with the given error.
+///
+/// Closures with the signature `Fn(&ImportRequest) -> Result<()>` implement
+/// this trait.
+///
+/// See [`ImportRules`] for a ready-made policy covering the common cases.
+pub trait ImportPolicy: Send + Sync {
+    /// Check whether the import described by `request` is allowed.
+    /// This is called before the specifier is resolved.
+    fn check(&self, request: &ImportRequest<'_>) -> Result<()>;
+
+    /// Check whether the import described by `request` is allowed now that it
+    /// has been resolved to `resolved_path`.
+    ///
+    /// `first_import` is `true` if `resolved_path` has not been resolved by this
+    /// sandbox before. The default implementation allows every import.
+    fn check_resolved(
+        &self,
+        request: &ImportRequest<'_>,
+        resolved_path: &str,
+        first_import: bool,
+    ) -> Result<()> {
+        let _ = (request, resolved_path, first_import);
+        Ok(())
+    }
+}
+
+impl<F> ImportPolicy for F
+where
+    F: Fn(&ImportRequest<'_>) -> Result<()> + Send + Sync,
+{
+    fn check(&self, request: &ImportRequest<'_>) -> Result<()> {
+        self(request)
+    }
+}
+
+/// A configurable [`ImportPolicy`] that allows or denies imports by specifier
+/// pattern and limits the size of the import graph.
+///
+/// Patterns are matched against the import specifier as written in the
+/// `import` statement, and may contain `*` wildcards matching any sequence of
+/// characters (e.g. `"./lib/*"`, `"*.mjs"`).
+///
+/// A specifier matching any deny pattern is rejected. If at least one allow
+/// pattern has been configured, a specifier must also match one of them.
+///
+/// # Example
+///
+/// ```
+/// use hyperlight_js::{ImportRules, ModuleLoaderOptions};
+///
+/// let rules = ImportRules::new()
+///     .allow("./lib/*")
+///     .deny("./lib/internal/*")
+///     .max_depth(3)
+///     .max_modules(50);
+///
+/// let options = ModuleLoaderOptions::new().with_import_policy(rules);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ImportRules {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    max_depth: Option<usize>,
+    max_modules: Option<usize>,
+}
+
+impl ImportRules {
+    /// Create a new set of rules that allows every import.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow imports whose specifier matches `pattern`.
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    /// Deny imports whose specifier matches `pattern`.
+    pub fn deny(mut self, pattern: impl Into<String>) -> Self {
+        self.deny.push(pattern.into());
+        self
+    }
+
+    /// Limit the depth of the import graph.
+    /// Modules imported directly by a handler have a depth of 1.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limit the number of distinct modules that can be imported.
+    pub fn max_modules(mut self, max_modules: usize) -> Self {
+        self.max_modules = Some(max_modules);
+        self
+    }
+}
+
+impl ImportPolicy for ImportRules {
+    fn check(&self, request: &ImportRequest<'_>) -> Result<()> {
+        let specifier = request.specifier;
+        if let Some(pattern) = self.deny.iter().find(|p| wildcard_match(p, specifier)) {
+            return Err(new_error!(
+                "Import of '{}' is denied by pattern '{}'",
+                specifier,
+                pattern
+            ));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| wildcard_match(p, specifier)) {
+            return Err(new_error!(
+                "Import of '{}' is not allowed by the import policy",
+                specifier
+            ));
+        }
+        if let Some(max_depth) = self.max_depth
+            && request.depth > max_depth
+        {
+            return Err(new_error!(
+                "Import of '{}' exceeds the maximum import depth of {}",
+                specifier,
+                max_depth
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_resolved(
+        &self,
+        request: &ImportRequest<'_>,
+        _resolved_path: &str,
+        first_import: bool,
+    ) -> Result<()> {
+        if let Some(max_modules) = self.max_modules
+            && first_import
+            && request.module_count >= max_modules
+        {
+            return Err(new_error!(
+                "Import of '{}' exceeds the maximum number of modules ({})",
+                request.specifier,
+                max_modules
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Match `text` against `pattern`, where `*` in the pattern matches any
+/// (possibly empty) sequence of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one element
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let Some(last) = parts.pop() else {
+        // no wildcard in the pattern
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// A thread-safe cache of module resolutions and sources that can be shared
+/// by the module loaders of many sandboxes.
+///
+/// Without a cache every sandbox load re-runs the resolver and re-reads the
+/// source of every imported module. When many sandboxes with the same
+/// dependency tree are loaded, sharing a cache through
+/// [`ModuleLoaderOptions::with_cache`] avoids redoing that work.
+///
+/// Resolutions are cached keyed by the directory of the importing module and
+/// the import specifier, and sources are cached keyed by the resolved path.
+/// Clones share the same underlying storage.
+///
+/// The cache does not observe changes to the file system, so only share a
+/// cache between loaders using the same file system contents, and call
+/// [`invalidate`](Self::invalidate) or [`clear`](Self::clear) when modules
+/// change. Import policies and integrity checks are still evaluated for
+/// cached modules.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{FileSystemMemory, ModuleCache, ModuleLoaderOptions, SandboxBuilder};
+///
+/// let fs = FileSystemMemory::new();
+/// fs.insert("math.js", "export const add = (a, b) => a + b;");
+///
+/// let cache = ModuleCache::new();
+/// for _ in 0..10 {
+///     let options = ModuleLoaderOptions::new().with_cache(cache.clone());
+///     let sandbox = SandboxBuilder::new()
+///         .build()
+///         .unwrap()
+///         .set_module_loader_with_options(fs.clone(), options)
+///         .unwrap()
+///         .load_runtime()
+///         .unwrap();
+///     // ...
+/// }
+///
+/// // math.js changed, make sure it is read again
+/// fs.insert("math.js", "export const add = (a, b) => b + a;");
+/// cache.invalidate("math.js");
+/// ```
+#[derive(Clone, Default)]
+pub struct ModuleCache {
+    inner: Arc<RwLock<ModuleCacheInner>>,
+}
+
+#[derive(Default)]
+struct ModuleCacheInner {
+    // (base directory, specifier) -> resolved path
+    resolutions: HashMap<(String, String), String>,
+    // normalized resolved path -> source
+    sources: HashMap<String, Arc<str>>,
+}
+
+impl ModuleCache {
+    /// Create a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the module at `path` from the cache, along with every cached
+    /// resolution to it.
+    pub fn invalidate(&self, path: impl AsRef<str>) {
+        let key = normalize_key(path.as_ref());
+        let mut inner = self.write();
+        inner.sources.remove(key.as_ref());
+        inner
+            .resolutions
+            .retain(|_, resolved| normalize_key(resolved) != key);
+    }
+
+    /// Remove every resolution and source from the cache.
+    pub fn clear(&self) {
+        let mut inner = self.write();
+        inner.resolutions.clear();
+        inner.sources.clear();
+    }
+
+    /// Returns the number of module sources in the cache.
+    pub fn len(&self) -> usize {
+        self.read_lock().sources.len()
+    }
+
+    /// Returns `true` if the cache holds no resolutions nor sources.
+    pub fn is_empty(&self) -> bool {
+        let inner = self.read_lock();
+        inner.sources.is_empty() && inner.resolutions.is_empty()
+    }
+
+    fn get_resolution(&self, base: &str, specifier: &str) -> Option<String> {
+        self.read_lock()
+            .resolutions
+            .get(&(base.to_string(), specifier.to_string()))
+            .cloned()
+    }
+
+    fn insert_resolution(&self, base: &str, specifier: &str, path: &str) {
+        self.write()
+            .resolutions
+            .insert((base.to_string(), specifier.to_string()), path.to_string());
+    }
+
+    fn get_source(&self, path: &str) -> Option<Arc<str>> {
+        self.read_lock()
+            .sources
+            .get(normalize_key(path).as_ref())
+            .cloned()
+    }
+
+    fn insert_source(&self, path: &str, source: &str) {
+        self.write()
+            .sources
+            .insert(normalize_key(path).into_owned(), source.into());
+    }
+
+    // A panic while holding the lock cannot leave the maps in an inconsistent
+    // state, so it is safe to recover from poisoning.
+    fn read_lock(&self) -> std::sync::RwLockReadGuard<'_, ModuleCacheInner> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, ModuleCacheInner> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Options for the module loader installed with
+/// [`ProtoJSSandbox::set_module_loader_with_options`](crate::ProtoJSSandbox::set_module_loader_with_options).
+#[derive(Clone, Default)]
+pub struct ModuleLoaderOptions {
+    import_policy: Option<Arc<dyn ImportPolicy>>,
+    // Expected SHA-256 digest of module sources, keyed by normalized module path.
+    integrity: HashMap<String, [u8; 32]>,
+    integrity_required: bool,
+    cache: Option<ModuleCache>,
+    prebundle: bool,
+}
+
+impl ModuleLoaderOptions {
+    /// Create a new set of options with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy deciding which modules guest code is allowed to import.
+    pub fn with_import_policy(mut self, policy: impl ImportPolicy + 'static) -> Self {
+        self.import_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Use `cache` to store module resolutions and sources.
+    /// See [`ModuleCache`] for details.
+    pub fn with_cache(mut self, cache: ModuleCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Resolve and load the modules imported by each handler when it is added
+    /// to a `JSSandbox`, and send them to the guest along with the handler.
+    ///
+    /// Without pre-bundling every `import` costs the guest two calls to the host
+    /// while the handler is being loaded, which dominates
+    /// `JSSandbox::get_loaded_sandbox` for deep dependency trees. Import policies
+    /// and integrity checks apply to pre-bundled modules as usual.
+    ///
+    /// The host finds the static `import` and `export ... from` declarations of
+    /// each module without fully parsing it. Modules it does not find (such as
+    /// the targets of dynamic `import()`s) are still resolved and loaded on
+    /// demand, and so are modules that fail to resolve or load when bundling, so
+    /// the guest reports the same errors either way.
+    ///
+    /// Disabled by default.
+    pub fn with_prebundling(mut self, enabled: bool) -> Self {
+        self.prebundle = enabled;
+        self
+    }
+
+    /// Associate the expected SHA-256 digest of its source with the module at `path`.
+    ///
+    /// `integrity` uses the [Subresource Integrity](https://developer.mozilla.org/en-US/docs/Web/Security/Subresource_Integrity)
+    /// format, i.e. `sha256-` followed by the base64 encoded digest, as produced by e.g.
+    /// `echo "sha256-$(openssl dgst -sha256 -binary math.js | base64)"`.
+    ///
+    /// `path` is the path of the module in the file system passed to the loader
+    /// (e.g. `"math.js"` or `"lib/util.js"`), leading `./` and `/` are ignored.
+    ///
+    /// The host verifies the digest every time the module is loaded, and fails the
+    /// import if the source does not match.
+    ///
+    /// Returns an error if `integrity` is not a valid SHA-256 integrity string.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::ModuleLoaderOptions;
+    ///
+    /// let options = ModuleLoaderOptions::new()
+    ///     .with_integrity("math.js", "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=")?
+    ///     .with_integrity_required(true);
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    pub fn with_integrity(mut self, path: impl AsRef<str>, integrity: &str) -> Result<Self> {
+        let digest = integrity
+            .strip_prefix("sha256-")
+            .and_then(|digest| BASE64.decode(digest).ok())
+            .and_then(|digest| <[u8; 32]>::try_from(digest).ok())
+            .ok_or_else(|| {
+                new_error!(
+                    "Invalid integrity '{}' for module '{}', expected 'sha256-<base64 digest>'",
+                    integrity,
+                    path.as_ref()
+                )
+            })?;
+        self.integrity
+            .insert(normalize_key(path.as_ref()).into_owned(), digest);
+        Ok(self)
+    }
+
+    /// Require every module to have an expected digest registered with
+    /// [`with_integrity`](Self::with_integrity).
+    /// When enabled, loading a module without a registered digest fails.
+    pub fn with_integrity_required(mut self, required: bool) -> Self {
+        self.integrity_required = required;
+        self
+    }
+
+    pub(crate) fn prebundle(&self) -> bool {
+        self.prebundle
+    }
+
+    fn verify_integrity(&self, path: &str, source: &str) -> Result<()> {
+        let Some(expected) = self.integrity.get(normalize_key(path).as_ref()) else {
+            if self.integrity_required {
+                return Err(new_error!(
+                    "Module '{}' has no integrity digest registered",
+                    path
+                ));
+            }
+            return Ok(());
+        };
+
+        let actual: [u8; 32] = Sha256::digest(source.as_bytes()).into();
+        if &actual != expected {
+            return Err(new_error!(
+                "Integrity check failed for module '{}': expected sha256-{}, got sha256-{}",
+                path,
+                BASE64.encode(expected),
+                BASE64.encode(actual)
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Debug for ModuleLoaderOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleLoaderOptions")
+            .field("import_policy", &self.import_policy.is_some())
+            .field("integrity", &self.integrity.keys().collect::<Vec<_>>())
+            .field("integrity_required", &self.integrity_required)
+            .field("cache", &self.cache.is_some())
+            .field("prebundle", &self.prebundle)
+            .finish()
+    }
+}
+
+/// Implementation of the `ResolveModule` and `LoadModule` host functions.
+pub(crate) struct ModuleLoader<Fs: FileSystem> {
+    resolver: ResolverGeneric<Fs>,
+    file_system: Fs,
+    options: ModuleLoaderOptions,
+    // Import depth of every module resolved so far, keyed by its resolved path.
+    depths: Mutex<HashMap<String, usize>>,
+}
+
+impl<Fs: FileSystem + Clone> ModuleLoader<Fs> {
+    pub(crate) fn new(file_system: Fs, options: ModuleLoaderOptions) -> Self {
+        let resolver = ResolverGeneric::new_with_file_system(
+            file_system.clone(),
+            ResolveOptions {
+                #[cfg(not(feature = "typescript"))]
+                extensions: vec![".js".into(), ".mjs".into()],
+                // TypeScript modules are imported either without extension, or
+                // (following the TypeScript convention) with a `.js` one.
+                #[cfg(feature = "typescript")]
+                extensions: vec![".js".into(), ".mjs".into(), ".ts".into(), ".mts".into()],
+                #[cfg(feature = "typescript")]
+                extension_alias: vec![
+                    (".js".into(), vec![".js".into(), ".ts".into()]),
+                    (".mjs".into(), vec![".mjs".into(), ".mts".into()]),
+                ],
+                condition_names: vec!["import".into(), "module".into()],
+                ..Default::default()
+            },
+        );
+
+        Self {
+            resolver,
+            file_system,
+            options,
+            depths: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve `specifier` as imported from the module at path `importer`.
+    pub(crate) fn resolve(&self, importer: &str, specifier: &str) -> Result<String> {
+        tracing::debug!(
+            importer = %importer,
+            specifier = %specifier,
+            "Resolving module"
+        );
+
+        // quickjs uses the module path as the base for relative imports
+        // but oxc_resolver expects the directory as the base
+        let (base, _) = importer.rsplit_once('/').unwrap_or((".", ""));
+
+        let mut depths = self
+            .depths
+            .lock()
+            .map_err(|e| new_error!("Error locking module depths: {}", e))?;
+
+        let request = ImportRequest {
+            specifier,
+            importer,
+            // modules that haven't been resolved through the loader are handlers
+            depth: depths.get(importer).map_or(1, |depth| depth + 1),
+            module_count: depths.len(),
+        };
+
+        if let Some(policy) = &self.options.import_policy {
+            policy.check(&request)?;
+        }
+
+        let cache = self.options.cache.as_ref();
+        let path = match cache.and_then(|cache| cache.get_resolution(base, specifier)) {
+            Some(path) => path,
+            None => {
+                let resolved = self.resolver.resolve(base, specifier).map_err(|e| {
+                    new_error!(
+                        "Failed to resolve module '{}' from '{}': {:?}",
+                        specifier,
+                        base,
+                        e
+                    )
+                })?;
+                let path = resolved.path().to_string_lossy().to_string();
+                if let Some(cache) = cache {
+                    cache.insert_resolution(base, specifier, &path);
+                }
+                path
+            }
+        };
+
+        // the guest uses forward slashes for the module paths
+        let key = path.replace('\\', "/");
+        let first_import = !depths.contains_key(&key);
+        if let Some(policy) = &self.options.import_policy {
+            policy.check_resolved(&request, &path, first_import)?;
+        }
+        depths
+            .entry(key)
+            .and_modify(|depth| *depth = (*depth).min(request.depth))
+            .or_insert(request.depth);
+
+        Ok(path)
+    }
+
+    /// Load the source of the module at `path`.
+    ///
+    /// TypeScript modules are transpiled to JavaScript when the `typescript`
+    /// feature is enabled. Integrity is verified against the TypeScript source.
+    pub(crate) fn load(&self, path: &str) -> Result<String> {
+        tracing::debug!(path = %path, "Loading module");
+        let cache = self.options.cache.as_ref();
+        let source = match cache.and_then(|cache| cache.get_source(path)) {
+            Some(source) => {
+                self.options.verify_integrity(path, &source)?;
+                source.to_string()
+            }
+            None => {
+                let source = self
+                    .file_system
+                    .read_to_string(Path::new(path))
+                    .map_err(|e| new_error!("Failed to read module '{}': {}", path, e))?;
+
+                self.options.verify_integrity(path, &source)?;
+
+                if let Some(cache) = cache {
+                    cache.insert_source(path, &source);
+                }
+                source
+            }
+        };
+
+        #[cfg(feature = "typescript")]
+        if crate::typescript::is_typescript_path(path) {
+            return crate::typescript::transpile(&source, path);
+        }
+
+        Ok(source)
+    }
+}
+
+/// The modules imported (directly or transitively) by a handler, resolved and
+/// loaded ahead of time and sent to the guest with the handler.
+// The serialization in here has to match the deserialization of
+// PreloadedModules in src/hyperlight-js-runtime/src/lib.rs
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct ModuleBundle {
+    // (importer, specifier, resolved path)
+    resolutions: Vec<(String, String, String)>,
+    // (resolved path, source)
+    sources: Vec<(String, String)>,
+}
+
+/// Builds the [`ModuleBundle`] for a handler, see [`ModuleLoaderOptions::with_prebundling`].
+pub(crate) trait ModuleBundler: Send + Sync {
+    /// Bundle the modules imported by the handler module at `handler_path`.
+    fn bundle(&self, handler_path: &str, source: &str) -> ModuleBundle;
+}
+
+impl<Fs: FileSystem + Clone> ModuleBundler for ModuleLoader<Fs> {
+    fn bundle(&self, handler_path: &str, source: &str) -> ModuleBundle {
+        let mut bundle = ModuleBundle::default();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([(handler_path.to_string(), source.to_string())]);
+
+        while let Some((importer, source)) = queue.pop_front() {
+            for specifier in scan_imports(&source) {
+                // Anything that fails here is left for the guest to request on
+                // demand, so that it gets the same error it would without bundling.
+                let Ok(path) = self.resolve(&importer, &specifier) else {
+                    continue;
+                };
+                // The guest refers to modules using forward slashes only
+                let path = path.replace('\\', "/");
+                bundle
+                    .resolutions
+                    .push((importer.clone(), specifier, path.clone()));
+
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+                if let Ok(source) = self.load(&path) {
+                    bundle.sources.push((path.clone(), source.clone()));
+                    queue.push_back((path, source));
+                }
+            }
+        }
+
+        bundle
+    }
+}
+
+/// The path of the module the guest declares for a handler.
+// This has to match make_handler_path in src/hyperlight-js-runtime/src/lib.rs
+pub(crate) fn handler_module_path(function_name: &str, handler_dir: &str) -> String {
+    let handler_dir = if handler_dir.is_empty() {
+        "."
+    } else {
+        handler_dir
+    };
+
+    let function_name = if function_name.is_empty() {
+        "handler"
+    } else {
+        function_name
+    };
+
+    let function_name = function_name.replace('\\', "/");
+    let mut handler_path = handler_dir.replace('\\', "/");
+    if !handler_path.ends_with('/') {
+        handler_path.push('/');
+    }
+    handler_path.push_str(&function_name);
+
+    if !handler_path.ends_with(".js") && !handler_path.ends_with(".mjs") {
+        handler_path.push_str(".js");
+    }
+
+    handler_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystemMemory;
+
+    fn loader(policy: impl ImportPolicy + 'static) -> ModuleLoader<FileSystemMemory> {
+        let fs = FileSystemMemory::new();
+        fs.insert("a.js", "import './b.js';");
+        fs.insert("b.js", "import './c.js';");
+        fs.insert("c.js", "");
+        fs.insert("lib/internal/secret.js", "");
+        ModuleLoader::new(fs, ModuleLoaderOptions::new().with_import_policy(policy))
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("./lib/*", "./lib/math.js"));
+        assert!(wildcard_match("*.mjs", "./a/b.mjs"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("./a*c*e.js", "./abcde.js"));
+        assert!(wildcard_match("exact.js", "exact.js"));
+        assert!(!wildcard_match("exact.js", "exact.js2"));
+        assert!(!wildcard_match("./lib/*", "./other/math.js"));
+        assert!(!wildcard_match("*.mjs", "./a/b.js"));
+    }
+
+    #[test]
+    fn test_import_rules_allow_and_deny() {
+        let loader = loader(ImportRules::new().allow("./*").deny("./lib/internal/*"));
+
+        assert!(loader.resolve("/handler.js", "./a.js").is_ok());
+        assert!(loader.resolve("/handler.js", "a.js").is_err());
+        let err = loader
+            .resolve("/handler.js", "./lib/internal/secret.js")
+            .unwrap_err();
+        assert!(err.to_string().contains("denied"), "{err}");
+    }
+
+    #[test]
+    fn test_import_rules_max_depth() {
+        let loader = loader(ImportRules::new().max_depth(2));
+
+        let a = loader.resolve("/handler.js", "./a.js").unwrap();
+        let b = loader.resolve(&a, "./b.js").unwrap();
+        let err = loader.resolve(&b, "./c.js").unwrap_err();
+        assert!(err.to_string().contains("maximum import depth"), "{err}");
+    }
+
+    #[test]
+    fn test_import_rules_max_modules() {
+        let loader = loader(ImportRules::new().max_modules(2));
+
+        loader.resolve("/handler.js", "./a.js").unwrap();
+        loader.resolve("/handler.js", "./b.js").unwrap();
+        // re-importing an already resolved module is fine
+        loader.resolve("/other.js", "./a.js").unwrap();
+        let err = loader.resolve("/handler.js", "./c.js").unwrap_err();
+        assert!(
+            err.to_string().contains("maximum number of modules"),
+            "{err}"
+        );
+    }
+
+    fn integrity_of(source: &str) -> String {
+        format!(
+            "sha256-{}",
+            BASE64.encode(Sha256::digest(source.as_bytes()))
+        )
+    }
+
+    #[test]
+    fn test_integrity() {
+        let fs = FileSystemMemory::new();
+        fs.insert("good.js", "export const x = 1;");
+        fs.insert("bad.js", "export const x = 'tampered';");
+        fs.insert("unchecked.js", "");
+
+        let options = ModuleLoaderOptions::new()
+            .with_integrity("./good.js", &integrity_of("export const x = 1;"))
+            .unwrap()
+            .with_integrity("bad.js", &integrity_of("export const x = 2;"))
+            .unwrap();
+        let loader = ModuleLoader::new(fs.clone(), options.clone());
+
+        assert!(loader.load("/good.js").is_ok());
+        assert!(loader.load("unchecked.js").is_ok());
+        let err = loader.load("bad.js").unwrap_err();
+        assert!(err.to_string().contains("Integrity check failed"), "{err}");
+
+        let loader = ModuleLoader::new(fs, options.with_integrity_required(true));
+        assert!(loader.load("good.js").is_ok());
+        let err = loader.load("unchecked.js").unwrap_err();
+        assert!(err.to_string().contains("no integrity digest"), "{err}");
+    }
+
+    #[test]
+    fn test_invalid_integrity() {
+        assert!(ModuleLoaderOptions::new()
+            .with_integrity("a.js", "sha384-abc")
+            .is_err());
+        assert!(ModuleLoaderOptions::new()
+            .with_integrity("a.js", "sha256-not base64")
+            .is_err());
+        assert!(ModuleLoaderOptions::new()
+            .with_integrity("a.js", "sha256-AAAA")
+            .is_err());
+    }
+
+    #[test]
+    fn test_cache_is_shared_and_invalidated() {
+        let fs = FileSystemMemory::new();
+        fs.insert("a.js", "export const v = 1;");
+
+        let cache = ModuleCache::new();
+        let options = ModuleLoaderOptions::new().with_cache(cache.clone());
+        let first = ModuleLoader::new(fs.clone(), options.clone());
+        let second = ModuleLoader::new(fs.clone(), options);
+
+        let path = first.resolve("/handler.js", "./a.js").unwrap();
+        assert_eq!(first.load(&path).unwrap(), "export const v = 1;");
+        assert_eq!(cache.len(), 1);
+
+        // The second loader is served from the cache, even after the module
+        // has been removed from the file system.
+        fs.remove("a.js");
+        let cached = second.resolve("/handler.js", "./a.js").unwrap();
+        assert_eq!(cached, path);
+        assert_eq!(second.load(&cached).unwrap(), "export const v = 1;");
+
+        fs.insert("a.js", "export const v = 2;");
+        cache.invalidate(&path);
+        assert!(cache.is_empty());
+        assert_eq!(second.load(&path).unwrap(), "export const v = 2;");
+    }
+
+    #[test]
+    fn test_bundle_follows_static_imports() {
+        let fs = FileSystemMemory::new();
+        fs.insert("a.js", "import { b } from './b.js'; export const a = b;");
+        fs.insert("b.js", "export * from './c.js'; export const b = 1;");
+        fs.insert("c.js", "import './a.js'; export const c = 1;");
+        let loader = ModuleLoader::new(fs, ModuleLoaderOptions::new());
+
+        let handler_path = handler_module_path("handler", "");
+        assert_eq!(handler_path, "./handler.js");
+        let source = "import { a } from './a.js'; import './missing.js';";
+        let bundle = loader.bundle(&handler_path, source);
+
+        let sources: Vec<_> = bundle.sources.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(sources, ["a.js", "b.js", "c.js"]);
+        // c.js imports a.js again, which is resolved but not loaded twice
+        assert_eq!(bundle.resolutions.len(), 4);
+        assert_eq!(
+            bundle.resolutions[0],
+            ("./handler.js".into(), "./a.js".into(), "a.js".into())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "typescript")]
+    fn test_typescript_modules_are_transpiled() {
+        let fs = FileSystemMemory::new();
+        fs.insert(
+            "math.ts",
+            "export const add = (a: number, b: number): number => a + b;",
+        );
+        let loader = ModuleLoader::new(fs, ModuleLoaderOptions::new());
+
+        // Both the TypeScript `.js` convention and extensionless imports work
+        let path = loader.resolve("./handler.ts", "./math.js").unwrap();
+        assert_eq!(path, "math.ts");
+        assert_eq!(loader.resolve("./handler.ts", "./math").unwrap(), path);
+
+        let source = loader.load(&path).unwrap();
+        assert!(source.contains("export const add = (a, b) => a + b"));
+    }
+
+    #[test]
+    fn test_closure_policy() {
+        let loader = loader(|request: &ImportRequest<'_>| {
+            if request.specifier.ends_with("c.js") {
+                Err(new_error!("no c"))
+            } else {
+                Ok(())
+            }
+        });
+
+        assert!(loader.resolve("/handler.js", "./a.js").is_ok());
+        assert!(loader.resolve("/handler.js", "./c.js").is_err());
+    }
+}
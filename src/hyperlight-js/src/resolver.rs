@@ -18,11 +18,38 @@ limitations under the License.
 //! This module provides the core abstractions and implementations for loading
 //! JavaScript modules into the sandbox environment.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 pub use oxc_resolver::{FileMetadata, FileSystem, ResolveError};
 use phf::Map;
 
+/// Normalize a path for consistent lookups.
+fn normalize_path(path: &Path) -> Option<Cow<'_, str>> {
+    path.to_str().map(normalize_key)
+}
+
+/// Normalize a module key, stripping leading `./` and `/` and using `/` as
+/// the separator.
+pub(crate) fn normalize_key(s: &str) -> Cow<'_, str> {
+    if s.contains('\\') || s.starts_with("./") || s.starts_with('/') {
+        Cow::Owned(
+            s.replace('\\', "/")
+                .trim_start_matches("./")
+                .trim_start_matches('/')
+                .to_string(),
+        )
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+fn invalid_path_error() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid UTF-8 in path")
+}
+
 /// File system implementation that uses embedded modules compiled into the binary.
 ///
 /// This implementation stores all module contents in a compile-time perfect hash map,
@@ -54,22 +81,6 @@ impl FileSystemEmbedded {
         Self { modules }
     }
 
-    /// Normalize a path for consistent lookups.
-    fn normalize_path<'a>(&self, path: &'a Path) -> Option<std::borrow::Cow<'a, str>> {
-        let s = path.to_str()?;
-
-        if s.contains('\\') || s.starts_with("./") || s.starts_with('/') {
-            Some(std::borrow::Cow::Owned(
-                s.replace('\\', "/")
-                    .trim_start_matches("./")
-                    .trim_start_matches('/')
-                    .to_string(),
-            ))
-        } else {
-            Some(std::borrow::Cow::Borrowed(s))
-        }
-    }
-
     /// Check if a normalized path represents a directory by seeing if any
     /// embedded modules have this path as a prefix.
     fn is_directory(&self, normalized: &str) -> bool {
@@ -92,9 +103,7 @@ impl FileSystem for FileSystemEmbedded {
     }
 
     fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
-        let normalized = self.normalize_path(path).ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid UTF-8 in path")
-        })?;
+        let normalized = normalize_path(path).ok_or_else(invalid_path_error)?;
 
         self.modules
             .get(&normalized)
@@ -108,9 +117,7 @@ impl FileSystem for FileSystemEmbedded {
     }
 
     fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
-        let normalized = self.normalize_path(path).ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid UTF-8 in path")
-        })?;
+        let normalized = normalize_path(path).ok_or_else(invalid_path_error)?;
 
         let is_file = self.modules.contains_key(normalized.as_ref());
         let is_dir = self.is_directory(normalized.as_ref());
@@ -140,10 +147,261 @@ impl FileSystem for FileSystemEmbedded {
     }
 
     fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
-        self.normalize_path(path)
+        normalize_path(path)
+            .ok_or_else(invalid_path_error)
+            .map(|v| PathBuf::from(v.into_owned()))
+    }
+}
+
+/// File system implementation that keeps module sources in memory and can be
+/// modified at runtime.
+///
+/// Unlike [`FileSystemEmbedded`], modules can be inserted and removed after the
+/// file system has been created, which makes it suitable for hosts that fetch
+/// module sources from a database or over the network. Clones share the same
+/// underlying storage, so a clone handed to
+/// [`ProtoJSSandbox::set_module_loader`](crate::ProtoJSSandbox::set_module_loader)
+/// observes modules inserted through the original afterwards.
+///
+/// Note that modules are only read by the guest while a handler (or a module it
+/// imports) is being loaded; changing a module does not affect handlers that
+/// have already been loaded into a sandbox.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{FileSystemMemory, SandboxBuilder};
+///
+/// let fs = FileSystemMemory::new();
+/// fs.insert("math.js", "export function add(a, b) { return a + b; }");
+///
+/// let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// let sandbox = proto_js_sandbox
+///     .set_module_loader(fs.clone())
+///     .unwrap()
+///     .load_runtime()
+///     .unwrap();
+///
+/// // Modules can still be added once the loader has been installed.
+/// fs.insert("strings.js", "export const hello = 'hello';");
+/// ```
+#[derive(Clone, Default)]
+pub struct FileSystemMemory {
+    modules: Arc<RwLock<HashMap<String, Arc<str>>>>,
+}
+
+impl FileSystemMemory {
+    /// Create a new, empty in-memory file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert (or replace) the module at `path` with the given source.
+    ///
+    /// Returns the previous source of the module, if there was one.
+    pub fn insert(&self, path: impl AsRef<str>, source: impl Into<Arc<str>>) -> Option<Arc<str>> {
+        let key = normalize_key(path.as_ref());
+        self.write().insert(key.into_owned(), source.into())
+    }
+
+    /// Remove the module at `path`, returning its source if it was present.
+    pub fn remove(&self, path: impl AsRef<str>) -> Option<Arc<str>> {
+        let key = normalize_key(path.as_ref());
+        self.write().remove(key.as_ref())
+    }
+
+    /// Returns `true` if a module exists at `path`.
+    pub fn contains(&self, path: impl AsRef<str>) -> bool {
+        let key = normalize_key(path.as_ref());
+        self.read_lock().contains_key(key.as_ref())
+    }
+
+    /// Remove all modules.
+    pub fn clear(&self) {
+        self.write().clear();
+    }
+
+    /// Returns the number of modules.
+    pub fn len(&self) -> usize {
+        self.read_lock().len()
+    }
+
+    /// Returns `true` if there are no modules.
+    pub fn is_empty(&self) -> bool {
+        self.read_lock().is_empty()
+    }
+
+    // A panic while holding the lock cannot leave the map in an inconsistent
+    // state, so it is safe to recover from poisoning.
+    fn read_lock(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, Arc<str>>> {
+        self.modules.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, Arc<str>>> {
+        self.modules.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FileSystem for FileSystemMemory {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.read_to_string(path).map(|s| s.into_bytes())
+    }
+
+    fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        let normalized = normalize_path(path).ok_or_else(invalid_path_error)?;
+
+        self.read_lock()
+            .get(normalized.as_ref())
+            .map(|content| content.to_string())
             .ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid UTF-8 in path")
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Module '{}' not found", normalized),
+                )
             })
+    }
+
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        let normalized = normalize_path(path).ok_or_else(invalid_path_error)?;
+
+        let modules = self.read_lock();
+        let is_file = modules.contains_key(normalized.as_ref());
+        let is_dir = if normalized.is_empty() {
+            !modules.is_empty()
+        } else {
+            let prefix = format!("{}/", normalized);
+            modules.keys().any(|key| key.starts_with(&prefix))
+        };
+
+        if !is_file && !is_dir {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Path '{}' not found", normalized),
+            ));
+        }
+
+        Ok(FileMetadata::new(
+            is_file, is_dir, false, /* is_symlink */
+        ))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        self.metadata(path)
+    }
+
+    fn read_link(&self, _path: &Path) -> Result<PathBuf, ResolveError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "symlinks are not supported in in-memory file system",
+        )
+        .into())
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        normalize_path(path)
+            .ok_or_else(invalid_path_error)
+            .map(|v| PathBuf::from(v.into_owned()))
+    }
+}
+
+/// File system implementation that reads modules from a directory of the host.
+///
+/// Module paths are resolved against the directory and cannot escape it:
+/// paths with a `..` component are rejected. Modules are read when the guest
+/// imports them, so changes to the files are picked up by the handlers loaded
+/// afterwards. Symbolic links inside the directory are followed.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{FileSystemDirectory, SandboxBuilder};
+///
+/// let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// let sandbox = proto_js_sandbox
+///     .set_module_loader(FileSystemDirectory::new("handlers/lib"))
+///     .unwrap()
+///     .load_runtime()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FileSystemDirectory {
+    root: Arc<Path>,
+}
+
+impl FileSystemDirectory {
+    /// Create a file system reading the modules in `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: Arc::from(root.as_ref()),
+        }
+    }
+
+    /// The directory the modules are read from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path on the host of the module at `path`.
+    fn host_path(&self, path: &Path) -> std::io::Result<PathBuf> {
+        let normalized = normalize_path(path).ok_or_else(invalid_path_error)?;
+        let mut host_path = self.root.to_path_buf();
+        for component in normalized.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Path '{}' is outside of the module directory", normalized),
+                    ));
+                }
+                component => host_path.push(component),
+            }
+        }
+        Ok(host_path)
+    }
+}
+
+impl FileSystem for FileSystemDirectory {
+    fn new() -> Self {
+        Self::new(".")
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.host_path(path)?)
+    }
+
+    fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(self.host_path(path)?)
+    }
+
+    fn metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        let metadata = std::fs::metadata(self.host_path(path)?)?;
+        Ok(FileMetadata::new(
+            metadata.is_file(),
+            metadata.is_dir(),
+            false, /* is_symlink */
+        ))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> std::io::Result<FileMetadata> {
+        self.metadata(path)
+    }
+
+    fn read_link(&self, _path: &Path) -> Result<PathBuf, ResolveError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "symlinks are followed in the module directory",
+        )
+        .into())
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        normalize_path(path)
+            .ok_or_else(invalid_path_error)
             .map(|v| PathBuf::from(v.into_owned()))
     }
 }
@@ -277,4 +535,93 @@ mod tests {
         let result = fs.read_to_string(Path::new("missing.js"));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("lib/util.js"), "export const x = 1;").unwrap();
+        let fs = FileSystemDirectory::new(dir.path());
+
+        assert_eq!(
+            fs.read_to_string(Path::new("/lib/util.js")).unwrap(),
+            "export const x = 1;"
+        );
+        assert!(fs.metadata(Path::new("lib")).unwrap().is_dir());
+        assert!(fs.metadata(Path::new("./lib/util.js")).unwrap().is_file());
+        assert!(fs.metadata(Path::new("")).unwrap().is_dir());
+        assert!(fs.read_to_string(Path::new("lib/missing.js")).is_err());
+
+        // Modules are read when imported
+        std::fs::write(dir.path().join("lib/util.js"), "export const x = 2;").unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("lib/util.js")).unwrap(),
+            "export const x = 2;"
+        );
+    }
+
+    #[test]
+    fn test_directory_rejects_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("modules")).unwrap();
+        std::fs::write(dir.path().join("secret.js"), "export const key = 42;").unwrap();
+        let fs = FileSystemDirectory::new(dir.path().join("modules"));
+
+        let err = fs.read_to_string(Path::new("../secret.js")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(fs.metadata(Path::new("lib/../../secret.js")).is_err());
+    }
+
+    #[test]
+    fn test_memory_insert_and_remove() {
+        let fs = FileSystemMemory::new();
+        assert!(fs.is_empty());
+
+        fs.insert("./lib/util.js", "export const x = 1;");
+        assert_eq!(
+            fs.read_to_string(Path::new("/lib/util.js")).unwrap(),
+            "export const x = 1;"
+        );
+        assert!(fs.metadata(Path::new("lib")).unwrap().is_dir());
+        assert!(fs.metadata(Path::new("lib/util.js")).unwrap().is_file());
+
+        let previous = fs.insert("lib/util.js", "export const x = 2;");
+        assert_eq!(previous.as_deref(), Some("export const x = 1;"));
+        assert_eq!(fs.len(), 1);
+
+        assert!(fs.remove("lib/util.js").is_some());
+        assert!(fs.read_to_string(Path::new("lib/util.js")).is_err());
+        assert!(fs.metadata(Path::new("lib")).is_err());
+    }
+
+    #[test]
+    fn test_memory_clones_share_storage() {
+        let fs = FileSystemMemory::new();
+        let clone = fs.clone();
+
+        fs.insert("shared.js", "content");
+        assert!(clone.contains("shared.js"));
+
+        clone.clear();
+        assert!(fs.is_empty());
+    }
+
+    #[test]
+    fn test_memory_concurrent_inserts() {
+        let fs = FileSystemMemory::new();
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let fs = fs.clone();
+                std::thread::spawn(move || {
+                    fs.insert(format!("mod{i}.js"), format!("export const i = {i};"));
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert_eq!(fs.len(), 8);
+    }
 }
@@ -0,0 +1,208 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::sync::Arc;
+
+use hyperlight_host::func::{HostFunction, ParameterTuple, SupportedReturnType};
+use hyperlight_host::hypervisor::InterruptHandle;
+#[cfg(feature = "in-process")]
+use hyperlight_host::is_hypervisor_present;
+#[cfg(any(feature = "in-process", feature = "testing"))]
+use hyperlight_host::new_error;
+use hyperlight_host::sandbox::snapshot::Snapshot;
+use hyperlight_host::sandbox::SandboxConfiguration;
+use hyperlight_host::{GuestBinary, MultiUseSandbox, Result, UninitializedSandbox};
+
+#[cfg(any(feature = "in-process", feature = "testing"))]
+use super::in_process::{InProcessCheckpoint, InProcessSandbox, UninitializedInProcessSandbox};
+
+/// Where the JavaScript runtime of a sandbox runs, before it is loaded.
+#[allow(clippy::large_enum_variant)] // the micro VM is the common case, so it is not boxed
+pub(crate) enum UninitializedBackend {
+    /// In a Hyperlight micro VM.
+    Vm(UninitializedSandbox),
+    /// In the host process, with no isolation, see [`super::in_process`].
+    #[cfg(any(feature = "in-process", feature = "testing"))]
+    InProcess(UninitializedInProcessSandbox),
+}
+
+impl UninitializedBackend {
+    /// A micro VM running `guest_binary`, or with the `in-process` feature,
+    /// the runtime in the host process if there is no hypervisor.
+    pub(crate) fn new(
+        guest_binary: GuestBinary,
+        cfg: Option<SandboxConfiguration>,
+    ) -> Result<Self> {
+        #[cfg(feature = "in-process")]
+        if !is_hypervisor_present() {
+            tracing::warn!(
+                "No hypervisor found, running the JavaScript runtime in-process without isolation"
+            );
+            return Ok(Self::in_process());
+        }
+        Ok(Self::Vm(UninitializedSandbox::new(guest_binary, cfg)?))
+    }
+
+    /// The runtime in the host process, whether there is a hypervisor or not.
+    #[cfg(any(feature = "in-process", feature = "testing"))]
+    pub(crate) fn in_process() -> Self {
+        Self::InProcess(UninitializedInProcessSandbox::default())
+    }
+
+    pub(crate) fn register<Args: ParameterTuple, Output: SupportedReturnType>(
+        &mut self,
+        name: &str,
+        host_func: impl Into<HostFunction<Output, Args>>,
+    ) -> Result<()> {
+        match self {
+            Self::Vm(sandbox) => sandbox.register(name, host_func),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => {
+                sandbox.register(name, host_func);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn register_print(
+        &mut self,
+        print_func: impl Into<HostFunction<i32, (String,)>>,
+    ) -> Result<()> {
+        self.register("HostPrint", print_func)
+    }
+
+    pub(crate) fn evolve(self) -> Result<Backend> {
+        match self {
+            Self::Vm(sandbox) => Ok(Backend::Vm(sandbox.evolve()?)),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => Ok(Backend::InProcess(sandbox.evolve()?)),
+        }
+    }
+
+    pub(crate) fn is_isolated(&self) -> bool {
+        matches!(self, Self::Vm(_))
+    }
+}
+
+/// Where the JavaScript runtime of a sandbox runs, once it is loaded.
+#[allow(clippy::large_enum_variant)] // the micro VM is the common case, so it is not boxed
+pub(crate) enum Backend {
+    /// In a Hyperlight micro VM.
+    Vm(MultiUseSandbox),
+    /// In the host process, with no isolation, see [`super::in_process`].
+    #[cfg(any(feature = "in-process", feature = "testing"))]
+    InProcess(InProcessSandbox),
+}
+
+/// A state of the runtime the sandbox can be rewound to.
+#[derive(Clone)]
+pub(crate) enum Checkpoint {
+    Vm(Arc<Snapshot>),
+    #[cfg(any(feature = "in-process", feature = "testing"))]
+    InProcess(InProcessCheckpoint),
+}
+
+impl Backend {
+    pub(crate) fn call<Output: SupportedReturnType>(
+        &mut self,
+        func_name: &str,
+        args: impl ParameterTuple,
+    ) -> Result<Output> {
+        match self {
+            Self::Vm(sandbox) => sandbox.call(func_name, args),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => sandbox.call(func_name, args),
+        }
+    }
+
+    pub(crate) fn poisoned(&self) -> bool {
+        match self {
+            Self::Vm(sandbox) => sandbox.poisoned(),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => sandbox.poisoned(),
+        }
+    }
+
+    pub(crate) fn interrupt_handle(&self) -> Arc<dyn InterruptHandle> {
+        match self {
+            Self::Vm(sandbox) => sandbox.interrupt_handle(),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => sandbox.interrupt_handle(),
+        }
+    }
+
+    pub(crate) fn is_isolated(&self) -> bool {
+        matches!(self, Self::Vm(_))
+    }
+
+    /// Take a snapshot of the sandbox, which only sandboxes running in a
+    /// micro VM support.
+    pub(crate) fn snapshot(&mut self) -> Result<Arc<Snapshot>> {
+        match self {
+            Self::Vm(sandbox) => sandbox.snapshot(),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(_) => Err(new_error!(
+                "Snapshots are not supported by sandboxes running in-process"
+            )),
+        }
+    }
+
+    /// Restore a snapshot of the sandbox, which only sandboxes running in a
+    /// micro VM support.
+    pub(crate) fn restore(&mut self, snapshot: Arc<Snapshot>) -> Result<()> {
+        match self {
+            Self::Vm(sandbox) => sandbox.restore(snapshot),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(_) => Err(new_error!(
+                "Snapshots are not supported by sandboxes running in-process"
+            )),
+        }
+    }
+
+    /// Record the current state of the runtime, to rewind to it later.
+    pub(crate) fn checkpoint(&mut self) -> Result<Checkpoint> {
+        match self {
+            Self::Vm(sandbox) => Ok(Checkpoint::Vm(sandbox.snapshot()?)),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(sandbox) => Ok(Checkpoint::InProcess(sandbox.checkpoint()?)),
+        }
+    }
+
+    /// Rewind the runtime to `checkpoint`, recovering it if it is poisoned.
+    pub(crate) fn rewind(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        match (self, checkpoint) {
+            (Self::Vm(sandbox), Checkpoint::Vm(snapshot)) => sandbox.restore(snapshot.clone()),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            (Self::InProcess(sandbox), Checkpoint::InProcess(checkpoint)) => {
+                sandbox.rewind(checkpoint)
+            }
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            _ => Err(new_error!(
+                "The checkpoint was not taken from a sandbox of the same kind"
+            )),
+        }
+    }
+
+    #[cfg(feature = "crashdump")]
+    pub(crate) fn generate_crashdump(&self) -> Result<()> {
+        match self {
+            Self::Vm(sandbox) => sandbox.generate_crashdump(),
+            #[cfg(any(feature = "in-process", feature = "testing"))]
+            Self::InProcess(_) => Err(new_error!(
+                "Crash dumps are not supported by sandboxes running in-process"
+            )),
+        }
+    }
+}
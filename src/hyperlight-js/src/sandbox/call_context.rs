@@ -0,0 +1,142 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! The context of the handler invocation a host function is called from.
+//!
+//! Host functions run on the thread that called into the guest, so the
+//! context of the invocation is kept per thread for the duration of the call.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use hyperlight_host::{new_error, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CallContext>> = const { RefCell::new(None) };
+}
+
+/// Context of a handler invocation, like the tenant or request it serves,
+/// passed to
+/// [`LoadedJSSandbox::handle_event_with_context`](crate::LoadedJSSandbox::handle_event_with_context)
+/// and available to the host functions the handler calls through
+/// [`CallContext::current`].
+///
+/// The context is never exposed to the guest.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{CallContext, SandboxBuilder};
+///
+/// let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// proto_js_sandbox
+///     .register("db", "tenant", || {
+///         CallContext::current()
+///             .and_then(|context| context.get("tenant").cloned())
+///             .unwrap_or_default()
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallContext(Arc<Value>);
+
+impl CallContext {
+    /// A context holding `value`.
+    pub fn new(value: impl Into<Value>) -> Self {
+        Self(Arc::new(value.into()))
+    }
+
+    /// The context of the handler invocation running on the current thread,
+    /// if it was given one.
+    pub fn current() -> Option<Self> {
+        CURRENT.with_borrow(Clone::clone)
+    }
+
+    /// The value of the context.
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+
+    /// The field `key` of the context, if it is an object with that field.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Deserialize the context into a `T`.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        T::deserialize(self.value())
+            .map_err(|e| new_error!("Failed to deserialize the call context: {}", e))
+    }
+
+    /// Make `context` the context of the current thread until the returned
+    /// guard is dropped.
+    pub(crate) fn enter(context: Option<Self>) -> CallContextGuard {
+        CallContextGuard(CURRENT.replace(context))
+    }
+}
+
+impl From<Value> for CallContext {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Guard restoring the previous context of the thread when dropped.
+pub(crate) struct CallContextGuard(Option<CallContext>);
+
+impl Drop for CallContextGuard {
+    fn drop(&mut self) {
+        CURRENT.set(self.0.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_call_context() {
+        assert_eq!(CallContext::current(), None);
+
+        let guard = CallContext::enter(Some(CallContext::new(json!({"tenant": "acme"}))));
+        let context = CallContext::current().unwrap();
+        assert_eq!(context.get("tenant"), Some(&json!("acme")));
+
+        #[derive(Deserialize)]
+        struct Tenant {
+            tenant: String,
+        }
+        assert_eq!(context.deserialize::<Tenant>().unwrap().tenant, "acme");
+        assert!(context.deserialize::<u32>().is_err());
+
+        // Other threads have their own context
+        let other = std::thread::spawn(CallContext::current).join().unwrap();
+        assert_eq!(other, None);
+
+        // Nested contexts are restored when their guard is dropped
+        let nested = CallContext::enter(None);
+        assert_eq!(CallContext::current(), None);
+        drop(nested);
+        assert_eq!(CallContext::current(), Some(context));
+
+        drop(guard);
+        assert_eq!(CallContext::current(), None);
+    }
+}
@@ -0,0 +1,292 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use hyperlight_host::hypervisor::InterruptHandle;
+use hyperlight_host::HyperlightError;
+
+/// The name of the error the guest fails a cancelled handler with.
+///
+/// This has to match `CANCELLED_ERROR` in src/hyperlight-js-runtime/src/lib.rs
+pub(crate) const CANCELLED_ERROR: &str = "ExecutionCancelled";
+
+/// Which phase of [`CancellationHandle::cancel_with_grace`] terminated the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TerminationPhase {
+    /// No handler was running, so there was nothing to terminate.
+    NotRunning,
+    /// The handler was cancelled and returned within the grace period.
+    /// The sandbox is not poisoned.
+    Cancelled,
+    /// The handler did not return within the grace period and the guest was
+    /// killed. The sandbox is poisoned.
+    Killed,
+}
+
+/// A handle to cooperatively cancel the handler running in a sandbox.
+///
+/// Unlike [`InterruptHandle::kill`](crate::InterruptHandle::kill), which stops
+/// the guest wherever it is and poisons the sandbox, cancelling asks the
+/// JavaScript engine in the guest to stop: the running script is interrupted
+/// with an `ExecutionCancelled` error, the handler unwinds normally and the
+/// sandbox stays usable without being restored.
+/// The interruption cannot be caught by the handler's own `try`/`catch`
+/// blocks, so a handler cannot swallow it and keep running.
+///
+/// The engine checks for cancellation periodically while it runs JavaScript,
+/// so a handler that is blocked in a single long-running host function or
+/// built-in (e.g. parsing a huge JSON string) only stops once that call
+/// returns. Use [`cancel_with_grace`](Self::cancel_with_grace) to kill the
+/// guest if the handler does not stop in time.
+///
+/// Cancellation is only available for sandboxes built with
+/// [`SandboxBuilder::with_cooperative_cancellation`](crate::SandboxBuilder::with_cooperative_cancellation).
+/// Clones share the same state, and a cancellation requested while no handler
+/// is running is discarded when the next handler starts.
+#[derive(Debug, Clone)]
+pub struct CancellationHandle {
+    state: Arc<CancellationState>,
+    interrupt_handle: Arc<dyn InterruptHandle>,
+}
+
+impl CancellationHandle {
+    pub(crate) fn new(
+        state: Arc<CancellationState>,
+        interrupt_handle: Arc<dyn InterruptHandle>,
+    ) -> Self {
+        Self {
+            state,
+            interrupt_handle,
+        }
+    }
+
+    /// Request the cancellation of the running handler.
+    pub fn cancel(&self) {
+        self.state.cancel();
+    }
+
+    /// Returns whether the cancellation of the running handler was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.is_cancelled()
+    }
+
+    /// Cancel the running handler, and kill the guest if the handler has not
+    /// returned once `grace` has elapsed.
+    ///
+    /// This blocks until the handler returns or is killed, and reports which
+    /// of the two happened.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let cancellation = loaded.cancellation_handle().expect("cooperative cancellation is enabled");
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(Duration::from_secs(1));
+    ///     match cancellation.cancel_with_grace(Duration::from_millis(100)) {
+    ///         TerminationPhase::Killed => println!("the sandbox must be restored"),
+    ///         _ => {}
+    ///     }
+    /// });
+    /// ```
+    pub fn cancel_with_grace(&self, grace: Duration) -> TerminationPhase {
+        self.state.cancel_with_grace(grace, || self.kill())
+    }
+
+    /// The grace period after which monitors kill a cancelled handler, if any.
+    pub(crate) fn grace_period(&self) -> Option<Duration> {
+        self.state.grace_period
+    }
+
+    /// Kill the guest.
+    pub(crate) fn kill(&self) {
+        self.interrupt_handle.kill();
+    }
+
+    /// Track a guest call, discarding any pending cancellation.
+    pub(crate) fn start_call(&self) -> RunningCall<'_> {
+        self.state.start_call()
+    }
+
+    /// Map the error of a guest call that was cancelled to
+    /// [`HyperlightError::ExecutionCanceledByHost`], the error returned when
+    /// the guest is killed, so callers can handle both the same way.
+    pub(crate) fn map_error(&self, err: HyperlightError) -> HyperlightError {
+        self.state.map_error(err)
+    }
+}
+
+/// The cancellation state of a sandbox, shared by its [`CancellationHandle`]s
+/// and the host function polled by the guest.
+#[derive(Debug, Default)]
+pub(crate) struct CancellationState {
+    cancelled: AtomicBool,
+    // The id of the running guest call, if any.
+    running: Mutex<Option<u64>>,
+    finished: Condvar,
+    next_call: AtomicU64,
+    grace_period: Option<Duration>,
+}
+
+impl CancellationState {
+    pub(crate) fn new(grace_period: Option<Duration>) -> Self {
+        Self {
+            grace_period,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    fn start_call(&self) -> RunningCall<'_> {
+        self.cancelled.store(false, Ordering::Release);
+        let id = self.next_call.fetch_add(1, Ordering::Relaxed);
+        *self.running() = Some(id);
+        RunningCall(self)
+    }
+
+    fn cancel_with_grace(&self, grace: Duration, kill: impl FnOnce()) -> TerminationPhase {
+        let running = self.running();
+        let Some(call) = *running else {
+            return TerminationPhase::NotRunning;
+        };
+        self.cancel();
+        let (running, _) = self
+            .finished
+            .wait_timeout_while(running, grace, |running| *running == Some(call))
+            .unwrap_or_else(|e| e.into_inner());
+        if *running != Some(call) {
+            return TerminationPhase::Cancelled;
+        }
+        drop(running);
+        kill();
+        TerminationPhase::Killed
+    }
+
+    fn map_error(&self, err: HyperlightError) -> HyperlightError {
+        match err {
+            HyperlightError::GuestError(_, ref message)
+                if self.is_cancelled() && message.contains(CANCELLED_ERROR) =>
+            {
+                HyperlightError::ExecutionCanceledByHost()
+            }
+            err => err,
+        }
+    }
+
+    // The state is only ever replaced as a whole, so it is safe to recover from poisoning.
+    fn running(&self) -> MutexGuard<'_, Option<u64>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Guard marking a guest call as running until it is dropped.
+pub(crate) struct RunningCall<'a>(&'a CancellationState);
+
+impl Drop for RunningCall<'_> {
+    fn drop(&mut self) {
+        *self.0.running() = None;
+        self.0.finished.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+
+    use super::*;
+
+    fn cancelled_error() -> HyperlightError {
+        HyperlightError::GuestError(
+            ErrorCode::GuestError,
+            format!("Error: {CANCELLED_ERROR}: the handler was cancelled by the host"),
+        )
+    }
+
+    #[test]
+    fn test_map_error() {
+        let state = CancellationState::default();
+        assert!(matches!(
+            state.map_error(cancelled_error()),
+            HyperlightError::GuestError(..)
+        ));
+
+        state.cancel();
+        assert!(state.is_cancelled());
+        assert!(matches!(
+            state.map_error(cancelled_error()),
+            HyperlightError::ExecutionCanceledByHost()
+        ));
+        assert!(matches!(
+            state.map_error(HyperlightError::Error("other".to_string())),
+            HyperlightError::Error(_)
+        ));
+
+        let _call = state.start_call();
+        assert!(!state.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancel_with_grace() {
+        let state = Arc::new(CancellationState::default());
+        let kills = AtomicUsize::new(0);
+        let kill = || {
+            kills.fetch_add(1, Ordering::Relaxed);
+        };
+
+        assert_eq!(
+            state.cancel_with_grace(Duration::from_millis(10), kill),
+            TerminationPhase::NotRunning
+        );
+
+        // The call does not return within the grace period
+        let call = state.start_call();
+        assert_eq!(
+            state.cancel_with_grace(Duration::from_millis(10), kill),
+            TerminationPhase::Killed
+        );
+        assert_eq!(kills.load(Ordering::Relaxed), 1);
+        drop(call);
+
+        // The call returns once it notices the cancellation
+        let running = state.clone();
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let _call = running.start_call();
+            started_tx.send(()).unwrap();
+            while !running.is_cancelled() {
+                std::thread::yield_now();
+            }
+        });
+        started_rx.recv().unwrap();
+        assert_eq!(
+            state.cancel_with_grace(Duration::from_secs(10), kill),
+            TerminationPhase::Cancelled
+        );
+        assert_eq!(kills.load(Ordering::Relaxed), 1);
+        thread.join().unwrap();
+    }
+}
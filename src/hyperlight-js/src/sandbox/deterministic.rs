@@ -0,0 +1,118 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A clock read by the guest that only moves when the host advances it.
+#[derive(Debug, Clone)]
+pub(crate) struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+    /// A clock stopped at `epoch`, or at the Unix epoch if `epoch` is before it.
+    pub(crate) fn new(epoch: SystemTime) -> Self {
+        let micros = epoch
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+        Self(Arc::new(AtomicU64::new(
+            u64::try_from(micros).unwrap_or(u64::MAX),
+        )))
+    }
+
+    /// The time of the clock, in microseconds since the Unix epoch.
+    pub(crate) fn now_micros(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// The time of the clock.
+    pub(crate) fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_micros(self.now_micros())
+    }
+
+    /// Move the clock forward by `by`.
+    pub(crate) fn advance(&self, by: Duration) {
+        let by = u64::try_from(by.as_micros()).unwrap_or(u64::MAX);
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |micros| {
+                Some(micros.saturating_add(by))
+            });
+    }
+}
+
+/// The settings of a sandbox in deterministic mode, see
+/// `SandboxBuilder::with_deterministic_mode`.
+#[derive(Debug, Clone)]
+pub(crate) struct DeterministicMode {
+    pub(crate) seed: u64,
+    pub(crate) clock: VirtualClock,
+}
+
+impl DeterministicMode {
+    pub(crate) fn new(seed: u64, epoch: SystemTime) -> Self {
+        Self {
+            seed,
+            clock: VirtualClock::new(epoch),
+        }
+    }
+
+    /// The seed of the invocation with the sequence number `sequence`, which
+    /// only depends on the seed of the sandbox and on `sequence`.
+    pub(crate) fn invocation_seed(&self, sequence: u64) -> u64 {
+        // A SplitMix64 step, so consecutive invocations get unrelated seeds.
+        let mut z = self
+            .seed
+            .wrapping_add(sequence.wrapping_mul(0x9e3779b97f4a7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock() {
+        let epoch = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = VirtualClock::new(epoch);
+        assert_eq!(clock.now(), epoch);
+
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), epoch + Duration::from_millis(1500));
+        assert_eq!(clock.now_micros(), 1_700_000_001_500_000);
+
+        assert_eq!(
+            VirtualClock::new(UNIX_EPOCH - Duration::from_secs(1)).now_micros(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_invocation_seed() {
+        let mode = DeterministicMode::new(42, UNIX_EPOCH);
+        let same = DeterministicMode::new(42, UNIX_EPOCH);
+        assert_eq!(mode.invocation_seed(1), same.invocation_seed(1));
+        assert_ne!(mode.invocation_seed(1), mode.invocation_seed(2));
+        assert_ne!(
+            mode.invocation_seed(1),
+            DeterministicMode::new(43, UNIX_EPOCH).invocation_seed(1)
+        );
+    }
+}
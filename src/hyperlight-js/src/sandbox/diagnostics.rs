@@ -0,0 +1,279 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use hyperlight_host::Result;
+use serde::Serialize;
+
+use super::memory_stats::HeapReport;
+use crate::Script;
+
+/// The number of invocations kept for [`Diagnostics::recent_invocations`].
+const MAX_RECENT_INVOCATIONS: usize = 16;
+
+/// A report on a `LoadedJSSandbox`, to attach to bug reports.
+///
+/// Created by [`LoadedJSSandbox::collect_diagnostics`](crate::LoadedJSSandbox::collect_diagnostics)
+/// and serialized with [`to_json`](Self::to_json).
+///
+/// The report includes the error messages of recent invocations, which may
+/// contain data from the events, so review it before sharing it.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Diagnostics {
+    /// The host library and platform.
+    pub runtime: RuntimeInfo,
+    /// The effective configuration of the sandbox.
+    pub configuration: ConfigurationInfo,
+    /// The sequence number of the most recent invocation.
+    pub sequence: u64,
+    /// Whether the sandbox is poisoned.
+    pub poisoned: bool,
+    /// The loaded handlers, sorted by name.
+    pub handlers: Vec<HandlerInfo>,
+    /// The most recent invocations since the handlers were loaded, oldest first.
+    pub recent_invocations: Vec<InvocationReport>,
+}
+
+impl Diagnostics {
+    /// Serialize the report to pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// The host library and platform a sandbox runs on.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct RuntimeInfo {
+    /// The version of hyperlight-js.
+    pub version: &'static str,
+    /// The operating system of the host.
+    pub os: &'static str,
+    /// The CPU architecture of the host.
+    pub arch: &'static str,
+    /// The optional features hyperlight-js was built with.
+    pub features: Vec<&'static str>,
+}
+
+impl RuntimeInfo {
+    pub(crate) fn current() -> Self {
+        let features = [
+            ("crashdump", cfg!(feature = "crashdump")),
+            (
+                "function_call_metrics",
+                cfg!(feature = "function_call_metrics"),
+            ),
+            ("gdb", cfg!(feature = "gdb")),
+            ("lint", cfg!(feature = "lint")),
+            ("monitor-cpu-time", cfg!(feature = "monitor-cpu-time")),
+            ("monitor-wall-clock", cfg!(feature = "monitor-wall-clock")),
+            ("typescript", cfg!(feature = "typescript")),
+        ];
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            features: features
+                .into_iter()
+                .filter_map(|(name, enabled)| enabled.then_some(name))
+                .collect(),
+        }
+    }
+}
+
+/// The effective configuration of a sandbox.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct ConfigurationInfo {
+    /// The maximum number of handlers, if limited.
+    pub max_handlers: Option<usize>,
+    /// The maximum cumulative size of the handler scripts, if limited.
+    pub max_script_bytes: Option<usize>,
+    /// The maximum number of host function calls per execution, if limited.
+    pub max_host_calls: Option<u64>,
+    /// The size from which events and results are compressed, if wire compression is enabled.
+    pub wire_compression_min_size: Option<usize>,
+    /// Whether `handle_event` is guarded by default monitors.
+    pub default_monitors: bool,
+    /// Whether poisoned sandboxes are restored automatically.
+    pub auto_recover: bool,
+    /// When garbage is collected after calls that don't pass a `gc` flag.
+    pub gc_policy: String,
+    /// The built-in host functions the guest may call, if restricted.
+    pub host_function_allowlist: Option<Vec<String>>,
+    /// The seed of the sandbox, if it is in deterministic mode.
+    pub deterministic_seed: Option<u64>,
+    /// Whether failed invocations report how the guest heap changed.
+    pub heap_reports: bool,
+    /// Whether snapshots may roll the invocation sequence backwards.
+    pub allow_rollback: bool,
+    /// Whether cooperative cancellation is enabled.
+    pub cooperative_cancellation: bool,
+    /// The grace period before a cancelled handler is killed, in milliseconds.
+    pub cancellation_grace_period_ms: Option<u64>,
+    /// Whether modules are bundled on the host before the handlers are loaded.
+    pub prebundled_modules: bool,
+    /// The directory crash dumps are written to, if crash dumps are enabled.
+    pub crashdump_dir: Option<String>,
+}
+
+/// A handler of a sandbox, listed by `JSSandbox::handlers` and
+/// `LoadedJSSandbox::handlers`.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct HandlerInfo {
+    /// The name of the handler.
+    pub name: String,
+    /// The SHA-256 digest of the handler script, in hex.
+    pub sha256: String,
+    /// The size of the handler script, in bytes.
+    pub bytes: usize,
+    /// The file the handler script was loaded from, if any.
+    pub path: Option<String>,
+    /// Whether the guest compiled the handler script. Handlers are only
+    /// compiled when the sandbox is loaded, and loading fails if one of them
+    /// fails to compile, so this is `false` for every handler of a
+    /// `JSSandbox` and `true` for every handler of a `LoadedJSSandbox`, as
+    /// reported by the guest.
+    pub compiled: bool,
+}
+
+impl HandlerInfo {
+    /// The info of `handlers`, sorted by name, where the handlers in
+    /// `compiled` were compiled by the guest.
+    pub(crate) fn list(
+        handlers: &HashMap<String, Script>,
+        compiled: &HashSet<String>,
+    ) -> Vec<Self> {
+        let mut handlers: Vec<Self> = handlers
+            .iter()
+            .map(|(name, script)| Self {
+                name: name.clone(),
+                sha256: script.content_hash().to_string(),
+                bytes: script.content().len(),
+                path: script.source_path().map(|path| path.display().to_string()),
+                compiled: compiled.contains(name),
+            })
+            .collect();
+        handlers.sort_by(|a, b| a.name.cmp(&b.name));
+        handlers
+    }
+}
+
+/// The outcome of an invocation of a handler.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct InvocationReport {
+    /// The sequence number of the invocation.
+    pub sequence: u64,
+    /// The seed of the invocation, to replay it with `LoadedJSSandbox::handle_event_with_seed`.
+    pub seed: u64,
+    /// The name of the handler.
+    pub handler: String,
+    /// How long the invocation took, in microseconds.
+    pub duration_us: u64,
+    /// The error the invocation failed with, if it failed.
+    pub error: Option<String>,
+    /// How the guest heap changed during the invocation, if it failed and
+    /// heap reports are enabled.
+    pub heap: Option<HeapReport>,
+}
+
+/// The most recent invocations of a sandbox.
+#[derive(Debug, Default)]
+pub(crate) struct RecentInvocations(VecDeque<InvocationReport>);
+
+impl RecentInvocations {
+    pub(crate) fn record<T>(
+        &mut self,
+        sequence: u64,
+        seed: u64,
+        handler: &str,
+        duration: Duration,
+        result: &Result<T>,
+        heap: Option<HeapReport>,
+    ) {
+        if self.0.len() == MAX_RECENT_INVOCATIONS {
+            self.0.pop_front();
+        }
+        self.0.push_back(InvocationReport {
+            sequence,
+            seed,
+            handler: handler.to_string(),
+            duration_us: duration.as_micros() as u64,
+            error: result.as_ref().err().map(ToString::to_string),
+            heap,
+        });
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<InvocationReport> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+/// The directory crash dumps are written to, see `LoadedJSSandbox::generate_crashdump`.
+#[cfg(feature = "crashdump")]
+pub(crate) fn crashdump_dir() -> Option<String> {
+    let dir = std::env::var_os("HYPERLIGHT_CORE_DUMP_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    Some(dir.to_string_lossy().into_owned())
+}
+
+/// The directory crash dumps are written to, see `LoadedJSSandbox::generate_crashdump`.
+#[cfg(not(feature = "crashdump"))]
+pub(crate) fn crashdump_dir() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperlight_host::new_error;
+
+    use super::*;
+
+    #[test]
+    fn test_recent_invocations() {
+        let mut recent = RecentInvocations::default();
+        for sequence in 1..=20 {
+            let result = if sequence % 2 == 0 {
+                Ok(())
+            } else {
+                Err(new_error!("failed"))
+            };
+            let seed = sequence * 7;
+            recent.record(
+                sequence,
+                seed,
+                "handler",
+                Duration::from_micros(5),
+                &result,
+                None,
+            );
+        }
+
+        let reports = recent.to_vec();
+        assert_eq!(reports.len(), MAX_RECENT_INVOCATIONS);
+        assert_eq!(reports[0].sequence, 5);
+        assert_eq!(reports[15].sequence, 20);
+        assert_eq!(reports[15].seed, 140);
+        assert_eq!(reports[0].duration_us, 5);
+        assert!(reports[0].error.as_deref().unwrap().contains("failed"));
+        assert!(reports[15].error.is_none());
+    }
+}
@@ -0,0 +1,136 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_host::sandbox::SandboxConfiguration;
+use hyperlight_host::{HyperlightError, Result};
+
+const EVENT_TOO_LARGE_ERROR: &str = "EventTooLarge: ";
+
+/// The bytes of the input buffer taken by a handler call besides the event
+/// and the name of the handler: the offsets the call is pushed with, and the
+/// flatbuffer tables of the call and of its other parameters, including the
+/// traceparent.
+const CALL_OVERHEAD: usize = 512;
+
+/// The error of an event too large for the input buffer of the guest, whose
+/// size is set with
+/// [`SandboxBuilder::with_guest_input_buffer_size`](crate::SandboxBuilder::with_guest_input_buffer_size).
+///
+/// The host checks the size of the event before calling into the guest, and
+/// the call fails with an error which [`EventTooLarge::from_error`]
+/// recognizes. The sandbox is not poisoned, and the sequence number is not
+/// used up.
+///
+/// # Example
+///
+/// ```text
+/// match loaded_sandbox.handle_event("handler", event, None) {
+///     Ok(result) => respond(200, result),
+///     Err(err) => match EventTooLarge::from_error(&err) {
+///         Some(too_large) => respond(413, too_large.to_string()),
+///         None => respond(500, err.to_string()),
+///     },
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventTooLarge {
+    /// The size of the event, in bytes sent to the guest.
+    pub size: usize,
+    /// The maximum size of an event for the handler, in bytes sent to the
+    /// guest.
+    pub limit: usize,
+}
+
+impl EventTooLarge {
+    /// The `EventTooLarge` error `err` is, if an event was too large for the
+    /// input buffer.
+    pub fn from_error(err: &HyperlightError) -> Option<Self> {
+        let HyperlightError::Error(message) = err else {
+            return None;
+        };
+        let mut numbers = message
+            .strip_prefix(EVENT_TOO_LARGE_ERROR)?
+            .split_whitespace()
+            .filter_map(|word| word.parse().ok());
+        Some(Self {
+            size: numbers.next()?,
+            limit: numbers.next()?,
+        })
+    }
+}
+
+impl fmt::Display for EventTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the event is {} bytes, more than the limit of {} bytes",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for EventTooLarge {}
+
+/// Checks that an event of `size` bytes, passed to a handler whose name is
+/// `name_len` bytes, fits an input buffer of `input_buffer_size` bytes, or of
+/// the default size if `None`.
+pub(crate) fn check_event_size(
+    size: usize,
+    name_len: usize,
+    input_buffer_size: Option<usize>,
+) -> Result<()> {
+    let input_buffer_size = input_buffer_size.unwrap_or(SandboxConfiguration::DEFAULT_INPUT_SIZE);
+    let limit = input_buffer_size.saturating_sub(CALL_OVERHEAD + name_len);
+    if size > limit {
+        return Err(HyperlightError::Error(format!(
+            "{EVENT_TOO_LARGE_ERROR}{}; raise the limit with SandboxBuilder::with_guest_input_buffer_size",
+            EventTooLarge { size, limit }
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_event_size() {
+        let limit = SandboxConfiguration::DEFAULT_INPUT_SIZE - CALL_OVERHEAD - 7;
+        assert!(check_event_size(limit, 7, None).is_ok());
+
+        let err = check_event_size(limit + 1, 7, None).unwrap_err();
+        assert_eq!(
+            EventTooLarge::from_error(&err),
+            Some(EventTooLarge {
+                size: limit + 1,
+                limit
+            })
+        );
+        assert!(err.to_string().contains("with_guest_input_buffer_size"));
+
+        let err = check_event_size(0x10000, 0, Some(0x10000)).unwrap_err();
+        assert_eq!(
+            EventTooLarge::from_error(&err).map(|too_large| too_large.limit),
+            Some(0x10000 - CALL_OVERHEAD)
+        );
+        assert!(check_event_size(0x10000, 0, Some(0x20000)).is_ok());
+
+        let err = HyperlightError::Error("boom".to_string());
+        assert_eq!(EventTooLarge::from_error(&err), None);
+    }
+}
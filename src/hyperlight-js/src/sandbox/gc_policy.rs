@@ -0,0 +1,105 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+/// When to run garbage collection after calls to `handle_event` that don't
+/// pass an explicit `gc` flag.
+///
+/// Passing `Some(true)` or `Some(false)` as `gc` still forces or skips the
+/// collection for that call, so callers that already decide per call keep
+/// working unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{GcPolicy, SandboxBuilder};
+///
+/// let proto_js_sandbox = SandboxBuilder::new()
+///     .with_gc_policy(GcPolicy::EveryNCalls(100))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GcPolicy {
+    /// Collect after every call.
+    #[default]
+    Always,
+    /// Never collect after calls, leaving it to
+    /// [`LoadedJSSandbox::run_gc`](crate::LoadedJSSandbox::run_gc) and to
+    /// QuickJS itself.
+    Never,
+    /// Collect after every `n`th call since the last collection.
+    EveryNCalls(u32),
+    /// Collect after a call leaves the QuickJS heap larger than this many
+    /// bytes. Measuring the heap costs a guest call after every call.
+    WhenHeapAbove(u64),
+}
+
+impl GcPolicy {
+    /// Whether to collect as part of a call, given the number of calls made
+    /// since the last collection.
+    pub(crate) fn collect_with_call(&self, calls_since_gc: u32) -> bool {
+        match self {
+            GcPolicy::Always => true,
+            GcPolicy::Never | GcPolicy::WhenHeapAbove(_) => false,
+            GcPolicy::EveryNCalls(n) => calls_since_gc.saturating_add(1) >= *n,
+        }
+    }
+
+    /// The size of the heap above which to collect after a call, if the
+    /// heap has to be measured.
+    pub(crate) fn heap_threshold(&self) -> Option<u64> {
+        match self {
+            GcPolicy::WhenHeapAbove(bytes) => Some(*bytes),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GcPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GcPolicy::Always => f.write_str("always"),
+            GcPolicy::Never => f.write_str("never"),
+            GcPolicy::EveryNCalls(n) => write!(f, "every {n} calls"),
+            GcPolicy::WhenHeapAbove(bytes) => write!(f, "when the heap is above {bytes} bytes"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_with_call() {
+        assert!(GcPolicy::Always.collect_with_call(0));
+        assert!(!GcPolicy::Never.collect_with_call(1000));
+        assert!(!GcPolicy::WhenHeapAbove(1024).collect_with_call(1000));
+
+        let every_third = GcPolicy::EveryNCalls(3);
+        let collections: Vec<bool> = (0..3).map(|n| every_third.collect_with_call(n)).collect();
+        assert_eq!(collections, [false, false, true]);
+
+        // Every 0 calls is every call
+        assert!(GcPolicy::EveryNCalls(0).collect_with_call(0));
+
+        assert_eq!(GcPolicy::WhenHeapAbove(1024).heap_threshold(), Some(1024));
+        assert_eq!(GcPolicy::Always.heap_threshold(), None);
+        assert_eq!(GcPolicy::EveryNCalls(10).to_string(), "every 10 calls");
+    }
+}
@@ -0,0 +1,63 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::HashMap;
+
+use hyperlight_host::{new_error, Result};
+
+use crate::Script;
+
+/// Limits on the handlers a `JSSandbox` accepts and on their executions.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct HandlerLimits {
+    pub(crate) max_handlers: Option<usize>,
+    pub(crate) max_script_bytes: Option<usize>,
+    pub(crate) max_host_calls: Option<u64>,
+}
+
+impl HandlerLimits {
+    /// Check whether `script` can be added to the existing `handlers`.
+    pub(crate) fn check_add(
+        &self,
+        handlers: &HashMap<String, Script>,
+        script: &Script,
+    ) -> Result<()> {
+        if let Some(max_handlers) = self.max_handlers
+            && handlers.len() >= max_handlers
+        {
+            return Err(new_error!(
+                "The sandbox already has the maximum number of handlers ({})",
+                max_handlers
+            ));
+        }
+
+        if let Some(max_script_bytes) = self.max_script_bytes {
+            let script_bytes = handlers
+                .values()
+                .map(|script| script.content().len())
+                .sum::<usize>()
+                .saturating_add(script.content().len());
+            if script_bytes > max_script_bytes {
+                return Err(new_error!(
+                    "Adding the handler would bring the handler scripts to {} bytes, over the limit of {} bytes",
+                    script_bytes,
+                    max_script_bytes
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
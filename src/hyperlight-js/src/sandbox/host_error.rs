@@ -0,0 +1,132 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_host::HyperlightError;
+use serde::Serialize;
+
+// This prefix and the serialization of `HostError` have to match the
+// deserialization of `HostError` in src/hyperlight-js-runtime/src/host_fn.rs
+const HOST_ERROR_PREFIX: &str = "HostError: ";
+
+/// An error returned by a host function that the guest throws as a JS
+/// `Error` with the same name, message and code, which the handler can
+/// `catch` and inspect.
+///
+/// Other errors returned by host functions are thrown as generic internal
+/// errors.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{HostError, SandboxBuilder};
+///
+/// let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// proto_js_sandbox
+///     .host_module("users")
+///     .register_fallible("find", |id: u64| {
+///         Err::<String, _>(
+///             HostError::new("NotFoundError", format!("No user {id}")).with_code("E_NOT_FOUND"),
+///         )
+///     });
+/// ```
+///
+/// which the handler sees as
+///
+/// ```js
+/// try {
+///     users.find(42);
+/// } catch (err) {
+///     // err.name === "NotFoundError", err.code === "E_NOT_FOUND"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostError {
+    name: String,
+    message: String,
+    code: Option<String>,
+}
+
+impl HostError {
+    /// An error with the given `name`, like `"NotFoundError"`, and `message`.
+    pub fn new(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            message: message.into(),
+            code: None,
+        }
+    }
+
+    /// Set the `code` property of the error.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// The name of the error.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The code of the error, if it has one.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)?;
+        if let Some(code) = &self.code {
+            write!(f, " ({code})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HostError {}
+
+impl From<HostError> for HyperlightError {
+    fn from(error: HostError) -> Self {
+        // The message of the error is passed as is to the guest
+        match serde_json::to_string(&error) {
+            Ok(json) => HyperlightError::Error(format!("{HOST_ERROR_PREFIX}{json}")),
+            Err(_) => HyperlightError::Error(error.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_error() {
+        let error = HostError::new("NotFoundError", "No user 42").with_code("E_NOT_FOUND");
+        assert_eq!(error.to_string(), "NotFoundError: No user 42 (E_NOT_FOUND)");
+
+        let error = HyperlightError::from(error);
+        assert_eq!(
+            error.to_string(),
+            r#"HostError: {"name":"NotFoundError","message":"No user 42","code":"E_NOT_FOUND"}"#
+        );
+    }
+}
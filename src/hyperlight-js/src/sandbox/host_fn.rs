@@ -14,11 +14,16 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 use serde::de::DeserializeOwned;
 use serde::ser::SerializeSeq;
 use serde::Serialize;
 
+use super::call_context::CallContext;
+use super::host_error::HostError;
+
 // Unlike hyperlight-host's Function, this Function trait uses `serde`'s Serialize and DeserializeOwned traits for input and output types.
 
 /// A trait representing a host function that can be called from the guest JavaScript code.
@@ -48,31 +53,133 @@ where
     }
 }
 
-type BoxFunction = Box<dyn Fn(String) -> crate::Result<String> + Send + Sync>;
+pub(crate) type BoxFunction = Box<dyn Fn(String) -> crate::Result<String> + Send + Sync>;
+
+// The guest passes the arguments as a JSON array, but serde only deserializes
+// the arguments of functions taking none, `()`, from `null`.
+fn deserialize_args<Args: DeserializeOwned>(args: &str) -> serde_json::Result<Args> {
+    serde_json::from_str(args).or_else(|e| match args {
+        "[]" => serde_json::from_str("null"),
+        _ => Err(e),
+    })
+}
 
 fn type_erased<Output: Serialize, Args: DeserializeOwned>(
     func: impl Function<Output, Args> + Send + Sync + 'static,
 ) -> BoxFunction {
     Box::new(move |args: String| {
-        let args: Args = serde_json::from_str(&args)?;
+        let args: Args = deserialize_args(&args)?;
         let output: Output = func.call(args);
         Ok(serde_json::to_string(&output)?)
     })
 }
 
+fn type_erased_fallible<Output: Serialize, Args: DeserializeOwned>(
+    func: impl Function<Result<Output, HostError>, Args> + Send + Sync + 'static,
+) -> BoxFunction {
+    Box::new(move |args: String| {
+        let args: Args = deserialize_args(&args)?;
+        let output: Output = func.call(args)?;
+        Ok(serde_json::to_string(&output)?)
+    })
+}
+
+/// A call from the guest to a registered host function, passed to the
+/// interceptors added with
+/// [`ProtoJSSandbox::with_host_call_interceptor`](crate::ProtoJSSandbox::with_host_call_interceptor).
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct HostCall<'a> {
+    /// The name of the host module of the function.
+    pub module: &'a str,
+    /// The name of the function.
+    pub function: &'a str,
+    /// The context of the handler invocation making the call, if it was
+    /// given one, see [`CallContext`].
+    pub context: Option<&'a CallContext>,
+    /// The W3C `traceparent` of the handler invocation making the call, if it
+    /// is traced, see [`current_traceparent`](crate::current_traceparent).
+    pub traceparent: Option<&'a str>,
+}
+
+/// The rest of the dispatch of a host call, called by an interceptor with the
+/// (JSON serialized) arguments to pass on.
+pub type HostCallNext<'a> = &'a dyn Fn(String) -> crate::Result<String>;
+
+pub(crate) type HostCallInterceptor =
+    Arc<dyn Fn(&HostCall<'_>, String, HostCallNext<'_>) -> crate::Result<String> + Send + Sync>;
+
+/// Call `func` with `args` through `interceptors`, the first one being the
+/// outermost.
+pub(crate) fn intercept(
+    interceptors: &[HostCallInterceptor],
+    call: &HostCall<'_>,
+    args: String,
+    func: &BoxFunction,
+) -> crate::Result<String> {
+    match interceptors.split_first() {
+        Some((interceptor, rest)) => {
+            interceptor(call, args, &|args| intercept(rest, call, args, func))
+        }
+        None => func(args),
+    }
+}
+
+/// How the guest caches the results of a host function.
+///
+/// Results are cached per arguments: a call with the same (JSON serialized)
+/// arguments as a previous successful call returns the cached result without
+/// calling the host. Errors are never cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HostFunctionCache {
+    /// Reuse results until the end of the current handler invocation.
+    Invocation,
+    /// Reuse results, across handler invocations, until the given time has elapsed.
+    Ttl(Duration),
+}
+
 /// A module containing host functions that can be called from the guest JavaScript code.
 #[derive(Default)]
 pub struct HostModule {
     functions: HashMap<String, BoxFunction>,
+    caches: HashMap<String, HostFunctionCache>,
 }
 
-// The serialization of this struct has to match the deserialization in
+// The serialization of these structs has to match the deserialization in
 // register_host_modules in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(Serialize)]
+struct FunctionDescriptor<'a> {
+    name: &'a str,
+    cache: Option<CacheDescriptor>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CacheDescriptor {
+    Invocation,
+    Ttl { micros: u64 },
+}
+
+impl From<HostFunctionCache> for CacheDescriptor {
+    fn from(cache: HostFunctionCache) -> Self {
+        match cache {
+            HostFunctionCache::Invocation => CacheDescriptor::Invocation,
+            HostFunctionCache::Ttl(ttl) => CacheDescriptor::Ttl {
+                micros: u64::try_from(ttl.as_micros()).unwrap_or(u64::MAX),
+            },
+        }
+    }
+}
+
 impl Serialize for HostModule {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         let mut seq_serializer = serializer.serialize_seq(Some(self.functions.len()))?;
-        for key in self.functions.keys() {
-            seq_serializer.serialize_element(key)?;
+        for name in self.functions.keys() {
+            seq_serializer.serialize_element(&FunctionDescriptor {
+                name,
+                cache: self.caches.get(name).copied().map(CacheDescriptor::from),
+            })?;
         }
         seq_serializer.end()
     }
@@ -92,6 +199,21 @@ impl HostModule {
         self
     }
 
+    /// Register a host function that can fail, like [`register`](Self::register).
+    ///
+    /// When the function returns an `Err`, the guest throws a JS `Error` with
+    /// the name, message and code of the [`HostError`], which the handler can
+    /// `catch` and inspect.
+    pub fn register_fallible<Output: Serialize, Args: DeserializeOwned>(
+        &mut self,
+        name: impl Into<String>,
+        func: impl Function<Result<Output, HostError>, Args> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.functions
+            .insert(name.into(), type_erased_fallible(func));
+        self
+    }
+
     /// Register a raw host function that operates on JSON strings directly.
     ///
     /// Unlike [`register`](Self::register), which handles serde serialization /
@@ -102,6 +224,10 @@ impl HostModule {
     /// This is primarily intended for dynamic / bridge scenarios (e.g. NAPI
     /// bindings) where argument types are not known at compile time.
     ///
+    /// Returning a [`HostError`], converted into a `HyperlightError`, makes the
+    /// guest throw it as a JS `Error` like for
+    /// [`register_fallible`](Self::register_fallible).
+    ///
     /// Registering a function with the same `name` as an existing function
     /// overwrites the previous registration.
     pub fn register_raw(
@@ -113,7 +239,47 @@ impl HostModule {
         self
     }
 
+    /// Mark the host function `name` as cacheable, so that the guest memoizes
+    /// its results according to `cache` instead of calling the host every time.
+    ///
+    /// This is meant for functions whose results rarely change, like
+    /// configuration or metadata lookups. Handlers do not need to change to
+    /// benefit from the cache.
+    ///
+    /// The function can be registered before or after being marked as cacheable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use hyperlight_js::{HostFunctionCache, SandboxBuilder};
+    ///
+    /// let mut sbox = SandboxBuilder::new().build()?;
+    /// sbox.host_module("config")
+    ///     .register("get", |key: String| format!("value of {key}"))
+    ///     .cacheable("get", HostFunctionCache::Ttl(Duration::from_secs(60)));
+    /// # Ok::<(), hyperlight_host::HyperlightError>(())
+    /// ```
+    pub fn cacheable(&mut self, name: impl Into<String>, cache: HostFunctionCache) -> &mut Self {
+        self.caches.insert(name.into(), cache);
+        self
+    }
+
     pub(crate) fn get(&self, name: &str) -> Option<&BoxFunction> {
         self.functions.get(name)
     }
 }
+
+/// A type whose methods are the host functions of a host module, registered
+/// with [`ProtoJSSandbox::register_module`](crate::ProtoJSSandbox::register_module).
+///
+/// This is implemented with the [`host_module`](crate::host_module) attribute
+/// rather than by hand.
+pub trait HostModuleDefinition: Send + Sync + 'static {
+    /// The name the guest imports the module with.
+    const NAME: &'static str;
+
+    /// Register the host functions of the module in `module`.
+    fn register_functions(self: Arc<Self>, module: &mut HostModule);
+}
@@ -0,0 +1,120 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use hyperlight_host::{new_error, Result};
+
+/// The built-in host functions the guest can call.
+pub(crate) const BUILTIN_HOST_FUNCTIONS: &[&str] = &[
+    "HostPrint",
+    "CurrentTimeMicros",
+    "MonotonicTimeMicros",
+    "IsExecutionCancelled",
+    "ResolveModule",
+    "LoadModule",
+    "CallHostJsFunction",
+    "CallHostJsFunctionBatch",
+];
+
+/// The built-in host functions the guest may call, see
+/// `SandboxBuilder::with_seccomp_style_host_function_allowlist`.
+///
+/// Without an allowlist, all the built-in host functions are allowed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HostFunctionAllowlist(Option<Arc<BTreeSet<String>>>);
+
+impl HostFunctionAllowlist {
+    pub(crate) fn new(names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(Some(Arc::new(names.into_iter().map(Into::into).collect())))
+    }
+
+    /// Check that the allowlist only names built-in host functions, so a typo
+    /// doesn't silently deny a function.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let unknown = self
+            .names()
+            .into_iter()
+            .flatten()
+            .find(|name| !BUILTIN_HOST_FUNCTIONS.contains(&name.as_str()));
+        match unknown {
+            Some(name) => Err(new_error!(
+                "Unknown built-in host function '{}' in the host function allowlist, expected one of {:?}",
+                name,
+                BUILTIN_HOST_FUNCTIONS
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the guest may call the built-in host function `name`.
+    pub(crate) fn allows(&self, name: &str) -> bool {
+        self.0.as_ref().is_none_or(|allowed| allowed.contains(name))
+    }
+
+    /// Check that the guest may call the built-in host function `name`,
+    /// logging the call if it is denied.
+    pub(crate) fn check(&self, name: &str) -> Result<()> {
+        if self.allows(name) {
+            return Ok(());
+        }
+        tracing::warn!(
+            host_function = name,
+            "Denied a call to a built-in host function that is not in the allowlist"
+        );
+        Err(new_error!(
+            "The built-in host function '{}' is not in the host function allowlist",
+            name
+        ))
+    }
+
+    /// The allowed built-in host functions, in order, or `None` if there is no allowlist.
+    pub(crate) fn names(&self) -> Option<Vec<String>> {
+        self.0
+            .as_ref()
+            .map(|allowed| allowed.iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowlist() {
+        let allow_all = HostFunctionAllowlist::default();
+        assert!(allow_all.validate().is_ok());
+        assert!(allow_all.check("CallHostJsFunction").is_ok());
+        assert_eq!(allow_all.names(), None);
+
+        let allowlist = HostFunctionAllowlist::new(["HostPrint", "CurrentTimeMicros"]);
+        assert!(allowlist.validate().is_ok());
+        assert!(allowlist.check("CurrentTimeMicros").is_ok());
+        let err = allowlist.check("CallHostJsFunction").unwrap_err();
+        assert!(err.to_string().contains("'CallHostJsFunction'"), "{err}");
+        assert_eq!(
+            allowlist.names(),
+            Some(vec![
+                "CurrentTimeMicros".to_string(),
+                "HostPrint".to_string()
+            ])
+        );
+
+        let typo = HostFunctionAllowlist::new(["CurrentTimeMicro"]);
+        let err = typo.validate().unwrap_err();
+        assert!(err.to_string().contains("'CurrentTimeMicro'"), "{err}");
+    }
+}
@@ -0,0 +1,655 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! The JavaScript runtime running in the host process, for machines with no
+//! hypervisor.
+//!
+//! **This provides no isolation at all**: handlers run on the thread calling
+//! into the sandbox, in the address space of the host, and only the
+//! JavaScript engine stands between them and the host. It is meant for tests
+//! and local development, never for untrusted code.
+//!
+//! The guest functions are dispatched to the same `hyperlight-js-runtime` the
+//! guest binary runs, with these differences:
+//! - snapshots are not supported; unloading handlers and auto-recovery start
+//!   again from a new runtime, set up like the original one
+//! - `print` and `console` write to the stdout of the process, rather than to
+//!   the host print function
+//! - `Date.now()` and `performance.now()` read the clocks of the process,
+//!   rather than the virtual clock of the deterministic mode
+//! - the state of the `random` module is shared by all the sandboxes of the
+//!   process
+//! - the guest heap and stack sizes of the sandbox configuration are ignored
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+use hyperlight_host::func::{
+    HostFunction, ParameterTuple, ParameterValue, ReturnValue, SupportedReturnType,
+};
+use hyperlight_host::hypervisor::InterruptHandle;
+use hyperlight_host::{new_error, HyperlightError, Result};
+use hyperlight_js_runtime::{wire, HandlerContext, HostError, HostFunctionCache, JsRuntime};
+use serde::{Deserialize, Serialize};
+
+use super::memory_stats::MemoryStats;
+
+type BoxHostFunction = Box<dyn Fn(Vec<ParameterValue>) -> Result<ReturnValue> + Send + Sync>;
+
+/// The host functions the runtime calls, by name, like the host functions
+/// registered with an `UninitializedSandbox`.
+#[derive(Clone, Default)]
+struct HostFunctions(Arc<HashMap<String, BoxHostFunction>>);
+
+impl HostFunctions {
+    fn call<Output: SupportedReturnType>(
+        &self,
+        name: &str,
+        args: impl ParameterTuple,
+    ) -> Result<Output> {
+        let function = self
+            .0
+            .get(name)
+            .ok_or_else(|| new_error!("Host function {} not found", name))?;
+        Ok(Output::from_value(function(args.into_value())?)?)
+    }
+}
+
+/// The host of the runtime, resolving and loading modules through the
+/// host functions like the guest does.
+struct InProcessHost(HostFunctions);
+
+impl hyperlight_js_runtime::host::Host for InProcessHost {
+    fn resolve_module(&self, base: String, name: String) -> anyhow::Result<String> {
+        self.0
+            .call("ResolveModule", (base.clone(), name.clone()))
+            .map_err(|e| anyhow!("{e}"))
+            .with_context(|| format!("Resolving module {name:?} from {base:?}"))
+    }
+
+    fn load_module(&self, name: String) -> anyhow::Result<String> {
+        self.0
+            .call("LoadModule", name.clone())
+            .map_err(|e| anyhow!("{e}"))
+            .with_context(|| format!("Loading module {name:?}"))
+    }
+}
+
+/// An in-process sandbox with host functions being registered, the
+/// counterpart of an `UninitializedSandbox`.
+#[derive(Default)]
+pub(crate) struct UninitializedInProcessSandbox {
+    host_functions: HashMap<String, BoxHostFunction>,
+}
+
+impl UninitializedInProcessSandbox {
+    pub(crate) fn register<Args: ParameterTuple, Output: SupportedReturnType>(
+        &mut self,
+        name: &str,
+        host_func: impl Into<HostFunction<Output, Args>>,
+    ) {
+        let host_func = host_func.into();
+        self.host_functions.insert(
+            name.to_string(),
+            Box::new(move |args| {
+                let args = Args::from_value(args)?;
+                Ok(host_func.call(args)?.into_value())
+            }),
+        );
+    }
+
+    pub(crate) fn evolve(self) -> Result<InProcessSandbox> {
+        let host_functions = HostFunctions(Arc::new(self.host_functions));
+        Ok(InProcessSandbox {
+            runtime: new_runtime(&host_functions)?,
+            host_functions,
+            interrupt: Arc::default(),
+            setup_calls: Vec::new(),
+            wire_compression_min_size: usize::MAX,
+            poisoned: false,
+        })
+    }
+}
+
+fn new_runtime(host_functions: &HostFunctions) -> Result<JsRuntime> {
+    let mut runtime = JsRuntime::new(InProcessHost(host_functions.clone()))
+        .map_err(|e| new_error!("Failed to initialize JS runtime: {:?}", e))?;
+    // `host.batch` is available whether or not the sandbox has host modules.
+    let host_functions = host_functions.clone();
+    runtime
+        .set_host_batch_function(move |calls: String| -> anyhow::Result<String> {
+            host_functions
+                .call("CallHostJsFunctionBatch", calls)
+                .map_err(|e| match HostError::from_message(&e.to_string()) {
+                    Some(error) => anyhow::Error::msg(error),
+                    None => anyhow!(
+                        "Calling host functions in a batch failed: {}",
+                        host_function_error(&e)
+                    ),
+                })
+        })
+        .map_err(|e| new_error!("Failed to initialize JS runtime: {:?}", e))?;
+    Ok(runtime)
+}
+
+/// A guest call, by name with its arguments.
+type GuestCall = (String, Vec<ParameterValue>);
+
+/// The guest calls setting up the runtime, which are replayed on a new
+/// runtime to rewind to a checkpoint.
+const SETUP_CALLS: &[&str] = &[
+    "RegisterHostModules",
+    "ConfigureRuntime",
+    "register_setup_script",
+    "register_handlers",
+    "register_pipeline",
+];
+
+/// The state of an in-process sandbox, as the guest calls that set it up.
+#[derive(Clone)]
+pub(crate) struct InProcessCheckpoint(Arc<[GuestCall]>);
+
+/// The JavaScript runtime of a sandbox, running in the host process.
+pub(crate) struct InProcessSandbox {
+    runtime: JsRuntime,
+    host_functions: HostFunctions,
+    interrupt: Arc<InProcessInterruptHandle>,
+    setup_calls: Vec<GuestCall>,
+    wire_compression_min_size: usize,
+    poisoned: bool,
+}
+
+impl InProcessSandbox {
+    /// Call the guest function `func_name`, like `MultiUseSandbox::call`.
+    pub(crate) fn call<Output: SupportedReturnType>(
+        &mut self,
+        func_name: &str,
+        args: impl ParameterTuple,
+    ) -> Result<Output> {
+        let result = self.call_with_values(func_name, args.into_value())?;
+        Ok(Output::from_value(result)?)
+    }
+
+    fn call_with_values(
+        &mut self,
+        func_name: &str,
+        args: Vec<ParameterValue>,
+    ) -> Result<ReturnValue> {
+        if self.poisoned {
+            return Err(HyperlightError::PoisonedSandbox);
+        }
+        let setup_call = SETUP_CALLS
+            .contains(&func_name)
+            .then(|| (func_name.to_string(), args.clone()));
+
+        self.interrupt.start();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.dispatch(func_name, args)));
+        let killed = self.interrupt.finish();
+
+        match result {
+            // Like a VM killed while in a host function, the call is cancelled
+            // even if it completed before the engine polled for the kill.
+            Ok(_) if killed => {
+                self.poisoned = true;
+                Err(HyperlightError::ExecutionCanceledByHost())
+            }
+            Ok(Ok(result)) => {
+                self.setup_calls.extend(setup_call);
+                Ok(result)
+            }
+            // Formatted like the errors of the guest
+            Ok(Err(e)) => Err(HyperlightError::GuestError(
+                ErrorCode::GuestError,
+                format!("Error: {e:?}"),
+            )),
+            Err(panic) => {
+                self.poisoned = true;
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(HyperlightError::GuestAborted(
+                    ErrorCode::UnknownError as u8,
+                    message,
+                ))
+            }
+        }
+    }
+
+    pub(crate) fn poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    pub(crate) fn interrupt_handle(&self) -> Arc<dyn InterruptHandle> {
+        self.interrupt.clone()
+    }
+
+    pub(crate) fn checkpoint(&self) -> Result<InProcessCheckpoint> {
+        if self.poisoned {
+            return Err(HyperlightError::PoisonedSandbox);
+        }
+        Ok(InProcessCheckpoint(self.setup_calls.clone().into()))
+    }
+
+    /// Replace the runtime with a new one, set up by replaying the guest
+    /// calls of `checkpoint`.
+    pub(crate) fn rewind(&mut self, checkpoint: &InProcessCheckpoint) -> Result<()> {
+        self.runtime = new_runtime(&self.host_functions)?;
+        self.setup_calls.clear();
+        self.wire_compression_min_size = usize::MAX;
+        self.poisoned = false;
+        for (func_name, args) in checkpoint.0.iter() {
+            self.call_with_values(func_name, args.clone())?;
+        }
+        Ok(())
+    }
+
+    // The dispatch in here has to match the guest functions in
+    // src/hyperlight-js-runtime/src/main/hyperlight.rs
+    fn dispatch(
+        &mut self,
+        func_name: &str,
+        mut args: Vec<ParameterValue>,
+    ) -> anyhow::Result<ReturnValue> {
+        let runtime = &mut self.runtime;
+        // The sandbox may have moved to another thread since the last call
+        runtime.update_stack_top();
+        match func_name {
+            "register_handlers" => {
+                let handlers: String = ParameterTuple::from_value(args)?;
+                let handlers: Vec<HandlerRegistration> = serde_json::from_str(&handlers)?;
+                let mut compiled = Vec::with_capacity(handlers.len());
+                for handler in handlers {
+                    let name = match handler {
+                        HandlerRegistration::Script {
+                            name,
+                            script,
+                            pwd,
+                            modules,
+                        } => {
+                            runtime.register_handler_with_modules(
+                                name.clone(),
+                                script,
+                                pwd,
+                                parse_modules(&modules)?,
+                            )?;
+                            name
+                        }
+                        HandlerRegistration::JsonLogic { name, rule } => {
+                            runtime.register_jsonlogic_handler(name.clone(), &rule)?;
+                            name
+                        }
+                    };
+                    compiled.push(name);
+                }
+                return Ok(ReturnValue::String(serde_json::to_string(&compiled)?));
+            }
+            "register_setup_script" => {
+                let (setup_script, setup_pwd, modules_json): (String, String, String) =
+                    ParameterTuple::from_value(args)?;
+                runtime.register_setup_script_with_modules(
+                    setup_script,
+                    setup_pwd,
+                    parse_modules(&modules_json)?,
+                )?;
+            }
+            "register_pipeline" => {
+                let (function_name, stages): (String, String) = ParameterTuple::from_value(args)?;
+                let stages: Vec<String> = serde_json::from_str(&stages)?;
+                runtime.register_pipeline(function_name, stages)?;
+            }
+            "memory_usage" => {
+                let usage = runtime.memory_usage();
+                let unsigned = |value: i64| value.max(0) as u64;
+                let stats = MemoryStats {
+                    malloc_size: unsigned(usage.malloc_size),
+                    malloc_count: unsigned(usage.malloc_count),
+                    memory_used_size: unsigned(usage.memory_used_size),
+                    atom_count: unsigned(usage.atom_count),
+                    atom_size: unsigned(usage.atom_size),
+                    string_count: unsigned(usage.str_count),
+                    string_size: unsigned(usage.str_size),
+                    object_count: unsigned(usage.obj_count),
+                    object_size: unsigned(usage.obj_size),
+                    property_count: unsigned(usage.prop_count),
+                    property_size: unsigned(usage.prop_size),
+                    function_count: unsigned(usage.js_func_count),
+                    function_size: unsigned(usage.js_func_size),
+                    array_count: unsigned(usage.array_count),
+                    global_count: runtime.global_count() as u64,
+                };
+                return Ok(ReturnValue::String(serde_json::to_string(&stats)?));
+            }
+            "run_handler_batch" => {
+                let (batch, run_gc): (String, bool) = ParameterTuple::from_value(args)?;
+                let Batch {
+                    events,
+                    invocations,
+                    traceparent,
+                } = serde_json::from_str(&batch)?;
+                let invocations = invocations
+                    .into_iter()
+                    .map(|(function_name, event, sequence, seed)| {
+                        let context = HandlerContext {
+                            sequence,
+                            seed,
+                            traceparent: traceparent.clone(),
+                        };
+                        (function_name, event, context)
+                    })
+                    .collect();
+                let results: Vec<BatchResult> = runtime
+                    .run_handler_batch(&events, invocations, run_gc)
+                    .into_iter()
+                    .map(|result| match result {
+                        Ok(result) => BatchResult::Ok(result),
+                        Err(e) => BatchResult::Err(format!("Error: {e:?}")),
+                    })
+                    .collect();
+                return Ok(ReturnValue::String(serde_json::to_string(&results)?));
+            }
+            "run_gc" => runtime.run_gc(),
+            "memory_usage_by_handler" => {
+                let usage = runtime.memory_usage_by_handler();
+                return Ok(ReturnValue::String(serde_json::to_string(&usage)?));
+            }
+            "take_profile" => return Ok(ReturnValue::String(runtime.take_profile())),
+            "RegisterHostModules" => {
+                let host_modules_json: String = ParameterTuple::from_value(args)?;
+                self.register_host_modules(&host_modules_json)?;
+            }
+            "ConfigureRuntime" => {
+                let options_json: String = ParameterTuple::from_value(args)?;
+                self.configure_runtime(&options_json)?;
+            }
+            function_name => {
+                let framed = if let Some(ParameterValue::VecBytes(frame)) = args.first() {
+                    let event = String::from_utf8(wire::decode(frame)?)
+                        .context("The event is not valid UTF-8")?;
+                    args[0] = ParameterValue::String(event);
+                    true
+                } else {
+                    false
+                };
+
+                let (event, run_gc, sequence, seed, traceparent): (String, bool, u64, u64, String) =
+                    ParameterTuple::from_value(args)?;
+                let context = HandlerContext {
+                    sequence,
+                    seed,
+                    traceparent: (!traceparent.is_empty()).then_some(traceparent),
+                };
+                let result =
+                    runtime.run_handler(function_name.to_string(), event, context, run_gc)?;
+                if framed {
+                    let frame = wire::encode(result.as_bytes(), self.wire_compression_min_size);
+                    return Ok(ReturnValue::VecBytes(frame));
+                }
+                return Ok(ReturnValue::String(result));
+            }
+        }
+        Ok(ReturnValue::Void(()))
+    }
+
+    fn register_host_modules(&mut self, host_modules_json: &str) -> anyhow::Result<()> {
+        let host_modules: HashMap<String, Vec<HostFunctionDescriptor>> =
+            serde_json::from_str(host_modules_json).context("Failed to parse host modules JSON")?;
+
+        for (module_name, functions) in host_modules {
+            for HostFunctionDescriptor {
+                name: function_name,
+                cache,
+            } in functions
+            {
+                let function = {
+                    let host_functions = self.host_functions.clone();
+                    let module_name = module_name.clone();
+                    let function_name = function_name.clone();
+                    move |args: String| -> anyhow::Result<String> {
+                        host_functions
+                            .call(
+                                "CallHostJsFunction",
+                                (module_name.clone(), function_name.clone(), args),
+                            )
+                            .map_err(|e| match HostError::from_message(&e.to_string()) {
+                                Some(error) => anyhow::Error::msg(error),
+                                None => anyhow!(
+                                    "Calling host function {module_name:?} {function_name:?} failed: {}",
+                                    host_function_error(&e)
+                                ),
+                            })
+                    }
+                };
+                match cache {
+                    Some(cache) => self.runtime.register_cached_json_host_function(
+                        module_name.clone(),
+                        function_name,
+                        cache.into(),
+                        function,
+                    )?,
+                    None => self.runtime.register_json_host_function(
+                        module_name.clone(),
+                        function_name,
+                        function,
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn configure_runtime(&mut self, options_json: &str) -> anyhow::Result<()> {
+        let options: RuntimeOptions =
+            serde_json::from_str(options_json).context("Failed to parse runtime options JSON")?;
+        let runtime = &mut self.runtime;
+
+        if options.seeded_math_random {
+            runtime.seed_math_random()?;
+        }
+        if options.dynamic_code_disabled {
+            runtime.disable_dynamic_code()?;
+        }
+        if let Some(interval) = options.profile_interval_micros {
+            runtime.enable_profiling(interval)?;
+        }
+        if options.freeze_builtins {
+            runtime.freeze_builtins()?;
+        }
+        if options.per_handler_realms {
+            runtime.set_per_handler_realms();
+        }
+
+        // The runtime is interrupted when it is killed, as well as when it is
+        // cooperatively cancelled.
+        let interrupt = self.interrupt.clone();
+        let host_functions = self.host_functions.clone();
+        let cooperative_cancellation = options.cooperative_cancellation;
+        runtime.set_cancellation_check(move || {
+            interrupt.killed()
+                || (cooperative_cancellation
+                    && host_functions
+                        .call("IsExecutionCancelled", ())
+                        .unwrap_or(false))
+        });
+
+        if let Some(min_size) = options.wire_compression_min_size {
+            self.wire_compression_min_size = min_size;
+        }
+        if let Some(limit) = options.memory_limit {
+            runtime.set_memory_limit(limit);
+        }
+        if let Some(limit) = options.max_stack_size {
+            runtime.set_max_stack_size(limit);
+        }
+        if let Some(threshold) = options.gc_threshold {
+            runtime.set_gc_threshold(threshold);
+        }
+        if let Some(limit) = options.max_result_size {
+            runtime.set_max_result_size(limit);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for InProcessSandbox {
+    fn drop(&mut self) {
+        self.interrupt.dropped.store(true, Ordering::Relaxed);
+    }
+}
+
+// Failed host function calls reach the guest as `HostFunctionError`s.
+fn host_function_error(e: &HyperlightError) -> String {
+    format!("{}: {e}", String::from(ErrorCode::HostFunctionError))
+}
+
+fn parse_modules(modules_json: &str) -> anyhow::Result<hyperlight_js_runtime::PreloadedModules> {
+    // An empty string means the host did not pre-bundle the modules.
+    if modules_json.is_empty() {
+        return Ok(Default::default());
+    }
+    serde_json::from_str(modules_json).context("Failed to parse pre-bundled modules JSON")
+}
+
+/// Interrupts the in-process runtime from another thread, through the
+/// interrupt handler of the JavaScript engine.
+#[derive(Debug, Default)]
+struct InProcessInterruptHandle {
+    running: AtomicBool,
+    killed: AtomicBool,
+    dropped: AtomicBool,
+}
+
+impl InProcessInterruptHandle {
+    fn start(&self) {
+        self.killed.store(false, Ordering::SeqCst);
+        self.running.store(true, Ordering::SeqCst);
+    }
+
+    /// Mark the guest call as over, returning whether it was killed.
+    fn finish(&self) -> bool {
+        self.running.store(false, Ordering::SeqCst);
+        self.killed.swap(false, Ordering::SeqCst)
+    }
+
+    fn killed(&self) -> bool {
+        self.killed.load(Ordering::SeqCst)
+    }
+}
+
+impl InterruptHandle for InProcessInterruptHandle {
+    fn kill(&self) -> bool {
+        if !self.running.load(Ordering::SeqCst) {
+            return false;
+        }
+        self.killed.store(true, Ordering::SeqCst);
+        true
+    }
+
+    #[cfg(all(feature = "gdb", debug_assertions))]
+    fn kill_from_debugger(&self) -> bool {
+        self.kill()
+    }
+
+    fn dropped(&self) -> bool {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+// The deserialization in here has to match the serialization of
+// HostModule in src/hyperlight_js/src/sandbox/host_fn.rs
+#[derive(Deserialize)]
+struct HostFunctionDescriptor {
+    name: String,
+    cache: Option<CacheDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CacheDescriptor {
+    Invocation,
+    Ttl { micros: u64 },
+}
+
+impl From<CacheDescriptor> for HostFunctionCache {
+    fn from(cache: CacheDescriptor) -> Self {
+        match cache {
+            CacheDescriptor::Invocation => HostFunctionCache::Invocation,
+            CacheDescriptor::Ttl { micros } => {
+                HostFunctionCache::Ttl(std::time::Duration::from_micros(micros))
+            }
+        }
+    }
+}
+
+// The deserialization in here has to match the serialization of
+// RuntimeOptions in src/hyperlight_js/src/sandbox/runtime_options.rs
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RuntimeOptions {
+    freeze_builtins: bool,
+    seeded_math_random: bool,
+    cooperative_cancellation: bool,
+    wire_compression_min_size: Option<usize>,
+    memory_limit: Option<usize>,
+    max_stack_size: Option<usize>,
+    gc_threshold: Option<usize>,
+    per_handler_realms: bool,
+    dynamic_code_disabled: bool,
+    profile_interval_micros: Option<u64>,
+    max_result_size: Option<usize>,
+}
+
+// The deserialization in here has to match the serialization of
+// HandlerRegistration in src/hyperlight-js/src/sandbox/js_sandbox.rs
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum HandlerRegistration {
+    Script {
+        name: String,
+        script: String,
+        pwd: String,
+        // The pre-bundled modules as JSON, or an empty string if there are none
+        modules: String,
+    },
+    JsonLogic {
+        name: String,
+        rule: String,
+    },
+}
+
+// The deserialization in here has to match the serialization of
+// Batch in src/hyperlight-js/src/sandbox/loaded_js_sandbox.rs
+#[derive(Deserialize)]
+struct Batch {
+    events: Vec<String>,
+    // `[handler, event index, sequence, seed]` for every invocation
+    invocations: Vec<(String, usize, u64, u64)>,
+    // The traceparent every invocation of the batch is made in
+    traceparent: Option<String>,
+}
+
+// The serialization in here has to match the deserialization of the batch
+// results in src/hyperlight-js/src/sandbox/loaded_js_sandbox.rs
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchResult {
+    Ok(String),
+    Err(String),
+}
@@ -0,0 +1,44 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/// How the handlers of a sandbox are isolated from each other in the guest.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{IsolationMode, SandboxBuilder};
+///
+/// let proto_js_sandbox = SandboxBuilder::new()
+///     .with_isolation_mode(IsolationMode::PerHandler)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IsolationMode {
+    /// All the handlers share one global object and set of built-ins, so a
+    /// global set by a handler is visible to the others.
+    #[default]
+    Shared,
+    /// Every handler is evaluated in its own realm, with its own global
+    /// object and built-ins, so handlers can't see each other's global
+    /// mutations.
+    ///
+    /// Host modules are available in every realm, and the setup script is
+    /// evaluated again in the realm of every handler that imports it. Each
+    /// realm costs some memory in the guest.
+    PerHandler,
+}
@@ -13,96 +13,282 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
 
-use hyperlight_host::sandbox::snapshot::Snapshot;
-use hyperlight_host::{new_error, MultiUseSandbox, Result};
-use tracing::{instrument, Level};
+use hyperlight_host::{new_error, Result};
+use tracing::field::Empty;
+use tracing::{instrument, Level, Span};
 
+use super::backend::{Backend, Checkpoint};
+use super::cancellation::CancellationHandle;
+use super::diagnostics::HandlerInfo;
+use super::js_sandbox_error::{
+    empty_handler_name, handler_already_exists, handler_not_found, no_handlers,
+};
 use super::loaded_js_sandbox::LoadedJSSandbox;
+use super::monitor::{host_calls, MonitorSet, MonitorTask};
+use super::sequence::InvocationSequence;
+use super::settings::SandboxSettings;
+use crate::module_loader::{handler_module_path, ModuleBundler};
 use crate::sandbox::metrics::SandboxMetricsGuard;
-use crate::Script;
+use crate::source_map::{remap_error, SourceMaps};
+use crate::{Script, ScriptKind};
+
+// The name the path of the setup module is made from, like the function name of a handler.
+// This has to match SETUP_MODULE_NAME in src/hyperlight-js-runtime/src/lib.rs
+const SETUP_MODULE_NAME: &str = "__setup__";
 
 /// A Hyperlight Sandbox with a JavaScript run time loaded but no guest code.
 pub struct JSSandbox {
-    pub(super) inner: MultiUseSandbox,
+    pub(super) inner: Backend,
     handlers: HashMap<String, Script>,
-    // Snapshot of state before any handlers are added.
+    // The stages of each pipeline, in order.
+    pipelines: HashMap<String, Vec<String>>,
+    // The setup script, and its pre-bundled modules serialized as JSON, if
+    // pre-bundling is enabled.
+    setup: Option<(Script, String)>,
+    // Pre-bundled modules of each handler, serialized as JSON, if pre-bundling is enabled.
+    bundles: HashMap<String, String>,
+    bundler: Option<Arc<dyn ModuleBundler>>,
+    // Checkpoint of state before any handlers are added.
     // This is used to restore state back to a neutral JSSandbox.
-    snapshot: Arc<Snapshot>,
+    snapshot: Checkpoint,
+    sequence: InvocationSequence,
+    settings: SandboxSettings,
+    cancellation: Option<CancellationHandle>,
     // metric drop guard to manage sandbox metric
     _metric_guard: SandboxMetricsGuard<JSSandbox>,
 }
 
 impl JSSandbox {
-    #[instrument(err(Debug), skip(inner), level=Level::INFO)]
-    pub(super) fn new(mut inner: MultiUseSandbox) -> Result<Self> {
-        let snapshot = inner.snapshot()?;
+    #[instrument(err(Debug), skip(inner, bundler), level=Level::INFO)]
+    pub(super) fn new(
+        mut inner: Backend,
+        sequence: InvocationSequence,
+        settings: SandboxSettings,
+        bundler: Option<Arc<dyn ModuleBundler>>,
+        cancellation: Option<CancellationHandle>,
+    ) -> Result<Self> {
+        let snapshot = inner.checkpoint()?;
+        let metric_guard = SandboxMetricsGuard::new(&settings.labels);
         Ok(Self {
             inner,
             handlers: HashMap::new(),
+            pipelines: HashMap::new(),
+            setup: None,
+            bundles: HashMap::new(),
+            bundler,
             snapshot,
-            _metric_guard: SandboxMetricsGuard::new(),
+            sequence,
+            settings,
+            cancellation,
+            _metric_guard: metric_guard,
         })
     }
 
-    /// Creates a new `JSSandbox` from a `MultiUseSandbox` and a `Snapshot` of state before any handlers were added.
+    /// Creates a new `JSSandbox` from a `Backend` and a `Checkpoint` of state before any handlers were added.
     pub(crate) fn from_loaded(
-        mut loaded: MultiUseSandbox,
-        snapshot: Arc<Snapshot>,
+        mut loaded: Backend,
+        snapshot: Checkpoint,
+        sequence: InvocationSequence,
+        settings: SandboxSettings,
+        bundler: Option<Arc<dyn ModuleBundler>>,
+        cancellation: Option<CancellationHandle>,
     ) -> Result<Self> {
-        loaded.restore(snapshot.clone())?;
+        loaded.rewind(&snapshot)?;
+        let metric_guard = SandboxMetricsGuard::new(&settings.labels);
         Ok(Self {
             inner: loaded,
             handlers: HashMap::new(),
+            pipelines: HashMap::new(),
+            setup: None,
+            bundles: HashMap::new(),
+            bundler,
             snapshot,
-            _metric_guard: SandboxMetricsGuard::new(),
+            sequence,
+            settings,
+            cancellation,
+            _metric_guard: metric_guard,
         })
     }
 
     /// Adds a new handler function to the sandboxes collection of handlers. This Handler will be
     /// available to the host to call once `get_loaded_sandbox` is called.
-    #[instrument(err(Debug), skip(self, script), level=Level::DEBUG)]
+    #[instrument(err(Debug), skip(self, script), level=Level::DEBUG, fields(script_sha256 = Empty, script_path = Empty, script_bytes = Empty))]
     pub fn add_handler<F>(&mut self, function_name: F, script: Script) -> Result<()>
     where
         F: Into<String> + std::fmt::Debug,
     {
+        script.record_provenance(&Span::current());
         let function_name = function_name.into();
         if function_name.is_empty() {
-            return Err(new_error!("Handler name must not be empty"));
+            return Err(empty_handler_name());
         }
-        if self.handlers.contains_key(&function_name) {
-            return Err(new_error!(
-                "Handler already exists for function name: {}",
-                function_name
-            ));
+        if self.handlers.contains_key(&function_name) || self.pipelines.contains_key(&function_name)
+        {
+            return Err(handler_already_exists(&function_name));
+        }
+
+        self.settings
+            .handler_limits
+            .check_add(&self.handlers, &script)?;
+
+        if let (Some(bundler), ScriptKind::JavaScript) = (&self.bundler, script.kind()) {
+            let handler_path = handler_module_path(&function_name, &script_dir(&script));
+            let bundle = bundler.bundle(&handler_path, script.content());
+            self.bundles
+                .insert(function_name.clone(), serde_json::to_string(&bundle)?);
         }
 
         self.handlers.insert(function_name, script);
         Ok(())
     }
 
+    /// Lints the script and adds it as a handler, like [`add_handler`](Self::add_handler).
+    ///
+    /// The handler is added regardless of the lint warnings, which are returned
+    /// so they can be shown to the handler author, see [`Script::lint`].
+    #[cfg(feature = "lint")]
+    pub fn add_handler_with_lint<F>(
+        &mut self,
+        function_name: F,
+        script: Script,
+    ) -> Result<Vec<crate::LintDiagnostic>>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let diagnostics = script.lint();
+        self.add_handler(function_name, script)?;
+        Ok(diagnostics)
+    }
+
+    /// Adds a setup script, doing the initialization shared by the handlers,
+    /// like loading configuration or building lookup tables.
+    ///
+    /// The script is a JavaScript module evaluated once in the guest, before
+    /// the handlers are registered, and every handler can import its exports
+    /// from the `"setup"` specifier. A sandbox has at most one setup script,
+    /// which is kept when handlers are removed or cleared.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// sandbox.add_setup_script(Script::from_content(
+    ///     "export const rates = new Map([['EUR', 1.08], ['GBP', 1.27]]);",
+    /// ))?;
+    /// sandbox.add_handler("convert", Script::from_content(r#"
+    ///     import { rates } from "setup";
+    ///     function handler(event) { return event.amount * rates.get(event.currency); }
+    /// "#))?;
+    /// ```
+    #[instrument(err(Debug), skip(self, script), level=Level::DEBUG, fields(script_sha256 = Empty, script_path = Empty, script_bytes = Empty))]
+    pub fn add_setup_script(&mut self, script: Script) -> Result<()> {
+        script.record_provenance(&Span::current());
+        if self.setup.is_some() {
+            return Err(new_error!("A setup script has already been added"));
+        }
+        if script.kind() != ScriptKind::JavaScript {
+            return Err(new_error!("The setup script must be JavaScript"));
+        }
+
+        // An empty string tells the guest there are no pre-bundled modules
+        let bundle = match &self.bundler {
+            Some(bundler) => {
+                let setup_path = handler_module_path(SETUP_MODULE_NAME, &script_dir(&script));
+                serde_json::to_string(&bundler.bundle(&setup_path, script.content()))?
+            }
+            None => String::new(),
+        };
+        self.setup = Some((script, bundle));
+        Ok(())
+    }
+
+    /// Adds a pipeline of handlers, called like a handler named `function_name`.
+    ///
+    /// The pipeline runs the handlers `stages` in order, in a single call into
+    /// the guest: the first stage gets the event of the call, every following
+    /// stage gets the result of the previous one as its event, and the result
+    /// of the last stage is the result of the call. If a stage fails, the
+    /// pipeline stops and fails with its error. Every stage gets the context
+    /// of the call, so they all draw the same random numbers.
+    ///
+    /// The stages have to be handlers added to the sandbox by the time
+    /// [`get_loaded_sandbox`](Self::get_loaded_sandbox) is called, and can't
+    /// be pipelines themselves.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// sandbox.add_handler("validate", validate_script)?;
+    /// sandbox.add_handler("enrich", enrich_script)?;
+    /// sandbox.add_pipeline("ingest", &["validate", "enrich"])?;
+    /// let mut loaded = sandbox.get_loaded_sandbox()?;
+    /// let result = loaded.handle_event("ingest", event, None)?;
+    /// ```
+    #[instrument(err(Debug), skip(self), level=Level::DEBUG)]
+    pub fn add_pipeline<F, S>(&mut self, function_name: F, stages: &[S]) -> Result<()>
+    where
+        F: Into<String> + std::fmt::Debug,
+        S: AsRef<str> + std::fmt::Debug,
+    {
+        let function_name = function_name.into();
+        if function_name.is_empty() {
+            return Err(empty_handler_name());
+        }
+        if self.handlers.contains_key(&function_name) || self.pipelines.contains_key(&function_name)
+        {
+            return Err(handler_already_exists(&function_name));
+        }
+        if stages.is_empty() {
+            return Err(new_error!("A pipeline must have at least one stage"));
+        }
+        let stages: Vec<String> = stages.iter().map(|s| s.as_ref().to_string()).collect();
+        if let Some(stage) = stages
+            .iter()
+            .find(|stage| self.pipelines.contains_key(*stage))
+        {
+            return Err(new_error!(
+                "The pipeline stage {} is a pipeline itself",
+                stage
+            ));
+        }
+        self.pipelines.insert(function_name, stages);
+        Ok(())
+    }
+
     /// Removes a handler function from the sandboxes collection of handlers.
     #[instrument(err(Debug), skip(self), level=Level::DEBUG)]
     pub fn remove_handler(&mut self, function_name: &str) -> Result<()> {
         if function_name.is_empty() {
-            return Err(new_error!("Handler name must not be empty"));
+            return Err(empty_handler_name());
+        }
+        self.bundles.remove(function_name);
+        if self.pipelines.remove(function_name).is_some() {
+            return Ok(());
         }
         match self.handlers.remove(function_name) {
             Some(_) => Ok(()),
-            None => Err(new_error!(
-                "Handler does not exist for function name: {}",
-                function_name
-            )),
+            None => Err(handler_not_found(function_name)),
         }
     }
 
-    /// Clears all handlers from the sandbox.
+    /// Clears all handlers and pipelines from the sandbox.
     #[instrument(skip_all, level=Level::TRACE)]
     pub fn clear_handlers(&mut self) {
         self.handlers.clear();
+        self.pipelines.clear();
+        self.bundles.clear();
+    }
+
+    /// Returns the handlers added to the sandbox, sorted by name, with the
+    /// size and SHA-256 digest of their scripts. Pipelines are not listed.
+    ///
+    /// The handlers are only compiled when the sandbox is loaded, see
+    /// [`LoadedJSSandbox::handlers`].
+    pub fn handlers(&self) -> Vec<HandlerInfo> {
+        HandlerInfo::list(&self.handlers, &HashSet::new())
     }
 
     /// Returns whether the sandbox is currently poisoned.
@@ -115,6 +301,13 @@ impl JSSandbox {
         self.inner.poisoned()
     }
 
+    /// Returns whether the JavaScript runtime runs in a Hyperlight micro VM,
+    /// rather than in-process without any isolation, see the `in-process`
+    /// feature.
+    pub fn is_isolated(&self) -> bool {
+        self.inner.is_isolated()
+    }
+
     #[cfg(test)]
     fn get_number_of_handlers(&self) -> usize {
         self.handlers.len()
@@ -124,23 +317,125 @@ impl JSSandbox {
     #[instrument(err(Debug), skip_all, level=Level::TRACE)]
     pub fn get_loaded_sandbox(mut self) -> Result<LoadedJSSandbox> {
         if self.handlers.is_empty() {
-            return Err(new_error!("No handlers have been added to the sandbox"));
+            return Err(no_handlers());
         }
 
-        let handlers = self.handlers.clone();
-        for (function_name, script) in handlers {
+        for (function_name, stages) in &self.pipelines {
+            if let Some(stage) = stages
+                .iter()
+                .find(|stage| !self.handlers.contains_key(*stage))
+            {
+                return Err(new_error!(
+                    "The pipeline {} has no handler for its stage {}",
+                    function_name,
+                    stage
+                ));
+            }
+        }
+
+        let source_maps = handler_source_maps(&self.handlers);
+
+        let call = self
+            .cancellation
+            .as_ref()
+            .map(CancellationHandle::start_call);
+        if let Some((script, modules)) = &self.setup {
             let content = script.content().to_owned();
+            self.inner
+                .call::<()>(
+                    "register_setup_script",
+                    (content, script_dir(script), modules.clone()),
+                )
+                .map_err(|e| match &self.cancellation {
+                    Some(cancellation) => cancellation.map_error(e),
+                    None => e,
+                })?;
+        }
+
+        // All handlers are registered with a single guest call, rather than
+        // one call per handler.
+        let handlers = self.handlers.clone();
+        let registrations: Vec<_> = handlers
+            .iter()
+            .map(|(function_name, script)| {
+                let modules = self.bundles.get(function_name).map(String::as_str);
+                HandlerRegistration::new(function_name, script, modules.unwrap_or_default())
+            })
+            .collect();
+        let registrations = serde_json::to_string(&registrations)?;
+        let compiled = tracing::debug_span!("register_handlers", handlers = handlers.len())
+            .in_scope(|| {
+                host_calls::reset_call_count();
+                self.inner
+                    .call::<String>("register_handlers", registrations)
+                    .map_err(|e| match &self.cancellation {
+                        Some(cancellation) => cancellation.map_error(e),
+                        None => e,
+                    })
+                    .map_err(|e| remap_error(e, &source_maps))
+            })?;
+        let compiled: HashSet<String> = serde_json::from_str(&compiled)?;
 
-            let path = script
-                .base_path()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default();
+        // Pipelines are registered last, as the guest checks that their
+        // stages are registered.
+        for (function_name, stages) in &self.pipelines {
+            // The serialization in here has to match the deserialization of
+            // the stages in src/hyperlight-js-runtime/src/main/hyperlight.rs
+            let stages = serde_json::to_string(stages)?;
             self.inner
-                .call::<()>("register_handler", (function_name, content, path))?;
+                .call::<()>("register_pipeline", (function_name.clone(), stages))
+                .map_err(|e| match &self.cancellation {
+                    Some(cancellation) => cancellation.map_error(e),
+                    None => e,
+                })
+                .map_err(|e| remap_error(e, &source_maps))?;
         }
 
-        LoadedJSSandbox::new(self.inner, self.snapshot)
+        drop(call);
+
+        LoadedJSSandbox::new(
+            self.inner,
+            self.snapshot,
+            self.sequence,
+            self.settings,
+            self.bundler,
+            self.cancellation,
+            handlers,
+        )
+        .map(|loaded| loaded.with_compiled(compiled))
+    }
+    /// Creates a new `LoadedJSSandbox` like [`get_loaded_sandbox`](Self::get_loaded_sandbox),
+    /// with the loading of the handlers monitored by `monitor`.
+    ///
+    /// Loading evaluates the top level code of every handler script and of the
+    /// modules they import, so this caps the worst-case cost of loading a
+    /// sandbox. If the monitor fires, loading is terminated and an error is
+    /// returned. As with `handle_event_with_monitor`, if the monitor fails to
+    /// initialize, the handlers are never loaded.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// use hyperlight_js::WallClockMonitor;
+    /// use std::time::Duration;
+    ///
+    /// let monitor = WallClockMonitor::new(Duration::from_millis(500))?;
+    /// let loaded = sandbox.get_loaded_sandbox_with_monitor(&monitor)?;
+    /// ```
+    #[instrument(err(Debug), skip_all, level=Level::TRACE)]
+    pub fn get_loaded_sandbox_with_monitor<M: MonitorSet>(
+        self,
+        monitor: &M,
+    ) -> Result<LoadedJSSandbox> {
+        let _monitor_task = MonitorTask::start(
+            monitor,
+            self.inner.interrupt_handle(),
+            self.cancellation.clone(),
+            self.settings.labels.clone(),
+        )?;
+        self.get_loaded_sandbox()
     }
+
     /// Generate a crash dump of the current state of the VM underlying this sandbox.
     ///
     /// Creates an ELF core dump file that can be used for debugging. The dump
@@ -182,10 +477,70 @@ impl JSSandbox {
     }
 }
 
+// The source maps of the handlers, keyed by the path of their module in the guest.
+pub(super) fn handler_source_maps(handlers: &HashMap<String, Script>) -> SourceMaps {
+    handlers
+        .iter()
+        .filter_map(|(function_name, script)| {
+            let source_map = script.source_map()?.clone();
+            Some((
+                handler_module_path(function_name, &script_dir(script)),
+                source_map,
+            ))
+        })
+        .collect()
+}
+
+// The serialization in here has to match the deserialization of
+// HandlerRegistration in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(super) enum HandlerRegistration<'a> {
+    Script {
+        name: &'a str,
+        script: &'a str,
+        pwd: String,
+        // The pre-bundled modules as JSON, or an empty string if there are none
+        modules: &'a str,
+    },
+    JsonLogic {
+        name: &'a str,
+        rule: &'a str,
+    },
+}
+
+impl<'a> HandlerRegistration<'a> {
+    // The registration of the handler `name` running `script`, with its
+    // pre-bundled modules serialized as JSON in `modules`.
+    pub(super) fn new(name: &'a str, script: &'a Script, modules: &'a str) -> Self {
+        match script.kind() {
+            ScriptKind::JsonLogic => HandlerRegistration::JsonLogic {
+                name,
+                rule: script.content(),
+            },
+            _ => HandlerRegistration::Script {
+                name,
+                script: script.content(),
+                pwd: script_dir(script),
+                modules,
+            },
+        }
+    }
+}
+
+// The directory the guest resolves the imports of a handler script relative to.
+pub(super) fn script_dir(script: &Script) -> String {
+    script
+        .base_path()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
 impl Debug for JSSandbox {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("JSSandbox")
             .field("handlers", &self.handlers)
+            .field("pipelines", &self.pipelines)
             .finish()
     }
 }
@@ -229,6 +584,46 @@ mod tests {
         assert_eq!(sandbox.get_number_of_handlers(), 0);
     }
 
+    #[test]
+    fn test_max_handlers() {
+        let proto_js_sandbox = SandboxBuilder::new().with_max_handlers(2).build().unwrap();
+        let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+        sandbox.add_handler("handler1", "script1".into()).unwrap();
+        sandbox.add_handler("handler2", "script2".into()).unwrap();
+
+        let err = sandbox
+            .add_handler("handler3", "script3".into())
+            .unwrap_err();
+        assert!(err.to_string().contains("maximum number of handlers"));
+
+        // Removing a handler makes room for another one
+        sandbox.remove_handler("handler1").unwrap();
+        sandbox.add_handler("handler3", "script3".into()).unwrap();
+        assert_eq!(sandbox.get_number_of_handlers(), 2);
+    }
+
+    #[test]
+    fn test_max_script_bytes() {
+        // 20 bytes
+        let script = "function handler(){}";
+
+        let proto_js_sandbox = SandboxBuilder::new()
+            .with_max_script_bytes(30)
+            .build()
+            .unwrap();
+        let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+        sandbox.add_handler("handler1", script.into()).unwrap();
+
+        let err = sandbox.add_handler("handler2", script.into()).unwrap_err();
+        assert!(err.to_string().contains("over the limit of 30 bytes"));
+
+        // The limit still applies after the handlers are loaded and unloaded
+        let loaded = sandbox.get_loaded_sandbox().unwrap();
+        let mut sandbox = loaded.unload().unwrap();
+        sandbox.add_handler("handler1", script.into()).unwrap();
+        assert!(sandbox.add_handler("handler2", script.into()).is_err());
+    }
+
     #[test]
     fn test_get_loaded_sandbox() {
         let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
@@ -0,0 +1,327 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_host::HyperlightError;
+
+use super::cancellation::CANCELLED_ERROR;
+use super::event_size::EventTooLarge;
+use super::out_of_memory::GuestOutOfMemory;
+use super::result_size::ResultTooLarge;
+
+const EMPTY_HANDLER_NAME: &str = "Handler name must not be empty";
+const HANDLER_EXISTS: &str = "Handler already exists for function name: ";
+const HANDLER_NOT_FOUND: &str = "Handler does not exist for function name: ";
+const NO_HANDLERS: &str = "No handlers have been added to the sandbox";
+// The names the runtime starts the messages of its errors with.
+// These have to match the constants of the same names in
+// src/hyperlight-js-runtime/src/lib.rs
+const UNKNOWN_HANDLER_ERROR: &str = "UnknownHandler";
+const MODULE_ERROR: &str = "ModuleResolution";
+// The message of `UNKNOWN_HANDLER_ERROR` before the name of the handler.
+const NO_HANDLER_REGISTERED: &str = "No handler registered for function ";
+const RUNTIME_ERROR: &str = "Runtime error";
+// hyperlight-guest formats the errors of guest functions as `Error: {error:?}`,
+// and the `Debug` format of anyhow lists the causes of an error after it.
+const GUEST_ERROR_PREFIX: &str = "Error: ";
+const CAUSES: &str = "\nCaused by:\n";
+
+/// The errors of hyperlight-js, to match on their kind rather than on their
+/// message.
+///
+/// The API returns [`HyperlightError`]s, which this is converted from with
+/// [`From`]. The errors of the guest are recognized by the names the runtime
+/// starts their messages with, rather than by their wording. The errors of
+/// hyperlight-js that are not recognized, and the errors of Hyperlight
+/// itself, are kept as [`JsSandboxError::Other`].
+///
+/// # Example
+///
+/// ```text
+/// match loaded_sandbox.handle_event(name, event, None).map_err(JsSandboxError::from) {
+///     Ok(result) => respond(200, result),
+///     Err(JsSandboxError::UnknownHandler(_)) => respond(404, "no such handler"),
+///     Err(JsSandboxError::GuestException(message)) => respond(422, message),
+///     Err(err) => respond(500, err.to_string()),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsSandboxError {
+    /// A handler name is empty.
+    EmptyHandlerName,
+    /// There is no handler with this name.
+    UnknownHandler(String),
+    /// There is already a handler with this name.
+    HandlerAlreadyExists(String),
+    /// The sandbox was loaded without any handlers.
+    NoHandlers,
+    /// The event is not valid JSON. The checks of the events are the only
+    /// errors returned as a [`HyperlightError::JsonConversionFailure`].
+    InvalidEvent(serde_json::Error),
+    /// A module imported by a handler could not be resolved or loaded. This
+    /// is the message of the guest.
+    ModuleResolution(String),
+    /// A handler threw an exception, or the runtime failed to evaluate it.
+    /// This is the message of the guest, with the exception and its stack.
+    GuestException(String),
+    /// The guest ran out of memory.
+    GuestOutOfMemory(GuestOutOfMemory),
+    /// The handler was cancelled by the host, by a monitor or a kill.
+    Cancelled,
+    /// The sandbox is poisoned, and must be restored or unloaded.
+    Poisoned,
+    /// The event is too large for the input buffer.
+    EventTooLarge(EventTooLarge),
+    /// The result of a handler is over the maximum result size.
+    ResultTooLarge(ResultTooLarge),
+    /// Any other error.
+    Other(HyperlightError),
+}
+
+impl From<HyperlightError> for JsSandboxError {
+    fn from(err: HyperlightError) -> Self {
+        if let Some(too_large) = EventTooLarge::from_error(&err) {
+            return Self::EventTooLarge(too_large);
+        }
+        if let Some(too_large) = ResultTooLarge::from_error(&err) {
+            return Self::ResultTooLarge(too_large);
+        }
+        if let Some(out_of_memory) = GuestOutOfMemory::from_error(&err) {
+            return Self::GuestOutOfMemory(out_of_memory);
+        }
+        if let HyperlightError::GuestError(_, message) = &err
+            && let Some(message) = guest_error_message(message, UNKNOWN_HANDLER_ERROR)
+            && let Some(name) = message.strip_prefix(NO_HANDLER_REGISTERED)
+        {
+            return Self::UnknownHandler(name.to_string());
+        }
+        match err {
+            HyperlightError::Error(message) => {
+                if message == EMPTY_HANDLER_NAME {
+                    Self::EmptyHandlerName
+                } else if message == NO_HANDLERS {
+                    Self::NoHandlers
+                } else if let Some(name) = message.strip_prefix(HANDLER_EXISTS) {
+                    Self::HandlerAlreadyExists(name.to_string())
+                } else if let Some(name) = message.strip_prefix(HANDLER_NOT_FOUND) {
+                    Self::UnknownHandler(name.to_string())
+                } else {
+                    Self::Other(HyperlightError::Error(message))
+                }
+            }
+            HyperlightError::GuestError(_, message)
+                if guest_error_message(&message, MODULE_ERROR).is_some() =>
+            {
+                Self::ModuleResolution(message)
+            }
+            HyperlightError::GuestError(_, message)
+                if guest_error_message(&message, RUNTIME_ERROR).is_some() =>
+            {
+                Self::GuestException(message)
+            }
+            HyperlightError::JsonConversionFailure(err) => Self::InvalidEvent(err),
+            err if is_cancelled(&err) => Self::Cancelled,
+            HyperlightError::PoisonedSandbox => Self::Poisoned,
+            err => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for JsSandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyHandlerName => f.write_str(EMPTY_HANDLER_NAME),
+            Self::UnknownHandler(name) => write!(f, "{HANDLER_NOT_FOUND}{name}"),
+            Self::HandlerAlreadyExists(name) => write!(f, "{HANDLER_EXISTS}{name}"),
+            Self::NoHandlers => f.write_str(NO_HANDLERS),
+            Self::InvalidEvent(err) => write!(f, "The event is not valid JSON: {err}"),
+            Self::ModuleResolution(message) | Self::GuestException(message) => f.write_str(message),
+            Self::GuestOutOfMemory(out_of_memory) => out_of_memory.fmt(f),
+            Self::Cancelled => f.write_str("The handler was cancelled by the host"),
+            Self::Poisoned => f.write_str("The sandbox is poisoned"),
+            Self::EventTooLarge(too_large) => too_large.fmt(f),
+            Self::ResultTooLarge(too_large) => too_large.fmt(f),
+            Self::Other(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for JsSandboxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidEvent(err) => Some(err),
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The message of the error with the name `code` in the message of a guest
+/// error, or in one of its causes, or `None` if there is none.
+///
+/// The runtime starts the message of its errors with their name, as in
+/// `UnknownHandler: No handler registered for function handler`.
+pub(crate) fn guest_error_message<'a>(message: &'a str, code: &str) -> Option<&'a str> {
+    let message = message.strip_prefix(GUEST_ERROR_PREFIX)?;
+    let (error, causes) = message.split_once(CAUSES).unwrap_or((message, ""));
+    let error = error.lines().next().unwrap_or_default();
+    // A single cause is indented, while several are numbered as well.
+    let causes = causes.lines().map(|cause| {
+        let cause = cause.trim_start();
+        match cause.split_once(": ") {
+            Some((index, cause)) if index.parse::<usize>().is_ok() => cause,
+            _ => cause,
+        }
+    });
+    std::iter::once(error)
+        .chain(causes)
+        .find_map(|error| error.strip_prefix(code)?.strip_prefix(": "))
+}
+
+/// Whether `err` is the error of a handler cancelled by the host, whether it
+/// was killed or failed with the error of the guest for cancelled handlers.
+pub(crate) fn is_cancelled(err: &HyperlightError) -> bool {
+    match err {
+        HyperlightError::ExecutionCanceledByHost() => true,
+        HyperlightError::GuestError(_, message) => {
+            guest_error_message(message, CANCELLED_ERROR).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// The error of a handler name that is empty.
+pub(crate) fn empty_handler_name() -> HyperlightError {
+    HyperlightError::Error(EMPTY_HANDLER_NAME.to_string())
+}
+
+/// The error of adding a handler whose name is taken.
+pub(crate) fn handler_already_exists(name: &str) -> HyperlightError {
+    HyperlightError::Error(format!("{HANDLER_EXISTS}{name}"))
+}
+
+/// The error of removing a handler that doesn't exist.
+pub(crate) fn handler_not_found(name: &str) -> HyperlightError {
+    HyperlightError::Error(format!("{HANDLER_NOT_FOUND}{name}"))
+}
+
+/// The error of loading a sandbox without handlers.
+pub(crate) fn no_handlers() -> HyperlightError {
+    HyperlightError::Error(NO_HANDLERS.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+
+    use super::*;
+
+    fn guest_error(message: &str) -> HyperlightError {
+        HyperlightError::GuestError(ErrorCode::GuestError, message.to_string())
+    }
+
+    #[test]
+    fn test_from_hyperlight_error() {
+        assert!(matches!(
+            JsSandboxError::from(empty_handler_name()),
+            JsSandboxError::EmptyHandlerName
+        ));
+        assert!(matches!(
+            JsSandboxError::from(handler_already_exists("a")),
+            JsSandboxError::HandlerAlreadyExists(name) if name == "a"
+        ));
+        assert!(matches!(
+            JsSandboxError::from(handler_not_found("b")),
+            JsSandboxError::UnknownHandler(name) if name == "b"
+        ));
+        assert!(matches!(
+            JsSandboxError::from(no_handlers()),
+            JsSandboxError::NoHandlers
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: UnknownHandler: No handler registered for function c\n\nStack backtrace:"
+            )),
+            JsSandboxError::UnknownHandler(name) if name == "c"
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: ModuleResolution: Error resolving module './a.js' from './h.js'"
+            )),
+            JsSandboxError::ModuleResolution(_)
+        ));
+        // The error of a pipeline stage is the cause of the error of the pipeline
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: In stage s of the pipeline p\n\nCaused by:\n    0: Runtime error: Exception(\n    1: boom"
+            )),
+            JsSandboxError::GuestException(message) if message.contains("pipeline p")
+        ));
+        // Only the name of the error counts, not its wording
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: Runtime error: Exception(Exception { message: Some(\"Error resolving module './a.js'\") })"
+            )),
+            JsSandboxError::GuestException(_)
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error("Error: boom: UnknownHandler: c")),
+            JsSandboxError::Other(_)
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: Runtime error: Exception(Exception { message: Some(\"boom\") })"
+            )),
+            JsSandboxError::GuestException(message) if message.contains("boom")
+        ));
+        assert!(matches!(
+            JsSandboxError::from(HyperlightError::ExecutionCanceledByHost()),
+            JsSandboxError::Cancelled
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error(
+                "Error: ExecutionCancelled: the handler was cancelled by the host"
+            )),
+            JsSandboxError::Cancelled
+        ));
+        assert!(matches!(
+            JsSandboxError::from(HyperlightError::PoisonedSandbox),
+            JsSandboxError::Poisoned
+        ));
+        assert!(matches!(
+            JsSandboxError::from(HyperlightError::Error("boom".to_string())),
+            JsSandboxError::Other(HyperlightError::Error(message)) if message == "boom"
+        ));
+        assert!(matches!(
+            JsSandboxError::from(guest_error("Error: boom")),
+            JsSandboxError::Other(HyperlightError::GuestError(_, message)) if message == "Error: boom"
+        ));
+    }
+
+    #[test]
+    fn test_display_matches_the_error() {
+        for err in [
+            empty_handler_name(),
+            handler_already_exists("a"),
+            handler_not_found("b"),
+            no_handlers(),
+        ] {
+            let message = err.to_string();
+            assert_eq!(JsSandboxError::from(err).to_string(), message);
+        }
+    }
+}
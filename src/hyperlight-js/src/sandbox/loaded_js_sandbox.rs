@@ -13,61 +13,151 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher, RandomState};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+use hyperlight_host::func::{ParameterTuple, SupportedReturnType};
 use hyperlight_host::hypervisor::InterruptHandle;
 use hyperlight_host::sandbox::snapshot::Snapshot;
 use hyperlight_host::HyperlightError::{self, JsonConversionFailure};
-use hyperlight_host::{MultiUseSandbox, Result};
-use tokio::task::JoinHandle;
-use tracing::{instrument, Level};
-
-use super::js_sandbox::JSSandbox;
-use super::metrics::{METRIC_SANDBOX_LOADS, METRIC_SANDBOX_UNLOADS};
-use super::monitor::runtime::get_monitor_runtime;
-use super::monitor::MonitorSet;
+use hyperlight_host::{new_error, Result};
+use tracing::field::Empty;
+use tracing::{instrument, Level, Span};
+
+use super::backend::{Backend, Checkpoint};
+use super::call_context::CallContext;
+use super::cancellation::CancellationHandle;
+use super::diagnostics::{
+    crashdump_dir, ConfigurationInfo, Diagnostics, HandlerInfo, RecentInvocations, RuntimeInfo,
+};
+use super::event_size::check_event_size;
+use super::js_sandbox::{handler_source_maps, script_dir, HandlerRegistration, JSSandbox};
+use super::js_sandbox_error::{empty_handler_name, handler_not_found};
+use super::memory_stats::{HeapReport, MemoryStats};
+use super::metrics::{record_guest_heap_bytes, record_sandbox_load, record_sandbox_unload};
+use super::monitor::{host_calls, MonitorSet, MonitorTask};
+use super::poison_reason::PoisonReason;
+use super::retry::{is_transient, restore_failed, Retried, RetryPolicy};
+use super::sequence::InvocationSequence;
+use super::settings::SandboxSettings;
+use super::trace_context;
+use super::wire_compression::{decode_result, encode_event};
+use crate::module_loader::{handler_module_path, ModuleBundler};
 #[cfg(feature = "function_call_metrics")]
 use crate::sandbox::metrics::EventHandlerMetricGuard;
 use crate::sandbox::metrics::SandboxMetricsGuard;
+use crate::source_map::{remap_error, SourceMaps};
+use crate::{Script, ScriptKind};
+
+// The guest function running a batch of invocations, see `call_batch`.
+const RUN_HANDLER_BATCH: &str = "run_handler_batch";
 
 /// A Hyperlight Sandbox with a JavaScript run time loaded and guest JavaScript handlers loaded.
 pub struct LoadedJSSandbox {
-    inner: MultiUseSandbox,
-    // Snapshot of state before the sandbox was loaded and before any handlers were added.
+    inner: Backend,
+    // Checkpoint of state before the sandbox was loaded and before any handlers were added.
     // This is used to restore state back to a JSSandbox.
-    snapshot: Arc<Snapshot>,
+    snapshot: Checkpoint,
+    sequence: InvocationSequence,
+    settings: SandboxSettings,
+    bundler: Option<Arc<dyn ModuleBundler>>,
+    cancellation: Option<CancellationHandle>,
+    // The loaded handlers, to record their provenance in traces.
+    handlers: HashMap<String, Script>,
+    // The handlers the guest reported as compiled.
+    compiled: HashSet<String>,
+    // Source maps of the loaded handlers, used to remap the locations in errors.
+    source_maps: SourceMaps,
+    // The most recent invocations, for diagnostics.
+    recent_invocations: RecentInvocations,
+    // Why the last guest call that poisoned the sandbox failed.
+    poison_reason: Option<PoisonReason>,
+    // Whether the last guest call poisoned the sandbox, even if it has been
+    // recovered since.
+    last_call_poisoned: bool,
+    // Checkpoint of the state right after the handlers were loaded, restored
+    // when a call poisons the sandbox if auto-recovery is enabled.
+    baseline: Option<Checkpoint>,
+    // The number of calls since garbage was last collected, for the GC policy.
+    calls_since_gc: u32,
     // metric drop guard to manage sandbox metric
     _metric_guard: SandboxMetricsGuard<LoadedJSSandbox>,
 }
 
-/// RAII guard that aborts a spawned monitor task on drop.
-///
-/// Wraps a tokio `JoinHandle` to ensure the monitor task is cancelled when
-/// the guard goes out of scope — whether that's after normal completion or
-/// on early return. Keeps the spawn-abort lifecycle in one place rather than
-/// requiring manual `abort()` calls at each exit point.
-struct MonitorTask(JoinHandle<()>);
-
-impl Drop for MonitorTask {
-    fn drop(&mut self) {
-        self.0.abort();
-    }
-}
-
 impl LoadedJSSandbox {
     #[instrument(err(Debug), skip_all, level=Level::INFO)]
-    pub(super) fn new(inner: MultiUseSandbox, snapshot: Arc<Snapshot>) -> Result<LoadedJSSandbox> {
-        metrics::counter!(METRIC_SANDBOX_LOADS).increment(1);
+    pub(super) fn new(
+        mut inner: Backend,
+        snapshot: Checkpoint,
+        sequence: InvocationSequence,
+        settings: SandboxSettings,
+        bundler: Option<Arc<dyn ModuleBundler>>,
+        cancellation: Option<CancellationHandle>,
+        handlers: HashMap<String, Script>,
+    ) -> Result<LoadedJSSandbox> {
+        record_sandbox_load();
+        let source_maps = handler_source_maps(&handlers);
+        // The baseline is not recorded in the invocation sequence: restoring
+        // it recovers the sandbox without rolling the sequence back.
+        let baseline = if settings.auto_recover {
+            Some(inner.checkpoint()?)
+        } else {
+            None
+        };
+        settings.observe(|observer| observer.on_loaded());
+        let metric_guard = SandboxMetricsGuard::new(&settings.labels);
         Ok(LoadedJSSandbox {
             inner,
             snapshot,
-            _metric_guard: SandboxMetricsGuard::new(),
+            sequence,
+            settings,
+            bundler,
+            cancellation,
+            handlers,
+            compiled: HashSet::new(),
+            source_maps,
+            recent_invocations: RecentInvocations::default(),
+            poison_reason: None,
+            last_call_poisoned: false,
+            baseline,
+            calls_since_gc: 0,
+            _metric_guard: metric_guard,
         })
     }
 
+    /// Records the handlers the guest reported as compiled when it loaded
+    /// them.
+    pub(super) fn with_compiled(mut self, compiled: HashSet<String>) -> Self {
+        self.compiled = compiled;
+        self
+    }
+
     /// Handles an event by calling the specified function with the event data.
-    #[instrument(err(Debug), skip(self, event, gc), level=Level::INFO)]
+    ///
+    /// The handler is called as `handler(event, context)`, where
+    /// `context.sequence` is the sequence number of this invocation and
+    /// `context.seed` is a random seed, as a hex string, from which the
+    /// `random` module draws its numbers. The seed is recorded in the
+    /// [diagnostics](Self::collect_diagnostics), to replay the invocation with
+    /// [`handle_event_with_seed`](Self::handle_event_with_seed).
+    ///
+    /// If `gc` is `None`, the [`GcPolicy`](crate::GcPolicy) of the sandbox
+    /// decides whether to run garbage collection after the call.
+    ///
+    /// If the handler throws and its script has a source map attached, the
+    /// locations in the error are remapped to the original sources.
+    ///
+    /// The span of the call records the SHA-256 digest, source path and size
+    /// of the handler script, to correlate traces with the handler code that ran.
+    ///
+    /// If the sandbox was built with
+    /// [`SandboxBuilder::with_default_monitors`](crate::SandboxBuilder::with_default_monitors),
+    /// the call is guarded by the default monitors, with the same semantics as
+    /// [`handle_event_with_monitor`](Self::handle_event_with_monitor).
     pub fn handle_event<F>(
         &mut self,
         func_name: F,
@@ -77,30 +167,676 @@ impl LoadedJSSandbox {
     where
         F: Into<String> + std::fmt::Debug,
     {
-        // check that this string is a valid JSON
+        self.call_with_default_monitors(func_name, event, None, gc)
+    }
+
+    /// Handles an event like [`handle_event`](Self::handle_event), with the
+    /// given seed instead of a random one.
+    ///
+    /// Handlers that only draw their random numbers from the `random` module
+    /// draw the same numbers when called with the same seed, so an invocation
+    /// can be replayed with the seed recorded in the diagnostics.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let seed = loaded_sandbox.collect_diagnostics().recent_invocations[0].seed;
+    /// let replayed = loaded_sandbox.handle_event_with_seed("handler", event, seed, None)?;
+    /// ```
+    pub fn handle_event_with_seed<F>(
+        &mut self,
+        func_name: F,
+        event: String,
+        seed: u64,
+        gc: Option<bool>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        self.call_with_default_monitors(func_name, event, Some(seed), gc)
+    }
+
+    /// Handles an event like [`handle_event`](Self::handle_event), making
+    /// `context` available to the host functions the handler calls through
+    /// [`CallContext::current`], so they can tell which tenant or request
+    /// they are serving.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let context = CallContext::new(serde_json::json!({"tenant": "acme"}));
+    /// let result = loaded_sandbox.handle_event_with_context("handler", event, context, None)?;
+    /// ```
+    pub fn handle_event_with_context<F>(
+        &mut self,
+        func_name: F,
+        event: String,
+        context: CallContext,
+        gc: Option<bool>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let _context = CallContext::enter(Some(context));
+        self.call_with_default_monitors(func_name, event, None, gc)
+    }
+
+    fn call_with_default_monitors<F>(
+        &mut self,
+        func_name: F,
+        event: String,
+        seed: Option<u64>,
+        gc: Option<bool>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        check_event(&event)?;
+        self.call_valid_with_default_monitors(func_name, event, seed, gc)
+    }
 
-        let _json_val: serde_json::Value =
-            serde_json::from_str(&event).map_err(JsonConversionFailure)?;
+    /// Handles an event like [`handle_event`](Self::handle_event), taking
+    /// and returning JSON values rather than strings.
+    ///
+    /// As a [`serde_json::Value`] is always valid JSON, the event is
+    /// serialized once for the guest, without the extra parse
+    /// [`handle_event`](Self::handle_event) does to check it, which saves a
+    /// pass over large events.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let result = loaded_sandbox.handle_event_value("handler", &json!({"n": 1}), None)?;
+    /// assert_eq!(result["n"], 1);
+    /// ```
+    pub fn handle_event_value<F>(
+        &mut self,
+        func_name: F,
+        event: &serde_json::Value,
+        gc: Option<bool>,
+    ) -> Result<serde_json::Value>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let result =
+            self.call_valid_with_default_monitors(func_name, event.to_string(), None, gc)?;
+        serde_json::from_str(&result)
+            .map_err(|e| new_error!("The result of the handler is not valid JSON: {}", e))
+    }
 
-        let should_gc = gc.unwrap_or(true);
+    /// Handles an event like [`handle_event`](Self::handle_event), retrying
+    /// the handler when it fails transiently, as decided by `policy`.
+    ///
+    /// An invocation fails transiently when it is cancelled by the host, e.g.
+    /// by one of the default monitors, or when it poisons the sandbox. Before
+    /// retrying, a poisoned sandbox is restored to its baseline: the state
+    /// right after the handlers were loaded if the sandbox was built with
+    /// [`SandboxBuilder::with_auto_recover`](crate::SandboxBuilder::with_auto_recover),
+    /// or else the state before the first attempt. Every attempt is a
+    /// separate invocation, with its own sequence number.
+    ///
+    /// The result reports how many attempts it took. If the last attempt
+    /// fails, or an attempt fails with an error that is not transient, the
+    /// error of that attempt is returned. So is it, with the failure as
+    /// context, if the sandbox can't be restored to retry it.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let policy = RetryPolicy::new(3);
+    /// let retried = loaded_sandbox.handle_event_with_retry(&policy, "handler", event, None)?;
+    /// if retried.attempts > 1 {
+    ///     tracing::warn!(attempts = retried.attempts, "The handler was retried");
+    /// }
+    /// ```
+    pub fn handle_event_with_retry<F>(
+        &mut self,
+        policy: &RetryPolicy,
+        func_name: F,
+        event: String,
+        gc: Option<bool>,
+    ) -> Result<Retried<String>>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        check_event(&event)?;
+        let func_name = func_name.into();
+        // Without auto-recovery, the state before the first attempt is only
+        // needed if the handler may be retried.
+        let baseline = match &self.baseline {
+            Some(baseline) => Some(baseline.clone()),
+            None if policy.max_attempts() > 1 => Some(self.inner.checkpoint()?),
+            None => None,
+        };
+        let mut attempt = 1;
+        loop {
+            let result =
+                self.call_valid_with_default_monitors(func_name.as_str(), event.clone(), None, gc);
+            let error = match result {
+                Ok(result) => {
+                    return Ok(Retried {
+                        result,
+                        attempts: attempt,
+                    })
+                }
+                Err(e)
+                    if attempt < policy.max_attempts()
+                        && is_transient(&e, self.last_call_poisoned) =>
+                {
+                    tracing::warn!(handler = %func_name, attempt, "Retrying the handler: {}", e);
+                    e
+                }
+                Err(e) => return Err(e),
+            };
+            if self.poisoned()
+                && let Some(baseline) = &baseline
+            {
+                if let Err(e) = self.inner.rewind(baseline) {
+                    return Err(restore_failed(error, e));
+                }
+                self.poison_reason = None;
+                self.settings.observe(|observer| observer.on_restored());
+            }
+            attempt += 1;
+            std::thread::sleep(policy.backoff(attempt));
+        }
+    }
+
+    /// Calls the handler with an event known to be valid JSON, guarded by the
+    /// default monitors.
+    fn call_valid_with_default_monitors<F>(
+        &mut self,
+        func_name: F,
+        event: String,
+        seed: Option<u64>,
+        gc: Option<bool>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let monitor_task = self
+            .settings
+            .default_monitors
+            .clone()
+            .map(|monitor| {
+                MonitorTask::start(
+                    monitor.as_ref(),
+                    self.interrupt_handle(),
+                    self.cancellation.clone(),
+                    self.settings.labels.clone(),
+                )
+            })
+            .transpose()?;
+        self.call_handler(func_name, event, seed, gc, monitor_task.as_ref())
+    }
+
+    /// Calls the handler, without starting the default monitors. `event` must
+    /// be valid JSON.
+    #[instrument(name = "handle_event", err(Debug), skip(self, event, gc, monitor_task), level=Level::INFO, fields(script_sha256 = Empty, script_path = Empty, script_bytes = Empty, sandbox_labels = Empty))]
+    fn call_handler<F>(
+        &mut self,
+        func_name: F,
+        event: String,
+        seed: Option<u64>,
+        gc: Option<bool>,
+        monitor_task: Option<&MonitorTask>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let should_gc = gc.unwrap_or_else(|| {
+            self.settings
+                .gc_policy
+                .collect_with_call(self.calls_since_gc)
+        });
         let func_name = func_name.into();
         if func_name.is_empty() {
-            return Err(HyperlightError::Error(
-                "Handler name must not be empty".to_string(),
-            ));
+            return Err(empty_handler_name());
         }
 
+        // The event is compressed before it is checked against the input
+        // buffer, which only has to fit the compressed frame.
+        let frame = self
+            .settings
+            .wire_compression
+            .min_size()
+            .map(|min_size| encode_event(&event, min_size));
+        check_event_size(
+            frame.as_ref().map_or(event.len(), Vec::len),
+            func_name.len(),
+            self.settings.input_buffer_size,
+        )?;
+
+        if let Some(script) = self.handlers.get(&func_name) {
+            script.record_provenance(&Span::current());
+        }
+        self.settings.labels.record(&Span::current());
+
         #[cfg(feature = "function_call_metrics")]
-        let _metric_guard = EventHandlerMetricGuard::new(&func_name, should_gc);
+        let mut metric_guard =
+            EventHandlerMetricGuard::new(&func_name, should_gc, self.settings.labels.clone());
+
+        let heap_before = if self.settings.heap_reports {
+            self.memory_usage().ok()
+        } else {
+            None
+        };
+
+        let call = self
+            .cancellation
+            .as_ref()
+            .map(CancellationHandle::start_call);
+        host_calls::reset_call_count();
+        let sequence = self.sequence.next();
+        let seed = seed.unwrap_or_else(|| self.invocation_seed(sequence));
+        let traceparent = trace_context::span_traceparent(&Span::current());
+        let _traceparent = trace_context::enter(traceparent.clone());
+        // The guest gets an empty traceparent when the invocation is not traced.
+        let traceparent = traceparent.unwrap_or_default();
+        self.settings
+            .observe(|observer| observer.on_handler_start(&func_name, sequence));
+        let start = Instant::now();
+        let result = match frame {
+            Some(frame) => self
+                .inner
+                .call::<Vec<u8>>(&func_name, (frame, should_gc, sequence, seed, traceparent))
+                .and_then(|frame| decode_result(&frame)),
+            None => self
+                .inner
+                .call(&func_name, (event, should_gc, sequence, seed, traceparent)),
+        };
+        let result = result.map_err(|e| match &self.cancellation {
+            Some(cancellation) => cancellation.map_error(e),
+            None => e,
+        });
+        // The guest call is over, and restoring the sandbox needs it mutably.
+        drop(call);
+        self.check_poisoned(&func_name, &result, monitor_task);
+        let duration = start.elapsed();
+        self.account_gc(should_gc, 1, gc.is_none() && result.is_ok());
+        let heap = match heap_before {
+            Some(before) if result.is_err() => Some(HeapReport {
+                before,
+                after: if self.inner.poisoned() {
+                    None
+                } else {
+                    self.memory_usage().ok()
+                },
+            }),
+            _ => None,
+        };
+        if result.is_err() && self.inner.poisoned() {
+            self.auto_recover();
+        }
+        let result = result.map_err(|e| {
+            let e = remap_error(e, &self.source_maps);
+            match (e, &heap) {
+                (HyperlightError::GuestError(code, message), Some(heap)) => {
+                    HyperlightError::GuestError(code, format!("{message}\n{heap}"))
+                }
+                (e, _) => e,
+            }
+        });
+        if let (Err(e), Some(heap)) = (&result, &heap) {
+            tracing::warn!(handler = %func_name, %heap, "Handler failed: {}", e);
+        }
+        self.recent_invocations
+            .record(sequence, seed, &func_name, duration, &result, heap);
+        self.settings.observe(|observer| {
+            observer.on_handler_end(&func_name, sequence, duration, result.as_ref().err())
+        });
+        #[cfg(feature = "function_call_metrics")]
+        metric_guard.set_outcome(&result);
+        result
+    }
+
+    /// Handles a batch of events with the same handler in a single call into
+    /// the guest, returning the result of every event in order.
+    ///
+    /// This saves entering the guest and a round of serialization per event,
+    /// for callers that invoke a handler many times in a row. Every event is
+    /// a separate invocation, with its own sequence number and seed, and a
+    /// failing invocation doesn't stop the batch: its error is returned in
+    /// place of its result. The outer error is returned if the batch as a
+    /// whole fails, e.g. because the guest was killed by a monitor.
+    ///
+    /// Unlike calling [`handle_event`](Self::handle_event) for every event:
+    /// * The default monitors, the limit on host function calls and the
+    ///   [`GcPolicy`](crate::GcPolicy) apply to the whole batch.
+    /// * The invocations are recorded in the diagnostics with the average
+    ///   duration of the batch.
+    /// * Heap reports and wire compression are not used.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let events = vec![r#"{"n":1}"#.to_string(), r#"{"n":2}"#.to_string()];
+    /// for result in loaded_sandbox.handle_events("handler", events, None)? {
+    ///     match result {
+    ///         Ok(result) => println!("{result}"),
+    ///         Err(e) => eprintln!("{e}"),
+    ///     }
+    /// }
+    /// ```
+    #[instrument(err(Debug), skip(self, events, gc), level=Level::INFO, fields(events = events.len(), script_sha256 = Empty, script_path = Empty, script_bytes = Empty, sandbox_labels = Empty))]
+    pub fn handle_events<F>(
+        &mut self,
+        func_name: F,
+        events: Vec<String>,
+        gc: Option<bool>,
+    ) -> Result<Vec<Result<String>>>
+    where
+        F: Into<String> + std::fmt::Debug,
+    {
+        let func_name = func_name.into();
+        if let Some(script) = self.handlers.get(&func_name) {
+            script.record_provenance(&Span::current());
+        }
+        self.settings.labels.record(&Span::current());
+        let invocations = (0..events.len())
+            .map(|event| (func_name.clone(), event))
+            .collect();
+        self.call_batch(events, invocations, gc)
+    }
+
+    /// Handles an event with each of the given handlers in a single call into
+    /// the guest, returning the result of every handler by name.
+    ///
+    /// This is for routing layers that run several handlers against the same
+    /// event: the event is sent to the guest once, and every handler gets its
+    /// own copy of it, so a handler modifying its event doesn't affect the
+    /// others. The handlers run in the given order, with the same semantics
+    /// as [`handle_events`](Self::handle_events): every handler is a separate
+    /// invocation, and a failing handler doesn't stop the others.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let results = loaded_sandbox.handle_event_multi(&["audit", "billing"], event, None)?;
+    /// if let Err(e) = &results["billing"] {
+    ///     eprintln!("billing failed: {e}");
+    /// }
+    /// ```
+    #[instrument(err(Debug), skip(self, event, gc), level=Level::INFO)]
+    pub fn handle_event_multi<H>(
+        &mut self,
+        handlers: &[H],
+        event: String,
+        gc: Option<bool>,
+    ) -> Result<HashMap<String, Result<String>>>
+    where
+        H: AsRef<str> + std::fmt::Debug,
+    {
+        let mut names: Vec<String> = Vec::with_capacity(handlers.len());
+        for handler in handlers {
+            let handler = handler.as_ref();
+            if names.iter().any(|name| name == handler) {
+                return Err(new_error!("Handler '{}' is given more than once", handler));
+            }
+            names.push(handler.to_string());
+        }
+        if names.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let invocations = names.iter().map(|name| (name.clone(), 0)).collect();
+        let results = self.call_batch(vec![event], invocations, gc)?;
+        Ok(names.into_iter().zip(results).collect())
+    }
+
+    /// Calls the handlers of a batch of invocations in a single call into the
+    /// guest, where every invocation is the name of a handler and the index
+    /// of its event in `events`.
+    fn call_batch(
+        &mut self,
+        events: Vec<String>,
+        invocations: Vec<(String, usize)>,
+        gc: Option<bool>,
+    ) -> Result<Vec<Result<String>>> {
+        if invocations
+            .iter()
+            .any(|(func_name, _)| func_name.is_empty())
+        {
+            return Err(empty_handler_name());
+        }
+        for event in &events {
+            let _json_val: serde_json::Value =
+                serde_json::from_str(event).map_err(JsonConversionFailure)?;
+        }
+        if invocations.is_empty() {
+            return Ok(Vec::new());
+        }
+        let count = u32::try_from(invocations.len()).unwrap_or(u32::MAX);
+
+        let monitor_task = self
+            .settings
+            .default_monitors
+            .clone()
+            .map(|monitor| {
+                MonitorTask::start(
+                    monitor.as_ref(),
+                    self.interrupt_handle(),
+                    self.cancellation.clone(),
+                    self.settings.labels.clone(),
+                )
+            })
+            .transpose()?;
+
+        let should_gc = gc.unwrap_or_else(|| {
+            self.settings
+                .gc_policy
+                .collect_with_call(self.calls_since_gc)
+        });
+
+        let sequence = self.sequence.current();
+        let invocations: Vec<(String, usize, u64, u64)> = invocations
+            .into_iter()
+            .map(|(func_name, event)| {
+                let sequence = self.sequence.next();
+                (func_name, event, sequence, self.invocation_seed(sequence))
+            })
+            .collect();
+        let traceparent = trace_context::span_traceparent(&Span::current());
+        let batch = serde_json::to_string(&Batch {
+            events: &events,
+            invocations: &invocations,
+            traceparent: traceparent.as_deref(),
+        })?;
+        if let Err(e) = check_event_size(
+            batch.len(),
+            RUN_HANDLER_BATCH.len(),
+            self.settings.input_buffer_size,
+        ) {
+            // The invocations never run, so they don't take sequence numbers.
+            self.sequence.restored(sequence);
+            return Err(e);
+        }
+        for (func_name, _, sequence, _) in &invocations {
+            self.settings
+                .observe(|observer| observer.on_handler_start(func_name, *sequence));
+        }
+        let _traceparent = trace_context::enter(traceparent);
+        // A monitor firing or the sandbox being poisoned is reported for the
+        // whole batch rather than for the invocation that was running.
+        let batch_name = batch_name(&invocations);
+
+        let call = self
+            .cancellation
+            .as_ref()
+            .map(CancellationHandle::start_call);
+        host_calls::reset_call_count();
+        let start = Instant::now();
+        let result = self
+            .inner
+            .call::<String>(RUN_HANDLER_BATCH, (batch, should_gc))
+            .and_then(|results| {
+                serde_json::from_str::<Vec<BatchResult>>(&results)
+                    .map_err(|e| new_error!("Failed to parse the results of the batch: {}", e))
+            });
+        let result = result.map_err(|e| match &self.cancellation {
+            Some(cancellation) => cancellation.map_error(e),
+            None => e,
+        });
+        // The guest call is over, and restoring the sandbox needs it mutably.
+        drop(call);
+        self.check_poisoned(&batch_name, &result, monitor_task.as_ref());
+        drop(monitor_task);
+        let duration = start.elapsed() / count;
+        self.account_gc(should_gc, count, gc.is_none() && result.is_ok());
+        if result.is_err() && self.inner.poisoned() {
+            self.auto_recover();
+        }
+
+        let results = match result {
+            Ok(results) => results,
+            Err(e) => {
+                let result = Err(remap_error(e, &self.source_maps));
+                for (func_name, _, sequence, seed) in invocations {
+                    self.recent_invocations
+                        .record(sequence, seed, &func_name, duration, &result, None);
+                    self.settings.observe(|observer| {
+                        observer.on_handler_end(
+                            &func_name,
+                            sequence,
+                            duration,
+                            result.as_ref().err(),
+                        )
+                    });
+                }
+                return result;
+            }
+        };
+        let results = invocations
+            .into_iter()
+            .zip(results)
+            .map(|((func_name, _, sequence, seed), result)| {
+                let result = match result {
+                    BatchResult::Ok(result) => Ok(result),
+                    BatchResult::Err(message) => Err(remap_error(
+                        HyperlightError::GuestError(ErrorCode::GuestError, message),
+                        &self.source_maps,
+                    )),
+                };
+                self.recent_invocations
+                    .record(sequence, seed, &func_name, duration, &result, None);
+                self.settings.observe(|observer| {
+                    observer.on_handler_end(&func_name, sequence, duration, result.as_ref().err())
+                });
+                result
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// The seed of the invocation with the sequence number `sequence`, when
+    /// the caller doesn't pass one.
+    fn invocation_seed(&self, sequence: u64) -> u64 {
+        match &self.settings.deterministic {
+            Some(deterministic) => deterministic.invocation_seed(sequence),
+            None => random_seed(),
+        }
+    }
+
+    /// Record the reason a guest call poisoned the sandbox, if it did, and
+    /// notify the observer of the monitor that fired during the call, if any.
+    fn check_poisoned<T>(
+        &mut self,
+        func_name: &str,
+        result: &Result<T>,
+        monitor_task: Option<&MonitorTask>,
+    ) {
+        self.last_call_poisoned = result.is_err() && self.inner.poisoned();
+        let fired_monitor = monitor_task.and_then(MonitorTask::fired);
+        if let Some(monitor) = fired_monitor {
+            self.settings
+                .observe(|observer| observer.on_monitor_fired(func_name, monitor));
+        }
+        // Keep the reason of the call that poisoned the sandbox, rather than
+        // the `PoisonedSandbox` errors of the calls that followed.
+        if let Err(e) = result
+            && self.poison_reason.is_none()
+            && self.inner.poisoned()
+        {
+            let reason = PoisonReason::from_error(e, fired_monitor.is_some());
+            self.settings
+                .observe(|observer| observer.on_poisoned(func_name, &reason));
+            self.poison_reason = Some(reason);
+        }
+    }
+
+    /// Account for `calls` invocations in a guest call for the GC policy,
+    /// given whether the guest call collected garbage, and whether to measure
+    /// the heap if the policy needs it.
+    fn account_gc(&mut self, collected: bool, calls: u32, measure_heap: bool) {
+        if collected {
+            self.calls_since_gc = 0;
+            return;
+        }
+        self.calls_since_gc = self.calls_since_gc.saturating_add(calls);
+        if measure_heap && let Some(threshold) = self.settings.gc_policy.heap_threshold() {
+            self.collect_if_heap_above(threshold);
+        }
+    }
+
+    /// Run garbage collection if the QuickJS heap is larger than `threshold` bytes.
+    fn collect_if_heap_above(&mut self, threshold: u64) {
+        let result = self.memory_usage().and_then(|stats| {
+            if stats.malloc_size > threshold {
+                self.run_gc()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to apply the GC policy: {}", e);
+        }
+    }
+
+    /// Restore the baseline snapshot of a poisoned sandbox, if auto-recovery is enabled.
+    fn auto_recover(&mut self) {
+        let Some(baseline) = self.baseline.clone() else {
+            return;
+        };
+        match self.inner.rewind(&baseline) {
+            Ok(()) => {
+                tracing::warn!(
+                    reason = ?self.poison_reason,
+                    "Sandbox was poisoned, restored the state after loading the handlers"
+                );
+                self.poison_reason = None;
+                self.settings.observe(|observer| observer.on_restored());
+            }
+            Err(e) => tracing::error!("Failed to recover the poisoned sandbox: {}", e),
+        }
+    }
 
-        self.inner.call(&func_name, (event, should_gc))
+    /// Returns the sequence number of the most recent invocation of this
+    /// sandbox, or 0 if no handler has been invoked yet.
+    ///
+    /// Sequence numbers increase by one on every invocation, whether or not it
+    /// succeeds, and are kept when handlers are unloaded and reloaded.
+    /// Restoring a snapshot resets the sequence to the one the snapshot was
+    /// taken at, see [`SandboxBuilder::with_allow_rollback`](crate::SandboxBuilder::with_allow_rollback).
+    pub fn sequence(&self) -> u64 {
+        self.sequence.current()
     }
 
     /// Unloads the Handlers from the sandbox and returns a `JSSandbox` with the JavaScript runtime loaded.
     #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
     pub fn unload(self) -> Result<JSSandbox> {
-        JSSandbox::from_loaded(self.inner, self.snapshot).inspect(|_| {
-            metrics::counter!(METRIC_SANDBOX_UNLOADS).increment(1);
+        let observer = self.settings.observer.clone();
+        JSSandbox::from_loaded(
+            self.inner,
+            self.snapshot,
+            self.sequence,
+            self.settings,
+            self.bundler,
+            self.cancellation,
+        )
+        .inspect(|_| {
+            record_sandbox_unload();
+            if let Some(observer) = observer {
+                observer.on_unloaded();
+            }
         })
     }
 
@@ -108,22 +844,206 @@ impl LoadedJSSandbox {
     /// This can be used to restore the state of the sandbox later.
     #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
     pub fn snapshot(&mut self) -> Result<Arc<Snapshot>> {
-        self.inner.snapshot()
+        let snapshot = self.inner.snapshot()?;
+        self.sequence.record_snapshot(&snapshot);
+        Ok(snapshot)
     }
 
     /// Restore the state of the sandbox to a previous snapshot.
+    ///
+    /// This fails if the sandbox was built with rollback disallowed and the
+    /// snapshot would roll the invocation sequence backwards.
     #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
     pub fn restore(&mut self, snapshot: Arc<Snapshot>) -> Result<()> {
+        let sequence = self.sequence.check_restore(&snapshot)?;
         self.inner.restore(snapshot)?;
+        self.poison_reason = None;
+        if let Some(sequence) = sequence {
+            self.sequence.restored(sequence);
+        }
+        self.settings.observe(|observer| observer.on_restored());
         Ok(())
     }
 
+    /// Move the virtual clock of a sandbox in deterministic mode forward, see
+    /// [`SandboxBuilder::with_deterministic_mode`](crate::SandboxBuilder::with_deterministic_mode).
+    ///
+    /// The clock is kept by the host, so restoring a snapshot does not move
+    /// it back. Fails if the sandbox is not in deterministic mode.
+    pub fn advance_virtual_time(&self, by: Duration) -> Result<()> {
+        match &self.settings.deterministic {
+            Some(deterministic) => {
+                deterministic.clock.advance(by);
+                Ok(())
+            }
+            None => Err(new_error!(
+                "The sandbox is not in deterministic mode, it has no virtual clock"
+            )),
+        }
+    }
+
+    /// Returns the time of the virtual clock of a sandbox in deterministic
+    /// mode, or `None` if the sandbox is not in deterministic mode.
+    pub fn virtual_time(&self) -> Option<SystemTime> {
+        self.settings
+            .deterministic
+            .as_ref()
+            .map(|deterministic| deterministic.clock.now())
+    }
+
     /// Get a handle to the interrupt handler for this sandbox,
     /// capable of interrupting guest execution.
     pub fn interrupt_handle(&self) -> Arc<dyn InterruptHandle> {
         self.inner.interrupt_handle()
     }
 
+    /// Get a handle to cooperatively cancel the handler running in this
+    /// sandbox, without poisoning it.
+    ///
+    /// Returns `None` unless the sandbox was built with
+    /// [`SandboxBuilder::with_cooperative_cancellation`](crate::SandboxBuilder::with_cooperative_cancellation).
+    pub fn cancellation_handle(&self) -> Option<CancellationHandle> {
+        self.cancellation.clone()
+    }
+
+    /// Collect a report on the sandbox to attach to bug reports: the version
+    /// of hyperlight-js, the effective configuration, the loaded handlers
+    /// with the digests of their scripts, and the outcome of the most recent
+    /// invocations.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// if let Err(e) = loaded_sandbox.handle_event("handler", event, None) {
+    ///     std::fs::write("diagnostics.json", loaded_sandbox.collect_diagnostics().to_json()?)?;
+    /// }
+    /// ```
+    pub fn collect_diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            runtime: RuntimeInfo::current(),
+            configuration: ConfigurationInfo {
+                max_handlers: self.settings.handler_limits.max_handlers,
+                max_script_bytes: self.settings.handler_limits.max_script_bytes,
+                max_host_calls: self.settings.handler_limits.max_host_calls,
+                wire_compression_min_size: self.settings.wire_compression.min_size(),
+                default_monitors: self.settings.default_monitors.is_some(),
+                auto_recover: self.settings.auto_recover,
+                gc_policy: self.settings.gc_policy.to_string(),
+                host_function_allowlist: self.settings.host_function_allowlist.names(),
+                deterministic_seed: self
+                    .settings
+                    .deterministic
+                    .as_ref()
+                    .map(|deterministic| deterministic.seed),
+                heap_reports: self.settings.heap_reports,
+                allow_rollback: self.sequence.allow_rollback(),
+                cooperative_cancellation: self.cancellation.is_some(),
+                cancellation_grace_period_ms: self
+                    .cancellation
+                    .as_ref()
+                    .and_then(CancellationHandle::grace_period)
+                    .map(|grace_period| grace_period.as_millis() as u64),
+                prebundled_modules: self.bundler.is_some(),
+                crashdump_dir: crashdump_dir(),
+            },
+            sequence: self.sequence.current(),
+            poisoned: self.poisoned(),
+            handlers: self.handlers(),
+            recent_invocations: self.recent_invocations.to_vec(),
+        }
+    }
+
+    /// Returns the handlers loaded in the sandbox, sorted by name, with the
+    /// size and SHA-256 digest of their scripts and whether the guest
+    /// compiled them, to check what is deployed or to build routing tables.
+    /// Pipelines are not listed.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// for handler in loaded_sandbox.handlers() {
+    ///     assert_eq!(handler.sha256, expected[&handler.name]);
+    /// }
+    /// ```
+    pub fn handlers(&self) -> Vec<HandlerInfo> {
+        HandlerInfo::list(&self.handlers, &self.compiled)
+    }
+
+    /// Replaces the script of the handler `function_name` with `script`,
+    /// recompiling only that handler in the guest.
+    ///
+    /// Unlike unloading and reloading the sandbox, the other handlers and the
+    /// state they accumulated are kept, and so is the state of the modules
+    /// already loaded: modules the new script imports are not evaluated
+    /// again. Only the top level state of the handler script itself starts
+    /// over. Pipelines running the handler run the new one.
+    ///
+    /// If the new script fails to compile or throws while evaluating, the
+    /// error is returned and the old handler keeps handling events.
+    ///
+    /// If the sandbox recovers automatically, see
+    /// [`SandboxBuilder::with_auto_recover`](crate::SandboxBuilder::with_auto_recover),
+    /// the state it recovers to is taken again after replacing the handler.
+    /// Restoring a snapshot taken before the replacement brings back the old
+    /// handler in the guest.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// loaded_sandbox.replace_handler("handler", Script::from_file("handler.v2.js")?)?;
+    /// ```
+    #[instrument(err(Debug), skip(self, script), level=Level::DEBUG, fields(script_sha256 = Empty, script_path = Empty, script_bytes = Empty))]
+    pub fn replace_handler(&mut self, function_name: &str, script: Script) -> Result<()> {
+        script.record_provenance(&Span::current());
+        if function_name.is_empty() {
+            return Err(empty_handler_name());
+        }
+        let mut handlers = self.handlers.clone();
+        if handlers.remove(function_name).is_none() {
+            return Err(handler_not_found(function_name));
+        }
+        self.settings.handler_limits.check_add(&handlers, &script)?;
+
+        let modules = match (&self.bundler, script.kind()) {
+            (Some(bundler), ScriptKind::JavaScript) => {
+                let handler_path = handler_module_path(function_name, &script_dir(&script));
+                serde_json::to_string(&bundler.bundle(&handler_path, script.content()))?
+            }
+            _ => String::new(),
+        };
+        handlers.insert(function_name.to_owned(), script);
+        let source_maps = handler_source_maps(&handlers);
+
+        let registrations = serde_json::to_string(&[HandlerRegistration::new(
+            function_name,
+            &handlers[function_name],
+            &modules,
+        )])?;
+        let call = self
+            .cancellation
+            .as_ref()
+            .map(CancellationHandle::start_call);
+        let result = self
+            .inner
+            .call::<String>("register_handlers", registrations)
+            .map_err(|e| match &self.cancellation {
+                Some(cancellation) => cancellation.map_error(e),
+                None => e,
+            })
+            .map_err(|e| remap_error(e, &source_maps));
+        drop(call);
+        self.check_poisoned(function_name, &result, None);
+        let compiled: HashSet<String> = serde_json::from_str(&result?)?;
+
+        self.compiled.extend(compiled);
+        self.handlers = handlers;
+        self.source_maps = source_maps;
+        if self.baseline.is_some() {
+            self.baseline = Some(self.inner.checkpoint()?);
+        }
+        Ok(())
+    }
+
     /// Returns whether the sandbox is currently poisoned.
     ///
     /// A poisoned sandbox is in an inconsistent state due to the guest not running to completion.
@@ -136,11 +1056,143 @@ impl LoadedJSSandbox {
         self.inner.poisoned()
     }
 
+    /// Returns whether the JavaScript runtime runs in a Hyperlight micro VM,
+    /// rather than in-process without any isolation, see the `in-process`
+    /// feature.
+    pub fn is_isolated(&self) -> bool {
+        self.inner.is_isolated()
+    }
+
+    /// Returns statistics on the QuickJS heap of the sandbox.
+    ///
+    /// Call this between invocations to size the guest heap, or to detect
+    /// handlers that leak memory across calls. The size of the heap is also
+    /// reported as the `guest_heap_bytes` gauge.
+    #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
+    pub fn memory_usage(&mut self) -> Result<MemoryStats> {
+        let stats: String = self.inner.call("memory_usage", ())?;
+        let stats: MemoryStats = serde_json::from_str(&stats)
+            .map_err(|e| new_error!("Failed to parse the memory statistics: {}", e))?;
+        record_guest_heap_bytes(stats.malloc_size);
+        Ok(stats)
+    }
+
+    /// Returns the number of bytes of the QuickJS heap attributed to every
+    /// handler, by name, to see which handler holds the heap, e.g. to evict
+    /// or quarantine it.
+    ///
+    /// All the handlers share the heap of the runtime, even in their own
+    /// realms with [`IsolationMode::PerHandler`](crate::IsolationMode::PerHandler),
+    /// so QuickJS can't tell which realm holds what. Instead, the heap is
+    /// attributed to the handler that allocated it: every allocation made
+    /// while a handler is registered or running, including its realm, counts
+    /// for that handler until it is freed. The rest of the heap, see
+    /// [`memory_usage`](Self::memory_usage), is used by the built-ins, the
+    /// setup script and the host modules. Pipelines are not listed, as their
+    /// stages are.
+    #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
+    pub fn memory_usage_by_handler(&mut self) -> Result<BTreeMap<String, u64>> {
+        let usage: String = self.inner.call("memory_usage_by_handler", ())?;
+        serde_json::from_str(&usage)
+            .map_err(|e| new_error!("Failed to parse the memory usage by handler: {}", e))
+    }
+
+    /// Run a garbage collection cycle in the guest.
+    ///
+    /// Handlers called with `gc` set to `Some(false)`, or under a
+    /// [`GcPolicy`](crate::GcPolicy) that doesn't collect after every call,
+    /// skip the collection after the call, so latency-sensitive invocations
+    /// don't pay for it.
+    /// Call this between bursts of such invocations to collect the garbage
+    /// they left behind.
+    #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
+    pub fn run_gc(&mut self) -> Result<()> {
+        self.inner.call::<()>("run_gc", ())?;
+        self.calls_since_gc = 0;
+        Ok(())
+    }
+
+    /// Calls the guest function `func_name` directly, with the typed call
+    /// API of Hyperlight, bypassing the handler dispatcher.
+    ///
+    /// This is for hosts running a patched runtime with their own guest
+    /// entry points. The call runs without monitors, garbage collection or
+    /// an invocation sequence number, and nothing checks that it leaves the
+    /// runtime in a state the handlers expect. A call that poisons the
+    /// sandbox is handled like a handler that does: its
+    /// [`poisoned_reason`](Self::poisoned_reason) is recorded, and the
+    /// sandbox is restored if it was built with
+    /// [`SandboxBuilder::with_auto_recover`](crate::SandboxBuilder::with_auto_recover).
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// // A guest function `fn checksum(data: Vec<u8>) -> u64` of a patched runtime
+    /// let checksum: u64 = loaded_sandbox.call_raw("checksum", (data,))?;
+    /// ```
+    #[instrument(err(Debug), skip(self, args), level=Level::DEBUG)]
+    pub fn call_raw<Output: SupportedReturnType>(
+        &mut self,
+        func_name: &str,
+        args: impl ParameterTuple,
+    ) -> Result<Output> {
+        let result = self.inner.call(func_name, args);
+        self.check_poisoned(func_name, &result, None);
+        if result.is_err() && self.inner.poisoned() {
+            self.auto_recover();
+        }
+        result
+    }
+
+    /// Returns the samples of the JavaScript call stack taken since the last
+    /// call, in the folded format of inferno and `flamegraph.pl`: one line
+    /// per distinct stack, with its frames from the outermost to the
+    /// innermost separated by `;`, followed by its number of samples.
+    ///
+    /// Frames are named after their function and script, like
+    /// `handler (handler.js)`. The samples live in the guest, so restoring a
+    /// snapshot also restores them. Returns an empty string unless the
+    /// sandbox was built with
+    /// [`SandboxBuilder::with_profiling`](crate::SandboxBuilder::with_profiling).
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// loaded_sandbox.handle_event("handler", event, None)?;
+    /// std::fs::write("handler.folded", loaded_sandbox.take_profile()?)?;
+    /// // inferno-flamegraph handler.folded > handler.svg
+    /// ```
+    #[instrument(err(Debug), skip_all, level=Level::DEBUG)]
+    pub fn take_profile(&mut self) -> Result<String> {
+        self.inner.call("take_profile", ())
+    }
+
+    /// Returns why the sandbox is poisoned, or `None` if it is not poisoned.
+    ///
+    /// This tells apart a guest killed through its [`InterruptHandle`], one
+    /// killed by an execution monitor, a guest that aborted, and a memory
+    /// violation, e.g. to decide whether to restore the sandbox or discard it.
+    pub fn poisoned_reason(&self) -> Option<PoisonReason> {
+        if !self.poisoned() {
+            return None;
+        }
+        Some(
+            self.poison_reason
+                .clone()
+                .unwrap_or_else(|| PoisonReason::Other("unknown".to_string())),
+        )
+    }
+
     /// Handles an event with execution monitoring.
     ///
     /// The monitor enforces execution limits (time, CPU usage, etc.) and will
     /// terminate execution if limits are exceeded. If terminated, the sandbox
-    /// will be poisoned and an error is returned.
+    /// will be poisoned and an error is returned, unless it was built with
+    /// [`SandboxBuilder::with_cooperative_cancellation`](crate::SandboxBuilder::with_cooperative_cancellation),
+    /// in which case the handler is cancelled and the sandbox stays usable.
+    ///
+    /// `monitor` is used instead of the default monitors of the sandbox, see
+    /// [`SandboxBuilder::with_default_monitors`](crate::SandboxBuilder::with_default_monitors).
     ///
     /// # Fail-Closed Semantics
     ///
@@ -166,7 +1218,9 @@ impl LoadedJSSandbox {
     /// * `event` - JSON string payload to pass to the handler.
     /// * `monitor` - The execution monitor (or tuple of monitors) to enforce limits.
     ///   Tuples race all sub-monitors; the first to fire wins and its name is logged.
-    /// * `gc` - Whether to run garbage collection after the call (defaults to `true` if `None`).
+    /// * `gc` - Whether to run garbage collection after the call. If `None`, the
+    ///   [`GcPolicy`](crate::GcPolicy) of the sandbox decides, which collects
+    ///   after every call by default.
     ///
     /// # Returns
     ///
@@ -201,42 +1255,59 @@ impl LoadedJSSandbox {
         F: Into<String> + std::fmt::Debug,
         M: MonitorSet,
     {
-        let func_name = func_name.into();
-        if func_name.is_empty() {
-            return Err(HyperlightError::Error(
-                "Handler name must not be empty".to_string(),
-            ));
-        }
-        let interrupt_handle = self.interrupt_handle();
-
-        // Phase 1: Build the racing future on the calling thread.
-        // to_race() calls each sub-monitor's get_monitor() here, where
-        // monitors can capture thread-local state (e.g., CPU clock handles).
-        // If any monitor fails to initialize, we fail closed — handler never runs.
-        let racing_future = monitor.to_race().map_err(|e| {
-            tracing::error!("Failed to initialize execution monitor: {}", e);
-            HyperlightError::Error(format!("Execution monitor failed to start: {}", e))
-        })?;
-
-        // Phase 2: Spawn the racing future on the shared runtime.
-        // When the first monitor fires, to_race() emits the metric and log,
-        // then we call kill() to terminate the guest.
-        // kill() is safe to call even if the guest already finished — hyperlight's
-        // InterruptHandle checks RUNNING_BIT and clear_cancel() at the start of
-        // the next guest call clears any stale CANCEL_BIT.
-        let runtime = get_monitor_runtime().ok_or_else(|| {
-            tracing::error!("Monitor runtime is unavailable");
-            HyperlightError::Error("Monitor runtime is unavailable".to_string())
-        })?;
+        check_event(&event)?;
+        self.call_valid_with_monitor(func_name, event, monitor, gc)
+    }
 
-        let _monitor_task = MonitorTask(runtime.spawn(async move {
-            racing_future.await;
-            interrupt_handle.kill();
-        }));
+    /// Handles an event like
+    /// [`handle_event_with_monitor`](Self::handle_event_with_monitor), taking
+    /// and returning JSON values like
+    /// [`handle_event_value`](Self::handle_event_value).
+    #[instrument(err(Debug), skip(self, event, monitor, gc), level=Level::INFO)]
+    pub fn handle_event_value_with_monitor<F, M>(
+        &mut self,
+        func_name: F,
+        event: &serde_json::Value,
+        monitor: &M,
+        gc: Option<bool>,
+    ) -> Result<serde_json::Value>
+    where
+        F: Into<String> + std::fmt::Debug,
+        M: MonitorSet,
+    {
+        let result = self.call_valid_with_monitor(func_name, event.to_string(), monitor, gc)?;
+        serde_json::from_str(&result)
+            .map_err(|e| new_error!("The result of the handler is not valid JSON: {}", e))
+    }
 
-        // Phase 3: Execute the handler (blocking). When this returns (success
-        // or error), _monitor_task drops and aborts the spawned monitor task.
-        self.handle_event(&func_name, event, gc)
+    /// Calls the handler with an event known to be valid JSON, guarded by
+    /// `monitor`.
+    fn call_valid_with_monitor<F, M>(
+        &mut self,
+        func_name: F,
+        event: String,
+        monitor: &M,
+        gc: Option<bool>,
+    ) -> Result<String>
+    where
+        F: Into<String> + std::fmt::Debug,
+        M: MonitorSet,
+    {
+        let func_name = func_name.into();
+        if func_name.is_empty() {
+            return Err(empty_handler_name());
+        }
+        let monitor_task = MonitorTask::start(
+            monitor,
+            self.interrupt_handle(),
+            self.cancellation.clone(),
+            self.settings.labels.clone(),
+        )?;
+
+        // Execute the handler (blocking). When this returns (success or
+        // error), monitor_task drops and aborts the spawned monitor task.
+        // The monitor replaces the default monitors of the sandbox, if any.
+        self.call_handler(&func_name, event, None, gc, Some(&monitor_task))
     }
 
     /// Generate a crash dump of the current state of the VM underlying this sandbox.
@@ -286,6 +1357,56 @@ impl Debug for LoadedJSSandbox {
     }
 }
 
+// The serialization in here has to match the deserialization of
+// Batch in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(serde::Serialize)]
+struct Batch<'a> {
+    events: &'a [String],
+    // `[handler, event index, sequence, seed]` for every invocation
+    invocations: &'a [(String, usize, u64, u64)],
+    // The traceparent every invocation of the batch is made in
+    traceparent: Option<&'a str>,
+}
+
+/// The names of the handlers of a batch, in order and joined with commas.
+fn batch_name(invocations: &[(String, usize, u64, u64)]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for (func_name, ..) in invocations {
+        if !names.contains(&func_name.as_str()) {
+            names.push(func_name);
+        }
+    }
+    names.join(",")
+}
+
+// The deserialization in here has to match the serialization of
+// BatchResult in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchResult {
+    Ok(String),
+    Err(String),
+}
+
+/// Checks that an event passed as a string is valid JSON.
+fn check_event(event: &str) -> Result<()> {
+    serde_json::from_str::<serde::de::IgnoredAny>(event).map_err(JsonConversionFailure)?;
+    Ok(())
+}
+
+/// A seed for an invocation that does not get one from the caller.
+fn random_seed() -> u64 {
+    // `RandomState` is randomly keyed, and every instance gets different keys.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +1478,35 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_collect_diagnostics() {
+        let mut loaded_js_sandbox = get_loaded_sandbox().unwrap();
+        loaded_js_sandbox
+            .handle_event("handler", get_valid_event(), None)
+            .unwrap();
+        loaded_js_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap_err();
+
+        let diagnostics = loaded_js_sandbox.collect_diagnostics();
+        assert_eq!(diagnostics.runtime.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(diagnostics.sequence, 2);
+        assert!(!diagnostics.poisoned);
+        assert_eq!(diagnostics.handlers.len(), 1);
+        assert_eq!(diagnostics.handlers[0].name, "handler");
+        assert_eq!(
+            diagnostics.handlers[0].sha256,
+            get_valid_handler().content_hash()
+        );
+        assert_eq!(diagnostics.recent_invocations.len(), 2);
+        assert!(diagnostics.recent_invocations[0].error.is_none());
+        assert!(diagnostics.recent_invocations[1].error.is_some());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&diagnostics.to_json().unwrap()).unwrap();
+        assert_eq!(json["handlers"][0]["name"], "handler");
+    }
+
     #[test]
     fn test_handle_event_accumulates_state() {
         let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
@@ -557,4 +1707,76 @@ mod tests {
             "Sandbox should not be poisoned when monitor fails to start"
         );
     }
+
+    fn get_sequence_handler() -> Script {
+        Script::from_content(
+            r#"
+        function handler(event, context) {
+            return { sequence: context.sequence };
+        }
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_sequence_increases_and_follows_restore() {
+        let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+        let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+        sandbox
+            .add_handler("handler", get_sequence_handler())
+            .unwrap();
+        let mut loaded_js_sandbox = sandbox.get_loaded_sandbox().unwrap();
+        assert_eq!(loaded_js_sandbox.sequence(), 0);
+
+        let result = loaded_js_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        assert_eq!(result, r#"{"sequence":1}"#);
+        let snapshot = loaded_js_sandbox.snapshot().unwrap();
+
+        let result = loaded_js_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        assert_eq!(result, r#"{"sequence":2}"#);
+        assert_eq!(loaded_js_sandbox.sequence(), 2);
+
+        // Rollback is allowed by default and resets the sequence
+        loaded_js_sandbox.restore(snapshot).unwrap();
+        assert_eq!(loaded_js_sandbox.sequence(), 1);
+
+        // The sequence survives unloading and reloading the handlers
+        let mut js_sandbox = loaded_js_sandbox.unload().unwrap();
+        js_sandbox
+            .add_handler("handler", get_sequence_handler())
+            .unwrap();
+        let mut loaded_js_sandbox = js_sandbox.get_loaded_sandbox().unwrap();
+        let result = loaded_js_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        assert_eq!(result, r#"{"sequence":2}"#);
+    }
+
+    #[test]
+    fn test_restore_rejects_rollback_when_not_allowed() {
+        let proto_js_sandbox = SandboxBuilder::new()
+            .with_allow_rollback(false)
+            .build()
+            .unwrap();
+        let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+        sandbox
+            .add_handler("handler", get_sequence_handler())
+            .unwrap();
+        let mut loaded_js_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+        let snapshot = loaded_js_sandbox.snapshot().unwrap();
+        // Restoring a snapshot of the current sequence is not a rollback
+        loaded_js_sandbox.restore(snapshot.clone()).unwrap();
+
+        loaded_js_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        let err = loaded_js_sandbox.restore(snapshot).unwrap_err();
+        assert!(err.to_string().contains("rollback is not allowed"));
+        assert_eq!(loaded_js_sandbox.sequence(), 1);
+    }
 }
@@ -0,0 +1,161 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Statistics on the QuickJS heap of a sandbox, see
+/// [`LoadedJSSandbox::memory_usage`](crate::LoadedJSSandbox::memory_usage).
+///
+/// Sizes are in bytes. Comparing the statistics across invocations shows
+/// whether handlers leak memory, e.g. by growing a global cache.
+// The deserialization in here has to match the serialization of
+// MemoryStats in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MemoryStats {
+    /// Total size of the memory allocated by QuickJS.
+    pub malloc_size: u64,
+    /// Number of live allocations.
+    pub malloc_count: u64,
+    /// Size of the memory used by the objects, strings, functions, etc. of the heap.
+    pub memory_used_size: u64,
+    /// Number of atoms (interned strings, e.g. property names).
+    pub atom_count: u64,
+    /// Size of the atoms.
+    pub atom_size: u64,
+    /// Number of strings.
+    pub string_count: u64,
+    /// Size of the strings.
+    pub string_size: u64,
+    /// Number of objects.
+    pub object_count: u64,
+    /// Size of the objects.
+    pub object_size: u64,
+    /// Number of properties.
+    pub property_count: u64,
+    /// Size of the properties.
+    pub property_size: u64,
+    /// Number of JavaScript functions.
+    pub function_count: u64,
+    /// Size of the JavaScript functions.
+    pub function_size: u64,
+    /// Number of arrays.
+    pub array_count: u64,
+    /// Number of enumerable properties of the global object. The built-in
+    /// globals are not enumerable, so these are mostly created by handlers.
+    pub global_count: u64,
+}
+
+/// The QuickJS heap before and after a failed invocation, see
+/// [`SandboxBuilder::with_heap_reports`](crate::SandboxBuilder::with_heap_reports).
+///
+/// A handler that fails after allocating a lot, or after creating globals,
+/// points at a bug in the handler, while a heap that keeps growing across
+/// invocations that allocate little points at a leak in the runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct HeapReport {
+    /// The heap before the invocation.
+    pub before: MemoryStats,
+    /// The heap after the invocation, or `None` if the invocation poisoned the
+    /// sandbox, which can then not be inspected.
+    pub after: Option<MemoryStats>,
+}
+
+impl HeapReport {
+    /// How many bytes the heap grew by during the invocation.
+    pub fn malloc_size_delta(&self) -> Option<i64> {
+        self.delta(|stats| stats.malloc_size)
+    }
+
+    /// How many objects were created, and not freed, during the invocation.
+    pub fn object_count_delta(&self) -> Option<i64> {
+        self.delta(|stats| stats.object_count)
+    }
+
+    /// How many globals were created during the invocation.
+    pub fn global_count_delta(&self) -> Option<i64> {
+        self.delta(|stats| stats.global_count)
+    }
+
+    fn delta(&self, field: impl Fn(&MemoryStats) -> u64) -> Option<i64> {
+        let after = self.after.as_ref()?;
+        Some(field(after) as i64 - field(&self.before) as i64)
+    }
+}
+
+impl fmt::Display for HeapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before = &self.before;
+        write!(
+            f,
+            "heap before the call: {} bytes, {} objects, {} globals",
+            before.malloc_size, before.object_count, before.global_count
+        )?;
+        if let (Some(bytes), Some(objects), Some(globals)) = (
+            self.malloc_size_delta(),
+            self.object_count_delta(),
+            self.global_count_delta(),
+        ) {
+            write!(
+                f,
+                "; during the call: {bytes:+} bytes, {objects:+} objects, {globals:+} globals"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heap_report() {
+        let before = MemoryStats {
+            malloc_size: 1000,
+            object_count: 50,
+            global_count: 2,
+            ..Default::default()
+        };
+        let killed = HeapReport {
+            before,
+            after: None,
+        };
+        assert_eq!(killed.malloc_size_delta(), None);
+        assert_eq!(
+            killed.to_string(),
+            "heap before the call: 1000 bytes, 50 objects, 2 globals"
+        );
+
+        let failed = HeapReport {
+            before,
+            after: Some(MemoryStats {
+                malloc_size: 800,
+                object_count: 60,
+                global_count: 3,
+                ..Default::default()
+            }),
+        };
+        assert_eq!(failed.malloc_size_delta(), Some(-200));
+        assert_eq!(failed.object_count_delta(), Some(10));
+        assert_eq!(
+            failed.to_string(),
+            "heap before the call: 1000 bytes, 50 objects, 2 globals; during the call: -200 bytes, +10 objects, +1 globals"
+        );
+    }
+}
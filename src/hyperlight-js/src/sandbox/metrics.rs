@@ -17,8 +17,17 @@ limitations under the License.
 This module contains the definitions and implementations of the metrics used by the sandbox module
 */
 
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use hyperlight_host::{new_error, Result};
+use metrics::Label;
 use tracing::{instrument, Level};
 
+use super::scheduler::RunOutcome;
 use crate::{JSSandbox, LoadedJSSandbox, ProtoJSSandbox};
 
 // Gauges, active sandboxes
@@ -32,12 +41,28 @@ static METRIC_TOTAL_LOADED_JS_SANDBOXES: &str = "loaded_js_sandboxes_total";
 static METRIC_TOTAL_PROTO_JS_SANDBOXES: &str = "proto_js_sandboxes_total";
 
 // Counters, total number of times loaded sandboxes have been loaded/unloaded during the lifetime of the process
-pub(crate) static METRIC_SANDBOX_LOADS: &str = "sandbox_loads_total";
-pub(crate) static METRIC_SANDBOX_UNLOADS: &str = "sandbox_unloads_total";
+static METRIC_SANDBOX_LOADS: &str = "sandbox_loads_total";
+static METRIC_SANDBOX_UNLOADS: &str = "sandbox_unloads_total";
 
 // Counters, execution monitor terminations
-pub(crate) static METRIC_MONITOR_TERMINATIONS: &str = "monitor_terminations_total";
-pub(crate) static METRIC_MONITOR_TYPE_LABEL: &str = "monitor_type";
+static METRIC_MONITOR_TERMINATIONS: &str = "monitor_terminations_total";
+static METRIC_MONITOR_TYPE_LABEL: &str = "monitor_type";
+
+// Counters, execution monitor soft thresholds crossed
+static METRIC_MONITOR_THRESHOLDS: &str = "monitor_thresholds_total";
+
+// Counters, host function calls made by handlers
+static METRIC_HOST_CALLS: &str = "host_calls_total";
+static METRIC_HOST_CALL_LIMIT_EXCEEDED: &str = "host_call_limit_exceeded_total";
+
+// Counters, runs of scheduled jobs
+static METRIC_SCHEDULED_RUNS: &str = "scheduled_runs_total";
+static METRIC_SCHEDULED_RUNS_SKIPPED: &str = "scheduled_runs_skipped_total";
+static METRIC_SCHEDULED_JOB_LABEL: &str = "job";
+static METRIC_SCHEDULED_RUN_OUTCOME_LABEL: &str = "outcome";
+
+// Gauges, size of the QuickJS heap of the last sandbox measured
+static METRIC_GUEST_HEAP_BYTES: &str = "guest_heap_bytes";
 
 // Counters, total number of times event handlers have been called
 #[cfg(feature = "function_call_metrics")]
@@ -46,32 +71,395 @@ static METRIC_EVENT_HANDLER_CALLS: &str = "event_handler_calls_total";
 static METRIC_EVENT_HANDLER_CALLS_WITH_GC: &str = "event_handler_calls_with_gc_total";
 #[cfg(feature = "function_call_metrics")]
 static METRIC_EVENT_HANDLER_NAME: &str = "event_handler_name";
+#[cfg(feature = "function_call_metrics")]
+static METRIC_EVENT_HANDLER_OUTCOME: &str = "outcome";
+#[cfg(feature = "function_call_metrics")]
+static METRIC_EVENT_HANDLER_GUEST_ERRORS: &str = "event_handler_guest_errors_total";
+
+// Histograms and counters, host function calls by module and function
+#[cfg(feature = "function_call_metrics")]
+static METRIC_HOST_FUNCTION_CALLS: &str = "host_function_calls_total";
+#[cfg(feature = "function_call_metrics")]
+static METRIC_HOST_FUNCTION_ERRORS: &str = "host_function_errors_total";
+#[cfg(feature = "function_call_metrics")]
+static METRIC_HOST_MODULE_LABEL: &str = "module";
+#[cfg(feature = "function_call_metrics")]
+static METRIC_HOST_FUNCTION_LABEL: &str = "function";
+
+// The labels of the metrics above, which sandbox labels must not shadow
+static RESERVED_LABELS: &[&str] = &[
+    "monitor_type",
+    "job",
+    "outcome",
+    "event_handler_name",
+    "module",
+    "function",
+];
+
+/// The labels of a sandbox, added to the metrics it emits and recorded on its
+/// `handle_event` spans, see `SandboxBuilder::with_label`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SandboxLabels(Arc<Vec<(String, String)>>);
+
+impl SandboxLabels {
+    /// Set the label `key` to `value`, replacing its previous value.
+    pub(crate) fn insert(&mut self, key: String, value: String) {
+        let labels = Arc::make_mut(&mut self.0);
+        match labels.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => labels.push((key, value)),
+        }
+    }
+
+    /// Check that no label shadows a label of the metrics of hyperlight-js.
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self
+            .0
+            .iter()
+            .find(|(key, _)| RESERVED_LABELS.contains(&key.as_str()))
+        {
+            Some((key, _)) => Err(new_error!(
+                "The sandbox label '{}' is reserved by the metrics of hyperlight-js",
+                key
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the labels in the `sandbox_labels` field of `span`, if there
+    /// are any.
+    pub(crate) fn record(&self, span: &tracing::Span) {
+        if self.0.is_empty() || span.is_disabled() {
+            return;
+        }
+        span.record("sandbox_labels", tracing::field::display(self));
+    }
+
+    /// The labels of a metric of the sandbox, with its own `labels` first.
+    fn metric_labels<const N: usize>(&self, labels: [Label; N]) -> Vec<Label> {
+        labels
+            .into_iter()
+            .chain(
+                self.0
+                    .iter()
+                    .map(|(key, value)| Label::new(key.clone(), value.clone())),
+            )
+            .collect()
+    }
+}
+
+/// The labels as `key=value` pairs separated by commas, as recorded on spans.
+impl fmt::Display for SandboxLabels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A point-in-time snapshot of the metrics recorded by hyperlight-js in this process.
+///
+/// These are the same values reported through the [`metrics`](https://docs.rs/metrics) crate,
+/// for embedders that do not install a metrics recorder. See [`metrics_snapshot`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct MetricsSnapshot {
+    /// Number of `ProtoJSSandbox`es currently alive.
+    pub active_proto_js_sandboxes: u64,
+    /// Number of `JSSandbox`es currently alive.
+    pub active_js_sandboxes: u64,
+    /// Number of `LoadedJSSandbox`es currently alive.
+    pub active_loaded_js_sandboxes: u64,
+    /// Number of `ProtoJSSandbox`es created.
+    pub total_proto_js_sandboxes: u64,
+    /// Number of `JSSandbox`es created.
+    pub total_js_sandboxes: u64,
+    /// Number of `LoadedJSSandbox`es created.
+    pub total_loaded_js_sandboxes: u64,
+    /// Number of times handlers have been loaded into a sandbox.
+    pub sandbox_loads: u64,
+    /// Number of times handlers have been unloaded from a sandbox.
+    pub sandbox_unloads: u64,
+    /// Number of handler executions terminated by an execution monitor, by monitor name.
+    pub monitor_terminations: HashMap<String, u64>,
+    /// Number of soft thresholds crossed by handler executions, by monitor name.
+    pub monitor_thresholds: HashMap<String, u64>,
+    /// Number of host function calls made by handlers.
+    pub host_calls: u64,
+    /// Number of host function calls rejected because the handler exceeded
+    /// its limit of host calls per execution.
+    pub host_call_limit_exceeded: u64,
+    /// Size of the QuickJS heap of the sandbox last measured with
+    /// `LoadedJSSandbox::memory_usage`, in bytes.
+    pub guest_heap_bytes: u64,
+    /// Latencies of the handler calls, by handler name.
+    /// Only recorded when the `function_call_metrics` feature is enabled.
+    pub handler_latencies: HashMap<String, HandlerLatencies>,
+}
+
+/// Aggregated latencies of the calls to a handler.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct HandlerLatencies {
+    /// Number of calls.
+    pub calls: u64,
+    /// Number of calls that ran a garbage collection cycle afterwards.
+    pub calls_with_gc: u64,
+    /// Total time spent in the calls.
+    pub total: Duration,
+    /// Time spent in the fastest call.
+    pub min: Duration,
+    /// Time spent in the slowest call.
+    pub max: Duration,
+    /// Number of calls that failed with an error thrown by the handler.
+    pub guest_errors: u64,
+}
+
+impl HandlerLatencies {
+    /// Average time spent in a call, or zero if there have been no calls.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.calls) {
+            Ok(0) => Duration::ZERO,
+            Ok(calls) => self.total / calls,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.calls as f64),
+        }
+    }
+
+    #[cfg_attr(not(feature = "function_call_metrics"), allow(dead_code))]
+    fn record(&mut self, duration: Duration, gc: bool) {
+        if self.calls == 0 || duration < self.min {
+            self.min = duration;
+        }
+        self.max = self.max.max(duration);
+        self.total += duration;
+        self.calls += 1;
+        if gc {
+            self.calls_with_gc += 1;
+        }
+    }
+}
+
+/// Returns a snapshot of the metrics recorded by hyperlight-js in this process.
+///
+/// This does not require a [`metrics`](https://docs.rs/metrics) recorder to be installed, so
+/// embedders can surface these numbers, e.g., in their own admin endpoints.
+///
+/// # Example
+///
+/// ```
+/// let snapshot = hyperlight_js::metrics_snapshot();
+/// println!("{} sandboxes loaded", snapshot.active_loaded_js_sandboxes);
+/// ```
+pub fn metrics_snapshot() -> MetricsSnapshot {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    MetricsSnapshot {
+        active_proto_js_sandboxes: load(&PROTO_JS_SANDBOXES.active),
+        active_js_sandboxes: load(&JS_SANDBOXES.active),
+        active_loaded_js_sandboxes: load(&LOADED_JS_SANDBOXES.active),
+        total_proto_js_sandboxes: load(&PROTO_JS_SANDBOXES.total),
+        total_js_sandboxes: load(&JS_SANDBOXES.total),
+        total_loaded_js_sandboxes: load(&LOADED_JS_SANDBOXES.total),
+        sandbox_loads: load(&SANDBOX_LOADS),
+        sandbox_unloads: load(&SANDBOX_UNLOADS),
+        monitor_terminations: lock(&MONITOR_TERMINATIONS).clone(),
+        monitor_thresholds: lock(&MONITOR_THRESHOLDS).clone(),
+        host_calls: load(&HOST_CALLS),
+        host_call_limit_exceeded: load(&HOST_CALL_LIMIT_EXCEEDED),
+        guest_heap_bytes: load(&GUEST_HEAP_BYTES),
+        handler_latencies: lock(&HANDLER_LATENCIES).clone(),
+    }
+}
+
+// In-process copies of the metrics, backing `metrics_snapshot`.
+pub(crate) struct SandboxCounts {
+    active: AtomicU64,
+    total: AtomicU64,
+}
+
+impl SandboxCounts {
+    const fn new() -> Self {
+        Self {
+            active: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+        }
+    }
+}
+
+static PROTO_JS_SANDBOXES: SandboxCounts = SandboxCounts::new();
+static JS_SANDBOXES: SandboxCounts = SandboxCounts::new();
+static LOADED_JS_SANDBOXES: SandboxCounts = SandboxCounts::new();
+static SANDBOX_LOADS: AtomicU64 = AtomicU64::new(0);
+static SANDBOX_UNLOADS: AtomicU64 = AtomicU64::new(0);
+static HOST_CALLS: AtomicU64 = AtomicU64::new(0);
+static HOST_CALL_LIMIT_EXCEEDED: AtomicU64 = AtomicU64::new(0);
+static GUEST_HEAP_BYTES: AtomicU64 = AtomicU64::new(0);
+static MONITOR_TERMINATIONS: LazyLock<Mutex<HashMap<String, u64>>> =
+    LazyLock::new(Default::default);
+static MONITOR_THRESHOLDS: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(Default::default);
+static HANDLER_LATENCIES: LazyLock<Mutex<HashMap<String, HandlerLatencies>>> =
+    LazyLock::new(Default::default);
+
+// Metrics must keep working even if a thread panicked while holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Record that handlers have been loaded into a sandbox.
+pub(crate) fn record_sandbox_load() {
+    metrics::counter!(METRIC_SANDBOX_LOADS).increment(1);
+    SANDBOX_LOADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record that handlers have been unloaded from a sandbox.
+pub(crate) fn record_sandbox_unload() {
+    metrics::counter!(METRIC_SANDBOX_UNLOADS).increment(1);
+    SANDBOX_UNLOADS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record that the monitor `monitor_type` terminated a handler execution of a
+/// sandbox with the labels `labels`.
+pub(crate) fn record_monitor_termination(monitor_type: &'static str, labels: &SandboxLabels) {
+    let labels = labels.metric_labels([Label::new(METRIC_MONITOR_TYPE_LABEL, monitor_type)]);
+    metrics::counter!(METRIC_MONITOR_TERMINATIONS, labels.iter()).increment(1);
+    *lock(&MONITOR_TERMINATIONS)
+        .entry(monitor_type.to_string())
+        .or_default() += 1;
+}
+
+/// Record that a handler execution crossed a soft threshold of the monitor `monitor_type`.
+pub(crate) fn record_monitor_threshold(monitor_type: &'static str) {
+    metrics::counter!(
+        METRIC_MONITOR_THRESHOLDS,
+        METRIC_MONITOR_TYPE_LABEL => monitor_type
+    )
+    .increment(1);
+    *lock(&MONITOR_THRESHOLDS)
+        .entry(monitor_type.to_string())
+        .or_default() += 1;
+}
+
+/// Record that a handler called a host function.
+pub(crate) fn record_host_call() {
+    metrics::counter!(METRIC_HOST_CALLS).increment(1);
+    HOST_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record that a host function call was rejected because the handler exceeded
+/// its limit of host calls.
+pub(crate) fn record_host_call_limit_exceeded() {
+    metrics::counter!(METRIC_HOST_CALL_LIMIT_EXCEEDED).increment(1);
+    HOST_CALL_LIMIT_EXCEEDED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record a call to the host function `function` of the host module `module`,
+/// that took `duration` and failed if `failed`.
+#[cfg(feature = "function_call_metrics")]
+pub(crate) fn record_host_function_call(
+    module: &str,
+    function: &str,
+    duration: Duration,
+    failed: bool,
+) {
+    metrics::histogram!(
+        METRIC_HOST_FUNCTION_CALLS,
+        METRIC_HOST_MODULE_LABEL => module.to_string(),
+        METRIC_HOST_FUNCTION_LABEL => function.to_string()
+    )
+    .record(duration.as_micros() as f64);
+    if failed {
+        metrics::counter!(
+            METRIC_HOST_FUNCTION_ERRORS,
+            METRIC_HOST_MODULE_LABEL => module.to_string(),
+            METRIC_HOST_FUNCTION_LABEL => function.to_string()
+        )
+        .increment(1);
+    }
+}
+
+/// Record a run of the scheduled job `job`.
+pub(crate) fn record_scheduled_run(job: &str, outcome: &RunOutcome) {
+    let outcome = match outcome {
+        RunOutcome::Succeeded => "succeeded",
+        RunOutcome::Failed(_) => "failed",
+        RunOutcome::TimedOut => "timed_out",
+    };
+    metrics::counter!(
+        METRIC_SCHEDULED_RUNS,
+        METRIC_SCHEDULED_JOB_LABEL => job.to_string(),
+        METRIC_SCHEDULED_RUN_OUTCOME_LABEL => outcome
+    )
+    .increment(1);
+}
+
+/// Record that `count` runs of the scheduled job `job` were skipped.
+pub(crate) fn record_scheduled_run_skipped(job: &str, count: u64) {
+    metrics::counter!(METRIC_SCHEDULED_RUNS_SKIPPED, METRIC_SCHEDULED_JOB_LABEL => job.to_string())
+        .increment(count);
+}
+
+/// Record the size of the QuickJS heap of a sandbox.
+pub(crate) fn record_guest_heap_bytes(bytes: u64) {
+    metrics::gauge!(METRIC_GUEST_HEAP_BYTES).set(bytes as f64);
+    GUEST_HEAP_BYTES.store(bytes, Ordering::Relaxed);
+}
 
 pub(crate) trait SandboxMetricsTrait {
     const GAUGE: &'static str;
     const COUNTER: &'static str;
+    fn counts() -> &'static SandboxCounts;
+}
+
+pub(crate) struct SandboxMetricsGuard<T: SandboxMetricsTrait> {
+    labels: Vec<Label>,
+    _sandbox: std::marker::PhantomData<T>,
 }
 
-pub(crate) struct SandboxMetricsGuard<T: SandboxMetricsTrait>(std::marker::PhantomData<T>);
+/// The outcome of a handler call, as the `outcome` label of its metrics.
+#[cfg(feature = "function_call_metrics")]
+fn handler_outcome<T>(result: &hyperlight_host::Result<T>) -> &'static str {
+    use hyperlight_host::HyperlightError;
+
+    match result {
+        Ok(_) => "success",
+        Err(HyperlightError::GuestError(..)) => "guest_error",
+        // Monitors and cancellation both end the call with this error
+        Err(HyperlightError::ExecutionCanceledByHost()) => "cancelled",
+        Err(_) => "error",
+    }
+}
 
 #[cfg(feature = "function_call_metrics")]
 pub(crate) struct EventHandlerMetricGuard<'a> {
     func_name: &'a str,
     gc: bool,
+    labels: SandboxLabels,
     start: std::time::Instant,
+    outcome: &'static str,
 }
 
 #[cfg(feature = "function_call_metrics")]
 impl<'a> EventHandlerMetricGuard<'a> {
     #[instrument(skip_all, level=Level::DEBUG)]
-    pub(crate) fn new(func_name: &'a str, gc: bool) -> Self {
+    pub(crate) fn new(func_name: &'a str, gc: bool, labels: SandboxLabels) -> Self {
         let start = std::time::Instant::now();
         Self {
             func_name,
             gc,
+            labels,
             start,
+            // The call did not get to its result
+            outcome: "error",
         }
     }
+
+    /// Record the outcome of the call, from its result.
+    pub(crate) fn set_outcome<T>(&mut self, result: &hyperlight_host::Result<T>) {
+        self.outcome = handler_outcome(result);
+    }
 }
 
 #[cfg(feature = "function_call_metrics")]
@@ -80,48 +468,86 @@ impl Drop for EventHandlerMetricGuard<'_> {
     fn drop(&mut self) {
         let duration = self.start.elapsed();
         let func_name = self.func_name.to_string();
-        if self.gc {
-            metrics::histogram!(METRIC_EVENT_HANDLER_CALLS_WITH_GC, METRIC_EVENT_HANDLER_NAME => func_name).record(duration.as_micros() as f64);
-        } else {
-            metrics::histogram!(METRIC_EVENT_HANDLER_CALLS, METRIC_EVENT_HANDLER_NAME => func_name)
-                .record(duration.as_micros() as f64);
+        let guest_error = self.outcome == "guest_error";
+        {
+            let mut latencies = lock(&HANDLER_LATENCIES);
+            let latencies = latencies.entry(func_name.clone()).or_default();
+            latencies.record(duration, self.gc);
+            if guest_error {
+                latencies.guest_errors += 1;
+            }
+        }
+        if guest_error {
+            let labels = self
+                .labels
+                .metric_labels([Label::new(METRIC_EVENT_HANDLER_NAME, func_name.clone())]);
+            metrics::counter!(METRIC_EVENT_HANDLER_GUEST_ERRORS, labels.iter()).increment(1);
         }
+        let histogram = if self.gc {
+            METRIC_EVENT_HANDLER_CALLS_WITH_GC
+        } else {
+            METRIC_EVENT_HANDLER_CALLS
+        };
+        let labels = self.labels.metric_labels([
+            Label::new(METRIC_EVENT_HANDLER_NAME, func_name),
+            Label::new(METRIC_EVENT_HANDLER_OUTCOME, self.outcome),
+        ]);
+        metrics::histogram!(histogram, labels.iter()).record(duration.as_micros() as f64);
     }
 }
 
 impl<T: SandboxMetricsTrait> SandboxMetricsGuard<T> {
     #[instrument(skip_all, level=Level::DEBUG)]
-    pub(crate) fn new() -> Self {
-        metrics::gauge!(T::GAUGE).increment(1);
-        metrics::counter!(T::COUNTER).increment(1);
-        Self(std::marker::PhantomData)
+    pub(crate) fn new(labels: &SandboxLabels) -> Self {
+        let labels = labels.metric_labels([]);
+        metrics::gauge!(T::GAUGE, labels.iter()).increment(1);
+        metrics::counter!(T::COUNTER, labels.iter()).increment(1);
+        T::counts().active.fetch_add(1, Ordering::Relaxed);
+        T::counts().total.fetch_add(1, Ordering::Relaxed);
+        Self {
+            labels,
+            _sandbox: std::marker::PhantomData,
+        }
     }
 }
 
 impl<T: SandboxMetricsTrait> Drop for SandboxMetricsGuard<T> {
     #[instrument(skip_all, level=Level::DEBUG)]
     fn drop(&mut self) {
-        metrics::gauge!(T::GAUGE).decrement(1);
+        metrics::gauge!(T::GAUGE, self.labels.iter()).decrement(1);
+        T::counts().active.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
 impl SandboxMetricsTrait for JSSandbox {
     const GAUGE: &'static str = METRIC_ACTIVE_JS_SANDBOXES;
     const COUNTER: &'static str = METRIC_TOTAL_JS_SANDBOXES;
+    fn counts() -> &'static SandboxCounts {
+        &JS_SANDBOXES
+    }
 }
 
 impl SandboxMetricsTrait for LoadedJSSandbox {
     const GAUGE: &'static str = METRIC_ACTIVE_LOADED_JS_SANDBOXES;
     const COUNTER: &'static str = METRIC_TOTAL_LOADED_JS_SANDBOXES;
+    fn counts() -> &'static SandboxCounts {
+        &LOADED_JS_SANDBOXES
+    }
 }
 
 impl SandboxMetricsTrait for ProtoJSSandbox {
     const GAUGE: &'static str = METRIC_ACTIVE_PROTO_JS_SANDBOXES;
     const COUNTER: &'static str = METRIC_TOTAL_PROTO_JS_SANDBOXES;
+    fn counts() -> &'static SandboxCounts {
+        &PROTO_JS_SANDBOXES
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use super::{metrics_snapshot, record_monitor_termination, HandlerLatencies, SandboxLabels};
     use crate::{SandboxBuilder, Script};
 
     fn get_valid_handler() -> Script {
@@ -177,4 +603,185 @@ mod tests {
             assert_eq!(snapshot.len(), 7);
         }
     }
+
+    #[test]
+    #[cfg(feature = "function_call_metrics")]
+    fn test_host_function_metrics() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+            proto_js_sandbox
+                .register("math", "add", |a: i32, b: i32| a + b)
+                .unwrap();
+            proto_js_sandbox
+                .register_raw("math", "fail", |_args: String| {
+                    Err(hyperlight_host::new_error!("failed"))
+                })
+                .unwrap();
+            let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+            let handler = Script::from_content(
+                r#"
+                import * as math from "math";
+                function handler(event) {
+                    math.add(1, 2);
+                    math.add(3, 4);
+                    try { math.fail(); } catch (e) {}
+                    return {};
+                }
+                "#,
+            );
+            sandbox.add_handler("handler", handler).unwrap();
+            let mut loaded_js_sandbox = sandbox.get_loaded_sandbox().unwrap();
+            loaded_js_sandbox
+                .handle_event("handler", "{}".to_string(), None)
+                .unwrap();
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let find = |name: &str, function: &str| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| {
+                    key.key().name() == name
+                        && key
+                            .key()
+                            .labels()
+                            .any(|label| label.key() == "function" && label.value() == function)
+                })
+                .map(|(.., value)| value)
+        };
+        match find("host_function_calls_total", "add") {
+            Some(DebugValue::Histogram(latencies)) => assert_eq!(latencies.len(), 2),
+            value => panic!("{value:?}"),
+        }
+        assert!(find("host_function_errors_total", "add").is_none());
+        assert_eq!(
+            find("host_function_errors_total", "fail"),
+            Some(&DebugValue::Counter(1))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "function_call_metrics")]
+    fn test_sandbox_labels() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let proto_js_sandbox = SandboxBuilder::new()
+                .with_label("tenant", "acme")
+                .build()
+                .unwrap();
+            let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+            sandbox.add_handler("handler", get_valid_handler()).unwrap();
+            let mut loaded_js_sandbox = sandbox.get_loaded_sandbox().unwrap();
+            loaded_js_sandbox
+                .handle_event("handler", get_valid_event(), Some(false))
+                .unwrap();
+
+            let snapshot = snapshotter.snapshot().into_vec();
+            let find = |name: &str| {
+                snapshot
+                    .iter()
+                    .find(|(key, ..)| {
+                        key.key().name() == name
+                            && key
+                                .key()
+                                .labels()
+                                .any(|label| label.key() == "tenant" && label.value() == "acme")
+                    })
+                    .map(|(.., value)| value)
+            };
+            assert_eq!(
+                find("active_loaded_js_sandboxes"),
+                Some(&DebugValue::Gauge(1.0.into()))
+            );
+            match find("event_handler_calls_total") {
+                Some(DebugValue::Histogram(latencies)) => assert_eq!(latencies.len(), 1),
+                value => panic!("{value:?}"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_reserved_sandbox_label() {
+        let err = SandboxBuilder::new()
+            .with_label("outcome", "success")
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("'outcome' is reserved"), "{err}");
+    }
+
+    #[test]
+    fn test_sandbox_labels_display() {
+        let mut labels = SandboxLabels::default();
+        assert_eq!(labels.to_string(), "");
+        labels.insert("tenant".to_string(), "acme".to_string());
+        labels.insert("region".to_string(), "westeurope".to_string());
+        labels.insert("tenant".to_string(), "contoso".to_string());
+        assert_eq!(labels.to_string(), "tenant=contoso,region=westeurope");
+        assert!(labels.validate().is_ok());
+    }
+
+    #[test]
+    fn test_handler_latencies() {
+        let mut latencies = HandlerLatencies::default();
+        assert_eq!(latencies.mean(), Duration::ZERO);
+
+        latencies.record(Duration::from_millis(30), false);
+        latencies.record(Duration::from_millis(10), true);
+        latencies.record(Duration::from_millis(20), false);
+
+        assert_eq!(latencies.calls, 3);
+        assert_eq!(latencies.calls_with_gc, 1);
+        assert_eq!(latencies.total, Duration::from_millis(60));
+        assert_eq!(latencies.min, Duration::from_millis(10));
+        assert_eq!(latencies.max, Duration::from_millis(30));
+        assert_eq!(latencies.mean(), Duration::from_millis(20));
+    }
+
+    #[test]
+    #[cfg(feature = "function_call_metrics")]
+    fn test_handler_outcome() {
+        use hyperlight_host::HyperlightError;
+
+        use super::handler_outcome;
+
+        assert_eq!(handler_outcome(&Ok(())), "success");
+        assert_eq!(
+            handler_outcome::<()>(&Err(HyperlightError::GuestError(
+                hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode::GuestError,
+                "Error: thrown".to_string()
+            ))),
+            "guest_error"
+        );
+        assert_eq!(
+            handler_outcome::<()>(&Err(HyperlightError::ExecutionCanceledByHost())),
+            "cancelled"
+        );
+        assert_eq!(
+            handler_outcome::<()>(&Err(HyperlightError::PoisonedSandbox)),
+            "error"
+        );
+    }
+
+    #[test]
+    fn test_metrics_snapshot_monitor_terminations() {
+        let terminations = |snapshot: &super::MetricsSnapshot| {
+            snapshot
+                .monitor_terminations
+                .get("test-snapshot-monitor")
+                .copied()
+                .unwrap_or_default()
+        };
+        let before = terminations(&metrics_snapshot());
+        let labels = SandboxLabels::default();
+        record_monitor_termination("test-snapshot-monitor", &labels);
+        record_monitor_termination("test-snapshot-monitor", &labels);
+        assert_eq!(terminations(&metrics_snapshot()), before + 2);
+    }
 }
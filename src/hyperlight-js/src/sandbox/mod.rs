@@ -15,21 +15,72 @@ limitations under the License.
 */
 //! The `sandbox` module contains the sandbox types for the Hyperlight JavaScript runtime.
 use std::env;
+/// Where the JavaScript runtime of a sandbox runs.
+pub(crate) mod backend;
+/// The context of the handler invocation host functions are called from.
+pub(crate) mod call_context;
+/// Cooperative cancellation of running handlers.
+pub(crate) mod cancellation;
+/// The virtual clock and seeds of sandboxes in deterministic mode.
+pub(crate) mod deterministic;
+/// Diagnostics reports of loaded sandboxes.
+pub(crate) mod diagnostics;
+/// The error of events too large for the input buffer of the guest.
+pub(crate) mod event_size;
+/// When to run garbage collection after handler calls.
+pub(crate) mod gc_policy;
+/// Limits on the handlers a sandbox accepts.
+pub(crate) mod handler_limits;
+/// Errors returned by host functions that the guest throws as JS errors.
+pub(crate) mod host_error;
 /// Definition of a host function that can be called from guest JavaScript code.
 pub(crate) mod host_fn;
+/// The built-in host functions the guest may call.
+pub(crate) mod host_function_allowlist;
+/// The JavaScript runtime running in the host process, without isolation.
+#[cfg(any(feature = "in-process", feature = "testing"))]
+pub(crate) mod in_process;
+/// How the handlers of a sandbox are isolated from each other.
+pub(crate) mod isolation_mode;
 /// A Hyperlight Sandbox with a JavaScript run time loaded but no guest code.
 pub(crate) mod js_sandbox;
+/// The errors of hyperlight-js, to match on their kind.
+pub(crate) mod js_sandbox_error;
 /// A Hyperlight Sandbox with a JavaScript run time loaded and guest code loaded.
 pub(crate) mod loaded_js_sandbox;
+/// Statistics on the QuickJS heap of a sandbox.
+pub(crate) mod memory_stats;
 /// Metric definitions for Sandbox module.
 pub(crate) mod metrics;
 /// Execution monitoring and enforcement (timeouts, resource limits, etc.).
 pub mod monitor;
+/// Callbacks on the lifecycle events of a sandbox.
+pub(crate) mod observer;
+/// The error of guests that run out of memory.
+pub(crate) mod out_of_memory;
+/// Why a sandbox is poisoned.
+pub(crate) mod poison_reason;
 /// A Hyperlight Sandbox with no JavaScript run time loaded and no guest code.
 /// This is used to register new host functions prior to loading the JavaScript runtime.
 pub(crate) mod proto_js_sandbox;
+/// The error of handlers returning results over the size limit.
+pub(crate) mod result_size;
+/// Retrying handlers that fail transiently.
+pub(crate) mod retry;
+/// Options for configuring the JavaScript runtime in the guest.
+pub(crate) mod runtime_options;
 /// A builder for creating a new `JSSandbox`
 pub(crate) mod sandbox_builder;
+/// Running handlers on schedules.
+pub(crate) mod scheduler;
+/// Invocation sequence numbers and snapshot rollback protection.
+pub(crate) mod sequence;
+/// Settings of a sandbox carried across loading and unloading its handlers.
+pub(crate) mod settings;
+/// Propagation of the W3C trace context of handler invocations.
+pub(crate) mod trace_context;
+/// Compression of the events and results crossing the sandbox boundary.
+pub(crate) mod wire_compression;
 // This include! macro is replaced by the build.rs script.
 // The build.rs script reads the hyperlight-js-runtime binary into a static byte array named JSRUNTIME.
 include!(concat!(env!("OUT_DIR"), "/host_resource.rs"));
@@ -0,0 +1,276 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Cumulative execution budget across handler invocations.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "monitor-wall-clock")]
+use std::time::Instant;
+
+use hyperlight_host::{HyperlightError, Result};
+
+#[cfg(feature = "monitor-cpu-time")]
+use super::cpu_time::ThreadCpuHandle;
+use super::ExecutionMonitor;
+
+/// Monitors the total time used by all the handler invocations it monitors.
+///
+/// Unlike [`WallClockMonitor`](super::WallClockMonitor) and
+/// [`CpuTimeMonitor`](super::CpuTimeMonitor), which limit each invocation on
+/// its own, a `BudgetMonitor` keeps a running total of the time used by every
+/// invocation it monitors, and terminates the invocation that takes the total
+/// over the budget. Once the budget is exhausted, further invocations fail to
+/// start until it is [`reset`](Self::reset).
+///
+/// Clones share the same running total, so keep one `BudgetMonitor` per
+/// sandbox (or per tenant) and pass it to every `handle_event_with_monitor`
+/// call. It composes with other monitors via tuples, e.g. to also limit each
+/// invocation.
+///
+/// # Example
+///
+/// ```text
+/// use hyperlight_js::BudgetMonitor;
+/// use std::time::Duration;
+///
+/// // This sandbox may use at most 10s of CPU time across all calls
+/// let budget = BudgetMonitor::cpu_time(Duration::from_secs(10))?;
+/// let result = sandbox.handle_event_with_monitor("handler", "{}".to_string(), &budget, None)?;
+/// println!("used {:?}, {:?} left", budget.consumed(), budget.remaining());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BudgetMonitor {
+    budget: Duration,
+    clock: BudgetClock,
+    // Nanoseconds used by the invocations that finished.
+    consumed: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BudgetClock {
+    #[cfg(feature = "monitor-wall-clock")]
+    WallClock,
+    #[cfg(feature = "monitor-cpu-time")]
+    CpuTime,
+}
+
+impl BudgetMonitor {
+    /// Create a monitor with a budget of `budget` of wall-clock time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `budget` is zero.
+    #[cfg(feature = "monitor-wall-clock")]
+    pub fn wall_clock(budget: Duration) -> Result<Self> {
+        Self::new(budget, BudgetClock::WallClock)
+    }
+
+    /// Create a monitor with a budget of `budget` of CPU time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `budget` is zero.
+    #[cfg(feature = "monitor-cpu-time")]
+    pub fn cpu_time(budget: Duration) -> Result<Self> {
+        Self::new(budget, BudgetClock::CpuTime)
+    }
+
+    fn new(budget: Duration, clock: BudgetClock) -> Result<Self> {
+        if budget.is_zero() {
+            return Err(HyperlightError::Error(
+                "budget must be non-zero".to_string(),
+            ));
+        }
+        Ok(Self {
+            budget,
+            clock,
+            consumed: Arc::default(),
+        })
+    }
+
+    /// The total budget.
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// The time used by the monitored invocations that finished.
+    pub fn consumed(&self) -> Duration {
+        Duration::from_nanos(self.consumed.load(Ordering::Relaxed))
+    }
+
+    /// The time left in the budget.
+    pub fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.consumed())
+    }
+
+    /// Reset the consumed time to zero, e.g. at the start of a new billing period.
+    pub fn reset(&self) {
+        self.consumed.store(0, Ordering::Relaxed);
+    }
+}
+
+impl ExecutionMonitor for BudgetMonitor {
+    fn get_monitor(&self) -> Result<impl Future<Output = ()> + Send + 'static> {
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            return Err(HyperlightError::Error(format!(
+                "Execution budget of {:?} is exhausted",
+                self.budget
+            )));
+        }
+
+        // Start measuring on the calling thread (e.g. to capture its CPU clock).
+        // The usage is added to the total when the future is dropped, which
+        // happens both when the budget runs out and when the handler finishes.
+        let usage = Usage {
+            meter: Meter::start(self.clock)?,
+            consumed: self.consumed.clone(),
+        };
+        let budget = self.budget;
+
+        Ok(async move {
+            loop {
+                let Some(used) = usage.meter.elapsed() else {
+                    tracing::error!(
+                        "Failed to read the used time — terminating execution (fail-closed)"
+                    );
+                    return;
+                };
+                if used >= remaining {
+                    break;
+                }
+                super::sleep(usage.meter.poll_interval(remaining - used)).await;
+            }
+            tracing::warn!(
+                budget_ms = budget.as_millis() as u64,
+                "Execution budget exhausted, terminating execution"
+            );
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "budget"
+    }
+}
+
+/// Measures the time used by one invocation.
+enum Meter {
+    #[cfg(feature = "monitor-wall-clock")]
+    WallClock(Instant),
+    #[cfg(feature = "monitor-cpu-time")]
+    CpuTime { handle: ThreadCpuHandle, start: u64 },
+}
+
+impl Meter {
+    fn start(clock: BudgetClock) -> Result<Self> {
+        match clock {
+            #[cfg(feature = "monitor-wall-clock")]
+            BudgetClock::WallClock => Ok(Self::WallClock(Instant::now())),
+            #[cfg(feature = "monitor-cpu-time")]
+            BudgetClock::CpuTime => {
+                let handle = ThreadCpuHandle::for_current_thread().ok_or_else(|| {
+                    HyperlightError::Error(
+                        "Failed to get CPU time handle for current thread".to_string(),
+                    )
+                })?;
+                let start = handle.elapsed().ok_or_else(|| {
+                    HyperlightError::Error("Failed to read initial CPU time".to_string())
+                })?;
+                Ok(Self::CpuTime { handle, start })
+            }
+        }
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        match self {
+            #[cfg(feature = "monitor-wall-clock")]
+            Self::WallClock(start) => Some(start.elapsed()),
+            #[cfg(feature = "monitor-cpu-time")]
+            Self::CpuTime { handle, start } => {
+                let ticks = handle.elapsed()?.saturating_sub(*start);
+                Some(Duration::from_nanos(handle.ticks_to_approx_nanos(ticks)))
+            }
+        }
+    }
+
+    /// How long to sleep before checking the usage again.
+    fn poll_interval(&self, remaining: Duration) -> Duration {
+        match self {
+            #[cfg(feature = "monitor-wall-clock")]
+            Self::WallClock(_) => remaining,
+            // CPU time only advances while the guest runs, so poll with the
+            // same adaptive interval as `CpuTimeMonitor`.
+            #[cfg(feature = "monitor-cpu-time")]
+            Self::CpuTime { .. } => {
+                (remaining / 2).clamp(Duration::from_millis(1), Duration::from_millis(10))
+            }
+        }
+    }
+}
+
+/// Adds the time measured by `meter` to the running total when dropped.
+struct Usage {
+    meter: Meter,
+    consumed: Arc<AtomicU64>,
+}
+
+impl Drop for Usage {
+    fn drop(&mut self) {
+        if let Some(used) = self.meter.elapsed() {
+            self.consumed
+                .fetch_add(used.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "monitor-wall-clock"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_budget_rejected() {
+        let result = BudgetMonitor::wall_clock(Duration::ZERO);
+        assert!(result.is_err(), "Zero budget should be rejected");
+    }
+
+    #[tokio::test]
+    async fn test_budget_is_cumulative() {
+        let budget = BudgetMonitor::wall_clock(Duration::from_millis(100)).unwrap();
+
+        // An invocation that finishes before the budget runs out
+        let future = budget.get_monitor().unwrap();
+        let fired = tokio::time::timeout(Duration::from_millis(40), future).await;
+        assert!(fired.is_err(), "Should not fire within the budget");
+        assert!(budget.consumed() >= Duration::from_millis(40));
+
+        // The next invocation only gets the rest of the budget
+        let clone = budget.clone();
+        let future = clone.get_monitor().unwrap();
+        let fired = tokio::time::timeout(Duration::from_secs(5), future).await;
+        assert!(fired.is_ok(), "Should fire once the budget is used up");
+        assert!(budget.remaining().is_zero());
+        assert!(
+            budget.get_monitor().is_err(),
+            "Should not start once the budget is exhausted"
+        );
+
+        budget.reset();
+        assert_eq!(budget.consumed(), Duration::ZERO);
+        assert!(budget.get_monitor().is_ok());
+    }
+}
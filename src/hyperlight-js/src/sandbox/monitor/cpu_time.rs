@@ -34,7 +34,7 @@ use std::time::Duration;
 
 use hyperlight_host::{HyperlightError, Result};
 
-use super::ExecutionMonitor;
+use super::{ExecutionMonitor, Threshold, ThresholdEvent};
 
 /// Monitors handler execution using CPU time.
 ///
@@ -75,9 +75,22 @@ use super::ExecutionMonitor;
 /// let monitor = CpuTimeMonitor::new(Duration::from_millis(100))?;
 /// let result = sandbox.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None)?;
 /// ```
+///
+/// # Soft Thresholds
+///
+/// Use [`on_threshold`](Self::on_threshold) to be notified when a handler
+/// gets close to its CPU budget, without terminating it:
+///
+/// ```text
+/// let monitor = CpuTimeMonitor::new(Duration::from_millis(500))?
+///     .on_threshold(Duration::from_millis(400), |event| {
+///         tracing::warn!("handler used {:?} of its {:?} CPU budget", event.threshold, event.limit);
+///     });
+/// ```
 #[derive(Debug, Clone)]
 pub struct CpuTimeMonitor {
     cpu_timeout: Duration,
+    thresholds: Vec<Threshold>,
 }
 
 impl CpuTimeMonitor {
@@ -96,7 +109,26 @@ impl CpuTimeMonitor {
                 "cpu_timeout must be non-zero".to_string(),
             ));
         }
-        Ok(Self { cpu_timeout })
+        Ok(Self {
+            cpu_timeout,
+            thresholds: Vec::new(),
+        })
+    }
+
+    /// Invoke `callback` when a handler has used `threshold` of CPU time,
+    /// without terminating it.
+    ///
+    /// Crossing the threshold also emits the `monitor_thresholds_total`
+    /// metric. The callback runs on the shared monitor runtime, so it must
+    /// not block. Thresholds at or above the CPU time limit never fire.
+    pub fn on_threshold(
+        mut self,
+        threshold: Duration,
+        callback: impl Fn(&ThresholdEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.thresholds.push(Threshold::new(threshold, callback));
+        self.thresholds.sort_by_key(|threshold| threshold.at);
+        self
     }
 }
 
@@ -118,6 +150,19 @@ impl ExecutionMonitor for CpuTimeMonitor {
             HyperlightError::Error("Failed to compute CPU tick deadline".to_string())
         })?;
         let deadline = start_ticks.saturating_add(tick_budget);
+        let mut thresholds = self
+            .thresholds
+            .iter()
+            .map(|threshold| {
+                let ticks = cpu_handle.deadline_for(threshold.at).ok_or_else(|| {
+                    HyperlightError::Error("Failed to compute CPU tick threshold".to_string())
+                })?;
+                Ok((start_ticks.saturating_add(ticks), threshold.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .peekable();
+        let name = self.name();
 
         Ok(async move {
             loop {
@@ -145,6 +190,11 @@ impl ExecutionMonitor for CpuTimeMonitor {
                     return;
                 }
 
+                while let Some((_, threshold)) = thresholds.next_if(|(ticks, _)| *ticks <= current)
+                {
+                    threshold.fire(name, cpu_timeout);
+                }
+
                 // Adaptive sleep: half of remaining time, clamped to reasonable bounds.
                 // Tokio's timer wheel resolution is ~1ms, so that's our effective floor.
                 // The maximum keeps polls frequent enough for reasonable deadline accuracy.
@@ -153,7 +203,10 @@ impl ExecutionMonitor for CpuTimeMonitor {
                 const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
                 const ADAPTIVE_DIVISOR: u64 = 2;
 
-                let remaining = deadline.saturating_sub(current);
+                let next = thresholds
+                    .peek()
+                    .map_or(deadline, |(ticks, _)| (*ticks).min(deadline));
+                let remaining = next.saturating_sub(current);
                 let remaining_nanos = cpu_handle.ticks_to_approx_nanos(remaining);
                 let sleep_duration = Duration::from_nanos(remaining_nanos / ADAPTIVE_DIVISOR)
                     .clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL);
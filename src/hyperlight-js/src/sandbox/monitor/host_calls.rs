@@ -0,0 +1,139 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Accounting of the host functions called by the guest.
+//!
+//! Host functions run on the thread that called into the guest, so the time
+//! spent in them and their number are tracked per thread. Monitors capture the
+//! clock of the calling thread in `get_monitor()`, like `CpuTimeMonitor`
+//! captures its CPU clock, and read it from the monitor runtime.
+
+use std::cell::Cell;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static CURRENT: Arc<HostCallClock> = Arc::default();
+    // The number of host calls made by the guest call running on this thread.
+    static CALLS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Reset the number of host calls counted on the current thread, at the start
+/// of a guest call.
+pub(crate) fn reset_call_count() {
+    CALLS.set(0);
+}
+
+/// Count a host call on the current thread, returning the number of host
+/// calls since the last [`reset_call_count`].
+pub(crate) fn count_call() -> u64 {
+    let calls = CALLS.get() + 1;
+    CALLS.set(calls);
+    calls
+}
+
+/// The time spent in host functions called by the guest on one thread.
+#[derive(Debug, Default)]
+pub(crate) struct HostCallClock {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // The time spent in host calls that returned.
+    total: Duration,
+    // When the host call in progress, if any, started.
+    since: Option<Instant>,
+}
+
+impl HostCallClock {
+    /// The clock of the current thread.
+    pub(crate) fn current() -> Arc<Self> {
+        CURRENT.with(Arc::clone)
+    }
+
+    /// Account the time until the returned guard is dropped as spent in a
+    /// host call on the current thread.
+    pub(crate) fn enter() -> HostCall {
+        let clock = Self::current();
+        clock.state().since.get_or_insert_with(Instant::now);
+        HostCall(clock)
+    }
+
+    /// The total time spent in host calls, including the call in progress.
+    #[cfg_attr(not(feature = "monitor-wall-clock"), allow(dead_code))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        let state = self.state();
+        state.total + state.since.map(|since| since.elapsed()).unwrap_or_default()
+    }
+
+    // The state is updated atomically, so it is safe to recover from poisoning.
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Guard accounting a host call until it is dropped.
+pub(crate) struct HostCall(Arc<HostCallClock>);
+
+impl Drop for HostCall {
+    fn drop(&mut self) {
+        let mut state = self.0.state();
+        if let Some(since) = state.since.take() {
+            state.total += since.elapsed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_call_clock() {
+        let clock = HostCallClock::current();
+        let before = clock.elapsed();
+
+        let call = HostCallClock::enter();
+        std::thread::sleep(Duration::from_millis(20));
+        // The call in progress is included
+        assert!(clock.elapsed() - before >= Duration::from_millis(20));
+        drop(call);
+
+        let after = clock.elapsed();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.elapsed(), after);
+
+        // Other threads have their own clock
+        let other = std::thread::spawn(|| HostCallClock::current().elapsed())
+            .join()
+            .unwrap();
+        assert_eq!(other, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_call_count() {
+        reset_call_count();
+        assert_eq!(count_call(), 1);
+        assert_eq!(count_call(), 2);
+
+        // Other threads have their own count
+        let other = std::thread::spawn(count_call).join().unwrap();
+        assert_eq!(other, 1);
+
+        reset_call_count();
+        assert_eq!(count_call(), 1);
+    }
+}
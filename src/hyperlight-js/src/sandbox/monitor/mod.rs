@@ -127,7 +127,10 @@ limitations under the License.
 //!
 //! # Runtime Configuration
 //!
-//! The shared async runtime thread count can be configured via environment variable:
+//! The shared async runtime can be sized, or replaced by an existing application
+//! runtime, with [`SandboxBuilder::with_monitor_runtime_threads`](crate::SandboxBuilder::with_monitor_runtime_threads)
+//! and [`SandboxBuilder::with_monitor_runtime`](crate::SandboxBuilder::with_monitor_runtime).
+//! Otherwise the thread count can be configured via environment variable:
 //!
 //! ```bash
 //! export HYPERLIGHT_MONITOR_THREADS=4  # Default is 2
@@ -135,24 +138,27 @@ limitations under the License.
 //!
 //! See the `runtime` module for details on the shared runtime.
 
-use std::future::Future;
+use std::future::{poll_fn, Future};
 use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::Poll;
+use std::time::Duration;
 
-use hyperlight_host::Result;
+use hyperlight_host::hypervisor::InterruptHandle;
+use hyperlight_host::{HyperlightError, Result};
+use tokio::task::JoinHandle;
 
-use crate::sandbox::metrics::{METRIC_MONITOR_TERMINATIONS, METRIC_MONITOR_TYPE_LABEL};
+use crate::sandbox::cancellation::CancellationHandle;
+use crate::sandbox::metrics::{
+    record_monitor_termination, record_monitor_threshold, SandboxLabels,
+};
 
 /// Record that a monitor triggered execution termination.
 ///
-/// Emits the `monitor_terminations_total` counter metric with the winning
-/// monitor's name as the `monitor_type` label, and logs a warning.
+/// Logs a warning with the winning monitor's name. The
+/// `monitor_terminations_total` metric is emitted by the [`MonitorTask`]
+/// racing the monitors, with the labels of its sandbox.
 fn record_monitor_triggered(triggered_by: &'static str) {
-    metrics::counter!(
-        METRIC_MONITOR_TERMINATIONS,
-        METRIC_MONITOR_TYPE_LABEL => triggered_by
-    )
-    .increment(1);
-
     tracing::warn!("Monitor '{triggered_by}' fired — requesting execution termination");
 }
 
@@ -223,6 +229,72 @@ pub trait ExecutionMonitor: Send + Sync {
     fn name(&self) -> &'static str;
 }
 
+// =============================================================================
+// Soft thresholds
+// =============================================================================
+
+/// Passed to the callbacks of the soft thresholds of the built-in monitors
+/// when a handler crosses them.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ThresholdEvent {
+    /// The name of the monitor, e.g. `wall-clock` or `cpu-time`.
+    pub monitor: &'static str,
+    /// The usage at which the threshold fires.
+    pub threshold: Duration,
+    /// The hard limit of the monitor, at which the handler is terminated.
+    pub limit: Duration,
+}
+
+/// A soft threshold of a built-in monitor: a callback invoked once the usage
+/// of a handler reaches `at`, without terminating it.
+#[derive(Clone)]
+#[cfg_attr(
+    not(any(feature = "monitor-wall-clock", feature = "monitor-cpu-time")),
+    allow(dead_code)
+)]
+pub(crate) struct Threshold {
+    pub(crate) at: Duration,
+    callback: Arc<dyn Fn(&ThresholdEvent) + Send + Sync>,
+}
+
+#[cfg_attr(
+    not(any(feature = "monitor-wall-clock", feature = "monitor-cpu-time")),
+    allow(dead_code)
+)]
+impl Threshold {
+    pub(crate) fn new(
+        at: Duration,
+        callback: impl Fn(&ThresholdEvent) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            at,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Emit the `monitor_thresholds_total` metric and invoke the callback.
+    pub(crate) fn fire(&self, monitor: &'static str, limit: Duration) {
+        record_monitor_threshold(monitor);
+        tracing::warn!(
+            threshold_ms = self.at.as_millis() as u64,
+            limit_ms = limit.as_millis() as u64,
+            "Monitor '{monitor}' soft threshold crossed"
+        );
+        (self.callback)(&ThresholdEvent {
+            monitor,
+            threshold: self.at,
+            limit,
+        });
+    }
+}
+
+impl std::fmt::Debug for Threshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Threshold").field("at", &self.at).finish()
+    }
+}
+
 // =============================================================================
 // MonitorSet — sealed composition trait
 // =============================================================================
@@ -253,22 +325,22 @@ pub trait MonitorSet: private::Sealed + Send + Sync {
     ///
     /// Each sub-monitor's `get_monitor()` is called on the **calling thread**
     /// so monitors can capture thread-local state (e.g., CPU clock handles).
-    /// The returned future completes when the first monitor fires, emitting
-    /// the `monitor_terminations_total` metric and a warning log with the
-    /// winning monitor's name.
-    fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>>;
+    /// The returned future completes with the winning monitor's name when the
+    /// first monitor fires, emitting a warning log with that name.
+    fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = &'static str> + Send>>>;
 }
 
 // Every ExecutionMonitor is automatically a MonitorSet of one.
 impl<M: ExecutionMonitor> private::Sealed for M {}
 
 impl<M: ExecutionMonitor> MonitorSet for M {
-    fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>> {
+    fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = &'static str> + Send>>> {
         let future = self.get_monitor()?;
         let name = self.name();
         Ok(Box::pin(async move {
             future.await;
             record_monitor_triggered(name);
+            name
         }))
     }
 }
@@ -288,7 +360,7 @@ macro_rules! impl_monitor_set_tuple {
         impl<$($P: ExecutionMonitor),+> private::Sealed for ($($P,)+) {}
 
         impl<$($P: ExecutionMonitor),+> MonitorSet for ($($P,)+) {
-            fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>> {
+            fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = &'static str> + Send>>> {
                 let ($($p,)+) = &self;
                 // Each get_monitor() runs here on the calling thread,
                 // preserving thread-local state (e.g. CPU clock handles).
@@ -300,6 +372,7 @@ macro_rules! impl_monitor_set_tuple {
                         $(_ = $p.0 => $p.1,)+
                     };
                     record_monitor_triggered(winner);
+                    winner
                 }))
             }
         }
@@ -316,7 +389,218 @@ impl_monitor_set_tuple!((m0: M0, m1: M1, m2: M2));
 impl_monitor_set_tuple!((m0: M0, m1: M1, m2: M2, m3: M3));
 impl_monitor_set_tuple!((m0: M0, m1: M1, m2: M2, m3: M3, m4: M4));
 
+// =============================================================================
+// BoxedMonitorSet — composition chosen at runtime
+// =============================================================================
+
+/// Object-safe counterpart of [`ExecutionMonitor`], whose `get_monitor()`
+/// returns an opaque type and so cannot be called through a `dyn`.
+trait DynExecutionMonitor: Send + Sync {
+    fn get_monitor(&self) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>>;
+    fn name(&self) -> &'static str;
+}
+
+impl<M: ExecutionMonitor> DynExecutionMonitor for M {
+    fn get_monitor(&self) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>> {
+        Ok(Box::pin(ExecutionMonitor::get_monitor(self)?))
+    }
+
+    fn name(&self) -> &'static str {
+        ExecutionMonitor::name(self)
+    }
+}
+
+/// A set of monitors chosen at runtime, with the same OR semantics as a tuple.
+///
+/// Tuples fix the number and types of the monitors at compile time. Use a
+/// `BoxedMonitorSet` when the monitors are only known at runtime, e.g. when
+/// they are read from configuration.
+///
+/// An empty set never fires, so the handler runs unmonitored.
+///
+/// # Example
+///
+/// ```text
+/// use hyperlight_js::{BoxedMonitorSet, CpuTimeMonitor, WallClockMonitor};
+///
+/// let mut monitor = BoxedMonitorSet::new();
+/// if let Some(wall_ms) = config.wall_clock_timeout_ms {
+///     monitor.push(WallClockMonitor::new(Duration::from_millis(wall_ms))?);
+/// }
+/// if let Some(cpu_ms) = config.cpu_timeout_ms {
+///     monitor.push(CpuTimeMonitor::new(Duration::from_millis(cpu_ms))?);
+/// }
+/// let result = loaded_sandbox.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None)?;
+/// ```
+#[derive(Default)]
+pub struct BoxedMonitorSet {
+    monitors: Vec<Box<dyn DynExecutionMonitor>>,
+}
+
+impl BoxedMonitorSet {
+    /// Create an empty set of monitors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `monitor` to the set.
+    pub fn push(&mut self, monitor: impl ExecutionMonitor + 'static) {
+        self.monitors.push(Box::new(monitor));
+    }
+
+    /// Add `monitor` to the set, returning the set for chaining.
+    pub fn with(mut self, monitor: impl ExecutionMonitor + 'static) -> Self {
+        self.push(monitor);
+        self
+    }
+
+    /// The number of monitors in the set.
+    pub fn len(&self) -> usize {
+        self.monitors.len()
+    }
+
+    /// Returns `true` if the set has no monitors.
+    pub fn is_empty(&self) -> bool {
+        self.monitors.is_empty()
+    }
+}
+
+impl std::fmt::Debug for BoxedMonitorSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.monitors.iter().map(|monitor| monitor.name()))
+            .finish()
+    }
+}
+
+impl private::Sealed for BoxedMonitorSet {}
+
+impl MonitorSet for BoxedMonitorSet {
+    fn to_race(&self) -> Result<Pin<Box<dyn Future<Output = &'static str> + Send>>> {
+        // Each get_monitor() runs here on the calling thread,
+        // preserving thread-local state (e.g. CPU clock handles).
+        let mut futures = self
+            .monitors
+            .iter()
+            .map(|monitor| Ok((monitor.get_monitor()?, monitor.name())))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Box::pin(async move {
+            // Race all monitors — first to complete wins.
+            let winner = poll_fn(|cx| {
+                futures
+                    .iter_mut()
+                    .find_map(|(future, name)| future.as_mut().poll(cx).is_ready().then_some(*name))
+                    .map_or(Poll::Pending, Poll::Ready)
+            })
+            .await;
+            record_monitor_triggered(winner);
+            winner
+        }))
+    }
+}
+
+// =============================================================================
+// MonitorTask — running a MonitorSet alongside a guest call
+// =============================================================================
+
+/// RAII guard that aborts a spawned monitor task on drop.
+///
+/// Wraps a tokio `JoinHandle` to ensure the monitor task is cancelled when
+/// the guard goes out of scope — whether that's after normal completion or
+/// on early return. Keeps the spawn-abort lifecycle in one place rather than
+/// requiring manual `abort()` calls at each exit point.
+pub(crate) struct MonitorTask {
+    task: JoinHandle<()>,
+    // Set to the name of the monitor that fired, before the handler is
+    // cancelled or killed.
+    fired: Arc<OnceLock<&'static str>>,
+}
+
+impl MonitorTask {
+    /// Start racing the monitors of `monitor` on the shared runtime, killing
+    /// the guest through `interrupt_handle` when the first one fires, or
+    /// cancelling the handler through `cancellation` if it is set, and then
+    /// killing the guest if it is still running after the grace period of
+    /// `cancellation`. The `monitor_terminations_total` metric of the monitor
+    /// that fired is labelled with `labels`.
+    ///
+    /// Fails closed: if any monitor fails to initialize, an error is returned
+    /// and the caller must not run the guest.
+    pub(crate) fn start<M: MonitorSet + ?Sized>(
+        monitor: &M,
+        interrupt_handle: Arc<dyn InterruptHandle>,
+        cancellation: Option<CancellationHandle>,
+        labels: SandboxLabels,
+    ) -> Result<Self> {
+        // Phase 1: Build the racing future on the calling thread.
+        // to_race() calls each sub-monitor's get_monitor() here, where
+        // monitors can capture thread-local state (e.g., CPU clock handles).
+        let racing_future = monitor.to_race().map_err(|e| {
+            tracing::error!("Failed to initialize execution monitor: {}", e);
+            HyperlightError::Error(format!("Execution monitor failed to start: {}", e))
+        })?;
+
+        // Phase 2: Spawn the racing future on the shared runtime.
+        // When the first monitor fires, to_race() emits the log, we emit the
+        // metric, then we call kill() to terminate the guest, or ask the guest to stop
+        // on its own if cooperative cancellation is enabled.
+        // kill() is safe to call even if the guest already finished — hyperlight's
+        // InterruptHandle checks RUNNING_BIT and clear_cancel() at the start of
+        // the next guest call clears any stale CANCEL_BIT.
+        let runtime = runtime::get_monitor_runtime().ok_or_else(|| {
+            tracing::error!("Monitor runtime is unavailable");
+            HyperlightError::Error("Monitor runtime is unavailable".to_string())
+        })?;
+
+        let fired = Arc::new(OnceLock::new());
+        let task = runtime.spawn({
+            let fired = fired.clone();
+            async move {
+                let winner = racing_future.await;
+                record_monitor_termination(winner, &labels);
+                let _ = fired.set(winner);
+                match cancellation {
+                    Some(cancellation) => {
+                        cancellation.cancel();
+                        // This task is aborted when the handler returns, so if the
+                        // grace period elapses the handler is still running.
+                        if let Some(grace_period) = cancellation.grace_period() {
+                            sleep(grace_period).await;
+                            tracing::warn!(
+                                "Handler did not stop within {:?} of being cancelled, killing the guest",
+                                grace_period
+                            );
+                            cancellation.kill();
+                        }
+                    }
+                    None => {
+                        interrupt_handle.kill();
+                    }
+                }
+            }
+        });
+        Ok(Self { task, fired })
+    }
+
+    /// The name of the monitor that fired, if any.
+    pub(crate) fn fired(&self) -> Option<&'static str> {
+        self.fired.get().copied()
+    }
+}
+
+impl Drop for MonitorTask {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
 // Feature-gated monitor implementations
+#[cfg(any(feature = "monitor-wall-clock", feature = "monitor-cpu-time"))]
+mod budget;
+#[cfg(any(feature = "monitor-wall-clock", feature = "monitor-cpu-time"))]
+pub use budget::BudgetMonitor;
+
 #[cfg(feature = "monitor-wall-clock")]
 mod wall_clock;
 #[cfg(feature = "monitor-wall-clock")]
@@ -330,6 +614,9 @@ pub use cpu_time::CpuTimeMonitor;
 // Shared runtime for monitor orchestration
 pub(crate) mod runtime;
 
+// Time spent by the guest in host functions
+pub(crate) mod host_calls;
+
 /// Async sleep function used by monitors.
 ///
 /// Re-exported here so that custom monitor implementations don't couple
@@ -337,3 +624,42 @@ pub(crate) mod runtime;
 /// future release, only this re-export needs updating — downstream
 /// monitors remain source-compatible.
 pub use tokio::time::sleep;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SleepMonitor(Duration, &'static str);
+
+    impl ExecutionMonitor for SleepMonitor {
+        fn get_monitor(&self) -> Result<impl Future<Output = ()> + Send + 'static> {
+            Ok(sleep(self.0))
+        }
+
+        fn name(&self) -> &'static str {
+            self.1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_boxed_monitor_set() {
+        let monitor = BoxedMonitorSet::new()
+            .with(SleepMonitor(Duration::from_secs(60), "slow"))
+            .with(SleepMonitor(Duration::from_millis(10), "fast"));
+        assert_eq!(monitor.len(), 2);
+        assert_eq!(format!("{monitor:?}"), r#"["slow", "fast"]"#);
+
+        let race = tokio::time::timeout(Duration::from_secs(5), monitor.to_race().unwrap());
+        assert_eq!(
+            race.await.ok(),
+            Some("fast"),
+            "The fast monitor should fire"
+        );
+
+        // An empty set never fires
+        let monitor = BoxedMonitorSet::new();
+        assert!(monitor.is_empty());
+        let race = tokio::time::timeout(Duration::from_millis(50), monitor.to_race().unwrap());
+        assert!(race.await.is_err(), "An empty set should never fire");
+    }
+}
@@ -22,8 +22,14 @@ limitations under the License.
 //!
 //! # Configuration
 //!
-//! The runtime thread count can be configured via the `HYPERLIGHT_MONITOR_THREADS`
-//! environment variable. This must be set before the first monitor is used.
+//! The runtime is shared by all the sandboxes in the process, and is
+//! configured once, before the first monitor is used, by either:
+//!
+//! 1. [`SandboxBuilder::with_monitor_runtime_threads`](crate::SandboxBuilder::with_monitor_runtime_threads)
+//!    or [`SandboxBuilder::with_monitor_runtime`](crate::SandboxBuilder::with_monitor_runtime),
+//!    to size the runtime or reuse an existing application runtime;
+//! 2. the `HYPERLIGHT_MONITOR_THREADS` environment variable;
+//! 3. a default of 2 worker threads otherwise.
 //!
 //! ```bash
 //! # Set to 4 worker threads (default is 2)
@@ -38,9 +44,11 @@ limitations under the License.
 //! it when the handler completes. Custom monitors simply return a `Future` from
 //! their `get_monitor()` method.
 
-use std::sync::LazyLock;
+use std::fmt;
+use std::sync::OnceLock;
 
-use tokio::runtime::Runtime;
+use hyperlight_host::{new_error, Result};
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
 
 /// Environment variable to configure the number of monitor runtime worker threads.
 pub(crate) const ENV_MONITOR_THREADS: &str = "HYPERLIGHT_MONITOR_THREADS";
@@ -49,43 +57,142 @@ pub(crate) const ENV_MONITOR_THREADS: &str = "HYPERLIGHT_MONITOR_THREADS";
 /// Two threads allows for concurrent wall-clock and CPU time monitoring.
 const DEFAULT_MONITOR_RUNTIME_WORKERS: usize = 2;
 
+/// How the monitor runtime is provided, see [`configure`].
+#[derive(Clone)]
+pub(crate) enum MonitorRuntimeConfig {
+    /// A runtime owned by hyperlight-js with this many worker threads.
+    Threads(usize),
+    /// An existing runtime owned by the application.
+    Handle(Handle),
+}
+
+impl MonitorRuntimeConfig {
+    /// The configuration from the environment, or the default one.
+    fn from_env() -> Self {
+        let workers = std::env::var(ENV_MONITOR_THREADS)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_MONITOR_RUNTIME_WORKERS);
+        Self::Threads(workers)
+    }
+
+    /// Whether a runtime created from `self` satisfies `other`.
+    ///
+    /// Handles cannot be compared, so any application runtime satisfies another.
+    fn satisfies(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Threads(a), Self::Threads(b)) => a == b,
+            (Self::Handle(_), Self::Handle(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for MonitorRuntimeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Threads(workers) => write!(f, "{workers} worker threads"),
+            Self::Handle(_) => f.write_str("an application runtime"),
+        }
+    }
+}
+
+/// The shared runtime and the configuration it was created from.
+struct MonitorRuntime {
+    config: MonitorRuntimeConfig,
+    // Owned runtimes are kept alive for the lifetime of the process.
+    _runtime: Option<Runtime>,
+    handle: Handle,
+}
+
+impl MonitorRuntime {
+    fn new(config: MonitorRuntimeConfig) -> Option<Self> {
+        match config {
+            MonitorRuntimeConfig::Threads(workers) => {
+                match tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(workers)
+                    .thread_name("hl-exec-monitor")
+                    .enable_time()
+                    .build()
+                {
+                    Ok(rt) => {
+                        tracing::debug!(workers, "Initialized monitor runtime");
+                        Some(Self {
+                            config,
+                            handle: rt.handle().clone(),
+                            _runtime: Some(rt),
+                        })
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                        "Failed to create execution monitor runtime: {}. Monitors will be unavailable.",
+                        e
+                    );
+                        None
+                    }
+                }
+            }
+            MonitorRuntimeConfig::Handle(ref handle) => {
+                tracing::debug!("Using the application runtime for monitors");
+                Some(Self {
+                    handle: handle.clone(),
+                    config,
+                    _runtime: None,
+                })
+            }
+        }
+    }
+}
+
 /// Shared Tokio runtime for all execution monitors.
 ///
 /// Lazily initialized on first access. If runtime creation fails (e.g. under
 /// resource exhaustion), the `None` is cached permanently — no retry mechanism,
 /// by design, to avoid retry storms.
-static MONITOR_RUNTIME: LazyLock<Option<Runtime>> = LazyLock::new(|| {
-    let workers = std::env::var(ENV_MONITOR_THREADS)
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .filter(|&n| n > 0)
-        .unwrap_or(DEFAULT_MONITOR_RUNTIME_WORKERS);
-
-    match tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(workers)
-        .thread_name("hl-exec-monitor")
-        .enable_time()
-        .build()
+static MONITOR_RUNTIME: OnceLock<Option<MonitorRuntime>> = OnceLock::new();
+
+/// Configure the shared monitor runtime.
+///
+/// The runtime is created from the first configuration, or from the
+/// environment if a monitor is used before any configuration. Configuring it
+/// again succeeds if the runtime in use satisfies the new configuration.
+///
+/// # Errors
+///
+/// Returns an error if `config` is an application runtime that is not
+/// multi-threaded, or if the runtime in use was created from a different
+/// configuration.
+pub(crate) fn configure(config: MonitorRuntimeConfig) -> Result<()> {
+    if let MonitorRuntimeConfig::Handle(handle) = &config
+        && handle.runtime_flavor() != RuntimeFlavor::MultiThread
     {
-        Ok(rt) => {
-            tracing::debug!(workers, "Initialized monitor runtime");
-            Some(rt)
-        }
-        Err(e) => {
-            tracing::error!(
-                "Failed to create execution monitor runtime: {}. Monitors will be unavailable.",
-                e
-            );
-            None
-        }
+        return Err(new_error!(
+            "The monitor runtime must be a multi-threaded Tokio runtime, got {:?}",
+            handle.runtime_flavor()
+        ));
+    }
+    if let MonitorRuntimeConfig::Threads(0) = config {
+        return Err(new_error!(
+            "The monitor runtime needs at least one worker thread"
+        ));
+    }
+
+    let runtime = MONITOR_RUNTIME.get_or_init(|| MonitorRuntime::new(config.clone()));
+    match runtime {
+        Some(runtime) if runtime.config.satisfies(&config) => Ok(()),
+        Some(runtime) => Err(new_error!(
+            "The monitor runtime is shared by all sandboxes and was already initialized with {}, which conflicts with {}",
+            runtime.config,
+            config
+        )),
+        None => Err(new_error!("Monitor runtime is unavailable")),
     }
-});
+}
 
-/// Get the shared monitor runtime.
+/// Get a handle to the shared monitor runtime.
 ///
-/// The runtime is lazily initialized on first access. Thread count is determined by:
-/// 1. The `HYPERLIGHT_MONITOR_THREADS` environment variable (if set and valid)
-/// 2. Default of 2 threads otherwise
+/// The runtime is lazily initialized on first access, see [`configure`].
 ///
 /// Returns `None` if runtime creation fails.
 ///
@@ -98,6 +205,39 @@ static MONITOR_RUNTIME: LazyLock<Option<Runtime>> = LazyLock::new(|| {
 ///     });
 /// }
 /// ```
-pub(crate) fn get_monitor_runtime() -> Option<&'static Runtime> {
-    MONITOR_RUNTIME.as_ref()
+pub(crate) fn get_monitor_runtime() -> Option<&'static Handle> {
+    MONITOR_RUNTIME
+        .get_or_init(|| MonitorRuntime::new(MonitorRuntimeConfig::from_env()))
+        .as_ref()
+        .map(|runtime| &runtime.handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configure_rejects_invalid_runtimes() {
+        assert!(configure(MonitorRuntimeConfig::Threads(0)).is_err());
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let err = configure(MonitorRuntimeConfig::Handle(runtime.handle().clone())).unwrap_err();
+        assert!(err.to_string().contains("multi-threaded"), "{err}");
+    }
+
+    #[test]
+    fn test_config_satisfies() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+        let handle = MonitorRuntimeConfig::Handle(runtime.handle().clone());
+
+        assert!(MonitorRuntimeConfig::Threads(2).satisfies(&MonitorRuntimeConfig::Threads(2)));
+        assert!(!MonitorRuntimeConfig::Threads(2).satisfies(&MonitorRuntimeConfig::Threads(4)));
+        assert!(!MonitorRuntimeConfig::Threads(2).satisfies(&handle));
+        assert!(handle.satisfies(&handle.clone()));
+    }
 }
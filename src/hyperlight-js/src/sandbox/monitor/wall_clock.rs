@@ -16,11 +16,12 @@ limitations under the License.
 //! Wall-clock time based execution monitor.
 
 use std::future::Future;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use hyperlight_host::{HyperlightError, Result};
 
-use super::ExecutionMonitor;
+use super::host_calls::HostCallClock;
+use super::{ExecutionMonitor, Threshold, ThresholdEvent};
 
 /// Monitors handler execution using wall-clock time.
 ///
@@ -52,9 +53,34 @@ use super::ExecutionMonitor;
 /// let monitor = WallClockMonitor::new(Duration::from_secs(5))?;
 /// let result = sandbox.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None)?;
 /// ```
+///
+/// # Excluding Host Calls
+///
+/// By default the time the guest spends waiting on host functions (e.g. a
+/// database query) counts towards the timeout. Use
+/// [`excluding_host_calls`](Self::excluding_host_calls) to only count the
+/// time spent outside of host functions:
+///
+/// ```text
+/// let monitor = WallClockMonitor::new(Duration::from_secs(5))?.excluding_host_calls();
+/// ```
+///
+/// # Soft Thresholds
+///
+/// Use [`on_threshold`](Self::on_threshold) to be notified when a handler
+/// gets close to the timeout, without terminating it:
+///
+/// ```text
+/// let monitor = WallClockMonitor::new(Duration::from_secs(5))?
+///     .on_threshold(Duration::from_secs(4), |event| {
+///         tracing::warn!("handler used {:?} of its {:?} budget", event.threshold, event.limit);
+///     });
+/// ```
 #[derive(Debug, Clone)]
 pub struct WallClockMonitor {
     timeout: Duration,
+    exclude_host_calls: bool,
+    thresholds: Vec<Threshold>,
 }
 
 impl WallClockMonitor {
@@ -69,15 +95,65 @@ impl WallClockMonitor {
                 "timeout must be non-zero".to_string(),
             ));
         }
-        Ok(Self { timeout })
+        Ok(Self {
+            timeout,
+            exclude_host_calls: false,
+            thresholds: Vec::new(),
+        })
+    }
+
+    /// Pause the timeout while the guest is waiting on a host function.
+    ///
+    /// A handler blocked in a host function that never returns is then never
+    /// terminated by this monitor, so combine it with a monitor that does
+    /// count host calls, e.g. a second `WallClockMonitor` with a longer
+    /// timeout.
+    pub fn excluding_host_calls(mut self) -> Self {
+        self.exclude_host_calls = true;
+        self
+    }
+
+    /// Invoke `callback` when a handler has run for `threshold`, without
+    /// terminating it.
+    ///
+    /// Crossing the threshold also emits the `monitor_thresholds_total`
+    /// metric. The callback runs on the shared monitor runtime, so it must
+    /// not block. Thresholds at or above the timeout never fire.
+    pub fn on_threshold(
+        mut self,
+        threshold: Duration,
+        callback: impl Fn(&ThresholdEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.thresholds.push(Threshold::new(threshold, callback));
+        self.thresholds.sort_by_key(|threshold| threshold.at);
+        self
     }
 }
 
 impl ExecutionMonitor for WallClockMonitor {
     fn get_monitor(&self) -> Result<impl Future<Output = ()> + Send + 'static> {
         let timeout = self.timeout;
+        let name = self.name();
+        // Capture the host call clock on the calling thread, which runs the host functions
+        let host_calls = self.exclude_host_calls.then(HostCallClock::current);
+        let mut thresholds = self.thresholds.clone().into_iter().peekable();
         Ok(async move {
-            super::sleep(timeout).await;
+            let start = Instant::now();
+            let host_start = host_calls.as_ref().map(|clock| clock.elapsed());
+            loop {
+                let mut elapsed = start.elapsed();
+                if let (Some(clock), Some(host_start)) = (&host_calls, host_start) {
+                    elapsed = elapsed.saturating_sub(clock.elapsed().saturating_sub(host_start));
+                }
+                if elapsed >= timeout {
+                    break;
+                }
+                while let Some(threshold) = thresholds.next_if(|t| t.at <= elapsed) {
+                    threshold.fire(name, timeout);
+                }
+                let next = thresholds.peek().map_or(timeout, |t| t.at.min(timeout));
+                super::sleep(next - elapsed).await;
+            }
             tracing::warn!(
                 timeout_ms = timeout.as_millis() as u64,
                 "Wall-clock timeout exceeded, terminating execution"
@@ -118,6 +194,55 @@ mod tests {
         assert!(future.is_ok(), "get_monitor() should return Ok");
     }
 
+    #[tokio::test]
+    async fn test_excluding_host_calls() {
+        let timeout = Duration::from_millis(50);
+        let monitor = WallClockMonitor::new(timeout)
+            .unwrap()
+            .excluding_host_calls();
+
+        let start = Instant::now();
+        monitor.get_monitor().unwrap().await;
+        assert!(start.elapsed() >= timeout);
+
+        // The timeout is paused while in a host call
+        let start = Instant::now();
+        let future = monitor.get_monitor().unwrap();
+        let call = HostCallClock::enter();
+        let fired = tokio::time::timeout(Duration::from_millis(200), future).await;
+        assert!(fired.is_err(), "Should not fire during a host call");
+        drop(call);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_on_threshold() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let crossed = Arc::new(AtomicUsize::new(0));
+        let counter = crossed.clone();
+        let timeout = Duration::from_millis(100);
+        let monitor = WallClockMonitor::new(timeout).unwrap().on_threshold(
+            Duration::from_millis(20),
+            move |event| {
+                assert_eq!(event.monitor, "wall-clock");
+                assert_eq!(event.limit, Duration::from_millis(100));
+                counter.fetch_add(1, Ordering::Relaxed);
+            },
+        );
+
+        let future = monitor.get_monitor().unwrap();
+        let fired = tokio::time::timeout(Duration::from_millis(60), future).await;
+        assert!(fired.is_err(), "Should not fire at the threshold");
+        assert_eq!(crossed.load(Ordering::Relaxed), 1);
+
+        let start = Instant::now();
+        monitor.get_monitor().unwrap().await;
+        assert!(start.elapsed() >= timeout);
+        assert_eq!(crossed.load(Ordering::Relaxed), 2);
+    }
+
     #[test]
     fn test_get_monitor_reuse() {
         // The same monitor instance should produce separate futures
@@ -0,0 +1,87 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::time::Duration;
+
+use hyperlight_host::HyperlightError;
+
+use super::poison_reason::PoisonReason;
+
+/// Callbacks on the lifecycle events of a sandbox, registered with
+/// [`SandboxBuilder::with_observer`](crate::SandboxBuilder::with_observer).
+///
+/// All the callbacks do nothing by default, so an observer only implements
+/// the ones it needs. They are called synchronously on the thread driving
+/// the sandbox, so they should return quickly.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use hyperlight_js::{HyperlightError, SandboxBuilder, SandboxObserver};
+///
+/// struct LogObserver;
+///
+/// impl SandboxObserver for LogObserver {
+///     fn on_handler_end(
+///         &self,
+///         handler: &str,
+///         _sequence: u64,
+///         duration: Duration,
+///         error: Option<&HyperlightError>,
+///     ) {
+///         println!("{handler} ran in {duration:?}, failed: {}", error.is_some());
+///     }
+/// }
+///
+/// let proto_js_sandbox = SandboxBuilder::new()
+///     .with_observer(LogObserver)
+///     .build()
+///     .unwrap();
+/// ```
+pub trait SandboxObserver: Send + Sync {
+    /// Called when handlers have been loaded into the sandbox.
+    fn on_loaded(&self) {}
+
+    /// Called when the handlers have been unloaded from the sandbox.
+    fn on_unloaded(&self) {}
+
+    /// Called before the handler `handler` is invoked, with the sequence
+    /// number of the invocation.
+    fn on_handler_start(&self, _handler: &str, _sequence: u64) {}
+
+    /// Called after the invocation of `handler` with the sequence number
+    /// `sequence` returns, with the error it failed with, if any.
+    fn on_handler_end(
+        &self,
+        _handler: &str,
+        _sequence: u64,
+        _duration: Duration,
+        _error: Option<&HyperlightError>,
+    ) {
+    }
+
+    /// Called when the invocation of `handler` poisons the sandbox.
+    fn on_poisoned(&self, _handler: &str, _reason: &PoisonReason) {}
+
+    /// Called when the monitor named `monitor` fired while `handler` was
+    /// running.
+    fn on_monitor_fired(&self, _handler: &str, _monitor: &'static str) {}
+
+    /// Called when the state of the sandbox has been restored from a
+    /// snapshot, including by auto-recovery.
+    fn on_restored(&self) {}
+}
@@ -0,0 +1,124 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+use hyperlight_host::HyperlightError;
+
+// This has to match `OUT_OF_MEMORY_ERROR` in src/hyperlight-js-runtime/src/lib.rs
+const OUT_OF_MEMORY_ERROR: &str = "GuestOutOfMemory: ";
+// The panic message of Rust allocations that fail in the guest.
+const ALLOCATION_FAILED: &str = "memory allocation of ";
+
+/// The error of a guest that ran out of memory, to tell it from other
+/// failures and, for example, retry in a sandbox with more memory.
+///
+/// A guest runs out of memory in one of two ways:
+/// * The JavaScript heap reaches the limit set with
+///   [`SandboxBuilder::with_js_memory_limit`](crate::SandboxBuilder::with_js_memory_limit),
+///   and the handler doesn't catch the `InternalError` QuickJS throws. The
+///   call fails with a guest error, and the sandbox is not poisoned.
+/// * The heap of the guest, set with
+///   [`SandboxBuilder::with_guest_heap_size`](crate::SandboxBuilder::with_guest_heap_size),
+///   is exhausted. The guest aborts, and the sandbox is poisoned.
+///
+/// # Example
+///
+/// ```text
+/// match loaded_sandbox.handle_event("handler", event, None) {
+///     Ok(result) => respond(200, result),
+///     Err(err) => match GuestOutOfMemory::from_error(&err) {
+///         Some(_) => route_to_larger_sandbox(event),
+///         None => respond(500, err.to_string()),
+///     },
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuestOutOfMemory {
+    /// Whether the heap of the guest was exhausted and the guest aborted,
+    /// rather than the JavaScript heap reaching its limit.
+    pub aborted: bool,
+}
+
+impl GuestOutOfMemory {
+    /// The `GuestOutOfMemory` error `err` is, if the guest ran out of memory.
+    pub fn from_error(err: &HyperlightError) -> Option<Self> {
+        match err {
+            HyperlightError::GuestError(_, message) if message.contains(OUT_OF_MEMORY_ERROR) => {
+                Some(Self { aborted: false })
+            }
+            HyperlightError::GuestAborted(code, message)
+                if *code == ErrorCode::MallocFailed as u8
+                    || *code == ErrorCode::FailureInDlmalloc as u8
+                    || (message.contains(ALLOCATION_FAILED) && message.contains(" failed")) =>
+            {
+                Some(Self { aborted: true })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GuestOutOfMemory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.aborted {
+            f.write_str("the guest heap is out of memory, the guest aborted")
+        } else {
+            f.write_str("the JavaScript heap is out of memory")
+        }
+    }
+}
+
+impl std::error::Error for GuestOutOfMemory {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let err = HyperlightError::GuestError(
+            ErrorCode::GuestError,
+            "Error: GuestOutOfMemory: the JavaScript heap is out of memory".to_string(),
+        );
+        assert_eq!(
+            GuestOutOfMemory::from_error(&err),
+            Some(GuestOutOfMemory { aborted: false })
+        );
+
+        let err = HyperlightError::GuestAborted(ErrorCode::MallocFailed as u8, String::new());
+        assert_eq!(
+            GuestOutOfMemory::from_error(&err),
+            Some(GuestOutOfMemory { aborted: true })
+        );
+        let err = HyperlightError::GuestAborted(
+            ErrorCode::UnknownError as u8,
+            "memory allocation of 1048576 bytes failed".to_string(),
+        );
+        assert_eq!(
+            GuestOutOfMemory::from_error(&err),
+            Some(GuestOutOfMemory { aborted: true })
+        );
+
+        let err = HyperlightError::GuestAborted(ErrorCode::UnknownError as u8, "boom".to_string());
+        assert_eq!(GuestOutOfMemory::from_error(&err), None);
+        let err = HyperlightError::GuestError(
+            ErrorCode::GuestError,
+            "Error: Runtime error: out of memory".to_string(),
+        );
+        assert_eq!(GuestOutOfMemory::from_error(&err), None);
+    }
+}
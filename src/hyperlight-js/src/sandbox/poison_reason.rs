@@ -0,0 +1,123 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_host::HyperlightError;
+
+/// Why a sandbox is poisoned, see [`LoadedJSSandbox::poisoned_reason`](crate::LoadedJSSandbox::poisoned_reason).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoisonReason {
+    /// The guest was killed through its [`InterruptHandle`](crate::InterruptHandle).
+    Killed,
+    /// The guest was killed by an execution monitor.
+    MonitorTimeout,
+    /// The guest aborted, e.g. because it panicked or ran out of memory.
+    GuestAborted {
+        /// The abort code.
+        code: u8,
+        /// The abort message.
+        message: String,
+    },
+    /// The guest accessed memory it is not allowed to.
+    MemoryViolation(String),
+    /// Another error left the guest in an inconsistent state.
+    Other(String),
+}
+
+impl PoisonReason {
+    /// The reason a guest call failed with `error` and poisoned the sandbox,
+    /// given whether an execution monitor fired during the call.
+    pub(crate) fn from_error(error: &HyperlightError, monitor_fired: bool) -> Self {
+        match error {
+            HyperlightError::ExecutionCanceledByHost() if monitor_fired => Self::MonitorTimeout,
+            HyperlightError::ExecutionCanceledByHost() => Self::Killed,
+            HyperlightError::GuestAborted(code, message) => Self::GuestAborted {
+                code: *code,
+                message: message.clone(),
+            },
+            HyperlightError::MemoryAccessViolation(..)
+            | HyperlightError::ExecutionAccessViolation(_) => {
+                Self::MemoryViolation(error.to_string())
+            }
+            error => Self::Other(error.to_string()),
+        }
+    }
+
+    /// A short name for the kind of reason, e.g. `monitorTimeout`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Killed => "killed",
+            Self::MonitorTimeout => "monitorTimeout",
+            Self::GuestAborted { .. } => "guestAborted",
+            Self::MemoryViolation(_) => "memoryViolation",
+            Self::Other(_) => "other",
+        }
+    }
+}
+
+impl fmt::Display for PoisonReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Killed => f.write_str("the guest was killed"),
+            Self::MonitorTimeout => f.write_str("the guest was killed by an execution monitor"),
+            Self::GuestAborted { code, message } => {
+                write!(f, "the guest aborted with code {code}: {message}")
+            }
+            Self::MemoryViolation(error) => write!(f, "memory violation: {error}"),
+            Self::Other(error) => f.write_str(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let killed = HyperlightError::ExecutionCanceledByHost();
+        assert_eq!(
+            PoisonReason::from_error(&killed, false),
+            PoisonReason::Killed
+        );
+        assert_eq!(
+            PoisonReason::from_error(&killed, true),
+            PoisonReason::MonitorTimeout
+        );
+
+        let aborted = HyperlightError::GuestAborted(3, "out of memory".to_string());
+        let reason = PoisonReason::from_error(&aborted, true);
+        assert_eq!(
+            reason,
+            PoisonReason::GuestAborted {
+                code: 3,
+                message: "out of memory".to_string()
+            }
+        );
+        assert_eq!(reason.kind(), "guestAborted");
+        assert_eq!(
+            reason.to_string(),
+            "the guest aborted with code 3: out of memory"
+        );
+
+        let other = HyperlightError::Error("boom".to_string());
+        assert_eq!(
+            PoisonReason::from_error(&other, false),
+            PoisonReason::Other(other.to_string())
+        );
+    }
+}
@@ -15,26 +15,103 @@ limitations under the License.
 */
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::Context;
-use hyperlight_host::sandbox::SandboxConfiguration;
-use hyperlight_host::{new_error, GuestBinary, Result, UninitializedSandbox};
+use hyperlight_host::{new_error, Result};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use tracing::{instrument, Level};
 
+use super::backend::UninitializedBackend;
+use super::call_context::CallContext;
+use super::cancellation::{CancellationHandle, CancellationState};
 use super::js_sandbox::JSSandbox;
+use super::runtime_options::RuntimeOptions;
 use super::sandbox_builder::SandboxBuilder;
-use crate::sandbox::host_fn::{Function, HostModule};
-use crate::sandbox::metrics::SandboxMetricsGuard;
+use super::sequence::InvocationSequence;
+use super::settings::SandboxSettings;
+use super::trace_context::current_traceparent;
+use crate::module_loader::{ModuleBundler, ModuleLoader, ModuleLoaderOptions};
+use crate::sandbox::host_fn::{
+    intercept, Function, HostCall, HostCallInterceptor, HostCallNext, HostModule,
+    HostModuleDefinition,
+};
+#[cfg(feature = "function_call_metrics")]
+use crate::sandbox::metrics::record_host_function_call;
+use crate::sandbox::metrics::{
+    record_host_call, record_host_call_limit_exceeded, SandboxMetricsGuard,
+};
+use crate::sandbox::monitor::host_calls::{self, HostCallClock};
 use crate::HostPrintFn;
 
+/// Call the host function `func_name` of the host module `module_name` with the JSON
+/// serialized `args` through `interceptors`, failing if the guest call already made
+/// `max_calls` host calls.
+fn call_host_function(
+    host_modules: &HashMap<String, HostModule>,
+    interceptors: &[HostCallInterceptor],
+    max_calls: Option<u64>,
+    module_name: &str,
+    func_name: &str,
+    args: String,
+) -> Result<String> {
+    let calls = host_calls::count_call();
+    record_host_call();
+    if let Some(max_calls) = max_calls
+        && calls > max_calls
+    {
+        record_host_call_limit_exceeded();
+        return Err(new_error!(
+            "The handler exceeded the limit of {} host function calls, calling '{}.{}'",
+            max_calls,
+            module_name,
+            func_name
+        ));
+    }
+
+    // Let monitors exclude the time spent in host functions
+    let _host_call = HostCallClock::enter();
+    let module = host_modules
+        .get(module_name)
+        .ok_or_else(|| new_error!("Host module '{}' not found", module_name))?;
+    let func = module.get(func_name).ok_or_else(|| {
+        new_error!(
+            "Host function '{}' not found in module '{}'",
+            func_name,
+            module_name
+        )
+    })?;
+    let context = CallContext::current();
+    let traceparent = current_traceparent();
+    let call = HostCall {
+        module: module_name,
+        function: func_name,
+        context: context.as_ref(),
+        traceparent: traceparent.as_deref(),
+    };
+    // Only calls to registered functions are recorded, so the guest cannot
+    // create metrics for arbitrary names.
+    #[cfg(feature = "function_call_metrics")]
+    let start = std::time::Instant::now();
+    let result = intercept(interceptors, &call, args, func);
+    #[cfg(feature = "function_call_metrics")]
+    record_host_function_call(module_name, func_name, start.elapsed(), result.is_err());
+    result
+}
+
 /// A Hyperlight Sandbox with no JavaScript run time loaded and no guest code.
 /// This is used to register new host functions prior to loading the JavaScript run time.
 pub struct ProtoJSSandbox {
-    inner: UninitializedSandbox,
+    inner: UninitializedBackend,
     host_modules: HashMap<String, HostModule>,
+    host_call_interceptors: Vec<HostCallInterceptor>,
+    runtime_options: RuntimeOptions,
+    allow_rollback: bool,
+    settings: SandboxSettings,
+    bundler: Option<Arc<dyn ModuleBundler>>,
+    cancellation: Option<Arc<CancellationState>>,
     // metric drop guard to manage sandbox metric
     _metric_guard: SandboxMetricsGuard<ProtoJSSandbox>,
 }
@@ -42,15 +119,29 @@ pub struct ProtoJSSandbox {
 impl ProtoJSSandbox {
     #[instrument(err(Debug), skip_all, level=Level::INFO, fields(version= env!("CARGO_PKG_VERSION")))]
     pub(super) fn new(
-        guest_binary: GuestBinary,
-        cfg: Option<SandboxConfiguration>,
+        mut usbox: UninitializedBackend,
         host_print_writer: Option<HostPrintFn>,
+        runtime_options: RuntimeOptions,
+        allow_rollback: bool,
+        settings: SandboxSettings,
+        cancellation_grace_period: Option<Duration>,
     ) -> Result<Self> {
-        let mut usbox: UninitializedSandbox = UninitializedSandbox::new(guest_binary, cfg)?;
+        let allowlist = settings.host_function_allowlist.clone();
 
         // Set the host print function
         if let Some(host_print_writer) = host_print_writer {
-            usbox.register_print(host_print_writer)?;
+            let allowlist = allowlist.clone();
+            usbox.register_print(move |message: String| -> Result<i32> {
+                allowlist.check("HostPrint")?;
+                host_print_writer.call((message,))
+            })?;
+        } else if !allowlist.allows("HostPrint") {
+            // Replace the default print function, which writes to stdout
+            let allowlist = allowlist.clone();
+            usbox.register_print(move |_message: String| -> Result<i32> {
+                allowlist.check("HostPrint")?;
+                Ok(0)
+            })?;
         }
 
         // host function used by rquickjs for Date.now()
@@ -61,12 +152,54 @@ impl ProtoJSSandbox {
                 .map(|d| d.as_micros() as u64)?)
         }
 
-        usbox.register("CurrentTimeMicros", current_time_micros)?;
+        let time_allowlist = allowlist.clone();
+        let virtual_clock = settings
+            .deterministic
+            .as_ref()
+            .map(|deterministic| deterministic.clock.clone());
+        let monotonic_allowlist = allowlist.clone();
+        let monotonic_clock = virtual_clock.clone();
+        usbox.register("CurrentTimeMicros", move || {
+            time_allowlist.check("CurrentTimeMicros")?;
+            match &virtual_clock {
+                Some(clock) => Ok(clock.now_micros()),
+                None => current_time_micros(),
+            }
+        })?;
+
+        // host function used by rquickjs for performance.now(), which must
+        // not go back when the wall clock is adjusted
+        let origin = Instant::now();
+        usbox.register("MonotonicTimeMicros", move || -> Result<u64> {
+            monotonic_allowlist.check("MonotonicTimeMicros")?;
+            match &monotonic_clock {
+                Some(clock) => Ok(clock.now_micros()),
+                None => Ok(u64::try_from(origin.elapsed().as_micros()).unwrap_or(u64::MAX)),
+            }
+        })?;
+
+        // host function polled by the guest to check for cooperative cancellation
+        let cancellation = runtime_options
+            .cooperative_cancellation
+            .then(|| Arc::new(CancellationState::new(cancellation_grace_period)));
+        if let Some(cancellation) = cancellation.clone() {
+            usbox.register("IsExecutionCancelled", move || -> Result<bool> {
+                allowlist.check("IsExecutionCancelled")?;
+                Ok(cancellation.is_cancelled())
+            })?;
+        }
 
+        let metric_guard = SandboxMetricsGuard::new(&settings.labels);
         Ok(Self {
             inner: usbox,
             host_modules: HashMap::new(),
-            _metric_guard: SandboxMetricsGuard::new(),
+            host_call_interceptors: Vec::new(),
+            runtime_options,
+            allow_rollback,
+            settings,
+            bundler: None,
+            cancellation,
+            _metric_guard: metric_guard,
         })
     }
 
@@ -75,54 +208,59 @@ impl ProtoJSSandbox {
     /// Enables JavaScript module imports using the provided ~FileSystem~ implementation.
     #[instrument(err(Debug), skip_all, level=Level::INFO)]
     pub fn set_module_loader<Fs: crate::resolver::FileSystem + Clone + 'static>(
-        mut self,
+        self,
         file_system: Fs,
     ) -> Result<Self> {
-        use std::path::PathBuf;
-
-        use oxc_resolver::{ResolveOptions, ResolverGeneric};
+        self.set_module_loader_with_options(file_system, ModuleLoaderOptions::default())
+    }
 
-        let resolver = ResolverGeneric::new_with_file_system(
-            file_system.clone(),
-            ResolveOptions {
-                extensions: vec![".js".into(), ".mjs".into()],
-                condition_names: vec!["import".into(), "module".into()],
-                ..Default::default()
-            },
-        );
+    /// Install a custom file system for module resolution and loading, with
+    /// additional options such as an [`ImportPolicy`](crate::ImportPolicy).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyperlight_js::{FileSystemMemory, ImportRules, ModuleLoaderOptions, SandboxBuilder};
+    ///
+    /// let fs = FileSystemMemory::new();
+    /// fs.insert("lib/math.js", "export const add = (a, b) => a + b;");
+    ///
+    /// let options = ModuleLoaderOptions::new()
+    ///     .with_import_policy(ImportRules::new().allow("./lib/*").max_modules(10));
+    ///
+    /// let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    /// let sandbox = proto_js_sandbox
+    ///     .set_module_loader_with_options(fs, options)
+    ///     .unwrap()
+    ///     .load_runtime()
+    ///     .unwrap();
+    /// ```
+    #[instrument(err(Debug), skip_all, level=Level::INFO)]
+    pub fn set_module_loader_with_options<Fs: crate::resolver::FileSystem + Clone + 'static>(
+        mut self,
+        file_system: Fs,
+        options: ModuleLoaderOptions,
+    ) -> Result<Self> {
+        let prebundle = options.prebundle();
+        let loader = Arc::new(ModuleLoader::new(file_system, options));
+        self.bundler = prebundle.then(|| loader.clone() as Arc<dyn ModuleBundler>);
 
+        let resolver = loader.clone();
+        let allowlist = self.settings.host_function_allowlist.clone();
         self.inner.register(
             "ResolveModule",
-            move |base: String, specifier: String| -> hyperlight_host::Result<String> {
-                tracing::debug!(
-                    base = %base,
-                    specifier = %specifier,
-                    "Resolving module"
-                );
-
-                let resolved = resolver.resolve(&base, &specifier).map_err(|e| {
-                    new_error!(
-                        "Failed to resolve module '{}' from '{}': {:?}",
-                        specifier,
-                        base,
-                        e
-                    )
-                })?;
-
-                Ok(resolved.path().to_string_lossy().to_string())
+            move |importer: String, specifier: String| -> hyperlight_host::Result<String> {
+                allowlist.check("ResolveModule")?;
+                resolver.resolve(&importer, &specifier)
             },
         )?;
 
+        let allowlist = self.settings.host_function_allowlist.clone();
         self.inner.register(
             "LoadModule",
             move |path: String| -> hyperlight_host::Result<String> {
-                tracing::debug!(path = %path, "Loading module");
-                let path_buf = PathBuf::from(&path);
-                let source = file_system
-                    .read_to_string(&path_buf)
-                    .map_err(|e| new_error!("Failed to read module '{}': {}", path, e))?;
-
-                Ok(source)
+                allowlist.check("LoadModule")?;
+                loader.load(&path)
             },
         )?;
 
@@ -135,29 +273,110 @@ impl ProtoJSSandbox {
         let host_modules = self.host_modules;
 
         let host_modules_json = serde_json::to_string(&host_modules)?;
+        let runtime_options_json = serde_json::to_string(&self.runtime_options)?;
 
+        let host_modules = Arc::new(host_modules);
+        let interceptors: Arc<[HostCallInterceptor]> = self.host_call_interceptors.into();
+        let max_calls = self.settings.handler_limits.max_host_calls;
+
+        let modules = host_modules.clone();
+        let call_interceptors = interceptors.clone();
+        let allowlist = self.settings.host_function_allowlist.clone();
         self.inner.register(
             "CallHostJsFunction",
             move |module_name: String, func_name: String, args: String| -> Result<String> {
-                let module = host_modules
-                    .get(&module_name)
-                    .ok_or_else(|| new_error!("Host module '{}' not found", module_name))?;
-                let func = module.get(&func_name).ok_or_else(|| {
-                    new_error!(
-                        "Host function '{}' not found in module '{}'",
-                        func_name,
-                        module_name
-                    )
-                })?;
-                func(args)
+                allowlist.check("CallHostJsFunction")?;
+                call_host_function(
+                    &modules,
+                    &call_interceptors,
+                    max_calls,
+                    &module_name,
+                    &func_name,
+                    args,
+                )
+            },
+        )?;
+
+        // The serialization in here has to match the serialization of
+        // the batched calls in src/hyperlight-js-runtime/src/host_fn.rs
+        let allowlist = self.settings.host_function_allowlist.clone();
+        self.inner.register(
+            "CallHostJsFunctionBatch",
+            move |calls: String| -> Result<String> {
+                allowlist.check("CallHostJsFunctionBatch")?;
+                let calls: Vec<(String, String, String)> = serde_json::from_str(&calls)
+                    .map_err(|e| new_error!("Failed to parse batched host calls: {}", e))?;
+                let results = calls
+                    .into_iter()
+                    .map(|(module_name, func_name, args)| {
+                        call_host_function(
+                            &host_modules,
+                            &interceptors,
+                            max_calls,
+                            &module_name,
+                            &func_name,
+                            args,
+                        )
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                Ok(serde_json::to_string(&results)?)
             },
         )?;
 
-        let mut multi_use_sandbox = self.inner.evolve()?;
+        let mut backend = self.inner.evolve()?;
+        let cancellation = self
+            .cancellation
+            .map(|state| CancellationHandle::new(state, backend.interrupt_handle()));
 
-        let _: () = multi_use_sandbox.call("RegisterHostModules", host_modules_json)?;
+        let _: () = backend.call("RegisterHostModules", host_modules_json)?;
+        let _: () = backend.call("ConfigureRuntime", runtime_options_json)?;
 
-        JSSandbox::new(multi_use_sandbox)
+        JSSandbox::new(
+            backend,
+            InvocationSequence::new(self.allow_rollback),
+            self.settings,
+            self.bundler,
+            cancellation,
+        )
+    }
+
+    /// Wrap every call from the guest to a registered host function with
+    /// `interceptor`, for cross-cutting concerns like authorization, argument
+    /// redaction, latency recording or rate limiting.
+    ///
+    /// The interceptor is called with the [`HostCall`] being made, the JSON
+    /// serialized arguments and the rest of the dispatch. It can change the
+    /// arguments before passing them on, change the result, or fail the call
+    /// without calling the function at all.
+    ///
+    /// Interceptors are called in the order they are added, the first one
+    /// being the outermost. They apply to batched host calls too, but not to
+    /// the built-in host functions of the runtime.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::{new_error, SandboxBuilder};
+    ///
+    /// let sbox = SandboxBuilder::new()
+    ///     .build()?
+    ///     .with_host_call_interceptor(|call, args, next| {
+    ///         if call.module == "admin" {
+    ///             return Err(new_error!("'{}' is not allowed", call.function));
+    ///         }
+    ///         next(args)
+    ///     });
+    /// # Ok::<(), hyperlight_host::HyperlightError>(())
+    /// ```
+    pub fn with_host_call_interceptor(
+        mut self,
+        interceptor: impl Fn(&HostCall<'_>, String, HostCallNext<'_>) -> Result<String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.host_call_interceptors.push(Arc::new(interceptor));
+        self
     }
 
     /// Register a host module that can be called from the guest JavaScript code.
@@ -213,6 +432,18 @@ impl ProtoJSSandbox {
         Ok(())
     }
 
+    /// Register the methods of `module` as the host functions of the host
+    /// module [`M::NAME`](HostModuleDefinition::NAME), see
+    /// [`host_module`](crate::host_module).
+    ///
+    /// Functions already registered in the host module are kept, unless a
+    /// method has the same name.
+    #[instrument(err(Debug), skip_all, fields(module = M::NAME), level=Level::INFO)]
+    pub fn register_module<M: HostModuleDefinition>(&mut self, module: M) -> Result<()> {
+        Arc::new(module).register_functions(self.host_module(M::NAME));
+        Ok(())
+    }
+
     /// Register a raw host function that operates on JSON strings directly.
     ///
     /// This is equivalent to calling `sbox.host_module(module).register_raw(name, func)`.
@@ -233,6 +464,13 @@ impl ProtoJSSandbox {
         self.host_module(module).register_raw(name, func);
         Ok(())
     }
+
+    /// Returns whether the JavaScript runtime runs in a Hyperlight micro VM,
+    /// rather than in-process without any isolation, see the `in-process`
+    /// feature.
+    pub fn is_isolated(&self) -> bool {
+        self.inner.is_isolated()
+    }
 }
 
 impl std::fmt::Debug for ProtoJSSandbox {
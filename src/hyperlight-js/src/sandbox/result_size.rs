@@ -0,0 +1,103 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+
+use hyperlight_host::HyperlightError;
+
+// This has to match `RESULT_TOO_LARGE_ERROR` in src/hyperlight-js-runtime/src/lib.rs,
+// and the message below the error the runtime fails with.
+const RESULT_TOO_LARGE_ERROR: &str = "ResultTooLarge: ";
+
+/// The error of a handler whose result is larger than the limit set with
+/// [`SandboxBuilder::with_max_result_size`](crate::SandboxBuilder::with_max_result_size).
+///
+/// The guest checks the size of the result before writing it out, and the
+/// call fails with a guest error, which [`ResultTooLarge::from_error`]
+/// recognizes. The sandbox is not poisoned.
+///
+/// # Example
+///
+/// ```text
+/// match loaded_sandbox.handle_event("handler", event, None) {
+///     Ok(result) => respond(200, result),
+///     Err(err) => match ResultTooLarge::from_error(&err) {
+///         Some(too_large) => respond(413, too_large.to_string()),
+///         None => respond(500, err.to_string()),
+///     },
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultTooLarge {
+    /// The size of the result, in bytes of JSON.
+    pub size: usize,
+    /// The maximum size of a result, in bytes of JSON.
+    pub limit: usize,
+}
+
+impl ResultTooLarge {
+    /// The `ResultTooLarge` error `err` is, if the result of a handler was
+    /// too large.
+    pub fn from_error(err: &HyperlightError) -> Option<Self> {
+        let HyperlightError::GuestError(_, message) = err else {
+            return None;
+        };
+        let start = message.find(RESULT_TOO_LARGE_ERROR)? + RESULT_TOO_LARGE_ERROR.len();
+        let mut numbers = message[start..]
+            .split_whitespace()
+            .filter_map(|word| word.parse().ok());
+        Some(Self {
+            size: numbers.next()?,
+            limit: numbers.next()?,
+        })
+    }
+}
+
+impl fmt::Display for ResultTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the result is {} bytes, more than the limit of {} bytes",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for ResultTooLarge {}
+
+#[cfg(test)]
+mod tests {
+    use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+
+    use super::*;
+
+    #[test]
+    fn test_from_error() {
+        let too_large = ResultTooLarge {
+            size: 1234,
+            limit: 1000,
+        };
+        let err = HyperlightError::GuestError(
+            ErrorCode::GuestError,
+            format!("Error: ResultTooLarge: {too_large}"),
+        );
+        assert_eq!(ResultTooLarge::from_error(&err), Some(too_large));
+
+        let err = HyperlightError::GuestError(ErrorCode::GuestError, "Error: boom".to_string());
+        assert_eq!(ResultTooLarge::from_error(&err), None);
+        let err = HyperlightError::Error(format!("ResultTooLarge: {too_large}"));
+        assert_eq!(ResultTooLarge::from_error(&err), None);
+    }
+}
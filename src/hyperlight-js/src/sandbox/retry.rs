@@ -0,0 +1,210 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::time::Duration;
+
+use hyperlight_host::HyperlightError;
+
+/// When and how often
+/// [`LoadedJSSandbox::handle_event_with_retry`](crate::LoadedJSSandbox::handle_event_with_retry)
+/// retries a handler.
+///
+/// An invocation is retried when it fails transiently: when it is cancelled
+/// by the host, e.g. by a monitor, or when it poisons the sandbox. Errors of
+/// the handler itself, like exceptions, are returned right away, as running
+/// it again with the same event would fail the same way.
+///
+/// The delay before the first retry is the initial backoff, and every
+/// following delay is the previous one times the multiplier, up to the
+/// maximum backoff.
+///
+/// # Example
+///
+/// ```text
+/// let policy = RetryPolicy::new(3).with_backoff(Duration::from_millis(10), Duration::from_secs(1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// The default delay before the first retry.
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+    /// The default maximum delay between retries.
+    pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(1);
+    /// The default factor the delay grows by after every retry.
+    pub const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+    /// A policy running the handler at most `max_attempts` times, counting
+    /// the first attempt, with the default backoff. A `max_attempts` of 0 is
+    /// taken as 1.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+            multiplier: Self::DEFAULT_MULTIPLIER,
+        }
+    }
+
+    /// Set the delay before the first retry, and the maximum delay between
+    /// retries.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Set the factor the delay grows by after every retry. Factors below 1
+    /// are taken as 1, for a constant delay.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// The maximum number of times the handler is run, counting the first
+    /// attempt.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The delay before attempt number `attempt`, counting from 1 for the
+    /// first attempt, which isn't delayed.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::ZERO;
+        }
+        let factor = self
+            .multiplier
+            .powi((attempt - 2).min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_backoff.as_secs_f64() * factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// A policy running the handler at most 3 times.
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// The result of a handler invoked with
+/// [`LoadedJSSandbox::handle_event_with_retry`](crate::LoadedJSSandbox::handle_event_with_retry).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retried<T> {
+    /// The result of the attempt that succeeded.
+    pub result: T,
+    /// The number of times the handler was run, 1 if it succeeded the first
+    /// time.
+    pub attempts: u32,
+}
+
+/// Whether `err` failed the invocation transiently, so that it is worth
+/// retrying, given whether the sandbox is poisoned.
+///
+/// With auto-recovery the sandbox is restored before this is called, so the
+/// errors that poison the sandbox count as transient too.
+pub(crate) fn is_transient(err: &HyperlightError, poisoned: bool) -> bool {
+    poisoned
+        || matches!(
+            err,
+            HyperlightError::ExecutionCanceledByHost()
+                | HyperlightError::PoisonedSandbox
+                | HyperlightError::GuestAborted(..)
+        )
+}
+
+/// The error of an attempt that could not be retried because restoring the
+/// sandbox failed with `restore_error`.
+///
+/// The error keeps its kind where it carries a message, so that callers can
+/// still tell a guest error or abort apart.
+pub(crate) fn restore_failed(
+    err: HyperlightError,
+    restore_error: HyperlightError,
+) -> HyperlightError {
+    let context = format!("restoring the sandbox to retry failed: {restore_error}");
+    match err {
+        HyperlightError::GuestError(code, message) => {
+            HyperlightError::GuestError(code, format!("{message} ({context})"))
+        }
+        HyperlightError::GuestAborted(code, message) => {
+            HyperlightError::GuestAborted(code, format!("{message} ({context})"))
+        }
+        err => HyperlightError::Error(format!("{err} ({context})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(5)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(50))
+            .with_multiplier(3.0);
+        assert_eq!(policy.backoff(1), Duration::ZERO);
+        assert_eq!(policy.backoff(2), Duration::from_millis(10));
+        assert_eq!(policy.backoff(3), Duration::from_millis(30));
+        assert_eq!(policy.backoff(4), Duration::from_millis(50));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(50));
+
+        let policy = RetryPolicy::new(0).with_multiplier(0.5);
+        assert_eq!(policy.max_attempts(), 1);
+        assert_eq!(policy.backoff(3), RetryPolicy::DEFAULT_INITIAL_BACKOFF);
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(
+            &HyperlightError::ExecutionCanceledByHost(),
+            false
+        ));
+        assert!(is_transient(&HyperlightError::PoisonedSandbox, false));
+        assert!(is_transient(
+            &HyperlightError::Error("boom".to_string()),
+            true
+        ));
+        assert!(!is_transient(
+            &HyperlightError::Error("boom".to_string()),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_restore_failed() {
+        let restore_error = || HyperlightError::Error("no memory".to_string());
+        match restore_failed(
+            HyperlightError::GuestAborted(1, "boom".to_string()),
+            restore_error(),
+        ) {
+            HyperlightError::GuestAborted(1, message) => assert_eq!(
+                message,
+                "boom (restoring the sandbox to retry failed: no memory)"
+            ),
+            err => panic!("Unexpected error: {err:?}"),
+        }
+        let err = restore_failed(HyperlightError::PoisonedSandbox, restore_error());
+        assert!(err
+            .to_string()
+            .ends_with("(restoring the sandbox to retry failed: no memory)"));
+    }
+}
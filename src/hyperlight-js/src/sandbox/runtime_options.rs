@@ -0,0 +1,47 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use serde::Serialize;
+
+/// Options applied to the JavaScript runtime in the guest once it has been
+/// loaded, before any handler is registered.
+///
+/// The serialization of this struct has to match the deserialization of
+/// `RuntimeOptions` in src/hyperlight-js-runtime/src/main/hyperlight.rs
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct RuntimeOptions {
+    /// Freeze the built-in constructors and prototypes.
+    pub(crate) freeze_builtins: bool,
+    /// Draw `Math.random` from the generator seeded for every invocation.
+    pub(crate) seeded_math_random: bool,
+    /// Poll the host for cooperative cancellation while running JavaScript.
+    pub(crate) cooperative_cancellation: bool,
+    /// Compress results of at least this many bytes when events are framed.
+    pub(crate) wire_compression_min_size: Option<usize>,
+    /// Limit the memory allocated by QuickJS, in bytes.
+    pub(crate) memory_limit: Option<usize>,
+    /// Limit the stack used by QuickJS, in bytes.
+    pub(crate) max_stack_size: Option<usize>,
+    /// Collect garbage whenever QuickJS allocated this many bytes.
+    pub(crate) gc_threshold: Option<usize>,
+    /// Evaluate every handler in its own realm.
+    pub(crate) per_handler_realms: bool,
+    /// Make `eval` and the function constructors throw.
+    pub(crate) dynamic_code_disabled: bool,
+    /// Sample the JavaScript call stack at this interval, in microseconds.
+    pub(crate) profile_interval_micros: Option<u64>,
+    /// Limit the results of the handlers, in bytes.
+    pub(crate) max_result_size: Option<usize>,
+}
@@ -13,19 +13,35 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
-#[cfg(target_os = "linux")]
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use hyperlight_host::sandbox::SandboxConfiguration;
 use hyperlight_host::{is_hypervisor_present, GuestBinary, HyperlightError, Result};
 
+use super::backend::UninitializedBackend;
+use super::deterministic::DeterministicMode;
+use super::gc_policy::GcPolicy;
+use super::host_function_allowlist::HostFunctionAllowlist;
+use super::isolation_mode::IsolationMode;
+use super::monitor::runtime::{self, MonitorRuntimeConfig};
+use super::monitor::MonitorSet;
+use super::observer::SandboxObserver;
 use super::proto_js_sandbox::ProtoJSSandbox;
+use super::runtime_options::RuntimeOptions;
+use super::settings::SandboxSettings;
+use super::wire_compression::WireCompression;
 use crate::HostPrintFn;
 
 /// A builder for a ProtoJSSandbox
 pub struct SandboxBuilder {
     config: SandboxConfiguration,
     host_print_fn: Option<HostPrintFn>,
+    runtime_options: RuntimeOptions,
+    allow_rollback: bool,
+    settings: SandboxSettings,
+    cancellation_grace_period: Option<Duration>,
+    monitor_runtime: Option<MonitorRuntimeConfig>,
 }
 
 /// The minimum scratch size for the JS runtime sandbox.
@@ -61,6 +77,11 @@ impl SandboxBuilder {
         Self {
             config,
             host_print_fn: None,
+            runtime_options: RuntimeOptions::default(),
+            allow_rollback: true,
+            settings: SandboxSettings::default(),
+            cancellation_grace_period: None,
+            monitor_runtime: None,
         }
     }
 
@@ -81,8 +102,14 @@ impl SandboxBuilder {
     /// to send data to the host
     /// The host can read from this buffer
     /// The guest can write to this buffer
+    ///
+    /// Events too large for the buffer are rejected with
+    /// [`EventTooLarge`](crate::EventTooLarge) before calling into the guest.
     pub fn with_guest_input_buffer_size(mut self, guest_input_buffer_size: usize) -> Self {
         self.config.set_input_data_size(guest_input_buffer_size);
+        // The configuration rounds the size up to its minimum.
+        self.settings.input_buffer_size =
+            Some(guest_input_buffer_size.max(SandboxConfiguration::MIN_INPUT_SIZE));
         self
     }
 
@@ -109,6 +136,62 @@ impl SandboxBuilder {
         self
     }
 
+    /// Limit the memory the JavaScript engine allocates, in bytes.
+    ///
+    /// Unlike the guest heap size, which bounds the whole guest, this bounds
+    /// QuickJS alone: an allocation over the limit throws an
+    /// `InternalError: out of memory` that the handler can catch, instead of
+    /// aborting the guest with a `malloc failed` error once the guest heap is
+    /// exhausted. Keep it comfortably below the guest heap size, which also
+    /// holds the runtime itself and the events and results. Either way, an
+    /// uncaught out of memory error is recognized by
+    /// [`GuestOutOfMemory::from_error`](crate::GuestOutOfMemory::from_error).
+    ///
+    /// Unlimited by default.
+    pub fn with_js_memory_limit(mut self, bytes: usize) -> Self {
+        self.runtime_options.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Limit the results of the handlers, in bytes of JSON.
+    ///
+    /// The guest checks the size of a result before writing it to the output
+    /// buffer, so a handler returning too much fails with an error that
+    /// [`ResultTooLarge::from_error`](crate::ResultTooLarge::from_error)
+    /// recognizes, e.g. to respond with a 413, instead of an opaque failure
+    /// to write the output. Keep it below the guest output buffer size.
+    ///
+    /// Unlimited by default.
+    pub fn with_max_result_size(mut self, bytes: usize) -> Self {
+        self.runtime_options.max_result_size = Some(bytes);
+        self
+    }
+
+    /// Limit the stack the JavaScript engine uses, in bytes.
+    ///
+    /// Deeper recursion throws a `RangeError: Maximum call stack size
+    /// exceeded` that the handler can catch, instead of overflowing the
+    /// guest stack.
+    ///
+    /// Defaults to the limit of QuickJS.
+    pub fn with_js_max_stack_size(mut self, bytes: usize) -> Self {
+        self.runtime_options.max_stack_size = Some(bytes);
+        self
+    }
+
+    /// Run garbage collection whenever the JavaScript engine has allocated
+    /// this many bytes since the last collection, during handler calls.
+    ///
+    /// This complements the collection after calls, see
+    /// [`with_gc_policy`](Self::with_gc_policy), for handlers that allocate a
+    /// lot in a single call.
+    ///
+    /// Defaults to the threshold of QuickJS.
+    pub fn with_js_gc_threshold(mut self, bytes: usize) -> Self {
+        self.runtime_options.gc_threshold = Some(bytes);
+        self
+    }
+
     /// Sets the offset from `SIGRTMIN` to determine the real-time signal used for
     /// interrupting the VCPU thread.
     ///
@@ -130,6 +213,387 @@ impl SandboxBuilder {
         self
     }
 
+    /// Freeze the JavaScript built-ins (`Object`, `Array`, `Function`, their
+    /// prototypes, etc.) once the runtime has been set up and before any
+    /// handler is evaluated.
+    ///
+    /// This prevents a handler from prototype-polluting the globals it shares
+    /// with the other handlers in the same sandbox. Handlers are evaluated as
+    /// (strict mode) modules, so an attempt to modify a frozen built-in throws
+    /// a `TypeError`.
+    ///
+    /// Assigning commonly overridden properties (such as `name`, `message`,
+    /// `constructor` or `toString`) on objects that inherit them from a frozen
+    /// prototype keeps working, so patterns like `this.name = "MyError"` in an
+    /// `Error` subclass are unaffected.
+    ///
+    /// Disabled by default.
+    pub fn with_frozen_builtins(mut self, enabled: bool) -> Self {
+        self.runtime_options.freeze_builtins = enabled;
+        self
+    }
+
+    /// Choose how the handlers of the sandbox are isolated from each other in
+    /// the guest, see [`IsolationMode`].
+    ///
+    /// [`IsolationMode::Shared`] by default.
+    pub fn with_isolation_mode(mut self, mode: IsolationMode) -> Self {
+        self.runtime_options.per_handler_realms = mode == IsolationMode::PerHandler;
+        self
+    }
+
+    /// Disable the evaluation of code from strings in the handlers.
+    ///
+    /// `eval` (direct or indirect), `new Function(...)` and the constructors
+    /// of async and generator functions throw an `EvalError`, and a handler
+    /// that imports a module it didn't import when it was loaded, e.g. with
+    /// `import()`, fails to load it. Modules that were loaded with the
+    /// handlers, host modules and built-in modules can still be imported.
+    ///
+    /// Disabled by default.
+    pub fn with_dynamic_code_disabled(mut self) -> Self {
+        self.runtime_options.dynamic_code_disabled = true;
+        self
+    }
+
+    /// Enable cooperative cancellation of the handlers.
+    ///
+    /// When enabled, the JavaScript engine in the guest periodically checks
+    /// whether the host asked it to stop, and monitors passed to
+    /// `handle_event_with_monitor` or `get_loaded_sandbox_with_monitor` cancel
+    /// the handler through a [`CancellationHandle`](crate::CancellationHandle)
+    /// instead of killing the guest. A cancelled handler fails with
+    /// [`HyperlightError::ExecutionCanceledByHost`], like a killed one, but
+    /// the sandbox is not poisoned and does not need to be restored.
+    ///
+    /// Checking for cancellation requires a call to the host every few
+    /// thousand JavaScript operations, which slows down compute-bound
+    /// handlers slightly.
+    ///
+    /// Disabled by default.
+    pub fn with_cooperative_cancellation(mut self, enabled: bool) -> Self {
+        self.runtime_options.cooperative_cancellation = enabled;
+        self
+    }
+
+    /// Set how long a handler cancelled by a monitor has to stop before the
+    /// guest is killed.
+    ///
+    /// A handler only notices a cancellation while it runs JavaScript, so a
+    /// handler blocked in a long-running host function or built-in can keep
+    /// running after a monitor fired. With a grace period, the guest is
+    /// killed, and the sandbox poisoned, if the handler is still running once
+    /// the grace period elapsed. This has no effect unless cooperative
+    /// cancellation is enabled with
+    /// [`with_cooperative_cancellation`](Self::with_cooperative_cancellation).
+    ///
+    /// By default, a cancelled handler is never killed.
+    pub fn with_cancellation_grace_period(mut self, grace_period: Duration) -> Self {
+        self.cancellation_grace_period = Some(grace_period);
+        self
+    }
+
+    /// Allow or reject restoring a `LoadedJSSandbox` to a snapshot that would
+    /// roll its invocation sequence backwards.
+    ///
+    /// Every invocation of a handler is stamped with a monotonically
+    /// increasing sequence number, available to the handler as
+    /// `context.sequence` and to the host as `LoadedJSSandbox::sequence`.
+    /// Restoring a snapshot resets the sequence to the one the snapshot was
+    /// taken at. When rollback is not allowed, `restore` fails for snapshots
+    /// taken before the most recent invocation (including failed invocations)
+    /// and for snapshots not taken from this sandbox, so sequence numbers are
+    /// never reused.
+    ///
+    /// Allowed by default.
+    pub fn with_allow_rollback(mut self, allow: bool) -> Self {
+        self.allow_rollback = allow;
+        self
+    }
+
+    /// Set the maximum number of handlers that can be added to the sandbox.
+    ///
+    /// `JSSandbox::add_handler` fails once the sandbox has this many handlers.
+    ///
+    /// Unlimited by default.
+    pub fn with_max_handlers(mut self, max_handlers: usize) -> Self {
+        self.settings.handler_limits.max_handlers = Some(max_handlers);
+        self
+    }
+
+    /// Set the maximum cumulative size, in bytes, of the handler scripts that
+    /// can be added to the sandbox.
+    ///
+    /// `JSSandbox::add_handler` fails if adding the handler would bring the
+    /// total size of the handler scripts over this limit. Modules imported by
+    /// the handlers do not count towards the limit.
+    ///
+    /// Unlimited by default.
+    pub fn with_max_script_bytes(mut self, max_script_bytes: usize) -> Self {
+        self.settings.handler_limits.max_script_bytes = Some(max_script_bytes);
+        self
+    }
+
+    /// Set the maximum number of host functions a handler can call in a
+    /// single execution.
+    ///
+    /// Once a handler has made this many host calls, further calls fail with
+    /// an error the handler can observe, rather than reaching the host. This
+    /// stops a misbehaving handler from hammering host functions in a tight
+    /// loop. Each call in a batch counts, and so do the calls made while the
+    /// handlers are loaded by `JSSandbox::get_loaded_sandbox`.
+    ///
+    /// Unlimited by default.
+    pub fn with_max_host_calls(mut self, max_host_calls: u64) -> Self {
+        self.settings.handler_limits.max_host_calls = Some(max_host_calls);
+        self
+    }
+
+    /// Restrict the built-in host functions the guest may call to exactly
+    /// the ones listed, like a seccomp filter for the sandbox boundary.
+    ///
+    /// The built-in host functions are `HostPrint`, `CurrentTimeMicros`,
+    /// `MonotonicTimeMicros`, `IsExecutionCancelled`, `ResolveModule`,
+    /// `LoadModule`, `CallHostJsFunction` and `CallHostJsFunctionBatch`.
+    /// Calls to the ones not listed, including built-ins added by future
+    /// versions, are denied with an error and logged as warnings. Building
+    /// the sandbox fails if the list names a function that is not a built-in,
+    /// so the list is an explicit and auditable definition of the boundary of
+    /// a deployment.
+    ///
+    /// All the built-in host functions are allowed by default.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyperlight_js::SandboxBuilder;
+    ///
+    /// // Handlers can print and read the clock, but not call host modules
+    /// let proto_js_sandbox = SandboxBuilder::new()
+    ///     .with_seccomp_style_host_function_allowlist(["HostPrint", "CurrentTimeMicros"])
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_seccomp_style_host_function_allowlist(
+        mut self,
+        host_functions: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.settings.host_function_allowlist = HostFunctionAllowlist::new(host_functions);
+        self
+    }
+
+    /// Set the number of worker threads of the runtime that runs the
+    /// execution monitors.
+    ///
+    /// The monitor runtime is shared by all the sandboxes in the process and
+    /// is created the first time it is configured or used, so this takes
+    /// precedence over the `HYPERLIGHT_MONITOR_THREADS` environment variable
+    /// only if it is set before any monitor runs. [`build`](Self::build) fails
+    /// if the runtime was already created with a different configuration.
+    ///
+    /// Defaults to `HYPERLIGHT_MONITOR_THREADS`, or 2 threads.
+    pub fn with_monitor_runtime_threads(mut self, threads: usize) -> Self {
+        self.monitor_runtime = Some(MonitorRuntimeConfig::Threads(threads));
+        self
+    }
+
+    /// Run the execution monitors on an existing Tokio runtime of the
+    /// application, instead of a runtime owned by hyperlight-js.
+    ///
+    /// The runtime must be multi-threaded and have the time driver enabled,
+    /// and must outlive the sandboxes: monitors do not fire once it is shut
+    /// down. As with [`with_monitor_runtime_threads`](Self::with_monitor_runtime_threads),
+    /// the monitor runtime is shared by all the sandboxes in the process, and
+    /// [`build`](Self::build) fails if it was already created with a different
+    /// configuration.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let proto_js_sandbox = SandboxBuilder::new()
+    ///     .with_monitor_runtime(tokio::runtime::Handle::current())
+    ///     .build()?;
+    /// ```
+    pub fn with_monitor_runtime(mut self, handle: tokio::runtime::Handle) -> Self {
+        self.monitor_runtime = Some(MonitorRuntimeConfig::Handle(handle));
+        self
+    }
+
+    /// Guard every call to [`LoadedJSSandbox::handle_event`](crate::LoadedJSSandbox::handle_event)
+    /// with `monitor`, so handlers can't run unmonitored by accident.
+    ///
+    /// `monitor` can be a single monitor, a tuple of monitors or a
+    /// [`BoxedMonitorSet`](crate::BoxedMonitorSet). Calls to
+    /// [`LoadedJSSandbox::handle_event_with_monitor`](crate::LoadedJSSandbox::handle_event_with_monitor)
+    /// use the monitor they are given instead of the default ones.
+    ///
+    /// No default monitors by default.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// let proto_js_sandbox = SandboxBuilder::new()
+    ///     .with_default_monitors((
+    ///         WallClockMonitor::new(Duration::from_secs(5))?,
+    ///         CpuTimeMonitor::new(Duration::from_millis(500))?,
+    ///     ))
+    ///     .build()?;
+    /// ```
+    pub fn with_default_monitors(mut self, monitor: impl MonitorSet + 'static) -> Self {
+        self.settings.default_monitors = Some(Arc::new(monitor));
+        self
+    }
+
+    /// Restore the sandbox automatically after a call to `handle_event`
+    /// poisons it, e.g. because a monitor killed the handler.
+    ///
+    /// When enabled, a snapshot is taken when the handlers are loaded, and a
+    /// call that poisons the sandbox restores it before returning its error,
+    /// so the sandbox stays usable without an explicit `restore`. The state
+    /// built up by the handlers since they were loaded is lost, but the
+    /// invocation sequence keeps increasing.
+    ///
+    /// Disabled by default.
+    pub fn with_auto_recover(mut self, auto_recover: bool) -> Self {
+        self.settings.auto_recover = auto_recover;
+        self
+    }
+
+    /// Make the sandbox deterministic, for replays, debugging and reproducible
+    /// CI runs.
+    ///
+    /// In deterministic mode:
+    /// * The clock read by the guest (`Date.now()`, `new Date()`, ...) is a
+    ///   virtual clock stopped at `epoch`, which only moves when the host
+    ///   calls [`LoadedJSSandbox::advance_virtual_time`](crate::LoadedJSSandbox::advance_virtual_time).
+    /// * The seed of every invocation is derived from `seed` and the sequence
+    ///   number of the invocation, instead of being random.
+    /// * `Math.random` draws from the same seeded generator as the `random`
+    ///   module, instead of the generator of QuickJS.
+    ///
+    /// Two sandboxes built with the same seed and epoch, loaded with the same
+    /// handlers and called with the same events therefore produce the same
+    /// results. The guest has no other source of time or randomness.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// use hyperlight_js::SandboxBuilder;
+    ///
+    /// let epoch = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let proto_js_sandbox = SandboxBuilder::new()
+    ///     .with_deterministic_mode(42, epoch)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_deterministic_mode(mut self, seed: u64, epoch: SystemTime) -> Self {
+        self.settings.deterministic = Some(DeterministicMode::new(seed, epoch));
+        self.runtime_options.seeded_math_random = true;
+        self
+    }
+
+    /// Choose when to run garbage collection after calls to `handle_event`
+    /// that don't pass an explicit `gc` flag, see [`GcPolicy`].
+    ///
+    /// Defaults to [`GcPolicy::Always`].
+    pub fn with_gc_policy(mut self, policy: GcPolicy) -> Self {
+        self.settings.gc_policy = policy;
+        self
+    }
+
+    /// Label the sandbox with `key` and `value`, e.g. the tenant it serves, to
+    /// tell sandboxes apart in multi-tenant hosts.
+    ///
+    /// The labels are added to the metrics of the sandbox, its handler calls
+    /// and the monitors terminating them, and recorded as the `sandbox_labels`
+    /// field of its `handle_event` spans, as `key=value` pairs separated by
+    /// commas. Setting a label again replaces its value.
+    ///
+    /// Every distinct value creates new metric series, so labels should have
+    /// few distinct values. Building the sandbox fails if `key` is a label of
+    /// the metrics of hyperlight-js, like `event_handler_name` or `outcome`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyperlight_js::SandboxBuilder;
+    ///
+    /// let proto_js_sandbox = SandboxBuilder::new()
+    ///     .with_label("tenant", "acme")
+    ///     .with_label("region", "westeurope")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.settings.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Report how the guest heap changed during invocations that fail.
+    ///
+    /// When enabled, the heap statistics of the guest are measured before
+    /// every call to `handle_event`, and again after a call that fails. The
+    /// resulting [`HeapReport`](crate::HeapReport) is appended to the message
+    /// of guest errors, logged, and recorded in the
+    /// [diagnostics](crate::LoadedJSSandbox::collect_diagnostics) of the
+    /// invocation, to help tell a bug in the handler from a leak in the runtime.
+    ///
+    /// Measuring the heap costs two guest calls per invocation, so this is
+    /// disabled by default.
+    pub fn with_heap_reports(mut self, heap_reports: bool) -> Self {
+        self.settings.heap_reports = heap_reports;
+        self
+    }
+
+    /// Sample the JavaScript call stack every `interval` while JavaScript
+    /// runs in the sandbox, to find where slow handlers spend their time.
+    ///
+    /// The samples are collected with
+    /// [`LoadedJSSandbox::take_profile`](crate::LoadedJSSandbox::take_profile),
+    /// in the folded format that [inferno](https://github.com/jonhoo/inferno)
+    /// and `flamegraph.pl` turn into flame graphs. The stack is sampled when
+    /// QuickJS polls for interrupts, so the interval is a lower bound, and
+    /// code that does not run JavaScript (host functions, JSON-logic rules)
+    /// is not sampled. This raises `Error.stackTraceLimit` to 64 to sample
+    /// deep stacks.
+    ///
+    /// Checking the interval reads the clock of the host, and in
+    /// [deterministic mode](Self::with_deterministic_mode) the virtual clock,
+    /// which only advances when the host moves it.
+    ///
+    /// Disabled by default.
+    pub fn with_profiling(mut self, interval: Duration) -> Self {
+        self.runtime_options.profile_interval_micros =
+            Some(u64::try_from(interval.as_micros()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Register callbacks on the lifecycle events of the sandbox: loading and
+    /// unloading handlers, invoking them, monitors firing, the sandbox being
+    /// poisoned and its state being restored, see [`SandboxObserver`].
+    ///
+    /// The observer is shared by the `JSSandbox` and `LoadedJSSandbox` built
+    /// from this builder.
+    pub fn with_observer(mut self, observer: impl SandboxObserver + 'static) -> Self {
+        self.settings.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Compress the events and results exchanged with the guest.
+    ///
+    /// Compression is transparent to the handlers and to the callers of
+    /// `handle_event`, and lets large events and results fit in smaller input
+    /// and output buffers, see [`WireCompression`].
+    ///
+    /// Disabled by default.
+    pub fn with_wire_compression(mut self, compression: WireCompression) -> Self {
+        self.settings.wire_compression = compression;
+        self.runtime_options.wire_compression_min_size = compression.min_size();
+        self
+    }
+
     /// Get the current configuration
     pub fn get_config(&self) -> &SandboxConfiguration {
         &self.config
@@ -168,13 +632,38 @@ impl SandboxBuilder {
 
     /// Build the ProtoJSSandbox
     pub fn build(self) -> Result<ProtoJSSandbox> {
-        if !is_hypervisor_present() {
+        self.settings.host_function_allowlist.validate()?;
+        self.settings.labels.validate()?;
+        // With the `in-process` feature, the runtime runs in-process instead.
+        if !is_hypervisor_present() && !cfg!(feature = "in-process") {
             return Err(HyperlightError::NoHypervisorFound());
         }
         let guest_binary = GuestBinary::Buffer(super::JSRUNTIME);
-        let proto_js_sandbox =
-            ProtoJSSandbox::new(guest_binary, Some(self.config), self.host_print_fn)?;
-        Ok(proto_js_sandbox)
+        let backend = UninitializedBackend::new(guest_binary, Some(self.config))?;
+        self.build_on(backend)
+    }
+
+    /// Build a ProtoJSSandbox whose runtime runs in-process, whether there is
+    /// a hypervisor or not.
+    #[cfg(feature = "testing")]
+    pub(crate) fn build_in_process(self) -> Result<ProtoJSSandbox> {
+        self.settings.host_function_allowlist.validate()?;
+        self.settings.labels.validate()?;
+        self.build_on(UninitializedBackend::in_process())
+    }
+
+    fn build_on(self, backend: UninitializedBackend) -> Result<ProtoJSSandbox> {
+        if let Some(monitor_runtime) = self.monitor_runtime {
+            runtime::configure(monitor_runtime)?;
+        }
+        ProtoJSSandbox::new(
+            backend,
+            self.host_print_fn,
+            self.runtime_options,
+            self.allow_rollback,
+            self.settings,
+            self.cancellation_grace_period,
+        )
     }
 }
 
@@ -0,0 +1,854 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hyperlight_host::{new_error, Result};
+
+use super::js_sandbox_error::is_cancelled;
+use super::metrics::{record_scheduled_run, record_scheduled_run_skipped};
+use super::monitor::MonitorSet;
+use crate::LoadedJSSandbox;
+
+/// The default number of runs kept in the history of a [`Scheduler`].
+pub const DEFAULT_HISTORY_SIZE: usize = 100;
+
+type HandlerCall = dyn Fn(&mut LoadedJSSandbox, &str, String) -> Result<String> + Send;
+type Reload = dyn FnMut() -> Result<LoadedJSSandbox> + Send;
+
+/// When a [`ScheduledJob`] runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every interval, starting one interval after the scheduler starts.
+    Every(Duration),
+    /// At the times matching a cron expression, see [`Schedule::cron`].
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    /// Run every `interval`, which must not be zero.
+    pub fn every(interval: Duration) -> Self {
+        Self::Every(interval)
+    }
+
+    /// Run at the times matching the cron `expression`, in UTC.
+    ///
+    /// The expression has the five fields `minute hour day-of-month month
+    /// day-of-week`, each a `*`, a value, a range `a-b`, or a list of them,
+    /// optionally with a step such as `*/15`. Sunday is `0` or `7`. The
+    /// shorthands `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly`
+    /// are accepted too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::Schedule;
+    ///
+    /// // At 02:30 on weekdays
+    /// let schedule = Schedule::cron("30 2 * * 1-5").unwrap();
+    /// assert!(Schedule::cron("61 * * * *").is_err());
+    /// ```
+    pub fn cron(expression: &str) -> Result<Self> {
+        Ok(Self::Cron(CronSchedule::parse(expression)?))
+    }
+
+    /// The first time the schedule fires after `after`, given that it last
+    /// fired, or started, at `previous`.
+    fn next(&self, previous: SystemTime, after: SystemTime) -> Option<SystemTime> {
+        match self {
+            // Rejected by `Scheduler::start`
+            Self::Every(interval) if interval.is_zero() => None,
+            Self::Every(interval) => {
+                let mut next = previous + *interval;
+                if next <= after {
+                    // Skip the intervals that went by, keeping the phase
+                    let behind = after.duration_since(next).unwrap_or_default();
+                    let missed = behind.as_nanos() / interval.as_nanos() + 1;
+                    next += *interval * u32::try_from(missed).unwrap_or(u32::MAX);
+                }
+                Some(next)
+            }
+            Self::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+/// A parsed cron expression, see [`Schedule::cron`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // Whether the day fields are `*`. When both are restricted, a day matches
+    // if either of them does, as in cron.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+impl CronSchedule {
+    fn parse(expression: &str) -> Result<Self> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(new_error!(
+                "Invalid cron expression '{}': expected 5 fields",
+                expression
+            ));
+        };
+        let mut days_of_week_mask = parse_field(days_of_week, 0, 7, expression)?;
+        // 7 is Sunday, like 0
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59, expression)?,
+            hours: parse_field(hours, 0, 23, expression)?,
+            days_of_month: parse_field(days_of_month, 1, 31, expression)?,
+            months: parse_field(months, 1, 12, expression)?,
+            days_of_week: days_of_week_mask,
+            any_day_of_month: days_of_month.starts_with('*'),
+            any_day_of_week: days_of_week.starts_with('*'),
+        })
+    }
+
+    /// The first minute after `after` matching the expression, or `None` if
+    /// none does in the next few years, e.g. for `0 0 30 2 *`.
+    fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let seconds = after.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let mut minute = seconds / 60 + 1;
+        let limit = minute + 5 * 366 * MINUTES_PER_DAY;
+        while minute < limit {
+            let day = minute / MINUTES_PER_DAY;
+            let (year, month, day_of_month) = civil_from_days(day);
+            if !bit(self.months, month) {
+                let (year, month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                minute = days_from_civil(year, month, 1) * MINUTES_PER_DAY;
+                continue;
+            }
+            // 1970-01-01 was a Thursday
+            let day_of_week = (day + 4) % 7;
+            let day_matches = match (self.any_day_of_month, self.any_day_of_week) {
+                (false, false) => {
+                    bit(self.days_of_month, day_of_month) || bit(self.days_of_week, day_of_week)
+                }
+                _ => bit(self.days_of_month, day_of_month) && bit(self.days_of_week, day_of_week),
+            };
+            if !day_matches {
+                minute = (day + 1) * MINUTES_PER_DAY;
+                continue;
+            }
+            if !bit(self.hours, minute % MINUTES_PER_DAY / 60) {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+            if !bit(self.minutes, minute % 60) {
+                minute += 1;
+                continue;
+            }
+            return Some(UNIX_EPOCH + Duration::from_secs(minute * 60));
+        }
+        None
+    }
+}
+
+fn bit(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse a field of a cron expression into a mask of the values it matches.
+fn parse_field(field: &str, min: u64, max: u64, expression: &str) -> Result<u64> {
+    let invalid = || new_error!("Invalid cron expression '{}': '{}'", expression, field);
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| invalid())?,
+                    end.parse().map_err(|_| invalid())?,
+                ),
+                // `a/n` runs from `a` to the end of the range
+                None if part.contains('/') => (range.parse().map_err(|_| invalid())?, max),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    (value, value)
+                }
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// The date of the day `days` after 1970-01-01, see
+/// <https://howardhinnant.github.io/date_algorithms.html>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The number of days from 1970-01-01 to the date, the inverse of
+/// [`civil_from_days`].
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// What to do with the runs of a job that are due while the sandbox is still
+/// busy with another run.
+///
+/// The runs of a [`Scheduler`] never overlap, as they share its sandbox, so a
+/// run that takes longer than the interval of its job, or that is due while
+/// another job runs, delays the runs that are due in the meantime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Skip the runs that are due while the sandbox is busy, and run the job
+    /// again at its next scheduled time.
+    #[default]
+    Skip,
+    /// Run the job once as soon as the sandbox is free, however many of its
+    /// runs were due in the meantime.
+    Delay,
+}
+
+/// A handler a [`Scheduler`] calls on a [`Schedule`].
+pub struct ScheduledJob {
+    name: String,
+    handler: String,
+    schedule: Schedule,
+    event: Option<String>,
+    call: Box<HandlerCall>,
+    jitter: Duration,
+    overlap_policy: OverlapPolicy,
+}
+
+impl ScheduledJob {
+    /// A job named `name`, calling the handler `handler` on `schedule`.
+    ///
+    /// Unless an event is set with [`with_event`](Self::with_event), the
+    /// handler is called with `{ "job": name, "scheduledTime": ms }`, where
+    /// `scheduledTime` is the time the run was due, in milliseconds since the
+    /// Unix epoch.
+    pub fn new(name: impl Into<String>, handler: impl Into<String>, schedule: Schedule) -> Self {
+        Self {
+            name: name.into(),
+            handler: handler.into(),
+            schedule,
+            event: None,
+            call: Box::new(|sandbox, handler, event| sandbox.handle_event(handler, event, None)),
+            jitter: Duration::ZERO,
+            overlap_policy: OverlapPolicy::default(),
+        }
+    }
+
+    /// Call the handler with the JSON `event` on every run.
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Guard every run with `monitor`, instead of the default monitors of the
+    /// sandbox. Runs terminated by the monitor are recorded as
+    /// [`RunOutcome::TimedOut`].
+    pub fn with_monitor(mut self, monitor: impl MonitorSet + 'static) -> Self {
+        self.call = Box::new(move |sandbox, handler, event| {
+            sandbox.handle_event_with_monitor(handler, event, &monitor, None)
+        });
+        self
+    }
+
+    /// Delay every run by a random duration up to `jitter`, to spread the
+    /// load of jobs scheduled at the same time.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// What to do with runs that are due while the sandbox is busy. Defaults
+    /// to [`OverlapPolicy::Skip`].
+    pub fn with_overlap_policy(mut self, overlap_policy: OverlapPolicy) -> Self {
+        self.overlap_policy = overlap_policy;
+        self
+    }
+}
+
+impl std::fmt::Debug for ScheduledJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduledJob")
+            .field("name", &self.name)
+            .field("handler", &self.handler)
+            .field("schedule", &self.schedule)
+            .field("event", &self.event)
+            .field("jitter", &self.jitter)
+            .field("overlap_policy", &self.overlap_policy)
+            .finish()
+    }
+}
+
+/// The outcome of a run of a [`ScheduledJob`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// The handler returned.
+    Succeeded,
+    /// The handler failed, with the message of the error.
+    Failed(String),
+    /// The handler was terminated by an execution monitor.
+    TimedOut,
+}
+
+/// A run of a [`ScheduledJob`], in the history of a [`Scheduler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunRecord {
+    /// The name of the job.
+    pub job: String,
+    /// When the run was due.
+    pub scheduled: SystemTime,
+    /// When the run started.
+    pub started: SystemTime,
+    /// How long the handler ran.
+    pub duration: Duration,
+    /// The outcome of the run.
+    pub outcome: RunOutcome,
+}
+
+/// Statistics on the runs of a [`ScheduledJob`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct JobStats {
+    /// Number of runs.
+    pub runs: u64,
+    /// Number of runs whose handler returned.
+    pub succeeded: u64,
+    /// Number of runs whose handler failed.
+    pub failed: u64,
+    /// Number of runs terminated by an execution monitor.
+    pub timed_out: u64,
+    /// Number of runs skipped because the sandbox was busy, see
+    /// [`OverlapPolicy::Skip`].
+    pub skipped: u64,
+    /// Total time the handler ran.
+    pub total_duration: Duration,
+    /// When the last run started.
+    pub last_run: Option<SystemTime>,
+    /// When the next run is due, or `None` if the schedule never fires again.
+    pub next_run: Option<SystemTime>,
+}
+
+/// Runs handlers of a sandbox on schedules, on a background thread.
+///
+/// The jobs of a scheduler share its sandbox, so their runs never overlap;
+/// see [`OverlapPolicy`] for what happens to runs that are due while the
+/// sandbox is busy. The scheduler keeps statistics on the runs of every job
+/// and a history of the last runs, and reports them as the
+/// `scheduled_runs_total` and `scheduled_runs_skipped_total` metrics.
+///
+/// # Example
+///
+/// ```text
+/// use std::time::Duration;
+///
+/// use hyperlight_js::{Schedule, ScheduledJob, Scheduler, SandboxBuilder, Script, WallClockMonitor};
+///
+/// let load = || {
+///     let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+///     sandbox.add_handler("cleanup", Script::from_file("cleanup.js")?)?;
+///     sandbox.get_loaded_sandbox()
+/// };
+/// let scheduler = Scheduler::new(load()?)
+///     .with_reload(load)
+///     .with_job(
+///         ScheduledJob::new("nightly-cleanup", "cleanup", Schedule::cron("0 3 * * *")?)
+///             .with_monitor(WallClockMonitor::new(Duration::from_secs(30))?),
+///     )
+///     .start()?;
+///
+/// // ...
+/// println!("{:?}", scheduler.stats("nightly-cleanup"));
+/// let sandbox = scheduler.stop()?;
+/// ```
+pub struct Scheduler {
+    sandbox: LoadedJSSandbox,
+    jobs: Vec<ScheduledJob>,
+    reload: Option<Box<Reload>>,
+    history_size: usize,
+}
+
+impl Scheduler {
+    /// A scheduler running its jobs in `sandbox`.
+    pub fn new(sandbox: LoadedJSSandbox) -> Self {
+        Self {
+            sandbox,
+            jobs: Vec::new(),
+            reload: None,
+            history_size: DEFAULT_HISTORY_SIZE,
+        }
+    }
+
+    /// Add `job` to the scheduler.
+    pub fn with_job(mut self, job: ScheduledJob) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    /// Replace the sandbox with a new one from `reload` when a run poisons
+    /// it, e.g. because a monitor terminated the handler. Without it, the
+    /// runs after a poisoned one fail.
+    pub fn with_reload(
+        mut self,
+        reload: impl FnMut() -> Result<LoadedJSSandbox> + Send + 'static,
+    ) -> Self {
+        self.reload = Some(Box::new(reload));
+        self
+    }
+
+    /// Keep the last `history_size` runs in the history. Defaults to
+    /// [`DEFAULT_HISTORY_SIZE`].
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.history_size = history_size;
+        self
+    }
+
+    /// Start running the jobs on a background thread.
+    ///
+    /// Fails if two jobs have the same name, or if a job runs every zero
+    /// interval.
+    pub fn start(self) -> Result<SchedulerHandle> {
+        let mut stats = HashMap::new();
+        let start = SystemTime::now();
+        let mut jobs = Vec::with_capacity(self.jobs.len());
+        for job in self.jobs {
+            let next = job.schedule.next(start, start);
+            let job_stats = JobStats {
+                next_run: next,
+                ..Default::default()
+            };
+            if matches!(job.schedule, Schedule::Every(interval) if interval.is_zero()) {
+                return Err(new_error!(
+                    "The scheduled job '{}' runs every zero interval",
+                    job.name
+                ));
+            }
+            if stats.insert(job.name.clone(), job_stats).is_some() {
+                return Err(new_error!("Duplicate scheduled job '{}'", job.name));
+            }
+            jobs.push(JobState {
+                next,
+                jitter: jitter(job.jitter),
+                job,
+            });
+        }
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                stopped: false,
+                stats,
+                history: VecDeque::new(),
+            }),
+            wake: Condvar::new(),
+        });
+        let worker = Worker {
+            sandbox: self.sandbox,
+            jobs,
+            reload: self.reload,
+            history_size: self.history_size,
+            shared: shared.clone(),
+        };
+        let thread = std::thread::Builder::new()
+            .name("hyperlight-js-scheduler".to_string())
+            .spawn(move || worker.run())
+            .map_err(|e| new_error!("Failed to start the scheduler thread: {}", e))?;
+        Ok(SchedulerHandle {
+            shared,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("jobs", &self.jobs)
+            .field("reload", &self.reload.is_some())
+            .field("history_size", &self.history_size)
+            .finish()
+    }
+}
+
+/// A running [`Scheduler`]. Dropping the handle stops the scheduler.
+#[derive(Debug)]
+pub struct SchedulerHandle {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<LoadedJSSandbox>>,
+}
+
+impl SchedulerHandle {
+    /// The statistics on the runs of the job `job`, or `None` if there is no
+    /// such job.
+    pub fn stats(&self, job: &str) -> Option<JobStats> {
+        self.shared.lock().stats.get(job).cloned()
+    }
+
+    /// The last runs of all the jobs, oldest first.
+    pub fn history(&self) -> Vec<RunRecord> {
+        self.shared.lock().history.iter().cloned().collect()
+    }
+
+    /// Stop the scheduler, waiting for the current run to finish, and return
+    /// its sandbox.
+    pub fn stop(mut self) -> Result<LoadedJSSandbox> {
+        self.shutdown()
+            .ok_or_else(|| new_error!("The scheduler thread panicked"))
+    }
+
+    fn shutdown(&mut self) -> Option<LoadedJSSandbox> {
+        self.shared.lock().stopped = true;
+        self.shared.wake.notify_all();
+        self.thread.take()?.join().ok()
+    }
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// The state shared by a [`SchedulerHandle`] and its thread.
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+impl Shared {
+    // The statistics must stay available even if a thread panicked while
+    // holding the lock.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    stopped: bool,
+    stats: HashMap<String, JobStats>,
+    history: VecDeque<RunRecord>,
+}
+
+struct JobState {
+    job: ScheduledJob,
+    // When the next run is due, without jitter
+    next: Option<SystemTime>,
+    // The jitter of the next run
+    jitter: Duration,
+}
+
+impl JobState {
+    fn due(&self) -> Option<SystemTime> {
+        self.next.map(|next| next + self.jitter)
+    }
+}
+
+/// A random duration up to `max`.
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    // A randomly seeded hash is random enough to spread runs
+    let random = RandomState::new().hash_one(Instant::now());
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+/// The background thread of a [`Scheduler`].
+struct Worker {
+    sandbox: LoadedJSSandbox,
+    jobs: Vec<JobState>,
+    reload: Option<Box<Reload>>,
+    history_size: usize,
+    shared: Arc<Shared>,
+}
+
+impl Worker {
+    fn run(mut self) -> LoadedJSSandbox {
+        loop {
+            let Some(index) = self.next_job() else {
+                // No job will ever run again, wait to be stopped
+                let mut state = self.shared.lock();
+                while !state.stopped {
+                    state = self
+                        .shared
+                        .wake
+                        .wait(state)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+                return self.sandbox;
+            };
+            let Some(due) = self.jobs[index].due() else {
+                continue;
+            };
+
+            let mut state = self.shared.lock();
+            loop {
+                if state.stopped {
+                    return self.sandbox;
+                }
+                match due.duration_since(SystemTime::now()) {
+                    Ok(wait) if !wait.is_zero() => {
+                        state = self
+                            .shared
+                            .wake
+                            .wait_timeout(state, wait)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0;
+                    }
+                    _ => break,
+                }
+            }
+            drop(state);
+            self.run_job(index);
+        }
+    }
+
+    /// The index of the job due first.
+    fn next_job(&self) -> Option<usize> {
+        self.jobs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, job)| Some((index, job.due()?)))
+            .min_by_key(|(_, due)| *due)
+            .map(|(index, _)| index)
+    }
+
+    fn run_job(&mut self, index: usize) {
+        let job_state = &mut self.jobs[index];
+        let job = &job_state.job;
+        let Some(scheduled) = job_state.next else {
+            return;
+        };
+        let event = job.event.clone().unwrap_or_else(|| {
+            let scheduled_ms = scheduled
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            serde_json::json!({ "job": job.name, "scheduledTime": scheduled_ms }).to_string()
+        });
+
+        let started = SystemTime::now();
+        let start = Instant::now();
+        let result = (job.call)(&mut self.sandbox, &job.handler, event);
+        let duration = start.elapsed();
+        let outcome = match result {
+            Ok(_) => RunOutcome::Succeeded,
+            Err(e) if is_cancelled(&e) => RunOutcome::TimedOut,
+            Err(e) => {
+                tracing::warn!(job = %job.name, error = %e, "Scheduled run failed");
+                RunOutcome::Failed(e.to_string())
+            }
+        };
+        record_scheduled_run(&job.name, &outcome);
+
+        // Work out the next run, counting the runs that went by meanwhile
+        let now = SystemTime::now();
+        let mut next = job.schedule.next(scheduled, scheduled);
+        let mut skipped = 0;
+        while let Some(due) = next
+            && due <= now
+        {
+            match job.overlap_policy {
+                OverlapPolicy::Skip => {
+                    skipped += 1;
+                    next = job.schedule.next(due, due);
+                }
+                OverlapPolicy::Delay => {
+                    next = Some(now);
+                    break;
+                }
+            }
+        }
+        if skipped > 0 {
+            record_scheduled_run_skipped(&job.name, skipped);
+        }
+        job_state.next = next;
+        job_state.jitter = jitter(job.jitter);
+
+        let mut state = self.shared.lock();
+        let stats = state.stats.entry(job.name.clone()).or_default();
+        stats.runs += 1;
+        match outcome {
+            RunOutcome::Succeeded => stats.succeeded += 1,
+            RunOutcome::Failed(_) => stats.failed += 1,
+            RunOutcome::TimedOut => stats.timed_out += 1,
+        }
+        stats.skipped += skipped;
+        stats.total_duration += duration;
+        stats.last_run = Some(started);
+        stats.next_run = next;
+        if self.history_size > 0 {
+            if state.history.len() == self.history_size {
+                state.history.pop_front();
+            }
+            state.history.push_back(RunRecord {
+                job: job.name.clone(),
+                scheduled,
+                started,
+                duration,
+                outcome,
+            });
+        }
+        drop(state);
+
+        if self.sandbox.poisoned()
+            && let Some(reload) = &mut self.reload
+        {
+            match reload() {
+                Ok(sandbox) => self.sandbox = sandbox,
+                Err(e) => tracing::warn!(error = %e, "Failed to reload the poisoned sandbox"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{civil_from_days, days_from_civil, Schedule};
+
+    fn at(seconds: u64) -> std::time::SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        // 2024-02-29
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        for days in (0..40_000).step_by(37) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn cron_schedules_fire_at_matching_minutes() {
+        // 2024-01-01T00:00:00Z, a Monday
+        let monday = 1_704_067_200;
+
+        let every_15 = Schedule::cron("*/15 * * * *").unwrap();
+        assert_eq!(
+            every_15.next(at(monday), at(monday)),
+            Some(at(monday + 15 * 60))
+        );
+
+        let weekdays = Schedule::cron("30 2 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next(at(monday), at(monday)),
+            Some(at(monday + 2 * 3600 + 30 * 60))
+        );
+        // Friday 02:30 is followed by Monday 02:30
+        let friday = monday + 4 * 86_400 + 2 * 3600 + 30 * 60;
+        assert_eq!(
+            weekdays.next(at(friday), at(friday)),
+            Some(at(friday + 3 * 86_400))
+        );
+
+        let sundays = Schedule::cron("0 0 * * 7").unwrap();
+        assert_eq!(
+            sundays.next(at(monday), at(monday)),
+            Some(at(monday + 6 * 86_400))
+        );
+
+        let yearly = Schedule::cron("@yearly").unwrap();
+        assert_eq!(
+            yearly.next(at(monday), at(monday)),
+            Some(at(days_from_civil(2025, 1, 1) * 86_400))
+        );
+
+        assert_eq!(
+            Schedule::cron("0 0 30 2 *")
+                .unwrap()
+                .next(at(monday), at(monday)),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_cron_expressions_are_rejected() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+        ] {
+            assert!(Schedule::cron(expression).is_err(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn intervals_skip_the_missed_runs() {
+        let every_10 = Schedule::every(Duration::from_secs(10));
+        assert_eq!(every_10.next(at(0), at(0)), Some(at(10)));
+        assert_eq!(every_10.next(at(0), at(35)), Some(at(40)));
+    }
+}
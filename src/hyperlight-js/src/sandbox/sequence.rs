@@ -0,0 +1,98 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::sync::{Arc, Weak};
+
+use hyperlight_host::sandbox::snapshot::Snapshot;
+use hyperlight_host::{new_error, Result};
+
+/// Tracks the monotonically increasing sequence number stamped on each
+/// invocation of a sandbox, and the sequence each snapshot was taken at.
+///
+/// It is carried from a `JSSandbox` to its `LoadedJSSandbox` and back, so
+/// snapshots keep their sequence across unloading and reloading handlers.
+#[derive(Debug)]
+pub(crate) struct InvocationSequence {
+    // Sequence number of the most recent invocation, 0 if there has been none.
+    current: u64,
+    allow_rollback: bool,
+    // Holding a `Weak` keeps the allocation alive, so a pointer can never be
+    // reused by a different snapshot while it is in this list.
+    snapshots: Vec<(Weak<Snapshot>, u64)>,
+}
+
+impl InvocationSequence {
+    pub(crate) fn new(allow_rollback: bool) -> Self {
+        Self {
+            current: 0,
+            allow_rollback,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// The sequence number of the most recent invocation.
+    pub(crate) fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// Whether snapshots may roll the sequence backwards.
+    pub(crate) fn allow_rollback(&self) -> bool {
+        self.allow_rollback
+    }
+
+    /// Allocate the sequence number for a new invocation.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.current += 1;
+        self.current
+    }
+
+    /// Record that `snapshot` captures the state at the current sequence.
+    pub(crate) fn record_snapshot(&mut self, snapshot: &Arc<Snapshot>) {
+        self.snapshots
+            .retain(|(snapshot, _)| snapshot.strong_count() > 0);
+        self.snapshots
+            .push((Arc::downgrade(snapshot), self.current));
+    }
+
+    /// Check whether the sandbox may be restored to `snapshot`, returning the
+    /// sequence number the snapshot was taken at.
+    pub(crate) fn check_restore(&self, snapshot: &Arc<Snapshot>) -> Result<Option<u64>> {
+        let sequence = self
+            .snapshots
+            .iter()
+            .find(|(s, _)| std::ptr::eq(s.as_ptr(), Arc::as_ptr(snapshot)))
+            .map(|(_, sequence)| *sequence);
+
+        if self.allow_rollback {
+            return Ok(sequence);
+        }
+        match sequence {
+            Some(sequence) if sequence >= self.current => Ok(Some(sequence)),
+            Some(sequence) => Err(new_error!(
+                "Restoring the snapshot would roll the invocation sequence back from {} to {}, and rollback is not allowed",
+                self.current,
+                sequence
+            )),
+            None => Err(new_error!(
+                "The snapshot was not taken from this sandbox, so its invocation sequence is unknown and rollback is not allowed"
+            )),
+        }
+    }
+
+    /// Set the current sequence after restoring a snapshot taken at `sequence`.
+    pub(crate) fn restored(&mut self, sequence: u64) {
+        self.current = sequence;
+    }
+}
@@ -0,0 +1,82 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use std::fmt;
+use std::sync::Arc;
+
+use super::deterministic::DeterministicMode;
+use super::gc_policy::GcPolicy;
+use super::handler_limits::HandlerLimits;
+use super::host_function_allowlist::HostFunctionAllowlist;
+use super::metrics::SandboxLabels;
+use super::monitor::MonitorSet;
+use super::observer::SandboxObserver;
+use super::wire_compression::WireCompression;
+
+/// The settings of a sandbox chosen with the `SandboxBuilder`.
+///
+/// They are carried from a `JSSandbox` to its `LoadedJSSandbox` and back, so
+/// they still apply after unloading the handlers.
+#[derive(Clone, Default)]
+pub(crate) struct SandboxSettings {
+    pub(crate) handler_limits: HandlerLimits,
+    pub(crate) wire_compression: WireCompression,
+    /// The monitors guarding `handle_event` calls that don't pass their own.
+    pub(crate) default_monitors: Option<Arc<dyn MonitorSet>>,
+    /// Whether to restore the loaded state after a call poisons the sandbox.
+    pub(crate) auto_recover: bool,
+    /// When to collect garbage after calls that don't pass a `gc` flag.
+    pub(crate) gc_policy: GcPolicy,
+    /// The built-in host functions the guest may call.
+    pub(crate) host_function_allowlist: HostFunctionAllowlist,
+    /// The seed and virtual clock of the sandbox, in deterministic mode.
+    pub(crate) deterministic: Option<DeterministicMode>,
+    /// Whether to compare the heap before and after failed invocations.
+    pub(crate) heap_reports: bool,
+    /// The callbacks on the lifecycle events of the sandbox.
+    pub(crate) observer: Option<Arc<dyn SandboxObserver>>,
+    /// The labels of the metrics and spans of the sandbox.
+    pub(crate) labels: SandboxLabels,
+    /// The size of the input buffer of the guest, which bounds the events,
+    /// if it is not the default size.
+    pub(crate) input_buffer_size: Option<usize>,
+}
+
+impl SandboxSettings {
+    /// Call `f` with the observer of the sandbox, if it has one.
+    pub(crate) fn observe(&self, f: impl FnOnce(&dyn SandboxObserver)) {
+        if let Some(observer) = &self.observer {
+            f(observer.as_ref());
+        }
+    }
+}
+
+impl fmt::Debug for SandboxSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SandboxSettings")
+            .field("handler_limits", &self.handler_limits)
+            .field("wire_compression", &self.wire_compression)
+            .field("default_monitors", &self.default_monitors.is_some())
+            .field("auto_recover", &self.auto_recover)
+            .field("gc_policy", &self.gc_policy)
+            .field("host_function_allowlist", &self.host_function_allowlist)
+            .field("deterministic", &self.deterministic)
+            .field("heap_reports", &self.heap_reports)
+            .field("observer", &self.observer.is_some())
+            .field("labels", &self.labels)
+            .field("input_buffer_size", &self.input_buffer_size)
+            .finish()
+    }
+}
@@ -0,0 +1,148 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Propagation of the W3C trace context of handler invocations.
+//!
+//! With the `opentelemetry` feature, every invocation is made with the
+//! [`traceparent`](https://www.w3.org/TR/trace-context/#traceparent-header)
+//! of its `handle_event` span, when that span is traced by a
+//! `tracing-opentelemetry` layer. The handler gets it as `context.traceparent`
+//! and `globalThis.__traceparent`, and the host functions it calls through
+//! [`current_traceparent`] and [`HostCall::traceparent`](crate::HostCall::traceparent).
+
+use std::cell::RefCell;
+
+use tracing::Span;
+
+thread_local! {
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The W3C `traceparent` of the handler invocation running on the current
+/// thread, if it is traced, so host functions can propagate it to downstream
+/// services.
+///
+/// This is only ever set with the `opentelemetry` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{current_traceparent, SandboxBuilder};
+///
+/// let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+/// proto_js_sandbox
+///     .register("http", "traceparent", || current_traceparent().unwrap_or_default())
+///     .unwrap();
+/// ```
+pub fn current_traceparent() -> Option<String> {
+    CURRENT.with_borrow(Clone::clone)
+}
+
+/// Make `traceparent` the trace context of the current thread until the
+/// returned guard is dropped.
+pub(crate) fn enter(traceparent: Option<String>) -> TraceparentGuard {
+    TraceparentGuard(CURRENT.replace(traceparent))
+}
+
+/// Guard restoring the previous trace context of the thread when dropped.
+pub(crate) struct TraceparentGuard(Option<String>);
+
+impl Drop for TraceparentGuard {
+    fn drop(&mut self) {
+        CURRENT.set(self.0.take());
+    }
+}
+
+/// The `traceparent` of `span`, if it is traced by OpenTelemetry.
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn span_traceparent(span: &Span) -> Option<String> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = span.context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return None;
+    }
+    Some(format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    ))
+}
+
+/// The `traceparent` of `span`, which is never traced without the
+/// `opentelemetry` feature.
+#[cfg(not(feature = "opentelemetry"))]
+pub(crate) fn span_traceparent(_span: &Span) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter() {
+        assert_eq!(current_traceparent(), None);
+
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let guard = enter(Some(traceparent.to_string()));
+        assert_eq!(current_traceparent().as_deref(), Some(traceparent));
+
+        let nested = enter(None);
+        assert_eq!(current_traceparent(), None);
+        drop(nested);
+        assert_eq!(current_traceparent().as_deref(), Some(traceparent));
+
+        drop(guard);
+        assert_eq!(current_traceparent(), None);
+    }
+
+    #[test]
+    fn test_untraced_span() {
+        assert_eq!(span_traceparent(&Span::none()), None);
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn test_span_traceparent() {
+        use opentelemetry::trace::TracerProvider;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_opentelemetry::OpenTelemetryLayer::new(provider.tracer("test")),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handle_event");
+            let traceparent = span_traceparent(&span).unwrap();
+            let parts: Vec<&str> = traceparent.split('-').collect();
+            assert_eq!(parts.len(), 4, "{traceparent}");
+            assert_eq!(parts[0], "00");
+            assert_eq!(parts[1].len(), 32);
+            assert_eq!(parts[2].len(), 16);
+            assert_eq!(parts[3], "01");
+
+            // Child spans are in the same trace
+            let child = span.in_scope(|| tracing::info_span!("child"));
+            let child = span_traceparent(&child).unwrap();
+            assert!(child.starts_with(&format!("00-{}-", parts[1])), "{child}");
+            assert_ne!(child, traceparent);
+        });
+    }
+}
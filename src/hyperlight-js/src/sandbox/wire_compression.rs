@@ -0,0 +1,128 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+use hyperlight_host::{new_error, Result};
+use hyperlight_js_runtime::wire;
+
+/// Compression of the events and results exchanged between the host and the guest.
+///
+/// Events and results cross the boundary through the input and output buffers
+/// of the sandbox, which must be large enough to hold them. Compressing large
+/// payloads trades a little CPU for much smaller buffers, since JSON usually
+/// compresses well.
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::{SandboxBuilder, WireCompression};
+///
+/// let proto_js_sandbox = SandboxBuilder::new()
+///     .with_wire_compression(WireCompression::Lz4 { min_size: 4096 })
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WireCompression {
+    /// Payloads are exchanged as is.
+    #[default]
+    None,
+    /// Payloads of at least `min_size` bytes are compressed with LZ4, when
+    /// that makes them smaller.
+    Lz4 {
+        /// The size, in bytes, from which payloads are compressed.
+        min_size: usize,
+    },
+}
+
+impl WireCompression {
+    /// The minimum size of the payloads to compress, if compression is enabled.
+    pub(crate) fn min_size(&self) -> Option<usize> {
+        match self {
+            WireCompression::None => None,
+            WireCompression::Lz4 { min_size } => Some(*min_size),
+        }
+    }
+}
+
+/// Frame the `event` to send to the guest, compressing it if it is at least `min_size` bytes long.
+pub(crate) fn encode_event(event: &str, min_size: usize) -> Vec<u8> {
+    wire::encode(event.as_bytes(), min_size)
+}
+
+/// Get the result of a handler from the `frame` returned by the guest.
+pub(crate) fn decode_result(frame: &[u8]) -> Result<String> {
+    let result = wire::decode(frame).map_err(|e| new_error!("Invalid result frame: {:#}", e))?;
+    String::from_utf8(result).map_err(|e| new_error!("The result is not valid UTF-8: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let small = r#"{"a":1}"#;
+        let large = serde_json::to_string(
+            &(0..1000)
+                .map(|i| serde_json::json!({ "id": i, "name": "item", "tags": ["a", "b"] }))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        for payload in [small, "", &large, &"x".repeat(100_000)] {
+            let frame = encode_event(payload, 64);
+            assert_eq!(decode_result(&frame).unwrap(), payload);
+        }
+
+        // Small payloads are sent as is
+        assert_eq!(encode_event(small, 64)[0], wire::RAW);
+        // Large ones are compressed
+        let frame = encode_event(&large, 64);
+        assert_eq!(frame[0], wire::LZ4);
+        assert!(frame.len() < large.len() / 4, "{} bytes", frame.len());
+    }
+
+    #[test]
+    fn test_round_trip_incompressible() {
+        // A xorshift generator, so the payload has no repetitions to compress
+        let mut state = 0x2545f4914f6cdd1du64;
+        let payload: String = (0..10_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                char::from(b'!' + (state % 90) as u8)
+            })
+            .collect();
+
+        let frame = encode_event(&payload, 0);
+        assert_eq!(frame[0], wire::RAW);
+        assert_eq!(decode_result(&frame).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_decode_rejects_corrupt_frames() {
+        let frame = encode_event(&"abcd".repeat(1000), 0);
+        assert!(decode_result(&frame[..frame.len() - 3]).is_err());
+        assert!(decode_result(&[]).is_err());
+        assert!(decode_result(&[7, 1, 2]).is_err());
+
+        // The declared length does not match the block
+        let mut lying = frame.clone();
+        lying[1..5].copy_from_slice(&100u32.to_le_bytes());
+        assert!(decode_result(&lying).is_err());
+    }
+}
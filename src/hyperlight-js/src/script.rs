@@ -14,9 +14,27 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
-use crate::{new_error, Result};
+use sha2::{Digest, Sha256};
+
+use crate::source_map::SourceMap;
+use crate::{new_error, FileSystemMemory, Result};
+
+/// The files [`Script::from_directory`] looks for, in order.
+const DIRECTORY_ENTRY_POINTS: [&str; 2] = ["handler.js", "index.js"];
+
+/// The kind of handler a [`Script`] defines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScriptKind {
+    /// A JavaScript module defining a `handler` function.
+    #[default]
+    JavaScript,
+    /// A [JSON-logic](https://jsonlogic.com) rule evaluated against the event by the guest runtime,
+    /// without running any JavaScript.
+    JsonLogic,
+}
 
 /// Represents a JavaScript immutable handler script with metadata about its source location.
 /// The source location metadata is required to resolve relative locations when the script imports
@@ -27,6 +45,14 @@ pub struct Script {
     content: Arc<str>,
     /// base path for resolving module imports
     base_path: Option<PathBuf>,
+    /// The kind of handler the content defines
+    kind: ScriptKind,
+    /// Source map of the content, used to remap locations in errors
+    source_map: Option<Arc<SourceMap>>,
+    /// The file the content was read from, if any
+    source_path: Option<PathBuf>,
+    /// SHA-256 digest of the content, computed on first use
+    content_hash: OnceLock<Arc<str>>,
 }
 
 impl Script {
@@ -36,42 +62,311 @@ impl Script {
         Self {
             content: Arc::from(content.into()),
             base_path: None,
+            kind: ScriptKind::JavaScript,
+            source_map: None,
+            source_path: None,
+            content_hash: OnceLock::new(),
         }
     }
 
+    /// Create a handler from a [JSON-logic](https://jsonlogic.com) rule.
+    ///
+    /// The rule is evaluated natively by the guest runtime with the event as its data, and the
+    /// handler returns the result of the rule. No JavaScript is run, which makes this both faster
+    /// and lower risk than a JavaScript handler for simple transforms.
+    ///
+    /// Returns an error if `rule` is not valid JSON.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::Script;
+    ///
+    /// let script = Script::from_jsonlogic(r#"{ "if": [{ ">": [{ "var": "temp" }, 30] }, "hot", "cold"] }"#)?;
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    pub fn from_jsonlogic(rule: impl Into<String>) -> Result<Self> {
+        let rule = rule.into();
+        serde_json::from_str::<serde_json::Value>(&rule)
+            .map_err(|e| new_error!("Invalid JSON-logic rule: {}", e))?;
+        Ok(Self {
+            content: Arc::from(rule),
+            base_path: None,
+            kind: ScriptKind::JsonLogic,
+            source_map: None,
+            source_path: None,
+            content_hash: OnceLock::new(),
+        })
+    }
+
+    /// Create a handler script from TypeScript source.
+    ///
+    /// Types are stripped on the host, and the resulting JavaScript is what runs in the guest.
+    /// Returns an error if `content` is not valid TypeScript.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::Script;
+    ///
+    /// let script = Script::from_typescript(
+    ///     "export function handler(event: { name: string }): string { return event.name; }",
+    /// )?;
+    /// assert!(!script.content().contains(": string"));
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    #[cfg(feature = "typescript")]
+    pub fn from_typescript(content: impl Into<String>) -> Result<Self> {
+        Self::from_content(content)
+            .transform(|source| crate::typescript::transpile(source, "handler.ts"))
+    }
+
     /// Create a script by reading from a file
     ///
-    /// The base path is automatically set to the directory containing the file
+    /// The base path is automatically set to the directory containing the file.
+    /// When the `typescript` feature is enabled, `.ts` and `.mts` files are transpiled to JavaScript.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
 
         let content = std::fs::read_to_string(path)
             .map_err(|e| new_error!("Failed to read script from '{}': {}", path.display(), e))?;
 
+        #[cfg(feature = "typescript")]
+        let content = {
+            let path = path.to_string_lossy();
+            if crate::typescript::is_typescript_path(&path) {
+                crate::typescript::transpile(&content, &path)?
+            } else {
+                content
+            }
+        };
+
         let base_path = path.parent().map(|p| p.to_path_buf());
         Ok(Self {
             content: Arc::from(content),
             base_path,
+            kind: ScriptKind::JavaScript,
+            source_map: None,
+            source_path: Some(path.to_path_buf()),
+            content_hash: OnceLock::new(),
         })
     }
 
+    /// Create a script from the entry point of a directory.
+    ///
+    /// The entry point is `handler.js` or, if there is none, `index.js`. The base path is set to
+    /// the virtual root `/`, so relative imports in the handler resolve against the files of the
+    /// directory when it is installed as the module loader's file system, e.g., with
+    /// [`embed_dir!`](crate::embed_dir). Use [`Script::from_directory_with_modules`] to also
+    /// load the files of the directory at runtime.
+    pub fn from_directory(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entry = DIRECTORY_ENTRY_POINTS
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                new_error!(
+                    "No {} found in '{}'",
+                    DIRECTORY_ENTRY_POINTS.join(" or "),
+                    dir.display()
+                )
+            })?;
+
+        Ok(Self::from_file(entry)?.with_virtual_base("/"))
+    }
+
+    /// Create a script from the entry point of a directory, inserting every file of the
+    /// directory into `modules`.
+    ///
+    /// This is [`Script::from_directory`], plus registering the files (including those in
+    /// subdirectories) under their path relative to `dir`, so the handler can import its sibling
+    /// modules once `modules` is installed with
+    /// [`ProtoJSSandbox::set_module_loader`](crate::ProtoJSSandbox::set_module_loader).
+    /// Hidden files and directories (starting with `.`) are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use hyperlight_js::{FileSystemMemory, SandboxBuilder, Script};
+    ///
+    /// let fs = FileSystemMemory::new();
+    /// let handler = Script::from_directory_with_modules("handlers/greeter", &fs)?;
+    ///
+    /// let mut sandbox = SandboxBuilder::new()
+    ///     .build()?
+    ///     .set_module_loader(fs)?
+    ///     .load_runtime()?;
+    /// sandbox.add_handler("greeter", handler)?;
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    pub fn from_directory_with_modules(
+        dir: impl AsRef<Path>,
+        modules: &FileSystemMemory,
+    ) -> Result<Self> {
+        let dir = dir.as_ref();
+        let script = Self::from_directory(dir)?;
+        insert_directory(dir, "", modules)?;
+        Ok(script)
+    }
+
     /// Set a virtual base path for module resolution.
     pub fn with_virtual_base(mut self, path: impl AsRef<str>) -> Self {
         self.base_path = Some(PathBuf::from(path.as_ref()));
         self
     }
 
+    /// Attach a [source map](https://tc39.es/ecma426/) to the script.
+    ///
+    /// Use this when the script was generated by a bundler or a compiler. When the handler throws,
+    /// the file, line and column locations of the script in the error are remapped to the original
+    /// sources before the error is returned.
+    ///
+    /// Returns an error if `source_map` is not a valid version 3 source map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::Script;
+    ///
+    /// let script = Script::from_content("function handler(event) { return event; }")
+    ///     .with_source_map(r#"{"version":3,"sources":["handler.ts"],"names":[],"mappings":"AAAA"}"#)?;
+    /// assert!(script.has_source_map());
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    pub fn with_source_map(mut self, source_map: impl AsRef<str>) -> Result<Self> {
+        self.source_map = Some(Arc::new(SourceMap::parse(source_map.as_ref())?));
+        Ok(self)
+    }
+
+    /// Transform the content of the script, keeping its base path and kind.
+    ///
+    /// This is a hook to compile handlers written in other languages (or
+    /// using syntax the guest does not support) to JavaScript before they are
+    /// added to a sandbox.
+    /// Any source map attached to the script is dropped, as it does not describe the
+    /// transformed content.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use hyperlight_js::Script;
+    ///
+    /// let script = Script::from_content("function handler(event) { return __VERSION__; }")
+    ///     .with_virtual_base("/handlers")
+    ///     .transform(|source| Ok(source.replace("__VERSION__", "\"1.2.3\"")))?;
+    /// assert_eq!(script.content(), r#"function handler(event) { return "1.2.3"; }"#);
+    /// # Ok::<(), hyperlight_js::HyperlightError>(())
+    /// ```
+    pub fn transform<F>(self, transform: F) -> Result<Self>
+    where
+        F: FnOnce(&str) -> Result<String>,
+    {
+        let content = transform(&self.content)?;
+        Ok(Self {
+            content: Arc::from(content),
+            source_map: None,
+            content_hash: OnceLock::new(),
+            ..self
+        })
+    }
+
     /// Get the script content
     pub fn content(&self) -> &str {
         &self.content
     }
 
+    /// Get the hex encoded SHA-256 digest of the script content.
+    ///
+    /// This identifies the exact version of the handler code, and is recorded
+    /// in the tracing spans of the handler along with its source path and size.
+    pub fn content_hash(&self) -> &str {
+        self.content_hash
+            .get_or_init(|| Arc::from(format!("{:x}", Sha256::digest(self.content.as_bytes()))))
+    }
+
+    /// Lint the script, returning warnings about code that is likely to fail
+    /// or misbehave in the guest: use of `eval`, undeclared variables, APIs of
+    /// other runtimes such as `fetch`, and very large literals.
+    ///
+    /// Locations refer to [`content`](Self::content), i.e. to the transpiled
+    /// JavaScript for TypeScript scripts. JSON-logic rules are not linted.
+    #[cfg(feature = "lint")]
+    pub fn lint(&self) -> Vec<crate::LintDiagnostic> {
+        match self.kind {
+            ScriptKind::JavaScript => crate::lint::lint(&self.content),
+            ScriptKind::JsonLogic => Vec::new(),
+        }
+    }
+
+    /// Get the path of the file the script was read from, if any
+    pub fn source_path(&self) -> Option<&Path> {
+        self.source_path.as_deref()
+    }
+
+    /// Get the kind of handler the script defines
+    pub fn kind(&self) -> ScriptKind {
+        self.kind
+    }
+
+    /// Whether a source map is attached to the script
+    pub fn has_source_map(&self) -> bool {
+        self.source_map.is_some()
+    }
+
+    pub(crate) fn source_map(&self) -> Option<&Arc<SourceMap>> {
+        self.source_map.as_ref()
+    }
+
+    /// Record the provenance of the script in the `script_sha256`, `script_path`
+    /// and `script_bytes` fields of `span`.
+    pub(crate) fn record_provenance(&self, span: &tracing::Span) {
+        if span.is_disabled() {
+            return;
+        }
+        span.record("script_sha256", self.content_hash());
+        span.record("script_bytes", self.content.len());
+        if let Some(path) = &self.source_path {
+            span.record("script_path", tracing::field::display(path.display()));
+        }
+    }
+
     /// Get the base path for module resolution, if any
     pub fn base_path(&self) -> Option<&Path> {
         self.base_path.as_deref()
     }
 }
 
+/// Insert the files in `dir` into `modules`, keyed by their path prefixed with `prefix`.
+fn insert_directory(dir: &Path, prefix: &str, modules: &FileSystemMemory) -> Result<()> {
+    let read_error = |e| new_error!("Failed to read directory '{}': {}", dir.display(), e);
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            return Err(new_error!(
+                "Invalid UTF-8 in path '{}'",
+                entry.path().display()
+            ));
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        let key = format!("{prefix}{name}");
+        if entry.file_type().map_err(read_error)?.is_dir() {
+            insert_directory(&path, &format!("{key}/"), modules)?;
+        } else {
+            let source = std::fs::read_to_string(&path).map_err(|e| {
+                new_error!("Failed to read module from '{}': {}", path.display(), e)
+            })?;
+            modules.insert(key, source);
+        }
+    }
+    Ok(())
+}
+
 impl From<String> for Script {
     fn from(content: String) -> Self {
         Self::from_content(content)
@@ -90,3 +385,81 @@ impl TryFrom<&Path> for Script {
         Self::from_file(path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::FileSystem;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_provenance() {
+        let script = Script::from_content("abc");
+        assert_eq!(
+            script.content_hash(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(script.source_path(), None);
+
+        let transformed = script.transform(|_| Ok(String::new())).unwrap();
+        assert_eq!(
+            transformed.content_hash(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("handler.js");
+        write(&path, "abc");
+        assert_eq!(
+            Script::from_file(&path).unwrap().source_path(),
+            Some(path.as_path())
+        );
+    }
+
+    #[test]
+    fn test_from_directory_prefers_handler_js() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("index.js"), "index");
+        assert_eq!(
+            Script::from_directory(dir.path()).unwrap().content(),
+            "index"
+        );
+
+        write(&dir.path().join("handler.js"), "handler");
+        let script = Script::from_directory(dir.path()).unwrap();
+        assert_eq!(script.content(), "handler");
+        assert_eq!(script.base_path(), Some(Path::new("/")));
+    }
+
+    #[test]
+    fn test_from_directory_without_entry_point() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("main.js"), "main");
+        let err = Script::from_directory(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("No handler.js or index.js found"));
+    }
+
+    #[test]
+    fn test_from_directory_with_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("handler.js"), "handler");
+        write(&dir.path().join("lib/math.js"), "math");
+        write(&dir.path().join(".hidden/secret.js"), "secret");
+
+        let fs = FileSystemMemory::new();
+        Script::from_directory_with_modules(dir.path(), &fs).unwrap();
+
+        assert_eq!(fs.len(), 2);
+        assert!(fs.contains("handler.js"));
+        assert_eq!(
+            fs.read_to_string(Path::new("/lib/math.js")).unwrap(),
+            "math"
+        );
+    }
+}
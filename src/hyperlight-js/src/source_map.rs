@@ -0,0 +1,274 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Decoding of [source maps](https://tc39.es/ecma426/) and remapping of the locations in guest
+//! stack traces from generated code back to the original sources.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use hyperlight_host::HyperlightError;
+use serde::Deserialize;
+
+use crate::{new_error, Result};
+
+/// The source maps of the handler modules loaded in a sandbox, keyed by module path.
+pub(crate) type SourceMaps = HashMap<String, Arc<SourceMap>>;
+
+/// A decoded version 3 source map.
+#[derive(Debug)]
+pub(crate) struct SourceMap {
+    sources: Vec<String>,
+    // The mappings of each generated line, sorted by generated column.
+    lines: Vec<Vec<Mapping>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    generated_column: u32,
+    source: u32,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<Option<String>>,
+    mappings: String,
+}
+
+impl SourceMap {
+    /// Parse a JSON encoded source map.
+    pub(crate) fn parse(json: &str) -> Result<Self> {
+        let raw: RawSourceMap =
+            serde_json::from_str(json).map_err(|e| new_error!("Invalid source map: {}", e))?;
+        if raw.version != 3 {
+            return Err(new_error!(
+                "Unsupported source map version: {}",
+                raw.version
+            ));
+        }
+
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                if root.is_empty() || root.ends_with('/') {
+                    format!("{root}{source}")
+                } else {
+                    format!("{root}/{source}")
+                }
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+        for encoded_line in raw.mappings.split(';') {
+            let mut mappings = Vec::new();
+            let mut generated_column = 0i64;
+            for segment in encoded_line.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                generated_column += fields[0];
+                // Segments with a single field do not map to any source.
+                if fields.len() < 4 {
+                    continue;
+                }
+                source += fields[1];
+                line += fields[2];
+                column += fields[3];
+                mappings.push(Mapping {
+                    generated_column: to_u32(generated_column)?,
+                    source: to_u32(source)?,
+                    line: to_u32(line)?,
+                    column: to_u32(column)?,
+                });
+            }
+            mappings.sort_by_key(|m| m.generated_column);
+            lines.push(mappings);
+        }
+
+        Ok(Self { sources, lines })
+    }
+
+    /// Find the original location of a generated location.
+    /// Lines and columns are zero-based, as in the source map itself.
+    fn lookup(&self, line: u32, column: u32) -> Option<(&str, u32, u32)> {
+        let mappings = self.lines.get(line as usize)?;
+        let index = mappings.partition_point(|m| m.generated_column <= column);
+        // Locations before the first mapping of the line are attributed to that mapping.
+        let mapping = mappings.get(index.saturating_sub(1))?;
+        let source = self.sources.get(mapping.source as usize)?;
+        Some((source, mapping.line, mapping.column))
+    }
+}
+
+fn to_u32(value: i64) -> Result<u32> {
+    u32::try_from(value).map_err(|_| new_error!("Invalid source map: mapping out of range"))
+}
+
+/// Decode a segment of base64 VLQ encoded values.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let mut value = 0i64;
+    let mut shift = 0;
+    for c in segment.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(new_error!("Invalid source map: bad mapping character")),
+        } as i64;
+        if shift > 32 {
+            return Err(new_error!("Invalid source map: mapping out of range"));
+        }
+        value += (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+        let magnitude = value >> 1;
+        values.push(if value & 1 == 1 {
+            -magnitude
+        } else {
+            magnitude
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 || !matches!(values.len(), 1 | 4 | 5) {
+        return Err(new_error!("Invalid source map: malformed mapping segment"));
+    }
+    Ok(values)
+}
+
+/// Rewrite the `path:line:column` locations of the source-mapped modules in a guest error to
+/// point at the original sources.
+pub(crate) fn remap_error(err: HyperlightError, source_maps: &SourceMaps) -> HyperlightError {
+    match err {
+        HyperlightError::GuestError(code, message) if !source_maps.is_empty() => {
+            HyperlightError::GuestError(code, remap_locations(&message, source_maps))
+        }
+        err => err,
+    }
+}
+
+fn remap_locations(message: &str, source_maps: &SourceMaps) -> String {
+    let mut remapped = message.to_string();
+    for (path, source_map) in source_maps {
+        let prefix = format!("{path}:");
+        let mut output = String::with_capacity(remapped.len());
+        let mut rest = remapped.as_str();
+        while let Some(start) = rest.find(&prefix) {
+            let (before, location) = rest.split_at(start);
+            output.push_str(before);
+            // Only match whole paths, not a suffix of a longer path.
+            let whole_path = before
+                .chars()
+                .next_back()
+                .is_none_or(|c| c.is_whitespace() || c == '(');
+            let parsed = whole_path
+                .then(|| parse_location(&location[prefix.len()..]))
+                .flatten();
+            let Some((line, column, len)) = parsed else {
+                output.push_str(&prefix);
+                rest = &location[prefix.len()..];
+                continue;
+            };
+            // Stack traces use one-based lines and columns.
+            match source_map.lookup(line.saturating_sub(1), column.saturating_sub(1)) {
+                Some((source, line, column)) => {
+                    output.push_str(&format!("{source}:{}:{}", line + 1, column + 1));
+                }
+                None => output.push_str(&location[..prefix.len() + len]),
+            }
+            rest = &location[prefix.len() + len..];
+        }
+        output.push_str(rest);
+        remapped = output;
+    }
+    remapped
+}
+
+/// Parse a `line:column` location, returning the line, the column, and the length of the location.
+fn parse_location(text: &str) -> Option<(u32, u32, usize)> {
+    let line_len = text.find(|c: char| !c.is_ascii_digit())?;
+    let line = text[..line_len].parse().ok()?;
+    let text = text[line_len..].strip_prefix(':')?;
+    let column_len = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let column = text[..column_len].parse().ok()?;
+    Some((line, column, line_len + 1 + column_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperlight_common::flatbuffer_wrappers::guest_error::ErrorCode;
+
+    use super::*;
+
+    // Generated from `original.ts`:
+    //   line 0: `function handler(event) {` maps to line 0
+    //   line 1: `  throw new Error("boom");` maps to line 2, column 4
+    const SOURCE_MAP: &str = r#"{
+        "version": 3,
+        "sources": ["original.ts"],
+        "names": [],
+        "mappings": "AAAA;EAEI"
+    }"#;
+
+    #[test]
+    fn test_lookup() {
+        let source_map = SourceMap::parse(SOURCE_MAP).unwrap();
+        assert_eq!(source_map.lookup(0, 0), Some(("original.ts", 0, 0)));
+        assert_eq!(source_map.lookup(1, 2), Some(("original.ts", 2, 4)));
+        assert_eq!(source_map.lookup(1, 10), Some(("original.ts", 2, 4)));
+        assert_eq!(source_map.lookup(5, 0), None);
+    }
+
+    #[test]
+    fn test_remap_error() {
+        let source_maps = SourceMaps::from([(
+            "/handler.js".to_string(),
+            Arc::new(SourceMap::parse(SOURCE_MAP).unwrap()),
+        )]);
+        let err = HyperlightError::GuestError(
+            ErrorCode::GuestError,
+            r#"stack: "    at handler (/handler.js:2:3)\n    at /other/handler.js:2:3\n""#
+                .to_string(),
+        );
+        let HyperlightError::GuestError(_, message) = remap_error(err, &source_maps) else {
+            panic!("expected a guest error");
+        };
+        assert_eq!(
+            message,
+            r#"stack: "    at handler (original.ts:3:5)\n    at /other/handler.js:2:3\n""#
+        );
+    }
+
+    #[test]
+    fn test_invalid_source_map() {
+        assert!(SourceMap::parse("{}").is_err());
+        assert!(SourceMap::parse(r#"{"version":3,"sources":[],"mappings":"!"}"#).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Helpers to unit test handlers and their host functions without a
+//! hypervisor, enabled by the `testing` feature.
+//!
+//! A [`MockSandbox`] runs the same JavaScript runtime as a sandbox, but in the
+//! test process rather than in a micro VM, so it works in plain `cargo test`
+//! on CI machines without hypervisor privileges. It provides **no isolation**
+//! and must not be used to run untrusted code.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::sandbox::host_fn::Function;
+use crate::sandbox::js_sandbox_error::no_handlers;
+use crate::{
+    new_error, JSSandbox, LoadedJSSandbox, ProtoJSSandbox, Result, SandboxBuilder, Script,
+};
+
+/// A call from a handler to a host function, recorded by a [`MockSandbox`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedHostCall {
+    /// The name of the host module of the function.
+    pub module: String,
+    /// The name of the function.
+    pub function: String,
+    /// The JSON serialized arguments of the call.
+    pub args: String,
+    /// The JSON serialized result of the call, or the message of its error.
+    pub result: std::result::Result<String, String>,
+}
+
+enum State {
+    Proto(ProtoJSSandbox),
+    Unloaded(JSSandbox),
+    Loaded(LoadedJSSandbox),
+}
+
+/// A stand-in for a sandbox to unit test handlers, running the JavaScript
+/// runtime in-process, see the [module documentation](self).
+///
+/// Host functions are registered first, then handlers are added and called
+/// in any order; the handlers are reloaded as needed. Calls to the host
+/// functions are recorded, see [`host_calls`](Self::host_calls).
+///
+/// # Example
+///
+/// ```no_run
+/// use hyperlight_js::testing::MockSandbox;
+/// use hyperlight_js::Script;
+///
+/// let mut sandbox = MockSandbox::new()?;
+/// sandbox.register("math", "add", |a: i32, b: i32| a + b)?;
+/// sandbox.add_handler(
+///     "handler",
+///     Script::from_content(
+///         r#"import { add } from "math";
+///         function handler(event) { return { sum: add(event.a, event.b) }; }"#,
+///     ),
+/// )?;
+///
+/// let result = sandbox.handle_event("handler", r#"{"a": 1, "b": 2}"#)?;
+/// assert_eq!(result, r#"{"sum":3}"#);
+/// assert_eq!(sandbox.host_calls()[0].args, "[1,2]");
+/// # Ok::<(), hyperlight_js::HyperlightError>(())
+/// ```
+pub struct MockSandbox {
+    // Only `None` while changing state, or after a failed change
+    state: Option<State>,
+    handlers: Vec<(String, Script)>,
+    host_calls: Arc<Mutex<Vec<RecordedHostCall>>>,
+}
+
+impl MockSandbox {
+    /// Create a mock sandbox with the default configuration.
+    pub fn new() -> Result<Self> {
+        Self::from_builder(SandboxBuilder::new())
+    }
+
+    /// Create a mock sandbox configured by `builder`, to test handlers with
+    /// the same settings as in production. Heap and stack sizes are ignored.
+    pub fn from_builder(builder: SandboxBuilder) -> Result<Self> {
+        let host_calls = Arc::new(Mutex::new(Vec::new()));
+        let recorder = host_calls.clone();
+        let proto =
+            builder
+                .build_in_process()?
+                .with_host_call_interceptor(move |call, args, next| {
+                    let result = next(args.clone());
+                    let recorded = RecordedHostCall {
+                        module: call.module.to_string(),
+                        function: call.function.to_string(),
+                        args,
+                        result: match &result {
+                            Ok(result) => Ok(result.clone()),
+                            Err(e) => Err(e.to_string()),
+                        },
+                    };
+                    recorder
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(recorded);
+                    result
+                });
+        Ok(Self {
+            state: Some(State::Proto(proto)),
+            handlers: Vec::new(),
+            host_calls,
+        })
+    }
+
+    /// Register a host function, like [`ProtoJSSandbox::register`].
+    ///
+    /// Host functions have to be registered before the first handler is
+    /// added.
+    pub fn register<Output: Serialize, Args: DeserializeOwned>(
+        &mut self,
+        module: impl Into<String> + Debug,
+        name: impl Into<String> + Debug,
+        func: impl Function<Output, Args> + Send + Sync + 'static,
+    ) -> Result<()> {
+        match &mut self.state {
+            Some(State::Proto(proto)) => proto.register(module, name, func),
+            _ => Err(new_error!(
+                "Host functions have to be registered before the first handler is added"
+            )),
+        }
+    }
+
+    /// Register a raw host function, like [`ProtoJSSandbox::register_raw`].
+    ///
+    /// Host functions have to be registered before the first handler is
+    /// added.
+    pub fn register_raw(
+        &mut self,
+        module: impl Into<String> + Debug,
+        name: impl Into<String> + Debug,
+        func: impl Fn(String) -> Result<String> + Send + Sync + 'static,
+    ) -> Result<()> {
+        match &mut self.state {
+            Some(State::Proto(proto)) => proto.register_raw(module, name, func),
+            _ => Err(new_error!(
+                "Host functions have to be registered before the first handler is added"
+            )),
+        }
+    }
+
+    /// Add a handler, like [`JSSandbox::add_handler`], unloading the handlers
+    /// already added if they are loaded.
+    pub fn add_handler(&mut self, function_name: impl Into<String>, script: Script) -> Result<()> {
+        let function_name = function_name.into();
+        let mut sandbox = match self.take_state()? {
+            State::Proto(proto) => proto.load_runtime()?,
+            State::Unloaded(sandbox) => sandbox,
+            State::Loaded(loaded) => {
+                let mut sandbox = loaded.unload()?;
+                for (name, script) in &self.handlers {
+                    sandbox.add_handler(name.as_str(), script.clone())?;
+                }
+                sandbox
+            }
+        };
+        let added = sandbox.add_handler(function_name.as_str(), script.clone());
+        self.state = Some(State::Unloaded(sandbox));
+        added?;
+        self.handlers.push((function_name, script));
+        Ok(())
+    }
+
+    /// Call the handler `function_name` with the JSON serialized `event`, like
+    /// [`LoadedJSSandbox::handle_event`], loading the handlers if needed.
+    pub fn handle_event(
+        &mut self,
+        function_name: &str,
+        event: impl Into<String>,
+    ) -> Result<String> {
+        self.loaded()?
+            .handle_event(function_name, event.into(), None)
+    }
+
+    /// The loaded sandbox running the handlers, to test with the rest of its
+    /// API.
+    pub fn loaded(&mut self) -> Result<&mut LoadedJSSandbox> {
+        if !matches!(self.state, Some(State::Loaded(_))) {
+            let loaded = match self.take_state()? {
+                State::Unloaded(sandbox) => sandbox.get_loaded_sandbox()?,
+                state => {
+                    self.state = Some(state);
+                    return Err(no_handlers());
+                }
+            };
+            self.state = Some(State::Loaded(loaded));
+        }
+        match &mut self.state {
+            Some(State::Loaded(loaded)) => Ok(loaded),
+            _ => Err(new_error!("The sandbox failed to load its handlers")),
+        }
+    }
+
+    /// The calls to host functions made by the handlers so far, in order.
+    pub fn host_calls(&self) -> Vec<RecordedHostCall> {
+        self.host_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Forget the host function calls recorded so far.
+    pub fn clear_host_calls(&self) {
+        self.host_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    fn take_state(&mut self) -> Result<State> {
+        self.state
+            .take()
+            .ok_or_else(|| new_error!("The sandbox failed to load its handlers"))
+    }
+}
+
+impl Debug for MockSandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockSandbox")
+            .field(
+                "handlers",
+                &self
+                    .handlers
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,95 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! TypeScript support.
+//!
+//! TypeScript sources are transpiled to JavaScript on the host, by stripping
+//! their types, so the guest only ever sees JavaScript.
+
+use std::path::Path;
+
+use hyperlight_host::{new_error, Result};
+use oxc_allocator::Allocator;
+use oxc_codegen::Codegen;
+use oxc_parser::Parser;
+use oxc_semantic::SemanticBuilder;
+use oxc_span::SourceType;
+use oxc_transformer::{TransformOptions, Transformer};
+
+/// Returns whether the module at `path` is a TypeScript module.
+pub(crate) fn is_typescript_path(path: &str) -> bool {
+    path.ends_with(".ts") || path.ends_with(".mts")
+}
+
+/// Transpile the TypeScript module `source` to JavaScript.
+///
+/// `path` is only used in error messages.
+pub(crate) fn transpile(source: &str, path: &str) -> Result<String> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, source, SourceType::ts()).parse();
+    if !parsed.errors.is_empty() {
+        return Err(transpile_error(path, parsed.errors));
+    }
+
+    let mut program = parsed.program;
+    let scoping = SemanticBuilder::new()
+        .build(&program)
+        .semantic
+        .into_scoping();
+    let transformed = Transformer::new(&allocator, Path::new(path), &TransformOptions::default())
+        .build_with_scoping(scoping, &mut program);
+    if !transformed.errors.is_empty() {
+        return Err(transpile_error(path, transformed.errors));
+    }
+
+    Ok(Codegen::new().build(&program).code)
+}
+
+fn transpile_error(path: &str, errors: Vec<impl ToString>) -> hyperlight_host::HyperlightError {
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    new_error!(
+        "Failed to transpile TypeScript module '{}': {}",
+        path,
+        errors.join("; ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transpile_strips_types() {
+        let source = r#"
+            import type { Event } from "./types";
+            import { add } from "./math.js";
+            interface Result { sum: number }
+            export function handler(event: Event): Result {
+                return { sum: add(event.a as number, event.b!) };
+            }
+        "#;
+        let js = transpile(source, "handler.ts").unwrap();
+        assert!(js.contains("import { add } from \"./math.js\""));
+        assert!(js.contains("export function handler(event)"));
+        assert!(!js.contains("./types"));
+        assert!(!js.contains("interface"));
+    }
+
+    #[test]
+    fn test_transpile_reports_syntax_errors() {
+        let err = transpile("let x: = 1;", "bad.ts").unwrap_err();
+        assert!(err.to_string().contains("bad.ts"));
+    }
+}
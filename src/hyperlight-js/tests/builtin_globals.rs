@@ -29,6 +29,10 @@ fn builtin_globals_should_be_defined() {
 
         function handler(event) {
             assert(typeof console.log === "function", "console.log should be defined");
+            assert(typeof console.time === "function", "console.time should be defined");
+            assert(typeof console.timeEnd === "function", "console.timeEnd should be defined");
+            assert(typeof console.timeLog === "function", "console.timeLog should be defined");
+            assert(typeof performance.now === "function", "performance.now should be defined");
             assert(typeof print === "function", "print should be defined");
             assert(typeof require === "function", "require should be defined");
             assert(typeof String.bytesFrom === "function", "String.bytesFrom should be defined");
@@ -56,3 +60,29 @@ fn builtin_globals_should_be_defined() {
 
     assert_eq!(res, "0");
 }
+
+#[test]
+fn performance_now_measures_elapsed_time() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const start = performance.now();
+            while (performance.now() - start < 5) {}
+            return { elapsed: performance.now() - start >= 5 };
+        }
+        "#,
+    );
+
+    let mut sandbox = SandboxBuilder::new()
+        .build()
+        .unwrap()
+        .load_runtime()
+        .unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"elapsed":true}"#);
+}
@@ -28,14 +28,18 @@ fn modules_exist_and_contains_expected_exports() {
         import * as crypto from "crypto";
         import * as console from "console";
         import * as io from "io";
+        import * as random from "random";
         import * as require from "require";
+        import * as temporal from "temporal";
 
         function handler(event) {
             return {
                 crypto: Object.keys(crypto),
                 console: Object.keys(console),
                 io: Object.keys(io),
+                random: Object.keys(random),
                 require: Object.keys(require),
+                temporal: Object.keys(temporal),
             };
         }
         "#,
@@ -66,15 +70,38 @@ fn modules_exist_and_contains_expected_exports() {
                 "crypto".to_string(),
                 HashSet::from(["Hmac".to_string(), "createHmac".to_string()])
             ),
-            ("console".to_string(), HashSet::from(["log".to_string()])),
+            (
+                "console".to_string(),
+                HashSet::from([
+                    "log".to_string(),
+                    "time".to_string(),
+                    "timeLog".to_string(),
+                    "timeEnd".to_string(),
+                ])
+            ),
             (
                 "io".to_string(),
                 HashSet::from(["print".to_string(), "flush".to_string()])
             ),
+            (
+                "random".to_string(),
+                HashSet::from(["random".to_string(), "randomInt".to_string()])
+            ),
             (
                 "require".to_string(),
                 HashSet::from(["default".to_string(), "require".to_string()])
             ),
+            (
+                "temporal".to_string(),
+                HashSet::from([
+                    "parseDuration".to_string(),
+                    "addToDate".to_string(),
+                    "addToInstant".to_string(),
+                    "daysBetween".to_string(),
+                    "dayOfWeek".to_string(),
+                    "toDate".to_string(),
+                ])
+            ),
         ])
     );
 }
@@ -0,0 +1,5 @@
+import { greet } from "./lib/greet.js";
+
+function handler(event) {
+    return greet(event.name);
+}
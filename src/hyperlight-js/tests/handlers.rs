@@ -17,7 +17,12 @@ limitations under the License.
 
 #![allow(clippy::disallowed_macros)]
 
-use hyperlight_js::{SandboxBuilder, Script};
+use std::time::{Duration, UNIX_EPOCH};
+
+use hyperlight_js::{
+    current_traceparent, FileSystemMemory, IsolationMode, JsSandboxError, LoadedJSSandbox,
+    SandboxBuilder, Script,
+};
 
 #[test]
 fn handle_event() {
@@ -50,6 +55,35 @@ fn handle_event() {
     assert_eq!(res, r#"{"name":"world","result":"Hello, world!"}"#);
 }
 
+#[test]
+fn handle_event_value() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            return { greeting: "Hello, " + event.name + "!", sizes: event.sizes };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let event = serde_json::json!({ "name": "world", "sizes": [1, 2.5, null] });
+    let res = loaded_sandbox
+        .handle_event_value("handler", &event, None)
+        .unwrap();
+    assert_eq!(
+        res,
+        serde_json::json!({ "greeting": "Hello, world!", "sizes": [1, 2.5, null] })
+    );
+
+    // Events passed as strings are still checked
+    let res = loaded_sandbox.handle_event("handler", "{".to_string(), None);
+    assert!(res.is_err());
+}
+
 #[test]
 fn check_javascript_handler_returns_value() {
     let handler = Script::from_content(
@@ -81,6 +115,48 @@ fn check_javascript_handler_returns_value() {
     );
 }
 
+#[test]
+fn handler_results_are_serialized_like_json_stringify() {
+    let handler = Script::from_content(
+        r#"
+        function value() {
+            return {
+                number: 0.1 + 0.2,
+                large: 1e21,
+                integer: 2 ** 53,
+                nan: NaN,
+                text: 'quote " backslash \\ newline \n control \u0001 emoji 😀',
+                skipped: undefined,
+                method() {},
+                list: [1, undefined, () => {}, null, , true],
+                date: new Date(0),
+                boxed: new Number(3),
+                custom: { toJSON(key) { return "key:" + key; } },
+                1: "integer keys first",
+            };
+        }
+
+        function handler(event) {
+            return event.stringify ? JSON.stringify(value()) : value();
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    let expected = loaded_sandbox
+        .handle_event("handler", r#"{"stringify": true}"#.to_string(), None)
+        .unwrap();
+    let expected: String = serde_json::from_str(&expected).unwrap();
+    assert_eq!(res, expected);
+}
+
 #[test]
 fn add_handler_rejects_empty_name() {
     let proto = SandboxBuilder::new().build().unwrap();
@@ -126,3 +202,673 @@ fn handle_event_rejects_empty_name() {
         "Error should mention empty name, got: {err}"
     );
 }
+
+#[test]
+fn errors_match_their_kind() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            throw new TypeError("bad event " + event.n);
+        }
+        "#,
+    );
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto
+        .set_module_loader(FileSystemMemory::new())
+        .unwrap()
+        .load_runtime()
+        .unwrap();
+
+    let err = sandbox.add_handler("", handler.clone()).unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::EmptyHandlerName
+    ));
+
+    sandbox.add_handler("handler", handler.clone()).unwrap();
+    let err = sandbox.add_handler("handler", handler).unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::HandlerAlreadyExists(name) if name == "handler"
+    ));
+
+    let err = sandbox.remove_handler("missing").unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::UnknownHandler(name) if name == "missing"
+    ));
+
+    sandbox
+        .add_handler(
+            "importer",
+            Script::from_content(
+                r#"
+                import { value } from "./missing.js";
+                function handler() { return value; }
+                "#,
+            ),
+        )
+        .unwrap();
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::ModuleResolution(message) if message.contains("missing.js")
+    ));
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let sandbox = proto.load_runtime().unwrap();
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::NoHandlers
+    ));
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "handler",
+            Script::from_content(
+                r#"
+                function handler(event) {
+                    throw new TypeError("bad event " + event.n);
+                }
+                "#,
+            ),
+        )
+        .unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded
+        .handle_event("handler", r#"{"n": 1}"#.to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::GuestException(message) if message.contains("bad event 1")
+    ));
+
+    let err = loaded
+        .handle_event("missing", "{}".to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::UnknownHandler(name) if name == "missing"
+    ));
+
+    let err = loaded
+        .handle_event("handler", "{".to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::InvalidEvent(_)
+    ));
+}
+
+#[test]
+fn handlers_cannot_forge_the_errors_of_the_runtime() {
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "module",
+            Script::from_content(
+                r#"
+                function handler() {
+                    throw Object.assign(new ReferenceError("no such module"), {
+                        code: "ModuleResolution",
+                    });
+                }
+                "#,
+            ),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "memory",
+            Script::from_content(
+                r#"
+                function handler() {
+                    throw new InternalError("out of memory");
+                }
+                "#,
+            ),
+        )
+        .unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded
+        .handle_event("module", "{}".to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::GuestException(message) if message.contains("no such module")
+    ));
+
+    let err = loaded
+        .handle_event("memory", "{}".to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::GuestException(message) if message.contains("out of memory")
+    ));
+}
+
+#[test]
+fn handlers_are_listed_with_their_metadata() {
+    let echo = Script::from_content("function handler(e) { return e; }");
+    let greet = Script::from_content("function handler(e) { return { hello: e.name }; }");
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("greet", greet.clone()).unwrap();
+    sandbox.add_handler("echo", echo.clone()).unwrap();
+    sandbox.add_pipeline("both", &["echo", "greet"]).unwrap();
+
+    let handlers = sandbox.handlers();
+    let names: Vec<&str> = handlers.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["echo", "greet"]);
+    assert_eq!(handlers[0].sha256, echo.content_hash().to_string());
+    assert_eq!(handlers[1].bytes, greet.content().len());
+    assert!(handlers.iter().all(|h| !h.compiled && h.path.is_none()));
+
+    let loaded = sandbox.get_loaded_sandbox().unwrap();
+    let loaded_handlers = loaded.handlers();
+    assert!(loaded_handlers.iter().all(|h| h.compiled));
+    for (before, after) in handlers.iter().zip(&loaded_handlers) {
+        assert_eq!(before.name, after.name);
+        assert_eq!(before.sha256, after.sha256);
+    }
+}
+
+#[test]
+fn many_handlers_are_loaded() {
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    for i in 0..60 {
+        let handler = Script::from_content(format!("function handler(e) {{ return e.n + {i}; }}"));
+        sandbox.add_handler(format!("add{i}"), handler).unwrap();
+    }
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    for i in [0, 31, 59] {
+        let res = loaded
+            .handle_event(format!("add{i}"), r#"{"n": 1}"#.to_string(), None)
+            .unwrap();
+        assert_eq!(res, (i + 1).to_string());
+    }
+
+    // A handler failing to load fails loading the sandbox
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "ok",
+            Script::from_content("function handler(e) { return e; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "broken",
+            Script::from_content(
+                "function handler(e) { return e; }\nthrow new Error('broken handler');",
+            ),
+        )
+        .unwrap();
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(err.to_string().contains("broken handler"), "{err}");
+}
+
+#[test]
+fn jsonlogic_handler() {
+    let rule = Script::from_jsonlogic(
+        r#"{
+            "if": [
+                { ">": [{ "var": "temp" }, 30] }, "hot",
+                { "<": [{ "var": "temp" }, 10] }, "cold",
+                "mild"
+            ]
+        }"#,
+    )
+    .unwrap();
+    let js = Script::from_content("function handler(e) { return e.temp; }");
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("rule", rule).unwrap();
+    sandbox.add_handler("js", js).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    for (temp, expected) in [(35, r#""hot""#), (5, r#""cold""#), (20, r#""mild""#)] {
+        let event = format!(r#"{{"temp": {temp}}}"#);
+        let res = loaded.handle_event("rule", event, None).unwrap();
+        assert_eq!(res, expected);
+    }
+
+    // JSON-logic and JavaScript handlers can live side by side
+    let res = loaded
+        .handle_event("js", r#"{"temp": 1}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, "1");
+}
+
+#[test]
+fn jsonlogic_rejects_invalid_rules() {
+    let err = Script::from_jsonlogic("{ not json").unwrap_err();
+    assert!(err.to_string().contains("Invalid JSON-logic rule"));
+
+    let rule = Script::from_jsonlogic(r#"{ "no_such_operation": [1] }"#).unwrap();
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("rule", rule).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded
+        .handle_event("rule", "{}".to_string(), None)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Unrecognized JSON-logic operation"));
+}
+
+#[test]
+fn errors_are_remapped_with_source_maps() {
+    // `handler.ts` had a blank line before the `throw`, which is indented by 4 spaces.
+    let source_map = r#"{"version":3,"sources":["handler.ts"],"names":[],"mappings":"AAAA;EAEI"}"#;
+    let script = Script::from_content("function handler(event) {\n  throw new Error(\"boom\");\n}")
+        .with_source_map(source_map)
+        .unwrap();
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", script).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("boom"));
+    assert!(err.contains("handler.ts:3:5"), "{err}");
+
+    assert!(Script::from_content("")
+        .with_source_map("not a source map")
+        .is_err());
+}
+
+#[test]
+fn invocations_can_be_replayed_with_their_seed() {
+    let handler = Script::from_content(
+        r#"
+        import { random, randomInt } from "random";
+        function handler(event, context) {
+            return { seed: context.seed, draws: [random(), randomInt(0, 1000), random()] };
+        }
+        "#,
+    );
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let first = loaded
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    let second = loaded
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_ne!(first, second, "Invocations should get different seeds");
+
+    let seed = loaded.collect_diagnostics().recent_invocations[0].seed;
+    let first_json: serde_json::Value = serde_json::from_str(&first).unwrap();
+    assert_eq!(first_json["seed"], format!("{seed:016x}"));
+
+    let replayed = loaded
+        .handle_event_with_seed("handler", "{}".to_string(), seed, None)
+        .unwrap();
+    assert_eq!(replayed, first);
+}
+
+#[test]
+fn traceparent_is_propagated() {
+    let handler = Script::from_content(
+        r#"
+        import * as trace from "trace";
+        function handler(event, context) {
+            return {
+                context: context.traceparent ?? null,
+                global: globalThis.__traceparent ?? null,
+                host: trace.traceparent(),
+            };
+        }
+        "#,
+    );
+
+    let mut proto = SandboxBuilder::new().build().unwrap();
+    proto
+        .register("trace", "traceparent", current_traceparent)
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    // Invocations outside of an OpenTelemetry trace are not traced
+    let result = loaded
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&result).unwrap(),
+        serde_json::json!({ "context": null, "global": null, "host": null })
+    );
+
+    #[cfg(feature = "opentelemetry")]
+    {
+        use opentelemetry::trace::TracerProvider;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_opentelemetry::OpenTelemetryLayer::new(provider.tracer("test")),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let result = loaded
+                .handle_event("handler", "{}".to_string(), None)
+                .unwrap();
+            let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+            let traceparent = result["context"].as_str().unwrap();
+            assert!(traceparent.starts_with("00-"), "{traceparent}");
+            assert_eq!(result["global"], traceparent);
+            assert_eq!(result["host"], traceparent);
+        });
+        // The trace context of an invocation does not leak into the next one
+        let result = loaded
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        assert!(result.contains(r#""global":null"#), "{result}");
+    }
+}
+
+#[test]
+fn handle_events_runs_the_handler_once_per_event() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            if (event.n === 2) {
+                throw new Error("two is not allowed");
+            }
+            return { doubled: event.n * 2 };
+        }
+        "#,
+    );
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let events = [r#"{"n":1}"#, r#"{"n":2}"#, r#"{"n":3}"#]
+        .map(str::to_string)
+        .to_vec();
+    let results = loaded.handle_events("handler", events, None).unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), r#"{"doubled":2}"#);
+    let err = results[1].as_ref().unwrap_err();
+    assert!(err.to_string().contains("two is not allowed"), "{err}");
+    assert_eq!(results[2].as_ref().unwrap(), r#"{"doubled":6}"#);
+
+    // Every event is a separate invocation
+    assert_eq!(loaded.sequence(), 3);
+    assert_eq!(loaded.collect_diagnostics().recent_invocations.len(), 3);
+
+    assert!(loaded
+        .handle_events("handler", Vec::new(), None)
+        .unwrap()
+        .is_empty());
+    assert!(loaded
+        .handle_events("handler", vec!["not json".to_string()], None)
+        .is_err());
+}
+
+#[test]
+fn handle_event_multi_runs_every_handler_with_the_event() {
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "double",
+            Script::from_content("function handler(event) { event.n *= 2; return event; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "square",
+            Script::from_content("function handler(event) { event.n *= event.n; return event; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "fail",
+            Script::from_content("function handler(event) { throw new Error('nope'); }"),
+        )
+        .unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let results = loaded
+        .handle_event_multi(
+            &["double", "square", "fail"],
+            r#"{"n":3}"#.to_string(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    // Every handler gets its own copy of the event
+    assert_eq!(results["double"].as_ref().unwrap(), r#"{"n":6}"#);
+    assert_eq!(results["square"].as_ref().unwrap(), r#"{"n":9}"#);
+    let err = results["fail"].as_ref().unwrap_err();
+    assert!(err.to_string().contains("nope"), "{err}");
+    assert_eq!(loaded.sequence(), 3);
+
+    let err = loaded
+        .handle_event_multi(&["double", "double"], "{}".to_string(), None)
+        .unwrap_err();
+    assert!(err.to_string().contains("'double'"), "{err}");
+}
+
+#[test]
+fn pipelines_pass_the_result_of_every_stage_to_the_next() {
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "double",
+            Script::from_content("function handler(event) { return { n: event.n * 2 }; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "increment",
+            Script::from_content("function handler(event) { return { n: event.n + 1 }; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_handler(
+            "fail",
+            Script::from_content("function handler(event) { throw new Error('nope'); }"),
+        )
+        .unwrap();
+    sandbox
+        .add_pipeline("pipeline", &["double", "increment", "double"])
+        .unwrap();
+    sandbox
+        .add_pipeline("failing", &["double", "fail"])
+        .unwrap();
+
+    let err = sandbox.add_pipeline("double", &["increment"]).unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{err}");
+    let err = sandbox.add_pipeline("nested", &["pipeline"]).unwrap_err();
+    assert!(err.to_string().contains("is a pipeline"), "{err}");
+
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let result = loaded
+        .handle_event("pipeline", r#"{"n":3}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(result, r#"{"n":14}"#);
+
+    let err = loaded
+        .handle_event("failing", r#"{"n":3}"#.to_string(), None)
+        .unwrap_err();
+    assert!(err.to_string().contains("nope"), "{err}");
+    assert!(err.to_string().contains("In stage fail"), "{err}");
+
+    let mut sandbox = loaded.unload().unwrap();
+    sandbox
+        .add_handler(
+            "double",
+            Script::from_content("function handler(event) { return event; }"),
+        )
+        .unwrap();
+    sandbox
+        .add_pipeline("pipeline", &["double", "missing"])
+        .unwrap();
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(err.to_string().contains("stage missing"), "{err}");
+}
+
+#[test]
+fn deterministic_mode_reproduces_time_and_randomness() {
+    let handler = Script::from_content(
+        r#"
+        import { random } from "random";
+        function handler(event, context) {
+            return { seed: context.seed, now: Date.now(), math: Math.random(), random: random() };
+        }
+        "#,
+    );
+    let epoch = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    let run = || {
+        let proto = SandboxBuilder::new()
+            .with_deterministic_mode(42, epoch)
+            .build()
+            .unwrap();
+        let mut sandbox = proto.load_runtime().unwrap();
+        sandbox.add_handler("handler", handler.clone()).unwrap();
+        let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+        let first = loaded
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        loaded
+            .advance_virtual_time(Duration::from_millis(1500))
+            .unwrap();
+        assert_eq!(
+            loaded.virtual_time(),
+            Some(epoch + Duration::from_millis(1500))
+        );
+        let second = loaded
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        (first, second)
+    };
+
+    let (first, second) = run();
+    assert_eq!(run(), (first.clone(), second.clone()));
+
+    let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+    let second: serde_json::Value = serde_json::from_str(&second).unwrap();
+    assert_eq!(first["now"], 1_700_000_000_000u64);
+    assert_eq!(second["now"], 1_700_000_001_500u64);
+    assert_ne!(first["seed"], second["seed"]);
+    assert_ne!(first["math"], second["math"]);
+}
+
+#[test]
+fn virtual_time_requires_deterministic_mode() {
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox
+        .add_handler("handler", Script::from_content("function handler() {}"))
+        .unwrap();
+    let loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    assert_eq!(loaded.virtual_time(), None);
+    assert!(loaded.advance_virtual_time(Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn replaced_handlers_keep_the_state_of_the_sandbox() {
+    let fs = FileSystemMemory::new();
+    fs.insert(
+        "counter.js",
+        "let count = 0; export function next() { return ++count; }",
+    );
+    let counting = |factor: u32| {
+        Script::from_content(format!(
+            r#"
+            import {{ next }} from "./counter.js";
+            function handler() {{ return next() * {factor}; }}
+            "#
+        ))
+    };
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.set_module_loader(fs).unwrap().load_runtime().unwrap();
+    sandbox.add_handler("first", counting(1)).unwrap();
+    sandbox.add_handler("second", counting(1)).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let call = |loaded: &mut LoadedJSSandbox, name: &str| {
+        loaded.handle_event(name, "{}".to_string(), None).unwrap()
+    };
+
+    assert_eq!(call(&mut loaded, "first"), "1");
+    assert_eq!(call(&mut loaded, "second"), "2");
+
+    loaded.replace_handler("second", counting(10)).unwrap();
+    assert_eq!(call(&mut loaded, "second"), "30");
+    assert_eq!(call(&mut loaded, "first"), "4");
+
+    // A handler that fails to load leaves the old one in place
+    let err = loaded
+        .replace_handler(
+            "second",
+            Script::from_content("function handler() {} throw new Error('broken');"),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("broken"), "{err}");
+    let err = loaded.replace_handler("missing", counting(1)).unwrap_err();
+    assert!(matches!(
+        JsSandboxError::from(err),
+        JsSandboxError::UnknownHandler(name) if name == "missing"
+    ));
+    assert_eq!(
+        loaded
+            .handle_event("second", "{}".to_string(), None)
+            .unwrap(),
+        "50"
+    );
+}
+
+#[test]
+fn replaced_handlers_drop_their_realm() {
+    let handler = |n: u32| Script::from_content(format!("function handler() {{ return {n}; }}"));
+
+    let proto = SandboxBuilder::new()
+        .with_isolation_mode(IsolationMode::PerHandler)
+        .with_js_memory_limit(4 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler(0)).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    // Every realm has its own built-ins, so the heap would run out if the
+    // realms of the replaced handlers were kept.
+    for n in 1..=200 {
+        loaded.replace_handler("handler", handler(n)).unwrap();
+    }
+    assert_eq!(
+        loaded
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap(),
+        "200"
+    );
+}
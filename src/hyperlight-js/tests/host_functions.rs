@@ -17,7 +17,11 @@ limitations under the License.
 
 #![allow(clippy::disallowed_macros)]
 
-use hyperlight_js::{new_error, SandboxBuilder, Script};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyperlight_js::{new_error, CallContext, HostError, HostFunctionCache, SandboxBuilder, Script};
 
 #[test]
 fn can_call_host_functions() {
@@ -359,3 +363,466 @@ fn register_raw_via_host_module() {
 
     assert_eq!(res, r#"{"greeting":"Hello, World!"}"#);
 }
+
+#[test]
+fn host_batch_calls_host_functions_in_order() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const results = host.batch([
+                { module: "kv", name: "get", args: ["a"] },
+                { module: "kv", name: "get", args: ["b"] },
+                { module: "math", name: "add", args: [10, 32] },
+                { module: "kv", name: "keys" },
+            ]);
+            return { results };
+        }
+        "#,
+    );
+
+    let event = r#"{}"#;
+
+    let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+
+    proto_js_sandbox
+        .register("kv", "get", |key: String| format!("value of {key}"))
+        .unwrap();
+    proto_js_sandbox
+        .register("kv", "keys", || vec!["a", "b"])
+        .unwrap();
+    proto_js_sandbox
+        .register("math", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", event.to_string(), None)
+        .unwrap();
+
+    assert_eq!(
+        res,
+        r#"{"results":["value of a","value of b",42,["a","b"]]}"#
+    );
+}
+
+#[test]
+fn host_batch_fails_if_any_call_fails() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            return host.batch([
+                { module: "math", name: "add", args: [1, 2] },
+                { module: "math", name: "missing" },
+            ]);
+        }
+        "#,
+    );
+
+    let event = r#"{}"#;
+
+    let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+
+    proto_js_sandbox
+        .register("math", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded_sandbox
+        .handle_event("handler", event.to_string(), None)
+        .unwrap_err();
+
+    assert!(err.to_string().contains("missing"));
+}
+
+#[test]
+fn host_batch_is_available_without_host_modules() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const results = host.batch([]);
+            try {
+                host.batch([{ module: "math", name: "add", args: [1, 2] }]);
+                return { results, error: null };
+            } catch (e) {
+                return { results, error: e.message.includes("math") };
+            }
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, r#"{"results":[],"error":true}"#);
+}
+
+#[test]
+fn cacheable_host_functions_are_memoized_by_the_guest() {
+    let handler = Script::from_content(
+        r#"
+        import * as config from "config";
+        function handler(event) {
+            return [
+                config.get("a"),
+                config.get("a"),
+                config.get("b"),
+                config.version(),
+                config.version(),
+            ];
+        }
+        "#,
+    );
+
+    let get_calls = Arc::new(AtomicUsize::new(0));
+    let version_calls = Arc::new(AtomicUsize::new(0));
+
+    let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+
+    let calls = get_calls.clone();
+    let version = version_calls.clone();
+    proto_js_sandbox
+        .host_module("config")
+        .register("get", move |key: String| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            format!("value of {key}")
+        })
+        .register("version", move || version.fetch_add(1, Ordering::SeqCst))
+        .cacheable("get", HostFunctionCache::Invocation)
+        .cacheable("version", HostFunctionCache::Ttl(Duration::from_secs(3600)));
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    for _ in 0..2 {
+        let res = loaded_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        assert_eq!(res, r#"["value of a","value of a","value of b",0,0]"#);
+    }
+
+    // Results cached for an invocation are dropped when the next one starts,
+    // while results with a TTL are reused across invocations.
+    assert_eq!(get_calls.load(Ordering::SeqCst), 4);
+    assert_eq!(version_calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn host_calls_are_limited_per_execution() {
+    let handler = Script::from_content(
+        r#"
+        import * as utils from "utils";
+        function handler(event) {
+            let total = 0;
+            for (let i = 0; i < event.calls; i++) {
+                total = utils.add(total, 1);
+            }
+            return { total };
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new()
+        .with_max_host_calls(3)
+        .build()
+        .unwrap();
+    proto_js_sandbox
+        .register("utils", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded_sandbox
+        .handle_event("handler", r#"{"calls":4}"#.to_string(), None)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("limit of 3 host function calls"),
+        "{err}"
+    );
+
+    // The count starts over for every execution
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"calls":3}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"total":3}"#);
+}
+
+#[test]
+fn host_function_allowlist_denies_unlisted_builtins() {
+    let handler = Script::from_content(
+        r#"
+        import * as utils from "utils";
+        function handler(event) {
+            if (event.callHost) {
+                return { sum: utils.add(1, 2) };
+            }
+            return { now: Date.now() > 0 };
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new()
+        .with_seccomp_style_host_function_allowlist(["HostPrint", "CurrentTimeMicros"])
+        .build()
+        .unwrap();
+    proto_js_sandbox
+        .register("utils", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"callHost":false}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"now":true}"#);
+
+    let err = loaded_sandbox
+        .handle_event("handler", r#"{"callHost":true}"#.to_string(), None)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("'CallHostJsFunction' is not in the host function allowlist"),
+        "{err}"
+    );
+
+    let allowlist = loaded_sandbox
+        .collect_diagnostics()
+        .configuration
+        .host_function_allowlist;
+    assert_eq!(
+        allowlist,
+        Some(vec![
+            "CurrentTimeMicros".to_string(),
+            "HostPrint".to_string()
+        ])
+    );
+}
+
+#[test]
+fn host_function_allowlist_rejects_unknown_builtins() {
+    let err = SandboxBuilder::new()
+        .with_seccomp_style_host_function_allowlist(["CallHostFunction"])
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("'CallHostFunction'"), "{err}");
+}
+
+#[test]
+fn host_call_interceptors_wrap_host_functions() {
+    let handler = Script::from_content(
+        r#"
+        import * as utils from "utils";
+        import * as admin from "admin";
+        function handler(event) {
+            if (event.admin) {
+                return { reset: admin.reset() };
+            }
+            return { sum: utils.add(1, 2), echo: utils.echo("secret") };
+        }
+        "#,
+    );
+
+    let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = calls.clone();
+    let mut proto_js_sandbox = SandboxBuilder::new()
+        .build()
+        .unwrap()
+        // Outermost: records every call, including the denied ones
+        .with_host_call_interceptor(move |call, args, next| {
+            recorded
+                .lock()
+                .unwrap()
+                .push(format!("{}.{}", call.module, call.function));
+            next(args)
+        })
+        .with_host_call_interceptor(|call, args, next| {
+            if call.module == "admin" {
+                return Err(new_error!("'{}' is not allowed", call.function));
+            }
+            next(args.replace("secret", "[redacted]"))
+        });
+    proto_js_sandbox
+        .register("utils", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+    proto_js_sandbox
+        .register("utils", "echo", |s: String| s)
+        .unwrap();
+    proto_js_sandbox
+        .register("admin", "reset", || true)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"admin":false}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"sum":3,"echo":"[redacted]"}"#);
+
+    let err = loaded_sandbox
+        .handle_event("handler", r#"{"admin":true}"#.to_string(), None)
+        .unwrap_err();
+    assert!(err.to_string().contains("'reset' is not allowed"), "{err}");
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        ["utils.add", "utils.echo", "admin.reset"]
+    );
+}
+
+#[test]
+fn host_functions_see_the_call_context() {
+    let handler = Script::from_content(
+        r#"
+        import * as db from "db";
+        function handler(event) {
+            return { tenant: db.tenant() };
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new()
+        .build()
+        .unwrap()
+        .with_host_call_interceptor(|call, args, next| match call.context {
+            Some(context) if context.get("blocked") == Some(&serde_json::json!(true)) => {
+                Err(new_error!("The tenant is blocked"))
+            }
+            _ => next(args),
+        });
+    proto_js_sandbox
+        .register("db", "tenant", || {
+            CallContext::current().and_then(|context| context.get("tenant").cloned())
+        })
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let context = CallContext::new(serde_json::json!({"tenant": "acme"}));
+    let res = loaded_sandbox
+        .handle_event_with_context("handler", "{}".to_string(), context, None)
+        .unwrap();
+    assert_eq!(res, r#"{"tenant":"acme"}"#);
+
+    // The context only applies to the call it was passed to
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"tenant":null}"#);
+
+    let context = CallContext::new(serde_json::json!({"tenant": "evil", "blocked": true}));
+    let err = loaded_sandbox
+        .handle_event_with_context("handler", "{}".to_string(), context, None)
+        .unwrap_err();
+    assert!(err.to_string().contains("The tenant is blocked"), "{err}");
+}
+
+struct Greeter {
+    greeting: String,
+}
+
+#[hyperlight_js::host_module("greeter")]
+impl Greeter {
+    fn new(greeting: &str) -> Self {
+        Self {
+            greeting: greeting.to_string(),
+        }
+    }
+
+    fn greet(&self, name: String) -> String {
+        format!("{}, {name}!", self.greeting)
+    }
+
+    fn count(&self, a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn host_module_attribute_registers_methods() {
+    let handler = Script::from_content(
+        r#"
+        import { greet, count } from "greeter";
+        function handler(event) {
+            return { greeting: greet(event.name), count: count(1, 2) };
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    proto_js_sandbox
+        .register_module(Greeter::new("Hello"))
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"name":"World"}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"greeting":"Hello, World!","count":3}"#);
+}
+
+#[test]
+fn host_errors_are_thrown_as_js_errors() {
+    let handler = Script::from_content(
+        r#"
+        import * as users from "users";
+        function handler(event) {
+            try {
+                return { name: users.find(event.id) };
+            } catch (err) {
+                return { error: err.name, code: err.code, message: err.message, isError: err instanceof Error };
+            }
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    proto_js_sandbox
+        .host_module("users")
+        .register_fallible("find", |id: u64| match id {
+            1 => Ok("alice".to_string()),
+            _ => {
+                Err(HostError::new("NotFoundError", format!("No user {id}"))
+                    .with_code("E_NOT_FOUND"))
+            }
+        });
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"id":1}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"name":"alice"}"#);
+
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"id":42}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(
+        res,
+        r#"{"error":"NotFoundError","code":"E_NOT_FOUND","message":"No user 42","isError":true}"#
+    );
+}
@@ -17,7 +17,10 @@ limitations under the License.
 
 #![allow(clippy::disallowed_macros)]
 
-use hyperlight_js::{embed_modules, SandboxBuilder, Script};
+use hyperlight_js::{
+    embed_dir, embed_modules, FileSystemDirectory, FileSystemMemory, ImportRules,
+    ModuleLoaderOptions, SandboxBuilder, Script,
+};
 
 #[test]
 fn test_handler_with_multiple_imports() {
@@ -58,6 +61,89 @@ fn test_handler_with_multiple_imports() {
     assert!(res.contains(r#""message":"RESULT: 8"#));
 }
 
+#[test]
+fn test_handler_imports_from_a_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("lib")).unwrap();
+    std::fs::copy("tests/fixtures/math.js", dir.path().join("lib/math.js")).unwrap();
+
+    let handler = r#"
+    import { add } from './lib/math.js';
+
+    function handler(event) {
+        return { sum: add(event.a, event.b) };
+    }
+    "#;
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox
+        .set_module_loader(FileSystemDirectory::new(dir.path()))
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "handler",
+            Script::from_content(handler).with_virtual_base("/"),
+        )
+        .unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"a": 5, "b": 3}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"sum":8}"#);
+}
+
+#[test]
+fn test_handlers_import_the_exports_of_the_setup_script() {
+    let fs = embed_modules! {
+        "math.js" => "fixtures/math.js",
+    };
+
+    let setup = r#"
+    import { multiply } from './math.js';
+    export let evaluations = 0;
+    evaluations += 1;
+    export const table = new Map([["a", multiply(2, 3)], ["b", multiply(4, 5)]]);
+    "#;
+
+    let handler = r#"
+    import { evaluations, table } from "setup";
+    function handler(event) {
+        return { value: table.get(event.key), evaluations };
+    }
+    "#;
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox.set_module_loader(fs).unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    sandbox
+        .add_setup_script(Script::from_content(setup).with_virtual_base("/"))
+        .unwrap();
+    let err = sandbox
+        .add_setup_script(Script::from_content(setup))
+        .unwrap_err();
+    assert!(err.to_string().contains("already been added"), "{err}");
+    sandbox
+        .add_handler("first", Script::from_content(handler))
+        .unwrap();
+    sandbox
+        .add_handler("second", Script::from_content(handler))
+        .unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let first = loaded_sandbox
+        .handle_event("first", r#"{"key":"a"}"#.to_string(), None)
+        .unwrap();
+    let second = loaded_sandbox
+        .handle_event("second", r#"{"key":"b"}"#.to_string(), None)
+        .unwrap();
+
+    // Both handlers share the module, which is only evaluated once
+    assert_eq!(first, r#"{"value":6,"evaluations":1}"#);
+    assert_eq!(second, r#"{"value":20,"evaluations":1}"#);
+}
+
 #[test]
 fn test_handler_import_restrictions() {
     let fs = embed_modules! {
@@ -148,3 +234,253 @@ fn test_handler_import_from_a_subfolder() {
 
     assert_eq!(res, "42");
 }
+
+#[test]
+fn test_handler_with_embedded_directory() {
+    let fs = embed_dir!("tests/fixtures");
+
+    let handler_content = r#"
+    import { ultimateQuestionOfEverything } from './galaxy/index.js';
+    import { add } from './math.js';
+
+    function handler(event) {
+        return add(ultimateQuestionOfEverything, event.offset);
+    }
+    "#;
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox.set_module_loader(fs).unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    let handler = Script::from_content(handler_content).with_virtual_base("/");
+    sandbox.add_handler("hitchhiker", handler).unwrap();
+
+    let event = r#"{"offset": 1}"#;
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("hitchhiker", event.to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, "43");
+}
+
+#[test]
+fn test_handler_with_in_memory_modules() {
+    let fs = FileSystemMemory::new();
+    fs.insert("math.js", include_str!("fixtures/math.js"));
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox.set_module_loader(fs.clone()).unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    // Modules inserted after the loader has been installed are visible
+    // to the sandbox since clones share storage.
+    fs.insert(
+        "lib/greeting.js",
+        "export const greeting = (name) => `Hello, ${name}!`;",
+    );
+
+    let handler_content = r#"
+    import { add } from './math.js';
+    import { greeting } from './lib/greeting.js';
+
+    function handler(event) {
+        return { sum: add(event.a, event.b), message: greeting(event.name) };
+    }
+    "#;
+
+    let handler = Script::from_content(handler_content).with_virtual_base("/");
+    sandbox.add_handler("memory", handler).unwrap();
+
+    let event = r#"{"a": 1, "b": 2, "name": "World"}"#;
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("memory", event.to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, r#"{"sum":3,"message":"Hello, World!"}"#);
+}
+
+#[test]
+fn test_handler_from_directory() {
+    let fs = FileSystemMemory::new();
+    let handler = Script::from_directory_with_modules(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/greeter"),
+        &fs,
+    )
+    .unwrap();
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox.set_module_loader(fs).unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("greeter", handler).unwrap();
+
+    let event = r#"{"name": "World"}"#;
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("greeter", event.to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, r#""Hello, World!""#);
+}
+
+#[test]
+fn test_import_policy_denies_module() {
+    let fs = embed_modules! {
+        "math.js" => "fixtures/math.js",
+        "strings.js" => "fixtures/strings.js",
+    };
+
+    let options =
+        ModuleLoaderOptions::new().with_import_policy(ImportRules::new().deny("./strings*"));
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox
+        .set_module_loader_with_options(fs, options)
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    let allowed = Script::from_content(
+        r#"
+        import { add } from './math.js';
+        function handler(event) { return add(event.a, event.b); }
+        "#,
+    )
+    .with_virtual_base("/");
+    sandbox.add_handler("allowed", allowed).unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("allowed", r#"{"a": 1, "b": 2}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res, "3");
+
+    let mut sandbox = loaded_sandbox.unload().unwrap();
+    let denied = Script::from_content(
+        r#"
+        import { toUpperCase } from './strings.js';
+        function handler(event) { return toUpperCase(event.s); }
+        "#,
+    )
+    .with_virtual_base("/");
+    sandbox.add_handler("denied", denied).unwrap();
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(
+        err.to_string().contains("denied by pattern"),
+        "Unexpected error: {err}"
+    );
+}
+
+#[test]
+fn test_module_integrity_mismatch_fails_import() {
+    let fs = FileSystemMemory::new();
+    fs.insert("math.js", "export const add = (a, b) => a - b; // tampered");
+
+    // digest of the expected (untampered) source
+    let expected = "export const add = (a, b) => a + b;";
+    let integrity = format!(
+        "sha256-{}",
+        base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            <sha2::Sha256 as sha2::Digest>::digest(expected)
+        )
+    );
+    let options = ModuleLoaderOptions::new()
+        .with_integrity("math.js", &integrity)
+        .unwrap();
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox
+        .set_module_loader_with_options(fs.clone(), options)
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    let handler = Script::from_content(
+        r#"
+        import { add } from './math.js';
+        function handler(event) { return add(event.a, event.b); }
+        "#,
+    )
+    .with_virtual_base("/");
+    sandbox.add_handler("calculator", handler).unwrap();
+
+    let err = sandbox.get_loaded_sandbox().unwrap_err();
+    assert!(
+        err.to_string().contains("Integrity check failed"),
+        "Unexpected error: {err}"
+    );
+}
+
+#[test]
+fn test_prebundled_modules_are_loaded_without_calling_the_host() {
+    let fs = FileSystemMemory::new();
+    fs.insert("math.js", include_str!("fixtures/math.js"));
+    fs.insert(
+        "lib/greeting.js",
+        "import { add } from '../math.js';\nexport const greeting = (name) => `Hello, ${name} ${add(1, 2)}!`;",
+    );
+
+    let options = ModuleLoaderOptions::new().with_prebundling(true);
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox
+        .set_module_loader_with_options(fs.clone(), options)
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    let handler_content = r#"
+    import { greeting } from './lib/greeting.js';
+
+    function handler(event) {
+        return { message: greeting(event.name) };
+    }
+    "#;
+
+    let handler = Script::from_content(handler_content).with_virtual_base("/");
+    sandbox.add_handler("bundled", handler).unwrap();
+
+    // The modules were bundled when the handler was added, so the guest
+    // doesn't need to read them from the file system anymore.
+    fs.clear();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("bundled", r#"{"name": "World"}"#.to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, r#"{"message":"Hello, World 3!"}"#);
+}
+
+#[test]
+#[cfg(feature = "typescript")]
+fn test_typescript_handler_importing_typescript_module() {
+    let fs = FileSystemMemory::new();
+    fs.insert(
+        "lib/math.ts",
+        "export function add(a: number, b: number): number { return a + b; }",
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let proto_js_sandbox = proto_js_sandbox.set_module_loader(fs).unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    let handler = Script::from_typescript(
+        r#"
+        import { add } from './lib/math.js';
+
+        interface Event { a: number; b: number }
+
+        export function handler(event: Event): { sum: number } {
+            return { sum: add(event.a, event.b) };
+        }
+        "#,
+    )
+    .unwrap()
+    .with_virtual_base("/");
+    sandbox.add_handler("typescript", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("typescript", r#"{"a": 1, "b": 2}"#.to_string(), None)
+        .unwrap();
+
+    assert_eq!(res, r#"{"sum":3}"#);
+}
@@ -23,7 +23,7 @@ use std::time::{Duration, Instant};
 #[cfg(feature = "monitor-cpu-time")]
 use hyperlight_js::CpuTimeMonitor;
 #[cfg(feature = "monitor-wall-clock")]
-use hyperlight_js::WallClockMonitor;
+use hyperlight_js::{PoisonReason, RetryPolicy, WallClockMonitor};
 use hyperlight_js::{SandboxBuilder, Script};
 
 /// Helper to create a sandbox with a CPU-burning handler.
@@ -96,6 +96,136 @@ fn wall_clock_monitor_kills_slow_handler() {
     // Result should be error since handler was killed
     assert!(result.is_err(), "Killed handler should return error");
     assert!(loaded.poisoned(), "Sandbox should be poisoned after kill");
+    assert_eq!(loaded.poisoned_reason(), Some(PoisonReason::MonitorTimeout));
+}
+
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn wall_clock_monitor_cancels_handler_cooperatively() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            if (event.forever) {
+                // The cancellation cannot be caught by the handler
+                try { while (true) {} } catch (e) { return "caught"; }
+            }
+            return "done";
+        }
+        "#,
+    );
+
+    let proto = SandboxBuilder::new()
+        .with_cooperative_cancellation(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    assert!(loaded.cancellation_handle().is_some());
+
+    let monitor = WallClockMonitor::new(Duration::from_millis(300)).unwrap();
+    let start = Instant::now();
+    let result = loaded.handle_event_with_monitor(
+        "handler",
+        r#"{"forever": true}"#.to_string(),
+        &monitor,
+        None,
+    );
+    assert!(
+        start.elapsed() < Duration::from_secs(2),
+        "Should cancel quickly, took {:?}",
+        start.elapsed()
+    );
+    assert!(
+        matches!(
+            result,
+            Err(hyperlight_js::HyperlightError::ExecutionCanceledByHost())
+        ),
+        "Cancelled handler should return an error: {:?}",
+        result
+    );
+
+    // The sandbox is still usable without restoring it
+    assert!(!loaded.poisoned(), "Sandbox should not be poisoned");
+    let result = loaded.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None);
+    assert_eq!(result.unwrap(), r#""done""#);
+}
+
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn wall_clock_monitor_kills_handler_after_grace_period() {
+    let handler = Script::from_content(
+        r#"
+        import * as host from "host";
+        function handler(event) {
+            // The handler cannot notice the cancellation while the host function runs
+            host.block();
+            return "done";
+        }
+        "#,
+    );
+
+    let mut proto = SandboxBuilder::new()
+        .with_cooperative_cancellation(true)
+        .with_cancellation_grace_period(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    proto
+        .register("host", "block", || {
+            std::thread::sleep(Duration::from_secs(2))
+        })
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    let monitor = WallClockMonitor::new(Duration::from_millis(200)).unwrap();
+    let result = loaded.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None);
+    assert!(
+        matches!(
+            result,
+            Err(hyperlight_js::HyperlightError::ExecutionCanceledByHost())
+        ),
+        "Killed handler should return an error: {:?}",
+        result
+    );
+    assert!(loaded.poisoned(), "Should be poisoned after kill");
+}
+
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn wall_clock_monitor_excluding_host_calls() {
+    let handler = Script::from_content(
+        r#"
+        import * as host from "host";
+        function handler(event) {
+            host.query();
+            return "done";
+        }
+        "#,
+    );
+
+    let mut proto = SandboxBuilder::new().build().unwrap();
+    proto
+        .register("host", "query", || {
+            std::thread::sleep(Duration::from_millis(500))
+        })
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+
+    // The time spent in the host function does not count towards the timeout
+    let monitor = WallClockMonitor::new(Duration::from_millis(300))
+        .unwrap()
+        .excluding_host_calls();
+    let result = loaded.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None);
+    assert_eq!(result.unwrap(), r#""done""#);
+
+    // By default it does
+    let monitor = WallClockMonitor::new(Duration::from_millis(300)).unwrap();
+    let result = loaded.handle_event_with_monitor("handler", "{}".to_string(), &monitor, None);
+    assert!(result.is_err(), "Should be killed: {:?}", result);
 }
 
 #[test]
@@ -334,6 +464,183 @@ fn monitor_reuse_across_calls() {
     assert!(!loaded.poisoned());
 }
 
+/// Default monitors guard plain `handle_event` calls, and are replaced by the
+/// monitor passed to `handle_event_with_monitor`.
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn default_monitors_guard_handle_event() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const startTime = Date.now();
+            while (Date.now() - startTime < event.runtime) {}
+            return event;
+        }
+        "#,
+    );
+    let proto = SandboxBuilder::new()
+        .with_default_monitors(WallClockMonitor::new(Duration::from_millis(500)).unwrap())
+        .build()
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let snapshot = loaded.snapshot().unwrap();
+
+    // A longer monitor overrides the default one
+    let monitor = WallClockMonitor::new(Duration::from_secs(5)).unwrap();
+    let event = r#"{"runtime": 1000}"#;
+    let result = loaded.handle_event_with_monitor("handler", event.to_string(), &monitor, None);
+    assert!(result.is_ok(), "Override should allow 1s: {:?}", result);
+
+    let result = loaded.handle_event("handler", r#"{"runtime": 50}"#.to_string(), None);
+    assert!(result.is_ok(), "Fast handler should complete: {:?}", result);
+
+    let start = Instant::now();
+    let result = loaded.handle_event("handler", r#"{"runtime": 5000}"#.to_string(), None);
+    let elapsed = start.elapsed();
+    assert!(result.is_err(), "Default monitor should kill the handler");
+    assert!(loaded.poisoned(), "Sandbox should be poisoned after kill");
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "Should terminate quickly, took {:?}",
+        elapsed
+    );
+
+    loaded.restore(snapshot).unwrap();
+    assert!(!loaded.poisoned());
+}
+
+/// With auto-recovery, a call that poisons the sandbox restores it.
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn auto_recover_restores_poisoned_sandbox() {
+    let handler = Script::from_content(
+        r#"
+        let calls = 0;
+        function handler(event) {
+            calls++;
+            const startTime = Date.now();
+            while (Date.now() - startTime < event.runtime) {}
+            return { calls };
+        }
+        "#,
+    );
+    let proto = SandboxBuilder::new()
+        .with_auto_recover(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let monitor = WallClockMonitor::new(Duration::from_millis(300)).unwrap();
+
+    let result = loaded.handle_event_with_monitor(
+        "handler",
+        r#"{"runtime": 0}"#.to_string(),
+        &monitor,
+        None,
+    );
+    assert_eq!(result.unwrap(), r#"{"calls":1}"#);
+
+    let result = loaded.handle_event_with_monitor(
+        "handler",
+        r#"{"runtime": 5000}"#.to_string(),
+        &monitor,
+        None,
+    );
+    assert!(result.is_err(), "Slow handler should be killed");
+    assert!(!loaded.poisoned(), "Sandbox should have been recovered");
+    assert_eq!(loaded.poisoned_reason(), None);
+
+    // The state of the handlers is back to right after they were loaded
+    let result = loaded.handle_event("handler", r#"{"runtime": 0}"#.to_string(), None);
+    assert_eq!(result.unwrap(), r#"{"calls":1}"#);
+    assert_eq!(loaded.sequence(), 3);
+}
+
+/// Handlers killed by a monitor are retried on the restored sandbox.
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn handle_event_with_retry_restores_and_retries_killed_handlers() {
+    // With auto-recovery, the sandbox is already restored when the attempt
+    // fails, and the attempt is still retried.
+    for auto_recover in [false, true] {
+        retry_killed_handlers(auto_recover);
+    }
+}
+
+#[cfg(feature = "monitor-wall-clock")]
+fn retry_killed_handlers(auto_recover: bool) {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    // The attempts are counted on the host, as restoring the sandbox resets
+    // the state of the guest.
+    let attempts = Arc::new(AtomicU32::new(0));
+    let handler = Script::from_content(
+        r#"
+        import { attempt } from "retry";
+        let calls = 0;
+        function handler(event) {
+            calls++;
+            if (event.fail) {
+                throw new Error("boom");
+            }
+            if (attempt() < event.succeed_at) {
+                while (true) {}
+            }
+            return { calls };
+        }
+        "#,
+    );
+    let mut proto = SandboxBuilder::new()
+        .with_default_monitors(WallClockMonitor::new(Duration::from_millis(200)).unwrap())
+        .with_auto_recover(auto_recover)
+        .build()
+        .unwrap();
+    let counter = attempts.clone();
+    proto
+        .register("retry", "attempt", move || {
+            counter.fetch_add(1, Ordering::SeqCst) + 1
+        })
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let policy =
+        RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+
+    let retried = loaded
+        .handle_event_with_retry(&policy, "handler", r#"{"succeed_at": 3}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(retried.attempts, 3);
+    // The killed attempts left no trace in the state of the handler
+    assert_eq!(retried.result, r#"{"calls":1}"#);
+    assert!(!loaded.poisoned());
+    assert_eq!(loaded.sequence(), 3);
+
+    // Errors of the handler itself are not retried
+    let err = loaded
+        .handle_event_with_retry(&policy, "handler", r#"{"fail": true}"#.to_string(), None)
+        .unwrap_err();
+    assert!(err.to_string().contains("boom"));
+    assert_eq!(loaded.sequence(), 4);
+
+    attempts.store(0, Ordering::SeqCst);
+    let err = loaded
+        .handle_event_with_retry(&policy, "handler", r#"{"succeed_at": 4}"#.to_string(), None)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        hyperlight_js::HyperlightError::ExecutionCanceledByHost()
+    ));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    // Like after `handle_event`, the last attempt leaves the sandbox poisoned
+    // unless it recovers automatically
+    assert_eq!(loaded.poisoned(), !auto_recover);
+}
+
 /// Single-element tuple monitors should work identically to a bare monitor.
 #[test]
 #[cfg(feature = "monitor-wall-clock")]
@@ -354,3 +661,136 @@ fn single_element_tuple_monitor() {
         elapsed
     );
 }
+
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn wall_clock_monitor_kills_slow_handler_loading() {
+    // The top level code of the script runs when the handlers are loaded
+    let handler = Script::from_content(
+        r#"
+        const startTime = Date.now();
+        while (Date.now() - startTime < 5000) {}
+        function handler(event) {
+            return event;
+        }
+        "#,
+    );
+
+    let proto = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let monitor = WallClockMonitor::new(Duration::from_millis(500)).unwrap();
+    let start = Instant::now();
+    let result = sandbox.get_loaded_sandbox_with_monitor(&monitor);
+    let elapsed = start.elapsed();
+
+    assert!(result.is_err(), "Slow loading should be terminated");
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "Loading should be terminated promptly, took {:?}",
+        elapsed
+    );
+}
+
+/// Records the lifecycle events of a sandbox.
+#[cfg(feature = "monitor-wall-clock")]
+#[derive(Default, Clone)]
+struct RecordingObserver(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+#[cfg(feature = "monitor-wall-clock")]
+impl hyperlight_js::SandboxObserver for RecordingObserver {
+    fn on_loaded(&self) {
+        self.0.lock().unwrap().push("loaded".to_string());
+    }
+
+    fn on_handler_start(&self, handler: &str, sequence: u64) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("start {handler} {sequence}"));
+    }
+
+    fn on_handler_end(
+        &self,
+        handler: &str,
+        sequence: u64,
+        _duration: Duration,
+        error: Option<&hyperlight_js::HyperlightError>,
+    ) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("end {handler} {sequence} {}", error.is_some()));
+    }
+
+    fn on_poisoned(&self, handler: &str, reason: &PoisonReason) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("poisoned {handler} {reason:?}"));
+    }
+
+    fn on_monitor_fired(&self, handler: &str, monitor: &'static str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("fired {handler} {monitor}"));
+    }
+
+    fn on_restored(&self) {
+        self.0.lock().unwrap().push("restored".to_string());
+    }
+
+    fn on_unloaded(&self) {
+        self.0.lock().unwrap().push("unloaded".to_string());
+    }
+}
+
+/// The observer of a sandbox is told about every step of its lifecycle.
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn observer_sees_lifecycle_events() {
+    let observer = RecordingObserver::default();
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const startTime = Date.now();
+            while (Date.now() - startTime < event.runtime) {}
+            return event;
+        }
+        "#,
+    );
+    let proto = SandboxBuilder::new()
+        .with_observer(observer.clone())
+        .build()
+        .unwrap();
+    let mut sandbox = proto.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+    let mut loaded = sandbox.get_loaded_sandbox().unwrap();
+    let snapshot = loaded.snapshot().unwrap();
+
+    let monitor = WallClockMonitor::new(Duration::from_millis(300)).unwrap();
+    let result = loaded.handle_event("handler", r#"{"runtime": 10}"#.to_string(), None);
+    assert!(result.is_ok(), "Fast handler should complete: {:?}", result);
+    let event = r#"{"runtime": 5000}"#;
+    let result = loaded.handle_event_with_monitor("handler", event.to_string(), &monitor, None);
+    assert!(result.is_err(), "Slow handler should be killed");
+    loaded.restore(snapshot).unwrap();
+    loaded.unload().unwrap();
+
+    assert_eq!(
+        *observer.0.lock().unwrap(),
+        [
+            "loaded",
+            "start handler 1",
+            "end handler 1 false",
+            "start handler 2",
+            "fired handler wall-clock",
+            "poisoned handler MonitorTimeout",
+            "end handler 2 true",
+            "restored",
+            "unloaded",
+        ]
+    );
+}
@@ -155,3 +155,53 @@ fn print_writes_to_host_print_function() {
     assert!(res.is_ok());
     assert_eq!(output(), "");
 }
+
+#[test]
+fn console_time_writes_to_host_print_function() {
+    let handler = Script::from_content(
+        r#"
+    function handler(event) {
+        console.time();
+        console.time("work");
+        console.timeLog("work", "halfway", 1);
+        console.timeEnd("work");
+        console.timeEnd("work");
+        console.time();
+        console.timeEnd();
+        return event
+    }
+    "#,
+    );
+
+    let (fn_writer, output) = host_print_fn();
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_host_print_fn(fn_writer.into())
+        .build()
+        .unwrap();
+
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox.handle_event("handler", "{}".to_string(), None);
+    assert!(res.is_ok());
+    let output = output();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 5, "{output}");
+    assert!(lines[0].starts_with("work: "), "{output}");
+    assert!(lines[0].ends_with("ms halfway 1"), "{output}");
+    assert!(lines[1].starts_with("work: "), "{output}");
+    assert!(lines[1].ends_with("ms"), "{output}");
+    assert_eq!(
+        lines[2],
+        "Warning: No such label 'work' for console.timeEnd()"
+    );
+    assert_eq!(
+        lines[3],
+        "Warning: Label 'default' already exists for console.time()"
+    );
+    assert!(lines[4].starts_with("default: "), "{output}");
+}
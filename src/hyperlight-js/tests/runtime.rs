@@ -17,7 +17,12 @@ limitations under the License.
 
 #![allow(clippy::disallowed_macros)]
 
-use hyperlight_js::{SandboxBuilder, Script};
+use std::time::Duration;
+
+use hyperlight_js::{
+    EventTooLarge, GcPolicy, GuestOutOfMemory, IsolationMode, ResultTooLarge, SandboxBuilder,
+    Script,
+};
 
 #[test]
 fn js_date_time_now_is_correct() {
@@ -145,3 +150,666 @@ fn async_support() {
         .unwrap();
     assert_eq!(res, "1234");
 }
+
+#[test]
+fn frozen_builtins_prevent_prototype_pollution() {
+    let polluter = Script::from_content(
+        r#"
+        function handler(event) {
+            try {
+                Array.prototype.polluted = "yes";
+            } catch (e) {
+                return { blocked: e instanceof TypeError };
+            }
+            return { blocked: false };
+        }
+        "#,
+    );
+
+    let victim = Script::from_content(
+        r#"
+        function handler(event) {
+            return { polluted: [].polluted ?? null };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_frozen_builtins(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("polluter", polluter).unwrap();
+    sandbox.add_handler("victim", victim).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("polluter", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"blocked":true}"#);
+
+    let res = loaded_sandbox
+        .handle_event("victim", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"polluted":null}"#);
+}
+
+#[test]
+fn frozen_builtins_allow_overriding_inherited_properties() {
+    let handler = Script::from_content(
+        r#"
+        class MyError extends Error {
+            constructor(message) {
+                super(message);
+                this.name = "MyError";
+            }
+        }
+
+        function handler(event) {
+            const obj = {};
+            obj.toString = () => "custom";
+            const err = new MyError("boom");
+            return { name: err.name, str: `${obj}`, errorName: Error.prototype.name };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_frozen_builtins(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        res,
+        r#"{"name":"MyError","str":"custom","errorName":"Error"}"#
+    );
+}
+
+#[test]
+fn per_handler_realms_isolate_globals() {
+    let writer = Script::from_content(
+        r#"
+        function handler(event) {
+            globalThis.shared = "leaked";
+            Array.prototype.polluted = "yes";
+            return {};
+        }
+        "#,
+    );
+
+    let reader = Script::from_content(
+        r#"
+        import { readFile } from "fs";
+        function handler(event) {
+            return {
+                shared: globalThis.shared ?? null,
+                polluted: [].polluted ?? null,
+                host: readFile("config"),
+            };
+        }
+        "#,
+    );
+
+    let mut proto_js_sandbox = SandboxBuilder::new()
+        .with_isolation_mode(IsolationMode::PerHandler)
+        .build()
+        .unwrap();
+    proto_js_sandbox
+        .register("fs", "readFile", |name: String| {
+            format!("contents of {name}")
+        })
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("writer", writer).unwrap();
+    sandbox.add_handler("reader", reader).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    loaded_sandbox
+        .handle_event("writer", "{}".to_string(), None)
+        .unwrap();
+    let res = loaded_sandbox
+        .handle_event("reader", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        res,
+        r#"{"shared":null,"polluted":null,"host":"contents of config"}"#
+    );
+}
+
+#[test]
+fn dynamic_code_can_be_disabled() {
+    let handler = Script::from_content(
+        r#"
+        const attempt = (f) => {
+            try {
+                f();
+                return "allowed";
+            } catch (e) {
+                return e.name;
+            }
+        };
+        function handler(event) {
+            return {
+                eval: attempt(() => eval("1 + 1")),
+                function: attempt(() => new Function("return 1")),
+                asyncFunction: attempt(() => (async () => {}).constructor("return 1")),
+                instanceOf: (() => {}) instanceof Function,
+            };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_dynamic_code_disabled()
+        .with_frozen_builtins(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        res,
+        r#"{"eval":"EvalError","function":"EvalError","asyncFunction":"EvalError","instanceOf":true}"#
+    );
+}
+
+#[test]
+fn shared_memory_primitives_are_not_supported() {
+    let handler = Script::from_content(
+        r#"
+        function attempt(f) {
+            try {
+                f();
+                return "no error";
+            } catch (err) {
+                return err instanceof Error ? err.name : "not an Error";
+            }
+        }
+
+        function handler(event) {
+            return {
+                construct: attempt(() => new SharedArrayBuffer(8)),
+                call: attempt(() => SharedArrayBuffer(8)),
+                wait: attempt(() => Atomics.wait(new Int32Array(1), 0, 0)),
+                add: attempt(() => Atomics.add(new Int32Array(1), 0, 1)),
+            };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        res,
+        r#"{"construct":"NotSupportedError","call":"NotSupportedError","wait":"NotSupportedError","add":"NotSupportedError"}"#
+    );
+}
+
+#[test]
+fn memory_usage_reports_guest_heap_growth() {
+    let handler = Script::from_content(
+        r#"
+        const cache = [];
+
+        function handler(event) {
+            for (let i = 0; i < 1000; i++) {
+                cache.push({ index: i, label: `entry ${i}` });
+            }
+            return { size: cache.length };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let before = loaded_sandbox.memory_usage().unwrap();
+    assert!(before.object_count > 0);
+    assert!(before.memory_used_size > 0);
+
+    loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+
+    let after = loaded_sandbox.memory_usage().unwrap();
+    assert!(after.object_count >= before.object_count + 1000);
+    assert!(after.memory_used_size > before.memory_used_size);
+}
+
+#[test]
+fn memory_usage_is_attributed_to_the_handlers_holding_it() {
+    let hoarder = Script::from_content(
+        r#"
+        const cache = [];
+
+        function handler(event) {
+            for (let i = 0; i < 1000; i++) {
+                cache.push({ index: i, label: `entry ${i}` });
+            }
+            return { size: cache.length };
+        }
+        "#,
+    );
+    let echo = Script::from_content("function handler(event) { return event; }");
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_isolation_mode(IsolationMode::PerHandler)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("hoarder", hoarder).unwrap();
+    sandbox.add_handler("echo", echo).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let before = loaded_sandbox.memory_usage_by_handler().unwrap();
+    assert_eq!(
+        before.keys().collect::<Vec<_>>(),
+        ["echo", "hoarder"],
+        "{before:?}"
+    );
+    // Each handler holds its realm
+    assert!(before.values().all(|&bytes| bytes > 0), "{before:?}");
+
+    for _ in 0..2 {
+        loaded_sandbox
+            .handle_event("hoarder", "{}".to_string(), None)
+            .unwrap();
+        loaded_sandbox
+            .handle_event("echo", r#"{"value": 1}"#.to_string(), None)
+            .unwrap();
+    }
+
+    let after = loaded_sandbox.memory_usage_by_handler().unwrap();
+    assert!(
+        after["hoarder"] >= before["hoarder"] + 2000 * 16,
+        "{before:?} {after:?}"
+    );
+    // The garbage of the echo handler was collected after every call
+    assert!(
+        after["echo"] < before["echo"] + 1024,
+        "{before:?} {after:?}"
+    );
+    let total = loaded_sandbox.memory_usage().unwrap().malloc_size;
+    assert!(after.values().sum::<u64>() < total);
+}
+
+#[test]
+fn run_gc_collects_garbage_left_by_handlers() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            // Reference cycles are only freed by the garbage collector
+            for (let i = 0; i < 1000; i++) {
+                const a = {};
+                const b = { a };
+                a.b = b;
+            }
+            return {};
+        }
+        "#,
+    );
+
+    // Keep QuickJS from collecting the cycles on its own
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_js_gc_threshold(64 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    loaded_sandbox
+        .handle_event("handler", "{}".to_string(), Some(false))
+        .unwrap();
+    let before = loaded_sandbox.memory_usage().unwrap();
+
+    loaded_sandbox.run_gc().unwrap();
+    let after = loaded_sandbox.memory_usage().unwrap();
+
+    assert!(after.object_count + 2000 <= before.object_count);
+}
+
+#[test]
+fn call_raw_calls_guest_functions() {
+    let handler = Script::from_content(
+        r#"
+        const cache = [];
+
+        function handler(event) {
+            cache.push({});
+            return {};
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    loaded_sandbox.call_raw::<()>("run_gc", ()).unwrap();
+    let stats: String = loaded_sandbox.call_raw("memory_usage", ()).unwrap();
+    assert!(stats.contains("object_count"));
+
+    // The handlers still work after raw calls
+    loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+}
+
+#[test]
+fn failed_invocations_report_heap_changes() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            globalThis.leaked = [];
+            for (let i = 0; i < 100; i++) {
+                globalThis.leaked.push({ index: i });
+            }
+            throw new Error("handler bug");
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_heap_reports(true)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("handler bug"), "{message}");
+    assert!(message.contains("+1 globals"), "{message}");
+
+    let diagnostics = loaded_sandbox.collect_diagnostics();
+    assert!(diagnostics.configuration.heap_reports);
+    let heap = diagnostics.recent_invocations[0].heap.unwrap();
+    assert_eq!(heap.global_count_delta(), Some(1));
+    assert!(heap.object_count_delta().unwrap() >= 100);
+}
+
+#[test]
+fn gc_policy_collects_every_n_calls() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            for (let i = 0; i < 1000; i++) {
+                const a = {};
+                const b = { a };
+                a.b = b;
+            }
+            return {};
+        }
+        "#,
+    );
+
+    // Keep QuickJS from collecting the cycles on its own
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_gc_policy(GcPolicy::EveryNCalls(3))
+        .with_js_gc_threshold(64 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let mut call = || {
+        loaded_sandbox
+            .handle_event("handler", "{}".to_string(), None)
+            .unwrap();
+        loaded_sandbox.memory_usage().unwrap().object_count
+    };
+
+    let first = call();
+    let second = call();
+    assert!(second >= first + 2000);
+    let third = call();
+    assert!(third + 4000 <= second);
+}
+
+#[test]
+fn js_memory_limit_throws_before_exhausting_the_guest_heap() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const chunks = [];
+            try {
+                for (;;) chunks.push(new Array(10000).fill(1));
+            } catch (err) {
+                return { error: `${err.name}: ${err.message}` };
+            }
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_guest_heap_size(32 * 1024 * 1024)
+        .with_js_memory_limit(8 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let res = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"error":"InternalError: out of memory"}"#);
+    assert!(!loaded_sandbox.poisoned());
+}
+
+#[test]
+fn uncaught_out_of_memory_errors_are_recognized() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            const chunks = [];
+            for (;;) chunks.push(new Array(10000).fill(1));
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_guest_heap_size(32 * 1024 * 1024)
+        .with_js_memory_limit(8 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    let err = loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap_err();
+    assert_eq!(
+        GuestOutOfMemory::from_error(&err),
+        Some(GuestOutOfMemory { aborted: false })
+    );
+    assert!(!loaded_sandbox.poisoned());
+}
+
+#[test]
+fn max_result_size_rejects_large_results() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            return { data: "x".repeat(event.length) };
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_max_result_size(100)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+
+    // {"data":"..."} is 11 bytes on top of the data
+    let res = loaded_sandbox
+        .handle_event("handler", r#"{"length": 89}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(res.len(), 100);
+
+    let err = loaded_sandbox
+        .handle_event("handler", r#"{"length": 1000}"#.to_string(), None)
+        .unwrap_err();
+    assert_eq!(
+        ResultTooLarge::from_error(&err),
+        Some(ResultTooLarge {
+            size: 1011,
+            limit: 100
+        })
+    );
+    assert!(!loaded_sandbox.poisoned());
+}
+
+#[test]
+fn events_too_large_for_the_input_buffer_are_rejected() {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            return { length: event.data.length };
+        }
+        "#,
+    );
+    let event = format!(r#"{{"data": "{}"}}"#, "x".repeat(64 * 1024));
+
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler.clone()).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let sequence = loaded_sandbox.sequence();
+
+    let err = loaded_sandbox
+        .handle_event("handler", event.clone(), None)
+        .unwrap_err();
+    let too_large = EventTooLarge::from_error(&err).unwrap();
+    assert_eq!(too_large.size, event.len());
+    assert!(too_large.limit < too_large.size);
+    assert!(err.to_string().contains("with_guest_input_buffer_size"));
+    assert!(!loaded_sandbox.poisoned());
+    assert_eq!(loaded_sandbox.sequence(), sequence);
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_guest_input_buffer_size(128 * 1024)
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    let res = loaded_sandbox
+        .handle_event("handler", event.clone(), None)
+        .unwrap();
+    assert_eq!(res, r#"{"length":65536}"#);
+
+    // Batches are checked as a whole
+    let sequence = loaded_sandbox.sequence();
+    let err = loaded_sandbox
+        .handle_events("handler", vec![event.clone(), event.clone()], None)
+        .unwrap_err();
+    assert!(EventTooLarge::from_error(&err).is_some());
+    let large_event = format!(r#"{{"data": "{}"}}"#, "x".repeat(160 * 1024));
+    let err = loaded_sandbox
+        .handle_event_multi(&["handler"], large_event, None)
+        .unwrap_err();
+    assert!(EventTooLarge::from_error(&err).is_some());
+    assert!(!loaded_sandbox.poisoned());
+    assert_eq!(loaded_sandbox.sequence(), sequence);
+}
+
+#[test]
+fn profiling_samples_the_call_stack() {
+    let handler = Script::from_content(
+        r#"
+        function spin(ms) {
+            const start = Date.now();
+            while (Date.now() - start < ms) {}
+        }
+
+        function handler(event) {
+            spin(event.ms);
+            return {};
+        }
+        "#,
+    );
+
+    let proto_js_sandbox = SandboxBuilder::new()
+        .with_profiling(Duration::from_millis(1))
+        .build()
+        .unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    loaded_sandbox.take_profile().unwrap();
+
+    loaded_sandbox
+        .handle_event("handler", r#"{"ms": 50}"#.to_string(), None)
+        .unwrap();
+
+    let profile = loaded_sandbox.take_profile().unwrap();
+    let mut samples = 0;
+    for line in profile.lines() {
+        let (stack, count) = line.rsplit_once(' ').unwrap();
+        samples += count.parse::<u64>().unwrap();
+        assert!(stack.starts_with("handler ("), "{profile}");
+    }
+    assert!(samples > 0, "{profile}");
+    assert!(profile.contains(";spin ("), "{profile}");
+    assert_eq!(loaded_sandbox.take_profile().unwrap(), "");
+
+    // Without profiling, there are no samples
+    let proto_js_sandbox = SandboxBuilder::new().build().unwrap();
+    let mut sandbox = proto_js_sandbox.load_runtime().unwrap();
+    sandbox
+        .add_handler(
+            "handler",
+            Script::from_content("function handler() { return {}; }"),
+        )
+        .unwrap();
+    let mut loaded_sandbox = sandbox.get_loaded_sandbox().unwrap();
+    loaded_sandbox
+        .handle_event("handler", "{}".to_string(), None)
+        .unwrap();
+    assert_eq!(loaded_sandbox.take_profile().unwrap(), "");
+}
@@ -0,0 +1,182 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Scheduler Integration Tests
+
+#![allow(clippy::disallowed_macros)]
+
+use std::thread;
+use std::time::Duration;
+
+use hyperlight_js::{
+    LoadedJSSandbox, OverlapPolicy, RunOutcome, SandboxBuilder, Schedule, ScheduledJob, Scheduler,
+    Script,
+};
+
+fn load() -> hyperlight_js::Result<LoadedJSSandbox> {
+    let handler = Script::from_content(
+        r#"
+        function handler(event) {
+            if (event.job !== "tick" || typeof event.scheduledTime !== "number") {
+                throw new Error("unexpected event");
+            }
+            return event;
+        }
+        "#,
+    );
+    let busy = Script::from_content(
+        r#"
+        function handler(event) {
+            const start = Date.now();
+            while (Date.now() - start < event.ms) {}
+            if (event.fail) {
+                throw new Error("failed");
+            }
+            return {};
+        }
+        "#,
+    );
+    let mut sandbox = SandboxBuilder::new().build()?.load_runtime()?;
+    sandbox.add_handler("tick", handler)?;
+    sandbox.add_handler("busy", busy)?;
+    sandbox.get_loaded_sandbox()
+}
+
+#[test]
+fn jobs_run_on_their_schedule() {
+    let scheduler = Scheduler::new(load().unwrap())
+        .with_job(ScheduledJob::new(
+            "tick",
+            "tick",
+            Schedule::every(Duration::from_millis(50)),
+        ))
+        .start()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(400));
+    let stats = scheduler.stats("tick").unwrap();
+    let history = scheduler.history();
+    assert!(scheduler.stats("unknown").is_none());
+    scheduler.stop().unwrap();
+
+    assert!(stats.runs >= 3, "{stats:?}");
+    assert_eq!(stats.succeeded, stats.runs, "{history:?}");
+    assert!(stats.last_run.is_some());
+    assert!(stats.next_run.is_some());
+    assert!(history
+        .iter()
+        .all(|run| run.job == "tick" && run.outcome == RunOutcome::Succeeded));
+}
+
+#[test]
+fn failed_runs_are_recorded() {
+    let scheduler = Scheduler::new(load().unwrap())
+        .with_history_size(2)
+        .with_job(
+            ScheduledJob::new("fail", "busy", Schedule::every(Duration::from_millis(50)))
+                .with_event(r#"{"ms": 0, "fail": true}"#),
+        )
+        .start()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(400));
+    let stats = scheduler.stats("fail").unwrap();
+    let history = scheduler.history();
+    drop(scheduler);
+
+    assert!(stats.runs >= 3, "{stats:?}");
+    assert_eq!(stats.failed, stats.runs);
+    assert_eq!(history.len(), 2);
+    assert!(
+        matches!(&history[0].outcome, RunOutcome::Failed(message) if message.contains("failed"))
+    );
+}
+
+#[test]
+fn runs_due_while_busy_are_skipped() {
+    let scheduler = Scheduler::new(load().unwrap())
+        .with_job(
+            ScheduledJob::new("slow", "busy", Schedule::every(Duration::from_millis(20)))
+                .with_event(r#"{"ms": 150}"#)
+                .with_overlap_policy(OverlapPolicy::Skip),
+        )
+        .start()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(500));
+    let stats = scheduler.stats("slow").unwrap();
+    scheduler.stop().unwrap();
+
+    assert!(stats.runs >= 1, "{stats:?}");
+    assert!(stats.skipped >= stats.runs, "{stats:?}");
+}
+
+#[test]
+fn duplicate_jobs_are_rejected() {
+    let schedule = Schedule::every(Duration::from_secs(1));
+    let result = Scheduler::new(load().unwrap())
+        .with_job(ScheduledJob::new("tick", "tick", schedule.clone()))
+        .with_job(ScheduledJob::new("tick", "tick", schedule))
+        .start();
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn zero_intervals_are_rejected() {
+    let result = Scheduler::new(load().unwrap())
+        .with_job(ScheduledJob::new(
+            "tick",
+            "tick",
+            Schedule::every(Duration::ZERO),
+        ))
+        .start();
+
+    assert!(result.is_err());
+}
+
+#[test]
+#[cfg(feature = "monitor-wall-clock")]
+fn poisoned_sandboxes_are_reloaded() {
+    use hyperlight_js::WallClockMonitor;
+
+    let scheduler = Scheduler::new(load().unwrap())
+        .with_reload(load)
+        .with_job(
+            ScheduledJob::new(
+                "timeout",
+                "busy",
+                Schedule::every(Duration::from_millis(50)),
+            )
+            .with_event(r#"{"ms": 10000}"#)
+            .with_monitor(WallClockMonitor::new(Duration::from_millis(50)).unwrap()),
+        )
+        .with_job(ScheduledJob::new(
+            "tick",
+            "tick",
+            Schedule::every(Duration::from_millis(50)),
+        ))
+        .start()
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(600));
+    let timeout = scheduler.stats("timeout").unwrap();
+    let tick = scheduler.stats("tick").unwrap();
+    scheduler.stop().unwrap();
+
+    assert!(timeout.timed_out >= 2, "{timeout:?}");
+    assert!(tick.succeeded >= 1, "{tick:?}");
+    assert_eq!(tick.failed, 0, "{tick:?}");
+}
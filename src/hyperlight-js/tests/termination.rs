@@ -21,7 +21,7 @@ use std::sync::{Arc, Barrier};
 use std::thread;
 use std::time::Duration;
 
-use hyperlight_js::{HyperlightError, Result, SandboxBuilder, Script};
+use hyperlight_js::{HyperlightError, PoisonReason, Result, SandboxBuilder, Script};
 
 #[ignore]
 #[test]
@@ -105,6 +105,7 @@ fn handle_termination() -> Result<()> {
         loaded_sandbox.poisoned(),
         "Sandbox should be poisoned after interruption"
     );
+    assert_eq!(loaded_sandbox.poisoned_reason(), Some(PoisonReason::Killed));
 
     // Restore the sandbox from snapshot
     loaded_sandbox.restore(snapshot)?;
@@ -114,6 +115,7 @@ fn handle_termination() -> Result<()> {
         !loaded_sandbox.poisoned(),
         "Sandbox should not be poisoned after restore"
     );
+    assert_eq!(loaded_sandbox.poisoned_reason(), None);
 
     Ok(())
 }
@@ -0,0 +1,109 @@
+/*
+Copyright 2026  The Hyperlight Authors.
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+//! Mock Sandbox Integration Tests
+
+#![cfg(feature = "testing")]
+#![allow(clippy::disallowed_macros)]
+
+use hyperlight_js::testing::{MockSandbox, RecordedHostCall};
+use hyperlight_js::{new_error, Script};
+
+#[test]
+fn mock_sandbox_runs_handlers_and_records_host_calls() {
+    let mut sandbox = MockSandbox::new().unwrap();
+    sandbox
+        .register("math", "add", |a: i32, b: i32| a + b)
+        .unwrap();
+    sandbox
+        .register_raw("math", "fail", |args: String| {
+            Err(new_error!("nope {args}"))
+        })
+        .unwrap();
+
+    let handler = Script::from_content(
+        r#"
+        import { add, fail } from "math";
+        function handler(event) {
+            try { fail("nope"); } catch (e) {}
+            return { sum: add(event.a, event.b) };
+        }
+        "#,
+    );
+    sandbox.add_handler("handler", handler).unwrap();
+
+    let result = sandbox.handle_event("handler", r#"{"a":1,"b":2}"#).unwrap();
+    assert_eq!(result, r#"{"sum":3}"#);
+
+    let calls = sandbox.host_calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].function, "fail");
+    assert!(calls[0].result.as_ref().unwrap_err().contains("nope"));
+    assert_eq!(
+        calls[1],
+        RecordedHostCall {
+            module: "math".to_string(),
+            function: "add".to_string(),
+            args: "[1,2]".to_string(),
+            result: Ok("3".to_string()),
+        }
+    );
+
+    sandbox.clear_host_calls();
+    assert!(sandbox.host_calls().is_empty());
+}
+
+#[test]
+fn mock_sandbox_reloads_handlers_added_after_a_call() {
+    let mut sandbox = MockSandbox::new().unwrap();
+    sandbox
+        .add_handler(
+            "first",
+            Script::from_content("function handler() { return 1; }"),
+        )
+        .unwrap();
+    assert_eq!(sandbox.handle_event("first", "{}").unwrap(), "1");
+
+    sandbox
+        .add_handler(
+            "second",
+            Script::from_content("function handler() { return 2; }"),
+        )
+        .unwrap();
+    assert_eq!(sandbox.handle_event("first", "{}").unwrap(), "1");
+    assert_eq!(sandbox.handle_event("second", "{}").unwrap(), "2");
+}
+
+#[test]
+fn mock_sandbox_rejects_late_host_functions_and_missing_handlers() {
+    let mut sandbox = MockSandbox::new().unwrap();
+    let err = sandbox.handle_event("handler", "{}").unwrap_err();
+    assert!(err.to_string().contains("No handlers"));
+
+    sandbox
+        .add_handler(
+            "handler",
+            Script::from_content("function handler(event) { return event; }"),
+        )
+        .unwrap();
+    let err = sandbox.register("math", "add", |a: i32| a).unwrap_err();
+    assert!(err.to_string().contains("before the first handler"));
+
+    // A failed add keeps the handlers already added
+    assert!(sandbox
+        .add_handler("handler", Script::from_content("function handler() {}"))
+        .is_err());
+    assert_eq!(sandbox.handle_event("handler", "[1]").unwrap(), "[1]");
+}
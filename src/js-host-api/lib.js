@@ -13,39 +13,17 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
-// ── Hyperlight JS Host API — Error enrichment wrapper ────────────────
+// ── Hyperlight JS Host API — JavaScript wrapper ──────────────────────
 //
-// This module re-exports the native napi-rs binding from index.js and
-// enriches thrown errors with structured `error.code` values.
+// This module re-exports the native napi-rs binding from index.js, with
+// the few adjustments that are simpler to make in JavaScript:
 //
-// ## Why this exists
+// - host function and `withSandbox()` callbacks may be sync or async, while
+//   the native side expects them to return a Promise
+// - `callHandler()` accepts an `AbortSignal`, which can't be passed to the
+//   native side
 //
-// napi-rs only implements `ToNapiValue` for `Result<T, Error<Status>>`,
-// not for custom error status types. Since all our methods are async
-// (using `spawn_blocking`), we can't use `Error<CustomStatus>` — the
-// async return path requires `ToNapiValue` on the Result.
-//
-// Our Rust code embeds domain-specific error codes as `[ERR_*]` prefixes
-// in error messages. This wrapper parses those prefixes and promotes
-// them to `error.code`, giving consumers idiomatic Node.js error handling:
-//
-//   catch (e) {
-//     if (e.code === 'ERR_POISONED') { await loaded.restore(snapshot); }
-//   }
-//
-// ## What would fix this upstream
-//
-// napi-rs would need to make `ToNapiValue` generic over the error status:
-//
-//   impl<T, S: AsRef<str>> ToNapiValue for Result<T, Error<S>> { ... }
-//
-// instead of the current:
-//
-//   impl<T> ToNapiValue for Result<T> { ... }  // Result<T> = Result<T, Error<Status>>
-//
-// See: napi-rs/napi-rs — crates/napi/src/bindgen_runtime/js_values.rs
-// When that lands, this wrapper can be removed and the Rust side can use
-// `Error<ErrorCode>` directly on async functions.
+// Errors need no adjustment: the native side sets their `code` itself.
 //
 // ─────────────────────────────────────────────────────────────────────
 
@@ -53,158 +31,126 @@ limitations under the License.
 
 const native = require('./index.js');
 
-// ── Error enrichment ─────────────────────────────────────────────────
-
-// Matches `[ERR_CODE]` prefix at the start of error messages.
-// The Rust side formats errors as: `[ERR_POISONED] sandbox is poisoned`
-const ERROR_CODE_RE = /^\[(ERR_\w+)\]\s*/;
+// ── Helpers ──────────────────────────────────────────────────────────
 
 /**
- * Extracts a `[ERR_*]` prefix from an error's message and promotes it
- * to `error.code`. Strips the prefix from the message for cleanliness.
+ * Creates the `ERR_CANCELLED` error of a call whose signal aborted before
+ * it started.
  *
- * @param {Error} err — the error to enrich (mutated in place)
- * @returns {Error} the same error, for chaining
+ * @param {AbortSignal} signal — the aborted signal
+ * @param {string} message — the message of the error
+ * @returns {Error} an error with the abort reason as `cause`
  */
-function enrichError(err) {
-    if (err instanceof Error) {
-        const match = err.message.match(ERROR_CODE_RE);
-        if (match) {
-            err.code = match[1];
-            err.message = err.message.slice(match[0].length);
-        }
-    }
+function abortedError(signal, message) {
+    const err = new Error(message, { cause: signal.reason });
+    err.code = 'ERR_CANCELLED';
     return err;
 }
 
 /**
- * Wraps an async method so that rejected promises have enriched errors.
- *
- * @param {Function} fn — the original async method
- * @returns {Function} a wrapper that catches and enriches errors
- */
-function wrapAsync(fn) {
-    return async function (...args) {
-        try {
-            return await fn.apply(this, args);
-        } catch (err) {
-            throw enrichError(err);
-        }
-    };
-}
-
-/**
- * Wraps a sync method so that thrown errors have enriched codes.
+ * Calls `call` with a handle from createCallAbort() in place of `signal`,
+ * aborted from a listener removed when the call settles, so a long-lived
+ * signal doesn't collect a listener per call.
  *
- * @param {Function} fn — the original sync method
- * @returns {Function} a wrapper that catches and enriches errors
+ * @param {AbortSignal} signal — the signal of the call
+ * @param {string} message — the message of the error if it already aborted
+ * @param {(abort: object) => Promise<any>} call — makes the native call
+ * @returns {Promise<any>} the result of the call
  */
-function wrapSync(fn) {
-    return function (...args) {
-        try {
-            return fn.apply(this, args);
-        } catch (err) {
-            throw enrichError(err);
+async function withCallAbort(signal, message, call) {
+    if (signal.aborted) {
+        throw abortedError(signal, message);
+    }
+    const abort = native.createCallAbort();
+    const onAbort = () => native.abortCall(abort);
+    signal.addEventListener('abort', onAbort, { once: true });
+    try {
+        return await call(abort);
+    } catch (err) {
+        if (signal.aborted && err.code === 'ERR_CANCELLED') {
+            err.cause = signal.reason;
         }
-    };
+        throw err;
+    } finally {
+        signal.removeEventListener('abort', onAbort);
+    }
 }
 
 // ── Prototype patching ───────────────────────────────────────────────
 //
 // We patch the native class prototypes when this module is loaded so that
 // all consumers in the same process (including code that later requires
-// index.js directly) get enriched errors. The native binding module is
+// index.js directly) get the same behavior. The native binding module is
 // cached by require(), so prototypes are patched once per process, after
 // this module has been required at least once.
 
-const { LoadedJSSandbox, JSSandbox, ProtoJSSandbox, SandboxBuilder, HostModule } = native;
+const { LoadedJSSandbox, ProtoJSSandbox, HostModule, SandboxPool } = native;
 
-/**
- * Wrap a getter so that thrown errors have enriched codes.
- *
- * @param {Function} cls    — the class whose prototype to patch
- * @param {string}   prop   — the property name with a getter
- */
-function wrapGetter(cls, prop) {
-    const desc = Object.getOwnPropertyDescriptor(cls.prototype, prop);
-    if (!desc || !desc.get) {
-        throw new Error(`Cannot wrap missing getter: ${cls.name}.${prop}`);
-    }
-    const origGet = desc.get;
-    Object.defineProperty(cls.prototype, prop, {
-        ...desc,
-        get() {
-            try {
-                return origGet.call(this);
-            } catch (err) {
-                throw enrichError(err);
-            }
-        },
-    });
-}
-
-// LoadedJSSandbox — async methods
-// Note: `poisoned` (AtomicBool read) and `interruptHandle` (Arc clone)
-// are infallible getters — no wrapping needed.
-for (const method of ['callHandler', 'unload', 'snapshot', 'restore']) {
-    const orig = LoadedJSSandbox.prototype[method];
-    if (!orig) throw new Error(`Cannot wrap missing method: LoadedJSSandbox.${method}`);
-    LoadedJSSandbox.prototype[method] = wrapAsync(orig);
-}
-
-// JSSandbox — async + sync methods + getters
-JSSandbox.prototype.getLoadedSandbox = wrapAsync(JSSandbox.prototype.getLoadedSandbox);
-
-for (const method of ['addHandler', 'removeHandler', 'clearHandlers']) {
-    const orig = JSSandbox.prototype[method];
-    if (!orig) throw new Error(`Cannot wrap missing method: JSSandbox.${method}`);
-    JSSandbox.prototype[method] = wrapSync(orig);
+// LoadedJSSandbox — callHandler() kills the guest when `options.signal` aborts.
+// An AbortSignal can't be passed to the native side, so we pass a handle from
+// createCallAbort() instead, see withCallAbort().
+{
+    const origCallHandler = LoadedJSSandbox.prototype.callHandler;
+    LoadedJSSandbox.prototype.callHandler = async function (handlerName, eventData, options) {
+        const signal = options?.signal;
+        if (signal == null) {
+            return origCallHandler.call(this, handlerName, eventData, options);
+        }
+        return withCallAbort(signal, 'Handler call aborted', (abort) =>
+            origCallHandler.call(this, handlerName, eventData, { ...options, signal: abort })
+        );
+    };
 }
-wrapGetter(JSSandbox, 'poisoned');
-
-// ProtoJSSandbox — async + sync methods
-ProtoJSSandbox.prototype.loadRuntime = wrapAsync(ProtoJSSandbox.prototype.loadRuntime);
-
-// hostModule() is sync — just wrap for error enrichment
-ProtoJSSandbox.prototype.hostModule = wrapSync(ProtoJSSandbox.prototype.hostModule);
 
-// ProtoJSSandbox — register() handle errors and wraps callback to return Promise
+// ProtoJSSandbox — register() wraps the callback to return a Promise
 {
     const origRegister = ProtoJSSandbox.prototype.register;
-    ProtoJSSandbox.prototype.register = wrapSync(function (moduleName, functionName, callback) {
+    ProtoJSSandbox.prototype.register = function (moduleName, functionName, callback) {
         // the rust code expects the host function to return a Promise, so we wrap the callback result in Promise.resolve().then(..) to allow sync functions as well
         // note that Promise.resolve(callback(...args)) would not work because if callback throws that would not return a rejected promise, it would just throw before returning the promise.
         return origRegister.call(this, moduleName, functionName, (...args) =>
             Promise.resolve().then(() => callback(...args))
         );
-    });
+    };
 }
 
 // HostModule — register()
 {
     const origRegister = HostModule.prototype.register;
     if (!origRegister) throw new Error('Cannot wrap missing method: HostModule.register');
-    HostModule.prototype.register = wrapSync(function (name, callback) {
+    HostModule.prototype.register = function (name, callback) {
         // the rust code expects the host function to return a Promise, so we wrap the callback result in Promise.resolve().then(..) to allow sync functions as well
         // note that Promise.resolve(callback(...args)) would not work because if callback throws that would not return a rejected promise, it would just throw before returning the promise.
         return origRegister.call(this, name, (...args) =>
             Promise.resolve().then(() => callback(...args))
         );
-    });
+    };
 }
 
-// SandboxBuilder — async build + sync setters
-SandboxBuilder.prototype.build = wrapAsync(SandboxBuilder.prototype.build);
-
-for (const method of [
-    'setHeapSize',
-    'setScratchSize',
-    'setInputBufferSize',
-    'setOutputBufferSize',
-]) {
-    const orig = SandboxBuilder.prototype[method];
-    if (!orig) throw new Error(`Cannot wrap missing method: SandboxBuilder.${method}`);
-    SandboxBuilder.prototype[method] = wrapSync(orig);
+// SandboxPool — acquire() stops waiting when `options.signal` aborts, like callHandler()
+{
+    const origAcquire = SandboxPool.prototype.acquire;
+    if (!origAcquire) throw new Error('Cannot wrap missing method: SandboxPool.acquire');
+    SandboxPool.prototype.acquire = async function (options) {
+        const signal = options?.signal;
+        if (signal == null) {
+            return origAcquire.call(this, options);
+        }
+        return withCallAbort(signal, 'Sandbox pool acquire aborted', (abort) =>
+            origAcquire.call(this, { ...options, signal: abort })
+        );
+    };
+}
+
+// SandboxPool — withSandbox() wraps the callback to return a Promise, like register()
+{
+    const origWithSandbox = SandboxPool.prototype.withSandbox;
+    if (!origWithSandbox) throw new Error('Cannot wrap missing method: SandboxPool.withSandbox');
+    SandboxPool.prototype.withSandbox = function (callback) {
+        return origWithSandbox.call(this, (sandbox) =>
+            Promise.resolve().then(() => callback(sandbox))
+        );
+    };
 }
 
 // ── Re-export ────────────────────────────────────────────────────────
@@ -13,21 +13,27 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use hyperlight_js::{
-    CpuTimeMonitor, HyperlightError, InterruptHandle, JSSandbox, LoadedJSSandbox, ProtoJSSandbox,
-    SandboxBuilder, Script, Snapshot, WallClockMonitor,
+    metrics_snapshot, monitor, BoxedMonitorSet, CpuTimeMonitor, EventTooLarge, ExecutionMonitor,
+    FileSystem, FileSystemDirectory, FileSystemMemory, GuestOutOfMemory, HyperlightError,
+    InterruptHandle, JSSandbox, LoadedJSSandbox, PoisonReason, ProtoJSSandbox, ResultTooLarge,
+    SandboxBuilder, SandboxObserver, Script, Snapshot, WallClockMonitor,
+};
+use napi::bindgen_prelude::{
+    spawn, ClassInstance, External, FromNapiValue, JsValuesTupleIntoVec, Promise, PromiseRaw,
+    ToNapiValue,
 };
-use napi::bindgen_prelude::{JsValuesTupleIntoVec, Promise, ToNapiValue};
 use napi::sys::{napi_env, napi_value};
 use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
-use napi::{tokio, Status};
+use napi::{tokio, Env, JsError, JsValue, Status};
 use napi_derive::napi;
 use serde_json::Value as JsonValue;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Notify, Semaphore, TryAcquireError};
 
 // ── napi-rs wrapper architecture ──────────────────────────────────────
 //
@@ -62,40 +68,32 @@ use tokio::sync::oneshot;
 
 // ── Error codes ──────────────────────────────────────────────────────
 //
-// ## Why we embed error codes in the message string
+// ## How errors get their `code`
 //
-// napi-rs supports custom error status types (`Error<S>`) for **sync**
-// functions, but the `ToNapiValue` impl for `Result<T>` (used by async
-// function return paths) is only implemented for `Result<T, Error<Status>>`.
-// Our entire API is async (`spawn_blocking`), so we can't use a custom
-// status type without hitting a compile error.
+// Every error of the API is a `napi::Error<ErrorCode>`. napi-rs turns it
+// into a JS `Error` whose `code` is the `ERR_*` string of the `ErrorCode`,
+// so sync methods simply return a `napi::Result<T, ErrorCode>`.
 //
-// **Workaround**: We use standard `napi::Result<T>` (= `Result<T, Error<Status>>`)
-// and prefix each error message with `[ERR_CODE]`. A thin JavaScript wrapper
-// (`lib.js`) parses the prefix and sets `error.code` on the JS side.
+// Async methods can't: napi-rs rejects the promise of an async method with
+// an `Error<Status>`, created on a background thread, whose `code` is the
+// napi status (e.g. `GenericFailure`). Instead, async methods resolve to a
+// `Settled<T>`, whose `ToNapiValue` runs on the JS thread when the method
+// completes. It turns an error into a promise rejected with the JS error,
+// `code` included, and the promise of the method adopts that rejection.
 //
-// **What would fix this properly**: napi-rs would need to implement
-// `ToNapiValue` for `Result<T, S>` (generic over the error status type),
-// not just `Result<T>`. This would allow:
-// ```rust
-// type HlResult<T> = Result<T, napi::Error<ErrorCode>>;
-// #[napi]
-// pub async fn call_handler(...) -> HlResult<JsonValue> { ... }
-// ```
-// See: https://github.com/napi-rs/napi-rs — `crates/napi/src/bindgen_runtime/js_values.rs`
+// `Settled<T>` is not a JS type, so async methods spell out their TypeScript
+// return type with `ts_return_type`.
 //
-// Until then, this workaround provides structured `error.code` values
-// on the JS side without any consumer-visible hacks.
+// The few places where napi-rs only accepts a `napi::Error` (chainable
+// setters, argument conversion) create the JS error themselves with
+// `js_error()`, and napi-rs throws it as is.
 
 /// Domain-specific error codes for the Hyperlight JS host API.
 ///
 /// Each variant maps to an `ERR_*` string that appears as `error.code`
 /// on the JavaScript side, following the Node.js convention.
-///
-/// These codes are embedded as `[ERR_*]` prefixes in error messages by the
-/// Rust side, then extracted and set as `error.code` by the JS wrapper.
 #[derive(Debug)]
-enum ErrorCode {
+pub enum ErrorCode {
     /// Sandbox is in a poisoned (inconsistent) state — restore or unload.
     Poisoned,
     /// Execution was cancelled by the host (monitor timeout or manual `kill()`).
@@ -106,30 +104,70 @@ enum ErrorCode {
     InvalidArg,
     /// Object has already been consumed — each transition is one-shot.
     Consumed,
+    /// The result of a handler is larger than the `maxResultBytes` of the call,
+    /// or than the maximum result size of the sandbox.
+    ResultTooLarge,
+    /// The event is too large for the input buffer of the sandbox.
+    EventTooLarge,
+    /// The guest ran out of memory, in the JavaScript heap or in the guest heap.
+    OutOfMemory,
     /// Internal / unexpected failure (lock poison, task join error, etc.).
     Internal,
 }
 
-impl ErrorCode {
+impl AsRef<str> for ErrorCode {
     /// Returns the `ERR_*` code string (e.g. `"ERR_POISONED"`).
-    fn as_code(&self) -> &'static str {
+    fn as_ref(&self) -> &str {
         match self {
             Self::Poisoned => "ERR_POISONED",
             Self::Cancelled => "ERR_CANCELLED",
             Self::GuestAbort => "ERR_GUEST_ABORT",
             Self::InvalidArg => "ERR_INVALID_ARG",
             Self::Consumed => "ERR_CONSUMED",
+            Self::ResultTooLarge => "ERR_RESULT_TOO_LARGE",
+            Self::EventTooLarge => "ERR_EVENT_TOO_LARGE",
+            Self::OutOfMemory => "ERR_OOM",
             Self::Internal => "ERR_INTERNAL",
         }
     }
 }
 
-impl std::fmt::Display for ErrorCode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.as_code())
+/// The outcome of an async method, settled on the JS thread so that the
+/// rejection keeps the `code` of the error (see above).
+pub enum Settled<T> {
+    /// The method succeeded with this value.
+    Ok(T),
+    /// The method failed with this error.
+    Err(napi::Error<ErrorCode>),
+    /// A JS callback of the method threw this error, rejected as is.
+    Thrown(napi::Error),
+}
+
+impl<T> From<napi::Result<T, ErrorCode>> for Settled<T> {
+    fn from(result: napi::Result<T, ErrorCode>) -> Self {
+        match result {
+            Ok(value) => Self::Ok(value),
+            Err(err) => Self::Err(err),
+        }
+    }
+}
+
+impl<T: ToNapiValue> ToNapiValue for Settled<T> {
+    unsafe fn to_napi_value(env: napi_env, val: Self) -> napi::Result<napi_value> {
+        let env = Env::from_raw(env);
+        match val {
+            Self::Ok(value) => unsafe { T::to_napi_value(env.raw(), value) },
+            Self::Err(err) => PromiseRaw::<()>::reject(&env, err).map(|promise| promise.raw()),
+            Self::Thrown(err) => PromiseRaw::<()>::reject(&env, err).map(|promise| promise.raw()),
+        }
     }
 }
 
+/// Run the body of an async method, and settle its result.
+async fn settle<T>(body: impl Future<Output = napi::Result<T, ErrorCode>>) -> Settled<T> {
+    body.await.into()
+}
+
 /// Minimum allowed timeout value in milliseconds.
 const MIN_TIMEOUT_MS: u32 = 1;
 
@@ -149,10 +187,8 @@ const MAX_TIMEOUT_MS: u32 = 3_600_000;
 /// library stores the exact module name it receives, with no transformation.
 const HOST_MODULE_PREFIX: &str = "host:";
 
-/// Creates a napi error with a `[ERR_CODE]` prefix in the message.
-///
-/// The JS wrapper (`lib.js`) parses this prefix and promotes it to
-/// `error.code`, giving consumers structured error handling:
+/// Creates an error whose `error.code` is `code` on the JS side, giving
+/// consumers structured error handling:
 ///
 /// ```js
 /// try { await loaded.callHandler(...); }
@@ -160,26 +196,43 @@ const HOST_MODULE_PREFIX: &str = "host:";
 ///     if (e.code === 'ERR_POISONED') { await loaded.restore(snapshot); }
 /// }
 /// ```
-fn hl_error(code: ErrorCode, msg: impl std::fmt::Display) -> napi::Error {
-    napi::Error::new(napi::Status::GenericFailure, format!("[{}] {}", code, msg))
+fn hl_error(code: ErrorCode, msg: impl std::fmt::Display) -> napi::Error<ErrorCode> {
+    napi::Error::new(code, msg.to_string())
 }
 
 // ── Error conversion ─────────────────────────────────────────────────
 
+/// Creates the JS error of `err` in `env`, as a `napi::Error` that napi-rs
+/// throws as is, keeping its code. For the few places where napi-rs only
+/// accepts a `napi::Error`.
+fn js_error(env: Env, err: napi::Error<ErrorCode>) -> napi::Error {
+    napi::Error::from(JsError::from(err).into_unknown(env))
+}
+
 /// Maps [`HyperlightError`] variants to napi errors with structured codes.
-fn to_napi_error(err: HyperlightError) -> napi::Error {
+fn to_napi_error(err: HyperlightError) -> napi::Error<ErrorCode> {
+    // The message of the Rust library names the Rust builder method.
+    if let Some(too_large) = EventTooLarge::from_error(&err) {
+        return hl_error(
+            ErrorCode::EventTooLarge,
+            format!("{too_large}; raise the limit with SandboxBuilder.setInputBufferSize()"),
+        );
+    }
     let code = match &err {
         HyperlightError::PoisonedSandbox => ErrorCode::Poisoned,
         HyperlightError::ExecutionCanceledByHost() => ErrorCode::Cancelled,
         HyperlightError::JsonConversionFailure(_) => ErrorCode::InvalidArg,
+        // Before guest aborts, which out of memory errors can be.
+        err if GuestOutOfMemory::from_error(err).is_some() => ErrorCode::OutOfMemory,
         HyperlightError::GuestAborted(_, _) => ErrorCode::GuestAbort,
+        err if ResultTooLarge::from_error(err).is_some() => ErrorCode::ResultTooLarge,
         _ => ErrorCode::Internal,
     };
     hl_error(code, err)
 }
 
 /// Creates an error for "already consumed" conditions.
-fn consumed_error(type_name: &str) -> napi::Error {
+fn consumed_error(type_name: &str) -> napi::Error<ErrorCode> {
     hl_error(
         ErrorCode::Consumed,
         format!("{type_name} has already been consumed — each instance can only be used once"),
@@ -187,12 +240,12 @@ fn consumed_error(type_name: &str) -> napi::Error {
 }
 
 /// Creates an error for invalid argument conditions.
-fn invalid_arg_error(msg: &str) -> napi::Error {
+fn invalid_arg_error(msg: &str) -> napi::Error<ErrorCode> {
     hl_error(ErrorCode::InvalidArg, msg)
 }
 
 /// Validates a host module name: must be non-empty.
-fn validate_module_name(name: &str) -> napi::Result<()> {
+fn validate_module_name(name: &str) -> napi::Result<(), ErrorCode> {
     if name.is_empty() {
         return Err(invalid_arg_error("Module name must not be empty"));
     }
@@ -200,7 +253,7 @@ fn validate_module_name(name: &str) -> napi::Result<()> {
 }
 
 /// Creates an error when a Mutex is poisoned (Rust-level, not sandbox-level).
-fn lock_error() -> napi::Error {
+fn lock_error() -> napi::Error<ErrorCode> {
     hl_error(
         ErrorCode::Internal,
         "Internal lock poisoned — this is a bug",
@@ -208,7 +261,7 @@ fn lock_error() -> napi::Error {
 }
 
 /// Converts a tokio `JoinError` from `spawn_blocking` into an error.
-fn join_error(err: tokio::task::JoinError) -> napi::Error {
+fn join_error(err: tokio::task::JoinError) -> napi::Error<ErrorCode> {
     hl_error(
         ErrorCode::Internal,
         format!("Background task failed: {err}"),
@@ -252,6 +305,7 @@ pub struct SnapshotWrapper {
 #[napi(js_name = "SandboxBuilder")]
 pub struct SandboxBuilderWrapper {
     inner: Arc<Mutex<Option<SandboxBuilder>>>,
+    listeners: Arc<Mutex<Vec<(&'static str, LifecycleListener)>>>,
 }
 
 impl Default for SandboxBuilderWrapper {
@@ -263,7 +317,7 @@ impl Default for SandboxBuilderWrapper {
 impl SandboxBuilderWrapper {
     /// Apply a builder transformation while holding the lock, or error if
     /// consumed (after `build()` has been called).
-    fn with_inner<F>(&self, f: F) -> napi::Result<&Self>
+    fn with_inner<F>(&self, f: F) -> napi::Result<&Self, ErrorCode>
     where
         F: FnOnce(SandboxBuilder) -> SandboxBuilder,
     {
@@ -275,8 +329,23 @@ impl SandboxBuilderWrapper {
         Ok(self)
     }
 
+    /// Apply a size setter, which requires a size greater than 0.
+    ///
+    /// napi-rs can only chain methods that fail with a `napi::Error`, so the
+    /// error is created in `env` with `js_error()`.
+    fn with_size<F>(&self, env: Env, size: u32, name: &str, f: F) -> napi::Result<&Self>
+    where
+        F: FnOnce(SandboxBuilder) -> SandboxBuilder,
+    {
+        if size == 0 {
+            let err = invalid_arg_error(&format!("{name} must be greater than 0"));
+            return Err(js_error(env, err));
+        }
+        self.with_inner(f).map_err(|err| js_error(env, err))
+    }
+
     /// Take ownership of the inner builder, or error if consumed.
-    fn take_inner(&self) -> napi::Result<SandboxBuilder> {
+    fn take_inner(&self) -> napi::Result<SandboxBuilder, ErrorCode> {
         self.inner
             .lock()
             .map_err(|_| lock_error())?
@@ -292,6 +361,7 @@ impl SandboxBuilderWrapper {
     pub fn new() -> Self {
         Self {
             inner: Arc::new(Mutex::new(Some(SandboxBuilder::new()))),
+            listeners: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -304,13 +374,10 @@ impl SandboxBuilderWrapper {
     /// @returns this (for chaining)
     /// @throws If size is 0
     #[napi]
-    pub fn set_output_buffer_size(&self, size: u32) -> napi::Result<&Self> {
-        if size == 0 {
-            return Err(invalid_arg_error(
-                "Output buffer size must be greater than 0",
-            ));
-        }
-        self.with_inner(|b| b.with_guest_output_buffer_size(size as usize))
+    pub fn set_output_buffer_size(&self, env: Env, size: u32) -> napi::Result<&Self> {
+        self.with_size(env, size, "Output buffer size", |b| {
+            b.with_guest_output_buffer_size(size as usize)
+        })
     }
 
     /// Set the guest input buffer size in bytes.
@@ -322,13 +389,10 @@ impl SandboxBuilderWrapper {
     /// @returns this (for chaining)
     /// @throws If size is 0
     #[napi]
-    pub fn set_input_buffer_size(&self, size: u32) -> napi::Result<&Self> {
-        if size == 0 {
-            return Err(invalid_arg_error(
-                "Input buffer size must be greater than 0",
-            ));
-        }
-        self.with_inner(|b| b.with_guest_input_buffer_size(size as usize))
+    pub fn set_input_buffer_size(&self, env: Env, size: u32) -> napi::Result<&Self> {
+        self.with_size(env, size, "Input buffer size", |b| {
+            b.with_guest_input_buffer_size(size as usize)
+        })
     }
 
     /// Set the guest scratch size in bytes.
@@ -341,28 +405,131 @@ impl SandboxBuilderWrapper {
     /// @returns this (for chaining)
     /// @throws If size is 0
     #[napi]
-    pub fn set_scratch_size(&self, size: u32) -> napi::Result<&Self> {
-        if size == 0 {
-            return Err(invalid_arg_error("Scratch size must be greater than 0"));
-        }
-        self.with_inner(|b| b.with_guest_scratch_size(size as usize))
+    pub fn set_scratch_size(&self, env: Env, size: u32) -> napi::Result<&Self> {
+        self.with_size(env, size, "Scratch size", |b| {
+            b.with_guest_scratch_size(size as usize)
+        })
     }
 
     /// Set the guest heap size in bytes.
     ///
     /// Controls how much heap memory the guest JavaScript engine can
     /// allocate. If handlers create many objects or large strings, increase
-    /// this. Too small will cause `malloc failed` errors in the guest.
+    /// this. Too small will cause `malloc failed` errors in the guest,
+    /// which reject with `ERR_OOM`.
     ///
     /// @param size - Heap size in bytes (must be > 0)
     /// @returns this (for chaining)
     /// @throws If size is 0
     #[napi]
-    pub fn set_heap_size(&self, size: u32) -> napi::Result<&Self> {
-        if size == 0 {
-            return Err(invalid_arg_error("Heap size must be greater than 0"));
-        }
-        self.with_inner(|b| b.with_guest_heap_size(size as u64))
+    pub fn set_heap_size(&self, env: Env, size: u32) -> napi::Result<&Self> {
+        self.with_size(env, size, "Heap size", |b| {
+            b.with_guest_heap_size(size as u64)
+        })
+    }
+
+    /// Limit the memory the JavaScript engine allocates, in bytes.
+    ///
+    /// An allocation over the limit throws an `InternalError: out of memory`
+    /// that handlers can catch. If they don't, the call rejects with
+    /// `ERR_OOM` and the sandbox is not poisoned. Keep it comfortably below
+    /// the heap size. Unlimited by default.
+    ///
+    /// @param size - Limit in bytes (must be > 0)
+    /// @returns this (for chaining)
+    /// @throws If size is 0
+    #[napi]
+    pub fn set_js_memory_limit(&self, env: Env, size: u32) -> napi::Result<&Self> {
+        self.with_size(env, size, "JS memory limit", |b| {
+            b.with_js_memory_limit(size as usize)
+        })
+    }
+
+    /// Forward the output of the guest (`console.log()` and `print()`) to
+    /// `callback`, one line at a time, instead of the stdout of the process.
+    ///
+    /// `callback` runs on the event loop, so output reaches it after the
+    /// guest printed it, usually once the call that printed it settles.
+    ///
+    /// ```js
+    /// const lines = [];
+    /// const proto = await new SandboxBuilder()
+    ///     .setHostPrint((line) => lines.push(line))
+    ///     .build();
+    /// ```
+    ///
+    /// @param callback - `(line: string) => void` — called for each line of output
+    /// @returns this (for chaining)
+    /// @throws If already consumed
+    #[napi]
+    pub fn set_host_print(
+        &self,
+        env: Env,
+        #[napi(ts_arg_type = "(line: string) => void")] callback: ThreadsafeFunction<
+            String,
+            (),
+            String,
+            Status,
+            false,
+            true,
+        >,
+    ) -> napi::Result<&Self> {
+        let print = move |text: String| -> i32 {
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            for line in text.split('\n') {
+                callback.call(line.to_string(), ThreadsafeFunctionCallMode::NonBlocking);
+            }
+            0
+        };
+        self.with_inner(|b| b.with_host_print_fn(print.into()))
+            .map_err(|err| js_error(env, err))
+    }
+
+    /// Call `callback` on each lifecycle event named `event` of the sandbox,
+    /// and of the sandboxes it evolves into:
+    ///
+    /// - `poisoned` — a call poisoned the sandbox, with its `handler` and `reason`
+    /// - `monitorFired` — a monitor terminated a call, with its `handler` and `monitor`
+    /// - `restored` — the sandbox was restored from a snapshot
+    /// - `unloaded` — the handlers were unloaded
+    ///
+    /// Like `setHostPrint()`, `callback` runs on the event loop after the
+    /// event happened.
+    ///
+    /// ```js
+    /// const proto = await new SandboxBuilder()
+    ///     .on('poisoned', ({ handler, reason }) => alert(`${handler}: ${reason.message}`))
+    ///     .build();
+    /// ```
+    ///
+    /// @param event - `poisoned`, `monitorFired`, `restored` or `unloaded`
+    /// @param callback - `(event: SandboxEvent) => void` — called for each event
+    /// @returns this (for chaining)
+    /// @throws If `event` is not a lifecycle event, or if already consumed
+    #[napi]
+    pub fn on(
+        &self,
+        env: Env,
+        event: String,
+        #[napi(ts_arg_type = "(event: SandboxEvent) => void")] callback: LifecycleListener,
+    ) -> napi::Result<&Self> {
+        let Some(event) = LIFECYCLE_EVENTS.iter().find(|name| **name == event) else {
+            let err = invalid_arg_error(&format!(
+                "Unknown sandbox event '{event}', expected one of: {}",
+                LIFECYCLE_EVENTS.join(", ")
+            ));
+            return Err(js_error(env, err));
+        };
+        // Installing the observer again for each listener also checks that
+        // the builder is not consumed.
+        let observer = ListenerObserver(self.listeners.clone());
+        self.with_inner(|b| b.with_observer(observer))
+            .map_err(|err| js_error(env, err))?;
+        self.listeners
+            .lock()
+            .map_err(|_| js_error(env, lock_error()))?
+            .push((event, callback));
+        Ok(self)
     }
 
     /// Build a `ProtoJSSandbox` from this builder's configuration.
@@ -375,16 +542,19 @@ impl SandboxBuilderWrapper {
     ///
     /// @returns A `Promise<ProtoJSSandbox>` ready to load the JavaScript runtime
     /// @throws On resource allocation failure, or if already consumed
-    #[napi]
-    pub async fn build(&self) -> napi::Result<ProtoJSSandboxWrapper> {
-        let builder = self.take_inner()?;
-        let proto_sandbox =
-            tokio::task::spawn_blocking(move || builder.build().map_err(to_napi_error))
-                .await
-                .map_err(join_error)??;
-        Ok(ProtoJSSandboxWrapper {
-            inner: Arc::new(Mutex::new(Some(proto_sandbox))),
+    #[napi(ts_return_type = "Promise<ProtoJSSandbox>")]
+    pub async fn build(&self) -> Settled<ProtoJSSandboxWrapper> {
+        settle(async move {
+            let builder = self.take_inner()?;
+            let proto_sandbox =
+                tokio::task::spawn_blocking(move || builder.build().map_err(to_napi_error))
+                    .await
+                    .map_err(join_error)??;
+            Ok(ProtoJSSandboxWrapper {
+                inner: Arc::new(Mutex::new(Some(proto_sandbox))),
+            })
         })
+        .await
     }
 }
 
@@ -413,9 +583,9 @@ pub struct ProtoJSSandboxWrapper {
 
 impl ProtoJSSandboxWrapper {
     /// Borrow the inner value mutably via Mutex, or error if consumed.
-    fn with_inner_mut<F, R>(&self, f: F) -> napi::Result<R>
+    fn with_inner_mut<F, R>(&self, f: F) -> napi::Result<R, ErrorCode>
     where
-        F: FnOnce(&mut ProtoJSSandbox) -> napi::Result<R>,
+        F: FnOnce(&mut ProtoJSSandbox) -> napi::Result<R, ErrorCode>,
     {
         let mut guard = self.inner.lock().map_err(|_| lock_error())?;
         let sandbox = guard
@@ -424,9 +594,27 @@ impl ProtoJSSandboxWrapper {
         f(sandbox)
     }
 
+    /// Install `file_system` as the module loader of the sandbox, or error
+    /// if consumed.
+    fn set_module_loader<Fs: FileSystem + Clone + 'static>(
+        &self,
+        file_system: Fs,
+    ) -> napi::Result<(), ErrorCode> {
+        let mut guard = self.inner.lock().map_err(|_| lock_error())?;
+        let sandbox = guard
+            .take()
+            .ok_or_else(|| consumed_error("ProtoJSSandbox"))?;
+        *guard = Some(
+            sandbox
+                .set_module_loader(file_system)
+                .map_err(to_napi_error)?,
+        );
+        Ok(())
+    }
+
     /// Take ownership of the inner value, returning a consumed-state error if
     /// this instance has already been used.
-    fn take_inner(&self) -> napi::Result<ProtoJSSandbox> {
+    fn take_inner(&self) -> napi::Result<ProtoJSSandbox, ErrorCode> {
         self.inner
             .lock()
             .map_err(|_| lock_error())?
@@ -435,6 +623,15 @@ impl ProtoJSSandboxWrapper {
     }
 }
 
+/// An in-memory file system with `modules`, keyed by path.
+fn memory_modules(modules: HashMap<String, String>) -> FileSystemMemory {
+    let file_system = FileSystemMemory::new();
+    for (path, source) in modules {
+        file_system.insert(path, source);
+    }
+    file_system
+}
+
 #[napi]
 impl ProtoJSSandboxWrapper {
     /// Load the JavaScript runtime into the sandbox.
@@ -447,18 +644,63 @@ impl ProtoJSSandboxWrapper {
     ///
     /// @returns A `Promise<JSSandbox>` ready for handler registration
     /// @throws If the runtime fails to load, or if already consumed
-    #[napi]
-    pub async fn load_runtime(&self) -> napi::Result<JSSandboxWrapper> {
-        let proto_sandbox = self.take_inner()?;
+    #[napi(ts_return_type = "Promise<JSSandbox>")]
+    pub async fn load_runtime(&self) -> Settled<JSSandboxWrapper> {
+        settle(async move {
+            let proto_sandbox = self.take_inner()?;
 
-        let js_sandbox = tokio::task::spawn_blocking(move || {
-            proto_sandbox.load_runtime().map_err(to_napi_error)
+            let js_sandbox = tokio::task::spawn_blocking(move || {
+                proto_sandbox.load_runtime().map_err(to_napi_error)
+            })
+            .await
+            .map_err(join_error)??;
+            Ok(JSSandboxWrapper {
+                inner: Arc::new(Mutex::new(Some(js_sandbox))),
+            })
         })
         .await
-        .map_err(join_error)??;
-        Ok(JSSandboxWrapper {
-            inner: Arc::new(Mutex::new(Some(js_sandbox))),
-        })
+    }
+
+    /// Let guest JavaScript import the given modules, keyed by path.
+    ///
+    /// Handler scripts resolve relative imports against the root of the
+    /// modules:
+    ///
+    /// ```js
+    /// proto.setModules({ 'lib/math.js': 'export const add = (a, b) => a + b;' });
+    /// const sandbox = await proto.loadRuntime();
+    /// sandbox.addHandler('handler', `
+    ///     import { add } from './lib/math.js';
+    ///     function handler(event) { return { sum: add(event.a, event.b) }; }
+    /// `);
+    /// ```
+    ///
+    /// Replaces the modules of a previous `setModules()` or
+    /// `setModuleDirectory()` call.
+    ///
+    /// @param modules - Module sources, keyed by path
+    /// @throws If already consumed
+    #[napi]
+    pub fn set_modules(&self, modules: HashMap<String, String>) -> napi::Result<(), ErrorCode> {
+        self.set_module_loader(memory_modules(modules))
+    }
+
+    /// Let guest JavaScript import the modules in a directory of the host,
+    /// like `setModules()`.
+    ///
+    /// Modules are read from the directory when a handler imports them, and
+    /// imports can't reach outside of it.
+    ///
+    /// @param path - The directory of the modules
+    /// @throws If `path` is not a directory, or if already consumed
+    #[napi]
+    pub fn set_module_directory(&self, path: String) -> napi::Result<(), ErrorCode> {
+        if !std::path::Path::new(&path).is_dir() {
+            return Err(invalid_arg_error(&format!(
+                "Module directory '{path}' is not a directory"
+            )));
+        }
+        self.set_module_loader(FileSystemDirectory::new(path))
     }
 
     /// Get a builder for registering host functions in a named module.
@@ -483,7 +725,7 @@ impl ProtoJSSandboxWrapper {
     /// @returns A `HostModule` for registering functions
     /// @throws If the module name is empty
     #[napi]
-    pub fn host_module(&self, name: String) -> napi::Result<HostModuleWrapper> {
+    pub fn host_module(&self, name: String) -> napi::Result<HostModuleWrapper, ErrorCode> {
         validate_module_name(&name)?;
         Ok(HostModuleWrapper {
             module_name: format!("{HOST_MODULE_PREFIX}{name}"),
@@ -518,7 +760,7 @@ impl ProtoJSSandboxWrapper {
             false,
             true,
         >,
-    ) -> napi::Result<()> {
+    ) -> napi::Result<(), ErrorCode> {
         self.host_module(module_name)?.register(function_name, func)
     }
 }
@@ -615,7 +857,7 @@ impl HostModuleWrapper {
             false,
             true,
         >,
-    ) -> napi::Result<()> {
+    ) -> napi::Result<(), ErrorCode> {
         if name.is_empty() {
             return Err(invalid_arg_error("Function name must not be empty"));
         }
@@ -679,9 +921,9 @@ pub struct JSSandboxWrapper {
 
 impl JSSandboxWrapper {
     /// Borrow the inner value mutably via Mutex, or error if consumed.
-    fn with_inner_mut<F, R>(&self, f: F) -> napi::Result<R>
+    fn with_inner_mut<F, R>(&self, f: F) -> napi::Result<R, ErrorCode>
     where
-        F: FnOnce(&mut JSSandbox) -> napi::Result<R>,
+        F: FnOnce(&mut JSSandbox) -> napi::Result<R, ErrorCode>,
     {
         let mut guard = self.inner.lock().map_err(|_| lock_error())?;
         let sandbox = guard.as_mut().ok_or_else(|| consumed_error("JSSandbox"))?;
@@ -689,9 +931,9 @@ impl JSSandboxWrapper {
     }
 
     /// Borrow the inner value immutably via Mutex, or error if consumed.
-    fn with_inner_ref<F, R>(&self, f: F) -> napi::Result<R>
+    fn with_inner_ref<F, R>(&self, f: F) -> napi::Result<R, ErrorCode>
     where
-        F: FnOnce(&JSSandbox) -> napi::Result<R>,
+        F: FnOnce(&JSSandbox) -> napi::Result<R, ErrorCode>,
     {
         let guard = self.inner.lock().map_err(|_| lock_error())?;
         let sandbox = guard.as_ref().ok_or_else(|| consumed_error("JSSandbox"))?;
@@ -699,7 +941,7 @@ impl JSSandboxWrapper {
     }
 
     /// Take ownership of the inner value via Mutex, or error if consumed.
-    fn take_inner(&self) -> napi::Result<JSSandbox> {
+    fn take_inner(&self) -> napi::Result<JSSandbox, ErrorCode> {
         self.inner
             .lock()
             .map_err(|_| lock_error())?
@@ -723,13 +965,16 @@ impl JSSandboxWrapper {
     /// @param script - JavaScript source defining a function named `handler`
     /// @throws If the handler name is empty, or if the sandbox is consumed
     #[napi]
-    pub fn add_handler(&self, handler_name: String, script: String) -> napi::Result<()> {
+    pub fn add_handler(&self, handler_name: String, script: String) -> napi::Result<(), ErrorCode> {
         if handler_name.is_empty() {
             return Err(invalid_arg_error("Handler name must not be empty"));
         }
         self.with_inner_mut(|sandbox| {
             sandbox
-                .add_handler(handler_name, Script::from_content(script))
+                .add_handler(
+                    handler_name,
+                    Script::from_content(script).with_virtual_base("/"),
+                )
                 .map_err(to_napi_error)
         })
     }
@@ -741,7 +986,7 @@ impl JSSandboxWrapper {
     /// @param functionName - Routing key of the handler to remove (must be non-empty)
     /// @throws If the handler name is empty, or if the sandbox is consumed
     #[napi]
-    pub fn remove_handler(&self, handler_name: String) -> napi::Result<()> {
+    pub fn remove_handler(&self, handler_name: String) -> napi::Result<(), ErrorCode> {
         if handler_name.is_empty() {
             return Err(invalid_arg_error("Handler name must not be empty"));
         }
@@ -754,7 +999,7 @@ impl JSSandboxWrapper {
     ///
     /// @throws If the sandbox is consumed
     #[napi]
-    pub fn clear_handlers(&self) -> napi::Result<()> {
+    pub fn clear_handlers(&self) -> napi::Result<(), ErrorCode> {
         self.with_inner_mut(|sandbox| {
             sandbox.clear_handlers();
             Ok(())
@@ -771,24 +1016,18 @@ impl JSSandboxWrapper {
     ///
     /// @returns A `Promise<LoadedJSSandbox>` ready to handle events
     /// @throws If loading fails, or if the sandbox is consumed
-    #[napi]
-    pub async fn get_loaded_sandbox(&self) -> napi::Result<LoadedJSSandboxWrapper> {
-        let js_sandbox = self.take_inner()?;
-        let loaded_sandbox = tokio::task::spawn_blocking(move || {
-            js_sandbox.get_loaded_sandbox().map_err(to_napi_error)
+    #[napi(ts_return_type = "Promise<LoadedJSSandbox>")]
+    pub async fn get_loaded_sandbox(&self) -> Settled<LoadedJSSandboxWrapper> {
+        settle(async move {
+            let js_sandbox = self.take_inner()?;
+            let loaded_sandbox = tokio::task::spawn_blocking(move || {
+                js_sandbox.get_loaded_sandbox().map_err(to_napi_error)
+            })
+            .await
+            .map_err(join_error)??;
+            Ok(loaded_sandbox.into())
         })
         .await
-        .map_err(join_error)??;
-        // Grab the interrupt handle and poisoned state before moving behind the Mutex.
-        // These are stored separately so they never contend with the inner lock —
-        // callers can read them even while guest code is executing on a background thread.
-        let interrupt = loaded_sandbox.interrupt_handle();
-        let poisoned_flag = Arc::new(AtomicBool::new(loaded_sandbox.poisoned()));
-        Ok(LoadedJSSandboxWrapper {
-            inner: Arc::new(Mutex::new(Some(loaded_sandbox))),
-            interrupt,
-            poisoned_flag,
-        })
     }
 
     /// Whether the sandbox is in a poisoned (inconsistent) state.
@@ -796,7 +1035,7 @@ impl JSSandboxWrapper {
     /// A poisoned sandbox has had its guest execution interrupted or
     /// aborted. Most operations will fail with an `ERR_POISONED` error code.
     #[napi(getter)]
-    pub fn poisoned(&self) -> napi::Result<bool> {
+    pub fn poisoned(&self) -> napi::Result<bool, ErrorCode> {
         self.with_inner_ref(|sandbox| Ok(sandbox.poisoned()))
     }
 }
@@ -818,6 +1057,7 @@ impl JSSandboxWrapper {
 /// console.log(result); // { msg: "hi World" }
 /// ```
 #[napi(js_name = "LoadedJSSandbox")]
+#[derive(Clone)]
 pub struct LoadedJSSandboxWrapper {
     inner: Arc<Mutex<Option<LoadedJSSandbox>>>,
 
@@ -839,6 +1079,36 @@ pub struct LoadedJSSandboxWrapper {
     /// (where we already hold the lock), read via `Ordering::Acquire` in the
     /// getter. See the module-level architecture comment for the full rationale.
     poisoned_flag: Arc<AtomicBool>,
+
+    /// Why the sandbox is poisoned, stored outside the Mutex like `poisoned_flag`.
+    ///
+    /// This Mutex is only held to copy the reason in or out, never while
+    /// guest code runs, so the `poisonedReason` getter does not block.
+    poisoned_reason: Arc<Mutex<Option<PoisonReason>>>,
+
+    /// Whether a handler call is running, for `InterruptHandle.isRunning`.
+    ///
+    /// Set by `call_handler()` while it holds the lock, and read without it
+    /// like `poisoned_flag`.
+    running: Arc<AtomicBool>,
+}
+
+impl From<LoadedJSSandbox> for LoadedJSSandboxWrapper {
+    fn from(loaded_sandbox: LoadedJSSandbox) -> Self {
+        // Grab the interrupt handle and poisoned state before moving behind the Mutex.
+        // These are stored separately so they never contend with the inner lock —
+        // callers can read them even while guest code is executing on a background thread.
+        let interrupt = loaded_sandbox.interrupt_handle();
+        let poisoned_flag = Arc::new(AtomicBool::new(loaded_sandbox.poisoned()));
+        let poisoned_reason = Arc::new(Mutex::new(loaded_sandbox.poisoned_reason()));
+        Self {
+            inner: Arc::new(Mutex::new(Some(loaded_sandbox))),
+            interrupt,
+            poisoned_flag,
+            poisoned_reason,
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
 }
 
 #[napi]
@@ -873,109 +1143,116 @@ impl LoadedJSSandboxWrapper {
     /// @param options - Optional timeout/GC configuration
     /// @returns A `Promise<object>` with the handler's return value
     /// @throws On missing handler, guest execution error, or `ERR_CANCELLED` if a monitor fires
-    #[napi]
+    #[napi(ts_return_type = "Promise<any>")]
     pub async fn call_handler(
         &self,
         handler_name: String,
         event_data: JsonValue,
         options: Option<CallHandlerOptions>,
-    ) -> napi::Result<JsonValue> {
-        if handler_name.is_empty() {
-            return Err(invalid_arg_error("Handler name must not be empty"));
-        }
+    ) -> Settled<JsonValue> {
+        settle(async move {
+            if handler_name.is_empty() {
+                return Err(invalid_arg_error("Handler name must not be empty"));
+            }
 
-        let options = options.unwrap_or_default();
+            let options = options.unwrap_or_default();
+
+            // Validate timeout values eagerly before spawning a blocking task.
+            // Zero or sub-millisecond timeouts would fire instantly, poisoning
+            // the sandbox for no good reason. Values above MAX_TIMEOUT_MS guard
+            // against accidental wrapping (e.g. JS `-1` → u32::MAX via ToUint32).
+            if let Some(wall_ms) = options.wall_clock_timeout_ms
+                && !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&wall_ms)
+            {
+                return Err(invalid_arg_error(&format!(
+                        "wallClockTimeoutMs must be between {MIN_TIMEOUT_MS}ms and {MAX_TIMEOUT_MS}ms, got {wall_ms}"
+                    )));
+            }
+            if let Some(cpu_ms) = options.cpu_timeout_ms
+                && !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&cpu_ms)
+            {
+                return Err(invalid_arg_error(&format!(
+                        "cpuTimeoutMs must be between {MIN_TIMEOUT_MS}ms and {MAX_TIMEOUT_MS}ms, got {cpu_ms}"
+                    )));
+            }
 
-        // Validate timeout values eagerly before spawning a blocking task.
-        // Zero or sub-millisecond timeouts would fire instantly, poisoning
-        // the sandbox for no good reason. Values above MAX_TIMEOUT_MS guard
-        // against accidental wrapping (e.g. JS `-1` → u32::MAX via ToUint32).
-        if let Some(wall_ms) = options.wall_clock_timeout_ms
-            && !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&wall_ms)
-        {
-            return Err(invalid_arg_error(&format!(
-                    "wallClockTimeoutMs must be between {MIN_TIMEOUT_MS}ms and {MAX_TIMEOUT_MS}ms, got {wall_ms}"
-                )));
-        }
-        if let Some(cpu_ms) = options.cpu_timeout_ms
-            && !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&cpu_ms)
-        {
-            return Err(invalid_arg_error(&format!(
-                    "cpuTimeoutMs must be between {MIN_TIMEOUT_MS}ms and {MAX_TIMEOUT_MS}ms, got {cpu_ms}"
-                )));
-        }
+            if options.max_result_bytes == Some(0) {
+                return Err(invalid_arg_error("maxResultBytes must be greater than 0"));
+            }
 
-        let inner = self.inner.clone();
-        let poisoned_flag = self.poisoned_flag.clone();
-        let gc = options.gc;
-        let wall_clock_timeout_ms = options.wall_clock_timeout_ms;
-        let cpu_timeout_ms = options.cpu_timeout_ms;
-
-        // Serialize the JS object to a JSON string for the hypervisor
-        let event_json = serde_json::to_string(&event_data)
-            .map_err(|e| invalid_arg_error(&format!("Failed to serialize event: {e}")))?;
-
-        let result_json = tokio::task::spawn_blocking(move || {
-            let mut guard = inner.lock().map_err(|_| lock_error())?;
-            let sandbox = guard
-                .as_mut()
-                .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
-
-            // Dispatch to the appropriate Rust method based on whether
-            // any monitor timeouts are specified.
-            //
-            // The three `handle_event_with_monitor` arms look duplicated, but
-            // each constructs a different concrete monitor type (single or tuple).
-            // The sealed `MonitorSet` trait is not object-safe, so we can't
-            // erase the type behind a `dyn` — the match is structurally required.
-            let result = match (wall_clock_timeout_ms, cpu_timeout_ms) {
-                // No monitors — fast path
-                (None, None) => sandbox
-                    .handle_event(handler_name, event_json, gc)
-                    .map_err(to_napi_error),
-                // Both — tuple with OR semantics (recommended)
-                (Some(wall_ms), Some(cpu_ms)) => {
-                    let monitor = (
+            let inner = self.inner.clone();
+            let poisoned_flag = self.poisoned_flag.clone();
+            let poisoned_reason = self.poisoned_reason.clone();
+            let gc = options.gc;
+            let wall_clock_timeout_ms = options.wall_clock_timeout_ms;
+            let cpu_timeout_ms = options.cpu_timeout_ms;
+            let abort = options.signal.map(|signal| signal.0);
+            let max_result_bytes = options.max_result_bytes;
+            let running = self.running.clone();
+
+            let result = tokio::task::spawn_blocking(move || {
+                let mut guard = inner.lock().map_err(|_| lock_error())?;
+                let sandbox = guard
+                    .as_mut()
+                    .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+
+                // The signal may have aborted while waiting for the lock
+                if let Some(abort) = &abort
+                    && abort.is_aborted()
+                {
+                    return Err(hl_error(ErrorCode::Cancelled, "Handler call aborted"));
+                }
+
+                let _running = Running::start(running);
+
+                // Dispatch to the appropriate Rust method based on whether
+                // any monitor timeouts or an abort signal are specified.
+                let mut monitor = BoxedMonitorSet::new();
+                if let Some(wall_ms) = wall_clock_timeout_ms {
+                    monitor.push(
                         WallClockMonitor::new(Duration::from_millis(wall_ms as u64))
                             .map_err(to_napi_error)?,
+                    );
+                }
+                if let Some(cpu_ms) = cpu_timeout_ms {
+                    monitor.push(
                         CpuTimeMonitor::new(Duration::from_millis(cpu_ms as u64))
                             .map_err(to_napi_error)?,
                     );
-                    sandbox
-                        .handle_event_with_monitor(handler_name, event_json, &monitor, gc)
-                        .map_err(to_napi_error)
                 }
-                // Wall-clock only
-                (Some(wall_ms), None) => {
-                    let monitor = WallClockMonitor::new(Duration::from_millis(wall_ms as u64))
-                        .map_err(to_napi_error)?;
-                    sandbox
-                        .handle_event_with_monitor(handler_name, event_json, &monitor, gc)
-                        .map_err(to_napi_error)
+                if let Some(abort) = abort {
+                    monitor.push(AbortMonitor(abort));
                 }
-                // CPU only
-                (None, Some(cpu_ms)) => {
-                    let monitor = CpuTimeMonitor::new(Duration::from_millis(cpu_ms as u64))
-                        .map_err(to_napi_error)?;
-                    sandbox
-                        .handle_event_with_monitor(handler_name, event_json, &monitor, gc)
-                        .map_err(to_napi_error)
+                let result = if monitor.is_empty() {
+                    // No monitors — fast path
+                    sandbox.handle_event_value(handler_name, &event_data, gc)
+                } else {
+                    // Monitors race with OR semantics — the first to fire terminates the handler
+                    sandbox.handle_event_value_with_monitor(handler_name, &event_data, &monitor, gc)
                 }
-            };
-            // Update poisoned flag while we hold the lock — keeps the getter
-            // lock-free so it never blocks the Node.js event loop.
-            poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
-            result
+                .map_err(to_napi_error);
+                // Update poisoned flag while we hold the lock — keeps the getter
+                // lock-free so it never blocks the Node.js event loop.
+                poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
+                store_poisoned_reason(&poisoned_reason, sandbox);
+                result
+            })
+            .await
+            .map_err(join_error)??;
+
+            // Measured outside of the lock, so other calls don't wait for it
+            if let Some(limit) = max_result_bytes {
+                let size = json_size(&result);
+                if size > limit as usize {
+                    return Err(hl_error(
+                        ErrorCode::ResultTooLarge,
+                        format!("Handler result is {size} bytes, more than maxResultBytes ({limit})"),
+                    ));
+                }
+            }
+            Ok(result)
         })
         .await
-        .map_err(join_error)??;
-        // Parse the JSON string result back into a JS object
-        serde_json::from_str(&result_json).map_err(|e| {
-            hl_error(
-                ErrorCode::Internal,
-                format!("Failed to parse handler result as JSON: {e}"),
-            )
-        })
     }
 
     /// Unload all handlers and return to the `JSSandbox` state.
@@ -987,21 +1264,24 @@ impl LoadedJSSandboxWrapper {
     ///
     /// @returns A `Promise<JSSandbox>` ready for new handler registration
     /// @throws If already consumed
-    #[napi]
-    pub async fn unload(&self) -> napi::Result<JSSandboxWrapper> {
-        let inner = self.inner.clone();
-        let js_sandbox = tokio::task::spawn_blocking(move || {
-            let mut guard = inner.lock().map_err(|_| lock_error())?;
-            let loaded = guard
-                .take()
-                .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
-            loaded.unload().map_err(to_napi_error)
+    #[napi(ts_return_type = "Promise<JSSandbox>")]
+    pub async fn unload(&self) -> Settled<JSSandboxWrapper> {
+        settle(async move {
+            let inner = self.inner.clone();
+            let js_sandbox = tokio::task::spawn_blocking(move || {
+                let mut guard = inner.lock().map_err(|_| lock_error())?;
+                let loaded = guard
+                    .take()
+                    .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+                loaded.unload().map_err(to_napi_error)
+            })
+            .await
+            .map_err(join_error)??;
+            Ok(JSSandboxWrapper {
+                inner: Arc::new(Mutex::new(Some(js_sandbox))),
+            })
         })
         .await
-        .map_err(join_error)??;
-        Ok(JSSandboxWrapper {
-            inner: Arc::new(Mutex::new(Some(js_sandbox))),
-        })
     }
 
     /// Get a handle that can interrupt currently running guest code.
@@ -1024,6 +1304,7 @@ impl LoadedJSSandboxWrapper {
     pub fn interrupt_handle(&self) -> InterruptHandleWrapper {
         InterruptHandleWrapper {
             inner: self.interrupt.clone(),
+            running: self.running.clone(),
         }
     }
 
@@ -1045,6 +1326,67 @@ impl LoadedJSSandboxWrapper {
         self.poisoned_flag.load(Ordering::Acquire)
     }
 
+    /// Why the sandbox is poisoned, or `null` if it is not poisoned.
+    ///
+    /// `kind` is one of `killed` (by `interruptHandle.kill()`),
+    /// `monitorTimeout` (by a `callHandler()` timeout), `guestAborted`,
+    /// `memoryViolation` or `other`, and `message` describes the failure.
+    ///
+    /// ```js
+    /// if (loaded.poisoned) {
+    ///     const { kind, message } = loaded.poisonedReason;
+    ///     console.warn(`sandbox poisoned (${kind}): ${message}`);
+    /// }
+    /// ```
+    ///
+    /// Like `poisoned`, this getter never blocks the event loop.
+    #[napi(getter)]
+    pub fn poisoned_reason(&self) -> napi::Result<Option<PoisonedReason>, ErrorCode> {
+        let reason = self.poisoned_reason.lock().map_err(|_| lock_error())?;
+        Ok(reason.as_ref().map(|reason| PoisonedReason {
+            kind: reason.kind().to_string(),
+            message: reason.to_string(),
+        }))
+    }
+
+    /// Run a garbage collection cycle in the guest.
+    ///
+    /// Pass `{ gc: false }` to `callHandler()` to keep garbage collection
+    /// out of latency-sensitive calls, and call this between bursts of
+    /// calls instead:
+    ///
+    /// ```js
+    /// for (const event of burst) {
+    ///     await loaded.callHandler('handler', event, { gc: false });
+    /// }
+    /// await loaded.runGc();
+    /// ```
+    ///
+    /// Returns a `Promise<void>`.
+    ///
+    /// @throws If the sandbox is poisoned or consumed
+    #[napi(ts_return_type = "Promise<void>")]
+    pub async fn run_gc(&self) -> Settled<()> {
+        settle(async move {
+            let inner = self.inner.clone();
+            let poisoned_flag = self.poisoned_flag.clone();
+            let poisoned_reason = self.poisoned_reason.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut guard = inner.lock().map_err(|_| lock_error())?;
+                let sandbox = guard
+                    .as_mut()
+                    .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+                let result = sandbox.run_gc().map_err(to_napi_error);
+                poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
+                store_poisoned_reason(&poisoned_reason, sandbox);
+                result
+            })
+            .await
+            .map_err(join_error)?
+        })
+        .await
+    }
+
     /// Capture the current sandbox state as a snapshot.
     ///
     /// Take a snapshot **before** risky operations so you can recover
@@ -1054,22 +1396,27 @@ impl LoadedJSSandboxWrapper {
     ///
     /// @returns A `Promise<Snapshot>` that can be passed to `restore()`
     /// @throws If already consumed
-    #[napi]
-    pub async fn snapshot(&self) -> napi::Result<SnapshotWrapper> {
-        let inner = self.inner.clone();
-        let poisoned_flag = self.poisoned_flag.clone();
-        let snapshot = tokio::task::spawn_blocking(move || {
-            let mut guard = inner.lock().map_err(|_| lock_error())?;
-            let sandbox = guard
-                .as_mut()
-                .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
-            let result = sandbox.snapshot().map_err(to_napi_error);
-            poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
-            result
+    #[napi(ts_return_type = "Promise<Snapshot>")]
+    pub async fn snapshot(&self) -> Settled<SnapshotWrapper> {
+        settle(async move {
+            let inner = self.inner.clone();
+            let poisoned_flag = self.poisoned_flag.clone();
+            let poisoned_reason = self.poisoned_reason.clone();
+            let snapshot = tokio::task::spawn_blocking(move || {
+                let mut guard = inner.lock().map_err(|_| lock_error())?;
+                let sandbox = guard
+                    .as_mut()
+                    .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+                let result = sandbox.snapshot().map_err(to_napi_error);
+                poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
+                store_poisoned_reason(&poisoned_reason, sandbox);
+                result
+            })
+            .await
+            .map_err(join_error)??;
+            Ok(SnapshotWrapper { inner: snapshot })
         })
         .await
-        .map_err(join_error)??;
-        Ok(SnapshotWrapper { inner: snapshot })
     }
 
     /// Restore the sandbox to a previously captured snapshot state.
@@ -1081,22 +1428,27 @@ impl LoadedJSSandboxWrapper {
     ///
     /// @param snapshot - A snapshot previously obtained from `snapshot()`
     /// @throws If the snapshot doesn't match this sandbox, or if consumed
-    #[napi]
-    pub async fn restore(&self, snapshot: &SnapshotWrapper) -> napi::Result<()> {
-        let inner = self.inner.clone();
-        let snap = snapshot.inner.clone();
-        let poisoned_flag = self.poisoned_flag.clone();
-        tokio::task::spawn_blocking(move || {
-            let mut guard = inner.lock().map_err(|_| lock_error())?;
-            let sandbox = guard
-                .as_mut()
-                .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
-            let result = sandbox.restore(snap).map_err(to_napi_error);
-            poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
-            result
+    #[napi(ts_return_type = "Promise<void>")]
+    pub async fn restore(&self, snapshot: &SnapshotWrapper) -> Settled<()> {
+        settle(async move {
+            let inner = self.inner.clone();
+            let snap = snapshot.inner.clone();
+            let poisoned_flag = self.poisoned_flag.clone();
+            let poisoned_reason = self.poisoned_reason.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut guard = inner.lock().map_err(|_| lock_error())?;
+                let sandbox = guard
+                    .as_mut()
+                    .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+                let result = sandbox.restore(snap).map_err(to_napi_error);
+                poisoned_flag.store(sandbox.poisoned(), Ordering::Release);
+                store_poisoned_reason(&poisoned_reason, sandbox);
+                result
+            })
+            .await
+            .map_err(join_error)?
         })
         .await
-        .map_err(join_error)?
     }
 }
 
@@ -1120,8 +1472,11 @@ impl LoadedJSSandboxWrapper {
 ///     wallClockTimeoutMs: 5000,
 ///     cpuTimeoutMs: 500,
 /// });
+///
+/// // Killed when the signal aborts
+/// await loaded.callHandler('handler', data, { signal: controller.signal });
 /// ```
-#[napi(object)]
+#[napi(object, object_to_js = false)]
 #[derive(Default)]
 pub struct CallHandlerOptions {
     /// Wall-clock timeout in milliseconds (minimum: 1ms).
@@ -1141,38 +1496,1014 @@ pub struct CallHandlerOptions {
     /// Whether to run garbage collection after the handler call.
     /// Defaults to `true` if not specified.
     pub gc: Option<bool>,
+
+    /// An `AbortSignal` that kills the handler when it aborts.
+    ///
+    /// The call is rejected with `ERR_CANCELLED`, and the abort reason as
+    /// the error's `cause`. Like a timeout, killing the handler poisons the
+    /// sandbox.
+    #[napi(ts_type = "AbortSignal")]
+    pub signal: Option<CallAbortSignal>,
+
+    /// The maximum size of the result, in bytes of JSON (minimum: 1).
+    ///
+    /// A larger result is rejected with `ERR_RESULT_TOO_LARGE`. The check
+    /// runs on the host once the handler returned, so the sandbox is not
+    /// poisoned, but the result must still fit in the output buffer.
+    pub max_result_bytes: Option<u32>,
 }
 
-// ── InterruptHandle ──────────────────────────────────────────────────
+/// The size of `value` serialized as JSON, without serializing it into a
+/// buffer.
+fn json_size(value: &JsonValue) -> usize {
+    struct ByteCount(usize);
 
-/// A handle for manually killing currently running guest code.
-///
-/// Since `callHandler()` is async, you can grab an interrupt handle
-/// and call `kill()` from the same JavaScript thread while the handler
-/// is executing — no worker threads required:
-///
-/// ```js
-/// const handle = loaded.interruptHandle;
-/// const promise = loaded.callHandler('compute', {});
-/// setTimeout(() => handle.kill(), 5000);
-/// try { await promise; } catch (e) { /* killed */ }
-/// ```
-///
-/// For automatic timeout-based interruption, pass timeout options to
-/// `callHandler()` which handles everything for you.
-#[napi(js_name = "InterruptHandle")]
-pub struct InterruptHandleWrapper {
-    inner: Arc<dyn InterruptHandle>,
+    impl std::io::Write for ByteCount {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut count = ByteCount(0);
+    // Writing a `Value` to a writer that never fails can't fail
+    let _ = serde_json::to_writer(&mut count, value);
+    count.0
 }
 
-#[napi]
-impl InterruptHandleWrapper {
-    /// Immediately terminate the currently executing guest code.
-    ///
-    /// The sandbox will be poisoned after this call. Use `restore()` with
-    /// a snapshot to recover, or `unload()` to discard handlers.
-    #[napi]
-    pub fn kill(&self) {
-        self.inner.kill();
+// ── Abort signals ────────────────────────────────────────────────────
+//
+// An `AbortSignal` can't cross into the background thread running the
+// handler, and listening to it from Rust would leave a listener on the
+// signal after every call. Instead, `lib.js` creates a handle with
+// `createCallAbort()` for each `callHandler()` or `SandboxPool.acquire()` call
+// made with a signal, passes it as `signal`, and calls `abortCall()` with it
+// from its own `abort` listener, which it removes when the call settles. The
+// call races an `AbortMonitor` against its other monitors, so the guest is
+// only killed while this call is running, not while it waits for another call
+// to finish. `acquire()` races the abort against the wait for a sandbox.
+
+/// Whether the `AbortSignal` of a call aborted, shared between `lib.js`
+/// and the monitor of the call.
+#[derive(Default)]
+pub struct AbortState {
+    aborted: AtomicBool,
+    notify: Notify,
+}
+
+/// Create the handle `lib.js` passes as the `signal` of a call, and aborts
+/// with `abortCall()`. Not part of the public API.
+#[napi(skip_typescript)]
+pub fn create_call_abort() -> External<Arc<AbortState>> {
+    External::new(Arc::default())
+}
+
+/// Kill the call of `abort`, if it is running or as soon as it starts.
+/// Not part of the public API.
+#[napi(skip_typescript)]
+pub fn abort_call(abort: &External<Arc<AbortState>>) {
+    abort.aborted.store(true, Ordering::Release);
+    abort.notify.notify_waiters();
+}
+
+/// The `signal` of `CallHandlerOptions`: the handle from `createCallAbort()`
+/// that `lib.js` passes in place of the `AbortSignal`.
+pub struct CallAbortSignal(Arc<AbortState>);
+
+impl FromNapiValue for CallAbortSignal {
+    unsafe fn from_napi_value(env: napi_env, value: napi_value) -> napi::Result<Self> {
+        let abort =
+            unsafe { <&External<Arc<AbortState>>>::from_napi_value(env, value) }.map_err(|_| {
+                // Create the JS error here, so it keeps its code when thrown
+                let err = JsError::from(invalid_arg_error("signal must be an AbortSignal"));
+                napi::Error::from(err.into_unknown(Env::from_raw(env)))
+            })?;
+        Ok(Self(Arc::clone(abort)))
+    }
+}
+
+impl AbortState {
+    fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Acquire)
+    }
+
+    /// Wait until the signal aborts.
+    async fn aborted(&self) {
+        loop {
+            // Register for the notification before checking the flag, so an
+            // abort between the two is not missed.
+            let notified = self.notify.notified();
+            if self.is_aborted() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// An execution monitor that fires when the `AbortSignal` of the call aborts.
+struct AbortMonitor(Arc<AbortState>);
+
+impl ExecutionMonitor for AbortMonitor {
+    fn get_monitor(
+        &self,
+    ) -> hyperlight_js::Result<impl std::future::Future<Output = ()> + Send + 'static> {
+        let state = self.0.clone();
+        Ok(async move { state.aborted().await })
+    }
+
+    fn name(&self) -> &'static str {
+        "abort-signal"
+    }
+}
+
+// ── PoisonedReason ───────────────────────────────────────────────────
+
+/// Why a sandbox is poisoned, see `LoadedJSSandbox.poisonedReason`.
+#[napi(object)]
+#[derive(Clone)]
+pub struct PoisonedReason {
+    /// `killed`, `monitorTimeout`, `guestAborted`, `memoryViolation` or `other`.
+    pub kind: String,
+    /// A description of the failure that poisoned the sandbox.
+    pub message: String,
+}
+
+// ── Lifecycle events ─────────────────────────────────────────────────
+
+/// The lifecycle events of `SandboxBuilder.on()`.
+const LIFECYCLE_EVENTS: [&str; 4] = ["poisoned", "monitorFired", "restored", "unloaded"];
+
+/// A listener of `SandboxBuilder.on()`.
+type LifecycleListener = ThreadsafeFunction<SandboxEvent, (), SandboxEvent, Status, false, true>;
+
+/// A lifecycle event of a sandbox, passed to the listeners of
+/// `SandboxBuilder.on()`.
+#[napi(object)]
+#[derive(Clone)]
+pub struct SandboxEvent {
+    /// `poisoned`, `monitorFired`, `restored` or `unloaded`.
+    pub event: String,
+    /// The handler that was running, for `poisoned` and `monitorFired`.
+    pub handler: Option<String>,
+    /// Why the sandbox is poisoned, for `poisoned`.
+    pub reason: Option<PoisonedReason>,
+    /// The name of the monitor that fired, for `monitorFired`.
+    pub monitor: Option<String>,
+}
+
+impl SandboxEvent {
+    fn new(event: &str) -> Self {
+        Self {
+            event: event.to_string(),
+            handler: None,
+            reason: None,
+            monitor: None,
+        }
+    }
+}
+
+/// The observer installed by `SandboxBuilder.on()`, forwarding events to
+/// the listeners of the builder.
+struct ListenerObserver(Arc<Mutex<Vec<(&'static str, LifecycleListener)>>>);
+
+impl ListenerObserver {
+    fn emit(&self, event: SandboxEvent) {
+        let Ok(listeners) = self.0.lock() else {
+            return;
+        };
+        for (_, listener) in listeners.iter().filter(|(name, _)| *name == event.event) {
+            listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+}
+
+impl SandboxObserver for ListenerObserver {
+    fn on_unloaded(&self) {
+        self.emit(SandboxEvent::new("unloaded"));
+    }
+
+    fn on_poisoned(&self, handler: &str, reason: &PoisonReason) {
+        self.emit(SandboxEvent {
+            handler: Some(handler.to_string()),
+            reason: Some(PoisonedReason {
+                kind: reason.kind().to_string(),
+                message: reason.to_string(),
+            }),
+            ..SandboxEvent::new("poisoned")
+        });
+    }
+
+    fn on_monitor_fired(&self, handler: &str, monitor: &'static str) {
+        self.emit(SandboxEvent {
+            handler: Some(handler.to_string()),
+            monitor: Some(monitor.to_string()),
+            ..SandboxEvent::new("monitorFired")
+        });
+    }
+
+    fn on_restored(&self) {
+        self.emit(SandboxEvent::new("restored"));
+    }
+}
+
+/// Copy the poisoned reason of `sandbox` out of the Mutex, for the
+/// `poisonedReason` getter.
+fn store_poisoned_reason(poisoned_reason: &Mutex<Option<PoisonReason>>, sandbox: &LoadedJSSandbox) {
+    if let Ok(mut reason) = poisoned_reason.lock() {
+        *reason = sandbox.poisoned_reason();
+    }
+}
+
+// ── InterruptHandle ──────────────────────────────────────────────────
+
+/// A handle for manually killing currently running guest code.
+///
+/// Since `callHandler()` is async, you can grab an interrupt handle
+/// and call `kill()` from the same JavaScript thread while the handler
+/// is executing — no worker threads required:
+///
+/// ```js
+/// const handle = loaded.interruptHandle;
+/// const promise = loaded.callHandler('compute', {});
+/// setTimeout(() => handle.kill(), 5000);
+/// try { await promise; } catch (e) { /* killed */ }
+/// ```
+///
+/// For automatic timeout-based interruption, pass timeout options to
+/// `callHandler()` which handles everything for you.
+#[napi(js_name = "InterruptHandle")]
+pub struct InterruptHandleWrapper {
+    inner: Arc<dyn InterruptHandle>,
+    running: Arc<AtomicBool>,
+}
+
+#[napi]
+impl InterruptHandleWrapper {
+    /// Immediately terminate the currently executing guest code.
+    ///
+    /// The sandbox will be poisoned after this call. Use `restore()` with
+    /// a snapshot to recover, or `unload()` to discard handlers.
+    #[napi]
+    pub fn kill(&self) {
+        self.inner.kill();
+    }
+
+    /// Terminate the guest code executing in `ms` milliseconds, like
+    /// `kill()`, unless the kill is cancelled first:
+    ///
+    /// ```js
+    /// const kill = loaded.interruptHandle.killAfter(5000);
+    /// try {
+    ///     await loaded.callHandler('compute', {});
+    /// } finally {
+    ///     kill.cancel();
+    /// }
+    /// ```
+    ///
+    /// Nothing is killed if no guest code is executing by then.
+    ///
+    /// @param ms - Delay in milliseconds (1ms to 1 hour)
+    /// @returns A `ScheduledKill` to `cancel()` the kill
+    /// @throws If `ms` is out of range
+    #[napi]
+    pub fn kill_after(&self, ms: u32) -> napi::Result<ScheduledKillWrapper, ErrorCode> {
+        if !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&ms) {
+            return Err(invalid_arg_error(&format!(
+                "killAfter() delay must be between {MIN_TIMEOUT_MS}ms and {MAX_TIMEOUT_MS}ms, got {ms}"
+            )));
+        }
+        let inner = self.inner.clone();
+        let task = spawn(async move {
+            monitor::sleep(Duration::from_millis(ms as u64)).await;
+            inner.kill();
+        });
+        Ok(ScheduledKillWrapper {
+            task: task.abort_handle(),
+        })
+    }
+
+    /// Whether a handler call is executing guest code.
+    ///
+    /// Like `poisoned`, this getter never blocks the event loop.
+    #[napi(getter)]
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+}
+
+/// Marks a handler call as running until dropped, for
+/// `InterruptHandle.isRunning`.
+struct Running(Arc<AtomicBool>);
+
+impl Running {
+    fn start(running: Arc<AtomicBool>) -> Self {
+        running.store(true, Ordering::Release);
+        Self(running)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// A kill scheduled with `InterruptHandle.killAfter()`.
+#[napi(js_name = "ScheduledKill")]
+pub struct ScheduledKillWrapper {
+    task: tokio::task::AbortHandle,
+}
+
+#[napi]
+impl ScheduledKillWrapper {
+    /// Cancel the kill, if it has not happened yet.
+    #[napi]
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+// ── createSandbox ────────────────────────────────────────────────────
+
+/// Options for `createSandbox()`.
+#[napi(object, object_to_js = false)]
+pub struct CreateSandboxOptions {
+    /// Handler scripts, keyed by their routing key for `callHandler()`,
+    /// like `JSSandbox.addHandler()`.
+    pub handlers: HashMap<String, String>,
+    /// Modules the handlers can import, keyed by path, like
+    /// `ProtoJSSandbox.setModules()`.
+    pub modules: Option<HashMap<String, String>>,
+    /// Guest heap size in bytes, like `SandboxBuilder.setHeapSize()`.
+    pub heap_size: Option<u32>,
+    /// Guest scratch size in bytes, like `SandboxBuilder.setScratchSize()`.
+    pub scratch_size: Option<u32>,
+    /// Guest input buffer size in bytes, like `SandboxBuilder.setInputBufferSize()`.
+    pub input_buffer_size: Option<u32>,
+    /// Guest output buffer size in bytes, like `SandboxBuilder.setOutputBufferSize()`.
+    pub output_buffer_size: Option<u32>,
+}
+
+impl CreateSandboxOptions {
+    /// Create a builder with the sizes of the options, which must be greater
+    /// than 0.
+    fn builder(&self) -> napi::Result<SandboxBuilder, ErrorCode> {
+        fn size(size: Option<u32>, name: &str) -> napi::Result<Option<u32>, ErrorCode> {
+            match size {
+                Some(0) => Err(invalid_arg_error(&format!("{name} must be greater than 0"))),
+                size => Ok(size),
+            }
+        }
+        let mut builder = SandboxBuilder::new();
+        if let Some(size) = size(self.heap_size, "Heap size")? {
+            builder = builder.with_guest_heap_size(size as u64);
+        }
+        if let Some(size) = size(self.scratch_size, "Scratch size")? {
+            builder = builder.with_guest_scratch_size(size as usize);
+        }
+        if let Some(size) = size(self.input_buffer_size, "Input buffer size")? {
+            builder = builder.with_guest_input_buffer_size(size as usize);
+        }
+        if let Some(size) = size(self.output_buffer_size, "Output buffer size")? {
+            builder = builder.with_guest_output_buffer_size(size as usize);
+        }
+        Ok(builder)
+    }
+}
+
+/// Create a sandbox with its handlers loaded, in one step.
+///
+/// This runs the builder → proto → runtime → loaded transitions for the
+/// common case. Use `SandboxBuilder` for anything the options don't cover,
+/// like host functions or snapshots taken before the handlers are loaded.
+///
+/// ```js
+/// const loaded = await createSandbox({
+///     handlers: { greet: 'function handler(e) { return { msg: `hi ${e.name}` }; }' },
+///     heapSize: 8 * 1024 * 1024,
+/// });
+/// await loaded.callHandler('greet', { name: 'World' });
+/// ```
+///
+/// Returns a `Promise` — does not block the Node.js event loop.
+///
+/// @param options - The handlers, and optionally modules and sizes
+/// @returns A `Promise<LoadedJSSandbox>` ready to handle events
+/// @throws If there are no handlers, a handler name is empty, a size is 0,
+///   or if creating the sandbox fails
+#[napi(ts_return_type = "Promise<LoadedJSSandbox>")]
+pub async fn create_sandbox(options: CreateSandboxOptions) -> Settled<LoadedJSSandboxWrapper> {
+    settle(async move {
+        if options.handlers.is_empty() {
+            return Err(invalid_arg_error(
+                "createSandbox() needs at least one handler",
+            ));
+        }
+        if options.handlers.contains_key("") {
+            return Err(invalid_arg_error("Handler name must not be empty"));
+        }
+        let builder = options.builder()?;
+        let loaded_sandbox = tokio::task::spawn_blocking(move || {
+            let mut proto_sandbox = builder.build()?;
+            if let Some(modules) = options.modules {
+                proto_sandbox = proto_sandbox.set_module_loader(memory_modules(modules))?;
+            }
+            let mut js_sandbox = proto_sandbox.load_runtime()?;
+            for (handler_name, script) in options.handlers {
+                js_sandbox.add_handler(
+                    handler_name,
+                    Script::from_content(script).with_virtual_base("/"),
+                )?;
+            }
+            js_sandbox.get_loaded_sandbox()
+        })
+        .await
+        .map_err(join_error)?
+        .map_err(to_napi_error)?;
+        Ok(loaded_sandbox.into())
+    })
+    .await
+}
+
+// ── Metrics ──────────────────────────────────────────────────────────
+
+/// The metrics recorded by hyperlight-js in this process, see `getMetrics()`.
+///
+/// Counters only go up, from the start of the process.
+#[napi(object)]
+pub struct Metrics {
+    /// Number of `ProtoJSSandbox`es currently alive.
+    pub active_proto_js_sandboxes: f64,
+    /// Number of `JSSandbox`es currently alive.
+    pub active_js_sandboxes: f64,
+    /// Number of `LoadedJSSandbox`es currently alive.
+    pub active_loaded_js_sandboxes: f64,
+    /// Number of `ProtoJSSandbox`es created.
+    pub total_proto_js_sandboxes: f64,
+    /// Number of `JSSandbox`es created.
+    pub total_js_sandboxes: f64,
+    /// Number of `LoadedJSSandbox`es created.
+    pub total_loaded_js_sandboxes: f64,
+    /// Number of times handlers have been loaded into a sandbox.
+    pub sandbox_loads: f64,
+    /// Number of times handlers have been unloaded from a sandbox.
+    pub sandbox_unloads: f64,
+    /// Number of handler calls terminated by a monitor, by monitor name
+    /// (e.g. `wall-clock`).
+    pub monitor_terminations: HashMap<String, f64>,
+    /// Number of host function calls made by handlers.
+    pub host_calls: f64,
+    /// Size of the guest heap last measured, in bytes.
+    pub guest_heap_bytes: f64,
+    /// Latencies of the handler calls, by handler name. Only recorded when
+    /// the native binding is built with the `function_call_metrics` feature
+    /// of hyperlight-js.
+    pub handler_latencies: HashMap<String, HandlerLatencyMetrics>,
+}
+
+/// Aggregated latencies of the calls to a handler, in `Metrics`.
+#[napi(object)]
+pub struct HandlerLatencyMetrics {
+    /// Number of calls.
+    pub calls: f64,
+    /// Number of calls that failed with an error thrown by the handler.
+    pub guest_errors: f64,
+    /// Total time spent in the calls, in milliseconds.
+    pub total_ms: f64,
+    /// Time spent in the fastest call, in milliseconds.
+    pub min_ms: f64,
+    /// Time spent in the slowest call, in milliseconds.
+    pub max_ms: f64,
+    /// Average time spent in a call, in milliseconds.
+    pub mean_ms: f64,
+}
+
+/// Get the metrics recorded by hyperlight-js in this process: the number of
+/// sandboxes, loads, monitor terminations, and more.
+///
+/// This needs no metrics recorder, so the values can be exported with any
+/// metrics library, e.g. in the `collect()` of a prom-client gauge:
+///
+/// ```js
+/// new client.Gauge({
+///     name: 'hyperlight_active_loaded_sandboxes',
+///     help: 'Loaded sandboxes currently alive',
+///     collect() {
+///         this.set(getMetrics().activeLoadedJsSandboxes);
+///     },
+/// });
+/// ```
+///
+/// @returns The current `Metrics`
+#[napi]
+pub fn get_metrics() -> Metrics {
+    let snapshot = metrics_snapshot();
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    Metrics {
+        active_proto_js_sandboxes: snapshot.active_proto_js_sandboxes as f64,
+        active_js_sandboxes: snapshot.active_js_sandboxes as f64,
+        active_loaded_js_sandboxes: snapshot.active_loaded_js_sandboxes as f64,
+        total_proto_js_sandboxes: snapshot.total_proto_js_sandboxes as f64,
+        total_js_sandboxes: snapshot.total_js_sandboxes as f64,
+        total_loaded_js_sandboxes: snapshot.total_loaded_js_sandboxes as f64,
+        sandbox_loads: snapshot.sandbox_loads as f64,
+        sandbox_unloads: snapshot.sandbox_unloads as f64,
+        monitor_terminations: snapshot
+            .monitor_terminations
+            .into_iter()
+            .map(|(monitor, count)| (monitor, count as f64))
+            .collect(),
+        host_calls: snapshot.host_calls as f64,
+        guest_heap_bytes: snapshot.guest_heap_bytes as f64,
+        handler_latencies: snapshot
+            .handler_latencies
+            .into_iter()
+            .map(|(handler, latencies)| {
+                let metrics = HandlerLatencyMetrics {
+                    calls: latencies.calls as f64,
+                    guest_errors: latencies.guest_errors as f64,
+                    total_ms: ms(latencies.total),
+                    min_ms: ms(latencies.min),
+                    max_ms: ms(latencies.max),
+                    mean_ms: ms(latencies.mean()),
+                };
+                (handler, metrics)
+            })
+            .collect(),
+    }
+}
+
+// ── SandboxPool ──────────────────────────────────────────────────────
+
+/// Options for `SandboxPool.acquire()`.
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct AcquireOptions {
+    /// An `AbortSignal` to stop waiting for a sandbox.
+    ///
+    /// The call is rejected with `ERR_CANCELLED`, and the abort reason as
+    /// the error's `cause`, and stops counting in `queueDepth`.
+    #[napi(ts_type = "AbortSignal")]
+    pub signal: Option<CallAbortSignal>,
+}
+
+/// A pool of loaded sandboxes, shared by concurrent requests.
+///
+/// Create the sandboxes as usual — with their host functions and
+/// handlers — and hand them to the pool. Each request then borrows a
+/// sandbox with `acquire()` and gives it back with `release()`, or with
+/// `withSandbox()`, which does both:
+///
+/// ```js
+/// const pool = new SandboxPool(await Promise.all([1, 2, 3, 4].map(() => createLoadedSandbox())));
+///
+/// const result = await pool.withSandbox((sandbox) =>
+///     sandbox.callHandler('handler', event, { wallClockTimeoutMs: 1000 })
+/// );
+/// ```
+///
+/// When every sandbox is in use, `acquire()` waits for one to be released,
+/// first come first served, unless its `signal` aborts first.
+/// `tryAcquire()` doesn't wait.
+///
+/// The pool snapshots each sandbox the first time it hands it out. A
+/// sandbox released poisoned (e.g. after a timeout) is restored from that
+/// snapshot before it is handed out again, and a sandbox that cannot be
+/// restored, or that was unloaded, is retired from the pool. Once every
+/// sandbox is retired, `acquire()` fails with `ERR_INTERNAL`.
+///
+/// A pool can't be shared across `worker_threads`: like every native object,
+/// it and its sandboxes belong to the thread that created them, and can't be
+/// transferred. Workers either build their own pool, or send their calls to
+/// the thread that owns the pool, e.g. over a `MessagePort`.
+#[napi(js_name = "SandboxPool")]
+pub struct SandboxPoolWrapper {
+    /// The sandboxes of the pool that are not acquired.
+    idle: Arc<Mutex<VecDeque<PoolMember>>>,
+
+    /// The sandboxes of the pool that are acquired, until they are released.
+    acquired: Arc<Mutex<Vec<PoolMember>>>,
+
+    /// One permit per idle sandbox. Waiting for a permit is waiting for a
+    /// sandbox, in the order `acquire()` was called.
+    permits: Arc<Semaphore>,
+
+    /// Number of sandboxes of the pool that have not been retired, wherever
+    /// they are: idle, acquired, or being snapshotted or healed.
+    members: Arc<AtomicU32>,
+
+    /// Number of `acquire()` calls waiting for a sandbox.
+    waiting: Arc<AtomicU32>,
+
+    /// Number of sandboxes restored from their snapshot because they were
+    /// given back poisoned.
+    poisoned_replaced: Arc<AtomicU32>,
+}
+
+/// The state of a `SandboxPool`, see `SandboxPool.stats()`.
+#[napi(object)]
+pub struct PoolStats {
+    /// Number of sandboxes not in use.
+    pub idle: u32,
+    /// Number of sandboxes in use, or being restored after they were
+    /// released.
+    pub busy: u32,
+    /// Number of times a sandbox was released poisoned and restored from
+    /// its snapshot, since the pool was created.
+    pub poisoned_replaced: u32,
+}
+
+/// A sandbox of a pool, and the snapshot it is restored from when poisoned.
+struct PoolMember {
+    sandbox: LoadedJSSandboxWrapper,
+    snapshot: Option<Arc<Snapshot>>,
+}
+
+impl PoolMember {
+    /// Snapshot the sandbox if it has no snapshot yet, so it can be restored
+    /// if it gets poisoned while acquired.
+    fn ensure_snapshot(&mut self) -> napi::Result<(), ErrorCode> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
+        let mut guard = self.sandbox.inner.lock().map_err(|_| lock_error())?;
+        let sandbox = guard
+            .as_mut()
+            .ok_or_else(|| consumed_error("LoadedJSSandbox"))?;
+        self.snapshot = Some(sandbox.snapshot().map_err(to_napi_error)?);
+        Ok(())
+    }
+
+    /// Restore the sandbox from its snapshot if it is poisoned, counting the
+    /// restore in `restored`.
+    ///
+    /// Returns `false` if the sandbox is not usable anymore: it has been
+    /// consumed, or it is poisoned and cannot be restored.
+    fn heal(&self, restored: &AtomicU32) -> bool {
+        let Ok(mut guard) = self.sandbox.inner.lock() else {
+            return false;
+        };
+        let Some(sandbox) = guard.as_mut() else {
+            return false;
+        };
+        if sandbox.poisoned()
+            && let Some(snapshot) = &self.snapshot
+        {
+            if sandbox.restore(snapshot.clone()).is_ok() {
+                restored.fetch_add(1, Ordering::Relaxed);
+            }
+            self.sandbox
+                .poisoned_flag
+                .store(sandbox.poisoned(), Ordering::Release);
+            store_poisoned_reason(&self.sandbox.poisoned_reason, sandbox);
+        }
+        !sandbox.poisoned()
+    }
+}
+
+/// Counts an `acquire()` call in `queueDepth` while it waits for a sandbox,
+/// until it gets one, fails, or is aborted.
+struct Waiting<'a>(&'a AtomicU32);
+
+impl<'a> Waiting<'a> {
+    fn start(waiting: &'a AtomicU32) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        Self(waiting)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The error of acquiring a sandbox from a pool whose sandboxes have all
+/// been retired.
+fn retired_pool_error() -> napi::Error<ErrorCode> {
+    hl_error(
+        ErrorCode::Internal,
+        "Every sandbox of the pool has been retired",
+    )
+}
+
+impl SandboxPoolWrapper {
+    /// Take an idle sandbox out of the pool, waiting for one if there is none,
+    /// or until `abort` aborts.
+    async fn take_idle(&self, abort: Option<&AbortState>) -> napi::Result<PoolMember, ErrorCode> {
+        let permit = {
+            let _waiting = Waiting::start(&self.waiting);
+            let acquire = self.permits.acquire();
+            match abort {
+                Some(abort) => tokio::select! {
+                    permit = acquire => permit,
+                    () = abort.aborted() => {
+                        return Err(hl_error(ErrorCode::Cancelled, "Sandbox pool acquire aborted"));
+                    }
+                },
+                None => acquire.await,
+            }
+        };
+        permit.map_err(|_| retired_pool_error())?.forget();
+        self.pop_idle()
+    }
+
+    /// Take an idle sandbox out of the pool, if there is one.
+    fn try_take_idle(&self) -> napi::Result<Option<PoolMember>, ErrorCode> {
+        match self.permits.try_acquire() {
+            Ok(permit) => permit.forget(),
+            Err(TryAcquireError::NoPermits) => return Ok(None),
+            Err(TryAcquireError::Closed) => return Err(retired_pool_error()),
+        }
+        self.pop_idle().map(Some)
+    }
+
+    /// Pop an idle sandbox, once its permit has been taken.
+    fn pop_idle(&self) -> napi::Result<PoolMember, ErrorCode> {
+        self.idle
+            .lock()
+            .map_err(|_| lock_error())?
+            .pop_front()
+            .ok_or_else(|| hl_error(ErrorCode::Internal, "Sandbox pool has no idle sandbox"))
+    }
+
+    /// Heal `member` and return it to the idle sandboxes, or retire it if it
+    /// is not usable anymore. Returns whether it was returned.
+    async fn put_idle(&self, member: PoolMember) -> napi::Result<bool, ErrorCode> {
+        let restored = self.poisoned_replaced.clone();
+        let (member, healthy) = tokio::task::spawn_blocking(move || {
+            let healthy = member.heal(&restored);
+            (member, healthy)
+        })
+        .await
+        .map_err(join_error)?;
+        if healthy {
+            self.idle
+                .lock()
+                .map_err(|_| lock_error())?
+                .push_back(member);
+            self.permits.add_permits(1);
+        } else if self.members.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Fail the waiting `acquire()` calls, no sandbox will be released
+            self.permits.close();
+        }
+        Ok(healthy)
+    }
+
+    /// Take a sandbox out of the pool for `acquire()`, waiting for one until
+    /// `abort` aborts.
+    async fn checkout(
+        &self,
+        abort: Option<&AbortState>,
+    ) -> napi::Result<LoadedJSSandboxWrapper, ErrorCode> {
+        let member = self.take_idle(abort).await?;
+        self.hand_out(member).await
+    }
+
+    /// Hand `member` out, snapshotted so it can be restored when it is
+    /// released.
+    async fn hand_out(
+        &self,
+        member: PoolMember,
+    ) -> napi::Result<LoadedJSSandboxWrapper, ErrorCode> {
+        let (member, result) = tokio::task::spawn_blocking(move || {
+            let mut member = member;
+            let result = member.ensure_snapshot();
+            (member, result)
+        })
+        .await
+        .map_err(join_error)?;
+        if let Err(err) = result {
+            // Give the sandbox back, or retire it if it cannot be used
+            self.put_idle(member).await?;
+            return Err(err);
+        }
+        let sandbox = member.sandbox.clone();
+        self.acquired.lock().map_err(|_| lock_error())?.push(member);
+        Ok(sandbox)
+    }
+
+    /// Give `sandbox` back to the pool for `release()`.
+    async fn checkin(&self, sandbox: &LoadedJSSandboxWrapper) -> napi::Result<(), ErrorCode> {
+        let member = {
+            let mut acquired = self.acquired.lock().map_err(|_| lock_error())?;
+            let index = acquired
+                .iter()
+                .position(|member| Arc::ptr_eq(&member.sandbox.inner, &sandbox.inner))
+                .ok_or_else(|| invalid_arg_error("Sandbox was not acquired from this pool"))?;
+            acquired.swap_remove(index)
+        };
+        self.put_idle(member).await?;
+        Ok(())
+    }
+}
+
+#[napi]
+impl SandboxPoolWrapper {
+    /// Create a pool of the given loaded sandboxes.
+    ///
+    /// The pool shares the sandboxes with the given objects; once pooled,
+    /// they should only be used through `acquire()` or `withSandbox()`.
+    ///
+    /// @param sandboxes - The loaded sandboxes of the pool (must not be empty)
+    /// @throws If `sandboxes` is empty
+    #[napi(constructor)]
+    pub fn new(
+        sandboxes: Vec<ClassInstance<LoadedJSSandboxWrapper>>,
+    ) -> napi::Result<Self, ErrorCode> {
+        if sandboxes.is_empty() {
+            return Err(invalid_arg_error(
+                "Sandbox pool must have at least one sandbox",
+            ));
+        }
+        let idle: VecDeque<PoolMember> = sandboxes
+            .iter()
+            .map(|sandbox| PoolMember {
+                sandbox: (**sandbox).clone(),
+                snapshot: None,
+            })
+            .collect();
+        Ok(Self {
+            permits: Arc::new(Semaphore::new(idle.len())),
+            members: Arc::new(AtomicU32::new(idle.len() as u32)),
+            idle: Arc::new(Mutex::new(idle)),
+            acquired: Arc::new(Mutex::new(Vec::new())),
+            waiting: Arc::new(AtomicU32::new(0)),
+            poisoned_replaced: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    /// Borrow a sandbox from the pool, waiting until one is released if
+    /// they are all in use.
+    ///
+    /// Every acquired sandbox must be given back with `release()`.
+    ///
+    /// Returns a `Promise` — does not block the Node.js event loop.
+    ///
+    /// ```js
+    /// // Give up waiting after a second
+    /// const sandbox = await pool.acquire({ signal: AbortSignal.timeout(1000) });
+    /// ```
+    ///
+    /// @param options - Optional `AcquireOptions`
+    /// @returns A `Promise<LoadedJSSandbox>` for the exclusive use of the caller
+    /// @throws `ERR_CANCELLED` if the signal aborts before a sandbox is free,
+    ///   or if the sandbox cannot be snapshotted
+    #[napi(ts_return_type = "Promise<LoadedJSSandbox>")]
+    pub async fn acquire(
+        &self,
+        options: Option<AcquireOptions>,
+    ) -> Settled<LoadedJSSandboxWrapper> {
+        let abort = options
+            .and_then(|options| options.signal)
+            .map(|signal| signal.0);
+        settle(self.checkout(abort.as_deref())).await
+    }
+
+    /// Borrow a sandbox from the pool if one is not in use, without waiting.
+    ///
+    /// Like with `acquire()`, the sandbox must be given back with `release()`.
+    ///
+    /// @returns A `Promise` with a sandbox for the exclusive use of the
+    ///   caller, or `null` if they are all in use
+    /// @throws If the sandbox cannot be snapshotted
+    #[napi(ts_return_type = "Promise<LoadedJSSandbox | null>")]
+    pub async fn try_acquire(&self) -> Settled<Option<LoadedJSSandboxWrapper>> {
+        settle(async move {
+            match self.try_take_idle()? {
+                Some(member) => self.hand_out(member).await.map(Some),
+                None => Ok(None),
+            }
+        })
+        .await
+    }
+
+    /// Give a sandbox obtained from `acquire()` back to the pool.
+    ///
+    /// A poisoned sandbox is restored before it is handed out again. A
+    /// sandbox that cannot be restored, or that was unloaded, is retired
+    /// from the pool, which then has one sandbox less.
+    ///
+    /// Returns a `Promise<void>`.
+    ///
+    /// @param sandbox - A sandbox acquired from this pool
+    /// @throws If the sandbox was not acquired from this pool
+    #[napi(ts_return_type = "Promise<void>")]
+    pub async fn release(&self, sandbox: &LoadedJSSandboxWrapper) -> Settled<()> {
+        settle(self.checkin(sandbox)).await
+    }
+
+    /// Acquire a sandbox, pass it to `callback`, and release it once the
+    /// callback settles, even if it throws.
+    ///
+    /// ```js
+    /// const result = await pool.withSandbox((sandbox) =>
+    ///     sandbox.callHandler('handler', { name: 'World' })
+    /// );
+    /// ```
+    ///
+    /// @param callback - `(sandbox) => any | Promise<any>` — returns a JSON-serializable value
+    /// @returns A `Promise` with the value returned by `callback`
+    /// @throws What `callback` throws, or if no sandbox can be acquired
+    #[napi(ts_return_type = "Promise<any | null>")]
+    #[allow(clippy::type_complexity)] // allow the type complexity here so that index.d.ts is cleaner
+    pub async fn with_sandbox(
+        &self,
+        callback: ThreadsafeFunction<
+            LoadedJSSandboxWrapper,
+            Promise<Option<JsonValue>>,
+            LoadedJSSandboxWrapper,
+            Status,
+            false,
+            true,
+        >,
+    ) -> Settled<Option<JsonValue>> {
+        let sandbox = match self.checkout(None).await {
+            Ok(sandbox) => sandbox,
+            Err(err) => return Settled::Err(err),
+        };
+        let result = match callback.call_async(sandbox.clone()).await {
+            Ok(promise) => promise.await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = self.checkin(&sandbox).await {
+            return Settled::Err(err);
+        }
+        match result {
+            Ok(value) => Settled::Ok(value),
+            Err(err) => Settled::Thrown(err),
+        }
+    }
+
+    /// Run a health check of the sandboxes that are not in use: poisoned
+    /// sandboxes are restored, and sandboxes that cannot be restored, or that
+    /// were unloaded, are retired from the pool.
+    ///
+    /// `release()` already does this for every sandbox it gets back, so
+    /// this is only needed when pooled sandboxes may be used directly.
+    ///
+    /// Returns a `Promise<number>`.
+    ///
+    /// @returns A `Promise` with the number of sandboxes retired
+    #[napi(ts_return_type = "Promise<number>")]
+    pub async fn check_health(&self) -> Settled<u32> {
+        settle(async move {
+            let mut members = Vec::new();
+            while let Ok(permit) = self.permits.try_acquire() {
+                permit.forget();
+                if let Some(member) = self.idle.lock().map_err(|_| lock_error())?.pop_front() {
+                    members.push(member);
+                }
+            }
+            let mut retired = 0;
+            for member in members {
+                if !self.put_idle(member).await? {
+                    retired += 1;
+                }
+            }
+            Ok(retired)
+        })
+        .await
+    }
+
+    /// Number of sandboxes in the pool, in use or not.
+    #[napi(getter)]
+    pub fn size(&self) -> u32 {
+        self.members.load(Ordering::Acquire)
+    }
+
+    /// Number of sandboxes of the pool that are not in use.
+    #[napi(getter)]
+    pub fn available(&self) -> napi::Result<u32, ErrorCode> {
+        Ok(self.idle.lock().map_err(|_| lock_error())?.len() as u32)
+    }
+
+    /// Number of `acquire()` calls waiting for a sandbox to be released.
+    #[napi(getter)]
+    pub fn queue_depth(&self) -> u32 {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// The number of idle and busy sandboxes of the pool, and how many times
+    /// a poisoned sandbox was restored, e.g. to export as metrics.
+    ///
+    /// ```js
+    /// const { idle, busy, poisonedReplaced } = pool.stats();
+    /// ```
+    ///
+    /// @returns The current `PoolStats`
+    #[napi]
+    pub fn stats(&self) -> napi::Result<PoolStats, ErrorCode> {
+        let idle = self.available()?;
+        Ok(PoolStats {
+            idle,
+            busy: self.size().saturating_sub(idle),
+            poisoned_replaced: self.poisoned_replaced.load(Ordering::Relaxed),
+        })
     }
 }
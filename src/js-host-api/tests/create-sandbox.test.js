@@ -0,0 +1,40 @@
+// createSandbox() tests
+import { describe, it, expect } from 'vitest';
+import { createSandbox } from '../lib.js';
+import { expectRejectsWithCode } from './test-helpers.js';
+
+describe('createSandbox', () => {
+    it('should create a loaded sandbox with its handlers', async () => {
+        const loaded = await createSandbox({
+            handlers: {
+                greet: 'function handler(event) { return { msg: `hi ${event.name}` }; }',
+                add: `
+                    import { add } from './math.js';
+                    function handler(event) { return { sum: add(event.a, event.b) }; }
+                `,
+            },
+            modules: { 'math.js': 'export const add = (a, b) => a + b;' },
+            heapSize: 8 * 1024 * 1024,
+        });
+        expect(await loaded.callHandler('greet', { name: 'World' })).toEqual({ msg: 'hi World' });
+        expect(await loaded.callHandler('add', { a: 5, b: 3 })).toEqual({ sum: 8 });
+    });
+
+    it('should reject options without handlers', async () => {
+        await expectRejectsWithCode(createSandbox({ handlers: {} }), 'ERR_INVALID_ARG');
+    });
+
+    it('should reject an empty handler name', async () => {
+        await expectRejectsWithCode(
+            createSandbox({ handlers: { '': 'function handler() {}' } }),
+            'ERR_INVALID_ARG'
+        );
+    });
+
+    it('should reject zero sizes', async () => {
+        await expectRejectsWithCode(
+            createSandbox({ handlers: { h: 'function handler() {}' }, heapSize: 0 }),
+            'ERR_INVALID_ARG'
+        );
+    });
+});
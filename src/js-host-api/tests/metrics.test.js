@@ -0,0 +1,32 @@
+// getMetrics() tests
+import { describe, it, expect } from 'vitest';
+import { createSandbox, getMetrics } from '../lib.js';
+import { expectRejectsWithCode } from './test-helpers.js';
+
+describe('getMetrics', () => {
+    it('should count sandboxes and loads', async () => {
+        const before = getMetrics();
+        const loaded = await createSandbox({ handlers: { handler: 'function handler() {}' } });
+
+        const after = getMetrics();
+        expect(after.totalLoadedJsSandboxes).toBe(before.totalLoadedJsSandboxes + 1);
+        expect(after.activeLoadedJsSandboxes).toBeGreaterThanOrEqual(1);
+        expect(after.sandboxLoads).toBe(before.sandboxLoads + 1);
+
+        await loaded.unload();
+        expect(getMetrics().sandboxUnloads).toBe(before.sandboxUnloads + 1);
+    });
+
+    it('should count monitor terminations', async () => {
+        const loaded = await createSandbox({
+            handlers: { handler: 'function handler() { while (true) {} }' },
+        });
+        const before = getMetrics().monitorTerminations['wall-clock'] ?? 0;
+
+        await expectRejectsWithCode(
+            loaded.callHandler('handler', {}, { wallClockTimeoutMs: 100 }),
+            'ERR_CANCELLED'
+        );
+        expect(getMetrics().monitorTerminations['wall-clock']).toBe(before + 1);
+    });
+});
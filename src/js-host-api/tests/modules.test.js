@@ -0,0 +1,74 @@
+// Module loader tests
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { mkdtempSync, mkdirSync, rmSync, writeFileSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { SandboxBuilder } from '../lib.js';
+import { expectThrowsWithCode } from './test-helpers.js';
+
+const HANDLER = `
+    import { add } from './lib/math.js';
+    function handler(event) {
+        return { sum: add(event.a, event.b) };
+    }
+`;
+
+const MATH = 'export const add = (a, b) => a + b;';
+
+/**
+ * Load `HANDLER` into `proto` and call it.
+ *
+ * @returns {Promise<object>}
+ */
+async function callHandler(proto) {
+    const sandbox = await proto.loadRuntime();
+    sandbox.addHandler('handler', HANDLER);
+    const loaded = await sandbox.getLoadedSandbox();
+    return loaded.callHandler('handler', { a: 5, b: 3 });
+}
+
+// ── setModules ───────────────────────────────────────────────────────
+
+describe('setModules', () => {
+    it('should import in-memory modules', async () => {
+        const proto = await new SandboxBuilder().build();
+        proto.setModules({ 'lib/math.js': MATH });
+        expect(await callHandler(proto)).toEqual({ sum: 8 });
+    });
+
+    it('should throw once consumed', async () => {
+        const proto = await new SandboxBuilder().build();
+        await proto.loadRuntime();
+        expectThrowsWithCode(() => proto.setModules({}), 'ERR_CONSUMED');
+    });
+});
+
+// ── setModuleDirectory ───────────────────────────────────────────────
+
+describe('setModuleDirectory', () => {
+    let dir;
+
+    beforeEach(() => {
+        dir = mkdtempSync(join(tmpdir(), 'hyperlight-modules-'));
+        mkdirSync(join(dir, 'lib'));
+        writeFileSync(join(dir, 'lib', 'math.js'), MATH);
+    });
+
+    afterEach(() => {
+        rmSync(dir, { recursive: true, force: true });
+    });
+
+    it('should import modules from a directory', async () => {
+        const proto = await new SandboxBuilder().build();
+        proto.setModuleDirectory(dir);
+        expect(await callHandler(proto)).toEqual({ sum: 8 });
+    });
+
+    it('should reject a path that is not a directory', async () => {
+        const proto = await new SandboxBuilder().build();
+        expectThrowsWithCode(
+            () => proto.setModuleDirectory(join(dir, 'lib', 'math.js')),
+            'ERR_INVALID_ARG'
+        );
+    });
+});
@@ -0,0 +1,173 @@
+// Sandbox pool tests
+import { describe, it, expect, beforeEach } from 'vitest';
+import { SandboxBuilder, SandboxPool } from '../lib.js';
+import { expectRejectsWithCode } from './test-helpers.js';
+
+// ── Helpers ──────────────────────────────────────────────────────────
+
+/**
+ * Build a loaded sandbox with a handler counting its calls, which spins
+ * for `event.runtime` milliseconds when given.
+ *
+ * @returns {Promise<import('../lib.js').LoadedJSSandbox>}
+ */
+async function buildLoadedSandbox() {
+    const proto = await new SandboxBuilder().build();
+    const sandbox = await proto.loadRuntime();
+    sandbox.addHandler(
+        'handler',
+        `
+        let calls = 0;
+        function handler(event) {
+            const start = Date.now();
+            while (Date.now() - start < (event.runtime || 0)) {}
+            return { calls: ++calls };
+        }
+    `
+    );
+    return sandbox.getLoadedSandbox();
+}
+
+// ── SandboxPool ──────────────────────────────────────────────────────
+
+describe('SandboxPool', () => {
+    let pool;
+
+    beforeEach(async () => {
+        pool = new SandboxPool([await buildLoadedSandbox(), await buildLoadedSandbox()]);
+    });
+
+    it('should reject an empty pool', () => {
+        expect(() => new SandboxPool([])).toThrow(/at least one sandbox/);
+    });
+
+    it('should acquire and release sandboxes', async () => {
+        expect(pool.size).toBe(2);
+        expect(pool.available).toBe(2);
+
+        const first = await pool.acquire();
+        const second = await pool.acquire();
+        expect(pool.available).toBe(0);
+        expect(pool.stats()).toEqual({ idle: 0, busy: 2, poisonedReplaced: 0 });
+        expect(await first.callHandler('handler', {})).toEqual({ calls: 1 });
+
+        await pool.release(first);
+        await pool.release(second);
+        expect(pool.available).toBe(2);
+        expect(pool.size).toBe(2);
+    });
+
+    it('should queue acquire() until a sandbox is released', async () => {
+        const first = await pool.acquire();
+        const second = await pool.acquire();
+
+        const third = pool.acquire();
+        await new Promise((resolve) => setTimeout(resolve, 50));
+        expect(pool.queueDepth).toBe(1);
+
+        await pool.release(second);
+        const sandbox = await third;
+        expect(pool.queueDepth).toBe(0);
+
+        await pool.release(sandbox);
+        await pool.release(first);
+        expect(pool.available).toBe(2);
+    });
+
+    it('should stop waiting when the signal of acquire() aborts', async () => {
+        const first = await pool.acquire();
+        const second = await pool.acquire();
+
+        const controller = new AbortController();
+        const third = pool.acquire({ signal: controller.signal });
+        await new Promise((resolve) => setTimeout(resolve, 50));
+        expect(pool.queueDepth).toBe(1);
+
+        controller.abort(new Error('gave up'));
+        const err = await third.catch((e) => e);
+        expect(err.code).toBe('ERR_CANCELLED');
+        expect(err.cause.message).toBe('gave up');
+        expect(pool.queueDepth).toBe(0);
+
+        await expectRejectsWithCode(pool.acquire({ signal: controller.signal }), 'ERR_CANCELLED');
+
+        // The sandbox released next goes to a caller still waiting
+        const fourth = pool.acquire();
+        await pool.release(second);
+        await pool.release(await fourth);
+        await pool.release(first);
+        expect(pool.available).toBe(2);
+    });
+
+    it('should not wait in tryAcquire()', async () => {
+        const first = await pool.tryAcquire();
+        const second = await pool.tryAcquire();
+        expect(first).not.toBeNull();
+        expect(second).not.toBeNull();
+        expect(await pool.tryAcquire()).toBeNull();
+        expect(pool.queueDepth).toBe(0);
+
+        await pool.release(first);
+        await pool.release(second);
+        expect(pool.available).toBe(2);
+    });
+
+    it('should reject releasing a sandbox twice', async () => {
+        const sandbox = await pool.acquire();
+        await pool.release(sandbox);
+        await expectRejectsWithCode(pool.release(sandbox), 'ERR_INVALID_ARG');
+    });
+
+    it('should release the sandbox after withSandbox()', async () => {
+        const result = await pool.withSandbox((sandbox) => sandbox.callHandler('handler', {}));
+        expect(result).toEqual({ calls: 1 });
+        expect(pool.available).toBe(2);
+
+        await expect(
+            pool.withSandbox(() => {
+                throw new Error('boom');
+            })
+        ).rejects.toThrow(/boom/);
+        expect(pool.available).toBe(2);
+    });
+
+    it('should restore poisoned sandboxes on release', async () => {
+        const sandbox = await pool.acquire();
+        await sandbox.callHandler('handler', {});
+        await expectRejectsWithCode(
+            sandbox.callHandler('handler', { runtime: 4000 }, { wallClockTimeoutMs: 100 }),
+            'ERR_CANCELLED'
+        );
+        expect(sandbox.poisoned).toBe(true);
+
+        await pool.release(sandbox);
+        expect(sandbox.poisoned).toBe(false);
+        expect(pool.size).toBe(2);
+        expect(pool.stats()).toEqual({ idle: 2, busy: 0, poisonedReplaced: 1 });
+        // Restored to its state when it was first acquired
+        expect(await sandbox.callHandler('handler', {})).toEqual({ calls: 1 });
+    });
+
+    it('should retire unloaded sandboxes', async () => {
+        const sandbox = await pool.acquire();
+        await sandbox.unload();
+        await pool.release(sandbox);
+        expect(pool.size).toBe(1);
+        expect(await pool.checkHealth()).toBe(0);
+    });
+
+    it('should keep serving sandboxes when one is retired while another is acquired', async () => {
+        const first = await pool.acquire();
+        await first.unload();
+
+        // The second sandbox is snapshotted while the first one is retired
+        const [second] = await Promise.all([pool.acquire(), pool.release(first)]);
+        expect(pool.size).toBe(1);
+
+        await pool.release(second);
+        const again = await pool.acquire();
+        expect(await again.callHandler('handler', {})).toEqual({ calls: 1 });
+        await pool.release(again);
+        expect(pool.available).toBe(1);
+    });
+});
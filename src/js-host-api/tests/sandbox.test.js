@@ -46,6 +46,24 @@ describe('SandboxBuilder', () => {
         expectThrowsWithCode(() => builder.setHeapSize(1024), 'ERR_CONSUMED');
     });
 
+    it('should forward guest output to the host print callback', async () => {
+        const lines = [];
+        const proto = await new SandboxBuilder().setHostPrint((line) => lines.push(line)).build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler(
+            'handler',
+            `function handler() {
+                console.log('hello', 'world');
+                console.log('one\\ntwo');
+                return {};
+            }`
+        );
+        const loaded = await sandbox.getLoadedSandbox();
+        await loaded.callHandler('handler', {});
+        await new Promise((resolve) => setTimeout(resolve, 50));
+        expect(lines).toEqual(['hello world', 'one', 'two']);
+    });
+
     // ── Validation ───────────────────────────────────────────────────
 
     it('should reject zero heap size', () => {
@@ -67,6 +85,74 @@ describe('SandboxBuilder', () => {
         const builder = new SandboxBuilder();
         expectThrowsWithCode(() => builder.setOutputBufferSize(0), 'ERR_INVALID_ARG');
     });
+
+    it('should reject zero JS memory limit', () => {
+        const builder = new SandboxBuilder();
+        expectThrowsWithCode(() => builder.setJsMemoryLimit(0), 'ERR_INVALID_ARG');
+    });
+
+    it('should not prefix error messages with their code', () => {
+        const builder = new SandboxBuilder();
+        expect(() => builder.setHeapSize(0)).toThrow(/^Heap size must be greater than 0$/);
+    });
+
+    it('should reject with OOM when the JS memory limit is reached', async () => {
+        const proto = await new SandboxBuilder()
+            .setHeapSize(32 * 1024 * 1024)
+            .setJsMemoryLimit(8 * 1024 * 1024)
+            .build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler(
+            'handler',
+            `function handler() {
+                const chunks = [];
+                for (;;) chunks.push(new Array(10000).fill(1));
+            }`
+        );
+        const loaded = await sandbox.getLoadedSandbox();
+        await expectRejectsWithCode(loaded.callHandler('handler', {}), 'ERR_OOM');
+        expect(loaded.poisoned).toBe(false);
+    });
+});
+
+// ── Lifecycle events ─────────────────────────────────────────────────
+
+describe('SandboxBuilder.on', () => {
+    it('should emit lifecycle events', async () => {
+        const events = [];
+        const listener = (event) => events.push(event);
+        const proto = await new SandboxBuilder()
+            .on('poisoned', listener)
+            .on('monitorFired', listener)
+            .on('unloaded', listener)
+            .build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler('handler', 'function handler() { while (true) {} }');
+        const loaded = await sandbox.getLoadedSandbox();
+
+        await expectRejectsWithCode(
+            loaded.callHandler('handler', {}, { wallClockTimeoutMs: 100 }),
+            'ERR_CANCELLED'
+        );
+        await loaded.unload();
+        await new Promise((resolve) => setTimeout(resolve, 50));
+
+        expect(events.map(({ event }) => event)).toEqual(['monitorFired', 'poisoned', 'unloaded']);
+        expect(events[0].handler).toBe('handler');
+        expect(events[0].monitor).toBe('wall-clock');
+        expect(events[1].reason.kind).toBe('monitorTimeout');
+    });
+
+    it('should reject unknown events', () => {
+        const builder = new SandboxBuilder();
+        expectThrowsWithCode(() => builder.on('exploded', () => {}), 'ERR_INVALID_ARG');
+    });
+
+    it('should throw CONSUMED after build()', async () => {
+        const builder = new SandboxBuilder();
+        await builder.build();
+        expectThrowsWithCode(() => builder.on('poisoned', () => {}), 'ERR_CONSUMED');
+    });
 });
 
 // ── ProtoJSSandbox ───────────────────────────────────────────────────
@@ -196,6 +282,13 @@ describe('LoadedJSSandbox', () => {
 
     it('should not be poisoned initially', () => {
         expect(loaded.poisoned).toBe(false);
+        expect(loaded.poisonedReason).toBeNull();
+    });
+
+    it('should run garbage collection on demand', async () => {
+        await loaded.callHandler('handler', { name: 'Burst' }, { gc: false });
+        await loaded.runGc();
+        expect(loaded.poisoned).toBe(false);
     });
 
     it('should take and restore snapshots', async () => {
@@ -230,6 +323,42 @@ describe('LoadedJSSandbox', () => {
         await expectRejectsWithCode(loaded.callHandler('', {}, { gc: false }), 'ERR_INVALID_ARG');
     });
 
+    it('should not prefix rejection messages with their code', async () => {
+        const error = await loaded.callHandler('', {}).catch((e) => e);
+        expect(error.code).toBe('ERR_INVALID_ARG');
+        expect(error.message).toBe('Handler name must not be empty');
+    });
+
+    it('should limit the size of the result', async () => {
+        // {"name":"World","message":"Hello, World!"} is 42 bytes
+        const event = { name: 'World' };
+        expect(await loaded.callHandler('handler', event, { maxResultBytes: 42 })).toEqual({
+            name: 'World',
+            message: 'Hello, World!',
+        });
+        await expectRejectsWithCode(
+            loaded.callHandler('handler', event, { maxResultBytes: 41 }),
+            'ERR_RESULT_TOO_LARGE'
+        );
+        expect(loaded.poisoned).toBe(false);
+    });
+
+    it('should reject events too large for the input buffer', async () => {
+        const error = await loaded
+            .callHandler('handler', { name: 'x'.repeat(64 * 1024) })
+            .catch((e) => e);
+        expect(error.code).toBe('ERR_EVENT_TOO_LARGE');
+        expect(error.message).toContain('setInputBufferSize()');
+        expect(loaded.poisoned).toBe(false);
+    });
+
+    it('should reject a zero maxResultBytes', async () => {
+        await expectRejectsWithCode(
+            loaded.callHandler('handler', {}, { maxResultBytes: 0 }),
+            'ERR_INVALID_ARG'
+        );
+    });
+
     it('should provide an interrupt handle', () => {
         // interruptHandle is now a getter, not a method
         const handle = loaded.interruptHandle;
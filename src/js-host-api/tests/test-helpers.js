@@ -1,7 +1,7 @@
 // ── Test helpers for structured error code assertions ─────────────────
 //
 // Vitest's built-in `.toThrow()` only matches on error messages, not on
-// the `error.code` property that our lib.js wrapper sets. These helpers
+// the `error.code` property that the native binding sets. These helpers
 // provide a clean way to assert that a function throws (or a promise
 // rejects) with a specific `error.code` value.
 
@@ -1,7 +1,7 @@
 // Timeout and interrupt tests
 import { describe, it, expect, beforeEach } from 'vitest';
 import { SandboxBuilder } from '../lib.js';
-import { expectRejectsWithCode } from './test-helpers.js';
+import { expectRejectsWithCode, expectThrowsWithCode } from './test-helpers.js';
 
 describe('Wall Clock Timeout', () => {
     let loaded;
@@ -71,6 +71,7 @@ describe('Wall Clock Timeout', () => {
         expect(elapsed).toBeLessThan(2000);
         expect(elapsed).toBeGreaterThan(300);
         expect(loaded.poisoned).toBe(true);
+        expect(loaded.poisonedReason.kind).toBe('monitorTimeout');
     });
 
     it('should recover from poisoned state with restore', async () => {
@@ -92,6 +93,7 @@ describe('Wall Clock Timeout', () => {
         await loaded.restore(snapshot);
 
         expect(loaded.poisoned).toBe(false);
+        expect(loaded.poisonedReason).toBeNull();
 
         // Should be able to use the sandbox again
         const result = await loaded.callHandler(
@@ -400,6 +402,50 @@ describe('Interrupt Handle', () => {
         expect(loaded.poisoned).toBe(true);
     });
 
+    it('should kill a running handler after a delay, and report it running', async () => {
+        const builder = new SandboxBuilder();
+        const proto = await builder.build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler(
+            'handler',
+            `
+            function handler(event) {
+                const startTime = Date.now();
+                while (Date.now() - startTime < (event.runtime || 10000)) { /* busy loop */ }
+                return event;
+            }
+        `
+        );
+        const loaded = await sandbox.getLoadedSandbox();
+        const handle = loaded.interruptHandle;
+        expect(handle.isRunning).toBe(false);
+
+        // A cancelled kill does not fire
+        const cancelled = handle.killAfter(100);
+        cancelled.cancel();
+        await loaded.callHandler('handler', { runtime: 300 });
+        expect(loaded.poisoned).toBe(false);
+
+        handle.killAfter(200);
+        const promise = loaded.callHandler('handler', {});
+        await new Promise((resolve) => setTimeout(resolve, 100));
+        expect(handle.isRunning).toBe(true);
+
+        await expectRejectsWithCode(promise, 'ERR_CANCELLED');
+        expect(handle.isRunning).toBe(false);
+        expect(loaded.poisoned).toBe(true);
+    });
+
+    it('should reject an out of range killAfter() delay', async () => {
+        const builder = new SandboxBuilder();
+        const proto = await builder.build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler('handler', 'function handler(e) { return e; }');
+        const loaded = await sandbox.getLoadedSandbox();
+
+        expectThrowsWithCode(() => loaded.interruptHandle.killAfter(0), 'ERR_INVALID_ARG');
+    });
+
     it('should reject empty handler name on callHandler', async () => {
         const builder = new SandboxBuilder();
         const proto = await builder.build();
@@ -413,3 +459,74 @@ describe('Interrupt Handle', () => {
         );
     });
 });
+
+describe('AbortSignal', () => {
+    let loaded;
+
+    beforeEach(async () => {
+        const proto = await new SandboxBuilder().build();
+        const sandbox = await proto.loadRuntime();
+        sandbox.addHandler(
+            'handler',
+            `
+            function handler(event) {
+                const startTime = Date.now();
+                while (Date.now() - startTime < (event.runtime || 0)) { /* busy loop */ }
+                return event;
+            }
+        `
+        );
+        loaded = await sandbox.getLoadedSandbox();
+    });
+
+    it('should kill a running handler when the signal aborts', async () => {
+        const controller = new AbortController();
+        const reason = new Error('client went away');
+        const promise = loaded.callHandler(
+            'handler',
+            { runtime: 10000 },
+            { signal: controller.signal }
+        );
+        setTimeout(() => controller.abort(reason), 200);
+
+        let caught;
+        try {
+            await promise;
+        } catch (e) {
+            caught = e;
+        }
+        expect(caught?.code).toBe('ERR_CANCELLED');
+        expect(caught.cause).toBe(reason);
+        expect(loaded.poisoned).toBe(true);
+    });
+
+    it('should reject without running the handler if the signal already aborted', async () => {
+        const signal = AbortSignal.abort('too late');
+
+        let caught;
+        try {
+            await loaded.callHandler('handler', {}, { signal });
+        } catch (e) {
+            caught = e;
+        }
+        expect(caught?.code).toBe('ERR_CANCELLED');
+        expect(caught.cause).toBe('too late');
+        expect(loaded.poisoned).toBe(false);
+    });
+
+    it('should complete when the signal does not abort', async () => {
+        const controller = new AbortController();
+        const result = await loaded.callHandler(
+            'handler',
+            { value: 42 },
+            { signal: controller.signal, wallClockTimeoutMs: 5000 }
+        );
+        expect(result.value).toBe(42);
+        expect(loaded.poisoned).toBe(false);
+
+        // Aborting after the call settled doesn't affect the next call
+        controller.abort();
+        const next = await loaded.callHandler('handler', { value: 1 });
+        expect(next.value).toBe(1);
+    });
+});